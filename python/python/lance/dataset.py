@@ -375,11 +375,13 @@ class LanceDataset(pa.dataset.Dataset):
         use_stats: Optional[bool] = None,
         fast_search: Optional[bool] = None,
         io_buffer_size: Optional[int] = None,
-        late_materialization: Optional[bool | List[str]] = None,
+        late_materialization: Optional[bool | List[str] | float] = None,
         use_scalar_index: Optional[bool] = None,
         include_deleted_rows: Optional[bool] = None,
         scan_stats_callback: Optional[Callable[[ScanStatistics], None]] = None,
         strict_batch_size: Optional[bool] = None,
+        batch_size_bytes: Optional[int] = None,
+        memory_limit: Optional[int] = None,
     ) -> LanceScanner:
         """Return a Scanner that can support various pushdowns.
 
@@ -415,9 +417,15 @@ class LanceDataset(pa.dataset.Dataset):
             The target size of batches returned.  In some cases batches can be up to
             twice this size (but never larger than this).  In some cases batches can
             be smaller than this size.
+        batch_size_bytes: int, default None
+            The target size of batches returned, in bytes.  See
+            ``ScannerBuilder.batch_size_bytes`` for more information.
         io_buffer_size: int, default None
             The size of the IO buffer.  See ``ScannerBuilder.io_buffer_size``
             for more information.
+        memory_limit: int, default None
+            The memory budget of the scan, in bytes.  Sorts that exceed it spill
+            to disk.  See ``ScannerBuilder.memory_limit`` for more information.
         batch_readahead: int, optional
             The number of batches to read ahead.
         fragment_readahead: int, optional
@@ -443,7 +451,7 @@ class LanceDataset(pa.dataset.Dataset):
             Lance will automatically use scalar indices to optimize a query.  In some
             corner cases this can make query performance worse and this parameter can
             be used to disable scalar indices in these cases.
-        late_materialization: bool or List[str], default None
+        late_materialization: bool, List[str] or float, default None
             Allows custom control over late materialization.  Late materialization
             fetches non-query columns using a take operation after the filter.  This
             is useful when there are few results or columns are very large.
@@ -455,6 +463,10 @@ class LanceDataset(pa.dataset.Dataset):
             If False, then all columns are early materialized.
             If a list of strings, then only the columns in the list are
             late materialized.
+            If a float, then it is the expected fraction (0-1) of rows that pass
+            the filter and each column is materialized early or late based on
+            its width.  This can be much better than the default for filters
+            that select more than a few percent of the rows.
 
             The default uses a heuristic that assumes filters will select about 0.1%
             of the rows.  If your filter is more selective (e.g. find by id) you may
@@ -539,6 +551,8 @@ class LanceDataset(pa.dataset.Dataset):
         setopt(builder.include_deleted_rows, include_deleted_rows)
         setopt(builder.scan_stats_callback, scan_stats_callback)
         setopt(builder.strict_batch_size, strict_batch_size)
+        setopt(builder.batch_size_bytes, batch_size_bytes)
+        setopt(builder.memory_limit, memory_limit)
         # columns=None has a special meaning. we can't treat it as "user didn't specify"
         if self._default_scan_options is None:
             # No defaults, use user-provided, if any
@@ -616,7 +630,7 @@ class LanceDataset(pa.dataset.Dataset):
         fast_search: Optional[bool] = None,
         full_text_query: Optional[Union[str, dict, FullTextQuery]] = None,
         io_buffer_size: Optional[int] = None,
-        late_materialization: Optional[bool | List[str]] = None,
+        late_materialization: Optional[bool | List[str] | float] = None,
         use_scalar_index: Optional[bool] = None,
         include_deleted_rows: Optional[bool] = None,
     ) -> pa.Table:
@@ -666,7 +680,7 @@ class LanceDataset(pa.dataset.Dataset):
             and memory use might increase.
         prefilter: bool, optional, default False
             Run filter before the vector search.
-        late_materialization: bool or List[str], default None
+        late_materialization: bool, List[str] or float, default None
             Allows custom control over late materialization.  See
             ``ScannerBuilder.late_materialization`` for more information.
         use_scalar_index: bool, default True
@@ -807,9 +821,10 @@ class LanceDataset(pa.dataset.Dataset):
         use_stats: Optional[bool] = None,
         full_text_query: Optional[Union[str, dict]] = None,
         io_buffer_size: Optional[int] = None,
-        late_materialization: Optional[bool | List[str]] = None,
+        late_materialization: Optional[bool | List[str] | float] = None,
         use_scalar_index: Optional[bool] = None,
         strict_batch_size: Optional[bool] = None,
+        batch_size_bytes: Optional[int] = None,
         **kwargs,
     ) -> Iterator[pa.RecordBatch]:
         """Read the dataset as materialized record batches.
@@ -842,6 +857,7 @@ class LanceDataset(pa.dataset.Dataset):
             use_stats=use_stats,
             full_text_query=full_text_query,
             strict_batch_size=strict_batch_size,
+            batch_size_bytes=batch_size_bytes,
         ).to_batches()
 
     def sample(
@@ -3238,6 +3254,8 @@ class ScannerBuilder:
         self._include_deleted_rows = None
         self._scan_stats_callback: Optional[Callable[[ScanStatistics], None]] = None
         self._strict_batch_size = False
+        self._batch_size_bytes = None
+        self._memory_limit: Optional[int] = None
 
     def apply_defaults(self, default_opts: Dict[str, Any]) -> ScannerBuilder:
         for key, value in default_opts.items():
@@ -3252,6 +3270,19 @@ class ScannerBuilder:
         self._batch_size = batch_size
         return self
 
+    def batch_size_bytes(self, batch_size_bytes: int) -> ScannerBuilder:
+        """
+        Set the target size of the batches returned, in bytes.
+
+        The number of rows that fits in a batch depends on the schema.  When this
+        is set, large batches are sliced and small batches are combined so that
+        each batch holds roughly ``batch_size_bytes`` of data.  A batch always has
+        at least one row.  This takes precedence over ``batch_size`` and
+        ``strict_batch_size`` for the returned batches.
+        """
+        self._batch_size_bytes = batch_size_bytes
+        return self
+
     def io_buffer_size(self, io_buffer_size: int) -> ScannerBuilder:
         """
         Set the I/O buffer size for the Scanner
@@ -3278,6 +3309,21 @@ class ScannerBuilder:
         self._io_buffer_size = io_buffer_size
         return self
 
+    def memory_limit(self, memory_limit: int) -> ScannerBuilder:
+        """
+        Set a memory budget, in bytes, for the Scanner
+
+        Half of the budget is used as the I/O buffer (unless ``io_buffer_size`` is
+        set) and the other half is given to the memory pool that tracks sorts and
+        top-k searches.  Sorts that exceed the pool spill to disk instead of failing.
+
+        Sorts reserve some memory up front for merging spilled data (10MiB by
+        default) so budgets of a few tens of MiB are the practical minimum for
+        scans with an ordering.  Batches are not tracked after they are returned.
+        """
+        self._memory_limit = memory_limit
+        return self
+
     def batch_readahead(self, nbatches: Optional[int] = None) -> ScannerBuilder:
         """
         This parameter is ignored when reading v2 files
@@ -3404,7 +3450,7 @@ class ScannerBuilder:
         return self
 
     def late_materialization(
-        self, late_materialization: bool | List[str]
+        self, late_materialization: bool | List[str] | float
     ) -> ScannerBuilder:
         self._late_materialization = late_materialization
         return self
@@ -3625,6 +3671,8 @@ class ScannerBuilder:
             self._include_deleted_rows,
             self._scan_stats_callback,
             self._strict_batch_size,
+            self._batch_size_bytes,
+            self._memory_limit,
         )
         return LanceScanner(scanner, self.ds)
 
@@ -210,7 +210,7 @@ class _Dataset:
         substrait_filter: Optional[bytes] = None,
         fast_search: Optional[bool] = None,
         full_text_query: Optional[dict] = None,
-        late_materialization: Optional[bool | List[str]] = None,
+        late_materialization: Optional[bool | List[str] | float] = None,
         use_scalar_index: Optional[bool] = None,
         include_deleted_rows: Optional[bool] = None,
     ) -> _Scanner: ...
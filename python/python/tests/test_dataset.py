@@ -234,6 +234,29 @@ def test_to_batches_with_partial_last_batch(tmp_path: Path):
         assert batch.num_rows == 8
 
 
+def test_batch_size_bytes(tmp_path: Path):
+    # 10 files of 100 int64 rows each
+    ds = lance.write_dataset(
+        pa.table({"a": range(1000)}), tmp_path, max_rows_per_file=100
+    )
+
+    # A large target combines the small per-fragment batches
+    batches = list(ds.to_batches(batch_size_bytes=1024 * 1024))
+    assert sum(b.num_rows for b in batches) == 1000
+    assert len(batches) == 1
+
+    # tiny targets still yield at least one row per batch
+    batches = list(ds.to_batches(batch_size_bytes=1, columns=["a"]))
+    assert len(batches) == 1000
+    assert pa.Table.from_batches(batches)["a"].to_pylist() == list(range(1000))
+
+
+def test_memory_limit(tmp_path: Path):
+    ds = lance.write_dataset(pa.table({"a": range(1000)}), tmp_path)
+    table = ds.scanner(memory_limit=64 * 1024 * 1024, filter="a >= 500").to_table()
+    assert table["a"].to_pylist() == list(range(500, 1000))
+
+
 def test_schema_metadata(tmp_path: Path):
     schema = pa.schema(
         [
@@ -3268,6 +3291,16 @@ def test_late_materialization_param(tmp_path: Path):
     assert ", values" in dataset.scanner(
         filter=filt, late_materialization=["filter"]
     ).explain_plan(True)
+    # The binary column is assumed to be narrow enough to fetch early when half of the
+    # rows pass the filter, but not when very few do
+    assert ", values" in dataset.scanner(
+        filter=filt, late_materialization=0.5
+    ).explain_plan(True)
+    assert "(values)" in dataset.scanner(
+        filter=filt, late_materialization=0.0001
+    ).explain_plan(True)
+    with pytest.raises(ValueError):
+        dataset.scanner(filter=filt, late_materialization=2.0)
 
     # These tests just make sure we can pass in the parameter.  There's no great
     # way to know if late materialization happened or not.  That will have to be
@@ -519,7 +519,7 @@ impl Dataset {
     }
 
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature=(columns=None, columns_with_transform=None, filter=None, prefilter=None, limit=None, offset=None, nearest=None, batch_size=None, io_buffer_size=None, batch_readahead=None, fragment_readahead=None, scan_in_order=None, fragments=None, with_row_id=None, with_row_address=None, use_stats=None, substrait_filter=None, fast_search=None, full_text_query=None, late_materialization=None, use_scalar_index=None, include_deleted_rows=None, scan_stats_callback=None, strict_batch_size=None))]
+    #[pyo3(signature=(columns=None, columns_with_transform=None, filter=None, prefilter=None, limit=None, offset=None, nearest=None, batch_size=None, io_buffer_size=None, batch_readahead=None, fragment_readahead=None, scan_in_order=None, fragments=None, with_row_id=None, with_row_address=None, use_stats=None, substrait_filter=None, fast_search=None, full_text_query=None, late_materialization=None, use_scalar_index=None, include_deleted_rows=None, scan_stats_callback=None, strict_batch_size=None, batch_size_bytes=None, memory_limit=None))]
     fn scanner(
         self_: PyRef<'_, Self>,
         columns: Option<Vec<String>>,
@@ -546,6 +546,8 @@ impl Dataset {
         include_deleted_rows: Option<bool>,
         scan_stats_callback: Option<&Bound<'_, PyAny>>,
         strict_batch_size: Option<bool>,
+        batch_size_bytes: Option<usize>,
+        memory_limit: Option<u64>,
     ) -> PyResult<Scanner> {
         let mut scanner: LanceScanner = self_.ds.scan();
         match (columns, columns_with_transform) {
@@ -708,6 +710,10 @@ impl Dataset {
                 } else {
                     scanner.materialization_style(MaterializationStyle::AllEarly);
                 }
+            } else if let Ok(selectivity) = late_materialization.extract::<f64>(self_.py()) {
+                scanner.materialization_style(
+                    MaterializationStyle::expected_selectivity(selectivity).infer_error()?,
+                );
             } else if let Ok(columns) = late_materialization.extract::<Vec<String>>(self_.py()) {
                 scanner.materialization_style(
                     MaterializationStyle::all_early_except(&columns, self_.ds.schema())
@@ -715,7 +721,7 @@ impl Dataset {
                 );
             } else {
                 return Err(PyValueError::new_err(
-                    "late_materialization must be a bool or a list of strings",
+                    "late_materialization must be a bool, a float or a list of strings",
                 ));
             }
         }
@@ -728,6 +734,14 @@ impl Dataset {
             scanner.strict_batch_size(strict_batch_size);
         }
 
+        if let Some(batch_size_bytes) = batch_size_bytes {
+            scanner.batch_size_bytes(batch_size_bytes);
+        }
+
+        if let Some(memory_limit) = memory_limit {
+            scanner.memory_limit(memory_limit);
+        }
+
         if let Some(nearest) = nearest {
             let column = nearest
                 .get_item("column")?
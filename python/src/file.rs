@@ -34,7 +34,7 @@ use lance_file::{
 };
 use lance_io::object_store::ObjectStoreParams;
 use lance_io::{
-    scheduler::{ScanScheduler, SchedulerConfig},
+    scheduler::{CoalescingConfig, IoPriorityClass, ScanScheduler, SchedulerConfig},
     utils::CachedFileSize,
     ReadBatchParams,
 };
@@ -215,11 +215,14 @@ impl LanceFileMetadata {
                 .iter()
                 .map(LanceBufferDescriptor::new)
                 .collect(),
-            columns: inner
-                .column_metadatas
-                .iter()
-                .map(LanceColumnMetadata::new)
-                .map(Some)
+            columns: (0..inner.column_metadatas.len() as u32)
+                .map(|column_index| {
+                    inner
+                        .column_metadatas
+                        .metadata(column_index)
+                        .ok()
+                        .map(LanceColumnMetadata::new)
+                })
                 .collect(),
         }
     }
@@ -400,6 +403,9 @@ impl LanceFileReader {
             object_store,
             SchedulerConfig {
                 io_buffer_size_bytes: 2 * 1024 * 1024 * 1024,
+                priority_class: IoPriorityClass::Interactive,
+                uncached_reads: false,
+                coalescing: CoalescingConfig::default(),
             },
         );
         let file = scheduler
@@ -541,9 +547,9 @@ impl LanceFileReader {
         LanceFileMetadata::new(inner_meta, py)
     }
 
-    pub fn file_statistics(&self) -> LanceFileStatistics {
-        let inner_stat = self.inner.file_statistics();
-        LanceFileStatistics::new(&inner_stat)
+    pub fn file_statistics(&self) -> PyResult<LanceFileStatistics> {
+        let inner_stat = self.inner.file_statistics().infer_error()?;
+        Ok(LanceFileStatistics::new(&inner_stat))
     }
 
     pub fn read_global_buffer(&mut self, index: u32) -> PyResult<Vec<u8>> {
@@ -259,6 +259,7 @@ impl FromPyObject<'_> for PyLance<Transaction> {
             operation,
             blobs_op,
             tag: None,
+            transaction_properties: None,
         }))
     }
 }
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Support for the `arrow.fixed_shape_tensor` canonical extension type.
+//!
+//! A fixed-shape tensor column is a `FixedSizeList` with one tensor per row.  The list values
+//! are the elements of each tensor in row-major order and the shape, along with optional
+//! dimension names and permutation, is stored as JSON in the extension metadata of the field.
+//!
+//! Every tensor has the same number of elements, so tensor `i` starts at element
+//! `i * num_elements` and any tensor, or range of tensors, can be sliced without copying.
+
+use std::sync::Arc;
+
+use arrow_array::{cast::AsArray, Array, ArrayRef, FixedSizeListArray};
+use arrow_schema::{ArrowError, DataType, Field as ArrowField};
+use serde::{Deserialize, Serialize};
+
+use crate::bfloat16::{ARROW_EXT_META_KEY, ARROW_EXT_NAME_KEY};
+
+pub const FIXED_SHAPE_TENSOR_EXT_NAME: &str = "arrow.fixed_shape_tensor";
+
+/// The extension metadata of a fixed-shape tensor field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixedShapeTensorMetadata {
+    /// The physical shape of each tensor, the shape of the values in row-major order.
+    pub shape: Vec<usize>,
+    /// Names of the dimensions, one per dimension.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dim_names: Option<Vec<String>>,
+    /// The physical dimension of each logical dimension, the logical shape is
+    /// `[shape[p] for p in permutation]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permutation: Option<Vec<usize>>,
+}
+
+impl FixedShapeTensorMetadata {
+    pub fn new(shape: impl Into<Vec<usize>>) -> Self {
+        Self {
+            shape: shape.into(),
+            dim_names: None,
+            permutation: None,
+        }
+    }
+
+    pub fn with_dim_names(mut self, dim_names: Vec<String>) -> Self {
+        self.dim_names = Some(dim_names);
+        self
+    }
+
+    pub fn with_permutation(mut self, permutation: Vec<usize>) -> Self {
+        self.permutation = Some(permutation);
+        self
+    }
+
+    /// Parse the metadata from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, ArrowError> {
+        serde_json::from_str(json).map_err(|e| {
+            ArrowError::InvalidArgumentError(format!(
+                "Invalid {} metadata '{}': {}",
+                FIXED_SHAPE_TENSOR_EXT_NAME, json, e
+            ))
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// The number of elements in each tensor.
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// The physical dimension of each logical dimension.
+    fn physical_dims(&self) -> Vec<usize> {
+        self.permutation
+            .clone()
+            .unwrap_or_else(|| (0..self.shape.len()).collect())
+    }
+
+    /// The logical shape of each tensor, the physical shape in the order of the permutation.
+    pub fn logical_shape(&self) -> Vec<usize> {
+        self.physical_dims()
+            .into_iter()
+            .map(|dim| self.shape[dim])
+            .collect()
+    }
+
+    /// The number of elements to skip, in the values of a tensor, to move one step
+    /// along each logical dimension.
+    pub fn strides(&self) -> Vec<usize> {
+        let mut physical_strides = vec![0; self.shape.len()];
+        let mut stride = 1;
+        for (dim, &size) in self.shape.iter().enumerate().rev() {
+            physical_strides[dim] = stride;
+            stride *= size;
+        }
+        self.physical_dims()
+            .into_iter()
+            .map(|dim| physical_strides[dim])
+            .collect()
+    }
+
+    /// The position of the element at the logical `index`, in the values of a tensor.
+    pub fn element_offset(&self, index: &[usize]) -> Result<usize, ArrowError> {
+        let shape = self.logical_shape();
+        if index.len() != shape.len() || index.iter().zip(&shape).any(|(&i, &dim)| i >= dim) {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Index {:?} is out of bounds for a tensor of logical shape {:?}",
+                index, shape
+            )));
+        }
+        Ok(index
+            .iter()
+            .zip(self.strides())
+            .map(|(&i, stride)| i * stride)
+            .sum())
+    }
+
+    /// Check that the metadata describes tensors of `list_size` elements.
+    pub fn validate(&self, list_size: i32) -> Result<(), ArrowError> {
+        let ndim = self.shape.len();
+        if self.num_elements() != list_size as usize {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "A tensor of shape {:?} has {} elements but the list size is {}",
+                self.shape,
+                self.num_elements(),
+                list_size
+            )));
+        }
+        if let Some(dim_names) = &self.dim_names {
+            if dim_names.len() != ndim {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Got {} dimension names for a tensor of shape {:?}",
+                    dim_names.len(),
+                    self.shape
+                )));
+            }
+        }
+        if let Some(permutation) = &self.permutation {
+            let mut sorted = permutation.clone();
+            sorted.sort_unstable();
+            if !sorted.into_iter().eq(0..ndim) {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "{:?} is not a permutation of the dimensions of a tensor of shape {:?}",
+                    permutation, self.shape
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check whether the given field is a fixed-shape tensor field.
+pub fn is_fixed_shape_tensor_field(field: &ArrowField) -> bool {
+    matches!(field.data_type(), DataType::FixedSizeList(_, _))
+        && field
+            .metadata()
+            .get(ARROW_EXT_NAME_KEY)
+            .map(|name| name == FIXED_SHAPE_TENSOR_EXT_NAME)
+            .unwrap_or_default()
+}
+
+/// The tensor metadata of a field, or `None` if it is not a fixed-shape tensor field.
+///
+/// Returns an error if the field is marked as a fixed-shape tensor but the metadata is
+/// missing or doesn't match the data type.
+pub fn fixed_shape_tensor_metadata(
+    field: &ArrowField,
+) -> Result<Option<FixedShapeTensorMetadata>, ArrowError> {
+    if field.metadata().get(ARROW_EXT_NAME_KEY).map(String::as_str)
+        != Some(FIXED_SHAPE_TENSOR_EXT_NAME)
+    {
+        return Ok(None);
+    }
+    let DataType::FixedSizeList(_, list_size) = field.data_type() else {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Field {} is a {} but has type {}, expected a fixed size list",
+            field.name(),
+            FIXED_SHAPE_TENSOR_EXT_NAME,
+            field.data_type()
+        )));
+    };
+    let json = field.metadata().get(ARROW_EXT_META_KEY).ok_or_else(|| {
+        ArrowError::InvalidArgumentError(format!(
+            "Field {} is a {} but has no shape",
+            field.name(),
+            FIXED_SHAPE_TENSOR_EXT_NAME
+        ))
+    })?;
+    let metadata = FixedShapeTensorMetadata::from_json(json)?;
+    metadata.validate(*list_size)?;
+    Ok(Some(metadata))
+}
+
+/// Create a fixed-shape tensor field with elements of `value_type`.
+pub fn fixed_shape_tensor_field(
+    name: &str,
+    value_type: DataType,
+    metadata: &FixedShapeTensorMetadata,
+    nullable: bool,
+) -> Result<ArrowField, ArrowError> {
+    let list_size = i32::try_from(metadata.num_elements()).map_err(|_| {
+        ArrowError::InvalidArgumentError(format!(
+            "A tensor of shape {:?} has too many elements",
+            metadata.shape
+        ))
+    })?;
+    metadata.validate(list_size)?;
+    let data_type = DataType::FixedSizeList(
+        Arc::new(ArrowField::new("item", value_type, true)),
+        list_size,
+    );
+    Ok(ArrowField::new(name, data_type, nullable).with_metadata(
+        [
+            (
+                ARROW_EXT_NAME_KEY.to_string(),
+                FIXED_SHAPE_TENSOR_EXT_NAME.to_string(),
+            ),
+            (ARROW_EXT_META_KEY.to_string(), metadata.to_json()),
+        ]
+        .into(),
+    ))
+}
+
+/// An array of fixed-shape tensors, backed by a [`FixedSizeListArray`].
+#[derive(Debug, Clone)]
+pub struct FixedShapeTensorArray {
+    inner: FixedSizeListArray,
+    metadata: FixedShapeTensorMetadata,
+}
+
+impl FixedShapeTensorArray {
+    /// Wrap `array`, which must be the data of the fixed-shape tensor field `field`.
+    pub fn try_new(field: &ArrowField, array: ArrayRef) -> Result<Self, ArrowError> {
+        let metadata = fixed_shape_tensor_metadata(field)?.ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!(
+                "Field {} is not a {}",
+                field.name(),
+                FIXED_SHAPE_TENSOR_EXT_NAME
+            ))
+        })?;
+        if array.data_type() != field.data_type() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Expected an array of type {} for field {} but got {}",
+                field.data_type(),
+                field.name(),
+                array.data_type()
+            )));
+        }
+        Ok(Self {
+            inner: array.as_fixed_size_list().clone(),
+            metadata,
+        })
+    }
+
+    pub fn metadata(&self) -> &FixedShapeTensorMetadata {
+        &self.metadata
+    }
+
+    /// The physical shape of each tensor, see [`FixedShapeTensorMetadata::logical_shape`].
+    pub fn shape(&self) -> &[usize] {
+        &self.metadata.shape
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn is_null(&self, i: usize) -> bool {
+        self.inner.is_null(i)
+    }
+
+    /// The elements of tensor `i`, without copying.
+    pub fn value(&self, i: usize) -> ArrayRef {
+        self.inner.value(i)
+    }
+
+    /// Element `index` of tensor `i`, as an array of length one, without copying.
+    pub fn element(&self, i: usize, index: &[usize]) -> Result<ArrayRef, ArrowError> {
+        let offset = self.metadata.element_offset(index)?;
+        Ok(self.value(i).slice(offset, 1))
+    }
+
+    /// The elements of all tensors in the array, one tensor after the other.
+    pub fn values(&self) -> &ArrayRef {
+        self.inner.values()
+    }
+
+    /// A zero-copy slice of `length` tensors starting at `offset`.
+    pub fn slice(&self, offset: usize, length: usize) -> Self {
+        Self {
+            inner: self.inner.slice(offset, length),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    pub fn into_inner(self) -> FixedSizeListArray {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{types::Float32Type, Float32Array};
+
+    fn tensors(num_tensors: usize, metadata: &FixedShapeTensorMetadata) -> (ArrowField, ArrayRef) {
+        let field = fixed_shape_tensor_field("tensor", DataType::Float32, metadata, true).unwrap();
+        let size = metadata.num_elements();
+        let values = Float32Array::from_iter_values((0..num_tensors * size).map(|v| v as f32));
+        let DataType::FixedSizeList(item, list_size) = field.data_type() else {
+            unreachable!()
+        };
+        let array =
+            FixedSizeListArray::try_new(item.clone(), *list_size, Arc::new(values), None).unwrap();
+        (field, Arc::new(array))
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata = FixedShapeTensorMetadata::new([2, 3, 4]).with_dim_names(vec![
+            "C".into(),
+            "H".into(),
+            "W".into(),
+        ]);
+        let (field, _) = tensors(1, &metadata);
+        assert!(is_fixed_shape_tensor_field(&field));
+        assert!(matches!(field.data_type(), DataType::FixedSizeList(_, 24)));
+        assert_eq!(
+            fixed_shape_tensor_metadata(&field).unwrap(),
+            Some(metadata.clone())
+        );
+        assert_eq!(
+            FixedShapeTensorMetadata::from_json(r#"{"shape":[2,3,4],"dim_names":["C","H","W"]}"#)
+                .unwrap(),
+            metadata
+        );
+
+        // The shape has to match the list size
+        let mut wrong_size = field.metadata().clone();
+        wrong_size.insert(ARROW_EXT_META_KEY.into(), r#"{"shape":[5,5]}"#.into());
+        let wrong_size = field.clone().with_metadata(wrong_size);
+        assert!(fixed_shape_tensor_metadata(&wrong_size).is_err());
+
+        let bad_permutation = FixedShapeTensorMetadata::new([2, 3]).with_permutation(vec![0, 0]);
+        assert!(bad_permutation.validate(6).is_err());
+
+        let not_a_tensor = ArrowField::new("x", DataType::Float32, true);
+        assert!(!is_fixed_shape_tensor_field(&not_a_tensor));
+        assert_eq!(fixed_shape_tensor_metadata(&not_a_tensor).unwrap(), None);
+    }
+
+    #[test]
+    fn test_strides() {
+        let metadata = FixedShapeTensorMetadata::new([2, 3, 4]);
+        assert_eq!(metadata.strides(), vec![12, 4, 1]);
+        assert_eq!(metadata.element_offset(&[1, 2, 3]).unwrap(), 23);
+        assert!(metadata.element_offset(&[2, 0, 0]).is_err());
+        assert!(metadata.element_offset(&[0, 0]).is_err());
+
+        // Stored as [2, 3, 4], with a logical shape of [4, 2, 3]
+        let metadata = metadata.with_permutation(vec![2, 0, 1]);
+        assert_eq!(metadata.logical_shape(), vec![4, 2, 3]);
+        assert_eq!(metadata.strides(), vec![1, 12, 4]);
+        assert_eq!(metadata.element_offset(&[3, 1, 2]).unwrap(), 23);
+        assert!(metadata.element_offset(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_slicing() {
+        let metadata = FixedShapeTensorMetadata::new([2, 2]);
+        let (field, array) = tensors(10, &metadata);
+        let tensors = FixedShapeTensorArray::try_new(&field, array).unwrap();
+        assert_eq!(tensors.len(), 10);
+        assert_eq!(tensors.shape(), &[2, 2]);
+
+        let sliced = tensors.slice(3, 4);
+        assert_eq!(sliced.len(), 4);
+        assert_eq!(
+            sliced
+                .value(0)
+                .as_primitive::<Float32Type>()
+                .values()
+                .to_vec(),
+            vec![12.0, 13.0, 14.0, 15.0]
+        );
+        assert_eq!(
+            sliced
+                .element(1, &[1, 0])
+                .unwrap()
+                .as_primitive::<Float32Type>()
+                .value(0),
+            18.0
+        );
+
+        let not_a_tensor = ArrowField::new("x", field.data_type().clone(), true);
+        let array: ArrayRef = Arc::new(sliced.into_inner());
+        assert!(FixedShapeTensorArray::try_new(&not_a_tensor, array).is_err());
+    }
+}
@@ -26,6 +26,7 @@ pub mod deepcopy;
 pub mod schema;
 pub use schema::*;
 pub mod bfloat16;
+pub mod fixed_shape_tensor;
 pub mod floats;
 pub use floats::*;
 pub mod cast;
@@ -738,16 +739,7 @@ fn project(struct_array: &StructArray, fields: &Fields) -> Result<StructArray> {
     let mut columns: Vec<ArrayRef> = vec![];
     for field in fields.iter() {
         if let Some(col) = struct_array.column_by_name(field.name()) {
-            match field.data_type() {
-                // TODO handle list-of-struct
-                DataType::Struct(subfields) => {
-                    let projected = project(col.as_struct(), subfields)?;
-                    columns.push(Arc::new(projected));
-                }
-                _ => {
-                    columns.push(col.clone());
-                }
-            }
+            columns.push(project_array(col, field.data_type())?);
         } else {
             return Err(ArrowError::SchemaError(format!(
                 "field {} does not exist in the RecordBatch",
@@ -755,7 +747,31 @@ fn project(struct_array: &StructArray, fields: &Fields) -> Result<StructArray> {
             )));
         }
     }
-    StructArray::try_new(fields.clone(), columns, None)
+    StructArray::try_new(fields.clone(), columns, struct_array.nulls().cloned())
+}
+
+/// Projects the nested structs of `array`, including those inside lists, to `data_type`
+fn project_array(array: &ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    match data_type {
+        DataType::Struct(subfields) => Ok(Arc::new(project(array.as_struct(), subfields)?)),
+        DataType::List(item_field) => project_list::<i32>(array, item_field),
+        DataType::LargeList(item_field) => project_list::<i64>(array, item_field),
+        _ => Ok(array.clone()),
+    }
+}
+
+fn project_list<O: OffsetSizeTrait>(array: &ArrayRef, item_field: &FieldRef) -> Result<ArrayRef> {
+    // A mismatched list type is reported when the parent is assembled
+    let Some(list) = array.as_list_opt::<O>() else {
+        return Ok(array.clone());
+    };
+    let values = project_array(list.values(), item_field.data_type())?;
+    Ok(Arc::new(GenericListArray::<O>::try_new(
+        item_field.clone(),
+        list.offsets().clone(),
+        values,
+        list.nulls().cloned(),
+    )?))
 }
 
 fn lists_have_same_offsets_helper<T: OffsetSizeTrait>(left: &dyn Array, right: &dyn Array) -> bool {
@@ -1488,4 +1504,57 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn test_project_by_schema_list_of_struct() {
+        let item_fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let items = StructArray::new(
+            item_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..6)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..6).map(|i| format!("str-{}", i)),
+                )),
+            ],
+            Some(vec![true, false, true, true, true, true].into()),
+        );
+        let item_field = Arc::new(Field::new("item", DataType::Struct(item_fields), true));
+        let docs = ListArray::new(
+            item_field,
+            OffsetBuffer::from_lengths([2, 0, 4]),
+            Arc::new(items),
+            Some(vec![true, false, true].into()),
+        );
+        let batch = RecordBatch::try_from_iter(vec![("docs", Arc::new(docs) as ArrayRef)]).unwrap();
+
+        let projected_item_fields = Fields::from(vec![Field::new("a", DataType::Int32, true)]);
+        let projected_item_field = Arc::new(Field::new(
+            "item",
+            DataType::Struct(projected_item_fields.clone()),
+            true,
+        ));
+        let projected_schema = Schema::new(vec![Field::new(
+            "docs",
+            DataType::List(projected_item_field.clone()),
+            true,
+        )]);
+        let projected = batch.project_by_schema(&projected_schema).unwrap();
+
+        let expected_items = StructArray::new(
+            projected_item_fields,
+            vec![Arc::new(Int32Array::from_iter_values(0..6))],
+            Some(vec![true, false, true, true, true, true].into()),
+        );
+        let expected = ListArray::new(
+            projected_item_field,
+            OffsetBuffer::from_lengths([2, 0, 4]),
+            Arc::new(expected_items),
+            Some(vec![true, false, true].into()),
+        );
+        assert_eq!(projected.schema().as_ref(), &projected_schema);
+        assert_eq!(projected.column(0).as_ref(), &expected as &dyn Array);
+    }
 }
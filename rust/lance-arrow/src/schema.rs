@@ -82,13 +82,14 @@ impl FieldExt for Field {
         result
     }
 
-    // Check if field has metadata `packed` set to true, this check is case insensitive.
+    // Check if field has metadata `lance-encoding:packed` (or the legacy `packed`) set to true,
+    // this check is case insensitive.
     fn is_packed_struct(&self) -> bool {
         let field_metadata = self.metadata();
-        field_metadata
-            .get("packed")
-            .map(|v| v.to_lowercase() == "true")
-            .unwrap_or(false)
+        ["lance-encoding:packed", "packed"]
+            .iter()
+            .filter_map(|key| field_metadata.get(*key))
+            .any(|v| v.eq_ignore_ascii_case("true"))
     }
 }
 
@@ -4,95 +4,172 @@
 //! Cache implementation
 
 use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
-    Arc,
+    Arc, Mutex, RwLock,
 };
 
 use futures::Future;
+#[cfg(not(target_arch = "wasm32"))]
 use moka::sync::Cache;
+#[cfg(target_arch = "wasm32")]
+use wasm::Cache;
 
+use crate::utils::metrics;
 use crate::Result;
 
 pub use deepsize::{Context, DeepSizeOf};
 
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+/// Category of entries inserted through a cache without one.
+pub const DEFAULT_CATEGORY: &str = "default";
+
 type ArcAny = Arc<dyn Any + Send + Sync>;
+type InnerCache = Cache<(String, TypeId), SizedRecord>;
 
 #[derive(Clone)]
 struct SizedRecord {
     record: ArcAny,
     size_accessor: Arc<dyn Fn(&ArcAny) -> usize + Send + Sync>,
+    category: Arc<str>,
 }
 
 impl std::fmt::Debug for SizedRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SizedRecord")
             .field("record", &self.record)
+            .field("category", &self.category)
             .finish()
     }
 }
 
 impl SizedRecord {
-    fn new<T: DeepSizeOf + Send + Sync + 'static>(record: Arc<T>) -> Self {
+    fn new<T: DeepSizeOf + Send + Sync + 'static>(record: Arc<T>, category: Arc<str>) -> Self {
         // +8 for the size of the Arc pointer itself
         let size_accessor =
             |record: &ArcAny| -> usize { record.downcast_ref::<T>().unwrap().deep_size_of() + 8 };
         Self {
             record,
             size_accessor: Arc::new(size_accessor),
+            category,
+        }
+    }
+
+    fn size(&self) -> usize {
+        (self.size_accessor)(&self.record)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// State shared by a cache and all handles derived from it.
+struct Shared {
+    /// Swapped out as a whole when the cache is resized.
+    cache: RwLock<Arc<InnerCache>>,
+    /// Whether the capacity counts entries instead of bytes.
+    count_entries: bool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    categories: Mutex<HashMap<String, Arc<Counters>>>,
+}
+
+impl Shared {
+    fn new(capacity: u64, count_entries: bool) -> Self {
+        Self {
+            cache: RwLock::new(Arc::new(Self::build(capacity, count_entries))),
+            count_entries,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            categories: Mutex::new(HashMap::new()),
         }
     }
+
+    fn build(capacity: u64, count_entries: bool) -> InnerCache {
+        Cache::builder()
+            .max_capacity(capacity)
+            .weigher(move |_, v: &SizedRecord| {
+                if count_entries {
+                    1
+                } else {
+                    v.size().try_into().unwrap_or(u32::MAX)
+                }
+            })
+            .support_invalidation_closures()
+            .build()
+    }
+
+    fn counters(&self, category: &str) -> Arc<Counters> {
+        self.categories
+            .lock()
+            .unwrap()
+            .entry(category.to_string())
+            .or_default()
+            .clone()
+    }
 }
 
+/// A cache of arbitrary values, bounded by their total size in bytes.
+///
+/// All handles created with [`Self::with_key_prefix`] and
+/// [`Self::with_category`] share the same entries and capacity. Categories
+/// tag the entries inserted through a handle so that [`Self::stats`] can
+/// report the memory used and hit rate of each kind of data separately.
 #[derive(Clone)]
 pub struct LanceCache {
-    cache: Arc<Cache<(String, TypeId), SizedRecord>>,
+    shared: Arc<Shared>,
     prefix: String,
-    hits: Arc<AtomicU64>,
-    misses: Arc<AtomicU64>,
+    category: Arc<str>,
+    counters: Arc<Counters>,
 }
 
 impl std::fmt::Debug for LanceCache {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LanceCache")
-            .field("cache", &self.cache)
+            .field("cache", &self.cache())
+            .field("category", &self.category)
             .finish()
     }
 }
 
 impl DeepSizeOf for LanceCache {
     fn deep_size_of_children(&self, _: &mut Context) -> usize {
-        self.cache
-            .iter()
-            .map(|(_, v)| (v.size_accessor)(&v.record))
-            .sum()
+        self.cache().iter().map(|(_, v)| v.size()).sum()
     }
 }
 
 impl LanceCache {
-    pub fn with_capacity(capacity: usize) -> Self {
-        let cache = Cache::builder()
-            .max_capacity(capacity as u64)
-            .weigher(|_, v: &SizedRecord| {
-                (v.size_accessor)(&v.record).try_into().unwrap_or(u32::MAX)
-            })
-            .support_invalidation_closures()
-            .build();
+    fn from_shared(shared: Shared) -> Self {
+        let counters = shared.counters(DEFAULT_CATEGORY);
         Self {
-            cache: Arc::new(cache),
+            shared: Arc::new(shared),
             prefix: String::new(),
-            hits: Arc::new(AtomicU64::new(0)),
-            misses: Arc::new(AtomicU64::new(0)),
+            category: DEFAULT_CATEGORY.into(),
+            counters,
         }
     }
 
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_shared(Shared::new(capacity as u64, false))
+    }
+
+    /// Create a cache bounded by the number of entries rather than their size.
+    pub fn with_entry_capacity(capacity: usize) -> Self {
+        Self::from_shared(Shared::new(capacity as u64, true))
+    }
+
     pub fn no_cache() -> Self {
-        Self {
-            cache: Arc::new(Cache::new(0)),
-            prefix: String::new(),
-            hits: Arc::new(AtomicU64::new(0)),
-            misses: Arc::new(AtomicU64::new(0)),
-        }
+        Self::with_capacity(0)
+    }
+
+    fn cache(&self) -> Arc<InnerCache> {
+        self.shared.cache.read().unwrap().clone()
     }
 
     /// Appends a prefix to the cache key
@@ -104,13 +181,28 @@ impl LanceCache {
     /// collisions between different caches.
     pub fn with_key_prefix(&self, prefix: &str) -> Self {
         Self {
-            cache: self.cache.clone(),
+            shared: self.shared.clone(),
             prefix: format!("{}{}/", self.prefix, prefix),
-            hits: self.hits.clone(),
-            misses: self.misses.clone(),
+            category: self.category.clone(),
+            counters: self.counters.clone(),
         }
     }
 
+    /// Returns a handle whose entries, hits and misses are accounted to
+    /// `category`.
+    pub fn with_category(&self, category: &str) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            prefix: self.prefix.clone(),
+            category: category.into(),
+            counters: self.shared.counters(category),
+        }
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
     fn get_key(&self, key: &str) -> String {
         if self.prefix.is_empty() {
             key.to_string()
@@ -119,39 +211,84 @@ impl LanceCache {
         }
     }
 
+    fn record_hit(&self) {
+        self.shared.hits.fetch_add(1, Ordering::Relaxed);
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        metrics::increment_counter(metrics::CACHE_HITS, &[("category", &*self.category)], 1);
+    }
+
+    fn record_miss(&self) {
+        self.shared.misses.fetch_add(1, Ordering::Relaxed);
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        metrics::increment_counter(metrics::CACHE_MISSES, &[("category", &*self.category)], 1);
+    }
+
     /// Invalidate all entries in the cache that start with the given prefix
     ///
     /// The given prefix is appended to the existing prefix of the cache. If you
     /// want to invalidate all at the current prefix, pass an empty string.
     pub fn invalidate_prefix(&self, prefix: &str) {
         let full_prefix = format!("{}{}", self.prefix, prefix);
-        self.cache
+        self.shared
+            .cache
+            .read()
+            .unwrap()
             .invalidate_entries_if(move |(key, _typeid), _value| key.starts_with(&full_prefix))
             .expect("Cache configured correctly");
     }
 
     pub fn size(&self) -> usize {
-        self.cache.run_pending_tasks();
-        self.cache.entry_count() as usize
+        let cache = self.cache();
+        cache.run_pending_tasks();
+        cache.entry_count() as usize
     }
 
     pub fn approx_size(&self) -> usize {
-        self.cache.entry_count() as usize
+        self.cache().entry_count() as usize
     }
 
     pub fn size_bytes(&self) -> usize {
-        self.cache.run_pending_tasks();
+        self.cache().run_pending_tasks();
         self.approx_size_bytes()
     }
 
     pub fn approx_size_bytes(&self) -> usize {
-        self.cache.weighted_size() as usize
+        if self.shared.count_entries {
+            self.deep_size_of()
+        } else {
+            self.cache().weighted_size() as usize
+        }
+    }
+
+    /// The capacity of the cache, in bytes or entries depending on how it
+    /// was created.
+    pub fn capacity(&self) -> usize {
+        self.cache().policy().max_capacity().unwrap_or_default() as usize
+    }
+
+    /// Change the capacity of the cache.
+    ///
+    /// Existing entries are kept as long as they fit. This affects every
+    /// handle sharing this cache.
+    pub fn resize(&self, capacity: usize) {
+        // Writes hold the read lock, so none of them can go to the old cache
+        // and be lost while the entries are copied.
+        let mut guard = self.shared.cache.write().unwrap();
+        let new = Shared::build(capacity as u64, self.shared.count_entries);
+        for (key, value) in guard.iter() {
+            new.insert(key.as_ref().clone(), value);
+        }
+        // Evict down to the new capacity before anyone can see the cache
+        new.run_pending_tasks();
+        *guard = Arc::new(new);
     }
 
     pub fn insert<T: DeepSizeOf + Send + Sync + 'static>(&self, key: &str, metadata: Arc<T>) {
         let key = self.get_key(key);
-        self.cache
-            .insert((key, TypeId::of::<T>()), SizedRecord::new(metadata));
+        self.shared.cache.read().unwrap().insert(
+            (key, TypeId::of::<T>()),
+            SizedRecord::new(metadata, self.category.clone()),
+        );
     }
 
     pub fn insert_unsized<T: DeepSizeOf + Send + Sync + 'static + ?Sized>(
@@ -163,13 +300,19 @@ impl LanceCache {
         self.insert(key, Arc::new(metadata))
     }
 
+    /// Whether an entry is cached, without counting as a hit or miss.
+    pub fn contains<T: DeepSizeOf + Send + Sync + 'static>(&self, key: &str) -> bool {
+        let key = self.get_key(key);
+        self.cache().contains_key(&(key, TypeId::of::<T>()))
+    }
+
     pub fn get<T: DeepSizeOf + Send + Sync + 'static>(&self, key: &str) -> Option<Arc<T>> {
         let key = self.get_key(key);
-        if let Some(metadata) = self.cache.get(&(key, TypeId::of::<T>())) {
-            self.hits.fetch_add(1, Ordering::Relaxed);
+        if let Some(metadata) = self.cache().get(&(key, TypeId::of::<T>())) {
+            self.record_hit();
             Some(metadata.record.clone().downcast::<T>().unwrap())
         } else {
-            self.misses.fetch_add(1, Ordering::Relaxed);
+            self.record_miss();
             None
         }
     }
@@ -197,31 +340,85 @@ impl LanceCache {
         Fut: Future<Output = Result<T>>,
     {
         let full_key = self.get_key(&key);
-        if let Some(metadata) = self.cache.get(&(full_key, TypeId::of::<T>())) {
-            self.hits.fetch_add(1, Ordering::Relaxed);
+        if let Some(metadata) = self.cache().get(&(full_key, TypeId::of::<T>())) {
+            self.record_hit();
             return Ok(metadata.record.clone().downcast::<T>().unwrap());
         }
 
-        self.misses.fetch_add(1, Ordering::Relaxed);
+        self.record_miss();
         let metadata = Arc::new(loader(&key).await?);
         self.insert(&key, metadata.clone());
         Ok(metadata)
     }
 
+    /// Statistics for the whole cache, including every category.
     pub fn stats(&self) -> CacheStats {
+        let cache = self.cache();
+        cache.run_pending_tasks();
+
+        let mut categories = self
+            .shared
+            .categories
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, counters)| {
+                let stats = CategoryStats {
+                    hits: counters.hits.load(Ordering::Relaxed),
+                    misses: counters.misses.load(Ordering::Relaxed),
+                    ..Default::default()
+                };
+                (name.clone(), stats)
+            })
+            .collect::<HashMap<_, _>>();
+        for (_, value) in cache.iter() {
+            let stats = categories.entry(value.category.to_string()).or_default();
+            stats.num_entries += 1;
+            stats.size_bytes += value.size() as u64;
+        }
+
         CacheStats {
-            hits: self.hits.load(Ordering::Relaxed),
-            misses: self.misses.load(Ordering::Relaxed),
+            hits: self.shared.hits.load(Ordering::Relaxed),
+            misses: self.shared.misses.load(Ordering::Relaxed),
+            num_entries: categories.values().map(|c| c.num_entries).sum(),
+            size_bytes: categories.values().map(|c| c.size_bytes).sum(),
+            categories,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CacheStats {
     /// Number of times `get`, `get_unsized`, or `get_or_insert` found an item in the cache.
     pub hits: u64,
     /// Number of times `get`, `get_unsized`, or `get_or_insert` did not find an item in the cache.
     pub misses: u64,
+    /// Number of entries in the cache.
+    pub num_entries: u64,
+    /// Total size of the entries in the cache, in bytes.
+    pub size_bytes: u64,
+    /// Breakdown by the category the entries were inserted with.
+    pub categories: HashMap<String, CategoryStats>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub num_entries: u64,
+    pub size_bytes: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that found an item, or 1.0 if there were none.
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            1.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
 }
 
 #[cfg(test)]
@@ -431,4 +628,63 @@ mod tests {
         assert_eq!(stats.hits, 1);
         assert_eq!(stats.misses, 2);
     }
+
+    #[test]
+    fn test_cache_categories() {
+        let cache = LanceCache::with_capacity(10_000);
+        let metadata = cache.with_category("metadata");
+        let index = cache.with_category("index").with_key_prefix("idx");
+
+        metadata.insert("a", Arc::new(vec![0u8; 100]));
+        index.insert("a", Arc::new(vec![0u8; 1000]));
+        assert!(metadata.get::<Vec<u8>>("a").is_some());
+        assert!(index.get::<Vec<u8>>("b").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.num_entries, 2);
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        let metadata_stats = &stats.categories["metadata"];
+        assert_eq!(metadata_stats.num_entries, 1);
+        assert_eq!(metadata_stats.hits, 1);
+        let index_stats = &stats.categories["index"];
+        assert_eq!(index_stats.misses, 1);
+        assert!(index_stats.size_bytes > metadata_stats.size_bytes);
+        assert_eq!(
+            stats.size_bytes,
+            index_stats.size_bytes + metadata_stats.size_bytes
+        );
+    }
+
+    #[test]
+    fn test_cache_resize() {
+        let cache = LanceCache::with_entry_capacity(10);
+        let handle = cache.with_key_prefix("x");
+        for i in 0..10 {
+            handle.insert(&i.to_string(), Arc::new(i));
+        }
+        assert_eq!(cache.size(), 10);
+
+        handle.resize(4);
+        assert_eq!(cache.capacity(), 4);
+        assert_eq!(cache.size(), 4);
+
+        cache.resize(100);
+        for i in 0..20 {
+            handle.insert(&i.to_string(), Arc::new(i));
+        }
+        assert_eq!(cache.size(), 20);
+        assert!(handle.get::<i32>("19").is_some());
+    }
+
+    #[test]
+    fn test_cache_resize_evicts() {
+        let cache = LanceCache::with_entry_capacity(100);
+        for i in 0..100 {
+            cache.insert(&i.to_string(), Arc::new(i));
+        }
+        // Entries over the new capacity are gone as soon as the resize returns,
+        // without waiting for the cache's maintenance.
+        cache.resize(10);
+        assert!(cache.cache().iter().count() <= 10);
+    }
 }
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! A stand-in for `moka::sync::Cache` on wasm
+//!
+//! moka reads the system clock, which is not available on wasm32-unknown-unknown.
+//! This cache implements the part of its API used by [`super::LanceCache`] with a
+//! plain map, evicting the least recently used entries when it is over capacity.
+//! Eviction scans every entry, which is fine for the small caches of a browser.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> u32 + Send + Sync>;
+
+struct Entry<V> {
+    value: V,
+    weight: u64,
+    last_used: u64,
+}
+
+struct Entries<K, V> {
+    map: HashMap<Arc<K>, Entry<V>>,
+    weighted_size: u64,
+    clock: u64,
+}
+
+pub struct Cache<K, V> {
+    entries: Mutex<Entries<K, V>>,
+    max_capacity: u64,
+    weigher: Weigher<K, V>,
+}
+
+pub struct CacheBuilder<K, V> {
+    max_capacity: u64,
+    weigher: Option<Weigher<K, V>>,
+}
+
+impl<K, V> CacheBuilder<K, V> {
+    pub fn max_capacity(mut self, max_capacity: u64) -> Self {
+        self.max_capacity = max_capacity;
+        self
+    }
+
+    pub fn weigher(mut self, weigher: impl Fn(&K, &V) -> u32 + Send + Sync + 'static) -> Self {
+        self.weigher = Some(Box::new(weigher));
+        self
+    }
+
+    /// Invalidation closures are always supported
+    pub fn support_invalidation_closures(self) -> Self {
+        self
+    }
+
+    pub fn build(self) -> Cache<K, V> {
+        Cache {
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                weighted_size: 0,
+                clock: 0,
+            }),
+            max_capacity: self.max_capacity,
+            weigher: self.weigher.unwrap_or_else(|| Box::new(|_, _| 1)),
+        }
+    }
+}
+
+pub struct Policy {
+    max_capacity: u64,
+}
+
+impl Policy {
+    pub fn max_capacity(&self) -> Option<u64> {
+        Some(self.max_capacity)
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Cache<K, V> {
+    pub fn builder() -> CacheBuilder<K, V> {
+        CacheBuilder {
+            max_capacity: u64::MAX,
+            weigher: None,
+        }
+    }
+
+    pub fn policy(&self) -> Policy {
+        Policy {
+            max_capacity: self.max_capacity,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.map.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            entry.value.clone()
+        })
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.lock().unwrap().map.contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let weight = (self.weigher)(&key, &value) as u64;
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let last_used = entries.clock;
+        if let Some(old) = entries.map.insert(
+            Arc::new(key),
+            Entry {
+                value,
+                weight,
+                last_used,
+            },
+        ) {
+            entries.weighted_size -= old.weight;
+        }
+        entries.weighted_size += weight;
+        while entries.weighted_size > self.max_capacity {
+            let Some(key) = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            let evicted = entries.map.remove(&key).unwrap();
+            entries.weighted_size -= evicted.weight;
+        }
+    }
+
+    pub fn invalidate_entries_if(
+        &self,
+        predicate: impl Fn(&K, &V) -> bool,
+    ) -> Result<(), std::convert::Infallible> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut removed = 0;
+        entries.map.retain(|key, entry| {
+            let remove = predicate(key, &entry.value);
+            if remove {
+                removed += entry.weight;
+            }
+            !remove
+        });
+        entries.weighted_size -= removed;
+        Ok(())
+    }
+
+    /// Entries are evicted as they are inserted, so there is nothing pending
+    pub fn run_pending_tasks(&self) {}
+
+    pub fn entry_count(&self) -> u64 {
+        self.entries.lock().unwrap().map.len() as u64
+    }
+
+    pub fn weighted_size(&self) -> u64 {
+        self.entries.lock().unwrap().weighted_size
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Arc<K>, V)> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .map
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.value.clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
@@ -32,6 +32,8 @@ pub const PACKED_STRUCT_META_KEY: &str = "lance-encoding:packed";
 pub const STRUCTURAL_ENCODING_META_KEY: &str = "lance-encoding:structural-encoding";
 pub const STRUCTURAL_ENCODING_MINIBLOCK: &str = "miniblock";
 pub const STRUCTURAL_ENCODING_FULLZIP: &str = "fullzip";
+pub const PAGE_BYTES_META_KEY: &str = "lance-encoding:page-bytes";
+pub const PAGE_ROWS_META_KEY: &str = "lance-encoding:page-rows";
 
 lazy_static::lazy_static! {
     pub static ref BLOB_DESC_FIELDS: Fields =
@@ -121,6 +123,8 @@ impl TryFrom<&DataType> for LogicalType {
             DataType::Binary => "binary".to_string(),
             DataType::LargeUtf8 => "large_string".to_string(),
             DataType::LargeBinary => "large_binary".to_string(),
+            DataType::Utf8View => "string_view".to_string(),
+            DataType::BinaryView => "binary_view".to_string(),
             DataType::Date32 => "date32:day".to_string(),
             DataType::Date64 => "date64:ms".to_string(),
             DataType::Time32(tu) => format!("time32:{}", timeunit_to_str(tu)),
@@ -143,6 +147,11 @@ impl TryFrom<&DataType> for LogicalType {
                     false
                 )
             }
+            DataType::RunEndEncoded(run_ends, values) => format!(
+                "run_end_encoded:{}:{}",
+                Self::try_from(run_ends.data_type())?.0,
+                Self::try_from(values.data_type())?.0
+            ),
             DataType::List(elem) => match elem.data_type() {
                 DataType::Struct(_) => "list.struct".to_string(),
                 _ => "list".to_string(),
@@ -200,6 +209,8 @@ impl TryFrom<&LogicalType> for DataType {
             "binary" => Some(Binary),
             "large_string" => Some(LargeUtf8),
             "large_binary" => Some(LargeBinary),
+            "string_view" => Some(Utf8View),
+            "binary_view" => Some(BinaryView),
             "date32:day" => Some(Date32),
             "date64:ms" => Some(Date64),
             "time32:s" => Some(Time32(TimeUnit::Second)),
@@ -284,6 +295,21 @@ impl TryFrom<&LogicalType> for DataType {
                         Ok(Dictionary(Box::new(index_type), Box::new(value_type)))
                     }
                 }
+                "run_end_encoded" => {
+                    if splits.len() < 3 {
+                        Err(Error::Schema {
+                            message: format!("Unsupported run-end encoded type: {}", lt),
+                            location: location!(),
+                        })
+                    } else {
+                        let run_end_type: Self = (&LogicalType::from(splits[1])).try_into()?;
+                        let value_type: Self = (&LogicalType(splits[2..].join(":"))).try_into()?;
+                        Ok(RunEndEncoded(
+                            Arc::new(ArrowField::new("run_ends", run_end_type, false)),
+                            Arc::new(ArrowField::new("values", value_type, true)),
+                        ))
+                    }
+                }
                 "decimal" => {
                     if splits.len() != 4 {
                         Err(Error::Schema {
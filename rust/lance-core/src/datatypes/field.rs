@@ -20,12 +20,14 @@ use arrow_array::{
 };
 use arrow_schema::{DataType, Field as ArrowField};
 use deepsize::DeepSizeOf;
-use lance_arrow::{bfloat16::ARROW_EXT_NAME_KEY, *};
+use lance_arrow::{
+    bfloat16::ARROW_EXT_NAME_KEY, fixed_shape_tensor::fixed_shape_tensor_metadata, *,
+};
 use snafu::location;
 
 use super::{
     schema::{compare_fields, explain_fields_difference},
-    Dictionary, LogicalType, Projection,
+    Dictionary, LogicalType, Projection, PACKED_STRUCT_LEGACY_META_KEY, PACKED_STRUCT_META_KEY,
 };
 use crate::{Error, Result};
 
@@ -901,13 +903,15 @@ impl Field {
         None
     }
 
-    // Check if field has metadata `packed` set to true, this check is case insensitive.
+    /// Check if the field asks for the packed struct encoding
+    ///
+    /// This is the case if the field metadata has `lance-encoding:packed` (or the legacy
+    /// `packed`) set to true, case insensitive.
     pub fn is_packed_struct(&self) -> bool {
-        let field_metadata = &self.metadata;
-        field_metadata
-            .get("packed")
-            .map(|v| v.to_lowercase() == "true")
-            .unwrap_or(false)
+        [PACKED_STRUCT_META_KEY, PACKED_STRUCT_LEGACY_META_KEY]
+            .iter()
+            .filter_map(|key| self.metadata.get(*key))
+            .any(|v| v.eq_ignore_ascii_case("true"))
     }
 
     /// Return true if the field is a leaf field.
@@ -955,6 +959,10 @@ impl TryFrom<&ArrowField> for Field {
             DataType::LargeList(item) => vec![Self::try_from(item.as_ref())?],
             _ => vec![],
         };
+        fixed_shape_tensor_metadata(field).map_err(|e| Error::Schema {
+            message: e.to_string(),
+            location: location!(),
+        })?;
         let storage_class = field
             .metadata()
             .get(LANCE_STORAGE_CLASS_SCHEMA_META_KEY)
@@ -1081,6 +1089,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fixed_shape_tensor_field() {
+        use lance_arrow::bfloat16::ARROW_EXT_META_KEY;
+        use lance_arrow::fixed_shape_tensor::{fixed_shape_tensor_field, FixedShapeTensorMetadata};
+
+        let tensor_metadata = FixedShapeTensorMetadata::new([3, 4]);
+        let arrow_field =
+            fixed_shape_tensor_field("tensor", DataType::Float32, &tensor_metadata, true).unwrap();
+        let field = Field::try_from(&arrow_field).unwrap();
+        assert_eq!(field.logical_type.0, "fixed_size_list:float:12");
+        assert_eq!(ArrowField::from(&field), arrow_field);
+
+        // A shape that doesn't match the list size is rejected
+        let mut metadata = arrow_field.metadata().clone();
+        metadata.insert(
+            ARROW_EXT_META_KEY.to_string(),
+            r#"{"shape":[3,5]}"#.to_string(),
+        );
+        let arrow_field = arrow_field.with_metadata(metadata);
+        assert!(matches!(
+            Field::try_from(&arrow_field),
+            Err(Error::Schema { .. })
+        ));
+    }
+
     #[test]
     fn test_nested_types() {
         assert_eq!(
@@ -111,6 +111,18 @@ pub enum Error {
         minor_version: u16,
         location: Location,
     },
+    #[snafu(display("Encryption error: {message}, {location}"))]
+    Encryption { message: String, location: Location },
+    #[snafu(display("Quota exceeded: {message}, {location}"))]
+    QuotaExceeded { message: String, location: Location },
+    #[snafu(display("Read-only: {message}, {location}"))]
+    ReadOnly { message: String, location: Location },
+    /// The query was aborted through its [`crate::utils::cancellation::CancellationToken`]
+    #[snafu(display("Query was cancelled, {location}"))]
+    Cancelled { location: Location },
+    /// The query ran past the deadline of its [`crate::utils::cancellation::CancellationToken`]
+    #[snafu(display("Query timed out: {message}, {location}"))]
+    Timeout { message: String, location: Location },
 }
 
 impl Error {
@@ -272,9 +284,9 @@ impl From<prost::UnknownEnumValue> for Error {
     }
 }
 
-impl From<tokio::task::JoinError> for Error {
+impl From<crate::utils::tokio::JoinError> for Error {
     #[track_caller]
-    fn from(e: tokio::task::JoinError) -> Self {
+    fn from(e: crate::utils::tokio::JoinError) -> Self {
         Self::IO {
             source: box_error(e),
             location: std::panic::Location::caller().to_snafu_location(),
@@ -359,7 +371,34 @@ impl From<datafusion_sql::sqlparser::tokenizer::TokenizerError> for Error {
 impl From<Error> for datafusion_common::DataFusionError {
     #[track_caller]
     fn from(e: Error) -> Self {
-        Self::Execution(e.to_string())
+        match e {
+            // Keep the error type so it can be recovered when the plan fails
+            Error::Cancelled { .. } | Error::Timeout { .. } => Self::External(Box::new(e)),
+            e => Self::Execution(e.to_string()),
+        }
+    }
+}
+
+/// Find a cancellation or timeout error wrapped somewhere inside a DataFusion error
+#[cfg(feature = "datafusion")]
+fn find_aborted_query(e: &datafusion_common::DataFusionError) -> Option<Error> {
+    use datafusion_common::DataFusionError;
+    match e {
+        DataFusionError::External(source) => match source.downcast_ref::<Error>() {
+            Some(Error::Cancelled { location }) => Some(Error::Cancelled {
+                location: *location,
+            }),
+            Some(Error::Timeout { message, location }) => Some(Error::Timeout {
+                message: message.clone(),
+                location: *location,
+            }),
+            _ => source
+                .downcast_ref::<DataFusionError>()
+                .and_then(find_aborted_query),
+        },
+        DataFusionError::Shared(inner) => find_aborted_query(inner),
+        DataFusionError::Context(_, inner) => find_aborted_query(inner),
+        _ => None,
     }
 }
 
@@ -368,6 +407,9 @@ impl From<datafusion_common::DataFusionError> for Error {
     #[track_caller]
     fn from(e: datafusion_common::DataFusionError) -> Self {
         let location = std::panic::Location::caller().to_snafu_location();
+        if let Some(aborted) = find_aborted_query(&e) {
+            return aborted;
+        }
         match e {
             datafusion_common::DataFusionError::SQL(..)
             | datafusion_common::DataFusionError::Plan(..)
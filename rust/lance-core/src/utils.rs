@@ -4,11 +4,13 @@
 pub mod address;
 pub mod backoff;
 pub mod bit;
+pub mod cancellation;
 pub mod cpu;
 pub mod deletion;
 pub mod futures;
 pub mod hash;
 pub mod mask;
+pub mod metrics;
 pub mod parse;
 pub mod path;
 pub mod testing;
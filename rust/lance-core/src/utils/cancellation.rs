@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Cooperative cancellation of queries
+//!
+//! A [`CancellationToken`] is handed to a query (e.g. a scan or a vector search)
+//! and checked by the query between units of work such as batches or index
+//! partitions.  Once the token is cancelled, or its deadline passes, the query
+//! fails with [`Error::Cancelled`] or [`Error::Timeout`] at the next check.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use snafu::location;
+
+use crate::{Error, Result};
+
+/// A token that allows the caller of a query to abort it
+///
+/// Clones of a token share the same cancellation state, so the token can be
+/// cloned into the query and kept by the caller to cancel it later.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    token: tokio_util::sync::CancellationToken,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Create a token that is only cancelled by calling [`Self::cancel`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that expires at `deadline`
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            token: tokio_util::sync::CancellationToken::new(),
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Create a token that expires `timeout` from now
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+
+    /// The instant after which the query should be aborted, if any
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Cancel the query, and any other query sharing a clone of this token
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns true if the token was cancelled or its deadline has passed
+    pub fn is_cancelled(&self) -> bool {
+        self.check().is_err()
+    }
+
+    /// Returns an error if the query should stop
+    ///
+    /// An explicit cancellation takes precedence over an expired deadline.
+    pub fn check(&self) -> Result<()> {
+        if self.token.is_cancelled() {
+            return Err(Error::Cancelled {
+                location: location!(),
+            });
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Error::Timeout {
+                message: format!(
+                    "query exceeded its deadline by {:?}",
+                    Instant::now() - deadline
+                ),
+                location: location!(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// A future that resolves once the token is cancelled or its deadline passes
+    ///
+    /// The future does not borrow the token so it can be stored alongside the
+    /// work it guards.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        let token = self.token.clone();
+        let deadline = self.deadline;
+        async move {
+            match deadline {
+                Some(deadline) => {
+                    tokio::select! {
+                        _ = token.cancelled() => {}
+                        _ = tokio::time::sleep_until(deadline.into()) => {}
+                    }
+                }
+                None => token.cancelled().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(clone.check().is_ok());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert!(matches!(clone.check(), Err(Error::Cancelled { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let token = CancellationToken::with_timeout(Duration::from_millis(50));
+        assert!(token.check().is_ok());
+        token.cancelled().await;
+        assert!(matches!(token.check(), Err(Error::Timeout { .. })));
+
+        // An explicit cancel wins over the deadline
+        token.cancel();
+        assert!(matches!(token.check(), Err(Error::Cancelled { .. })));
+    }
+}
@@ -7,6 +7,7 @@ use lazy_static::lazy_static;
 pub enum SimdSupport {
     None,
     Neon,
+    Sve,
     Sse,
     Avx2,
     Avx512,
@@ -19,10 +20,12 @@ lazy_static! {
     pub static ref FP16_SIMD_SUPPORT: SimdSupport = {
         #[cfg(target_arch = "aarch64")]
         {
-            if aarch64::has_neon_f16_support() {
-                SimdSupport::Neon
-            } else {
+            if !aarch64::has_neon_f16_support() {
                 SimdSupport::None
+            } else if aarch64::has_sve_support() {
+                SimdSupport::Sve
+            } else {
+                SimdSupport::Neon
             }
         }
         #[cfg(target_arch = "x86_64")]
@@ -46,6 +49,43 @@ lazy_static! {
             }
         }
     };
+
+    /// Support for the f32 SIMD kernels that are dispatched at runtime
+    ///
+    /// Only aarch64 has such kernels, other architectures use the kernels the compiler
+    /// generates for the target.
+    pub static ref F32_SIMD_SUPPORT: SimdSupport = {
+        #[cfg(target_arch = "aarch64")]
+        {
+            if aarch64::has_sve_support() {
+                SimdSupport::Sve
+            } else {
+                SimdSupport::Neon
+            }
+        }
+        #[cfg(not(target_arch = "aarch64"))]
+        {
+            SimdSupport::None
+        }
+    };
+
+    /// Support for int8 SIMD operations
+    ///
+    /// [`SimdSupport::Avx512`] means AVX-512 with the VNNI dot product instructions.
+    pub static ref INT8_SIMD_SUPPORT: SimdSupport = {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if x86::has_avx512_vnni_support() {
+                SimdSupport::Avx512
+            } else {
+                SimdSupport::None
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            SimdSupport::None
+        }
+    };
 }
 
 #[cfg(target_arch = "x86_64")]
@@ -69,6 +109,13 @@ mod x86 {
         let ext_cpuid_result = unsafe { __cpuid(7) };
         check_flag(ext_cpuid_result.edx as usize, 23)
     }
+
+    pub fn has_avx512_vnni_support() -> bool {
+        is_x86_feature_detected!("avx512f")
+            && is_x86_feature_detected!("avx512bw")
+            && is_x86_feature_detected!("avx512vnni")
+            && is_x86_feature_detected!("bmi2")
+    }
 }
 
 // Inspired by https://github.com/RustCrypto/utils/blob/master/cpufeatures/src/aarch64.rs
@@ -81,6 +128,11 @@ mod aarch64 {
         // Maybe we can assume it's there?
         true
     }
+
+    pub fn has_sve_support() -> bool {
+        // Apple silicon does not implement SVE
+        false
+    }
 }
 
 #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
@@ -90,6 +142,12 @@ mod aarch64 {
         let flags = unsafe { libc::getauxval(libc::AT_HWCAP) };
         flags & libc::HWCAP_FPHP != 0
     }
+
+    pub fn has_sve_support() -> bool {
+        // SVE2 CPUs also set this flag, they run the same kernels
+        let flags = unsafe { libc::getauxval(libc::AT_HWCAP) };
+        flags & libc::HWCAP_SVE != 0
+    }
 }
 
 #[cfg(all(target_arch = "aarch64", target_os = "windows"))]
@@ -98,6 +156,10 @@ mod aarch64 {
         // https://github.com/lancedb/lance/issues/2411
         false
     }
+
+    pub fn has_sve_support() -> bool {
+        false
+    }
 }
 
 #[cfg(target_arch = "loongarch64")]
@@ -119,4 +181,8 @@ mod aarch64 {
     pub fn has_neon_f16_support() -> bool {
         false
     }
+
+    pub fn has_sve_support() -> bool {
+        false
+    }
 }
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! A facade for operational metrics
+//!
+//! Lance reports counters and histograms for scans, writes, commits, the caches and I/O
+//! through the [`MetricsRecorder`] installed with [`set_metrics_recorder`].  Nothing is
+//! recorded until a recorder is installed, so the cost of an unused metric is a single
+//! atomic load.  Host applications implement the recorder to forward the values to
+//! Prometheus, OpenTelemetry or any other metrics system.
+//!
+//! The metric names follow the Prometheus conventions: counters end in `_total` and
+//! durations are histograms in seconds.
+
+use std::sync::{Arc, OnceLock};
+
+use snafu::location;
+
+use crate::{Error, Result};
+
+/// Number of queries run by the scanner
+pub const SCANS: &str = "lance_scans_total";
+/// Number of rows output by queries
+pub const SCAN_ROWS: &str = "lance_scan_rows_total";
+/// Time from starting a query to its last batch
+pub const SCAN_DURATION_SECONDS: &str = "lance_scan_duration_seconds";
+/// Number of rows written to data files
+pub const WRITE_ROWS: &str = "lance_write_rows_total";
+/// Number of data files written
+pub const WRITE_FILES: &str = "lance_write_files_total";
+/// Time taken to write data files, not including the commit
+pub const WRITE_DURATION_SECONDS: &str = "lance_write_duration_seconds";
+/// Number of commits, labelled with the `operation` and the `outcome`
+pub const COMMITS: &str = "lance_commits_total";
+/// Number of commit attempts that failed because of a concurrent commit
+pub const COMMIT_CONFLICTS: &str = "lance_commit_conflicts_total";
+/// Time taken to commit a transaction, including retries
+pub const COMMIT_DURATION_SECONDS: &str = "lance_commit_duration_seconds";
+/// Number of cache hits, labelled with the cache `category`
+pub const CACHE_HITS: &str = "lance_cache_hits_total";
+/// Number of cache misses, labelled with the cache `category`
+pub const CACHE_MISSES: &str = "lance_cache_misses_total";
+/// Number of read requests issued by the I/O scheduler
+pub const IO_REQUESTS: &str = "lance_io_requests_total";
+/// Number of bytes read by the I/O scheduler
+pub const IO_BYTES_READ: &str = "lance_io_bytes_read_total";
+/// Time taken by each read request of the I/O scheduler
+pub const IO_READ_DURATION_SECONDS: &str = "lance_io_read_duration_seconds";
+
+/// Receives the metrics reported by Lance
+///
+/// Implementations must be cheap and must not block, they are called on hot paths such as
+/// every cache lookup and every read.
+pub trait MetricsRecorder: Send + Sync {
+    /// Add `value` to the counter `name`
+    fn increment_counter(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64);
+
+    /// Record one observation of the histogram `name`
+    fn record_histogram(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+}
+
+static RECORDER: OnceLock<Arc<dyn MetricsRecorder>> = OnceLock::new();
+
+/// Install the recorder that receives all metrics of the process
+///
+/// The recorder can only be installed once, installing another one returns an error.
+pub fn set_metrics_recorder(recorder: Arc<dyn MetricsRecorder>) -> Result<()> {
+    RECORDER.set(recorder).map_err(|_| {
+        Error::invalid_input("A metrics recorder has already been installed", location!())
+    })
+}
+
+/// Add `value` to the counter `name`, if a recorder is installed
+pub fn increment_counter(name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.increment_counter(name, labels, value);
+    }
+}
+
+/// Record an observation of the histogram `name`, if a recorder is installed
+pub fn record_histogram(name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.record_histogram(name, labels, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestRecorder {
+        counters: Mutex<HashMap<(String, Vec<(String, String)>), u64>>,
+        histograms: Mutex<HashMap<String, Vec<f64>>>,
+    }
+
+    fn label_key(labels: &[(&'static str, &str)]) -> Vec<(String, String)> {
+        labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    impl MetricsRecorder for TestRecorder {
+        fn increment_counter(
+            &self,
+            name: &'static str,
+            labels: &[(&'static str, &str)],
+            value: u64,
+        ) {
+            *self
+                .counters
+                .lock()
+                .unwrap()
+                .entry((name.to_string(), label_key(labels)))
+                .or_default() += value;
+        }
+
+        fn record_histogram(
+            &self,
+            name: &'static str,
+            _labels: &[(&'static str, &str)],
+            value: f64,
+        ) {
+            self.histograms
+                .lock()
+                .unwrap()
+                .entry(name.to_string())
+                .or_default()
+                .push(value);
+        }
+    }
+
+    #[test]
+    fn test_recorder() {
+        // Nothing to record to yet
+        increment_counter("test_counter", &[], 100);
+
+        let recorder = Arc::new(TestRecorder::default());
+        set_metrics_recorder(recorder.clone()).unwrap();
+        assert!(set_metrics_recorder(Arc::new(TestRecorder::default())).is_err());
+
+        increment_counter("test_counter", &[("kind", "a")], 1);
+        increment_counter("test_counter", &[("kind", "a")], 2);
+        increment_counter("test_counter", &[("kind", "b")], 5);
+        record_histogram("test_histogram", &[], 0.5);
+
+        let counters = recorder.counters.lock().unwrap();
+        let counter = |kind: &str| {
+            counters
+                .get(&(
+                    "test_counter".to_string(),
+                    vec![("kind".to_string(), kind.to_string())],
+                ))
+                .copied()
+        };
+        assert_eq!(counter("a"), Some(3));
+        assert_eq!(counter("b"), Some(5));
+        assert!(!counters.contains_key(&("test_counter".to_string(), vec![])));
+        assert_eq!(
+            recorder.histograms.lock().unwrap().get("test_histogram"),
+            Some(&vec![0.5])
+        );
+    }
+}
@@ -1,20 +1,37 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::Duration;
 
 use crate::Result;
 
-use futures::{Future, FutureExt};
+use futures::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::FutureExt;
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::runtime::{Builder, Runtime};
+#[cfg(not(target_arch = "wasm32"))]
 use tracing::Span;
 
+/// Number of CPUs, which is always 1 on wasm
+fn available_cpus() -> usize {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        num_cpus::get()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        1
+    }
+}
+
 pub fn get_num_compute_intensive_cpus() -> usize {
     if let Ok(user_specified) = std::env::var("LANCE_CPU_THREADS") {
         return user_specified.parse().unwrap();
     }
 
-    let cpus = num_cpus::get();
+    let cpus = available_cpus();
 
     if cpus <= *IO_CORE_RESERVATION {
         // If the user is not setting a custom value for LANCE_IO_CORE_RESERVATION then we don't emit
@@ -28,12 +45,15 @@ pub fn get_num_compute_intensive_cpus() -> usize {
         return 1;
     }
 
-    num_cpus::get() - *IO_CORE_RESERVATION
+    cpus - *IO_CORE_RESERVATION
 }
 
 lazy_static::lazy_static! {
     pub static ref IO_CORE_RESERVATION: usize = std::env::var("LANCE_IO_CORE_RESERVATION").unwrap_or("2".to_string()).parse().unwrap();
+}
 
+#[cfg(not(target_arch = "wasm32"))]
+lazy_static::lazy_static! {
     pub static ref CPU_RUNTIME: Runtime = Builder::new_multi_thread()
         .thread_name("lance-cpu")
         .max_blocking_threads(get_num_compute_intensive_cpus())
@@ -52,6 +72,7 @@ lazy_static::lazy_static! {
 ///
 /// This can also be used to convert a big chunk of synchronous work into a future
 /// so that it can be run in parallel with something like StreamExt::buffered()
+#[cfg(not(target_arch = "wasm32"))]
 pub fn spawn_cpu<F: FnOnce() -> Result<R> + Send + 'static, R: Send + 'static>(
     func: F,
 ) -> impl Future<Output = Result<R>> {
@@ -65,3 +86,118 @@ pub fn spawn_cpu<F: FnOnce() -> Result<R> + Send + 'static, R: Send + 'static>(
     });
     recv.map(|res| res.unwrap())
 }
+
+/// Run a CPU intensive task
+///
+/// There are no threads to offload work to on wasm, so the task runs
+/// when the returned future is first polled.
+#[cfg(target_arch = "wasm32")]
+pub fn spawn_cpu<F: FnOnce() -> Result<R> + Send + 'static, R: Send + 'static>(
+    func: F,
+) -> impl Future<Output = Result<R>> {
+    futures::future::lazy(move |_| func())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use tokio::task::{JoinError, JoinHandle};
+
+/// Spawn a task on the current Tokio runtime
+///
+/// The task keeps running if the returned handle is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future)
+}
+
+/// Run blocking work on the blocking thread pool of the current Tokio runtime
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_blocking<F, R>(func: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(func)
+}
+
+/// The error of a task that did not complete
+///
+/// Tasks on wasm can't be cancelled and panics abort, so this is only returned
+/// if the JavaScript event loop dropped the task.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug)]
+pub struct JoinError(futures::channel::oneshot::Canceled);
+
+#[cfg(target_arch = "wasm32")]
+impl JoinError {
+    /// Tasks can't panic without aborting on wasm, so this always fails.
+    pub fn try_into_panic(self) -> std::result::Result<Box<dyn std::any::Any + Send>, Self> {
+        Err(self)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task was dropped before it completed")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl std::error::Error for JoinError {}
+
+/// A handle to a task started with [`spawn`], which resolves to its output
+#[cfg(target_arch = "wasm32")]
+pub struct JoinHandle<T>(futures::channel::oneshot::Receiver<T>);
+
+#[cfg(target_arch = "wasm32")]
+impl<T> std::fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JoinHandle").finish()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<T> Future for JoinHandle<T> {
+    type Output = std::result::Result<T, JoinError>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.0).poll(cx).map_err(JoinError)
+    }
+}
+
+/// Spawn a task
+///
+/// There is no Tokio runtime in the browser, so on wasm the task is driven by
+/// the JavaScript event loop instead. The task keeps running if the returned
+/// handle is dropped.
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (send, recv) = futures::channel::oneshot::channel();
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = send.send(future.await);
+    });
+    JoinHandle(recv)
+}
+
+/// Run blocking work
+///
+/// There are no threads on wasm, so the work runs on the event loop.
+#[cfg(target_arch = "wasm32")]
+pub fn spawn_blocking<F, R>(func: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    spawn(async move { func() })
+}
@@ -75,3 +75,7 @@ pub const IO_TYPE_LOAD_VECTOR_PART: &str = "load_vector_part";
 pub const IO_TYPE_LOAD_SCALAR_PART: &str = "load_scalar_part";
 pub const TRACE_EXECUTION: &str = "lance::execution";
 pub const EXECUTION_PLAN_RUN: &str = "plan_run";
+/// Target of the spans that follow a query through the scanner, index searches and I/O
+///
+/// These spans are only created when the `query-tracing` feature is enabled.
+pub const TRACE_QUERY: &str = "lance::query";
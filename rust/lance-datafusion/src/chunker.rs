@@ -196,11 +196,111 @@ pub fn chunk_concat_stream(
     Box::pin(RecordBatchStreamAdapter::new(schema_copy, chunk_concat))
 }
 
+struct ByteSizedState {
+    inner: SendableRecordBatchStream,
+    target_bytes: usize,
+    /// Slices waiting to be combined into the next output batch
+    pending: Vec<RecordBatch>,
+    pending_bytes: usize,
+    ready: VecDeque<RecordBatch>,
+    done: bool,
+}
+
+impl ByteSizedState {
+    fn flush(&mut self) -> Result<()> {
+        let batch = match self.pending.len() {
+            0 => return Ok(()),
+            1 => self.pending.pop().unwrap(),
+            _ => {
+                let batch =
+                    kernels::concat::concat_batches(&self.pending[0].schema(), &self.pending)?;
+                self.pending.clear();
+                batch
+            }
+        };
+        self.pending_bytes = 0;
+        self.ready.push_back(batch);
+        Ok(())
+    }
+
+    fn push(&mut self, batch: RecordBatch) -> Result<()> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(());
+        }
+        // Measured before slicing since slices report the size of the buffers they share
+        let bytes_per_row = (batch.get_array_memory_size() / num_rows).max(1);
+        let mut offset = 0;
+        while offset < num_rows {
+            let rows_that_fit =
+                self.target_bytes.saturating_sub(self.pending_bytes) / bytes_per_row;
+            if rows_that_fit == 0 && !self.pending.is_empty() {
+                self.flush()?;
+                continue;
+            }
+            // Every batch gets at least one row, even if a single row is larger than the target
+            let rows = rows_that_fit.max(1).min(num_rows - offset);
+            self.pending.push(batch.slice(offset, rows));
+            self.pending_bytes += rows * bytes_per_row;
+            offset += rows;
+            if self.pending_bytes >= self.target_bytes {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Re-batches a stream so that each batch has roughly `target_bytes` of data
+///
+/// Batches that are too large are sliced (zero-copy) and batches that are too small are
+/// concatenated.  The size of a row is estimated from the in-memory size of the batch it
+/// came from.  Every batch has at least one row, so a batch can exceed the target when a
+/// single row does.
+pub fn byte_sized_stream(
+    stream: SendableRecordBatchStream,
+    target_bytes: usize,
+) -> SendableRecordBatchStream {
+    let schema = stream.schema();
+    let state = ByteSizedState {
+        inner: stream,
+        target_bytes: target_bytes.max(1),
+        pending: Vec::new(),
+        pending_bytes: 0,
+        ready: VecDeque::new(),
+        done: false,
+    };
+    let batches = futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(batch) = state.ready.pop_front() {
+                return Some((Ok(batch), state));
+            }
+            if state.done {
+                return None;
+            }
+            let next = match state.inner.next().await {
+                Some(Ok(batch)) => state.push(batch),
+                Some(Err(err)) => return Some((Err(err), state)),
+                None => {
+                    state.done = true;
+                    state.flush()
+                }
+            };
+            if let Err(err) = next {
+                state.done = true;
+                return Some((Err(DataFusionError::from(err)), state));
+            }
+        }
+    })
+    .boxed();
+    Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use arrow::datatypes::Int32Type;
+    use arrow::datatypes::{Int32Type, Int64Type};
     use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
     use futures::{StreamExt, TryStreamExt};
     use lance_datagen::RowCount;
@@ -266,4 +366,52 @@ mod tests {
         assert_eq!(chunked[2].num_rows(), 5);
         assert_eq!(chunked[3].num_rows(), 8);
     }
+
+    #[tokio::test]
+    async fn test_byte_sized_stream() {
+        let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+            arrow::datatypes::Field::new("", arrow::datatypes::DataType::Int64, false),
+        ]));
+        let make_stream = |sizes: &[u64]| {
+            let batches = sizes
+                .iter()
+                .map(|num_rows| {
+                    datafusion_common::Result::Ok(
+                        lance_datagen::gen()
+                            .anon_col(lance_datagen::array::step::<Int64Type>())
+                            .into_batch_rows(RowCount::from(*num_rows))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            Box::pin(RecordBatchStreamAdapter::new(
+                schema.clone(),
+                futures::stream::iter(batches).boxed(),
+            ))
+        };
+        let row_counts = |batches: Vec<arrow_array::RecordBatch>| {
+            batches
+                .iter()
+                .map(|batch| batch.num_rows())
+                .collect::<Vec<_>>()
+        };
+
+        // 8 bytes per row (the array overhead is negligible at these sizes) so a target of
+        // 80KB is 10K rows.  Large batches are split and small batches are combined
+        let batches = super::byte_sized_stream(
+            make_stream(&[25_000, 3_000, 3_000, 0, 3_000, 2_000]),
+            80_000,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(row_counts(batches), vec![10_000, 10_000, 10_000, 6_000]);
+
+        // A row larger than the target still makes a batch
+        let batches = super::byte_sized_stream(make_stream(&[3]), 1)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(row_counts(batches), vec![1, 1, 1]);
+    }
 }
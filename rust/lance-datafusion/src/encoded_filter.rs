@@ -0,0 +1,502 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Filters that are evaluated against the encoded values of a column
+//!
+//! Dictionary and run-end encoded columns are often used for low-cardinality
+//! strings.  Comparing every row against a literal means comparing a string
+//! for every row (or materializing the column first).  Instead, an equality or
+//! `IN` predicate on one of these columns is evaluated once per distinct value
+//! (the dictionary values or the run values) and the result is then mapped back
+//! to the rows through the dictionary keys or the run ends.
+
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use arrow::compute::{not, or_kleene};
+use arrow_array::builder::BooleanBuilder;
+use arrow_array::{
+    cast::{as_run_array, AsArray},
+    types::{Int16Type, Int32Type, Int64Type, RunEndIndexType},
+    Array, ArrayRef, BooleanArray, RecordBatch, RunArray,
+};
+use arrow_buffer::ArrowNativeType;
+use arrow_schema::{DataType, Schema};
+use datafusion::common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{ColumnarValue, Operator};
+use datafusion::physical_expr::expressions::{BinaryExpr, CastExpr, Column, InListExpr, Literal};
+use datafusion::physical_plan::PhysicalExpr;
+use datafusion::scalar::ScalarValue;
+
+/// Replace the equality and `IN` predicates on dictionary and run-end encoded
+/// columns in `expr` with [`EncodedInListExpr`]
+///
+/// Short `IN` lists are turned into `OR` chains by the simplifier, these are
+/// merged back into a single [`EncodedInListExpr`].
+pub fn rewrite_encoded_filters(
+    expr: Arc<dyn PhysicalExpr>,
+    schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    expr.transform_up(|expr| {
+        Ok(match EncodedInListExpr::try_from_expr(&expr, schema)? {
+            Some(rewritten) => Transformed::yes(Arc::new(rewritten) as Arc<dyn PhysicalExpr>),
+            None => Transformed::no(expr),
+        })
+    })
+    .data()
+}
+
+/// `expr IN (values)` (or `expr NOT IN (values)`) evaluated against the
+/// distinct values of a dictionary or run-end encoded array
+///
+/// If the input is not encoded then every row is compared, as with a normal
+/// `IN` list.  The values may not be null.
+#[derive(Debug)]
+pub struct EncodedInListExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    values: Vec<ScalarValue>,
+    negated: bool,
+}
+
+impl EncodedInListExpr {
+    /// Create a new expression
+    ///
+    /// The values must have the type of the dictionary values / run values
+    /// of `expr`.
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        values: Vec<ScalarValue>,
+        negated: bool,
+    ) -> Result<Self> {
+        if values.is_empty() || values.iter().any(|value| value.is_null()) {
+            return Err(DataFusionError::Internal(
+                "EncodedInListExpr requires at least one value and no null values".to_string(),
+            ));
+        }
+        Ok(Self {
+            expr,
+            values,
+            negated,
+        })
+    }
+
+    /// The type of the dictionary values / run values of an encoded column
+    fn encoded_value_type(data_type: &DataType) -> Option<&DataType> {
+        match data_type {
+            DataType::Dictionary(_, value_type) => Some(value_type.as_ref()),
+            DataType::RunEndEncoded(_, values) => Some(values.data_type()),
+            _ => None,
+        }
+    }
+
+    /// Returns the column (and the type of its values) if `expr` is a
+    /// reference to an encoded column
+    ///
+    /// Type coercion may have cast the column to its value type, which is a
+    /// no-op as far as the predicate is concerned.
+    fn encoded_column(
+        expr: &Arc<dyn PhysicalExpr>,
+        schema: &Schema,
+    ) -> Result<Option<(Arc<dyn PhysicalExpr>, DataType)>> {
+        let (column, cast_type) = if let Some(cast) = expr.as_any().downcast_ref::<CastExpr>() {
+            (cast.expr(), Some(cast.cast_type()))
+        } else {
+            (expr, None)
+        };
+        if column.as_any().downcast_ref::<Column>().is_none() {
+            return Ok(None);
+        }
+        let data_type = column.data_type(schema)?;
+        let Some(value_type) = Self::encoded_value_type(&data_type) else {
+            return Ok(None);
+        };
+        if cast_type.is_some_and(|cast_type| cast_type != value_type) {
+            return Ok(None);
+        }
+        Ok(Some((column.clone(), value_type.clone())))
+    }
+
+    /// Returns the literal, cast to `value_type`, if `expr` is a non-null literal
+    fn literal_value(expr: &Arc<dyn PhysicalExpr>, value_type: &DataType) -> Option<ScalarValue> {
+        let literal = expr.as_any().downcast_ref::<Literal>()?;
+        let value = match literal.value() {
+            ScalarValue::Dictionary(_, value) => value.as_ref(),
+            value => value,
+        };
+        if value.is_null() {
+            return None;
+        }
+        value.cast_to(value_type).ok()
+    }
+
+    /// Merge `lhs OR rhs` (or `lhs AND rhs` if both are negated) into a single
+    /// expression, if both refer to the same column
+    fn try_merge(
+        lhs: &Arc<dyn PhysicalExpr>,
+        rhs: &Arc<dyn PhysicalExpr>,
+        op: Operator,
+    ) -> Option<Self> {
+        let lhs = lhs.as_any().downcast_ref::<Self>()?;
+        let rhs = rhs.as_any().downcast_ref::<Self>()?;
+        let negated = match op {
+            Operator::Or => false,
+            Operator::And => true,
+            _ => return None,
+        };
+        if lhs.negated != negated || rhs.negated != negated || !lhs.expr.eq(&rhs.expr) {
+            return None;
+        }
+        let mut values = lhs.values.clone();
+        values.extend(
+            rhs.values
+                .iter()
+                .filter(|value| !lhs.values.contains(value))
+                .cloned(),
+        );
+        Some(Self {
+            expr: lhs.expr.clone(),
+            values,
+            negated,
+        })
+    }
+
+    /// Convert `expr` into an [`EncodedInListExpr`] if it is an equality or `IN`
+    /// predicate on an encoded column
+    fn try_from_expr(expr: &Arc<dyn PhysicalExpr>, schema: &Schema) -> Result<Option<Self>> {
+        if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+            let negated = match binary.op() {
+                Operator::Eq => false,
+                Operator::NotEq => true,
+                op => return Ok(Self::try_merge(binary.left(), binary.right(), *op)),
+            };
+            for (column, literal) in [
+                (binary.left(), binary.right()),
+                (binary.right(), binary.left()),
+            ] {
+                if let Some((column, value_type)) = Self::encoded_column(column, schema)? {
+                    return Self::literal_value(literal, &value_type)
+                        .map(|value| Self::try_new(column, vec![value], negated))
+                        .transpose();
+                }
+            }
+        } else if let Some(in_list) = expr.as_any().downcast_ref::<InListExpr>() {
+            if let Some((column, value_type)) = Self::encoded_column(in_list.expr(), schema)? {
+                let values = in_list
+                    .list()
+                    .iter()
+                    .map(|item| Self::literal_value(item, &value_type))
+                    .collect::<Option<Vec<_>>>();
+                return match values {
+                    Some(values) if !values.is_empty() => {
+                        Ok(Some(Self::try_new(column, values, in_list.negated())?))
+                    }
+                    _ => Ok(None),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// Evaluate the predicate against each value of `values`
+    fn matches(&self, values: &ArrayRef) -> Result<BooleanArray> {
+        let mut matched: Option<BooleanArray> = None;
+        for value in &self.values {
+            let is_match = arrow_ord::cmp::eq(values, &value.to_scalar()?)?;
+            matched = Some(match matched {
+                Some(matched) => or_kleene(&matched, &is_match)?,
+                None => is_match,
+            });
+        }
+        // There is always at least one value
+        let matched = matched.unwrap();
+        if self.negated {
+            Ok(not(&matched)?)
+        } else {
+            Ok(matched)
+        }
+    }
+
+    /// Evaluate the predicate once per run and then expand the runs
+    fn evaluate_runs<R: RunEndIndexType>(&self, array: &RunArray<R>) -> Result<BooleanArray> {
+        let run_ends = array.run_ends();
+        let mut builder = BooleanBuilder::with_capacity(run_ends.len());
+        if run_ends.is_empty() {
+            return Ok(builder.finish());
+        }
+        let matched = self.matches(array.values())?;
+        // The run ends are logical positions in the unsliced array
+        let offset = run_ends.offset();
+        let len = run_ends.len();
+        let mut run_start = 0;
+        for physical_idx in run_ends.get_start_physical_index()..=run_ends.get_end_physical_index()
+        {
+            let run_end = run_ends.values()[physical_idx]
+                .as_usize()
+                .saturating_sub(offset)
+                .min(len);
+            let run_len = run_end - run_start;
+            if matched.is_valid(physical_idx) {
+                builder.append_n(run_len, matched.value(physical_idx));
+            } else {
+                builder.append_nulls(run_len);
+            }
+            run_start = run_end;
+        }
+        Ok(builder.finish())
+    }
+
+    fn evaluate_array(&self, array: &ArrayRef) -> Result<BooleanArray> {
+        if let Some(dictionary) = array.as_any_dictionary_opt() {
+            let matched = self.matches(dictionary.values())?;
+            let rows = arrow_select::take::take(&matched, dictionary.keys(), None)?;
+            return Ok(rows.as_boolean().clone());
+        }
+        match array.data_type() {
+            DataType::RunEndEncoded(run_ends, _) => match run_ends.data_type() {
+                DataType::Int16 => self.evaluate_runs(as_run_array::<Int16Type>(array)),
+                DataType::Int32 => self.evaluate_runs(as_run_array::<Int32Type>(array)),
+                DataType::Int64 => self.evaluate_runs(as_run_array::<Int64Type>(array)),
+                run_end_type => Err(DataFusionError::Internal(format!(
+                    "Unexpected run end type {}",
+                    run_end_type
+                ))),
+            },
+            _ => self.matches(array),
+        }
+    }
+
+    fn fmt_values(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = if self.negated { "NOT IN" } else { "IN" };
+        write!(f, " {} (", op)?;
+        for (idx, value) in self.values.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl PartialEq for EncodedInListExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr.eq(&other.expr) && self.values == other.values && self.negated == other.negated
+    }
+}
+
+impl Eq for EncodedInListExpr {}
+
+impl Hash for EncodedInListExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.values.hash(state);
+        self.negated.hash(state);
+    }
+}
+
+impl Display for EncodedInListExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)?;
+        self.fmt_values(f)?;
+        write!(f, " (encoded)")
+    }
+}
+
+impl PhysicalExpr for EncodedInListExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.expr.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        match self.expr.evaluate(batch)? {
+            ColumnarValue::Array(array) => {
+                Ok(ColumnarValue::Array(Arc::new(self.evaluate_array(&array)?)))
+            }
+            ColumnarValue::Scalar(scalar) => {
+                let array = self.evaluate_array(&scalar.to_array()?)?;
+                Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                    &array, 0,
+                )?))
+            }
+        }
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn PhysicalExpr>> {
+        vec![&self.expr]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Internal(
+                "EncodedInListExpr: invalid number of children".to_string(),
+            ));
+        }
+        Ok(Arc::new(Self::try_new(
+            children.pop().unwrap(),
+            self.values.clone(),
+            self.negated,
+        )?))
+    }
+
+    fn fmt_sql(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.expr.fmt_sql(f)?;
+        self.fmt_values(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{DictionaryArray, Int32Array, StringArray};
+    use arrow_schema::Field;
+    use datafusion::physical_expr::expressions::col;
+
+    use super::*;
+    use crate::planner::Planner;
+
+    fn test_batch() -> RecordBatch {
+        let dict = DictionaryArray::<Int32Type>::try_new(
+            Int32Array::from(vec![Some(0), Some(1), None, Some(2), Some(0), Some(1)]),
+            Arc::new(StringArray::from(vec!["a", "b", "c"])),
+        )
+        .unwrap();
+        let runs = RunArray::<Int32Type>::try_new(
+            &Int32Array::from(vec![2, 3, 6]),
+            &StringArray::from(vec![Some("a"), None, Some("c")]),
+        )
+        .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("dict", dict.data_type().clone(), true),
+            Field::new("runs", runs.data_type().clone(), true),
+        ]));
+        RecordBatch::try_new(schema, vec![Arc::new(dict), Arc::new(runs)]).unwrap()
+    }
+
+    fn evaluate(expr: &dyn PhysicalExpr, batch: &RecordBatch) -> BooleanArray {
+        expr.evaluate(batch)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap()
+            .as_boolean()
+            .clone()
+    }
+
+    #[test]
+    fn test_dictionary_filters_are_rewritten() {
+        let batch = test_batch();
+        let planner = Planner::new(batch.schema());
+
+        for (filter, expected) in [
+            (
+                "dict = 'a'",
+                vec![
+                    Some(true),
+                    Some(false),
+                    None,
+                    Some(false),
+                    Some(true),
+                    Some(false),
+                ],
+            ),
+            (
+                "dict IN ('b', 'c')",
+                vec![
+                    Some(false),
+                    Some(true),
+                    None,
+                    Some(true),
+                    Some(false),
+                    Some(true),
+                ],
+            ),
+            (
+                "dict NOT IN ('b', 'c')",
+                vec![
+                    Some(true),
+                    Some(false),
+                    None,
+                    Some(false),
+                    Some(true),
+                    Some(false),
+                ],
+            ),
+        ] {
+            let expr = planner.parse_filter(filter).unwrap();
+            let expr = planner.optimize_expr(expr).unwrap();
+            let physical_expr = planner.create_physical_expr(&expr).unwrap();
+            // Short lists become OR chains, which are merged back together
+            assert!(
+                physical_expr
+                    .as_any()
+                    .downcast_ref::<EncodedInListExpr>()
+                    .is_some(),
+                "{} was not rewritten: {}",
+                filter,
+                physical_expr
+            );
+            assert_eq!(
+                evaluate(physical_expr.as_ref(), &batch),
+                BooleanArray::from(expected),
+                "{}",
+                filter
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_end_encoded() {
+        let batch = test_batch();
+        let schema = batch.schema();
+        let expr = EncodedInListExpr::try_new(
+            col("runs", &schema).unwrap(),
+            vec![
+                ScalarValue::Utf8(Some("a".to_string())),
+                ScalarValue::Utf8(Some("c".to_string())),
+            ],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            evaluate(&expr, &batch),
+            BooleanArray::from(vec![
+                Some(true),
+                Some(true),
+                None,
+                Some(true),
+                Some(true),
+                Some(true)
+            ])
+        );
+
+        // Slices start and end in the middle of a run
+        let sliced = batch.slice(1, 4);
+        assert_eq!(
+            evaluate(&expr, &sliced),
+            BooleanArray::from(vec![Some(true), None, Some(true), Some(true)])
+        );
+
+        let expr = EncodedInListExpr::try_new(
+            col("runs", &schema).unwrap(),
+            vec![ScalarValue::Utf8(Some("a".to_string()))],
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            evaluate(&expr, &sliced),
+            BooleanArray::from(vec![Some(false), None, Some(true), Some(true)])
+        );
+    }
+}
@@ -6,6 +6,7 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use arrow_array::RecordBatch;
@@ -47,7 +48,8 @@ use snafu::location;
 
 use crate::utils::{
     MetricsExt, BYTES_READ_METRIC, INDEX_COMPARISONS_METRIC, INDICES_LOADED_METRIC, IOPS_METRIC,
-    PARTS_LOADED_METRIC, REQUESTS_METRIC,
+    PAGES_DECODED_METRIC, PARTITIONS_SEARCHED_METRIC, PARTS_LOADED_METRIC, PREFETCH_HITS_METRIC,
+    REQUESTS_METRIC,
 };
 
 /// An source execution node created from an existing stream
@@ -317,7 +319,7 @@ pub struct ExecutionSummaryCounts {
     pub all_counts: HashMap<String, usize>,
 }
 
-fn visit_node(node: &dyn ExecutionPlan, counts: &mut ExecutionSummaryCounts) {
+fn add_node_counts(node: &dyn ExecutionPlan, counts: &mut ExecutionSummaryCounts) {
     if let Some(metrics) = node.metrics() {
         for (metric_name, count) in metrics.iter_counts() {
             match metric_name.as_ref() {
@@ -337,18 +339,31 @@ fn visit_node(node: &dyn ExecutionPlan, counts: &mut ExecutionSummaryCounts) {
             }
         }
     }
+}
+
+fn visit_node(node: &dyn ExecutionPlan, counts: &mut ExecutionSummaryCounts) {
+    add_node_counts(node, counts);
     for child in node.children() {
         visit_node(child.as_ref(), counts);
     }
 }
 
+/// Sum up the metrics of every node in the plan
+///
+/// This can be called while the plan is still running to get a snapshot of the
+/// work done so far.
+pub fn plan_summary_counts(plan: &dyn ExecutionPlan) -> ExecutionSummaryCounts {
+    let mut counts = ExecutionSummaryCounts::default();
+    visit_node(plan, &mut counts);
+    counts
+}
+
 fn report_plan_summary_metrics(plan: &dyn ExecutionPlan, options: &LanceExecutionOptions) {
     let output_rows = plan
         .metrics()
         .map(|m| m.output_rows().unwrap_or(0))
         .unwrap_or(0);
-    let mut counts = ExecutionSummaryCounts::default();
-    visit_node(plan, &mut counts);
+    let counts = plan_summary_counts(plan);
     tracing::info!(
         target: TRACE_EXECUTION,
         type = EXECUTION_PLAN_RUN,
@@ -416,6 +431,98 @@ pub async fn analyze_plan(
     Ok(format!("{}", display.indent(true)))
 }
 
+/// The metrics of a single node of an analyzed plan, see [`analyze_plan_metrics`]
+#[derive(Clone, Debug)]
+pub struct PlanNodeMetrics {
+    /// The name of the node (e.g. `LanceScan`)
+    pub name: String,
+    /// The node as it is displayed when explaining the plan
+    pub description: String,
+    /// The number of rows produced by the node
+    pub output_rows: usize,
+    /// The time spent computing the output of the node, not including its children
+    pub elapsed_compute: Duration,
+    /// The counts recorded by this node, not including its children
+    pub counts: ExecutionSummaryCounts,
+    /// The inputs of the node
+    pub children: Vec<Self>,
+}
+
+impl PlanNodeMetrics {
+    fn from_plan(node: &dyn ExecutionPlan) -> Self {
+        let metrics = node.metrics().map(|metrics| metrics.aggregate_by_name());
+        let mut counts = ExecutionSummaryCounts::default();
+        add_node_counts(node, &mut counts);
+        Self {
+            name: node.name().to_string(),
+            description: DisplayableExecutionPlan::new(node).one_line().to_string(),
+            output_rows: metrics
+                .as_ref()
+                .and_then(|metrics| metrics.output_rows())
+                .unwrap_or(0),
+            elapsed_compute: Duration::from_nanos(
+                metrics
+                    .as_ref()
+                    .and_then(|metrics| metrics.elapsed_compute())
+                    .unwrap_or(0) as u64,
+            ),
+            counts,
+            children: node
+                .children()
+                .into_iter()
+                .map(|child| Self::from_plan(child.as_ref()))
+                .collect(),
+        }
+    }
+
+    fn other_count(&self, name: &str) -> usize {
+        self.counts.all_counts.get(name).copied().unwrap_or(0)
+    }
+
+    /// The number of bytes read from storage by this node
+    pub fn bytes_read(&self) -> usize {
+        self.counts.bytes_read
+    }
+
+    /// The number of pages decoded by this node
+    pub fn pages_decoded(&self) -> usize {
+        self.other_count(PAGES_DECODED_METRIC)
+    }
+
+    /// The number of index partitions searched by this node
+    pub fn partitions_probed(&self) -> usize {
+        self.other_count(PARTITIONS_SEARCHED_METRIC)
+    }
+
+    /// The number of reads that were served by data already fetched ahead of time
+    pub fn cache_hits(&self) -> usize {
+        self.other_count(PREFETCH_HITS_METRIC)
+    }
+
+    /// The fraction of reads served by data already fetched ahead of time
+    ///
+    /// Returns None if the node didn't read anything.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let reads = self.cache_hits() + self.counts.iops;
+        (reads > 0).then(|| self.cache_hits() as f64 / reads as f64)
+    }
+}
+
+/// Runs the plan to completion and returns the metrics of every node as a tree
+///
+/// This is the structured equivalent of [`analyze_plan`].  The root of the tree is
+/// the root of the plan.
+pub async fn analyze_plan_metrics(
+    plan: Arc<dyn ExecutionPlan>,
+    options: LanceExecutionOptions,
+) -> Result<PlanNodeMetrics> {
+    let mut stream = execute_plan(plan.clone(), options)?;
+    while let Some(batch) = stream.next().await {
+        batch?;
+    }
+    Ok(PlanNodeMetrics::from_plan(plan.as_ref()))
+}
+
 pub trait SessionContextExt {
     /// Creates a DataFrame for reading a stream of data
     ///
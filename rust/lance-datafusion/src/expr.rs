@@ -17,6 +17,19 @@ const MS_PER_DAY: i64 = 86400000;
 // will always yield "x = 7_u64" regardless of the type of the column "x".  As a result, we
 // need to do that literal coercion ourselves.
 pub fn safe_coerce_scalar(value: &ScalarValue, ty: &DataType) -> Option<ScalarValue> {
+    // Encoded columns are compared against their values
+    match ty {
+        DataType::Dictionary(key_type, value_type)
+            if !matches!(value, ScalarValue::Null | ScalarValue::Dictionary(..)) =>
+        {
+            return safe_coerce_scalar(value, value_type)
+                .map(|value| ScalarValue::Dictionary(key_type.clone(), Box::new(value)));
+        }
+        DataType::RunEndEncoded(_, values) => {
+            return safe_coerce_scalar(value, values.data_type());
+        }
+        _ => {}
+    }
     match value {
         ScalarValue::Int8(val) => match ty {
             DataType::Int8 => Some(value.clone()),
@@ -426,6 +439,8 @@ pub fn safe_coerce_scalar(value: &ScalarValue, ty: &DataType) -> Option<ScalarVa
 
 #[cfg(test)]
 mod tests {
+    use arrow_schema::Field;
+
     use super::*;
 
     #[test]
@@ -702,4 +717,26 @@ mod tests {
             Some(ScalarValue::Time64Nanosecond(Some(5000000000)))
         );
     }
+
+    #[test]
+    fn test_encoded_coerce() {
+        // Literals compared to a dictionary become dictionary literals
+        let dict_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        assert_eq!(
+            safe_coerce_scalar(&ScalarValue::Utf8(Some("a".to_string())), &dict_type),
+            Some(ScalarValue::Dictionary(
+                Box::new(DataType::Int32),
+                Box::new(ScalarValue::Utf8(Some("a".to_string())))
+            ))
+        );
+        // Literals compared to run-end encoded values are coerced to the value type
+        let ree_type = DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", DataType::Int64, true)),
+        );
+        assert_eq!(
+            safe_coerce_scalar(&ScalarValue::Int32(Some(5)), &ree_type),
+            Some(ScalarValue::Int64(Some(5)))
+        );
+    }
 }
@@ -4,6 +4,7 @@
 pub mod chunker;
 pub mod dataframe;
 pub mod datagen;
+pub mod encoded_filter;
 pub mod exec;
 pub mod expr;
 pub mod logical_expr;
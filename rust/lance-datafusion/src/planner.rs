@@ -7,6 +7,7 @@ use std::borrow::Cow;
 use std::collections::{BTreeSet, VecDeque};
 use std::sync::Arc;
 
+use crate::encoded_filter::rewrite_encoded_filters;
 use crate::expr::safe_coerce_scalar;
 use crate::logical_expr::{coerce_filter_type_to_boolean, get_as_string_scalar_opt, resolve_expr};
 use crate::sql::{parse_sql_expr, parse_sql_filter};
@@ -856,13 +857,20 @@ impl Planner {
     }
 
     /// Create the [`PhysicalExpr`] from a logical [`Expr`]
+    ///
+    /// Equality and `IN` predicates on dictionary and run-end encoded columns are
+    /// evaluated against the encoded values, see [`crate::encoded_filter`].
     pub fn create_physical_expr(&self, expr: &Expr) -> Result<Arc<dyn PhysicalExpr>> {
         let df_schema = Arc::new(DFSchema::try_from(self.schema.as_ref().clone())?);
 
-        Ok(datafusion::physical_expr::create_physical_expr(
+        let physical_expr = datafusion::physical_expr::create_physical_expr(
             expr,
             df_schema.as_ref(),
             &Default::default(),
+        )?;
+        Ok(rewrite_encoded_filters(
+            physical_expr,
+            self.schema.as_ref(),
         )?)
     }
 
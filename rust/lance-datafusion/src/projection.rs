@@ -2,13 +2,19 @@
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
 use arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema};
-use arrow_array::RecordBatch;
+use arrow_array::{cast::AsArray, Array, ArrayRef, GenericListArray, OffsetSizeTrait, RecordBatch};
 use arrow_schema::{DataType, SchemaRef};
 use datafusion::{
-    execution::SendableRecordBatchStream, logical_expr::Expr,
+    error::{DataFusionError, Result as DFResult},
+    execution::SendableRecordBatchStream,
+    logical_expr::{
+        expr::ScalarFunction, ColumnarValue, Expr, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl,
+        Signature, Volatility,
+    },
     physical_plan::projection::ProjectionExec,
 };
-use datafusion_common::DFSchema;
+use datafusion_common::{Column, DFSchema};
+use datafusion_functions::core::getfield::GetFieldFunc;
 use datafusion_physical_expr::{expressions, PhysicalExpr};
 use futures::TryStreamExt;
 use snafu::location;
@@ -24,9 +30,122 @@ use lance_core::{
 
 use crate::{
     exec::{execute_plan, LanceExecutionOptions, OneShotExec},
+    logical_expr::get_as_string_scalar_opt,
     planner::Planner,
 };
 
+/// Extracts a field from the items of a list column, keeping the lists
+///
+/// `path` starts at the items of the column, e.g. `["item", "a"]` for `docs.item.a`, and
+/// the result has one list per row with the values of the field in that row's items.
+#[derive(Debug)]
+struct ListItemFieldUdf {
+    // The path is part of the name so that different fields compare as different functions
+    name: String,
+    path: Vec<String>,
+    signature: Signature,
+}
+
+impl ListItemFieldUdf {
+    fn new(path: Vec<String>) -> Self {
+        Self {
+            name: format!("_list_item_field({})", path.join(".")),
+            path,
+            signature: Signature::any(1, Volatility::Immutable),
+        }
+    }
+}
+
+fn field_not_found(name: &str, data_type: &DataType) -> DataFusionError {
+    DataFusionError::Plan(format!("Field {} not found in {}", name, data_type))
+}
+
+fn item_field_type(data_type: &DataType, path: &[String]) -> DFResult<DataType> {
+    let Some((name, rest)) = path.split_first() else {
+        return Ok(data_type.clone());
+    };
+    match data_type {
+        DataType::List(item) | DataType::LargeList(item) if item.name() == name => {
+            let item = Arc::new(ArrowField::new(
+                name,
+                item_field_type(item.data_type(), rest)?,
+                true,
+            ));
+            Ok(match data_type {
+                DataType::List(_) => DataType::List(item),
+                _ => DataType::LargeList(item),
+            })
+        }
+        DataType::Struct(fields) => {
+            let (_, field) = fields
+                .find(name)
+                .ok_or_else(|| field_not_found(name, data_type))?;
+            item_field_type(field.data_type(), rest)
+        }
+        _ => Err(field_not_found(name, data_type)),
+    }
+}
+
+fn item_field_array(array: &ArrayRef, path: &[String]) -> DFResult<ArrayRef> {
+    let Some((name, rest)) = path.split_first() else {
+        return Ok(array.clone());
+    };
+    match array.data_type() {
+        DataType::List(_) => list_item_field_array(array.as_list::<i32>(), name, rest),
+        DataType::LargeList(_) => list_item_field_array(array.as_list::<i64>(), name, rest),
+        DataType::Struct(_) => {
+            let child = array
+                .as_struct()
+                .column_by_name(name)
+                .ok_or_else(|| field_not_found(name, array.data_type()))?;
+            item_field_array(child, rest)
+        }
+        data_type => Err(field_not_found(name, data_type)),
+    }
+}
+
+fn list_item_field_array<O: OffsetSizeTrait>(
+    list: &GenericListArray<O>,
+    item_name: &str,
+    path: &[String],
+) -> DFResult<ArrayRef> {
+    let values = item_field_array(list.values(), path)?;
+    let item = Arc::new(ArrowField::new(item_name, values.data_type().clone(), true));
+    Ok(Arc::new(GenericListArray::<O>::try_new(
+        item,
+        list.offsets().clone(),
+        values,
+        list.nulls().cloned(),
+    )?))
+}
+
+impl ScalarUDFImpl for ListItemFieldUdf {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> DFResult<DataType> {
+        item_field_type(&arg_types[0], &self.path)
+    }
+
+    fn invoke_with_args(&self, func_args: ScalarFunctionArgs) -> DFResult<ColumnarValue> {
+        let ColumnarValue::Array(array) = &func_args.args[0] else {
+            return Err(DataFusionError::Execution(
+                "_list_item_field only supports array arguments".to_string(),
+            ));
+        };
+        Ok(ColumnarValue::Array(item_field_array(array, &self.path)?))
+    }
+}
+
 #[derive(Debug)]
 pub struct ProjectionPlan {
     /// The physical schema (before dynamic projection) that must be loaded from the dataset
@@ -42,7 +161,10 @@ pub struct ProjectionPlan {
 }
 
 impl ProjectionPlan {
-    fn unload_blobs(schema: &Arc<Schema>) -> Arc<Schema> {
+    /// Replaces blob columns with their descriptions, the position and size of each blob
+    ///
+    /// Scans return the descriptions instead of loading the blobs.
+    pub fn unload_blobs(schema: &Arc<Schema>) -> Arc<Schema> {
         let mut modified = false;
         let fields = schema
             .fields
@@ -74,6 +196,49 @@ impl ProjectionPlan {
         }
     }
 
+    /// If `expr` refers to a field inside the items of a list, e.g. `docs.item.a`, returns
+    /// an expression that extracts the field from the items of the top-level column
+    ///
+    /// Such a reference can't be evaluated with `get_field`.  Instead, the top-level column
+    /// is read with its items pruned down to the referenced fields, and the field is taken
+    /// from each item, so that every row gets a list of the field's values.
+    fn list_item_reference(expr: &Expr, schema: &Schema) -> Option<Expr> {
+        let mut path = Vec::new();
+        let mut current = expr;
+        loop {
+            match current {
+                Expr::Column(column) => {
+                    path.push(column.name.as_str());
+                    break;
+                }
+                Expr::ScalarFunction(udf) if udf.name() == GetFieldFunc::default().name() => {
+                    path.push(get_as_string_scalar_opt(&udf.args[1])?);
+                    current = &udf.args[0];
+                }
+                _ => return None,
+            }
+        }
+        path.reverse();
+
+        let mut field = schema.field(path[0])?;
+        let mut in_list = false;
+        for name in &path[1..] {
+            in_list |= matches!(
+                field.data_type(),
+                DataType::List(_) | DataType::LargeList(_)
+            );
+            field = field.children.iter().find(|child| child.name == *name)?;
+        }
+        in_list.then(|| {
+            let udf =
+                ListItemFieldUdf::new(path[1..].iter().map(|name| name.to_string()).collect());
+            Expr::ScalarFunction(ScalarFunction {
+                func: Arc::new(ScalarUDF::new_from_impl(udf)),
+                args: vec![Expr::Column(Column::new_unqualified(path[0]))],
+            })
+        })
+    }
+
     pub fn try_new(
         base_schema: &Schema,
         columns: &[(impl AsRef<str>, impl AsRef<str>)],
@@ -91,7 +256,8 @@ impl ProjectionPlan {
                     location!(),
                 ));
             }
-            let expr = planner.parse_expr(raw_expr.as_ref())?;
+            let mut expr = planner.parse_expr(raw_expr.as_ref())?;
+            let list_item_expr = Self::list_item_reference(&expr, base_schema);
             for col in Planner::column_names_in_expr(&expr) {
                 if physical_cols_set.contains(&col) {
                     continue;
@@ -99,6 +265,9 @@ impl ProjectionPlan {
                 physical_cols.push(col.clone());
                 physical_cols_set.insert(col);
             }
+            if let Some(list_item_expr) = list_item_expr {
+                expr = list_item_expr;
+            }
             output.insert(output_name.as_ref().to_string(), expr);
         }
 
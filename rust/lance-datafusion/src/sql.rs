@@ -85,6 +85,63 @@ pub(crate) fn parse_sql_expr(expr: &str) -> Result<Expr> {
     Ok(expr.clone())
 }
 
+/// Parse a SQL projection item, such as `price * qty AS total`, into an output name and
+/// an expression.
+///
+/// Items without an alias are named after the column they reference, or after the text of
+/// the expression if it is not a plain column (e.g. `lower(name)`).
+pub fn parse_sql_projection(item: &str) -> Result<(String, String)> {
+    let sql = format!("SELECT {item} FROM t");
+    let statement = parse_statement(&sql)?;
+
+    let select_item = if let Statement::Query(query) = &statement {
+        if let SetExpr::Select(s) = query.body.as_ref() {
+            if s.projection.len() == 1 {
+                Some(&s.projection[0])
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    match select_item {
+        Some(SelectItem::ExprWithAlias { expr, alias }) => {
+            Ok((alias.value.clone(), expr.to_string()))
+        }
+        Some(SelectItem::UnnamedExpr(expr)) => {
+            let name = match expr {
+                Expr::Identifier(ident) => ident.value.clone(),
+                Expr::CompoundIdentifier(idents) => idents
+                    .iter()
+                    .map(|ident| ident.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join("."),
+                _ => item.trim().to_string(),
+            };
+            Ok((name, expr.to_string()))
+        }
+        _ => Err(Error::invalid_input(
+            format!("Projection is not valid: {item}"),
+            location!(),
+        )),
+    }
+}
+
+/// If the SQL expression is a plain (possibly nested) column reference, such as
+/// `doc.meta.lang`, returns the names along the path to the column.
+pub fn parse_sql_column_path(expr: &str) -> Result<Option<Vec<String>>> {
+    Ok(match parse_sql_expr(expr)? {
+        Expr::Identifier(ident) => Some(vec![ident.value]),
+        Expr::CompoundIdentifier(idents) => {
+            Some(idents.into_iter().map(|ident| ident.value).collect())
+        }
+        _ => None,
+    })
+}
+
 fn parse_statement(statement: &str) -> Result<Statement> {
     let dialect = LanceDialect::new();
 
@@ -192,4 +249,28 @@ mod tests {
             expr
         );
     }
+
+    #[test]
+    fn test_projection() {
+        let (name, expr) = parse_sql_projection("price * qty AS total").unwrap();
+        assert_eq!(name, "total");
+        assert_eq!(expr, "price * qty");
+
+        let (name, expr) = parse_sql_projection(" lower(name) ").unwrap();
+        assert_eq!(name, "lower(name)");
+        assert_eq!(expr, "lower(name)");
+
+        let (name, expr) = parse_sql_projection("`outer field`.`inner field`").unwrap();
+        assert_eq!(name, "outer field.inner field");
+        assert_eq!(expr, "`outer field`.`inner field`");
+
+        assert!(parse_sql_projection("a, b").is_err());
+        assert!(parse_sql_projection("*").is_err());
+
+        assert_eq!(
+            parse_sql_column_path("`outer field`.inner").unwrap(),
+            Some(vec!["outer field".to_string(), "inner".to_string()])
+        );
+        assert_eq!(parse_sql_column_path("lower(a)").unwrap(), None);
+    }
 }
@@ -2,6 +2,8 @@
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
 use std::borrow::Cow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use arrow::ffi_stream::ArrowArrayStreamReader;
 use arrow_array::{RecordBatch, RecordBatchIterator, RecordBatchReader};
@@ -17,8 +19,10 @@ use datafusion::{
     },
 };
 use datafusion_common::DataFusionError;
-use futures::{stream, StreamExt, TryStreamExt};
+use futures::future::BoxFuture;
+use futures::{stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use lance_core::datatypes::Schema;
+use lance_core::utils::cancellation::CancellationToken;
 use lance_core::Result;
 use tokio::task::spawn;
 
@@ -150,6 +154,51 @@ pub fn reader_to_stream(batches: Box<dyn RecordBatchReader + Send>) -> SendableR
     Box::pin(stream)
 }
 
+struct CancellableStream {
+    inner: SendableRecordBatchStream,
+    cancelled: BoxFuture<'static, ()>,
+    token: CancellationToken,
+    done: bool,
+}
+
+impl Stream for CancellableStream {
+    type Item = datafusion_common::Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if self.cancelled.poll_unpin(cx).is_ready() {
+            self.done = true;
+            return Poll::Ready(self.token.check().err().map(|e| Err(e.into())));
+        }
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl RecordBatchStream for CancellableStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// Abort a stream once `token` is cancelled or its deadline passes.
+///
+/// The stream yields a single [`lance_core::Error::Cancelled`] or
+/// [`lance_core::Error::Timeout`] and then ends, even if it was waiting
+/// on I/O at the time.
+pub fn cancellable_stream(
+    stream: SendableRecordBatchStream,
+    token: CancellationToken,
+) -> SendableRecordBatchStream {
+    Box::pin(CancellableStream {
+        inner: stream,
+        cancelled: token.cancelled().boxed(),
+        token,
+        done: false,
+    })
+}
+
 pub trait MetricsExt {
     fn find_count(&self, name: &str) -> Option<Count>;
     fn iter_counts(&self) -> impl Iterator<Item = (impl AsRef<str>, &Count)>;
@@ -220,3 +269,5 @@ pub const ROWS_SCANNED_METRIC: &str = "rows_scanned";
 pub const TASK_WAIT_TIME_METRIC: &str = "task_wait_time";
 pub const DELTAS_SEARCHED_METRIC: &str = "deltas_searched";
 pub const PARTITIONS_SEARCHED_METRIC: &str = "partitions_searched";
+pub const PREFETCH_HITS_METRIC: &str = "prefetch_hits";
+pub const PAGES_DECODED_METRIC: &str = "pages_decoded";
@@ -47,6 +47,7 @@ const PRIMITIVE_TYPES_FOR_FSL: &[DataType] = &[DataType::Int8, DataType::Float32
 
 const ENCODING_OPTIONS: EncodingOptions = EncodingOptions {
     cache_bytes_per_column: 8 * 1024 * 1024,
+    cache_rows_per_column: None,
     max_page_bytes: 32 * 1024 * 1024,
     keep_original_array: true,
     buffer_alignment: 64,
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+use std::sync::Arc;
+
+use arrow_array::{
+    types::{Float32Type, Int32Type},
+    RecordBatch,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use lance_datagen::{array, ArrayGenerator, ByteCount, Dimension, RowCount};
+use lance_encoding::{
+    encoder::{default_encoding_strategy, encode_batch, EncodingOptions},
+    version::LanceFileVersion,
+};
+
+const ENCODING_OPTIONS: EncodingOptions = EncodingOptions {
+    cache_bytes_per_column: 8 * 1024 * 1024,
+    cache_rows_per_column: None,
+    max_page_bytes: 32 * 1024 * 1024,
+    keep_original_array: true,
+    buffer_alignment: 64,
+};
+
+const NUM_ROWS: u64 = 1024 * 1024;
+
+// Synthetic columns with different distributions, so each compressive encoding
+// (bitpacking, dictionary, fsst, ...) is exercised as well as the plain paths.
+fn datasets() -> Vec<(&'static str, Box<dyn ArrayGenerator>, u64)> {
+    vec![
+        ("int32_random", array::rand::<Int32Type>(), NUM_ROWS),
+        ("int32_sequential", array::step::<Int32Type>(), NUM_ROWS),
+        (
+            "utf8_low_cardinality",
+            array::cycle_utf8_literals(&["red", "green", "blue", "yellow", "purple"]),
+            NUM_ROWS,
+        ),
+        (
+            "utf8_random",
+            array::rand_utf8(ByteCount::from(16), false),
+            NUM_ROWS,
+        ),
+        (
+            "vector_f32_128",
+            array::rand_vec::<Float32Type>(Dimension::from(128)),
+            NUM_ROWS / 16,
+        ),
+    ]
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("encode");
+    for (name, generator, num_rows) in datasets() {
+        let data: RecordBatch = lance_datagen::gen()
+            .anon_col(generator)
+            .into_batch_rows(RowCount::from(num_rows))
+            .unwrap();
+        let lance_schema =
+            Arc::new(lance_core::datatypes::Schema::try_from(data.schema().as_ref()).unwrap());
+        let num_bytes = data
+            .columns()
+            .iter()
+            .map(|array| array.get_buffer_memory_size() as u64)
+            .sum();
+        group.throughput(Throughput::Bytes(num_bytes));
+        for version in [LanceFileVersion::V2_0, LanceFileVersion::V2_1] {
+            let func_name = format!("{}_v{}", name, version);
+            group.bench_function(func_name, |b| {
+                let encoding_strategy = default_encoding_strategy(version);
+                b.iter(|| {
+                    let encoded = rt
+                        .block_on(encode_batch(
+                            &data,
+                            lance_schema.clone(),
+                            encoding_strategy.as_ref(),
+                            &ENCODING_OPTIONS,
+                        ))
+                        .unwrap();
+                    assert_eq!(encoded.num_rows, num_rows);
+                })
+            });
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+criterion_group!(
+    name=benches;
+    config = Criterion::default().significance_level(0.1).sample_size(10)
+        .with_profiler(pprof::criterion::PProfProfiler::new(100, pprof::criterion::Output::Flamegraph(None)));
+    targets = bench_encode);
+
+// Non-linux version does not support pprof.
+#[cfg(not(target_os = "linux"))]
+criterion_group!(
+    name=benches;
+    config = Criterion::default().significance_level(0.1).sample_size(10);
+    targets = bench_encode);
+criterion_main!(benches);
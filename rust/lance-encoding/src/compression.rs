@@ -27,8 +27,12 @@ use crate::{
                 VariableDecoder, VariableEncoder,
             },
             bitpack::InlineBitpacking,
-            block::CompressedBufferEncoder,
+            block::{
+                CompressedBufferEncoder, CompressionConfig, CompressionScheme,
+                GeneralMiniBlockCompressor, GeneralMiniBlockDecompressor,
+            },
             constant::ConstantDecompressor,
+            frame_of_reference::FrameOfReferenceBitpacking,
             fsst::{
                 FsstMiniBlockDecompressor, FsstMiniBlockEncoder, FsstPerValueDecompressor,
                 FsstPerValueEncoder,
@@ -36,6 +40,7 @@ use crate::{
             packed::{
                 PackedStructFixedWidthMiniBlockDecompressor, PackedStructFixedWidthMiniBlockEncoder,
             },
+            rle::RunLengthEncoding,
             value::{ValueDecompressor, ValueEncoder},
         },
     },
@@ -109,8 +114,88 @@ pub trait CompressionStrategy: Send + Sync + std::fmt::Debug {
 #[derive(Debug, Default)]
 pub struct DefaultCompressionStrategy;
 
-impl CompressionStrategy for DefaultCompressionStrategy {
-    fn create_miniblock_compressor(
+impl DefaultCompressionStrategy {
+    /// Whether to FSST compress variable width data
+    ///
+    /// Users can request FSST, or turn it off, with the `lance-encoding:compression` field
+    /// metadata (`"fsst"` or `"none"`).  Otherwise we only use FSST when the values are long
+    /// enough and there is enough data to train a useful symbol table.
+    ///
+    /// FSST only supports 32-bit offsets, so requesting it for large strings or binary is an
+    /// error.
+    fn use_fsst(field: &Field, data: &VariableWidthBlock) -> Result<bool> {
+        match field.metadata.get(COMPRESSION_META_KEY).map(String::as_str) {
+            Some("fsst") if data.bits_per_offset != 32 => Err(Error::invalid_input(
+                format!(
+                    "FSST compression was requested for field '{}' but is not supported for {}",
+                    field.name,
+                    field.data_type()
+                ),
+                location!(),
+            )),
+            Some("fsst") => Ok(true),
+            Some("none") => Ok(false),
+            _ => {
+                let data_size = data.expect_single_stat::<UInt64Type>(Stat::DataSize);
+                let max_len = data.expect_single_stat::<UInt64Type>(Stat::MaxLength);
+                Ok(data.bits_per_offset == 32
+                    && max_len >= FSST_LEAST_INPUT_MAX_LENGTH
+                    && data_size >= FSST_LEAST_INPUT_SIZE as u64)
+            }
+        }
+    }
+
+    /// The general purpose compression (zstd or lz4) requested for a field, if any
+    ///
+    /// This is set with the `lance-encoding:compression` and `lance-encoding:compression-level`
+    /// field metadata.
+    fn general_compression(field: &Field) -> Option<CompressionConfig> {
+        CompressionConfig::from_field_metadata(&field.metadata).filter(|compression| {
+            matches!(
+                compression.scheme(),
+                CompressionScheme::Zstd | CompressionScheme::Lz4
+            )
+        })
+    }
+
+    /// The smaller of frame of reference and delta encoding, and its compressed size
+    ///
+    /// The decision is made from the `RangeBitWidth` and `DeltaBitWidth` statistics.
+    fn frame_of_reference_compressor(
+        data: &FixedWidthDataBlock,
+    ) -> Option<(u64, Box<dyn MiniBlockCompressor>)> {
+        let bits_per_value = data.bits_per_value;
+        let candidates = [
+            (false, data.get_stat(Stat::RangeBitWidth)?),
+            (true, data.get_stat(Stat::DeltaBitWidth)?),
+        ];
+        candidates
+            .into_iter()
+            .filter_map(|(delta, bit_widths)| {
+                let bit_widths = bit_widths.as_primitive::<UInt64Type>().values();
+                // A chunk that can't be packed wouldn't fit into a mini-block with its header
+                if bit_widths
+                    .iter()
+                    .any(|&bit_width| bit_width >= bits_per_value)
+                {
+                    return None;
+                }
+                let compressed_size = FrameOfReferenceBitpacking::compressed_size(
+                    bits_per_value,
+                    delta,
+                    bit_widths.iter().copied(),
+                );
+                Some((compressed_size, delta))
+            })
+            .min()
+            .map(|(compressed_size, delta)| {
+                let compressor: Box<dyn MiniBlockCompressor> =
+                    Box::new(FrameOfReferenceBitpacking::new(bits_per_value, delta));
+                (compressed_size, compressor)
+            })
+    }
+
+    fn create_lightweight_miniblock_compressor(
         &self,
         field: &Field,
         data: &DataBlock,
@@ -132,30 +217,61 @@ impl CompressionStrategy for DefaultCompressionStrategy {
                 // size might be smaller than the compressed size.
                 let too_small = bit_widths.len() == 1
                     && InlineBitpacking::min_size_bytes(bit_widths.value(0)) >= data.data_size();
-                if !has_all_zeros
+                let use_bitpacking = !has_all_zeros
                     && !too_small
                     && (fixed_width_data.bits_per_value == 8
                         || fixed_width_data.bits_per_value == 16
                         || fixed_width_data.bits_per_value == 32
-                        || fixed_width_data.bits_per_value == 64)
+                        || fixed_width_data.bits_per_value == 64);
+
+                let (mut size, mut compressor): (u64, Box<dyn MiniBlockCompressor>) =
+                    if use_bitpacking {
+                        let size = bit_widths
+                            .values()
+                            .iter()
+                            .map(|&bit_width| {
+                                InlineBitpacking::min_size_bytes(bit_width)
+                                    + fixed_width_data.bits_per_value / 8
+                            })
+                            .sum();
+                        (
+                            size,
+                            Box::new(InlineBitpacking::new(fixed_width_data.bits_per_value)),
+                        )
+                    } else {
+                        (data.data_size(), Box::new(ValueEncoder::default()))
+                    };
+
+                // Values that are close together but far from zero (e.g. timestamps) pack
+                // much better relative to a reference value
+                if let Some((compressed_size, for_compressor)) =
+                    Self::frame_of_reference_compressor(fixed_width_data)
                 {
-                    Ok(Box::new(InlineBitpacking::new(
+                    if compressed_size < size {
+                        size = compressed_size;
+                        compressor = for_compressor;
+                    }
+                }
+
+                // Low cardinality values that are sorted or clustered form long runs
+                if let Some(run_count) = fixed_width_data.get_stat(Stat::RunCount) {
+                    let run_count = run_count.as_primitive::<UInt64Type>().value(0);
+                    let compressed_size = RunLengthEncoding::compressed_size(
                         fixed_width_data.bits_per_value,
-                    )))
-                } else {
-                    Ok(Box::new(ValueEncoder::default()))
+                        run_count,
+                    );
+                    if compressed_size < size {
+                        compressor =
+                            Box::new(RunLengthEncoding::new(fixed_width_data.bits_per_value));
+                    }
                 }
+
+                Ok(compressor)
             }
             DataBlock::VariableWidth(variable_width_data) => {
+                let use_fsst = Self::use_fsst(field, variable_width_data)?;
                 if variable_width_data.bits_per_offset == 32 {
-                    let data_size =
-                        variable_width_data.expect_single_stat::<UInt64Type>(Stat::DataSize);
-                    let max_len =
-                        variable_width_data.expect_single_stat::<UInt64Type>(Stat::MaxLength);
-
-                    if max_len >= FSST_LEAST_INPUT_MAX_LENGTH
-                        && data_size >= FSST_LEAST_INPUT_SIZE as u64
-                    {
+                    if use_fsst {
                         Ok(Box::new(FsstMiniBlockEncoder::default()))
                     } else {
                         Ok(Box::new(BinaryMiniBlockEncoder::default()))
@@ -202,10 +318,27 @@ impl CompressionStrategy for DefaultCompressionStrategy {
             }),
         }
     }
+}
+
+impl CompressionStrategy for DefaultCompressionStrategy {
+    fn create_miniblock_compressor(
+        &self,
+        field: &Field,
+        data: &DataBlock,
+    ) -> Result<Box<dyn MiniBlockCompressor>> {
+        let compressor = self.create_lightweight_miniblock_compressor(field, data)?;
+        // General purpose compression is applied on top of the lightweight compression
+        match (data, Self::general_compression(field)) {
+            (DataBlock::FixedWidth(_) | DataBlock::VariableWidth(_), Some(compression)) => Ok(
+                Box::new(GeneralMiniBlockCompressor::new(compressor, compression)),
+            ),
+            _ => Ok(compressor),
+        }
+    }
 
     fn create_per_value(
         &self,
-        _field: &Field,
+        field: &Field,
         data: &DataBlock,
     ) -> Result<Box<dyn PerValueCompressor>> {
         match data {
@@ -214,6 +347,11 @@ impl CompressionStrategy for DefaultCompressionStrategy {
             DataBlock::VariableWidth(variable_width) => {
                 let max_len = variable_width.expect_single_stat::<UInt64Type>(Stat::MaxLength);
                 let data_size = variable_width.expect_single_stat::<UInt64Type>(Stat::DataSize);
+                let use_fsst = Self::use_fsst(field, variable_width)?;
+
+                if let Some(compression) = Self::general_compression(field) {
+                    return Ok(Box::new(CompressedBufferEncoder::new(compression)));
+                }
 
                 // If values are very large then use block compression on a per-value basis
                 //
@@ -223,14 +361,9 @@ impl CompressionStrategy for DefaultCompressionStrategy {
                 }
 
                 if variable_width.bits_per_offset == 32 {
-                    let data_size = variable_width.expect_single_stat::<UInt64Type>(Stat::DataSize);
-                    let max_len = variable_width.expect_single_stat::<UInt64Type>(Stat::MaxLength);
-
                     let variable_compression = Box::new(VariableEncoder::default());
 
-                    if max_len >= FSST_LEAST_INPUT_MAX_LENGTH
-                        && data_size >= FSST_LEAST_INPUT_SIZE as u64
-                    {
+                    if use_fsst {
                         Ok(Box::new(FsstPerValueEncoder::new(variable_compression)))
                     } else {
                         Ok(variable_compression)
@@ -329,6 +462,32 @@ impl DecompressionStrategy for DefaultDecompressionStrategy {
             pb::array_encoding::ArrayEncoding::InlineBitpacking(description) => {
                 Ok(Box::new(InlineBitpacking::from_description(description)))
             }
+            pb::array_encoding::ArrayEncoding::FrameOfReferenceBitpacking(description) => Ok(
+                Box::new(FrameOfReferenceBitpacking::from_description(description)),
+            ),
+            pb::array_encoding::ArrayEncoding::RunLength(description) => {
+                Ok(Box::new(RunLengthEncoding::from_description(description)))
+            }
+            pb::array_encoding::ArrayEncoding::GeneralMiniBlock(description) => {
+                let inner = self.create_miniblock_decompressor(
+                    description.inner.as_ref().ok_or_else(|| Error::Internal {
+                        message: "GeneralMiniBlock encoding is missing the inner encoding".into(),
+                        location: location!(),
+                    })?,
+                )?;
+                let compression =
+                    description
+                        .compression
+                        .as_ref()
+                        .ok_or_else(|| Error::Internal {
+                            message: "GeneralMiniBlock encoding is missing the compression".into(),
+                            location: location!(),
+                        })?;
+                Ok(Box::new(GeneralMiniBlockDecompressor::try_new(
+                    inner,
+                    compression,
+                )?))
+            }
             pb::array_encoding::ArrayEncoding::Variable(variable) => Ok(Box::new(
                 BinaryMiniBlockDecompressor::new(variable.bits_per_offset as u8),
             )),
@@ -379,9 +538,9 @@ impl DecompressionStrategy for DefaultDecompressionStrategy {
                     Box::new(VariableDecoder::default()),
                 )))
             }
-            pb::array_encoding::ArrayEncoding::Block(ref block) => Ok(Box::new(
-                CompressedBufferEncoder::from_scheme(&block.scheme)?,
-            )),
+            pb::array_encoding::ArrayEncoding::Block(ref block) => {
+                Ok(Box::new(CompressedBufferEncoder::from_description(block)?))
+            }
             _ => todo!("variable-per-value decompressor for {:?}", description),
         }
     }
@@ -405,3 +564,223 @@ impl DecompressionStrategy for DefaultDecompressionStrategy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow_array::{ArrayRef, Int64Array, LargeStringArray, StringArray, UInt32Array};
+    use arrow_schema::{DataType, Field as ArrowField};
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn field_with_compression(data_type: DataType, compression: Option<&str>) -> Field {
+        let metadata = compression
+            .map(|compression| {
+                HashMap::from([(COMPRESSION_META_KEY.to_string(), compression.into())])
+            })
+            .unwrap_or_default();
+        let field = ArrowField::new("urls", data_type, false).with_metadata(metadata);
+        Field::try_from(&field).unwrap()
+    }
+
+    fn is_fsst(encoding: &pb::ArrayEncoding) -> bool {
+        matches!(
+            encoding.array_encoding,
+            Some(pb::array_encoding::ArrayEncoding::Fsst(_))
+        )
+    }
+
+    #[test]
+    fn test_fsst_from_field_metadata() {
+        let urls = || {
+            DataBlock::from_array(StringArray::from_iter_values(
+                (0..1000).map(|i| format!("https://example.com/products/{}", i)),
+            ))
+        };
+        let strategy = DefaultCompressionStrategy;
+        let compress = |compression| {
+            let field = field_with_compression(DataType::Utf8, compression);
+            let (miniblock, miniblock_encoding) = strategy
+                .create_miniblock_compressor(&field, &urls())
+                .unwrap()
+                .compress(urls())
+                .unwrap();
+            let (per_value, per_value_encoding) = strategy
+                .create_per_value(&field, &urls())
+                .unwrap()
+                .compress(urls())
+                .unwrap();
+            let miniblock_size = miniblock.data.iter().map(|buf| buf.len()).sum::<usize>();
+            (
+                (miniblock_size, miniblock_encoding),
+                (per_value.data_size() as usize, per_value_encoding),
+            )
+        };
+
+        // Too little data to use FSST by default
+        let (miniblock, per_value) = compress(None);
+        assert!(!is_fsst(&miniblock.1));
+        assert!(!is_fsst(&per_value.1));
+        let (none_miniblock, none_per_value) = compress(Some("none"));
+        assert!(!is_fsst(&none_miniblock.1));
+        assert!(!is_fsst(&none_per_value.1));
+
+        // When requested, the data is compressed even though it is small
+        let (fsst_miniblock, fsst_per_value) = compress(Some("fsst"));
+        assert!(is_fsst(&fsst_miniblock.1));
+        assert!(is_fsst(&fsst_per_value.1));
+        let symbol_table_size = fsst::fsst::FSST_SYMBOL_TABLE_SIZE;
+        assert!(
+            fsst_miniblock.0 + symbol_table_size < miniblock.0,
+            "{} + {} >= {}",
+            fsst_miniblock.0,
+            symbol_table_size,
+            miniblock.0
+        );
+        assert!(
+            fsst_per_value.0 + symbol_table_size < per_value.0,
+            "{} + {} >= {}",
+            fsst_per_value.0,
+            symbol_table_size,
+            per_value.0
+        );
+    }
+
+    #[test]
+    fn test_fsst_large_strings_not_supported() {
+        let urls = || {
+            DataBlock::from_array(LargeStringArray::from_iter_values(
+                (0..1000).map(|i| format!("https://example.com/products/{}", i)),
+            ))
+        };
+        let strategy = DefaultCompressionStrategy;
+        let field = field_with_compression(DataType::LargeUtf8, Some("fsst"));
+        assert!(matches!(
+            strategy.create_miniblock_compressor(&field, &urls()),
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            strategy.create_per_value(&field, &urls()),
+            Err(Error::InvalidInput { .. })
+        ));
+
+        // Large strings are still stored without FSST by default
+        let field = field_with_compression(DataType::LargeUtf8, None);
+        strategy
+            .create_miniblock_compressor(&field, &urls())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_general_compression_from_field_metadata() {
+        let data = || {
+            DataBlock::from_array(StringArray::from_iter_values(
+                (0..100).map(|i| format!("https://example.com/products/{}", i)),
+            ))
+        };
+        let metadata = CompressionConfig::new(CompressionScheme::Zstd, Some(7)).to_field_metadata();
+        let field = ArrowField::new("urls", DataType::Utf8, false).with_metadata(metadata);
+        let field = Field::try_from(&field).unwrap();
+        let strategy = DefaultCompressionStrategy;
+
+        // The scheme and level are recorded in the encoding
+        let (compressed, encoding) = strategy
+            .create_miniblock_compressor(&field, &data())
+            .unwrap()
+            .compress(data())
+            .unwrap();
+        let Some(pb::array_encoding::ArrayEncoding::GeneralMiniBlock(general)) =
+            &encoding.array_encoding
+        else {
+            panic!(
+                "Expected general mini-block compression but got {:?}",
+                encoding
+            )
+        };
+        assert_eq!(
+            general.compression,
+            Some(pb::Compression {
+                scheme: "zstd".to_string(),
+                level: Some(7),
+            })
+        );
+
+        // A single chunk can be decompressed from the description
+        assert_eq!(compressed.chunks.len(), 1);
+        let decompressor = DefaultDecompressionStrategy::default()
+            .create_miniblock_decompressor(&encoding)
+            .unwrap();
+        let decompressed = decompressor
+            .decompress(compressed.data, compressed.num_values)
+            .unwrap()
+            .as_variable_width()
+            .unwrap();
+        let expected = data().as_variable_width().unwrap();
+        assert_eq!(decompressed.data, expected.data);
+
+        let (_, encoding) = strategy
+            .create_per_value(&field, &data())
+            .unwrap()
+            .compress(data())
+            .unwrap();
+        assert_eq!(
+            encoding.array_encoding,
+            Some(pb::array_encoding::ArrayEncoding::Block(pb::Block {
+                scheme: "zstd".to_string(),
+                level: Some(7),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_fixed_width_selection() {
+        let field = Field::try_from(&ArrowField::new("", DataType::Int64, false)).unwrap();
+        let encoding_for = |array: ArrayRef| {
+            let data = DataBlock::from_array(array.clone());
+            let (_, encoding) = DefaultCompressionStrategy
+                .create_miniblock_compressor(&field, &data)
+                .unwrap()
+                .compress(DataBlock::from_array(array))
+                .unwrap();
+            encoding.array_encoding.unwrap()
+        };
+
+        // Evenly spaced timestamps use delta encoding
+        let timestamps = Int64Array::from_iter_values(
+            (0..4096).map(|i| 1_700_000_000_000_000_000 + i * 1_000_000_000),
+        );
+        assert!(matches!(
+            encoding_for(Arc::new(timestamps)),
+            pb::array_encoding::ArrayEncoding::FrameOfReferenceBitpacking(
+                pb::FrameOfReferenceBitpacking { delta: true, .. }
+            )
+        ));
+
+        // Unsorted values in a narrow range use frame of reference encoding
+        let clustered = Int64Array::from_iter_values(
+            (0..4096).map(|i| 1_700_000_000_000_000_000 + (i * 7919) % 4096),
+        );
+        assert!(matches!(
+            encoding_for(Arc::new(clustered)),
+            pb::array_encoding::ArrayEncoding::FrameOfReferenceBitpacking(
+                pb::FrameOfReferenceBitpacking { delta: false, .. }
+            )
+        ));
+
+        // Long runs use run-length encoding
+        let sorted = Int64Array::from_iter_values((0..4096).map(|i| i / 1000 - 2));
+        assert!(matches!(
+            encoding_for(Arc::new(sorted)),
+            pb::array_encoding::ArrayEncoding::RunLength(_)
+        ));
+
+        // Small values are bitpacked as before
+        let small = UInt32Array::from_iter_values((0..4096).map(|i| (i * 7919) % 4096));
+        assert!(matches!(
+            encoding_for(Arc::new(small)),
+            pb::array_encoding::ArrayEncoding::InlineBitpacking(_)
+        ));
+    }
+}
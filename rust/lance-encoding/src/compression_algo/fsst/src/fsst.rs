@@ -993,15 +993,17 @@ fn decompress_bulk(
 
 struct FsstEncoder {
     symbol_table: Box<SymbolTable>,
-    // when in_buf is less than FSST_LEAST_INPUT_SIZE, we simply copy the input to the output
+    // when in_buf is less than least_input_size, we simply copy the input to the output
     encoder_switch: bool,
+    least_input_size: usize,
 }
 
 impl FsstEncoder {
-    fn new() -> Self {
+    fn new(least_input_size: usize) -> Self {
         Self {
             symbol_table: Box::new(SymbolTable::new()),
             encoder_switch: false,
+            least_input_size,
         }
     }
 
@@ -1024,7 +1026,7 @@ impl FsstEncoder {
             ));
         }
 
-        if in_buf.len() < FSST_LEAST_INPUT_SIZE {
+        if in_buf.len() < self.least_input_size {
             return Ok(());
         }
 
@@ -1095,7 +1097,7 @@ impl FsstEncoder {
         )?;
         self.export(symbol_table_buf)?;
 
-        // if the input buffer is less than FSST_LEAST_INPUT_SIZE, we simply copy the input to the output
+        // if the input buffer is less than least_input_size, we simply copy the input to the output
         if !self.encoder_switch {
             out_buf.resize(in_buf.len(), 0);
             out_buf.copy_from_slice(in_buf);
@@ -1242,7 +1244,27 @@ pub fn compress(
     out_buf: &mut Vec<u8>,
     out_offsets_buf: &mut Vec<i32>,
 ) -> io::Result<()> {
-    FsstEncoder::new().compress(
+    compress_with_least_input_size(
+        FSST_LEAST_INPUT_SIZE,
+        symbol_table,
+        in_buf,
+        in_offsets_buf,
+        out_buf,
+        out_offsets_buf,
+    )
+}
+
+/// Same as [`compress`], but the input is compressed as soon as it has `least_input_size` bytes
+/// rather than FSST_LEAST_INPUT_SIZE, e.g. when FSST was explicitly requested for small data
+pub fn compress_with_least_input_size(
+    least_input_size: usize,
+    symbol_table: &mut [u8],
+    in_buf: &[u8],
+    in_offsets_buf: &[i32],
+    out_buf: &mut Vec<u8>,
+    out_offsets_buf: &mut Vec<i32>,
+) -> io::Result<()> {
+    FsstEncoder::new(least_input_size).compress(
         in_buf,
         in_offsets_buf,
         out_buf,
@@ -20,7 +20,11 @@ use std::{
 };
 
 use arrow::array::{ArrayData, ArrayDataBuilder, AsArray};
-use arrow_array::{new_empty_array, new_null_array, Array, ArrayRef, OffsetSizeTrait, UInt64Array};
+use arrow_array::{
+    new_empty_array, new_null_array,
+    types::{BinaryViewType, ByteViewType, StringViewType},
+    Array, ArrayRef, OffsetSizeTrait, UInt64Array,
+};
 use arrow_buffer::{
     ArrowNativeType, BooleanBuffer, BooleanBufferBuilder, NullBuffer, ScalarBuffer,
 };
@@ -1291,6 +1295,39 @@ fn arrow_binary_to_data_block(
     })
 }
 
+// Views can point anywhere into any number of buffers so, unlike with offsets, the values
+// have to be copied.  32-bit offsets are used unless the values are too large for them.
+fn arrow_view_to_data_block<T: ByteViewType>(arrays: &[ArrayRef], num_values: u64) -> DataBlock
+where
+    T::Native: AsRef<[u8]>,
+{
+    let mut data = Vec::new();
+    let mut offsets = Vec::with_capacity(num_values as usize + 1);
+    offsets.push(0_u64);
+    for array in arrays {
+        let array = array.as_byte_view::<T>();
+        for idx in 0..array.len() {
+            if array.is_valid(idx) {
+                data.extend_from_slice(array.value(idx).as_ref());
+            }
+            offsets.push(data.len() as u64);
+        }
+    }
+    let (offsets, bits_per_offset) = if data.len() <= i32::MAX as usize {
+        let offsets = offsets.into_iter().map(|offset| offset as u32).collect();
+        (LanceBuffer::reinterpret_vec::<u32>(offsets), 32)
+    } else {
+        (LanceBuffer::reinterpret_vec(offsets), 64)
+    };
+    DataBlock::VariableWidth(VariableWidthBlock {
+        data: LanceBuffer::Owned(data),
+        offsets,
+        bits_per_offset,
+        num_values,
+        block_info: BlockInfo::new(),
+    })
+}
+
 fn encode_flat_data(arrays: &[ArrayRef], num_values: u64) -> LanceBuffer {
     let bytes_per_value = arrays[0].data_type().byte_width();
     let mut buffer = Vec::with_capacity(num_values as usize * bytes_per_value);
@@ -1322,7 +1359,7 @@ fn encode_bitmap_data(arrays: &[ArrayRef], num_values: u64) -> LanceBuffer {
 
 // Concatenate dictionary arrays.  This is a bit tricky because we might overflow the
 // index type.  If we do, we need to upscale the indices to a larger type.
-fn concat_dict_arrays(arrays: &[ArrayRef]) -> ArrayRef {
+pub(crate) fn concat_dict_arrays(arrays: &[ArrayRef]) -> ArrayRef {
     let value_type = arrays[0].as_any_dictionary().values().data_type();
     let array_refs = arrays.iter().map(|arr| arr.as_ref()).collect::<Vec<_>>();
     match arrow_select::concat::concat(&array_refs) {
@@ -1530,9 +1567,8 @@ impl DataBlock {
 
         let mut encoded = match data_type {
             DataType::Binary | DataType::Utf8 => arrow_binary_to_data_block(arrays, num_values, 32),
-            DataType::BinaryView | DataType::Utf8View => {
-                todo!()
-            }
+            DataType::BinaryView => arrow_view_to_data_block::<BinaryViewType>(arrays, num_values),
+            DataType::Utf8View => arrow_view_to_data_block::<StringViewType>(arrays, num_values),
             DataType::LargeBinary | DataType::LargeUtf8 => {
                 arrow_binary_to_data_block(arrays, num_values, 64)
             }
@@ -231,6 +231,7 @@ use snafu::location;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::mpsc::{self, unbounded_channel};
 
+use lance_core::utils::tokio::{spawn, JoinHandle};
 use lance_core::{ArrowResult, Error, Result};
 use tracing::{instrument, Instrument};
 
@@ -694,7 +695,46 @@ impl CoreFieldDecoderStrategy {
                         as Box<dyn StructuralFieldScheduler>,
                 )
             }
-            DataType::Binary | DataType::Utf8 | DataType::LargeBinary | DataType::LargeUtf8 => {
+            DataType::Binary
+            | DataType::Utf8
+            | DataType::LargeBinary
+            | DataType::LargeUtf8
+            | DataType::BinaryView
+            | DataType::Utf8View => {
+                let column_info = column_infos.expect_next()?;
+                let scheduler = Box::new(StructuralPrimitiveFieldScheduler::try_new(
+                    column_info.as_ref(),
+                    self.decompressor_strategy.as_ref(),
+                )?);
+                column_infos.next_top_level();
+                Ok(scheduler)
+            }
+            // The dictionary is kept or rebuilt when the pages are decoded
+            DataType::Dictionary(_, value_type)
+                if Self::is_primitive(value_type)
+                    || value_type.is_binary_like()
+                    || matches!(
+                        value_type.as_ref(),
+                        DataType::BinaryView | DataType::Utf8View
+                    ) =>
+            {
+                let column_info = column_infos.expect_next()?;
+                let scheduler = Box::new(StructuralPrimitiveFieldScheduler::try_new(
+                    column_info.as_ref(),
+                    self.decompressor_strategy.as_ref(),
+                )?);
+                column_infos.next_top_level();
+                Ok(scheduler)
+            }
+            // The runs are found again when the pages are decoded
+            DataType::RunEndEncoded(_, values)
+                if Self::is_primitive(values.data_type())
+                    || values.data_type().is_binary_like()
+                    || matches!(
+                        values.data_type(),
+                        DataType::BinaryView | DataType::Utf8View
+                    ) =>
+            {
                 let column_info = column_infos.expect_next()?;
                 let scheduler = Box::new(StructuralPrimitiveFieldScheduler::try_new(
                     column_info.as_ref(),
@@ -1372,7 +1412,7 @@ impl BatchDecodeStream {
             let next_task = next_task.transpose().map(|next_task| {
                 let num_rows = next_task.as_ref().map(|t| t.num_rows).unwrap_or(0);
                 let emitted_batch_size_warning = slf.emitted_batch_size_warning.clone();
-                let task = tokio::spawn(
+                let task = spawn(
                     (async move {
                         let next_task = next_task?;
                         next_task.into_batch(emitted_batch_size_warning)
@@ -1699,7 +1739,7 @@ impl StructuralBatchDecodeStream {
             let next_task = next_task.transpose().map(|next_task| {
                 let num_rows = next_task.as_ref().map(|t| t.num_rows).unwrap_or(0);
                 let emitted_batch_size_warning = slf.emitted_batch_size_warning.clone();
-                let task = tokio::spawn(async move {
+                let task = spawn(async move {
                     let next_task = next_task?;
                     next_task.into_batch(emitted_batch_size_warning)
                 });
@@ -1746,7 +1786,7 @@ pub struct SchedulerDecoderConfig {
 
 fn check_scheduler_on_drop(
     stream: BoxStream<'static, ReadBatchTask>,
-    scheduler_handle: tokio::task::JoinHandle<()>,
+    scheduler_handle: JoinHandle<()>,
 ) -> BoxStream<'static, ReadBatchTask> {
     // This is a bit weird but we create an "empty stream" that unwraps the scheduler handle (which
     // will panic if the scheduler panicked).  This let's us check if the scheduler panicked
@@ -1847,7 +1887,7 @@ fn create_scheduler_decoder(
         rx,
     );
 
-    let scheduler_handle = tokio::task::spawn(async move {
+    let scheduler_handle = spawn(async move {
         let mut decode_scheduler = match DecodeBatchScheduler::try_new(
             target_schema.as_ref(),
             &column_indices,
@@ -19,7 +19,7 @@ use arrow_array::{Array, ArrayRef, RecordBatch};
 use arrow_schema::DataType;
 use bytes::{Bytes, BytesMut};
 use futures::future::BoxFuture;
-use lance_core::datatypes::{Field, Schema};
+use lance_core::datatypes::{Field, Schema, PAGE_BYTES_META_KEY, PAGE_ROWS_META_KEY};
 use lance_core::utils::bit::{is_pwr_two, pad_bytes_to};
 use lance_core::{Error, Result};
 use snafu::location;
@@ -31,6 +31,7 @@ use crate::encodings::logical::list::ListStructuralEncoder;
 use crate::encodings::logical::primitive::PrimitiveStructuralEncoder;
 use crate::encodings::logical::r#struct::StructStructuralEncoder;
 use crate::repdef::RepDefBuilder;
+use crate::utils::accumulation::AccumulationQueue;
 use crate::version::LanceFileVersion;
 use crate::{
     decoder::{ColumnInfo, PageInfo},
@@ -218,6 +219,13 @@ pub struct EncodingOptions {
     ///
     /// This cache is applied on a per-column basis
     pub cache_bytes_per_column: u64,
+    /// How many rows to cache in-memory before writing a page, even if less
+    /// than `cache_bytes_per_column` bytes are cached
+    ///
+    /// This is also applied on a per-column basis.  Smaller pages make point
+    /// lookups cheaper, larger pages are better for scans.  By default only
+    /// the size in bytes is considered.
+    pub cache_rows_per_column: Option<u64>,
     /// The maximum size of a page in bytes, if a single array would create
     /// a page larger than this then it will be split into multiple pages
     pub max_page_bytes: u64,
@@ -237,6 +245,7 @@ impl Default for EncodingOptions {
     fn default() -> Self {
         Self {
             cache_bytes_per_column: 8 * 1024 * 1024,
+            cache_rows_per_column: None,
             max_page_bytes: 32 * 1024 * 1024,
             keep_original_array: true,
             buffer_alignment: 64,
@@ -244,6 +253,40 @@ impl Default for EncodingOptions {
     }
 }
 
+impl EncodingOptions {
+    /// Creates the queue that accumulates the pages of a column
+    ///
+    /// The [`PAGE_BYTES_META_KEY`] and [`PAGE_ROWS_META_KEY`] field metadata override
+    /// `cache_bytes_per_column` and `cache_rows_per_column`.  Each key is looked up
+    /// in `metadata` in order, the first map that has it wins.
+    pub(crate) fn accumulation_queue(
+        &self,
+        column_index: u32,
+        metadata: &[&HashMap<String, String>],
+    ) -> Result<AccumulationQueue> {
+        let page_option = |key: &str| -> Result<Option<u64>> {
+            metadata
+                .iter()
+                .find_map(|metadata| metadata.get(key))
+                .map(|value| {
+                    value.parse::<u64>().map_err(|e| {
+                        Error::invalid_input(
+                            format!("Invalid value '{}' for {}: {}", value, key, e),
+                            location!(),
+                        )
+                    })
+                })
+                .transpose()
+        };
+        let cache_bytes = page_option(PAGE_BYTES_META_KEY)?.unwrap_or(self.cache_bytes_per_column);
+        let cache_rows = page_option(PAGE_ROWS_META_KEY)?.or(self.cache_rows_per_column);
+        Ok(
+            AccumulationQueue::new(cache_bytes, column_index, self.keep_original_array)
+                .with_cache_rows(cache_rows),
+        )
+    }
+}
+
 /// A trait to pick which kind of field encoding to use for a field
 ///
 /// Unlike the ArrayEncodingStrategy, the field encoding strategy is
@@ -329,11 +372,32 @@ impl StructuralEncodingStrategy {
                 | DataType::FixedSizeList(_, _)
                 | DataType::Binary
                 | DataType::LargeBinary
+                | DataType::BinaryView
                 | DataType::Utf8
-                | DataType::LargeUtf8,
+                | DataType::LargeUtf8
+                | DataType::Utf8View,
         )
     }
 
+    // Packed structs are stored row-major so every child must have a fixed, byte-aligned width
+    fn validate_packed_struct(field: &Field) -> Result<()> {
+        for child in &field.children {
+            let data_type = child.data_type();
+            let is_fixed_width = data_type.primitive_width().is_some()
+                || matches!(data_type, DataType::FixedSizeBinary(_));
+            if !is_fixed_width {
+                return Err(Error::invalid_input(
+                    format!(
+                        "the packed struct {} has the child {} of type {}, packed structs only support fixed-width children",
+                        field.name, child.name, data_type
+                    ),
+                    location!(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn do_create_field_encoder(
         &self,
         _encoding_strategy_root: &dyn FieldEncodingStrategy,
@@ -369,6 +433,7 @@ impl StructuralEncodingStrategy {
                 }
                 DataType::Struct(_) => {
                     if field.is_packed_struct() {
+                        Self::validate_packed_struct(field)?;
                         Ok(Box::new(PrimitiveStructuralEncoder::try_new(
                             options,
                             self.compression_strategy.clone(),
@@ -415,6 +480,27 @@ impl StructuralEncodingStrategy {
                         Err(Error::NotSupported { source: format!("cannot encode a dictionary column whose value type is a logical type ({})", value_type).into(), location: location!() })
                     }
                 }
+                DataType::RunEndEncoded(_, values) => {
+                    // The runs are expanded on write and found again on read
+                    if Self::is_primitive_type(values.data_type()) {
+                        Ok(Box::new(PrimitiveStructuralEncoder::try_new(
+                            options,
+                            self.compression_strategy.clone(),
+                            column_index.next_column_index(field.id as u32),
+                            field.clone(),
+                            Arc::new(root_field_metadata.clone()),
+                        )?))
+                    } else {
+                        Err(Error::NotSupported {
+                            source: format!(
+                                "cannot encode a run-end encoded column whose value type is a logical type ({})",
+                                values.data_type()
+                            )
+                            .into(),
+                            location: location!(),
+                        })
+                    }
+                }
                 _ => todo!("Implement encoding for field {}", field),
             }
         }
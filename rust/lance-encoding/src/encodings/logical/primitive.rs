@@ -13,8 +13,12 @@ use std::{
 };
 
 use arrow::array::AsArray;
-use arrow_array::{make_array, types::UInt64Type, Array, ArrayRef, PrimitiveArray};
-use arrow_buffer::{BooleanBuffer, NullBuffer, ScalarBuffer};
+use arrow_array::{
+    make_array,
+    types::{Int16Type, Int32Type, Int64Type, RunEndIndexType, UInt64Type},
+    Array, ArrayRef, PrimitiveArray, RunArray, UInt32Array,
+};
+use arrow_buffer::{ArrowNativeType, BooleanBuffer, NullBuffer, ScalarBuffer};
 use arrow_schema::{DataType, Field as ArrowField};
 use futures::{future::BoxFuture, stream::FuturesOrdered, FutureExt, TryStreamExt};
 use itertools::Itertools;
@@ -35,7 +39,7 @@ use crate::{
     compression::{
         BlockDecompressor, CompressionStrategy, DecompressionStrategy, MiniBlockDecompressor,
     },
-    data::{AllNullDataBlock, DataBlock, VariableWidthBlock},
+    data::{AllNullDataBlock, DataBlock, DictionaryDataBlock, VariableWidthBlock},
     utils::bytepack::BytepackedIntegerEncoder,
 };
 use crate::{
@@ -60,7 +64,7 @@ use lance_core::{datatypes::Field, utils::tokio::spawn_cpu, Result};
 
 use crate::{
     buffer::LanceBuffer,
-    data::{BlockInfo, DataBlockBuilder, FixedWidthDataBlock},
+    data::{concat_dict_arrays, BlockInfo, DataBlockBuilder, FixedWidthDataBlock},
     decoder::{
         ColumnInfo, DecodePageTask, DecodedArray, DecodedPage, FilterExpression, LoadedPage,
         MessageType, PageEncoding, PageInfo, ScheduledScanLine, SchedulerContext,
@@ -541,29 +545,25 @@ impl DecodePageTask for DecodeMiniBlockTask {
 
         let unraveler = RepDefUnraveler::new(repbuf, defbuf, self.def_meaning.clone());
 
-        // if dictionary encoding is applied, do dictionary decode here.
+        // If dictionary encoding is applied the values are indices into the dictionary.  The
+        // dictionary is only materialized once we know if the caller wants to keep it.
         if let Some(dictionary) = &self.dictionary_data {
-            // assume the indices are uniformly distributed.
-            let estimated_size_bytes = dictionary.data_size()
-                * (data.num_values() + dictionary.num_values() - 1)
-                / dictionary.num_values();
-            let mut data_builder = DataBlockBuilder::with_capacity_estimate(estimated_size_bytes);
-
-            // if dictionary encoding is applied, indices are of type `Int32`
-            if let DataBlock::FixedWidth(mut fixed_width_data_block) = data {
-                let indices = fixed_width_data_block.data.borrow_to_typed_slice::<i32>();
-                let indices = indices.as_ref();
-
-                indices.iter().for_each(|&idx| {
-                    data_builder.append(dictionary, idx as u64..idx as u64 + 1);
-                });
-
-                let data = data_builder.finish();
-                return Ok(DecodedPage {
-                    data,
-                    repdef: unraveler,
+            let DataBlock::FixedWidth(indices) = data else {
+                return Err(Error::Internal {
+                    message: format!(
+                        "Dictionary indices should be fixed width but got {}",
+                        data.name()
+                    ),
+                    location: location!(),
                 });
-            }
+            };
+            return Ok(DecodedPage {
+                data: DataBlock::Dictionary(DictionaryDataBlock {
+                    indices,
+                    dictionary: Box::new(dictionary.try_clone()?),
+                }),
+                repdef: unraveler,
+            });
         }
 
         Ok(DecodedPage {
@@ -1522,14 +1522,12 @@ impl StructuralPageScheduler for MiniBlockScheduler {
             // decode dictionary
             if let Some(ref mut dictionary) = self.dictionary {
                 let dictionary_data = dictionary_bytes.unwrap();
-                page_meta.dictionary =
-                    Some(Arc::new(dictionary.dictionary_decompressor.decompress(
-                        LanceBuffer::from_bytes(
-                            dictionary_data,
-                            dictionary.dictionary_data_alignment,
-                        ),
-                        dictionary.num_dictionary_items,
-                    )?));
+                let mut dictionary_data = dictionary.dictionary_decompressor.decompress(
+                    LanceBuffer::from_bytes(dictionary_data, dictionary.dictionary_data_alignment),
+                    dictionary.num_dictionary_items,
+                )?;
+                // Borrowed so that every decode task can cheaply clone the dictionary
+                page_meta.dictionary = Some(Arc::new(dictionary_data.borrow_and_clone()));
             };
             let page_meta = Arc::new(page_meta);
             self.page_meta = Some(page_meta.clone());
@@ -2743,6 +2741,143 @@ pub struct StructuralCompositeDecodeArrayTask {
 }
 
 impl StructuralCompositeDecodeArrayTask {
+    /// Views are stored as plain string or binary values, this is the type those values
+    /// are decoded to before they are converted to views
+    fn view_storage_type(data: &DataBlock, data_type: &DataType) -> Option<DataType> {
+        match (data, data_type) {
+            (DataBlock::Dictionary(dictionary), _) => {
+                Self::view_storage_type(&dictionary.dictionary, data_type)
+            }
+            (data, DataType::Dictionary(key_type, value_type)) => {
+                Self::view_storage_type(data, value_type)
+                    .map(|value_type| DataType::Dictionary(key_type.clone(), Box::new(value_type)))
+            }
+            (DataBlock::VariableWidth(data), DataType::Utf8View) => {
+                Some(if data.bits_per_offset == 32 {
+                    DataType::Utf8
+                } else {
+                    DataType::LargeUtf8
+                })
+            }
+            (DataBlock::VariableWidth(data), DataType::BinaryView) => {
+                Some(if data.bits_per_offset == 32 {
+                    DataType::Binary
+                } else {
+                    DataType::LargeBinary
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts the data of a page into an array of `data_type`
+    ///
+    /// Dictionary encoded pages keep their dictionary if `data_type` is a dictionary and are
+    /// materialized otherwise.  Pages that are not dictionary encoded are dictionary encoded
+    /// if `data_type` is a dictionary.
+    fn page_to_array(
+        data: DataBlock,
+        data_type: &DataType,
+        should_validate: bool,
+    ) -> Result<ArrayRef> {
+        if let Some(storage_type) = Self::view_storage_type(&data, data_type) {
+            // The views point into the decoded values, they are not copied
+            let array = Self::page_to_array(data, &storage_type, should_validate)?;
+            return Ok(arrow_cast::cast(&array, data_type)?);
+        }
+        match (data, data_type) {
+            (DataBlock::Dictionary(dictionary), _) => {
+                // Indices are stored unsigned with the width of the original keys
+                let key_type = match dictionary.indices.bits_per_value {
+                    8 => DataType::UInt8,
+                    16 => DataType::UInt16,
+                    32 => DataType::UInt32,
+                    64 => DataType::UInt64,
+                    bits_per_value => {
+                        return Err(Error::Internal {
+                            message: format!(
+                                "Dictionary indices with {} bits per value",
+                                bits_per_value
+                            ),
+                            location: location!(),
+                        })
+                    }
+                };
+                let value_type = match data_type {
+                    DataType::Dictionary(_, value_type) => value_type.as_ref().clone(),
+                    _ => data_type.clone(),
+                };
+                let page_type = DataType::Dictionary(Box::new(key_type), Box::new(value_type));
+                let array = make_array(
+                    DataBlock::Dictionary(dictionary)
+                        .into_arrow(page_type.clone(), should_validate)?,
+                );
+                if &page_type == data_type {
+                    Ok(array)
+                } else if let DataType::Dictionary(_, _) = data_type {
+                    Ok(arrow_cast::cast(&array, data_type)?)
+                } else {
+                    let array = array.as_any_dictionary();
+                    Ok(arrow_select::take::take(
+                        array.values(),
+                        array.keys(),
+                        None,
+                    )?)
+                }
+            }
+            (data @ DataBlock::AllNull(_), _) => Ok(make_array(
+                data.into_arrow(data_type.clone(), should_validate)?,
+            )),
+            (data, DataType::Dictionary(_, value_type)) => {
+                let values =
+                    make_array(data.into_arrow(value_type.as_ref().clone(), should_validate)?);
+                Ok(arrow_cast::cast(&values, data_type)?)
+            }
+            (data, _) => Ok(make_array(
+                data.into_arrow(data_type.clone(), should_validate)?,
+            )),
+        }
+    }
+
+    fn typed_runs<R: RunEndIndexType>(
+        ranges: &[Range<usize>],
+        values: &dyn Array,
+    ) -> Result<ArrayRef> {
+        let run_ends = ranges
+            .iter()
+            .map(|range| {
+                R::Native::from_usize(range.end).ok_or_else(|| {
+                    Error::invalid_input(
+                        format!(
+                            "{} rows do not fit in run ends of type {}",
+                            range.end,
+                            R::DATA_TYPE
+                        ),
+                        location!(),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let run_ends = PrimitiveArray::<R>::from_iter_values(run_ends);
+        Ok(Arc::new(RunArray::<R>::try_new(&run_ends, values)?))
+    }
+
+    /// Run-end encodes an array, consecutive equal values (or nulls) become one run
+    fn encode_runs(array: &ArrayRef, run_end_type: &DataType) -> Result<ArrayRef> {
+        let ranges = arrow::compute::partition(&[array.clone()])?.ranges();
+        let starts = UInt32Array::from_iter_values(ranges.iter().map(|range| range.start as u32));
+        let values = arrow_select::take::take(array, &starts, None)?;
+        match run_end_type {
+            DataType::Int16 => Self::typed_runs::<Int16Type>(&ranges, &values),
+            DataType::Int32 => Self::typed_runs::<Int32Type>(&ranges, &values),
+            DataType::Int64 => Self::typed_runs::<Int64Type>(&ranges, &values),
+            _ => Err(Error::invalid_input(
+                format!("Run ends of type {} are not supported", run_end_type),
+                location!(),
+            )),
+        }
+    }
+
     fn restore_validity(
         array: Arc<dyn Array>,
         unraveler: &mut CompositeRepDefUnraveler,
@@ -2770,17 +2905,19 @@ impl StructuralCompositeDecodeArrayTask {
 
 impl StructuralDecodeArrayTask for StructuralCompositeDecodeArrayTask {
     fn decode(self: Box<Self>) -> Result<DecodedArray> {
+        // Run-end encoded arrays are decoded as values, the runs are found once the validity
+        // has been restored
+        let value_type = match &self.data_type {
+            DataType::RunEndEncoded(_, values) => values.data_type(),
+            data_type => data_type,
+        };
         let mut arrays = Vec::with_capacity(self.tasks.len());
         let mut unravelers = Vec::with_capacity(self.tasks.len());
         for task in self.tasks {
             let decoded = task.decode()?;
             unravelers.push(decoded.repdef);
 
-            let array = make_array(
-                decoded
-                    .data
-                    .into_arrow(self.data_type.clone(), self.should_validate)?,
-            );
+            let array = Self::page_to_array(decoded.data, value_type, self.should_validate)?;
 
             arrays.push(array);
         }
@@ -2788,7 +2925,10 @@ impl StructuralDecodeArrayTask for StructuralCompositeDecodeArrayTask {
         let array = arrow_select::concat::concat(&array_refs)?;
         let mut repdef = CompositeRepDefUnraveler::new(unravelers);
 
-        let array = Self::restore_validity(array, &mut repdef);
+        let mut array = Self::restore_validity(array, &mut repdef);
+        if let DataType::RunEndEncoded(run_ends, _) = &self.data_type {
+            array = Self::encode_runs(&array, run_ends.data_type())?;
+        }
 
         Ok(DecodedArray { array, repdef })
     }
@@ -2946,11 +3086,8 @@ impl PrimitiveStructuralEncoder {
         encoding_metadata: Arc<HashMap<String, String>>,
     ) -> Result<Self> {
         Ok(Self {
-            accumulation_queue: AccumulationQueue::new(
-                options.cache_bytes_per_column,
-                column_index,
-                options.keep_original_array,
-            ),
+            accumulation_queue: options
+                .accumulation_queue(column_index, &[&field.metadata, encoding_metadata.as_ref()])?,
             keep_original_array: options.keep_original_array,
             accumulated_repdefs: Vec::new(),
             column_index,
@@ -3837,6 +3974,78 @@ impl PrimitiveStructuralEncoder {
         }
     }
 
+    /// Splits dictionary arrays into the indices and the dictionary
+    ///
+    /// Null indices are replaced with 0 since the validity is stored in the rep/def levels.
+    fn split_dictionary(arrays: &[ArrayRef]) -> (DataBlock, DataBlock) {
+        let array = concat_dict_arrays(arrays);
+        let array = array.as_any_dictionary();
+        let keys = array.keys().to_data();
+        let bytes_per_index = keys.data_type().primitive_width().unwrap();
+        let mut indices = keys.buffers()[0].as_slice()[keys.offset() * bytes_per_index..]
+            [..keys.len() * bytes_per_index]
+            .to_vec();
+        if let Some(nulls) = keys.nulls() {
+            for null_idx in (!nulls.inner()).set_indices() {
+                indices[null_idx * bytes_per_index..][..bytes_per_index].fill(0);
+            }
+        }
+        let mut indices = DataBlock::FixedWidth(FixedWidthDataBlock {
+            data: LanceBuffer::Owned(indices),
+            bits_per_value: bytes_per_index as u64 * 8,
+            num_values: keys.len() as u64,
+            block_info: BlockInfo::new(),
+        });
+        indices.compute_stat();
+
+        let dictionary = DataBlock::from_array(array.values().clone()).remove_outer_validity();
+        (indices, dictionary)
+    }
+
+    fn expand_typed_runs<R: RunEndIndexType>(array: &dyn Array) -> Result<ArrayRef> {
+        let array = array
+            .as_any()
+            .downcast_ref::<RunArray<R>>()
+            .expect("run-end encoded array with unexpected run end type");
+        if array.is_empty() {
+            return Ok(arrow_array::new_empty_array(array.values().data_type()));
+        }
+        let run_ends = array.run_ends();
+        let mut indices = Vec::with_capacity(array.len());
+        for physical_idx in run_ends.get_start_physical_index()..=run_ends.get_end_physical_index()
+        {
+            let run_end = run_ends.values()[physical_idx].as_usize() - run_ends.offset();
+            indices.resize(run_end.min(run_ends.len()), physical_idx as u32);
+        }
+        Ok(arrow_select::take::take(
+            array.values(),
+            &UInt32Array::from(indices),
+            None,
+        )?)
+    }
+
+    /// Expands a run-end encoded array into one value per item
+    fn expand_runs(array: &ArrayRef, run_end_type: &DataType) -> Result<ArrayRef> {
+        match run_end_type {
+            DataType::Int16 => Self::expand_typed_runs::<Int16Type>(array.as_ref()),
+            DataType::Int32 => Self::expand_typed_runs::<Int32Type>(array.as_ref()),
+            DataType::Int64 => Self::expand_typed_runs::<Int64Type>(array.as_ref()),
+            _ => Err(Error::invalid_input(
+                format!("Run ends of type {} are not supported", run_end_type),
+                location!(),
+            )),
+        }
+    }
+
+    /// Whether a dictionary from the input can be stored as the dictionary of a page
+    fn can_store_dictionary(dictionary: &DataBlock) -> bool {
+        match dictionary {
+            DataBlock::FixedWidth(fixed_width) => fixed_width.bits_per_value % 8 == 0,
+            DataBlock::VariableWidth(variable_width) => variable_width.bits_per_offset == 32,
+            _ => false,
+        }
+    }
+
     fn should_dictionary_encode(data_block: &DataBlock) -> bool {
         // Don't dictionary encode tiny arrays
         let too_small = env::var("LANCE_ENCODING_DICT_TOO_SMALL")
@@ -3913,6 +4122,41 @@ impl PrimitiveStructuralEncoder {
                 };
             }
 
+            let mut arrays = arrays;
+            if let DataType::Dictionary(_, value_type) = field.data_type() {
+                // Keep the dictionary of the input so it can be returned on read
+                let (indices_data_block, dictionary_data_block) = Self::split_dictionary(&arrays);
+                if Self::can_store_dictionary(&dictionary_data_block) {
+                    log::debug!(
+                        "Encoding column {} with {} items using the input dictionary (mini-block layout)",
+                        column_idx,
+                        num_values
+                    );
+                    return Self::encode_miniblock(
+                        column_idx,
+                        &field,
+                        compression_strategy.as_ref(),
+                        indices_data_block,
+                        repdefs,
+                        row_number,
+                        Some(dictionary_data_block),
+                        num_rows,
+                    );
+                }
+                // Otherwise store the values, they are dictionary encoded again on read
+                arrays = arrays
+                    .iter()
+                    .map(|array| arrow_cast::cast(array, &value_type))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+            }
+            if let DataType::RunEndEncoded(run_ends, _) = field.data_type() {
+                // Long runs are compressed again by run-length encoding
+                arrays = arrays
+                    .iter()
+                    .map(|array| Self::expand_runs(array, run_ends.data_type()))
+                    .collect::<Result<Vec<_>>>()?;
+            }
+
             let data_block = DataBlock::from_arrays(&arrays, num_values);
 
             // if the `data_block` is a `StructDataBlock`, then this is a struct with packed struct encoding.
@@ -3922,7 +4166,10 @@ impl PrimitiveStructuralEncoder {
                     .iter()
                     .any(|child| !matches!(child, DataBlock::FixedWidth(_)))
                 {
-                    panic!("packed struct encoding currently only supports fixed-width fields.")
+                    return Err(Error::invalid_input(
+                        "packed struct encoding currently only supports fixed-width fields",
+                        location!(),
+                    ));
                 }
             }
 
@@ -4008,8 +4255,14 @@ impl PrimitiveStructuralEncoder {
             DataType::Null => {
                 repdef.add_validity_bitmap(NullBuffer::new(BooleanBuffer::new_unset(array.len())));
             }
-            DataType::Dictionary(_, _) => {
-                unreachable!()
+            // The items of a dictionary are null if the key is null or if the key refers to a
+            // null value.  We store this in the rep/def levels and drop it from the dictionary.
+            // Run-end encoded arrays only have nulls in their values.
+            DataType::Dictionary(_, _) | DataType::RunEndEncoded(_, _) => {
+                match array.logical_nulls() {
+                    Some(validity) => repdef.add_validity_bitmap(validity),
+                    None => repdef.add_no_null(array.len()),
+                }
             }
             // Extract our validity buf but NOT any child validity bufs. (they will be encoded in
             // as part of the values).  Note: for FSL we do not use repdef.add_fsl because we do
@@ -4074,16 +4327,51 @@ impl FieldEncoder for PrimitiveStructuralEncoder {
 mod tests {
     use std::{collections::VecDeque, sync::Arc};
 
-    use arrow_array::{ArrayRef, Int8Array, StringArray};
+    use arrow_array::{
+        types::Int16Type, Array, ArrayRef, Int16Array, Int32Array, Int8Array, RunArray, StringArray,
+    };
+    use arrow_schema::DataType;
 
     use crate::encodings::logical::primitive::{
-        ChunkDrainInstructions, PrimitiveStructuralEncoder,
+        ChunkDrainInstructions, PrimitiveStructuralEncoder, StructuralCompositeDecodeArrayTask,
     };
 
     use super::{
         ChunkInstructions, DataBlock, DecodeMiniBlockTask, PreambleAction, RepetitionIndex,
     };
 
+    #[test]
+    fn test_expand_and_encode_runs() {
+        let run_ends = Int16Array::from(vec![2, 5, 6, 9]);
+        let values = Int32Array::from(vec![Some(1), None, Some(1), Some(3)]);
+        let array = RunArray::<Int16Type>::try_new(&run_ends, &values).unwrap();
+        // The slice starts and ends in the middle of a run
+        let array: ArrayRef = Arc::new(array.slice(1, 7));
+
+        let expanded = PrimitiveStructuralEncoder::expand_runs(&array, &DataType::Int16).unwrap();
+        let expected = Int32Array::from(vec![Some(1), None, None, None, Some(1), Some(3), Some(3)]);
+        assert_eq!(expanded.as_ref(), &expected as &dyn Array);
+
+        let encoded =
+            StructuralCompositeDecodeArrayTask::encode_runs(&expanded, &DataType::Int16).unwrap();
+        assert_eq!(encoded.data_type(), array.data_type());
+        let encoded = encoded
+            .as_any()
+            .downcast_ref::<RunArray<Int16Type>>()
+            .unwrap();
+        assert_eq!(encoded.run_ends().values(), &[1_i16, 4, 5, 7]);
+        assert_eq!(
+            encoded.values().as_ref(),
+            &Int32Array::from(vec![Some(1), None, Some(1), Some(3)]) as &dyn Array
+        );
+
+        // Too many rows for the run end type
+        let expanded: ArrayRef = Arc::new(Int32Array::from(vec![0; 40_000]));
+        assert!(
+            StructuralCompositeDecodeArrayTask::encode_runs(&expanded, &DataType::Int16).is_err()
+        );
+    }
+
     #[test]
     fn test_is_narrow() {
         let int8_array = Int8Array::from(vec![1, 2, 3]);
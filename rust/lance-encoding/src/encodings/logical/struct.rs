@@ -248,7 +248,6 @@ impl StructuralStructDecoder {
                     field.data_type().clone(),
                 ))
             }
-            DataType::RunEndEncoded(_, _) => todo!(),
             DataType::ListView(_) | DataType::LargeListView(_) => todo!(),
             DataType::Map(_, _) => todo!(),
             DataType::Union(_, _) => todo!(),
@@ -5,6 +5,8 @@ pub mod binary;
 pub mod bitpack;
 pub mod block;
 pub mod constant;
+pub mod frame_of_reference;
 pub mod fsst;
 pub mod packed;
+pub mod rle;
 pub mod value;
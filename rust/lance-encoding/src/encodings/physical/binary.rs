@@ -466,6 +466,37 @@ pub mod tests {
         check_round_trip_encoding_random(field, LanceFileVersion::V2_0).await;
     }
 
+    #[rstest]
+    #[test_log::test(tokio::test)]
+    async fn test_view_types(
+        #[values(STRUCTURAL_ENCODING_MINIBLOCK, STRUCTURAL_ENCODING_FULLZIP)]
+        structural_encoding: &str,
+        #[values(DataType::Utf8View, DataType::BinaryView)] data_type: DataType,
+    ) {
+        // Short values are inlined in the views, long values are in the data buffers
+        let string_array = StringArray::from(vec![
+            Some("abc"),
+            None,
+            Some("a value that is too long to be inlined in the view"),
+            None,
+            Some("m"),
+        ]);
+        let array = arrow_cast::cast(&string_array, &data_type).unwrap();
+
+        let mut field_metadata = HashMap::new();
+        field_metadata.insert(
+            STRUCTURAL_ENCODING_META_KEY.to_string(),
+            structural_encoding.into(),
+        );
+
+        let test_cases = TestCases::default()
+            .with_range(0..2)
+            .with_range(1..4)
+            .with_indices(vec![0, 2, 4])
+            .with_file_version(LanceFileVersion::V2_1);
+        check_round_trip_encoding_of_data(vec![array], &test_cases, field_metadata).await;
+    }
+
     #[rstest]
     #[test_log::test(tokio::test)]
     async fn test_simple_binary(
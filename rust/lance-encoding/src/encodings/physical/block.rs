@@ -17,24 +17,34 @@
 //! traditional block compressor.  It is implemented for the most common compression schemes
 //! (zstd, lz4, etc).
 //!
-//! There is not yet a mini-block variant of this compressor (but could easily be one) and the
-//! full zip variant works by applying compression on a per-value basis (which allows it to be
-//! transparent).
+//! The mini-block variant, [`GeneralMiniBlockCompressor`], compresses each buffer of each chunk
+//! produced by another mini-block compressor.  The full zip variant works by applying compression
+//! on a per-value basis (which allows it to be transparent).
+//!
+//! Users pick the scheme and level for a column with the `lance-encoding:compression` and
+//! `lance-encoding:compression-level` field metadata, see [`CompressionConfig::from_field_metadata`].
 
 use arrow_buffer::ArrowNativeType;
 use snafu::location;
 use std::{
+    collections::HashMap,
     io::{Cursor, Write},
     str::FromStr,
 };
 
-use lance_core::{Error, Result};
+use lance_core::{
+    datatypes::{COMPRESSION_LEVEL_META_KEY, COMPRESSION_META_KEY},
+    Error, Result,
+};
 
 use crate::{
     buffer::LanceBuffer,
-    compression::VariablePerValueDecompressor,
+    compression::{MiniBlockDecompressor, VariablePerValueDecompressor},
     data::{BlockInfo, DataBlock, VariableWidthBlock},
-    encodings::logical::primitive::fullzip::{PerValueCompressor, PerValueDataBlock},
+    encodings::logical::primitive::{
+        fullzip::{PerValueCompressor, PerValueDataBlock},
+        miniblock::{MiniBlockChunk, MiniBlockCompressed, MiniBlockCompressor},
+    },
     format::{pb, ProtobufUtils},
 };
 
@@ -45,9 +55,42 @@ pub struct CompressionConfig {
 }
 
 impl CompressionConfig {
-    pub(crate) fn new(scheme: CompressionScheme, level: Option<i32>) -> Self {
+    pub fn new(scheme: CompressionScheme, level: Option<i32>) -> Self {
         Self { scheme, level }
     }
+
+    pub fn scheme(&self) -> CompressionScheme {
+        self.scheme
+    }
+
+    pub fn level(&self) -> Option<i32> {
+        self.level
+    }
+
+    /// The compression requested by the metadata of a field, if any
+    ///
+    /// Unknown schemes and levels that are not integers are ignored.
+    pub fn from_field_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        let scheme = metadata.get(COMPRESSION_META_KEY)?.parse().ok()?;
+        let level = metadata
+            .get(COMPRESSION_LEVEL_META_KEY)
+            .and_then(|level| level.parse().ok());
+        Some(Self::new(scheme, level))
+    }
+
+    /// The field metadata that requests this compression
+    pub fn to_field_metadata(&self) -> HashMap<String, String> {
+        let mut metadata =
+            HashMap::from([(COMPRESSION_META_KEY.to_string(), self.scheme.to_string())]);
+        if let Some(level) = self.level {
+            metadata.insert(COMPRESSION_LEVEL_META_KEY.to_string(), level.to_string());
+        }
+        metadata
+    }
+
+    fn from_description(description: &pb::Compression) -> Result<Self> {
+        Ok(Self::new(description.scheme.parse()?, description.level))
+    }
 }
 
 impl Default for CompressionConfig {
@@ -85,7 +128,9 @@ impl FromStr for CompressionScheme {
     fn from_str(s: &str) -> Result<Self> {
         match s {
             "none" => Ok(Self::None),
+            "fsst" => Ok(Self::Fsst),
             "zstd" => Ok(Self::Zstd),
+            "lz4" => Ok(Self::Lz4),
             _ => Err(Error::invalid_input(
                 format!("Unknown compression scheme: {}", s),
                 location!(),
@@ -137,25 +182,28 @@ pub struct Lz4BufferCompressor {}
 
 impl BufferCompressor for Lz4BufferCompressor {
     fn compress(&self, input_buf: &[u8], output_buf: &mut Vec<u8>) -> Result<()> {
-        lz4::block::compress_to_buffer(input_buf, None, true, output_buf)
-            .map_err(|err| Error::Internal {
+        // The uncompressed size is prepended so we know how much space to allocate
+        let compressed =
+            lz4::block::compress(input_buf, None, true).map_err(|err| Error::Internal {
                 message: format!("LZ4 compression error: {}", err),
                 location: location!(),
-            })
-            .map(|_| ())
+            })?;
+        output_buf.extend_from_slice(&compressed);
+        Ok(())
     }
 
     fn decompress(&self, input_buf: &[u8], output_buf: &mut Vec<u8>) -> Result<()> {
-        lz4::block::decompress_to_buffer(input_buf, None, output_buf)
-            .map_err(|err| Error::Internal {
+        let decompressed =
+            lz4::block::decompress(input_buf, None).map_err(|err| Error::Internal {
                 message: format!("LZ4 decompression error: {}", err),
                 location: location!(),
-            })
-            .map(|_| ())
+            })?;
+        output_buf.extend_from_slice(&decompressed);
+        Ok(())
     }
 
     fn name(&self) -> &str {
-        "zstd"
+        "lz4"
     }
 }
 
@@ -197,33 +245,49 @@ impl GeneralBufferCompressor {
 // An encoder which uses generic compression, such as zstd/lz4 to encode buffers
 #[derive(Debug)]
 pub struct CompressedBufferEncoder {
+    pub(crate) compression_config: CompressionConfig,
     pub(crate) compressor: Box<dyn BufferCompressor>,
 }
 
 impl Default for CompressedBufferEncoder {
     fn default() -> Self {
-        Self {
-            compressor: GeneralBufferCompressor::get_compressor(CompressionConfig {
-                scheme: CompressionScheme::Zstd,
-                level: Some(0),
-            }),
-        }
+        Self::new(CompressionConfig {
+            scheme: CompressionScheme::Zstd,
+            level: Some(0),
+        })
     }
 }
 
 impl CompressedBufferEncoder {
     pub fn new(compression_config: CompressionConfig) -> Self {
         let compressor = GeneralBufferCompressor::get_compressor(compression_config);
-        Self { compressor }
+        Self {
+            compression_config,
+            compressor,
+        }
     }
 
     pub fn from_scheme(scheme: &str) -> Result<Self> {
-        let scheme = CompressionScheme::from_str(scheme)?;
-        Ok(Self {
-            compressor: GeneralBufferCompressor::get_compressor(CompressionConfig {
-                scheme,
-                level: Some(0),
-            }),
+        Self::try_new(CompressionConfig {
+            scheme: CompressionScheme::from_str(scheme)?,
+            level: Some(0),
+        })
+    }
+
+    fn try_new(compression_config: CompressionConfig) -> Result<Self> {
+        if compression_config.scheme == CompressionScheme::Fsst {
+            return Err(Error::invalid_input(
+                "FSST is not a general purpose buffer compression scheme",
+                location!(),
+            ));
+        }
+        Ok(Self::new(compression_config))
+    }
+
+    pub fn from_description(description: &pb::Block) -> Result<Self> {
+        Self::try_new(CompressionConfig {
+            scheme: CompressionScheme::from_str(&description.scheme)?,
+            level: description.level,
         })
     }
 }
@@ -305,7 +369,7 @@ impl PerValueCompressor for CompressedBufferEncoder {
             block_info: BlockInfo::new(),
         });
 
-        let encoding = ProtobufUtils::block(self.compressor.name());
+        let encoding = ProtobufUtils::block(self.compression_config);
 
         Ok((compressed, encoding))
     }
@@ -324,7 +388,7 @@ impl VariablePerValueDecompressor for CompressedBufferEncoder {
             )?,
             64 => self.per_value_decompress(
                 data_bytes,
-                &data.offsets.borrow_to_typed_slice::<u32>(),
+                &data.offsets.borrow_to_typed_slice::<u64>(),
                 &mut decompressed,
             )?,
             _ => unreachable!(),
@@ -339,10 +403,126 @@ impl VariablePerValueDecompressor for CompressedBufferEncoder {
     }
 }
 
+/// Mini-block compression that applies a general purpose compressor to the chunks of
+/// another mini-block compressor
+///
+/// Each buffer of each chunk is compressed on its own so that chunks can still be
+/// decompressed independently.
+#[derive(Debug)]
+pub struct GeneralMiniBlockCompressor {
+    inner: Box<dyn MiniBlockCompressor>,
+    compression_config: CompressionConfig,
+    compressor: Box<dyn BufferCompressor>,
+}
+
+impl GeneralMiniBlockCompressor {
+    pub fn new(inner: Box<dyn MiniBlockCompressor>, compression_config: CompressionConfig) -> Self {
+        Self {
+            inner,
+            compression_config,
+            compressor: GeneralBufferCompressor::get_compressor(compression_config),
+        }
+    }
+}
+
+impl MiniBlockCompressor for GeneralMiniBlockCompressor {
+    fn compress(&self, page: DataBlock) -> Result<(MiniBlockCompressed, pb::ArrayEncoding)> {
+        let (inner, inner_encoding) = self.inner.compress(page)?;
+
+        let mut compressed = vec![Vec::new(); inner.data.len()];
+        let mut offsets = vec![0; inner.data.len()];
+        let mut chunks = Vec::with_capacity(inner.chunks.len());
+        for chunk in inner.chunks {
+            let mut buffer_sizes = Vec::with_capacity(chunk.buffer_sizes.len());
+            for (buffer_index, &size) in chunk.buffer_sizes.iter().enumerate() {
+                let start = offsets[buffer_index];
+                offsets[buffer_index] += size as usize;
+                let output = &mut compressed[buffer_index];
+                let output_start = output.len();
+                // Empty buffers are left empty, general compressors would add a header
+                if size > 0 {
+                    self.compressor
+                        .compress(&inner.data[buffer_index][start..][..size as usize], output)?;
+                }
+                let compressed_size = output.len() - output_start;
+                buffer_sizes.push(u16::try_from(compressed_size).map_err(|_| Error::Internal {
+                    message: format!(
+                        "Compressing a mini-block chunk with {} produced {} bytes",
+                        self.compressor.name(),
+                        compressed_size
+                    ),
+                    location: location!(),
+                })?);
+            }
+            chunks.push(MiniBlockChunk {
+                buffer_sizes,
+                log_num_values: chunk.log_num_values,
+            });
+        }
+
+        Ok((
+            MiniBlockCompressed {
+                data: compressed.into_iter().map(LanceBuffer::Owned).collect(),
+                chunks,
+                num_values: inner.num_values,
+            },
+            ProtobufUtils::general_mini_block(inner_encoding, self.compression_config),
+        ))
+    }
+}
+
+/// Decompresses the chunks written by [`GeneralMiniBlockCompressor`]
+#[derive(Debug)]
+pub struct GeneralMiniBlockDecompressor {
+    inner: Box<dyn MiniBlockDecompressor>,
+    compressor: Box<dyn BufferCompressor>,
+}
+
+impl GeneralMiniBlockDecompressor {
+    pub fn try_new(
+        inner: Box<dyn MiniBlockDecompressor>,
+        description: &pb::Compression,
+    ) -> Result<Self> {
+        let compression_config = CompressionConfig::from_description(description)?;
+        if compression_config.scheme == CompressionScheme::Fsst {
+            return Err(Error::invalid_input(
+                "FSST is not a general purpose buffer compression scheme",
+                location!(),
+            ));
+        }
+        Ok(Self {
+            inner,
+            compressor: GeneralBufferCompressor::get_compressor(compression_config),
+        })
+    }
+}
+
+impl MiniBlockDecompressor for GeneralMiniBlockDecompressor {
+    fn decompress(&self, data: Vec<LanceBuffer>, num_values: u64) -> Result<DataBlock> {
+        let decompressed = data
+            .into_iter()
+            .map(|buffer| {
+                let mut decompressed = Vec::new();
+                if !buffer.is_empty() {
+                    self.compressor.decompress(&buffer, &mut decompressed)?;
+                }
+                Ok(LanceBuffer::Owned(decompressed))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.inner.decompress(decompressed, num_values)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
     use std::str::FromStr;
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Int32Array, StringArray};
+
+    use super::*;
+    use crate::testing::{check_round_trip_encoding_of_data, TestCases};
+    use crate::version::LanceFileVersion;
 
     #[test]
     fn test_compression_scheme_from_str() {
@@ -354,6 +534,79 @@ mod tests {
             CompressionScheme::from_str("zstd").unwrap(),
             CompressionScheme::Zstd
         );
+        assert_eq!(
+            CompressionScheme::from_str("fsst").unwrap(),
+            CompressionScheme::Fsst
+        );
+        assert_eq!(
+            CompressionScheme::from_str("lz4").unwrap(),
+            CompressionScheme::Lz4
+        );
+    }
+
+    #[test]
+    fn test_buffer_compressors() {
+        let data = (0..10_000u32)
+            .flat_map(|i| (i % 100).to_le_bytes())
+            .collect::<Vec<_>>();
+        for scheme in [CompressionScheme::Zstd, CompressionScheme::Lz4] {
+            let compressor =
+                GeneralBufferCompressor::get_compressor(CompressionConfig::new(scheme, Some(3)));
+            assert_eq!(compressor.name(), scheme.to_string());
+            let mut compressed = vec![1, 2, 3];
+            compressor.compress(&data, &mut compressed).unwrap();
+            assert!(compressed.len() < data.len() / 4);
+            assert_eq!(&compressed[..3], &[1, 2, 3]);
+
+            let mut decompressed = Vec::new();
+            compressor
+                .decompress(&compressed[3..], &mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    fn test_compression_config_field_metadata() {
+        let config = CompressionConfig::new(CompressionScheme::Zstd, Some(9));
+        let metadata = config.to_field_metadata();
+        assert_eq!(
+            CompressionConfig::from_field_metadata(&metadata),
+            Some(config)
+        );
+
+        let metadata = HashMap::from([(COMPRESSION_META_KEY.to_string(), "lz4".to_string())]);
+        assert_eq!(
+            CompressionConfig::from_field_metadata(&metadata),
+            Some(CompressionConfig::new(CompressionScheme::Lz4, None))
+        );
+        assert_eq!(
+            CompressionConfig::from_field_metadata(&HashMap::new()),
+            None
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_general_mini_block_round_trip() {
+        let ints: ArrayRef = Arc::new(Int32Array::from_iter_values((0..10_000).map(|i| i % 7)));
+        let strings: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..10_000).map(|i| format!("value-{}", i % 100)),
+        ));
+        for (scheme, level) in [("zstd", Some("5")), ("lz4", None)] {
+            let mut metadata =
+                HashMap::from([(COMPRESSION_META_KEY.to_string(), scheme.to_string())]);
+            if let Some(level) = level {
+                metadata.insert(COMPRESSION_LEVEL_META_KEY.to_string(), level.to_string());
+            }
+            for array in [ints.clone(), strings.clone()] {
+                check_round_trip_encoding_of_data(
+                    vec![array],
+                    &TestCases::default().with_file_version(LanceFileVersion::V2_1),
+                    metadata.clone(),
+                )
+                .await;
+            }
+        }
     }
 
     #[test]
@@ -0,0 +1,409 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Frame of reference and delta encodings
+//!
+//! Bitpacking only discards high order bits that are zero in every value.  Integers that are
+//! close together but far from zero, such as timestamps or auto-increment ids, don't have any.
+//! A nanosecond timestamp needs 61 bits even if all of the timestamps in a page fall within
+//! the same minute.
+//!
+//! Frame of reference encoding subtracts the smallest value of each chunk of 1024 values before
+//! bitpacking, so that only the spread of the values within the chunk matters.
+//!
+//! Delta encoding is used for sorted (or nearly sorted) values.  It stores the first value of
+//! each chunk and bitpacks the differences between neighbouring values, after subtracting the
+//! smallest difference of the chunk.  Evenly spaced values, such as ids or timestamps taken at
+//! a regular interval, take no bits at all.
+//!
+//! Each chunk is self contained so that a chunk can be decoded without reading the chunks
+//! before it.  This keeps random access cheap.
+
+use arrow_buffer::ArrowNativeType;
+use num_traits::{PrimInt, WrappingAdd, WrappingSub};
+use snafu::location;
+
+use lance_core::{Error, Result};
+
+use crate::buffer::LanceBuffer;
+use crate::compression::MiniBlockDecompressor;
+use crate::compression_algo::fastlanes::BitPacking;
+use crate::data::{BlockInfo, DataBlock, FixedWidthDataBlock};
+use crate::encodings::logical::primitive::miniblock::{
+    MiniBlockChunk, MiniBlockCompressed, MiniBlockCompressor,
+};
+use crate::format::{pb, ProtobufUtils};
+use crate::statistics::narrowest_range;
+
+const LOG_ELEMS_PER_CHUNK: u8 = 10;
+const ELEMS_PER_CHUNK: usize = 1 << LOG_ELEMS_PER_CHUNK;
+
+/// Mini-block compression that bitpacks values relative to a reference value
+#[derive(Debug)]
+pub struct FrameOfReferenceBitpacking {
+    uncompressed_bit_width: u64,
+    delta: bool,
+}
+
+impl FrameOfReferenceBitpacking {
+    /// Creates a new encoder, `delta` selects delta encoding over frame of reference encoding
+    pub fn new(uncompressed_bit_width: u64, delta: bool) -> Self {
+        Self {
+            uncompressed_bit_width,
+            delta,
+        }
+    }
+
+    pub fn from_description(description: &pb::FrameOfReferenceBitpacking) -> Self {
+        Self {
+            uncompressed_bit_width: description.uncompressed_bits_per_value,
+            delta: description.delta,
+        }
+    }
+
+    /// The number of words at the start of each chunk
+    fn header_len(delta: bool) -> usize {
+        if delta {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// The compressed size, in bytes, given the bit width of each chunk
+    ///
+    /// The bit widths are the `RangeBitWidth` statistic for frame of reference encoding and the
+    /// `DeltaBitWidth` statistic for delta encoding.
+    pub fn compressed_size(
+        uncompressed_bit_width: u64,
+        delta: bool,
+        bit_widths: impl Iterator<Item = u64>,
+    ) -> u64 {
+        let header_size = Self::header_len(delta) as u64 * uncompressed_bit_width / 8;
+        bit_widths
+            .map(|bit_width| header_size + (ELEMS_PER_CHUNK as u64 * bit_width).div_ceil(8))
+            .sum()
+    }
+
+    fn compress_chunk<T: ArrowNativeType + BitPacking + PrimInt + WrappingSub>(
+        values: &[T],
+        delta: bool,
+        output: &mut Vec<T>,
+    ) {
+        let mut offsets = vec![T::zero(); ELEMS_PER_CHUNK];
+        let header_start = output.len();
+        if delta {
+            let deltas = values.windows(2).map(|pair| pair[1].wrapping_sub(&pair[0]));
+            let (min_delta, _) = narrowest_range(deltas.clone());
+            for (offset, delta) in offsets[1..].iter_mut().zip(deltas) {
+                *offset = delta.wrapping_sub(&min_delta);
+            }
+            output.extend([T::zero(), values[0], min_delta]);
+        } else {
+            let (min, _) = narrowest_range(values.iter().copied());
+            for (offset, value) in offsets.iter_mut().zip(values) {
+                *offset = value.wrapping_sub(&min);
+            }
+            output.extend([T::zero(), min]);
+        }
+
+        let uncompressed_bit_width = std::mem::size_of::<T>() * 8;
+        let max_offset = offsets.iter().fold(T::zero(), |acc, &offset| acc | offset);
+        let bit_width = uncompressed_bit_width - max_offset.leading_zeros() as usize;
+        output[header_start] = T::from_usize(bit_width).unwrap();
+
+        let packed_len = ELEMS_PER_CHUNK * bit_width / uncompressed_bit_width;
+        let packed_start = output.len();
+        output.resize(packed_start + packed_len, T::zero());
+        // Chunks where every offset is zero are just the header
+        if bit_width > 0 {
+            unsafe {
+                BitPacking::unchecked_pack(bit_width, &offsets, &mut output[packed_start..]);
+            }
+        }
+    }
+
+    fn compress_chunked<T: ArrowNativeType + BitPacking + PrimInt + WrappingSub>(
+        mut data: FixedWidthDataBlock,
+        delta: bool,
+    ) -> MiniBlockCompressed {
+        let values = data.data.borrow_to_typed_slice::<T>();
+        let values = values.as_ref();
+
+        let mut output: Vec<T> = Vec::with_capacity(values.len());
+        let mut chunks = Vec::with_capacity(values.len().div_ceil(ELEMS_PER_CHUNK));
+        for chunk in values.chunks(ELEMS_PER_CHUNK) {
+            let chunk_start = output.len();
+            Self::compress_chunk(chunk, delta, &mut output);
+            chunks.push(MiniBlockChunk {
+                buffer_sizes: vec![
+                    ((output.len() - chunk_start) * std::mem::size_of::<T>()) as u16,
+                ],
+                log_num_values: LOG_ELEMS_PER_CHUNK,
+            });
+        }
+        // The number of values in the last chunk is implied
+        if let Some(last_chunk) = chunks.last_mut() {
+            last_chunk.log_num_values = 0;
+        }
+
+        MiniBlockCompressed {
+            data: vec![LanceBuffer::reinterpret_vec(output)],
+            chunks,
+            num_values: data.num_values,
+        }
+    }
+
+    fn decompress_chunk<T: ArrowNativeType + BitPacking + PrimInt + WrappingAdd>(
+        mut data: LanceBuffer,
+        num_values: u64,
+        delta: bool,
+    ) -> Result<DataBlock> {
+        let words = data.borrow_to_typed_slice::<T>();
+        let words = words.as_ref();
+        let header_len = Self::header_len(delta);
+        if words.len() < header_len || num_values > ELEMS_PER_CHUNK as u64 {
+            return Err(Error::Internal {
+                message: format!(
+                    "Invalid frame of reference chunk of {} bytes with {} values",
+                    data.len(),
+                    num_values
+                ),
+                location: location!(),
+            });
+        }
+        let uncompressed_bit_width = std::mem::size_of::<T>() * 8;
+        let bit_width = words[0].as_usize();
+        let packed = &words[header_len..];
+        if bit_width > uncompressed_bit_width
+            || packed.len() != ELEMS_PER_CHUNK * bit_width / uncompressed_bit_width
+        {
+            return Err(Error::Internal {
+                message: format!(
+                    "Invalid frame of reference chunk of {} bytes with a bit width of {}",
+                    data.len(),
+                    bit_width
+                ),
+                location: location!(),
+            });
+        }
+
+        let mut values = vec![T::zero(); ELEMS_PER_CHUNK];
+        if bit_width > 0 {
+            unsafe {
+                BitPacking::unchecked_unpack(bit_width, packed, &mut values);
+            }
+        }
+        values.truncate(num_values as usize);
+
+        let reference = words[1];
+        if delta {
+            let min_delta = words[2];
+            let mut previous = reference;
+            for (i, value) in values.iter_mut().enumerate() {
+                if i > 0 {
+                    let offset = *value;
+                    previous = previous.wrapping_add(&offset).wrapping_add(&min_delta);
+                }
+                *value = previous;
+            }
+        } else {
+            for value in values.iter_mut() {
+                *value = value.wrapping_add(&reference);
+            }
+        }
+
+        Ok(DataBlock::FixedWidth(FixedWidthDataBlock {
+            data: LanceBuffer::reinterpret_vec(values),
+            bits_per_value: uncompressed_bit_width as u64,
+            num_values,
+            block_info: BlockInfo::new(),
+        }))
+    }
+}
+
+impl MiniBlockCompressor for FrameOfReferenceBitpacking {
+    fn compress(&self, page: DataBlock) -> Result<(MiniBlockCompressed, pb::ArrayEncoding)> {
+        let DataBlock::FixedWidth(fixed_width) = page else {
+            return Err(Error::InvalidInput {
+                source: format!(
+                    "Cannot compress a data block of type {} with FrameOfReferenceBitpacking",
+                    page.name()
+                )
+                .into(),
+                location: location!(),
+            });
+        };
+        if fixed_width.bits_per_value != self.uncompressed_bit_width {
+            return Err(Error::InvalidInput {
+                source: format!(
+                    "Cannot compress {}-bit values with {}-bit FrameOfReferenceBitpacking",
+                    fixed_width.bits_per_value, self.uncompressed_bit_width
+                )
+                .into(),
+                location: location!(),
+            });
+        }
+        let compressed = match self.uncompressed_bit_width {
+            8 => Self::compress_chunked::<u8>(fixed_width, self.delta),
+            16 => Self::compress_chunked::<u16>(fixed_width, self.delta),
+            32 => Self::compress_chunked::<u32>(fixed_width, self.delta),
+            64 => Self::compress_chunked::<u64>(fixed_width, self.delta),
+            bit_width => {
+                return Err(Error::InvalidInput {
+                    source: format!(
+                        "Bitpacking word size must be 8, 16, 32, or 64 but got {}",
+                        bit_width
+                    )
+                    .into(),
+                    location: location!(),
+                })
+            }
+        };
+        Ok((
+            compressed,
+            ProtobufUtils::frame_of_reference_bitpacking(self.uncompressed_bit_width, self.delta),
+        ))
+    }
+}
+
+impl MiniBlockDecompressor for FrameOfReferenceBitpacking {
+    fn decompress(&self, data: Vec<LanceBuffer>, num_values: u64) -> Result<DataBlock> {
+        let [data]: [LanceBuffer; 1] = data.try_into().map_err(|data: Vec<_>| Error::Internal {
+            message: format!(
+                "Frame of reference chunks have 1 buffer but got {}",
+                data.len()
+            ),
+            location: location!(),
+        })?;
+        match self.uncompressed_bit_width {
+            8 => Self::decompress_chunk::<u8>(data, num_values, self.delta),
+            16 => Self::decompress_chunk::<u16>(data, num_values, self.delta),
+            32 => Self::decompress_chunk::<u32>(data, num_values, self.delta),
+            64 => Self::decompress_chunk::<u64>(data, num_values, self.delta),
+            bit_width => Err(Error::Internal {
+                message: format!(
+                    "Bitpacking word size must be 8, 16, 32, or 64 but got {}",
+                    bit_width
+                ),
+                location: location!(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::{make_array, ArrayRef, Int64Array, TimestampNanosecondArray, UInt32Array};
+    use arrow_select::concat::concat;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::testing::{check_round_trip_encoding_of_data, TestCases};
+    use crate::version::LanceFileVersion;
+
+    fn round_trip(array: &ArrayRef, delta: bool) -> ArrayRef {
+        let data = DataBlock::from_array(array.clone());
+        let bits_per_value = data.as_fixed_width_ref().unwrap().bits_per_value;
+        let encoder = FrameOfReferenceBitpacking::new(bits_per_value, delta);
+        let (compressed, encoding) = encoder.compress(data).unwrap();
+        let Some(pb::array_encoding::ArrayEncoding::FrameOfReferenceBitpacking(description)) =
+            encoding.array_encoding
+        else {
+            panic!("unexpected encoding {:?}", encoding);
+        };
+        let decoder = FrameOfReferenceBitpacking::from_description(&description);
+
+        // Decode every chunk on its own, like a reader would
+        let buffer = &compressed.data[0];
+        let mut offset = 0;
+        let mut values_so_far = 0;
+        let mut decoded = Vec::new();
+        for chunk in &compressed.chunks {
+            let size = chunk.buffer_sizes[0] as usize;
+            let num_values = chunk.num_values(values_so_far, compressed.num_values);
+            let block = decoder
+                .decompress(vec![buffer.slice_with_length(offset, size)], num_values)
+                .unwrap();
+            decoded.push(make_array(
+                block.into_arrow(array.data_type().clone(), true).unwrap(),
+            ));
+            offset += size;
+            values_so_far += num_values;
+        }
+        let decoded = decoded
+            .iter()
+            .map(|array| array.as_ref())
+            .collect::<Vec<_>>();
+        concat(&decoded).unwrap()
+    }
+
+    #[rstest]
+    fn test_round_trip(#[values(false, true)] delta: bool) {
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(
+                (0..3000).map(|i| 1_700_000_000_000_000_000 + i * 1_000_000_000),
+            )),
+            Arc::new(Int64Array::from_iter_values((0..2000).map(|i| (i % 7) - 3))),
+            Arc::new(UInt32Array::from_iter_values((0..1500).rev())),
+            Arc::new(UInt32Array::from(vec![u32::MAX, 0, 17])),
+        ];
+        for array in arrays {
+            assert_eq!(round_trip(&array, delta).as_ref(), array.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_compressed_size() {
+        // Evenly spaced values only need the chunk headers with delta encoding
+        let array: ArrayRef = Arc::new(Int64Array::from_iter_values(
+            (0..2048).map(|i| 1_700_000_000_000_000_000 + i * 1_000_000_000),
+        ));
+        let data = DataBlock::from_array(array);
+        let (compressed, _) = FrameOfReferenceBitpacking::new(64, true)
+            .compress(data)
+            .unwrap();
+        assert_eq!(compressed.data[0].len(), 2 * 3 * 8);
+        assert_eq!(
+            FrameOfReferenceBitpacking::compressed_size(64, true, [0, 0].into_iter()),
+            2 * 3 * 8
+        );
+    }
+
+    #[test]
+    fn test_corrupt_chunks() {
+        let decoder = FrameOfReferenceBitpacking::new(32, false);
+        let chunk = |words: Vec<u32>| LanceBuffer::reinterpret_vec(words);
+        // Too many buffers
+        assert!(decoder
+            .decompress(vec![chunk(vec![0, 0]), chunk(vec![0, 0])], 1)
+            .is_err());
+        // A bit width wider than the values
+        assert!(decoder.decompress(vec![chunk(vec![33, 0])], 1).is_err());
+        // Fewer packed words than the bit width needs
+        assert!(decoder.decompress(vec![chunk(vec![4, 0, 0])], 1).is_err());
+        // Unsupported word size
+        assert!(FrameOfReferenceBitpacking::new(12, false)
+            .decompress(vec![chunk(vec![0, 0])], 1)
+            .is_err());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_timestamps() {
+        let timestamps: ArrayRef = Arc::new(TimestampNanosecondArray::from_iter_values(
+            (0..10_000).map(|i| 1_700_000_000_000_000_000 + i * 1_000_000_000 + (i % 3) * 1000),
+        ));
+        let ids: ArrayRef = Arc::new(Int64Array::from_iter_values(1_000_000..1_010_000));
+        for array in [timestamps, ids] {
+            check_round_trip_encoding_of_data(
+                vec![array],
+                &TestCases::default().with_file_version(LanceFileVersion::V2_1),
+                HashMap::new(),
+            )
+            .await;
+        }
+    }
+}
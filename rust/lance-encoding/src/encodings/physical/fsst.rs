@@ -52,8 +52,10 @@ impl FsstCompressed {
                 let mut dest_values = vec![0_u8; bytes_data.len() * 2];
                 let mut symbol_table = vec![0_u8; fsst::fsst::FSST_SYMBOL_TABLE_SIZE];
 
-                // fsst compression
-                fsst::fsst::compress(
+                // The compression strategy only picks FSST for small data when it was
+                // requested, so compress anything that may outweigh the symbol table
+                fsst::fsst::compress_with_least_input_size(
+                    fsst::fsst::FSST_SYMBOL_TABLE_SIZE,
                     &mut symbol_table,
                     bytes_data.as_slice(),
                     offsets_slice,
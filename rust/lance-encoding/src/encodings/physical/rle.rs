@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Run-length encoding
+//!
+//! Low cardinality columns are often stored sorted or clustered, e.g. a country or status
+//! column in a table sorted by it.  Such pages consist of a few long runs of the same value.
+//! Run-length encoding stores each run once, as the value and the number of times it repeats.
+//!
+//! Each mini-block chunk has two buffers: the value of each run, with the width of the
+//! uncompressed values, and the length of each run as a u16.  Runs never cross chunks, so any
+//! chunk can be decoded on its own.
+
+use snafu::location;
+
+use lance_core::{Error, Result};
+
+use crate::buffer::LanceBuffer;
+use crate::compression::MiniBlockDecompressor;
+use crate::data::{BlockInfo, DataBlock, FixedWidthDataBlock};
+use crate::encodings::logical::primitive::miniblock::{
+    MiniBlockChunk, MiniBlockCompressed, MiniBlockCompressor, MAX_MINIBLOCK_BYTES,
+    MAX_MINIBLOCK_VALUES,
+};
+use crate::format::{pb, ProtobufUtils};
+use crate::statistics::count_runs;
+
+// Leaves room for padding the two buffers of a chunk
+const MAX_CHUNK_BYTES: u64 = MAX_MINIBLOCK_BYTES - 16;
+const LOG_MAX_VALUES_PER_CHUNK: u8 = MAX_MINIBLOCK_VALUES.trailing_zeros() as u8;
+
+/// Mini-block compression that stores runs of equal values once
+#[derive(Debug)]
+pub struct RunLengthEncoding {
+    bits_per_value: u64,
+}
+
+impl RunLengthEncoding {
+    pub fn new(bits_per_value: u64) -> Self {
+        assert_eq!(bits_per_value % 8, 0);
+        Self { bits_per_value }
+    }
+
+    pub fn from_description(description: &pb::RunLength) -> Self {
+        Self::new(description.bits_per_value)
+    }
+
+    /// The compressed size, in bytes, of a page with `num_runs` runs
+    pub fn compressed_size(bits_per_value: u64, num_runs: u64) -> u64 {
+        num_runs * (bits_per_value / 8 + 2)
+    }
+
+    fn chunk_size(&self, num_runs: u64) -> u64 {
+        Self::compressed_size(self.bits_per_value, num_runs)
+    }
+
+    fn chunk_data(&self, data: FixedWidthDataBlock) -> MiniBlockCompressed {
+        let bytes_per_value = (self.bits_per_value / 8) as usize;
+        let values = &data.data[..];
+        let num_values = data.num_values as usize;
+
+        let mut run_values = Vec::new();
+        let mut run_lengths: Vec<u16> = Vec::new();
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < num_values {
+            // Use the largest power of two number of values that fits, or whatever is left
+            let remaining = num_values - start;
+            let mut log_num_values = LOG_MAX_VALUES_PER_CHUNK;
+            let num_chunk_values = loop {
+                let num_chunk_values = (1 << log_num_values).min(remaining);
+                let chunk_values =
+                    &values[start * bytes_per_value..][..num_chunk_values * bytes_per_value];
+                if log_num_values == 0
+                    || self.chunk_size(count_runs(chunk_values, bytes_per_value)) <= MAX_CHUNK_BYTES
+                {
+                    break num_chunk_values;
+                }
+                log_num_values -= 1;
+            };
+
+            let values_start = run_values.len();
+            let lengths_start = run_lengths.len();
+            let chunk_values =
+                &values[start * bytes_per_value..][..num_chunk_values * bytes_per_value];
+            for value in chunk_values.chunks_exact(bytes_per_value) {
+                if run_values.len() > values_start
+                    && &run_values[run_values.len() - bytes_per_value..] == value
+                {
+                    *run_lengths.last_mut().unwrap() += 1;
+                } else {
+                    run_values.extend_from_slice(value);
+                    run_lengths.push(1);
+                }
+            }
+
+            start += num_chunk_values;
+            chunks.push(MiniBlockChunk {
+                buffer_sizes: vec![
+                    (run_values.len() - values_start) as u16,
+                    ((run_lengths.len() - lengths_start) * 2) as u16,
+                ],
+                // The number of values in the last chunk is implied
+                log_num_values: if start == num_values {
+                    0
+                } else {
+                    log_num_values
+                },
+            });
+        }
+
+        MiniBlockCompressed {
+            data: vec![
+                LanceBuffer::Owned(run_values),
+                LanceBuffer::reinterpret_vec(run_lengths),
+            ],
+            chunks,
+            num_values: data.num_values,
+        }
+    }
+
+    /// Decodes a chunk without expanding the runs
+    ///
+    /// Returns the value of each run and the length of each run.
+    fn decompress_runs(&self, data: Vec<LanceBuffer>) -> Result<(FixedWidthDataBlock, Vec<u16>)> {
+        let [run_values, mut run_lengths]: [LanceBuffer; 2] =
+            data.try_into().map_err(|data: Vec<_>| Error::Internal {
+                message: format!(
+                    "Run-length encoded chunks have 2 buffers but got {}",
+                    data.len()
+                ),
+                location: location!(),
+            })?;
+        let run_lengths = run_lengths.borrow_to_typed_slice::<u16>().to_vec();
+        let num_runs = run_lengths.len() as u64;
+        if run_values.len() as u64 != num_runs * self.bits_per_value / 8 {
+            return Err(Error::Internal {
+                message: format!(
+                    "Run-length encoded chunk has {} bytes of values for {} runs",
+                    run_values.len(),
+                    num_runs
+                ),
+                location: location!(),
+            });
+        }
+        let runs = FixedWidthDataBlock {
+            data: run_values,
+            bits_per_value: self.bits_per_value,
+            num_values: num_runs,
+            block_info: BlockInfo::new(),
+        };
+        Ok((runs, run_lengths))
+    }
+}
+
+impl MiniBlockCompressor for RunLengthEncoding {
+    fn compress(&self, page: DataBlock) -> Result<(MiniBlockCompressed, pb::ArrayEncoding)> {
+        match page {
+            DataBlock::FixedWidth(fixed_width) => {
+                assert_eq!(fixed_width.bits_per_value, self.bits_per_value);
+                Ok((
+                    self.chunk_data(fixed_width),
+                    ProtobufUtils::run_length(self.bits_per_value),
+                ))
+            }
+            _ => Err(Error::InvalidInput {
+                source: format!(
+                    "Cannot compress a data block of type {} with RunLengthEncoding",
+                    page.name()
+                )
+                .into(),
+                location: location!(),
+            }),
+        }
+    }
+}
+
+impl MiniBlockDecompressor for RunLengthEncoding {
+    fn decompress(&self, data: Vec<LanceBuffer>, num_values: u64) -> Result<DataBlock> {
+        let (runs, run_lengths) = self.decompress_runs(data)?;
+        let total_length = run_lengths.iter().map(|&length| length as u64).sum::<u64>();
+        if total_length != num_values {
+            return Err(Error::Internal {
+                message: format!(
+                    "Run-length encoded chunk has {} values but {} were expected",
+                    total_length, num_values
+                ),
+                location: location!(),
+            });
+        }
+
+        let bytes_per_value = (self.bits_per_value / 8) as usize;
+        let mut values = Vec::with_capacity(num_values as usize * bytes_per_value);
+        for (value, &length) in runs.data[..]
+            .chunks_exact(bytes_per_value)
+            .zip(run_lengths.iter())
+        {
+            for _ in 0..length {
+                values.extend_from_slice(value);
+            }
+        }
+        Ok(DataBlock::FixedWidth(FixedWidthDataBlock {
+            data: LanceBuffer::Owned(values),
+            bits_per_value: self.bits_per_value,
+            num_values,
+            block_info: BlockInfo::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Int32Array, UInt64Array, UInt8Array};
+
+    use super::*;
+    use crate::testing::{check_round_trip_encoding_of_data, TestCases};
+    use crate::version::LanceFileVersion;
+
+    fn runs_of<T: Clone>(values: &[T], run_length: usize) -> Vec<T> {
+        values
+            .iter()
+            .flat_map(|value| std::iter::repeat_n(value.clone(), run_length))
+            .collect()
+    }
+
+    #[test]
+    fn test_chunking() {
+        // Long runs fit into chunks of the maximum number of values
+        let values = UInt64Array::from(runs_of(&[1, 2, 3], 5000));
+        let data = DataBlock::from_array(values);
+        let (compressed, _) = RunLengthEncoding::new(64).compress(data).unwrap();
+        assert_eq!(compressed.chunks.len(), 4);
+        assert_eq!(compressed.chunks[0].log_num_values, 12);
+        assert_eq!(compressed.chunks[0].buffer_sizes, vec![8, 2]);
+
+        // Without runs the chunks get smaller to stay within the size limit
+        let values = UInt64Array::from_iter_values(0..10_000);
+        let data = DataBlock::from_array(values);
+        let (compressed, _) = RunLengthEncoding::new(64).compress(data).unwrap();
+        for chunk in &compressed.chunks {
+            let size = chunk
+                .buffer_sizes
+                .iter()
+                .map(|&size| size as u64)
+                .sum::<u64>();
+            assert!(size <= MAX_CHUNK_BYTES);
+        }
+    }
+
+    #[test]
+    fn test_decompress_runs() {
+        let encoding = RunLengthEncoding::new(32);
+        let values = Int32Array::from(runs_of(&[7, 3, 7], 100));
+        let (compressed, _) = encoding.compress(DataBlock::from_array(values)).unwrap();
+        assert_eq!(compressed.chunks.len(), 1);
+
+        let (mut runs, run_lengths) = encoding.decompress_runs(compressed.data).unwrap();
+        assert_eq!(run_lengths, vec![100, 100, 100]);
+        assert_eq!(runs.num_values, 3);
+        assert_eq!(
+            runs.data.borrow_to_typed_slice::<i32>().as_ref(),
+            &[7, 3, 7]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_round_trip() {
+        let statuses: ArrayRef = Arc::new(UInt8Array::from(runs_of(&[0, 1, 2, 1], 3000)));
+        let ids: ArrayRef = Arc::new(Int32Array::from(runs_of(&[-5, 1 << 20, 42], 2500)));
+        for array in [statuses, ids] {
+            check_round_trip_encoding_of_data(
+                vec![array],
+                &TestCases::default().with_file_version(LanceFileVersion::V2_1),
+                HashMap::new(),
+            )
+            .await;
+        }
+    }
+}
@@ -21,9 +21,9 @@ use pb::{
     nullable::{AllNull, NoNull, Nullability, SomeNull},
     page_layout::Layout,
     AllNullLayout, ArrayEncoding, Binary, Bitpacked, BitpackedForNonNeg, Block, Dictionary,
-    FixedSizeBinary, FixedSizeList, Flat, Fsst, InlineBitpacking, MiniBlockLayout, Nullable,
-    OutOfLineBitpacking, PackedStruct, PackedStructFixedWidthMiniBlock, PageLayout, RepDefLayer,
-    Variable,
+    FixedSizeBinary, FixedSizeList, Flat, FrameOfReferenceBitpacking, Fsst, GeneralMiniBlock,
+    InlineBitpacking, MiniBlockLayout, Nullable, OutOfLineBitpacking, PackedStruct,
+    PackedStructFixedWidthMiniBlock, PageLayout, RepDefLayer, RunLength, Variable,
 };
 
 use crate::{encodings::physical::block::CompressionConfig, repdef::DefinitionInterpretation};
@@ -74,14 +74,32 @@ impl ProtobufUtils {
         }
     }
 
-    pub fn block(scheme: &str) -> ArrayEncoding {
+    pub fn block(compression: CompressionConfig) -> ArrayEncoding {
         ArrayEncoding {
             array_encoding: Some(ArrayEncodingEnum::Block(Block {
-                scheme: scheme.to_string(),
+                scheme: compression.scheme.to_string(),
+                level: compression.level,
             })),
         }
     }
 
+    pub fn general_mini_block(
+        inner: ArrayEncoding,
+        compression: CompressionConfig,
+    ) -> ArrayEncoding {
+        ArrayEncoding {
+            array_encoding: Some(ArrayEncodingEnum::GeneralMiniBlock(Box::new(
+                GeneralMiniBlock {
+                    inner: Some(Box::new(inner)),
+                    compression: Some(pb::Compression {
+                        scheme: compression.scheme.to_string(),
+                        level: compression.level,
+                    }),
+                },
+            ))),
+        }
+    }
+
     pub fn flat_encoding(
         bits_per_value: u64,
         buffer_index: u32,
@@ -154,6 +172,24 @@ impl ProtobufUtils {
             })),
         }
     }
+    pub fn frame_of_reference_bitpacking(
+        uncompressed_bits_per_value: u64,
+        delta: bool,
+    ) -> ArrayEncoding {
+        ArrayEncoding {
+            array_encoding: Some(ArrayEncodingEnum::FrameOfReferenceBitpacking(
+                FrameOfReferenceBitpacking {
+                    uncompressed_bits_per_value,
+                    delta,
+                },
+            )),
+        }
+    }
+    pub fn run_length(bits_per_value: u64) -> ArrayEncoding {
+        ArrayEncoding {
+            array_encoding: Some(ArrayEncodingEnum::RunLength(RunLength { bits_per_value })),
+        }
+    }
     pub fn out_of_line_bitpacking(
         uncompressed_bits_per_value: u64,
         compressed_bits_per_value: u64,
@@ -74,6 +74,11 @@ pub trait EncodingsIo: std::fmt::Debug + Send + Sync {
             .map_ok(|mut v| v.pop().unwrap())
             .boxed()
     }
+
+    /// Records that `num_pages` pages are being decoded from this source
+    ///
+    /// This only feeds statistics and the default implementation ignores it.
+    fn record_pages_decoded(&self, _num_pages: u64) {}
 }
 
 /// An implementation of EncodingsIo that serves data from an in-memory buffer
@@ -10,7 +10,7 @@ use std::{
 use arrow::{array::AsArray, datatypes::UInt64Type};
 use arrow_array::{Array, ArrowPrimitiveType, UInt64Array};
 use hyperloglogplus::{HyperLogLog, HyperLogLogPlus};
-use num_traits::PrimInt;
+use num_traits::{PrimInt, WrappingSub};
 
 use crate::data::{
     AllNullDataBlock, DataBlock, DictionaryDataBlock, FixedSizeListBlock, FixedWidthDataBlock,
@@ -20,6 +20,13 @@ use crate::data::{
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Stat {
     BitWidth,
+    /// The bit width of each chunk of 1024 values relative to the smallest value of the chunk
+    RangeBitWidth,
+    /// The bit width of the differences between neighbouring values of each chunk of 1024
+    /// values, relative to the smallest difference of the chunk
+    DeltaBitWidth,
+    /// The number of runs of equal values
+    RunCount,
     DataSize,
     Cardinality,
     FixedSize,
@@ -31,6 +38,9 @@ impl fmt::Debug for Stat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::BitWidth => write!(f, "BitWidth"),
+            Self::RangeBitWidth => write!(f, "RangeBitWidth"),
+            Self::DeltaBitWidth => write!(f, "DeltaBitWidth"),
+            Self::RunCount => write!(f, "RunCount"),
             Self::DataSize => write!(f, "DataSize"),
             Self::Cardinality => write!(f, "Cardinality"),
             Self::FixedSize => write!(f, "FixedSize"),
@@ -99,6 +109,13 @@ impl ComputeStat for FixedWidthDataBlock {
         let max_len = self.bits_per_value / 8;
         let max_len_array = Arc::new(UInt64Array::from(vec![max_len]));
 
+        let range_bit_widths = self.range_bit_widths();
+
+        let run_count = (self.bits_per_value % 8 == 0).then(|| {
+            let num_runs = count_runs(&self.data[..], self.bits_per_value as usize / 8);
+            Arc::new(UInt64Array::from(vec![num_runs])) as Arc<dyn Array>
+        });
+
         let cardidinality_array = if self.bits_per_value == 128 {
             Some(self.cardinality())
         } else {
@@ -109,6 +126,13 @@ impl ComputeStat for FixedWidthDataBlock {
         info.insert(Stat::DataSize, data_size_array);
         info.insert(Stat::BitWidth, max_bit_widths);
         info.insert(Stat::MaxLength, max_len_array);
+        if let Some((range_bit_widths, delta_bit_widths)) = range_bit_widths {
+            info.insert(Stat::RangeBitWidth, range_bit_widths);
+            info.insert(Stat::DeltaBitWidth, delta_bit_widths);
+        }
+        if let Some(run_count) = run_count {
+            info.insert(Stat::RunCount, run_count);
+        }
         if let Some(cardinality_array) = cardidinality_array {
             info.insert(Stat::Cardinality, cardinality_array);
         }
@@ -314,7 +338,90 @@ impl GetStat for FixedWidthDataBlock {
     }
 }
 
+/// The number of runs of equal values in `values`, which holds values of `bytes_per_value`
+/// bytes each
+pub(crate) fn count_runs(values: &[u8], bytes_per_value: usize) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    let num_changes = values
+        .chunks_exact(bytes_per_value)
+        .zip(values.chunks_exact(bytes_per_value).skip(1))
+        .filter(|(previous, value)| previous != value)
+        .count();
+    num_changes as u64 + 1
+}
+
+/// The smallest of `values` and the number of bits needed to store each value as an offset from it
+///
+/// The values are compared both as unsigned and as two's complement integers, since we don't know
+/// which they are, and the narrower range is used.  Either way the offsets are computed with
+/// wrapping arithmetic, so adding them back to the smallest value restores the input.
+pub(crate) fn narrowest_range<T: PrimInt + WrappingSub>(
+    values: impl Iterator<Item = T>,
+) -> (T, u64) {
+    let bits = T::zero().count_zeros();
+    let sign_bit = T::one() << (bits as usize - 1);
+    let mut min = T::max_value();
+    let mut max = T::zero();
+    // The keys order the values as signed integers
+    let mut min_key = T::max_value();
+    let mut max_key = T::zero();
+    let mut empty = true;
+    for value in values {
+        empty = false;
+        min = min.min(value);
+        max = max.max(value);
+        let key = value ^ sign_bit;
+        min_key = min_key.min(key);
+        max_key = max_key.max(key);
+    }
+    if empty {
+        return (T::zero(), 0);
+    }
+    let range = max.wrapping_sub(&min);
+    let signed_range = max_key.wrapping_sub(&min_key);
+    if signed_range < range {
+        (
+            min_key ^ sign_bit,
+            (bits - signed_range.leading_zeros()) as u64,
+        )
+    } else {
+        (min, (bits - range.leading_zeros()) as u64)
+    }
+}
+
 impl FixedWidthDataBlock {
+    /// The `RangeBitWidth` and `DeltaBitWidth` statistics, for integer widths only
+    fn range_bit_widths(&mut self) -> Option<(Arc<dyn Array>, Arc<dyn Array>)> {
+        const CHUNK_SIZE: usize = 1024;
+
+        fn calculate<T: PrimInt + WrappingSub>(slice: &[T]) -> (Arc<dyn Array>, Arc<dyn Array>) {
+            let (range_widths, delta_widths): (Vec<u64>, Vec<u64>) = slice
+                .chunks(CHUNK_SIZE)
+                .map(|chunk| {
+                    let deltas = chunk.windows(2).map(|pair| pair[1].wrapping_sub(&pair[0]));
+                    (
+                        narrowest_range(chunk.iter().copied()).1,
+                        narrowest_range(deltas).1,
+                    )
+                })
+                .unzip();
+            (
+                Arc::new(UInt64Array::from(range_widths)),
+                Arc::new(UInt64Array::from(delta_widths)),
+            )
+        }
+
+        match self.bits_per_value {
+            8 => Some(calculate(self.data.borrow_to_typed_slice::<u8>().as_ref())),
+            16 => Some(calculate(self.data.borrow_to_typed_slice::<u16>().as_ref())),
+            32 => Some(calculate(self.data.borrow_to_typed_slice::<u32>().as_ref())),
+            64 => Some(calculate(self.data.borrow_to_typed_slice::<u64>().as_ref())),
+            _ => None,
+        }
+    }
+
     fn max_bit_widths(&mut self) -> Arc<dyn Array> {
         assert!(self.num_values > 0);
 
@@ -866,6 +973,65 @@ mod tests {
         assert_eq!(actual_bit_width.as_ref(), expected_bit_width.as_ref());
     }
 
+    #[test]
+    fn test_range_bit_width_stats() {
+        let check = |array: ArrayRef, range: Vec<u64>, delta: Vec<u64>| {
+            let block = DataBlock::from_array(array);
+            let expected = Arc::new(UInt64Array::from(range)) as ArrayRef;
+            assert_eq!(
+                block.expect_stat(Stat::RangeBitWidth).as_ref(),
+                expected.as_ref()
+            );
+            let expected = Arc::new(UInt64Array::from(delta)) as ArrayRef;
+            assert_eq!(
+                block.expect_stat(Stat::DeltaBitWidth).as_ref(),
+                expected.as_ref()
+            );
+        };
+
+        // Nanosecond timestamps one second apart need 40 bits, instead of 61, relative to the
+        // start of a chunk, and no bits at all as differences
+        let start = 1_700_000_000_000_000_000_i64;
+        check(
+            Arc::new(Int64Array::from_iter_values(
+                (0..2048).map(|i| start + i * 1_000_000_000),
+            )),
+            vec![40, 40],
+            vec![0, 0],
+        );
+        check(
+            Arc::new(UInt32Array::from_iter_values(1_000_000..1_001_500)),
+            vec![10, 9],
+            vec![0, 0],
+        );
+
+        // Small negative and positive values are close together as signed integers
+        check(
+            Arc::new(Int8Array::from(vec![-3, 1, -1, 4])),
+            vec![3],
+            vec![3],
+        );
+        // A single value has no differences
+        check(Arc::new(UInt16Array::from(vec![7])), vec![0], vec![0]);
+    }
+
+    #[test]
+    fn test_run_count_stat() {
+        let run_count = |array: ArrayRef| {
+            let block = DataBlock::from_array(array);
+            block.expect_single_stat::<UInt64Type>(Stat::RunCount)
+        };
+        assert_eq!(
+            run_count(Arc::new(Int32Array::from(vec![1, 1, 2, 2, 2, 1]))),
+            3
+        );
+        assert_eq!(run_count(Arc::new(UInt8Array::from(vec![5; 10_000]))), 1);
+        assert_eq!(
+            run_count(Arc::new(Int64Array::from_iter_values(0..100))),
+            100
+        );
+    }
+
     #[test]
     fn test_bit_width_stat_more_than_1024() {
         for data_type in [
@@ -280,6 +280,7 @@ pub async fn check_round_trip_encoding_generated(
             let encoding_options = EncodingOptions {
                 max_page_bytes: MAX_PAGE_BYTES,
                 cache_bytes_per_column: page_size,
+                cache_rows_per_column: None,
                 keep_original_array: true,
                 buffer_alignment: MIN_PAGE_BUFFER_ALIGNMENT,
             };
@@ -412,6 +413,7 @@ pub async fn check_round_trip_encoding_of_data(
         let mut column_index_seq = ColumnIndexSequence::default();
         let encoding_options = EncodingOptions {
             cache_bytes_per_column: *page_size,
+            cache_rows_per_column: None,
             max_page_bytes: test_cases.get_max_page_size(),
             keep_original_array: true,
             buffer_alignment: MIN_PAGE_BUFFER_ALIGNMENT,
@@ -10,6 +10,7 @@ use log::{debug, trace};
 #[derive(Debug)]
 pub struct AccumulationQueue {
     cache_bytes: u64,
+    cache_rows: Option<u64>,
     keep_original_array: bool,
     buffered_arrays: Vec<ArrayRef>,
     current_bytes: u64,
@@ -25,6 +26,7 @@ impl AccumulationQueue {
     pub fn new(cache_bytes: u64, column_index: u32, keep_original_array: bool) -> Self {
         Self {
             cache_bytes,
+            cache_rows: None,
             buffered_arrays: Vec::new(),
             current_bytes: 0,
             column_index,
@@ -34,6 +36,13 @@ impl AccumulationQueue {
         }
     }
 
+    /// Also flush once this many rows have been accumulated, even if there are less
+    /// than `cache_bytes` bytes
+    pub fn with_cache_rows(mut self, cache_rows: Option<u64>) -> Self {
+        self.cache_rows = cache_rows;
+        self
+    }
+
     /// Adds an array to the queue, if there is enough data then the queue is flushed
     /// and returned
     pub fn insert(
@@ -47,10 +56,14 @@ impl AccumulationQueue {
         }
         self.num_rows += num_rows;
         self.current_bytes += array.get_array_memory_size() as u64;
-        if self.current_bytes > self.cache_bytes {
+        if self.current_bytes > self.cache_bytes
+            || self
+                .cache_rows
+                .is_some_and(|cache_rows| self.num_rows >= cache_rows)
+        {
             debug!(
-                "Flushing column {} page of size {} bytes (unencoded)",
-                self.column_index, self.current_bytes
+                "Flushing column {} page of size {} bytes (unencoded) and {} rows",
+                self.column_index, self.current_bytes, self.num_rows
             );
             // Push into buffered_arrays without copy since we are about to flush anyways
             self.buffered_arrays.push(array);
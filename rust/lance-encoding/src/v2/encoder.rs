@@ -39,10 +39,7 @@ use crate::{
     version::LanceFileVersion,
 };
 
-use lance_core::datatypes::{
-    Field, BLOB_DESC_FIELD, BLOB_META_KEY, COMPRESSION_LEVEL_META_KEY, COMPRESSION_META_KEY,
-    PACKED_STRUCT_LEGACY_META_KEY, PACKED_STRUCT_META_KEY,
-};
+use lance_core::datatypes::{Field, BLOB_DESC_FIELD, BLOB_META_KEY, PACKED_STRUCT_META_KEY};
 use lance_core::{Error, Result};
 
 /// An encoded array
@@ -203,18 +200,12 @@ impl FieldEncodingStrategy for CoreFieldEncodingStrategy {
                     Ok(Box::new(ListFieldEncoder::new(
                         inner_encoding,
                         offsets_encoder,
-                        options.cache_bytes_per_column,
-                        options.keep_original_array,
+                        options.accumulation_queue(list_idx, &[&field.metadata])?,
                         list_idx,
                     )))
                 }
                 DataType::Struct(_) => {
-                    let field_metadata = &field.metadata;
-                    if field_metadata
-                        .get(PACKED_STRUCT_LEGACY_META_KEY)
-                        .map(|v| v == "true")
-                        .unwrap_or(field_metadata.contains_key(PACKED_STRUCT_META_KEY))
-                    {
+                    if field.is_packed_struct() {
                         Ok(Box::new(PrimitiveFieldEncoder::try_new(
                             options,
                             self.array_encoding_strategy.clone(),
@@ -259,6 +250,15 @@ impl FieldEncodingStrategy for CoreFieldEncodingStrategy {
                         Err(Error::NotSupported { source: format!("cannot encode a dictionary column whose value type is a logical type ({})", value_type).into(), location: location!() })
                     }
                 }
+                DataType::BinaryView | DataType::Utf8View | DataType::RunEndEncoded(_, _) => Err(Error::NotSupported {
+                    source: format!(
+                        "cannot encode field {} with type {}, this type requires file version 2.1 or later",
+                        field.name,
+                        field.data_type()
+                    )
+                    .into(),
+                    location: location!(),
+                }),
                 _ => todo!("Implement encoding for field {}", field),
             }
         }
@@ -287,17 +287,7 @@ impl CoreArrayEncodingStrategy {
     }
 
     fn get_field_compression(field_meta: &HashMap<String, String>) -> Option<CompressionConfig> {
-        let compression = field_meta.get(COMPRESSION_META_KEY)?;
-        let compression_scheme = compression.parse::<CompressionScheme>();
-        match compression_scheme {
-            Ok(compression_scheme) => Some(CompressionConfig::new(
-                compression_scheme,
-                field_meta
-                    .get(COMPRESSION_LEVEL_META_KEY)
-                    .and_then(|level| level.parse().ok()),
-            )),
-            Err(_) => None,
-        }
+        CompressionConfig::from_field_metadata(field_meta)
     }
 
     fn default_binary_encoder(
@@ -12,10 +12,10 @@ use arrow_array::{
 use arrow_buffer::{BooleanBuffer, BooleanBufferBuilder, Buffer, NullBuffer, OffsetBuffer};
 use arrow_schema::{DataType, Field, Fields};
 use futures::{future::BoxFuture, FutureExt};
+use lance_core::utils::tokio::{spawn, JoinHandle};
 use lance_core::{cache::LanceCache, Error, Result};
 use log::trace;
 use snafu::location;
-use tokio::task::JoinHandle;
 
 use crate::{
     buffer::LanceBuffer,
@@ -459,7 +459,7 @@ impl SchedulingJob for ListFieldSchedulingJob<'_> {
         let cache = context.cache().clone();
 
         // Immediately spawn the indirect scheduling
-        let indirect_fut = tokio::spawn(indirect_schedule_task(
+        let indirect_fut = spawn(indirect_schedule_task(
             next_offsets_decoder,
             list_reqs,
             null_offset_adjustment,
@@ -867,17 +867,12 @@ struct ListOffsetsEncoder {
 
 impl ListOffsetsEncoder {
     fn new(
-        cache_bytes: u64,
-        keep_original_array: bool,
+        accumulation_queue: AccumulationQueue,
         column_index: u32,
         inner_encoder: Arc<dyn ArrayEncoder>,
     ) -> Self {
         Self {
-            accumulation_queue: AccumulationQueue::new(
-                cache_bytes,
-                column_index,
-                keep_original_array,
-            ),
+            accumulation_queue,
             inner_encoder,
             column_index,
         }
@@ -918,7 +913,7 @@ impl ListOffsetsEncoder {
         let offset_arrays = arrays.iter().step_by(2).cloned().collect::<Vec<_>>();
         let validity_arrays = arrays.into_iter().skip(1).step_by(2).collect::<Vec<_>>();
 
-        tokio::task::spawn(async move {
+        spawn(async move {
             let num_rows =
                 offset_arrays.iter().map(|arr| arr.len()).sum::<usize>() - offset_arrays.len();
             let num_rows = num_rows as u64;
@@ -1146,14 +1141,12 @@ impl ListFieldEncoder {
     pub fn new(
         items_encoder: Box<dyn FieldEncoder>,
         inner_offsets_encoder: Arc<dyn ArrayEncoder>,
-        cache_bytes_per_columns: u64,
-        keep_original_array: bool,
+        offsets_queue: AccumulationQueue,
         column_index: u32,
     ) -> Self {
         Self {
             offsets_encoder: ListOffsetsEncoder::new(
-                cache_bytes_per_columns,
-                keep_original_array,
+                offsets_queue,
                 column_index,
                 inner_offsets_encoder,
             ),
@@ -16,7 +16,7 @@ use crate::utils::accumulation::AccumulationQueue;
 use crate::v2::decoder::{FieldScheduler, LogicalPageDecoder, SchedulingJob};
 use crate::v2::encoder::ArrayEncodingStrategy;
 use crate::{data::DataBlock, v2::encodings::physical::decoder_from_array_encoding};
-use lance_core::{datatypes::Field, Result};
+use lance_core::{datatypes::Field, utils::tokio::spawn, Result};
 
 use crate::{
     decoder::{
@@ -410,11 +410,7 @@ impl PrimitiveFieldEncoder {
         field: Field,
     ) -> Result<Self> {
         Ok(Self {
-            accumulation_queue: AccumulationQueue::new(
-                options.cache_bytes_per_column,
-                column_index,
-                options.keep_original_array,
-            ),
+            accumulation_queue: options.accumulation_queue(column_index, &[&field.metadata])?,
             column_index,
             max_page_bytes: options.max_page_bytes,
             array_encoding_strategy,
@@ -429,7 +425,7 @@ impl PrimitiveFieldEncoder {
         let column_idx = self.column_index;
         let data_type = self.field.data_type();
 
-        Ok(tokio::task::spawn(async move {
+        Ok(spawn(async move {
             let num_values = arrays.iter().map(|arr| arr.len() as u64).sum();
             let data = DataBlock::from_arrays(&arrays, num_values);
             let mut buffer_index = 0;
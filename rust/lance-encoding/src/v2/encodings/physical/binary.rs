@@ -30,7 +30,7 @@ use crate::{
 
 use arrow_array::{PrimitiveArray, UInt64Array};
 use arrow_schema::DataType;
-use lance_core::Result;
+use lance_core::{utils::tokio::spawn, Result};
 
 struct IndicesNormalizer {
     indices: Vec<u64>,
@@ -157,7 +157,7 @@ impl PageScheduler for BinaryPageScheduler {
         let null_adjustment = self.null_adjustment;
         let offsets_type = self.offsets_type.clone();
 
-        tokio::spawn(async move {
+        spawn(async move {
             // For the following data:
             // "abcd", "hello", "abcd", "apple", "hello", "abcd"
             //   4,        9,     13,      18,      23,     27
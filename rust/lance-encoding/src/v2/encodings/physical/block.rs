@@ -5,7 +5,7 @@ use arrow_schema::DataType;
 
 use crate::{
     data::{BlockInfo, DataBlock, OpaqueBlock},
-    encodings::physical::block::{CompressedBufferEncoder, CompressionConfig, CompressionScheme},
+    encodings::physical::block::CompressedBufferEncoder,
     format::ProtobufUtils,
     v2::encoder::{ArrayEncoder, EncodedArray},
 };
@@ -37,7 +37,7 @@ impl ArrayEncoder for CompressedBufferEncoder {
         let encoding = ProtobufUtils::flat_encoding(
             uncompressed_data.bits_per_value,
             comp_buf_index,
-            Some(CompressionConfig::new(CompressionScheme::Zstd, None)),
+            Some(self.compression_config),
         );
 
         Ok(EncodedArray {
@@ -13,7 +13,7 @@ use arrow_array::{
 use arrow_schema::DataType;
 use futures::{future::BoxFuture, FutureExt};
 use lance_arrow::DataTypeExt;
-use lance_core::{Error, Result};
+use lance_core::{utils::tokio::spawn, Error, Result};
 use snafu::location;
 use std::collections::HashMap;
 
@@ -88,7 +88,7 @@ impl PageScheduler for DictionaryPageScheduler {
         let copy_size = self.num_dictionary_items as u64;
 
         if self.should_decode_dict {
-            tokio::spawn(async move {
+            spawn(async move {
                 let items_decoder: Arc<dyn PrimitivePageDecoder> =
                     Arc::from(items_page_decoder.await?);
 
@@ -115,7 +115,7 @@ impl PageScheduler for DictionaryPageScheduler {
             .boxed()
         } else {
             let num_dictionary_items = self.num_dictionary_items;
-            tokio::spawn(async move {
+            spawn(async move {
                 let items_decoder: Arc<dyn PrimitivePageDecoder> =
                     Arc::from(items_page_decoder.await?);
 
@@ -412,6 +412,7 @@ pub mod tests {
         ArrayRef, StringArray, UInt8Array,
     };
     use arrow_schema::{DataType, Field};
+    use rstest::rstest;
     use std::{collections::HashMap, sync::Arc, vec};
 
     use crate::{
@@ -565,13 +566,17 @@ pub mod tests {
 
     // These tests cover the case where the input is already dictionary encoded
 
+    #[rstest]
     #[test_log::test(tokio::test)]
-    async fn test_random_dictionary_input() {
+    async fn test_random_dictionary_input(
+        #[values(LanceFileVersion::V2_0, LanceFileVersion::V2_1)] version: LanceFileVersion,
+        #[values(DataType::UInt16, DataType::Int32)] key_type: DataType,
+    ) {
         let dict_field = Field::new(
             "",
-            DataType::Dictionary(Box::new(DataType::UInt16), Box::new(DataType::Utf8)),
+            DataType::Dictionary(Box::new(key_type), Box::new(DataType::Utf8)),
             false,
         );
-        check_round_trip_encoding_random(dict_field, LanceFileVersion::V2_0).await;
+        check_round_trip_encoding_random(dict_field, version).await;
     }
 }
@@ -8,7 +8,7 @@ use bytes::Bytes;
 use bytes::BytesMut;
 use futures::{future::BoxFuture, FutureExt};
 use lance_arrow::DataTypeExt;
-use lance_core::{Error, Result};
+use lance_core::{utils::tokio::spawn, Error, Result};
 use snafu::location;
 
 use crate::data::BlockInfo;
@@ -82,7 +82,7 @@ impl PageScheduler for PackedStructPageScheduler {
 
         let copy_struct_fields = self.fields.clone();
 
-        tokio::spawn(async move {
+        spawn(async move {
             let bytes = bytes.await?;
 
             let mut combined_bytes = BytesMut::default();
@@ -265,7 +265,10 @@ pub mod tests {
     use arrow_schema::{DataType, Field, Fields};
     use std::{collections::HashMap, sync::Arc, vec};
 
+    use lance_core::datatypes::PACKED_STRUCT_META_KEY;
+
     use crate::{
+        encoder::{default_encoding_strategy, ColumnIndexSequence, EncodingOptions},
         testing::{check_round_trip_encoding_of_data, check_round_trip_encoding_random, TestCases},
         version::LanceFileVersion,
     };
@@ -288,6 +291,53 @@ pub mod tests {
         check_round_trip_encoding_random(field, version).await;
     }
 
+    #[rstest]
+    #[test_log::test(tokio::test)]
+    async fn test_packed_struct_meta_key(
+        #[values(LanceFileVersion::V2_0, LanceFileVersion::V2_1)] version: LanceFileVersion,
+    ) {
+        let data_type = DataType::Struct(Fields::from(vec![
+            Field::new("a", DataType::Int16, false),
+            Field::new("b", DataType::Float64, false),
+            Field::new("c", DataType::UInt32, false),
+        ]));
+        let mut metadata = HashMap::new();
+        metadata.insert(PACKED_STRUCT_META_KEY.to_string(), "true".to_string());
+
+        let field = Field::new("", data_type, false).with_metadata(metadata);
+
+        check_round_trip_encoding_random(field, version).await;
+    }
+
+    #[test]
+    fn test_packed_struct_variable_width_child() {
+        let data_type = DataType::Struct(Fields::from(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let mut metadata = HashMap::new();
+        metadata.insert(PACKED_STRUCT_META_KEY.to_string(), "true".to_string());
+        let field = Field::new("s", data_type, false).with_metadata(metadata);
+        let field = lance_core::datatypes::Field::try_from(&field).unwrap();
+
+        let strategy = default_encoding_strategy(LanceFileVersion::V2_1);
+        let err = strategy
+            .create_field_encoder(
+                strategy.as_ref(),
+                &field,
+                &mut ColumnIndexSequence::default(),
+                &EncodingOptions::default(),
+            )
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("only support fixed-width children"),
+            "{}",
+            err
+        );
+    }
+
     #[rstest]
     #[test_log::test(tokio::test)]
     async fn test_specific_packed_struct(
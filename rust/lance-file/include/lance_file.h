@@ -0,0 +1,147 @@
+/*
+ * SPDX-License-Identifier: Apache-2.0
+ * SPDX-FileCopyrightText: Copyright The Lance Authors
+ */
+
+/*
+ * C API for reading and writing Lance files.
+ *
+ * Link against the lance_file library built with the `ffi` feature. Data is
+ * exchanged through the Arrow C data and stream interfaces.
+ *
+ * Functions returning a pointer return NULL on failure and functions
+ * returning an integer return a negative value. The message is available from
+ * lance_file_last_error() on the same thread.
+ *
+ * Calls block the calling thread, which must not be running a Tokio runtime.
+ */
+
+#ifndef LANCE_FILE_H
+#define LANCE_FILE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The Arrow C data interface, see
+ * https://arrow.apache.org/docs/format/CDataInterface.html */
+#ifndef ARROW_C_DATA_INTERFACE
+#define ARROW_C_DATA_INTERFACE
+
+#define ARROW_FLAG_DICTIONARY_ORDERED 1
+#define ARROW_FLAG_NULLABLE 2
+#define ARROW_FLAG_MAP_KEYS_SORTED 4
+
+struct ArrowSchema {
+  const char* format;
+  const char* name;
+  const char* metadata;
+  int64_t flags;
+  int64_t n_children;
+  struct ArrowSchema** children;
+  struct ArrowSchema* dictionary;
+  void (*release)(struct ArrowSchema*);
+  void* private_data;
+};
+
+struct ArrowArray {
+  int64_t length;
+  int64_t null_count;
+  int64_t offset;
+  int64_t n_buffers;
+  int64_t n_children;
+  const void** buffers;
+  struct ArrowArray** children;
+  struct ArrowArray* dictionary;
+  void (*release)(struct ArrowArray*);
+  void* private_data;
+};
+
+#endif /* ARROW_C_DATA_INTERFACE */
+
+/* The Arrow C stream interface, see
+ * https://arrow.apache.org/docs/format/CStreamInterface.html */
+#ifndef ARROW_C_STREAM_INTERFACE
+#define ARROW_C_STREAM_INTERFACE
+
+struct ArrowArrayStream {
+  int (*get_schema)(struct ArrowArrayStream*, struct ArrowSchema* out);
+  int (*get_next)(struct ArrowArrayStream*, struct ArrowArray* out);
+  const char* (*get_last_error)(struct ArrowArrayStream*);
+  void (*release)(struct ArrowArrayStream*);
+  void* private_data;
+};
+
+#endif /* ARROW_C_STREAM_INTERFACE */
+
+typedef struct LanceFileReader LanceFileReader;
+typedef struct LanceFileWriter LanceFileWriter;
+
+/* Open a Lance file, given as a local path or an object store URI. */
+LanceFileReader* lance_file_reader_open(const char* path);
+
+/* The number of rows in the file, or -1 if reader is NULL. */
+int64_t lance_file_reader_num_rows(const LanceFileReader* reader);
+
+/* Export the schema of the file into out. */
+int lance_file_reader_schema(const LanceFileReader* reader, struct ArrowSchema* out);
+
+/*
+ * Read the rows in [start, end) as a stream.
+ *
+ * columns is an array of num_columns column names, or NULL to read every
+ * column. A batch_size of 0 uses the default of 1024 rows. The stream may
+ * outlive the reader.
+ */
+int lance_file_reader_read(const LanceFileReader* reader, uint64_t start, uint64_t end,
+                           const char* const* columns, size_t num_columns,
+                           uint32_t batch_size, struct ArrowArrayStream* out);
+
+/* Close a reader. NULL is ignored. */
+void lance_file_reader_close(LanceFileReader* reader);
+
+/*
+ * Create a Lance file, given as a local path or an object store URI.
+ *
+ * If schema is NULL, the schema is taken from the first batch written. The
+ * schema is not released.
+ */
+LanceFileWriter* lance_file_writer_open(const char* path, const struct ArrowSchema* schema);
+
+/*
+ * Write a record batch, exported as a struct array.
+ *
+ * The array is consumed (released) by this call, the schema is not.
+ */
+int lance_file_writer_write(LanceFileWriter* writer, struct ArrowArray* batch,
+                            const struct ArrowSchema* schema);
+
+/*
+ * Finish the file and free the writer.
+ *
+ * Returns the number of rows written. The writer is freed even on failure.
+ */
+int64_t lance_file_writer_finish(LanceFileWriter* writer);
+
+/*
+ * Free a writer without finishing the file and delete what was written.
+ *
+ * Returns 0, or -1 if the partial file could not be deleted. NULL is ignored.
+ */
+int lance_file_writer_abort(LanceFileWriter* writer);
+
+/*
+ * The message of the last error raised on the calling thread, or NULL.
+ *
+ * The pointer stays valid until the next error is raised on the same thread.
+ */
+const char* lance_file_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LANCE_FILE_H */
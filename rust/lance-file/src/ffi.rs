@@ -0,0 +1,494 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! A C API for reading and writing Lance files.
+//!
+//! Data is exchanged through the Arrow C data and stream interfaces. Readers
+//! and writers are opaque handles:
+//!
+//! ```c
+//! typedef struct LanceFileReader LanceFileReader;
+//! typedef struct LanceFileWriter LanceFileWriter;
+//!
+//! LanceFileReader* lance_file_reader_open(const char* path);
+//! int64_t lance_file_reader_num_rows(const LanceFileReader* reader);
+//! int lance_file_reader_schema(const LanceFileReader* reader, struct ArrowSchema* out);
+//! int lance_file_reader_read(const LanceFileReader* reader, uint64_t start, uint64_t end,
+//!                            const char* const* columns, size_t num_columns,
+//!                            uint32_t batch_size, struct ArrowArrayStream* out);
+//! void lance_file_reader_close(LanceFileReader* reader);
+//!
+//! LanceFileWriter* lance_file_writer_open(const char* path, const struct ArrowSchema* schema);
+//! int lance_file_writer_write(LanceFileWriter* writer, struct ArrowArray* batch,
+//!                             const struct ArrowSchema* schema);
+//! int64_t lance_file_writer_finish(LanceFileWriter* writer);
+//! int lance_file_writer_abort(LanceFileWriter* writer);
+//!
+//! const char* lance_file_last_error(void);
+//! ```
+//!
+//! These are declared in `include/lance_file.h`. The crate is built as a
+//! shared and a static library for linking from C.
+//!
+//! Paths may be local paths or object store URIs. Functions returning a
+//! pointer return null on failure and functions returning an integer return a
+//! negative value; the message is available from `lance_file_last_error`.
+//!
+//! Calls block the calling thread, which must not be running a Tokio runtime.
+
+use std::ffi::{c_char, c_int};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow_array::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
+use arrow_array::{RecordBatch, RecordBatchReader, StructArray};
+use arrow_schema::{ArrowError, Schema as ArrowSchema, SchemaRef};
+use futures::StreamExt;
+use lance_core::cache::LanceCache;
+use lance_core::datatypes::Schema as LanceSchema;
+use lance_core::{Error, Result};
+use lance_encoding::decoder::{DecoderPlugins, FilterExpression};
+use lance_io::ffi::{c_str, guard, last_error, set_last_error, RT};
+use lance_io::object_store::ObjectStore;
+use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
+use lance_io::stream::RecordBatchStream;
+use lance_io::utils::CachedFileSize;
+use lance_io::ReadBatchParams;
+use object_store::path::Path;
+use snafu::location;
+
+use crate::v2::reader::{FileReader, FileReaderOptions, ReaderProjection};
+use crate::v2::writer::{FileWriter, FileWriterOptions};
+
+/// Borrow a string argument that must not be null
+unsafe fn required_str<'a>(ptr: *const c_char, what: &str) -> Result<&'a str> {
+    c_str(ptr)?
+        .ok_or_else(|| Error::invalid_input(format!("{} must not be null", what), location!()))
+}
+
+/// Split a file path or URI into an object store and the path of the file in it.
+async fn open_store(path: &str) -> Result<(Arc<ObjectStore>, Path)> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or((".", path));
+    let dir = if dir.is_empty() { "/" } else { dir };
+    let (store, dir_path) = ObjectStore::from_uri(dir).await?;
+    Ok((store, dir_path.child(name)))
+}
+
+/// The message of the last error raised on the calling thread, or null.
+#[no_mangle]
+pub extern "C" fn lance_file_last_error() -> *const c_char {
+    last_error()
+}
+
+/// An open Lance file, behind the `LanceFileReader*` handle.
+pub struct CFileReader {
+    reader: FileReader,
+}
+
+/// Open a Lance file for reading.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_reader_open(path: *const c_char) -> *mut CFileReader {
+    guard(std::ptr::null_mut(), || {
+        let path = required_str(path, "path")?;
+        let reader = RT.block_on(async {
+            let (store, path) = open_store(path).await?;
+            let config = SchedulerConfig::max_bandwidth(&store);
+            let scheduler = ScanScheduler::new(store, config);
+            let file = scheduler
+                .open_file(&path, &CachedFileSize::unknown())
+                .await?;
+            FileReader::try_open(
+                file,
+                None,
+                Arc::<DecoderPlugins>::default(),
+                &LanceCache::no_cache(),
+                FileReaderOptions::default(),
+            )
+            .await
+        })?;
+        Ok(Box::into_raw(Box::new(CFileReader { reader })))
+    })
+}
+
+/// Number of rows in the file, or -1 if `reader` is null.
+///
+/// # Safety
+///
+/// `reader` must be null or a handle returned by [`lance_file_reader_open`].
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_reader_num_rows(reader: *const CFileReader) -> i64 {
+    match reader.as_ref() {
+        Some(reader) => reader.reader.num_rows() as i64,
+        None => {
+            set_last_error("reader must not be null");
+            -1
+        }
+    }
+}
+
+/// Export the schema of the file.
+///
+/// # Safety
+///
+/// `reader` must be a handle returned by [`lance_file_reader_open`] and `out`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_reader_schema(
+    reader: *const CFileReader,
+    out: *mut FFI_ArrowSchema,
+) -> c_int {
+    guard(-1, || {
+        let reader = reader
+            .as_ref()
+            .ok_or_else(|| Error::invalid_input("reader must not be null", location!()))?;
+        let schema = ArrowSchema::from(reader.reader.schema().as_ref());
+        let schema = FFI_ArrowSchema::try_from(&schema)?;
+        std::ptr::write_unaligned(out, schema);
+        Ok(0)
+    })
+}
+
+/// Read the rows in `[start, end)` as an Arrow C stream.
+///
+/// `columns` is an array of `num_columns` column names, or null to read every
+/// column. A `batch_size` of 0 uses the default of 1024 rows.
+///
+/// # Safety
+///
+/// `reader` must be a handle returned by [`lance_file_reader_open`], `columns`
+/// must be null or point to `num_columns` NUL-terminated strings, and `out`
+/// must be valid for writes. The stream may outlive the reader.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_reader_read(
+    reader: *const CFileReader,
+    start: u64,
+    end: u64,
+    columns: *const *const c_char,
+    num_columns: usize,
+    batch_size: u32,
+    out: *mut FFI_ArrowArrayStream,
+) -> c_int {
+    guard(-1, || {
+        let reader = &reader
+            .as_ref()
+            .ok_or_else(|| Error::invalid_input("reader must not be null", location!()))?
+            .reader;
+        if start > end || end > reader.num_rows() {
+            return Err(Error::invalid_input(
+                format!(
+                    "Cannot read rows {}..{} from a file with {} rows",
+                    start,
+                    end,
+                    reader.num_rows()
+                ),
+                location!(),
+            ));
+        }
+        let file_metadata = reader.metadata();
+        let projection = if columns.is_null() {
+            ReaderProjection::from_whole_schema(&file_metadata.file_schema, file_metadata.version())
+        } else {
+            let names = (0..num_columns)
+                .map(|i| required_str(*columns.add(i), "column name"))
+                .collect::<Result<Vec<_>>>()?;
+            ReaderProjection::from_column_names(
+                file_metadata.version(),
+                &file_metadata.file_schema,
+                &names,
+            )?
+        };
+        let batch_size = if batch_size == 0 { 1024 } else { batch_size };
+        let stream = {
+            // Reads are scheduled on the runtime.
+            let _guard = RT.enter();
+            reader.read_stream_projected(
+                ReadBatchParams::Range(start as usize..end as usize),
+                batch_size,
+                16,
+                projection,
+                FilterExpression::no_filter(),
+            )?
+        };
+        let stream = FFI_ArrowArrayStream::new(Box::new(BlockingReader { stream }));
+        std::ptr::write_unaligned(out, stream);
+        Ok(0)
+    })
+}
+
+/// Close a reader.
+///
+/// # Safety
+///
+/// `reader` must be null or a handle returned by [`lance_file_reader_open`]
+/// that was not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_reader_close(reader: *mut CFileReader) {
+    if !reader.is_null() {
+        drop(Box::from_raw(reader));
+    }
+}
+
+struct BlockingReader {
+    stream: Pin<Box<dyn RecordBatchStream>>,
+}
+
+impl Iterator for BlockingReader {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        RT.block_on(self.stream.next())
+            .map(|batch| batch.map_err(|e| ArrowError::ExternalError(Box::new(e))))
+    }
+}
+
+impl RecordBatchReader for BlockingReader {
+    fn schema(&self) -> SchemaRef {
+        self.stream.schema()
+    }
+}
+
+/// A Lance file being written, behind the `LanceFileWriter*` handle.
+pub struct CFileWriter {
+    writer: FileWriter,
+    store: Arc<ObjectStore>,
+    path: Path,
+}
+
+/// Create a Lance file.
+///
+/// If `schema` is null, the schema is taken from the first batch written.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string and `schema` must be null or a
+/// valid Arrow schema. The schema is not released.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_writer_open(
+    path: *const c_char,
+    schema: *const FFI_ArrowSchema,
+) -> *mut CFileWriter {
+    guard(std::ptr::null_mut(), || {
+        let path = required_str(path, "path")?;
+        let schema = schema
+            .as_ref()
+            .map(|schema| -> Result<LanceSchema> {
+                LanceSchema::try_from(&ArrowSchema::try_from(schema)?)
+            })
+            .transpose()?;
+        let writer = RT.block_on(async {
+            let (store, path) = open_store(path).await?;
+            let object_writer = store.create(&path).await?;
+            let options = FileWriterOptions::default();
+            let writer = match schema {
+                Some(schema) => FileWriter::try_new(object_writer, schema, options)?,
+                None => FileWriter::new_lazy(object_writer, options),
+            };
+            Ok::<_, Error>(CFileWriter {
+                writer,
+                store,
+                path,
+            })
+        })?;
+        Ok(Box::into_raw(Box::new(writer)))
+    })
+}
+
+/// Write a record batch, exported as a struct array.
+///
+/// The array is consumed (released) by this call, the schema is not.
+///
+/// # Safety
+///
+/// `writer` must be a handle returned by [`lance_file_writer_open`], `batch`
+/// a valid Arrow array and `schema` its valid Arrow schema.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_writer_write(
+    writer: *mut CFileWriter,
+    batch: *mut FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+) -> c_int {
+    guard(-1, || {
+        let writer = writer
+            .as_mut()
+            .ok_or_else(|| Error::invalid_input("writer must not be null", location!()))?;
+        let (Some(schema), false) = (schema.as_ref(), batch.is_null()) else {
+            return Err(Error::invalid_input(
+                "batch and schema must not be null",
+                location!(),
+            ));
+        };
+        let data = from_ffi(FFI_ArrowArray::from_raw(batch), schema)?;
+        let batch = RecordBatch::from(StructArray::from(data));
+        RT.block_on(writer.writer.write_batch(&batch))?;
+        Ok(0)
+    })
+}
+
+/// Finish the file and free the writer.
+///
+/// Returns the number of rows written. The writer is freed even on failure.
+///
+/// # Safety
+///
+/// `writer` must be a handle returned by [`lance_file_writer_open`] that was
+/// not finished or aborted yet.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_writer_finish(writer: *mut CFileWriter) -> i64 {
+    if writer.is_null() {
+        set_last_error("writer must not be null");
+        return -1;
+    }
+    let mut writer = Box::from_raw(writer);
+    guard(-1, || {
+        // The writer is dropped on the runtime, which aborts an unfinished upload
+        let rows = RT.block_on(async move { writer.writer.finish().await })?;
+        Ok(rows as i64)
+    })
+}
+
+/// Free a writer without finishing the file and delete what was written.
+///
+/// Returns 0, or -1 if the partial file could not be deleted.
+///
+/// # Safety
+///
+/// `writer` must be null or a handle returned by [`lance_file_writer_open`]
+/// that was not finished or aborted yet.
+#[no_mangle]
+pub unsafe extern "C" fn lance_file_writer_abort(writer: *mut CFileWriter) -> c_int {
+    if writer.is_null() {
+        return 0;
+    }
+    let writer = Box::from_raw(writer);
+    guard(-1, || {
+        RT.block_on(async move {
+            let CFileWriter {
+                writer,
+                store,
+                path,
+            } = *writer;
+            // Dropping the writer aborts an upload in progress
+            drop(writer);
+            match store.delete(&path).await {
+                Ok(()) | Err(Error::DatasetNotFound { .. }) => Ok(0),
+                Err(err) => Err(err),
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ffi::{CStr, CString};
+
+    use arrow_array::ffi_stream::ArrowArrayStreamReader;
+    use arrow_array::{Array, Int32Array, StringArray};
+    use arrow_schema::{DataType, Field};
+
+    #[test]
+    fn test_c_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().join("data.lance").to_str().unwrap()).unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..100)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..100).map(|i| i.to_string()),
+                )),
+            ],
+        )
+        .unwrap();
+
+        unsafe {
+            let ffi_schema = FFI_ArrowSchema::try_from(schema.as_ref()).unwrap();
+            let writer = lance_file_writer_open(path.as_ptr(), &ffi_schema);
+            assert!(!writer.is_null());
+            for _ in 0..2 {
+                let data = StructArray::from(batch.clone()).into_data();
+                let mut array = FFI_ArrowArray::new(&data);
+                assert_eq!(lance_file_writer_write(writer, &mut array, &ffi_schema), 0);
+            }
+            assert_eq!(lance_file_writer_finish(writer), 200);
+
+            let reader = lance_file_reader_open(path.as_ptr());
+            assert!(!reader.is_null());
+            assert_eq!(lance_file_reader_num_rows(reader), 200);
+
+            let mut out_schema = FFI_ArrowSchema::empty();
+            assert_eq!(lance_file_reader_schema(reader, &mut out_schema), 0);
+            assert_eq!(ArrowSchema::try_from(&out_schema).unwrap(), *schema);
+
+            let column = CString::new("name").unwrap();
+            let columns = [column.as_ptr()];
+            let mut stream = FFI_ArrowArrayStream::empty();
+            assert_eq!(
+                lance_file_reader_read(reader, 90, 110, columns.as_ptr(), 1, 8, &mut stream),
+                0
+            );
+            lance_file_reader_close(reader);
+
+            let batches = ArrowArrayStreamReader::try_new(stream)
+                .unwrap()
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 20);
+            let names = batches[0]
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            assert_eq!(names.value(0), "90");
+            assert_eq!(batches[0].num_columns(), 1);
+
+            let reader = lance_file_reader_open(path.as_ptr());
+            let mut stream = FFI_ArrowArrayStream::empty();
+            assert_eq!(
+                lance_file_reader_read(reader, 0, 500, std::ptr::null(), 0, 0, &mut stream),
+                -1
+            );
+            let message = CStr::from_ptr(lance_file_last_error());
+            assert!(message.to_str().unwrap().contains("200 rows"));
+            lance_file_reader_close(reader);
+        }
+    }
+
+    #[test]
+    fn test_c_writer_abort() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.lance");
+        let path = CString::new(file.to_str().unwrap()).unwrap();
+        let schema = ArrowSchema::new(vec![Field::new("id", DataType::Int32, false)]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Int32Array::from_iter_values(0..1000))],
+        )
+        .unwrap();
+
+        unsafe {
+            // A file left by an earlier attempt is removed too
+            std::fs::write(&file, b"partial").unwrap();
+            let ffi_schema = FFI_ArrowSchema::try_from(&schema).unwrap();
+            let writer = lance_file_writer_open(path.as_ptr(), &ffi_schema);
+            assert!(!writer.is_null());
+            let data = StructArray::from(batch).into_data();
+            let mut array = FFI_ArrowArray::new(&data);
+            assert_eq!(lance_file_writer_write(writer, &mut array, &ffi_schema), 0);
+            assert_eq!(lance_file_writer_abort(writer), 0);
+            assert!(!file.exists());
+
+            assert_eq!(lance_file_writer_abort(std::ptr::null_mut()), 0);
+        }
+    }
+}
@@ -2,6 +2,8 @@
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
 pub mod datatypes;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod format;
 pub mod page_table;
 pub mod reader;
@@ -24,6 +24,7 @@ use futures::{stream, Future, FutureExt, StreamExt, TryStreamExt};
 use lance_arrow::*;
 use lance_core::cache::LanceCache;
 use lance_core::datatypes::{Field, Schema};
+use lance_core::utils::tokio::spawn_blocking;
 use lance_core::{Error, Result};
 use lance_io::encodings::dictionary::DictionaryDecoder;
 use lance_io::encodings::AsyncIndex;
@@ -298,7 +299,7 @@ impl FileReader {
             return Ok(batches[0].clone());
         }
         let schema = batches[0].schema();
-        Ok(tokio::task::spawn_blocking(move || concat_batches(&schema, &batches)).await??)
+        Ok(spawn_blocking(move || concat_batches(&schema, &batches)).await??)
     }
 
     /// Take by records by indices within the file.
@@ -332,7 +333,7 @@ impl FileReader {
 
         let schema = Arc::new(ArrowSchema::from(projection));
 
-        Ok(tokio::task::spawn_blocking(move || concat_batches(&schema, &batches)).await??)
+        Ok(spawn_blocking(move || concat_batches(&schema, &batches)).await??)
     }
 
     /// Get the schema of the statistics page table, for the given data field ids.
@@ -1,8 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+pub mod checksum;
+pub mod encryption;
 pub(crate) mod io;
 pub mod reader;
+pub mod statistics;
+#[cfg(feature = "fs")]
 pub mod testing;
 pub mod writer;
 
@@ -0,0 +1,427 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Checksums of page and column buffers
+//!
+//! When enabled, the writer records an XXH3-64 checksum of every page buffer
+//! and column buffer in the column metadata.  The checksums of the column
+//! metadata blocks themselves are stored in the schema metadata of the file,
+//! which is written before the column metadata.  The reader removes that entry
+//! from the schema it exposes, so it does not show up as user metadata.
+//!
+//! Readers can verify the checksums to catch bit rot and truncated uploads.
+//! This is optional because a checksum can only be verified against a whole
+//! buffer.  Any read that touches a checksummed buffer is expanded to the whole
+//! buffer, which can be much more I/O than a small random access needs.
+//! Failures are reported as [`Error::CorruptFile`] naming the column, page and
+//! buffer that did not match.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt};
+use lance_core::{Error, Result};
+use lance_encoding::EncodingsIo;
+use object_store::path::Path;
+use snafu::location;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::format::pbfile;
+
+/// The schema metadata entry holding the checksums of the column metadata blocks
+pub(crate) const COLUMN_METADATA_CHECKSUMS_META_KEY: &str = "lance:checksums:column-metadata";
+
+pub(crate) fn checksum(data: &[u8]) -> u64 {
+    xxh3_64(data)
+}
+
+/// Encode the checksums of the column metadata blocks as a schema metadata value
+pub(crate) fn encode_checksums(checksums: &[u64]) -> String {
+    checksums
+        .iter()
+        .map(|checksum| format!("{:016x}", checksum))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn decode_checksums(value: &str) -> Result<Vec<u64>> {
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|checksum| {
+            u64::from_str_radix(checksum, 16).map_err(|_| {
+                Error::invalid_input(
+                    format!("invalid checksum `{}` in file metadata", checksum),
+                    location!(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Verify the column metadata blocks against the checksums recorded in the schema
+///
+/// `expected` is the recorded value and `actual` holds the checksum of each
+/// block as it was read.  Files written without checksums pass.
+pub(crate) fn verify_column_metadata(
+    path: &Path,
+    expected: Option<&str>,
+    actual: &[u64],
+) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let expected = decode_checksums(expected)?;
+    if expected.len() != actual.len() {
+        return Err(Error::corrupt_file(
+            path.clone(),
+            format!(
+                "the file has {} column metadata blocks but checksums for {}",
+                actual.len(),
+                expected.len()
+            ),
+            location!(),
+        ));
+    }
+    for (column_index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        if expected != actual {
+            return Err(Error::corrupt_file(
+                path.clone(),
+                format!(
+                    "checksum mismatch in the metadata of column {}",
+                    column_index
+                ),
+                location!(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A buffer with a recorded checksum
+#[derive(Debug)]
+struct ChecksummedRegion {
+    column_index: u32,
+    /// `None` for column buffers
+    page_index: Option<usize>,
+    buffer_index: usize,
+    range: Range<u64>,
+    checksum: u64,
+}
+
+impl ChecksummedRegion {
+    fn verify(&self, path: &Path, data: &[u8]) -> Result<()> {
+        if checksum(data) == self.checksum {
+            return Ok(());
+        }
+        let location = match self.page_index {
+            Some(page_index) => format!(
+                "column {} page {} buffer {}",
+                self.column_index, page_index, self.buffer_index
+            ),
+            None => format!(
+                "column {} column buffer {}",
+                self.column_index, self.buffer_index
+            ),
+        };
+        Err(Error::corrupt_file(
+            path.clone(),
+            format!(
+                "checksum mismatch in {} (bytes {}..{})",
+                location, self.range.start, self.range.end
+            ),
+            location!(),
+        ))
+    }
+}
+
+/// Verifies page and column buffers as they are read
+#[derive(Debug)]
+pub(crate) struct ChecksumVerifier {
+    path: Path,
+    // Sorted by position
+    regions: Vec<ChecksummedRegion>,
+}
+
+impl ChecksumVerifier {
+    /// Collect the buffer checksums recorded in the column metadata
+    ///
+    /// Returns `None` if the file has no checksums.
+    pub(crate) fn try_new(
+        path: Path,
+        column_metadatas: &[&pbfile::ColumnMetadata],
+    ) -> Result<Option<Self>> {
+        fn add_regions(
+            regions: &mut Vec<ChecksummedRegion>,
+            column_index: u32,
+            page_index: Option<usize>,
+            offsets: &[u64],
+            sizes: &[u64],
+            checksums: &[u64],
+        ) -> Result<()> {
+            if checksums.is_empty() {
+                return Ok(());
+            }
+            if checksums.len() != offsets.len() {
+                return Err(Error::invalid_input(
+                    format!(
+                        "column {} has {} buffers but {} checksums",
+                        column_index,
+                        offsets.len(),
+                        checksums.len()
+                    ),
+                    location!(),
+                ));
+            }
+            for (buffer_index, ((offset, size), checksum)) in
+                offsets.iter().zip(sizes).zip(checksums).enumerate()
+            {
+                if *size > 0 {
+                    regions.push(ChecksummedRegion {
+                        column_index,
+                        page_index,
+                        buffer_index,
+                        range: *offset..offset + size,
+                        checksum: *checksum,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        let mut regions = Vec::new();
+        for (column_index, column) in column_metadatas.iter().enumerate() {
+            let column_index = column_index as u32;
+            for (page_index, page) in column.pages.iter().enumerate() {
+                add_regions(
+                    &mut regions,
+                    column_index,
+                    Some(page_index),
+                    &page.buffer_offsets,
+                    &page.buffer_sizes,
+                    &page.buffer_checksums,
+                )?;
+            }
+            add_regions(
+                &mut regions,
+                column_index,
+                None,
+                &column.buffer_offsets,
+                &column.buffer_sizes,
+                &column.buffer_checksums,
+            )?;
+        }
+        if regions.is_empty() {
+            return Ok(None);
+        }
+        regions.sort_by_key(|region| region.range.start);
+        Ok(Some(Self { path, regions }))
+    }
+
+    /// The indices of the regions that overlap `range`
+    fn overlapping(&self, range: &Range<u64>) -> Range<usize> {
+        let start = self
+            .regions
+            .partition_point(|region| region.range.end <= range.start);
+        let end = self
+            .regions
+            .partition_point(|region| region.range.start < range.end);
+        start..end.max(start)
+    }
+}
+
+/// Wraps an [`EncodingsIo`] to verify the checksums of the buffers it reads
+#[derive(Debug)]
+pub(crate) struct VerifyingIo {
+    inner: Arc<dyn EncodingsIo>,
+    verifier: Arc<ChecksumVerifier>,
+}
+
+impl VerifyingIo {
+    pub(crate) fn new(inner: Arc<dyn EncodingsIo>, verifier: Arc<ChecksumVerifier>) -> Self {
+        Self { inner, verifier }
+    }
+}
+
+impl EncodingsIo for VerifyingIo {
+    fn submit_request(
+        &self,
+        ranges: Vec<Range<u64>>,
+        priority: u64,
+    ) -> BoxFuture<'static, Result<Vec<Bytes>>> {
+        // Expand each read to cover every buffer it touches
+        let mut physical_ranges = Vec::with_capacity(ranges.len());
+        let mut requests = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let regions = self.verifier.overlapping(&range);
+            let physical_range = if regions.is_empty() || range.is_empty() {
+                range.clone()
+            } else {
+                let first = &self.verifier.regions[regions.start];
+                let last = &self.verifier.regions[regions.end - 1];
+                first.range.start.min(range.start)..last.range.end.max(range.end)
+            };
+            physical_ranges.push(physical_range.clone());
+            requests.push((range, physical_range, regions));
+        }
+
+        let data = self.inner.submit_request(physical_ranges, priority);
+        let verifier = self.verifier.clone();
+        async move {
+            let data = data.await?;
+            data.into_iter()
+                .zip(requests)
+                .map(|(bytes, (range, physical_range, regions))| {
+                    if bytes.len() as u64 != physical_range.end - physical_range.start {
+                        return Err(Error::corrupt_file(
+                            verifier.path.clone(),
+                            format!(
+                                "expected {} bytes at {} but read {}, the file may be truncated",
+                                physical_range.end - physical_range.start,
+                                physical_range.start,
+                                bytes.len()
+                            ),
+                            location!(),
+                        ));
+                    }
+                    if range.is_empty() {
+                        return Ok(bytes);
+                    }
+                    let relative = |pos: u64| (pos - physical_range.start) as usize;
+                    for region in &verifier.regions[regions] {
+                        region.verify(
+                            &verifier.path,
+                            &bytes[relative(region.range.start)..relative(region.range.end)],
+                        )?;
+                    }
+                    Ok(bytes.slice(relative(range.start)..relative(range.end)))
+                })
+                .collect()
+        }
+        .boxed()
+    }
+
+    fn record_pages_decoded(&self, num_pages: u64) {
+        self.inner.record_pages_decoded(num_pages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator, StringArray};
+    use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+    use futures::TryStreamExt;
+    use lance_encoding::decoder::{DecoderPlugins, FilterExpression};
+    use lance_io::{utils::CachedFileSize, ReadBatchParams};
+
+    use super::*;
+    use crate::v2::reader::{FileReader, FileReaderOptions};
+    use crate::v2::testing::{test_cache, write_lance_file, FsFixture};
+    use crate::v2::writer::FileWriterOptions;
+
+    async fn read_file(fs: &FsFixture, verify_checksums: bool) -> Result<Vec<RecordBatch>> {
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await?;
+        let reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default().with_verify_checksums(verify_checksums),
+        )
+        .await?;
+        reader
+            .read_stream(
+                ReadBatchParams::RangeFull,
+                1024,
+                16,
+                FilterExpression::no_filter(),
+            )?
+            .try_collect()
+            .await
+    }
+
+    #[test]
+    fn test_encode_checksums() {
+        for checksums in [vec![], vec![0], vec![1, u64::MAX, 0xabcdef]] {
+            assert_eq!(
+                decode_checksums(&encode_checksums(&checksums)).unwrap(),
+                checksums
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checksum_verification() {
+        let fs = FsFixture::default();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("text", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..1000).map(|i| format!("needle-{:04}", i)),
+                )),
+            ],
+        )
+        .unwrap();
+        let data = RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+        let options = FileWriterOptions {
+            checksums: Some(true),
+            ..Default::default()
+        };
+        write_lance_file(data, &fs, options).await;
+
+        let batches = read_file(&fs, true).await.unwrap();
+        assert_eq!(batches[0].columns(), batch.columns());
+        // The checksums of the column metadata are not user metadata
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(reader.metadata().column_metadata_checksums.is_some());
+        assert!(!reader
+            .schema()
+            .metadata
+            .contains_key(COLUMN_METADATA_CHECKSUMS_META_KEY));
+
+        // Flip a bit in the string data
+        let path = fs.tmp_path.to_string();
+        let mut file_bytes = std::fs::read(&path).unwrap();
+        let pos = file_bytes
+            .windows(b"needle-0500".len())
+            .position(|w| w == b"needle-0500")
+            .unwrap();
+        file_bytes[pos] ^= 1;
+        std::fs::write(&path, &file_bytes).unwrap();
+
+        let err = read_file(&fs, true).await.unwrap_err();
+        assert!(matches!(err, Error::CorruptFile { .. }), "{}", err);
+        assert!(
+            err.to_string()
+                .contains("checksum mismatch in column 1 page 0"),
+            "{}",
+            err
+        );
+        // Without verification the corruption goes unnoticed
+        read_file(&fs, false).await.unwrap();
+    }
+}
@@ -0,0 +1,632 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Column-level encryption
+//!
+//! Selected columns can be encrypted when a file is written.  Every encrypted
+//! column gets a fresh 256-bit data key.  The data key is wrapped (encrypted) by
+//! a user-provided [`KeyManagementService`] and the wrapped key is stored, along
+//! with the id of the master key, in the schema metadata of the file.
+//!
+//! Each page buffer (and column buffer) of an encrypted column is sealed with
+//! AES-256-GCM and stored as `ciphertext | tag | nonce`.  The column metadata
+//! records the position and size of the ciphertext, which is the same size as
+//! the plaintext.  This means the encodings can compute byte ranges exactly as
+//! they would for an unencrypted file.  The reader expands any request that
+//! touches an encrypted buffer to the whole sealed buffer, decrypts it, and
+//! then slices out the requested range.
+//!
+//! The position of the buffer is used as associated data so that sealed buffers
+//! cannot be moved around within a file.
+//!
+//! Readers without access to the master key can still read the columns that
+//! are not encrypted.  Reading an encrypted column fails in that case.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt};
+use lance_core::datatypes::{Field, Schema, StorageClass, BLOB_META_KEY};
+use lance_core::{Error, Result};
+use lance_encoding::EncodingsIo;
+pub use lance_io::object_store::kms::KeyManagementService;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use snafu::location;
+
+use crate::v2::reader::ColumnMetadatas;
+
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+
+/// The number of bytes an encrypted buffer takes up beyond its plaintext size
+pub(crate) const ENCRYPTION_OVERHEAD: u64 = (TAG_LEN + NONCE_LEN) as u64;
+
+const KEY_ID_META_PREFIX: &str = "lance-encryption:key-id:";
+const WRAPPED_KEY_META_PREFIX: &str = "lance-encryption:wrapped-key:";
+
+/// Which columns to encrypt when writing a file
+#[derive(Debug, Clone)]
+pub struct ColumnEncryptionOptions {
+    /// The service used to wrap the data keys
+    pub key_management: Arc<dyn KeyManagementService>,
+    /// Map from top-level column name to the id of the master key that should
+    /// protect it.  Nested fields are encrypted along with their parent.
+    pub columns: HashMap<String, String>,
+}
+
+impl ColumnEncryptionOptions {
+    pub fn new(key_management: Arc<dyn KeyManagementService>) -> Self {
+        Self {
+            key_management,
+            columns: HashMap::new(),
+        }
+    }
+
+    /// Encrypt the column `name` with a data key wrapped by the master key `key_id`
+    pub fn with_column(mut self, name: impl Into<String>, key_id: impl Into<String>) -> Self {
+        self.columns.insert(name.into(), key_id.into());
+        self
+    }
+}
+
+/// A data key used to seal the buffers of one or more columns
+pub(crate) struct DataKey {
+    key_id: String,
+    raw: [u8; KEY_LEN],
+    key: LessSafeKey,
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the key material
+        f.debug_struct("DataKey")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+fn encryption_error(message: impl Into<String>) -> Error {
+    Error::Encryption {
+        message: message.into(),
+        location: location!(),
+    }
+}
+
+impl DataKey {
+    fn try_new(key_id: String, raw: [u8; KEY_LEN]) -> Result<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, &raw)
+            .map_err(|_| encryption_error("invalid data key"))?;
+        Ok(Self {
+            key_id,
+            raw,
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// Generate a new random data key
+    pub(crate) fn generate(key_id: String) -> Result<Self> {
+        let mut raw = [0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut raw)
+            .map_err(|_| encryption_error("failed to generate a data key"))?;
+        Self::try_new(key_id, raw)
+    }
+
+    pub(crate) fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub(crate) async fn wrap(&self, kms: &dyn KeyManagementService) -> Result<Vec<u8>> {
+        kms.wrap_key(&self.key_id, &self.raw).await
+    }
+
+    async fn unwrap(kms: &dyn KeyManagementService, key_id: &str, wrapped: &[u8]) -> Result<Self> {
+        let raw = kms.unwrap_key(key_id, wrapped).await?;
+        let raw = <[u8; KEY_LEN]>::try_from(raw.as_slice()).map_err(|_| {
+            encryption_error(format!(
+                "the key management service returned a data key of {} bytes, expected {}",
+                raw.len(),
+                KEY_LEN
+            ))
+        })?;
+        Self::try_new(key_id.to_string(), raw)
+    }
+
+    /// Seal a buffer that will be written at `position` in the file
+    ///
+    /// Returns `ciphertext | tag | nonce`
+    pub(crate) fn encrypt(&self, plaintext: &[u8], position: u64) -> Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| encryption_error("failed to generate a nonce"))?;
+        let mut sealed = Vec::with_capacity(plaintext.len() + ENCRYPTION_OVERHEAD as usize);
+        sealed.extend_from_slice(plaintext);
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(position.to_le_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| encryption_error("failed to encrypt buffer"))?;
+        sealed.extend_from_slice(&nonce);
+        Ok(sealed)
+    }
+
+    /// Open a buffer sealed by [`Self::encrypt`] that was read from `position`
+    fn decrypt(&self, sealed: &[u8], position: u64) -> Result<Bytes> {
+        if sealed.len() < ENCRYPTION_OVERHEAD as usize {
+            return Err(encryption_error(format!(
+                "encrypted buffer at {} is truncated",
+                position
+            )));
+        }
+        let (ciphertext, nonce) = sealed.split_at(sealed.len() - NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| encryption_error("invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = self
+            .key
+            .open_in_place(nonce, Aad::from(position.to_le_bytes()), &mut in_out)
+            .map_err(|_| {
+                encryption_error(format!(
+                    "failed to decrypt buffer at {}, the file may be corrupt",
+                    position
+                ))
+            })?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(Bytes::from(in_out))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        write!(s, "{:02x}", b).unwrap();
+        s
+    })
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    // Slicing a string with multi-byte characters could split one of them
+    if !s.is_ascii() || s.len() % 2 != 0 {
+        return Err(encryption_error("invalid wrapped key in file metadata"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16)
+                .map_err(|_| encryption_error("invalid wrapped key in file metadata"))
+        })
+        .collect()
+}
+
+/// The schema metadata entries that describe the key of an encrypted column
+pub(crate) fn key_metadata(
+    column_index: u32,
+    key_id: &str,
+    wrapped_key: &[u8],
+) -> [(String, String); 2] {
+    [
+        (
+            format!("{}{}", KEY_ID_META_PREFIX, column_index),
+            key_id.to_string(),
+        ),
+        (
+            format!("{}{}", WRAPPED_KEY_META_PREFIX, column_index),
+            to_hex(wrapped_key),
+        ),
+    ]
+}
+
+/// Assign a data key to every column of the fields selected for encryption
+///
+/// Returns one entry per data key along with the indices of the columns it
+/// protects.
+pub(crate) fn assign_column_keys(
+    schema: &Schema,
+    options: &ColumnEncryptionOptions,
+    field_id_to_column_indices: &[(u32, u32)],
+) -> Result<Vec<(Arc<DataKey>, Vec<u32>)>> {
+    fn collect_ids(field: &Field, ids: &mut Vec<u32>) {
+        ids.push(field.id as u32);
+        for child in &field.children {
+            collect_ids(child, ids);
+        }
+    }
+
+    let mut keys = Vec::with_capacity(options.columns.len());
+    for (name, key_id) in &options.columns {
+        let field = schema.field(name).ok_or_else(|| {
+            Error::invalid_input(
+                format!("Cannot encrypt column `{}`, it is not in the schema", name),
+                location!(),
+            )
+        })?;
+        if field.storage_class() == StorageClass::Blob || field.metadata.contains_key(BLOB_META_KEY)
+        {
+            return Err(Error::invalid_input(
+                format!("Cannot encrypt blob column `{}`", name),
+                location!(),
+            ));
+        }
+        let mut field_ids = Vec::new();
+        collect_ids(field, &mut field_ids);
+        let columns = field_id_to_column_indices
+            .iter()
+            .filter(|(field_id, _)| field_ids.contains(field_id))
+            .map(|(_, column_index)| *column_index)
+            .collect();
+        keys.push((Arc::new(DataKey::generate(key_id.clone())?), columns));
+    }
+    Ok(keys)
+}
+
+/// A buffer of an encrypted column
+#[derive(Debug)]
+struct EncryptedRegion {
+    column_index: u32,
+    /// The position and size of the ciphertext
+    range: Range<u64>,
+    /// The data key, or why it could not be obtained
+    key: Arc<std::result::Result<DataKey, String>>,
+}
+
+impl EncryptedRegion {
+    fn sealed_range(&self) -> Range<u64> {
+        self.range.start..self.range.end + ENCRYPTION_OVERHEAD
+    }
+}
+
+/// Decrypts the buffers of encrypted columns as they are read
+#[derive(Debug)]
+pub(crate) struct ColumnDecryptor {
+    // Sorted by position
+    regions: Vec<EncryptedRegion>,
+}
+
+impl ColumnDecryptor {
+    /// Load the data keys described in the schema metadata of a file
+    ///
+    /// Returns `None` if the file has no encrypted columns.  Keys that can't be
+    /// unwrapped (for example, because the caller is not authorized) are not an
+    /// error here.  Reading from a column with such a key fails instead.
+    pub(crate) async fn try_new(
+        schema: &Schema,
+        column_metadatas: &ColumnMetadatas,
+        kms: Option<&Arc<dyn KeyManagementService>>,
+    ) -> Result<Option<Self>> {
+        let mut regions = Vec::new();
+        for (meta_key, key_id) in &schema.metadata {
+            let Some(column_index) = meta_key.strip_prefix(KEY_ID_META_PREFIX) else {
+                continue;
+            };
+            let column_index = column_index.parse::<u32>().map_err(|_| {
+                encryption_error(format!("invalid encryption metadata key {}", meta_key))
+            })?;
+            let wrapped_key = schema
+                .metadata
+                .get(&format!("{}{}", WRAPPED_KEY_META_PREFIX, column_index))
+                .ok_or_else(|| {
+                    encryption_error(format!(
+                        "missing wrapped key for encrypted column {}",
+                        column_index
+                    ))
+                })?;
+            let wrapped_key = from_hex(wrapped_key)?;
+            let key = match kms {
+                Some(kms) => DataKey::unwrap(kms.as_ref(), key_id, &wrapped_key)
+                    .await
+                    .map_err(|err| err.to_string()),
+                None => Err("no key management service was provided".to_string()),
+            };
+            let key = Arc::new(key);
+
+            // Only the metadata of the encrypted columns is decoded
+            if column_index as usize >= column_metadatas.len() {
+                return Err(encryption_error(format!(
+                    "encrypted column {} does not exist",
+                    column_index
+                )));
+            }
+            let column_info = column_metadatas.info(column_index)?;
+            let buffers = column_info
+                .page_infos
+                .iter()
+                .flat_map(|page| page.buffer_offsets_and_sizes.iter())
+                .chain(column_info.buffer_offsets_and_sizes.iter());
+            for (offset, size) in buffers {
+                regions.push(EncryptedRegion {
+                    column_index,
+                    range: *offset..(offset + size),
+                    key: key.clone(),
+                });
+            }
+        }
+        if regions.is_empty() {
+            return Ok(None);
+        }
+        regions.sort_by_key(|region| region.range.start);
+        Ok(Some(Self { regions }))
+    }
+
+    /// Find the encrypted buffer that contains `range`, if any
+    fn region_for(&self, range: &Range<u64>) -> Result<Option<&EncryptedRegion>> {
+        let idx = self
+            .regions
+            .partition_point(|region| region.range.start <= range.start);
+        if idx > 0 {
+            let region = &self.regions[idx - 1];
+            if range.start < region.range.end {
+                if range.end > region.range.end {
+                    return Err(encryption_error(format!(
+                        "read of {:?} spans past the end of an encrypted buffer",
+                        range
+                    )));
+                }
+                return Ok(Some(region));
+            }
+        }
+        if let Some(next) = self.regions.get(idx) {
+            if range.end > next.range.start {
+                return Err(encryption_error(format!(
+                    "read of {:?} spans into an encrypted buffer",
+                    range
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Wraps an [`EncodingsIo`] to decrypt the buffers of encrypted columns
+#[derive(Debug)]
+pub(crate) struct DecryptingIo {
+    inner: Arc<dyn EncodingsIo>,
+    decryptor: Arc<ColumnDecryptor>,
+}
+
+impl DecryptingIo {
+    pub(crate) fn new(inner: Arc<dyn EncodingsIo>, decryptor: Arc<ColumnDecryptor>) -> Self {
+        Self { inner, decryptor }
+    }
+}
+
+impl EncodingsIo for DecryptingIo {
+    fn submit_request(
+        &self,
+        ranges: Vec<Range<u64>>,
+        priority: u64,
+    ) -> BoxFuture<'static, Result<Vec<Bytes>>> {
+        let mut physical_ranges = Vec::with_capacity(ranges.len());
+        let mut requests = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match self.decryptor.region_for(&range) {
+                Ok(Some(region)) => {
+                    if let Err(message) = region.key.as_ref() {
+                        let err = encryption_error(format!(
+                            "column {} is encrypted and cannot be read: {}",
+                            region.column_index, message
+                        ));
+                        return futures::future::ready(Err(err)).boxed();
+                    }
+                    physical_ranges.push(region.sealed_range());
+                    requests.push((range, Some((region.range.start, region.key.clone()))));
+                }
+                Ok(None) => {
+                    physical_ranges.push(range.clone());
+                    requests.push((range, None));
+                }
+                Err(err) => return futures::future::ready(Err(err)).boxed(),
+            }
+        }
+
+        let data = self.inner.submit_request(physical_ranges, priority);
+        async move {
+            let data = data.await?;
+            data.into_iter()
+                .zip(requests)
+                .map(|(bytes, (range, region))| match region {
+                    None => Ok(bytes),
+                    Some((start, key)) => {
+                        let Ok(key) = key.as_ref() else {
+                            unreachable!("missing keys are rejected before the read")
+                        };
+                        let plaintext = key.decrypt(&bytes, start)?;
+                        Ok(plaintext
+                            .slice((range.start - start) as usize..(range.end - start) as usize))
+                    }
+                })
+                .collect()
+        }
+        .boxed()
+    }
+
+    fn record_pages_decoded(&self, num_pages: u64) {
+        self.inner.record_pages_decoded(num_pages);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator, StringArray};
+    use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+    use futures::TryStreamExt;
+    use lance_encoding::decoder::{DecoderPlugins, FilterExpression};
+    use lance_io::{utils::CachedFileSize, ReadBatchParams};
+
+    use super::*;
+    use crate::v2::reader::{FileReader, FileReaderOptions, ReaderProjection};
+    use crate::v2::testing::{test_cache, write_lance_file, FsFixture};
+    use crate::v2::writer::FileWriterOptions;
+
+    /// A toy key management service that "wraps" keys by XOR-ing them with a
+    /// per-key-id mask
+    #[derive(Debug, Default)]
+    pub(crate) struct XorKms {
+        denied: Mutex<Vec<String>>,
+    }
+
+    impl XorKms {
+        pub(crate) fn deny(&self, key_id: &str) {
+            self.denied.lock().unwrap().push(key_id.to_string());
+        }
+
+        fn mask(key_id: &str, data: &[u8]) -> Vec<u8> {
+            let mask = key_id.as_bytes();
+            data.iter()
+                .enumerate()
+                .map(|(i, b)| b ^ mask[i % mask.len()])
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl KeyManagementService for XorKms {
+        async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>> {
+            Ok(Self::mask(key_id, data_key))
+        }
+
+        async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>> {
+            if self.denied.lock().unwrap().iter().any(|id| id == key_id) {
+                return Err(Error::Encryption {
+                    message: format!("access to {} denied", key_id),
+                    location: location!(),
+                });
+            }
+            Ok(Self::mask(key_id, wrapped_key))
+        }
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = DataKey::generate("k".to_string()).unwrap();
+        let plaintext = (0..100u8).collect::<Vec<_>>();
+        let sealed = key.encrypt(&plaintext, 64).unwrap();
+        assert_eq!(sealed.len() as u64, 100 + ENCRYPTION_OVERHEAD);
+        assert_ne!(&sealed[..100], plaintext.as_slice());
+        assert_eq!(key.decrypt(&sealed, 64).unwrap().as_ref(), plaintext);
+
+        // The position is authenticated
+        assert!(key.decrypt(&sealed, 128).is_err());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0, 1, 127, 128, 255];
+        assert_eq!(to_hex(&bytes), "00017f80ff");
+        assert_eq!(from_hex(&to_hex(&bytes)).unwrap(), bytes);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("aéb").is_err());
+    }
+
+    async fn open_reader(fs: &FsFixture, options: FileReaderOptions) -> FileReader {
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            options,
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn read_all(
+        reader: &FileReader,
+        projection: ReaderProjection,
+    ) -> Result<Vec<RecordBatch>> {
+        reader
+            .read_stream_projected(
+                ReadBatchParams::RangeFull,
+                1024,
+                16,
+                projection,
+                FilterExpression::no_filter(),
+            )?
+            .try_collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_column_round_trip() {
+        let fs = FsFixture::default();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("secret", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..1000).map(|i| format!("top-secret-{}", i)),
+                )),
+            ],
+        )
+        .unwrap();
+        let data = RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+
+        let kms = Arc::new(XorKms::default());
+        let options = FileWriterOptions {
+            encryption: Some(
+                ColumnEncryptionOptions::new(kms.clone()).with_column("secret", "key-1"),
+            ),
+            ..Default::default()
+        };
+        let written = write_lance_file(data, &fs, options).await;
+
+        // The plaintext never reaches the disk
+        let file_bytes = std::fs::read(fs.tmp_path.to_string()).unwrap();
+        assert!(!file_bytes
+            .windows(b"top-secret".len())
+            .any(|w| w == b"top-secret"));
+
+        // Authorized readers see the data
+        let reader = open_reader(
+            &fs,
+            FileReaderOptions::default().with_key_management(kms.clone()),
+        )
+        .await;
+        assert_eq!(
+            reader
+                .schema()
+                .metadata
+                .get("lance-encryption:key-id:1")
+                .unwrap(),
+            "key-1"
+        );
+        let version = reader.metadata().version();
+        let all = ReaderProjection::from_whole_schema(&written.schema, version);
+        let batches = read_all(&reader, all.clone()).await.unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].columns(), batch.columns());
+
+        // Unauthorized readers can only read the unencrypted columns
+        kms.deny("key-1");
+        for options in [
+            FileReaderOptions::default(),
+            FileReaderOptions::default().with_key_management(kms.clone()),
+        ] {
+            let reader = open_reader(&fs, options).await;
+            let err = read_all(&reader, all.clone()).await.unwrap_err();
+            assert!(err.to_string().contains("is encrypted"), "{}", err);
+
+            let id_only =
+                ReaderProjection::from_column_names(version, &written.schema, &["id"]).unwrap();
+            let batches = read_all(&reader, id_only).await.unwrap();
+            assert_eq!(batches[0].column(0), batch.column(0));
+        }
+    }
+}
@@ -16,4 +16,8 @@ impl EncodingsIo for LanceEncodingsIo {
     ) -> BoxFuture<'static, lance_core::Result<Vec<bytes::Bytes>>> {
         self.0.submit_request(range, priority).boxed()
     }
+
+    fn record_pages_decoded(&self, num_pages: u64) {
+        self.0.record_pages_decoded(num_pages);
+    }
 }
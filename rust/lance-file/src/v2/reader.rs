@@ -6,15 +6,16 @@ use std::{
     io::Cursor,
     ops::Range,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, OnceLock},
 };
 
-use arrow_array::RecordBatchReader;
-use arrow_schema::Schema as ArrowSchema;
+use arrow_array::{RecordBatch, RecordBatchReader, UInt32Array};
+use arrow_schema::{DataType, Schema as ArrowSchema};
+use arrow_select::{concat::concat_batches, take::take_record_batch};
 use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
 use bytes::{Bytes, BytesMut};
 use deepsize::{Context, DeepSizeOf};
-use futures::{stream::BoxStream, Stream, StreamExt};
+use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use lance_encoding::{
     decoder::{
         schedule_and_decode, schedule_and_decode_blocking, ColumnInfo, DecoderPlugins,
@@ -32,7 +33,7 @@ use snafu::location;
 
 use lance_core::{
     cache::LanceCache,
-    datatypes::{Field, Schema},
+    datatypes::{Field, LogicalType, Schema, BLOB_META_KEY},
     Error, Result,
 };
 use lance_encoding::format::pb as pbenc;
@@ -48,7 +49,10 @@ use crate::{
     v2::writer::PAGE_BUFFER_ALIGNMENT,
 };
 
+use super::checksum::{self, ChecksumVerifier, VerifyingIo};
+use super::encryption::{ColumnDecryptor, DecryptingIo, KeyManagementService};
 use super::io::LanceEncodingsIo;
+use super::statistics::{statistics_buffer_index, ChunkStatistics};
 
 // For now, we don't use global buffers for anything other than schema.  If we
 // use these later we should make them lazily loaded and then cached once loaded.
@@ -78,14 +82,148 @@ pub struct ColumnStatistics {
     pub size_bytes: u64,
 }
 
-// TODO: Caching
+/// The metadata of the columns of a file, decoded on first use
+///
+/// Files with wide schemas can have thousands of columns.  Decoding the metadata of every
+/// column when the file is opened makes opening the file O(total columns), even when only
+/// a handful of columns are read.  Instead, the raw metadata blocks are kept and the
+/// metadata of a column is decoded the first time the column is needed.
+///
+/// The column metadata section is still read as a whole when the file is opened, which is
+/// a single request however many columns there are.
+#[derive(Debug)]
+pub struct ColumnMetadatas {
+    /// The column metadata section of the file
+    bytes: Bytes,
+    /// The range of the metadata block of each column within `bytes`
+    blocks: Vec<Range<usize>>,
+    version: LanceFileVersion,
+    decoded: Vec<OnceLock<DecodedColumn>>,
+}
+
+#[derive(Debug)]
+struct DecodedColumn {
+    metadata: pbfile::ColumnMetadata,
+    info: Arc<ColumnInfo>,
+}
+
+impl DecodedColumn {
+    /// An estimate of the memory used by the decoded column
+    fn estimated_size(&self) -> usize {
+        // The metadata and the column info both hold the encodings and the buffer
+        // positions, which take about as much memory as they take encoded
+        2 * self.metadata.encoded_len()
+            + self.info.page_infos.len() * std::mem::size_of::<PageInfo>()
+            + std::mem::size_of::<ColumnInfo>()
+    }
+}
+
+impl ColumnMetadatas {
+    fn new(bytes: Bytes, footer: &Footer, version: LanceFileVersion) -> Self {
+        let blocks = FileReader::column_metadata_blocks(&bytes, footer);
+        let decoded = blocks.iter().map(|_| OnceLock::new()).collect();
+        Self {
+            bytes,
+            blocks,
+            version,
+            decoded,
+        }
+    }
+
+    /// The number of columns in the file
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    fn decode(&self, column_index: u32) -> Result<&DecodedColumn> {
+        let decoded = self.decoded.get(column_index as usize).ok_or_else(|| {
+            Error::invalid_input(
+                format!(
+                    "the column index {} is out of bounds, the file has {} columns",
+                    column_index,
+                    self.len()
+                ),
+                location!(),
+            )
+        })?;
+        if let Some(column) = decoded.get() {
+            return Ok(column);
+        }
+        let block = &self.bytes[self.blocks[column_index as usize].clone()];
+        let metadata = pbfile::ColumnMetadata::decode(block)?;
+        let info = FileReader::meta_to_col_info(column_index, &metadata, self.version);
+        // Another thread may have decoded the column in the meantime, either result is fine
+        let _ = decoded.set(DecodedColumn { metadata, info });
+        Ok(decoded.get().unwrap())
+    }
+
+    /// The metadata of the column at `column_index`
+    pub fn metadata(&self, column_index: u32) -> Result<&pbfile::ColumnMetadata> {
+        Ok(&self.decode(column_index)?.metadata)
+    }
+
+    /// The column info of the column at `column_index`, as needed by the decoder
+    pub fn info(&self, column_index: u32) -> Result<Arc<ColumnInfo>> {
+        Ok(self.decode(column_index)?.info.clone())
+    }
+
+    /// Decodes the metadata of all columns
+    pub fn all_metadata(&self) -> Result<Vec<&pbfile::ColumnMetadata>> {
+        (0..self.len() as u32)
+            .map(|column_index| self.metadata(column_index))
+            .collect()
+    }
+
+    /// Decodes the column infos of all columns
+    pub fn all_infos(&self) -> Result<Vec<Arc<ColumnInfo>>> {
+        (0..self.len() as u32)
+            .map(|column_index| self.info(column_index))
+            .collect()
+    }
+
+    #[cfg(test)]
+    fn num_decoded(&self) -> usize {
+        self.decoded
+            .iter()
+            .filter(|column| column.get().is_some())
+            .count()
+    }
+
+    /// The checksum of each column metadata block, as read
+    pub fn checksums(&self) -> Vec<u64> {
+        self.blocks
+            .iter()
+            .map(|block| checksum::checksum(&self.bytes[block.clone()]))
+            .collect()
+    }
+}
+
+impl DeepSizeOf for ColumnMetadatas {
+    // Grows as columns are decoded
+    fn deep_size_of_children(&self, _context: &mut Context) -> usize {
+        let decoded_size = self
+            .decoded
+            .iter()
+            .filter_map(OnceLock::get)
+            .map(DecodedColumn::estimated_size)
+            .sum::<usize>();
+        self.bytes.len()
+            + self.blocks.capacity() * std::mem::size_of::<Range<usize>>()
+            + self.decoded.capacity() * std::mem::size_of::<OnceLock<DecodedColumn>>()
+            + decoded_size
+    }
+}
+
 #[derive(Debug)]
 pub struct CachedFileMetadata {
     /// The schema of the file
     pub file_schema: Arc<Schema>,
-    /// The column metadatas
-    pub column_metadatas: Vec<pbfile::ColumnMetadata>,
-    pub column_infos: Vec<Arc<ColumnInfo>>,
+    /// The column metadatas, decoded as the columns are read
+    pub column_metadatas: ColumnMetadatas,
     /// The number of rows in the file
     pub num_rows: u64,
     pub file_buffers: Vec<BufferDescriptor>,
@@ -100,12 +238,20 @@ pub struct CachedFileMetadata {
     pub num_footer_bytes: u64,
     pub major_version: u16,
     pub minor_version: u16,
+    /// The recorded checksums of the column metadata blocks, if any
+    ///
+    /// They are written in the schema metadata and removed from
+    /// [`Self::file_schema`] when the file is opened.
+    pub(crate) column_metadata_checksums: Option<String>,
 }
 
 impl DeepSizeOf for CachedFileMetadata {
-    // TODO: include size for `column_metadatas` and `column_infos`.
     fn deep_size_of_children(&self, context: &mut Context) -> usize {
         self.file_schema.deep_size_of_children(context)
+            + self.column_metadatas.deep_size_of_children(context)
+            + self
+                .column_metadata_checksums
+                .deep_size_of_children(context)
             + self
                 .file_buffers
                 .iter()
@@ -311,9 +457,51 @@ impl ReaderProjection {
     }
 }
 
+const ENV_LANCE_FILE_VERIFY_CHECKSUMS: &str = "LANCE_FILE_VERIFY_CHECKSUMS";
+
 #[derive(Clone, Debug, Default)]
 pub struct FileReaderOptions {
     validate_on_decode: bool,
+    key_management: Option<Arc<dyn KeyManagementService>>,
+    verify_checksums: Option<bool>,
+    use_view_types: bool,
+}
+
+impl FileReaderOptions {
+    /// Verify the checksums of the buffers that are read
+    ///
+    /// Files written without checksums are read as usual.  Verification expands
+    /// every read to the whole buffer, see [`super::checksum`].  Defaults to the
+    /// `LANCE_FILE_VERIFY_CHECKSUMS` environment variable, or false.
+    pub fn with_verify_checksums(mut self, verify_checksums: bool) -> Self {
+        self.verify_checksums = Some(verify_checksums);
+        self
+    }
+
+    fn verify_checksums(&self) -> bool {
+        self.verify_checksums.unwrap_or_else(|| {
+            std::env::var(ENV_LANCE_FILE_VERIFY_CHECKSUMS)
+                .map(|val| val == "1" || val.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Read string and binary columns as view arrays (`Utf8View` / `BinaryView`)
+    ///
+    /// The views point into the decoded values, which is cheaper than converting the
+    /// batches afterwards.  Only supported for files with version 2.1 or later.
+    pub fn with_view_types(mut self, use_view_types: bool) -> Self {
+        self.use_view_types = use_view_types;
+        self
+    }
+
+    /// The service used to unwrap the data keys of encrypted columns
+    ///
+    /// Without one, encrypted columns cannot be read (other columns can).
+    pub fn with_key_management(mut self, key_management: Arc<dyn KeyManagementService>) -> Self {
+        self.key_management = Some(key_management);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -326,6 +514,8 @@ pub struct FileReader {
     decoder_plugins: Arc<DecoderPlugins>,
     cache: Arc<LanceCache>,
     options: FileReaderOptions,
+    decryptor: Option<Arc<ColumnDecryptor>>,
+    verifier: Option<Arc<ChecksumVerifier>>,
 }
 #[derive(Debug)]
 struct Footer {
@@ -347,13 +537,31 @@ const FOOTER_LEN: usize = 40;
 impl FileReader {
     pub fn with_scheduler(&self, scheduler: Arc<dyn EncodingsIo>) -> Self {
         Self {
-            scheduler,
+            scheduler: Self::wrap_io(scheduler, self.decryptor.as_ref(), self.verifier.as_ref()),
             base_projection: self.base_projection.clone(),
             cache: self.cache.clone(),
             decoder_plugins: self.decoder_plugins.clone(),
             metadata: self.metadata.clone(),
             options: self.options.clone(),
             num_rows: self.num_rows,
+            decryptor: self.decryptor.clone(),
+            verifier: self.verifier.clone(),
+        }
+    }
+
+    fn wrap_io(
+        io: Arc<dyn EncodingsIo>,
+        decryptor: Option<&Arc<ColumnDecryptor>>,
+        verifier: Option<&Arc<ChecksumVerifier>>,
+    ) -> Arc<dyn EncodingsIo> {
+        let io: Arc<dyn EncodingsIo> = match decryptor {
+            Some(decryptor) => Arc::new(DecryptingIo::new(io, decryptor.clone())),
+            None => io,
+        };
+        // Checksums are of the plaintext, so verify after decrypting
+        match verifier {
+            Some(verifier) => Arc::new(VerifyingIo::new(io, verifier.clone())),
+            None => io,
         }
     }
 
@@ -365,11 +573,14 @@ impl FileReader {
         &self.metadata
     }
 
-    pub fn file_statistics(&self) -> FileStatistics {
-        let column_metadatas = &self.metadata().column_metadatas;
+    /// Summarizes the metadata of every column
+    ///
+    /// This decodes the metadata of all columns in the file
+    pub fn file_statistics(&self) -> Result<FileStatistics> {
+        let column_metadatas = self.metadata().column_metadatas.all_metadata()?;
 
         let column_stats = column_metadatas
-            .iter()
+            .into_iter()
             .map(|col_metadata| {
                 let num_pages = col_metadata.pages.len();
                 let size_bytes = col_metadata
@@ -384,9 +595,9 @@ impl FileReader {
             })
             .collect();
 
-        FileStatistics {
+        Ok(FileStatistics {
             columns: column_stats,
-        }
+        })
     }
 
     pub async fn read_global_buffer(&self, index: u32) -> Result<Bytes> {
@@ -399,17 +610,43 @@ impl FileReader {
             .await
     }
 
-    async fn read_tail(scheduler: &FileScheduler) -> Result<(Bytes, u64)> {
+    /// Reads the statistics of the row chunks of the file
+    ///
+    /// Returns `None` if the file was written without statistics.  See
+    /// [`super::statistics`] for details.
+    pub async fn read_statistics(&self) -> Result<Option<ChunkStatistics>> {
+        let Some(index) = statistics_buffer_index(&self.metadata.file_schema)? else {
+            return Ok(None);
+        };
+        let bytes = self.read_global_buffer(index).await?;
+        ChunkStatistics::decode(bytes).await.map(Some)
+    }
+
+    async fn tail_range(scheduler: &FileScheduler) -> Result<Range<u64>> {
         let file_size = scheduler.reader().size().await? as u64;
         let begin = if file_size < scheduler.reader().block_size() as u64 {
             0
         } else {
             file_size - scheduler.reader().block_size() as u64
         };
-        let tail_bytes = scheduler.submit_single(begin..file_size, 0).await?;
+        Ok(begin..file_size)
+    }
+
+    async fn read_tail(scheduler: &FileScheduler) -> Result<(Bytes, u64)> {
+        let range = Self::tail_range(scheduler).await?;
+        let file_size = range.end;
+        let tail_bytes = scheduler.submit_single(range, 0).await?;
         Ok((tail_bytes, file_size))
     }
 
+    /// Start reading the tail of the file, which holds the footer and usually
+    /// all of the metadata, ahead of [`Self::read_all_metadata`].
+    pub async fn prefetch_metadata(scheduler: &FileScheduler) -> Result<()> {
+        let range = Self::tail_range(scheduler).await?;
+        scheduler.prefetch(vec![range], 0);
+        Ok(())
+    }
+
     // Checks to make sure the footer is written correctly and returns the
     // position of the file descriptor (which comes from the footer)
     fn decode_footer(footer_bytes: &Bytes) -> Result<Footer> {
@@ -463,15 +700,12 @@ impl FileReader {
         })
     }
 
-    // TODO: Once we have coalesced I/O we should only read the column metadatas that we need
-    fn read_all_column_metadata(
-        column_metadata_bytes: Bytes,
-        footer: &Footer,
-    ) -> Result<Vec<pbfile::ColumnMetadata>> {
+    // Returns the range of each column metadata block within `column_metadata_bytes`
+    fn column_metadata_blocks(column_metadata_bytes: &Bytes, footer: &Footer) -> Vec<Range<usize>> {
         let column_metadata_start = footer.column_meta_start;
         // cmo == column_metadata_offsets
         let cmo_table_size = 16 * footer.num_columns as usize;
-        let cmo_table = column_metadata_bytes.slice(column_metadata_bytes.len() - cmo_table_size..);
+        let cmo_table = &column_metadata_bytes[column_metadata_bytes.len() - cmo_table_size..];
 
         (0..footer.num_columns)
             .map(|col_idx| {
@@ -479,12 +713,23 @@ impl FileReader {
                 let position = LittleEndian::read_u64(&cmo_table[offset..offset + 8]);
                 let length = LittleEndian::read_u64(&cmo_table[offset + 8..offset + 16]);
                 let normalized_position = (position - column_metadata_start) as usize;
-                let normalized_end = normalized_position + (length as usize);
+                normalized_position..normalized_position + (length as usize)
+            })
+            .collect()
+    }
+
+    fn read_all_column_metadata(
+        column_metadata_bytes: Bytes,
+        footer: &Footer,
+    ) -> Result<Vec<pbfile::ColumnMetadata>> {
+        Self::column_metadata_blocks(&column_metadata_bytes, footer)
+            .into_iter()
+            .map(|block| {
                 Ok(pbfile::ColumnMetadata::decode(
-                    &column_metadata_bytes[normalized_position..normalized_end],
+                    &column_metadata_bytes[block],
                 )?)
             })
-            .collect::<Result<Vec<_>>>()
+            .collect()
     }
 
     async fn optimistic_tail_read(
@@ -605,7 +850,10 @@ impl FileReader {
             Self::optimistic_tail_read(&tail_bytes, schema_start, scheduler, file_len).await?;
 
         let schema_bytes = all_metadata_bytes.slice(0..schema_size as usize);
-        let (num_rows, schema) = Self::decode_schema(schema_bytes)?;
+        let (num_rows, mut schema) = Self::decode_schema(schema_bytes)?;
+        let column_metadata_checksums = schema
+            .metadata
+            .remove(checksum::COLUMN_METADATA_CHECKSUMS_META_KEY);
 
         // Next, read the metadata for the columns
         // This is both the column metadata and the CMO table
@@ -613,18 +861,15 @@ impl FileReader {
         let column_metadata_end = (footer.global_buff_offsets_start - schema_start) as usize;
         let column_metadata_bytes =
             all_metadata_bytes.slice(column_metadata_start..column_metadata_end);
-        let column_metadatas = Self::read_all_column_metadata(column_metadata_bytes, &footer)?;
+        let column_metadatas = ColumnMetadatas::new(column_metadata_bytes, &footer, file_version);
 
         let num_global_buffer_bytes = gbo_table.iter().map(|buf| buf.size).sum::<u64>();
         let num_data_bytes = footer.column_meta_start - num_global_buffer_bytes;
         let num_column_metadata_bytes = footer.global_buff_offsets_start - footer.column_meta_start;
 
-        let column_infos = Self::meta_to_col_infos(column_metadatas.as_slice(), file_version);
-
         Ok(CachedFileMetadata {
             file_schema: Arc::new(schema),
             column_metadatas,
-            column_infos,
             num_rows,
             num_data_bytes,
             num_column_metadata_bytes,
@@ -633,6 +878,7 @@ impl FileReader {
             file_buffers: gbo_table,
             major_version: footer.major_version,
             minor_version: footer.minor_version,
+            column_metadata_checksums,
         })
     }
 
@@ -657,61 +903,67 @@ impl FileReader {
             .iter()
             .enumerate()
             .map(|(col_idx, col_meta)| {
-                let page_infos = col_meta
-                    .pages
-                    .iter()
-                    .map(|page| {
-                        let num_rows = page.length;
-                        let encoding = match file_version {
-                            LanceFileVersion::V2_0 => {
-                                PageEncoding::Legacy(Self::fetch_encoding::<pbenc::ArrayEncoding>(
-                                    page.encoding.as_ref().unwrap(),
-                                ))
-                            }
-                            _ => {
-                                PageEncoding::Structural(Self::fetch_encoding::<pbenc::PageLayout>(
-                                    page.encoding.as_ref().unwrap(),
-                                ))
-                            }
-                        };
-                        let buffer_offsets_and_sizes = Arc::from(
-                            page.buffer_offsets
-                                .iter()
-                                .zip(page.buffer_sizes.iter())
-                                .map(|(offset, size)| {
-                                    // Starting with version 2.1 we can assert that page buffers are aligned
-                                    assert!(
-                                        file_version < LanceFileVersion::V2_1
-                                            || offset % PAGE_BUFFER_ALIGNMENT as u64 == 0
-                                    );
-                                    (*offset, *size)
-                                })
-                                .collect::<Vec<_>>(),
-                        );
-                        PageInfo {
-                            buffer_offsets_and_sizes,
-                            encoding,
-                            num_rows,
-                            priority: page.priority,
-                        }
-                    })
-                    .collect::<Vec<_>>();
+                Self::meta_to_col_info(col_idx as u32, col_meta, file_version)
+            })
+            .collect::<Vec<_>>()
+    }
+
+    fn meta_to_col_info(
+        col_idx: u32,
+        col_meta: &pbfile::ColumnMetadata,
+        file_version: LanceFileVersion,
+    ) -> Arc<ColumnInfo> {
+        let page_infos = col_meta
+            .pages
+            .iter()
+            .map(|page| {
+                let num_rows = page.length;
+                let encoding = match file_version {
+                    LanceFileVersion::V2_0 => {
+                        PageEncoding::Legacy(Self::fetch_encoding::<pbenc::ArrayEncoding>(
+                            page.encoding.as_ref().unwrap(),
+                        ))
+                    }
+                    _ => PageEncoding::Structural(Self::fetch_encoding::<pbenc::PageLayout>(
+                        page.encoding.as_ref().unwrap(),
+                    )),
+                };
                 let buffer_offsets_and_sizes = Arc::from(
-                    col_meta
-                        .buffer_offsets
+                    page.buffer_offsets
                         .iter()
-                        .zip(col_meta.buffer_sizes.iter())
-                        .map(|(offset, size)| (*offset, *size))
+                        .zip(page.buffer_sizes.iter())
+                        .map(|(offset, size)| {
+                            // Starting with version 2.1 we can assert that page buffers are aligned
+                            assert!(
+                                file_version < LanceFileVersion::V2_1
+                                    || offset % PAGE_BUFFER_ALIGNMENT as u64 == 0
+                            );
+                            (*offset, *size)
+                        })
                         .collect::<Vec<_>>(),
                 );
-                Arc::new(ColumnInfo {
-                    index: col_idx as u32,
-                    page_infos: Arc::from(page_infos),
+                PageInfo {
                     buffer_offsets_and_sizes,
-                    encoding: Self::fetch_encoding(col_meta.encoding.as_ref().unwrap()),
-                })
+                    encoding,
+                    num_rows,
+                    priority: page.priority,
+                }
             })
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        let buffer_offsets_and_sizes = Arc::from(
+            col_meta
+                .buffer_offsets
+                .iter()
+                .zip(col_meta.buffer_sizes.iter())
+                .map(|(offset, size)| (*offset, *size))
+                .collect::<Vec<_>>(),
+        );
+        Arc::new(ColumnInfo {
+            index: col_idx,
+            page_infos: Arc::from(page_infos),
+            buffer_offsets_and_sizes,
+            encoding: Self::fetch_encoding(col_meta.encoding.as_ref().unwrap()),
+        })
     }
 
     fn validate_projection(
@@ -736,8 +988,8 @@ impl FileReader {
                     location!(),
                 ));
             }
-            if *column_index >= metadata.column_infos.len() as u32 {
-                return Err(Error::invalid_input(format!("The projection specified the column index {} but there are only {} columns in the file", column_index, metadata.column_infos.len()), location!()));
+            if *column_index >= metadata.column_metadatas.len() as u32 {
+                return Err(Error::invalid_input(format!("The projection specified the column index {} but there are only {} columns in the file", column_index, metadata.column_metadatas.len()), location!()));
             }
         }
         Ok(())
@@ -790,8 +1042,27 @@ impl FileReader {
             Self::validate_projection(base_projection, &file_metadata)?;
         }
         let num_rows = file_metadata.num_rows;
+        let decryptor = ColumnDecryptor::try_new(
+            &file_metadata.file_schema,
+            &file_metadata.column_metadatas,
+            options.key_management.as_ref(),
+        )
+        .await?
+        .map(Arc::new);
+        let verifier = if options.verify_checksums() {
+            checksum::verify_column_metadata(
+                &path,
+                file_metadata.column_metadata_checksums.as_deref(),
+                &file_metadata.column_metadatas.checksums(),
+            )?;
+            // Verifying checksums needs the buffer checksums of every column
+            let column_metadatas = file_metadata.column_metadatas.all_metadata()?;
+            ChecksumVerifier::try_new(path, &column_metadatas)?.map(Arc::new)
+        } else {
+            None
+        };
         Ok(Self {
-            scheduler,
+            scheduler: Self::wrap_io(scheduler, decryptor.as_ref(), verifier.as_ref()),
             base_projection: base_projection.unwrap_or(ReaderProjection::from_whole_schema(
                 file_metadata.file_schema.as_ref(),
                 file_metadata.version(),
@@ -801,6 +1072,8 @@ impl FileReader {
             decoder_plugins,
             cache,
             options,
+            decryptor,
+            verifier,
         })
     }
 
@@ -817,11 +1090,82 @@ impl FileReader {
     // At the moment this method words because our rules are simple and we just repeat them here.  See
     // Self::default_projection for a similar problem.  In the future this is something the encodings
     // registry will need to figure out.
+    fn use_view_type(field: &mut Field) {
+        if field.metadata.contains_key(BLOB_META_KEY) {
+            return;
+        }
+        match field.data_type() {
+            DataType::Utf8 | DataType::LargeUtf8 => {
+                field.logical_type = LogicalType::try_from(&DataType::Utf8View).unwrap();
+            }
+            DataType::Binary | DataType::LargeBinary => {
+                field.logical_type = LogicalType::try_from(&DataType::BinaryView).unwrap();
+            }
+            _ => field.children.iter_mut().for_each(Self::use_view_type),
+        }
+    }
+
+    /// Changes the string and binary fields of the projection to view types, if requested
+    fn apply_view_types(&self, projection: ReaderProjection) -> Result<ReaderProjection> {
+        if !self.options.use_view_types {
+            return Ok(projection);
+        }
+        let version = self.metadata.version();
+        if version < LanceFileVersion::V2_1 {
+            return Err(Error::invalid_input(
+                format!(
+                    "view types can only be read from files with version 2.1 or later but the file has version {}",
+                    version
+                ),
+                location!(),
+            ));
+        }
+        let mut schema = projection.schema.as_ref().clone();
+        schema.fields.iter_mut().for_each(Self::use_view_type);
+        Ok(ReaderProjection {
+            schema: Arc::new(schema),
+            column_indices: projection.column_indices,
+        })
+    }
+
+    // Returns the column infos needed to read the projection and the projection, with the
+    // column indices adjusted to point into the returned column infos
     fn collect_columns_from_projection(
         &self,
-        _projection: &ReaderProjection,
-    ) -> Result<Vec<Arc<ColumnInfo>>> {
-        Ok(self.metadata.column_infos.to_vec())
+        projection: ReaderProjection,
+    ) -> Result<(Vec<Arc<ColumnInfo>>, ReaderProjection)> {
+        let column_metadatas = &self.metadata.column_metadatas;
+        if self.metadata.version() < LanceFileVersion::V2_1 {
+            // The legacy decoders walk through the columns in order and so they need all of them
+            return Ok((column_metadatas.all_infos()?, projection));
+        }
+        // Structural decoding looks up every leaf column through the column indices so we only
+        // need to decode the metadata of the projected columns
+        let column_infos = projection
+            .column_indices
+            .iter()
+            .map(|column_index| column_metadatas.info(*column_index))
+            .collect::<Result<Vec<_>>>()?;
+        let projection = ReaderProjection {
+            schema: projection.schema,
+            column_indices: (0..column_infos.len() as u32).collect(),
+        };
+        Ok((column_infos, projection))
+    }
+
+    // Reports the pages a read will decode so they show up in the scan statistics
+    fn record_pages_decoded(
+        &self,
+        column_infos: &[Arc<ColumnInfo>],
+        projection: &ReaderProjection,
+        ranges: &[Range<u64>],
+    ) {
+        let columns = projection
+            .column_indices
+            .iter()
+            .filter_map(|column_index| column_infos.get(*column_index as usize));
+        self.scheduler
+            .record_pages_decoded(count_pages(columns, ranges, self.num_rows));
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -874,8 +1218,10 @@ impl FileReader {
         filter: FilterExpression,
     ) -> Result<BoxStream<'static, ReadBatchTask>> {
         // Create and initialize the stream
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &[range.clone()]);
         Self::do_read_range(
-            self.collect_columns_from_projection(&projection)?,
+            column_infos,
             self.scheduler.clone(),
             self.cache.clone(),
             self.num_rows,
@@ -929,15 +1275,17 @@ impl FileReader {
         ))
     }
 
-    fn take_rows(
+    fn read_indices(
         &self,
         indices: Vec<u64>,
         batch_size: u32,
         projection: ReaderProjection,
     ) -> Result<BoxStream<'static, ReadBatchTask>> {
         // Create and initialize the stream
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &index_ranges(&indices));
         Self::do_take_rows(
-            self.collect_columns_from_projection(&projection)?,
+            column_infos,
             self.scheduler.clone(),
             self.cache.clone(),
             self.decoder_plugins.clone(),
@@ -999,8 +1347,10 @@ impl FileReader {
         projection: ReaderProjection,
         filter: FilterExpression,
     ) -> Result<BoxStream<'static, ReadBatchTask>> {
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &ranges);
         Self::do_read_ranges(
-            self.collect_columns_from_projection(&projection)?,
+            column_infos,
             self.scheduler.clone(),
             self.cache.clone(),
             self.decoder_plugins.clone(),
@@ -1031,6 +1381,7 @@ impl FileReader {
     ) -> Result<Pin<Box<dyn Stream<Item = ReadBatchTask> + Send>>> {
         let projection = projection.unwrap_or_else(|| self.base_projection.clone());
         Self::validate_projection(&projection, &self.metadata)?;
+        let projection = self.apply_view_types(projection)?;
         let verify_bound = |params: &ReadBatchParams, bound: u64, inclusive: bool| {
             if bound > self.num_rows || bound == self.num_rows && inclusive {
                 Err(Error::invalid_input(
@@ -1060,7 +1411,7 @@ impl FileReader {
                     }
                 }
                 let indices = indices.iter().map(|idx| idx.unwrap() as u64).collect();
-                self.take_rows(indices, batch_size, projection)
+                self.read_indices(indices, batch_size, projection)
             }
             ReadBatchParams::Range(range) => {
                 verify_bound(&params, range.end as u64, false)?;
@@ -1127,6 +1478,7 @@ impl FileReader {
         projection: ReaderProjection,
         filter: FilterExpression,
     ) -> Result<Pin<Box<dyn RecordBatchStream>>> {
+        let projection = self.apply_view_types(projection)?;
         let arrow_schema = Arc::new(ArrowSchema::from(projection.schema.as_ref()));
         let tasks_stream = self.read_tasks(params, batch_size, Some(projection), filter)?;
         let batch_stream = tasks_stream
@@ -1139,6 +1491,63 @@ impl FileReader {
         )))
     }
 
+    /// Reads the rows at `indices`, in the order given
+    ///
+    /// This is a convenience for point lookups, e.g. fetching the candidates of a vector
+    /// search for re-ranking.  Unlike [`Self::read_stream`], the indices may be in any order
+    /// and may contain duplicates.  The indices are sorted and deduplicated, read with the
+    /// same plan as [`ReadBatchParams::Indices`], in which each column only schedules the
+    /// pages (or, in 2.1 files, the mini-block chunks) that contain the requested rows, and
+    /// the rows are then put back in the requested order.
+    ///
+    /// If `projection` is `None` then the base projection of the reader is used.
+    pub async fn take_rows(
+        &self,
+        indices: &[u64],
+        projection: Option<ReaderProjection>,
+    ) -> Result<RecordBatch> {
+        let projection = projection.unwrap_or_else(|| self.base_projection.clone());
+        Self::validate_projection(&projection, &self.metadata)?;
+        let projection = self.apply_view_types(projection)?;
+        let arrow_schema = Arc::new(ArrowSchema::from(projection.schema.as_ref()));
+        if let Some(index) = indices.iter().find(|index| **index >= self.num_rows) {
+            return Err(Error::invalid_input(
+                format!(
+                    "cannot take row {} from file with {} rows",
+                    index, self.num_rows
+                ),
+                location!(),
+            ));
+        }
+
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.is_empty() {
+            return Ok(RecordBatch::new_empty(arrow_schema));
+        }
+
+        let batch_size = u32::try_from(sorted.len()).unwrap_or(u32::MAX);
+        let batches = self
+            .read_indices(sorted.clone(), batch_size, projection)?
+            .map(|task| task.task)
+            .buffered(1)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let batch = concat_batches(&arrow_schema, &batches)?;
+        if sorted.as_slice() == indices {
+            return Ok(batch);
+        }
+
+        // Map each requested index to its position in the sorted, deduplicated rows
+        let positions = UInt32Array::from_iter_values(
+            indices
+                .iter()
+                .map(|index| sorted.binary_search(index).unwrap() as u32),
+        );
+        Ok(take_record_batch(&batch, &positions)?)
+    }
+
     fn take_rows_blocking(
         &self,
         indices: Vec<u64>,
@@ -1146,7 +1555,8 @@ impl FileReader {
         projection: ReaderProjection,
         filter: FilterExpression,
     ) -> Result<Box<dyn RecordBatchReader + Send + 'static>> {
-        let column_infos = self.collect_columns_from_projection(&projection)?;
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &index_ranges(&indices));
         debug!(
             "Taking {} rows spread across range {}..{} with batch_size {} from columns {:?}",
             indices.len(),
@@ -1183,7 +1593,8 @@ impl FileReader {
         projection: ReaderProjection,
         filter: FilterExpression,
     ) -> Result<Box<dyn RecordBatchReader + Send + 'static>> {
-        let column_infos = self.collect_columns_from_projection(&projection)?;
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &ranges);
         let num_rows = ranges.iter().map(|r| r.end - r.start).sum::<u64>();
         debug!(
             "Taking {} ranges ({} rows) spread across range {}..{} with batch_size {} from columns {:?}",
@@ -1222,7 +1633,8 @@ impl FileReader {
         projection: ReaderProjection,
         filter: FilterExpression,
     ) -> Result<Box<dyn RecordBatchReader + Send + 'static>> {
-        let column_infos = self.collect_columns_from_projection(&projection)?;
+        let (column_infos, projection) = self.collect_columns_from_projection(projection)?;
+        self.record_pages_decoded(&column_infos, &projection, &[range.clone()]);
         let num_rows = self.num_rows;
 
         debug!(
@@ -1274,6 +1686,7 @@ impl FileReader {
     ) -> Result<Box<dyn RecordBatchReader + Send + 'static>> {
         let projection = projection.unwrap_or_else(|| self.base_projection.clone());
         Self::validate_projection(&projection, &self.metadata)?;
+        let projection = self.apply_view_types(projection)?;
         let verify_bound = |params: &ReadBatchParams, bound: u64, inclusive: bool| {
             if bound > self.num_rows || bound == self.num_rows && inclusive {
                 Err(Error::invalid_input(
@@ -1367,6 +1780,56 @@ impl FileReader {
     }
 }
 
+fn index_ranges(indices: &[u64]) -> Vec<Range<u64>> {
+    indices.iter().map(|index| *index..*index + 1).collect()
+}
+
+/// Counts the pages, across `columns`, that hold at least one of the rows in `ranges`
+///
+/// The pages of some columns (e.g. the items of a list column in a 2.0 file) don't line up
+/// with the rows of the file.  All pages of those columns are counted.
+fn count_pages<'a>(
+    columns: impl Iterator<Item = &'a Arc<ColumnInfo>>,
+    ranges: &[Range<u64>],
+    num_rows: u64,
+) -> u64 {
+    let mut ranges = ranges
+        .iter()
+        .filter(|range| !range.is_empty())
+        .cloned()
+        .collect::<Vec<_>>();
+    ranges.sort_by_key(|range| range.start);
+    columns
+        .map(|column| {
+            let column_rows = column
+                .page_infos
+                .iter()
+                .map(|page| page.num_rows)
+                .sum::<u64>();
+            if column_rows != num_rows {
+                return column.page_infos.len() as u64;
+            }
+            let mut num_pages = 0;
+            let mut page_start = 0;
+            let mut range_idx = 0;
+            for page in column.page_infos.iter() {
+                let page_end = page_start + page.num_rows;
+                while range_idx < ranges.len() && ranges[range_idx].end <= page_start {
+                    range_idx += 1;
+                }
+                if range_idx == ranges.len() {
+                    break;
+                }
+                if ranges[range_idx].start < page_end {
+                    num_pages += 1;
+                }
+                page_start = page_end;
+            }
+            num_pages
+        })
+        .sum()
+}
+
 /// Inspects a page and returns a String describing the page's encoding
 pub fn describe_encoding(page: &pbfile::column_metadata::Page) -> String {
     if let Some(encoding) = &page.encoding {
@@ -1524,13 +1987,25 @@ pub mod tests {
     use std::{collections::BTreeMap, pin::Pin, sync::Arc};
 
     use arrow_array::{
-        types::{Float64Type, Int32Type},
-        RecordBatch, UInt32Array,
+        cast::AsArray,
+        types::{Float32Type, Float64Type, Int32Type, UInt16Type},
+        Array, DictionaryArray, FixedSizeListArray, Float32Array, Int32Array, RecordBatch,
+        RecordBatchIterator, RunArray, StringArray, UInt16Array, UInt32Array, UInt64Array,
     };
     use arrow_schema::{DataType, Field, Fields, Schema as ArrowSchema};
+    use arrow_select::{
+        concat::concat_batches,
+        take::{take, take_record_batch},
+    };
     use bytes::Bytes;
+    use deepsize::DeepSizeOf;
     use futures::{prelude::stream::TryStreamExt, StreamExt};
-    use lance_arrow::RecordBatchExt;
+    use lance_arrow::{
+        fixed_shape_tensor::{
+            fixed_shape_tensor_field, FixedShapeTensorArray, FixedShapeTensorMetadata,
+        },
+        RecordBatchExt,
+    };
     use lance_core::{datatypes::Schema, ArrowResult};
     use lance_datagen::{array, gen, BatchCount, ByteCount, RowCount};
     use lance_encoding::{
@@ -1545,7 +2020,7 @@ pub mod tests {
 
     use crate::v2::{
         reader::{EncodedBatchReaderExt, FileReader, FileReaderOptions, ReaderProjection},
-        testing::{test_cache, write_lance_file, FsFixture, WrittenFile},
+        testing::{read_lance_file, test_cache, write_lance_file, FsFixture, WrittenFile},
         writer::{EncodedBatchWriteExt, FileWriter, FileWriterOptions},
     };
 
@@ -1683,6 +2158,7 @@ pub mod tests {
 
         let encoding_options = EncodingOptions {
             cache_bytes_per_column: 4096,
+            cache_rows_per_column: None,
             max_page_bytes: 32 * 1024 * 1024,
             keep_original_array: true,
             buffer_alignment: 64,
@@ -1988,6 +2464,53 @@ pub mod tests {
         assert_eq!(batches[0].num_rows(), total_rows);
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_take_rows(
+        #[values(LanceFileVersion::V2_0, LanceFileVersion::V2_1)] version: LanceFileVersion,
+    ) {
+        let fs = FsFixture::default();
+        let WrittenFile { data, schema, .. } = create_some_file(&fs, version).await;
+        let data = concat_batches(&data[0].schema(), &data).unwrap();
+
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let file_reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        // Unsorted, with duplicates and runs of adjacent rows
+        let indices = vec![70_000, 5, 6, 7, 99_999, 5, 0, 42_123, 42_124, 1];
+        let expected = take_record_batch(&data, &UInt64Array::from(indices.clone())).unwrap();
+        let actual = file_reader.take_rows(&indices, None).await.unwrap();
+        assert_eq!(actual.columns(), expected.columns());
+
+        let projection =
+            ReaderProjection::from_column_names(version, &schema, &["categories"]).unwrap();
+        let actual = file_reader
+            .take_rows(&indices, Some(projection))
+            .await
+            .unwrap();
+        assert_eq!(actual.num_columns(), 1);
+        assert_eq!(
+            actual.column(0),
+            expected.column_by_name("categories").unwrap()
+        );
+
+        let empty = file_reader.take_rows(&[], None).await.unwrap();
+        assert_eq!(empty.num_rows(), 0);
+        assert!(file_reader.take_rows(&[100_000], None).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_blocking_take() {
         let fs = FsFixture::default();
@@ -2107,8 +2630,8 @@ pub mod tests {
 
         let projection =
             ReaderProjection::from_whole_schema(&written_file.schema, LanceFileVersion::V2_0);
-        let column_infos = file_reader
-            .collect_columns_from_projection(&projection)
+        let (column_infos, projection) = file_reader
+            .collect_columns_from_projection(projection)
             .unwrap();
         let mut decode_scheduler = DecodeBatchScheduler::try_new(
             &projection.schema,
@@ -2188,4 +2711,385 @@ pub mod tests {
         let buf = file_reader.read_global_buffer(1).await.unwrap();
         assert_eq!(buf, test_bytes);
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_view_types(
+        #[values(LanceFileVersion::V2_0, LanceFileVersion::V2_1)] version: LanceFileVersion,
+    ) {
+        let fs = FsFixture::default();
+
+        let strings_type = DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+        let reader = gen()
+            .col("string", array::rand_type(&DataType::Utf8))
+            .col("binary", array::rand_type(&DataType::Binary))
+            .col("strings", array::rand_type(&strings_type))
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(2));
+        let written_file = write_lance_file(
+            reader,
+            &fs,
+            FileWriterOptions {
+                format_version: Some(version),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let file_reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default().with_view_types(true),
+        )
+        .await
+        .unwrap();
+
+        let stream = file_reader.read_stream(
+            lance_io::ReadBatchParams::RangeFull,
+            1000,
+            16,
+            FilterExpression::no_filter(),
+        );
+        if version < LanceFileVersion::V2_1 {
+            assert!(stream.is_err());
+            return;
+        }
+        let stream = stream.unwrap();
+        let view_strings_type =
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8View, true)));
+        assert_eq!(stream.schema().field(0).data_type(), &DataType::Utf8View);
+        assert_eq!(stream.schema().field(1).data_type(), &DataType::BinaryView);
+        assert_eq!(stream.schema().field(2).data_type(), &view_strings_type);
+
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(batches.len(), written_file.data.len());
+        for (batch, expected) in batches.iter().zip(written_file.data.iter()) {
+            let strings = batch.column(0).as_string_view();
+            assert!(strings
+                .iter()
+                .eq(expected.column(0).as_string::<i32>().iter()));
+            let binary = batch.column(1).as_binary_view();
+            assert!(binary
+                .iter()
+                .eq(expected.column(1).as_binary::<i32>().iter()));
+            let lists = batch.column(2).as_list::<i32>();
+            let expected_lists = expected.column(2).as_list::<i32>();
+            for (list, expected_list) in lists.iter().zip(expected_lists.iter()) {
+                assert_eq!(list.is_some(), expected_list.is_some());
+                if let (Some(list), Some(expected_list)) = (list, expected_list) {
+                    assert!(list
+                        .as_string_view()
+                        .iter()
+                        .eq(expected_list.as_string::<i32>().iter()));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_end_encoded_round_trip() {
+        let fs = FsFixture::default();
+
+        // Long runs of a few values, including a run of nulls
+        let run_ends = Int32Array::from(vec![3000, 3500, 7000, 10_000]);
+        let values = StringArray::from(vec![Some("active"), None, Some("closed"), Some("active")]);
+        let array = RunArray::<Int32Type>::try_new(&run_ends, &values).unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "status",
+            array.data_type().clone(),
+            true,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(array.clone())]).unwrap();
+
+        write_lance_file(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            &fs,
+            FileWriterOptions {
+                format_version: Some(LanceFileVersion::V2_1),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let batches = read_lance_file(
+            &fs,
+            Arc::<DecoderPlugins>::default(),
+            FilterExpression::no_filter(),
+        )
+        .await;
+        let mut expected = array.downcast::<StringArray>().unwrap().into_iter();
+        let mut num_rows = 0;
+        for batch in batches {
+            let column = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<RunArray<Int32Type>>()
+                .unwrap();
+            // The runs are kept instead of being expanded
+            assert!(column.values().len() <= 4);
+            for value in column.downcast::<StringArray>().unwrap() {
+                assert_eq!(value, expected.next().unwrap());
+            }
+            num_rows += column.len();
+        }
+        assert_eq!(num_rows, 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_dictionary_round_trip() {
+        let fs = FsFixture::default();
+
+        // Nulls can be in the keys or in the dictionary
+        let dictionary = Arc::new(StringArray::from(vec![
+            Some("red"),
+            None,
+            Some("green"),
+            Some("blue"),
+        ]));
+        let keys =
+            UInt16Array::from_iter(
+                (0..10_000).map(|i| if i % 7 == 0 { None } else { Some(i % 4) }),
+            );
+        let array = DictionaryArray::<UInt16Type>::try_new(keys, dictionary).unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "color",
+            array.data_type().clone(),
+            true,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(array.clone())]).unwrap();
+
+        write_lance_file(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            &fs,
+            FileWriterOptions {
+                format_version: Some(LanceFileVersion::V2_1),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let batches = read_lance_file(
+            &fs,
+            Arc::<DecoderPlugins>::default(),
+            FilterExpression::no_filter(),
+        )
+        .await;
+        let mut offset = 0;
+        for batch in batches {
+            let column = batch.column(0).as_dictionary::<UInt16Type>();
+            // The dictionary is kept instead of being materialized
+            assert!(column.values().len() < 10);
+
+            let expected = array.slice(offset, column.len());
+            let expected = take(expected.values(), expected.keys(), None).unwrap();
+            let actual = take(column.values(), column.keys(), None).unwrap();
+            assert_eq!(actual.as_ref(), expected.as_ref());
+            offset += column.len();
+        }
+        assert_eq!(offset, 10_000);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_fixed_shape_tensor_round_trip(
+        #[values(LanceFileVersion::V2_0, LanceFileVersion::V2_1)] version: LanceFileVersion,
+    ) {
+        let fs = FsFixture::default();
+
+        let tensor_metadata = FixedShapeTensorMetadata::new([2, 3, 4]).with_dim_names(vec![
+            "C".into(),
+            "H".into(),
+            "W".into(),
+        ]);
+        let field =
+            fixed_shape_tensor_field("image", DataType::Float32, &tensor_metadata, true).unwrap();
+        let DataType::FixedSizeList(item, list_size) = field.data_type().clone() else {
+            unreachable!()
+        };
+        let num_rows = 1000;
+        let values = Float32Array::from_iter_values((0..num_rows * 24).map(|v| v as f32));
+        let array = FixedSizeListArray::try_new(item, list_size, Arc::new(values), None).unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![field.clone()]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(array)]).unwrap();
+
+        write_lance_file(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            &fs,
+            FileWriterOptions {
+                format_version: Some(version),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let file_reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        // The shape is kept in the schema of the file
+        let read_field = Field::from(&file_reader.schema().fields[0]);
+        assert_eq!(read_field, field);
+
+        // Random access to a few tensors
+        let indices = vec![3, 500, 999];
+        let batches = file_reader
+            .read_stream(
+                lance_io::ReadBatchParams::Indices(UInt32Array::from(indices.clone())),
+                1024,
+                16,
+                FilterExpression::no_filter(),
+            )
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        let tensors =
+            FixedShapeTensorArray::try_new(batch.schema().field(0), batch.column(0).clone())
+                .unwrap();
+        assert_eq!(tensors.len(), 3);
+        for (i, row) in indices.into_iter().enumerate() {
+            let start = row as f32 * 24.0;
+            let expected = (0..24).map(|v| start + v as f32).collect::<Vec<_>>();
+            assert_eq!(
+                tensors
+                    .value(i)
+                    .as_primitive::<Float32Type>()
+                    .values()
+                    .to_vec(),
+                expected
+            );
+            let element = tensors.element(i, &[1, 2, 3]).unwrap();
+            assert_eq!(element.as_primitive::<Float32Type>().value(0), start + 23.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wide_schema_projection() {
+        let fs = FsFixture::default();
+        let num_columns = 1000;
+        let schema = Arc::new(ArrowSchema::new(
+            (0..num_columns)
+                .map(|i| Field::new(format!("c{}", i), DataType::Int32, false))
+                .collect::<Vec<_>>(),
+        ));
+        let columns = (0..num_columns)
+            .map(|i| Arc::new(Int32Array::from_iter_values(i..i + 100)) as Arc<dyn Array>)
+            .collect::<Vec<_>>();
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let written_file = write_lance_file(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            &fs,
+            FileWriterOptions {
+                format_version: Some(LanceFileVersion::V2_1),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let file_reader = FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default(),
+        )
+        .await
+        .unwrap();
+        let column_metadatas = &file_reader.metadata().column_metadatas;
+        assert_eq!(column_metadatas.len(), num_columns as usize);
+        // Opening the file doesn't decode any column metadata
+        assert_eq!(column_metadatas.num_decoded(), 0);
+        let undecoded_size = column_metadatas.deep_size_of();
+
+        let projection = ReaderProjection::from_column_names(
+            LanceFileVersion::V2_1,
+            &written_file.schema,
+            &["c500"],
+        )
+        .unwrap();
+        let batches = file_reader
+            .read_stream_projected(
+                lance_io::ReadBatchParams::RangeFull,
+                1024,
+                16,
+                projection,
+                FilterExpression::no_filter(),
+            )
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_columns(), 1);
+        assert_eq!(
+            batches[0].column(0).as_primitive::<Int32Type>(),
+            &Int32Array::from_iter_values(500..600)
+        );
+        // Only the projected column was decoded, and is accounted for
+        assert_eq!(column_metadatas.num_decoded(), 1);
+        assert!(column_metadatas.deep_size_of() > undecoded_size);
+    }
+
+    #[test]
+    fn test_count_pages() {
+        use lance_encoding::{
+            decoder::{ColumnInfo, PageEncoding, PageInfo},
+            format::pb,
+        };
+
+        let column = |page_rows: &[u64]| {
+            let pages = page_rows
+                .iter()
+                .map(|num_rows| PageInfo {
+                    num_rows: *num_rows,
+                    priority: 0,
+                    encoding: PageEncoding::Legacy(pb::ArrayEncoding::default()),
+                    buffer_offsets_and_sizes: Arc::from(Vec::new()),
+                })
+                .collect::<Vec<_>>();
+            Arc::new(ColumnInfo::new(
+                0,
+                Arc::from(pages),
+                vec![],
+                pb::ColumnEncoding::default(),
+            ))
+        };
+        let columns = [column(&[10, 10, 10]), column(&[30])];
+
+        let count =
+            |ranges: &[std::ops::Range<u64>]| super::count_pages(columns.iter(), ranges, 30);
+        assert_eq!(count(&[0..30]), 4);
+        assert_eq!(count(&[12..15]), 2);
+        assert_eq!(count(&[25..26, 0..1]), 3);
+        assert_eq!(count(&[10..20]), 2);
+        assert_eq!(count(&[]), 0);
+
+        // Pages that don't line up with the rows of the file are all counted
+        let items = [column(&[40, 40])];
+        assert_eq!(super::count_pages(items.iter(), &[0..1], 30), 2);
+    }
 }
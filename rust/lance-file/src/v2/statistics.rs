@@ -0,0 +1,430 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Statistics of row chunks
+//!
+//! When enabled, the writer splits the rows of the file into chunks of a fixed
+//! number of rows and records the null count, minimum and maximum of every
+//! scalar column in each chunk.  The statistics are stored in a global buffer,
+//! as a small self-described Lance file with one row per chunk, and the index
+//! of that buffer is stored in the schema metadata.
+//!
+//! Chunks are independent of pages, so they line up across all columns and
+//! encodings.  A reader can use the statistics to rule out the chunks that
+//! cannot match a filter before reading any column data.
+//!
+//! The statistics have a `num_rows` column with the number of rows in each
+//! chunk, followed by one struct column per field, named after the field id,
+//! with `null_count`, `min_value` and `max_value` children.  This is the same
+//! layout as the page statistics of legacy files.  Only fields with a type that
+//! supports statistics are included.  Blob columns and encrypted columns are
+//! always left out.  Minimums and maximums of strings and binaries are
+//! truncated to a prefix.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::UInt64Type;
+use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+use bytes::Bytes;
+use lance_core::datatypes::{Schema, BLOB_META_KEY};
+use lance_core::{Error, Result};
+use lance_encoding::decoder::{decode_batch, DecoderPlugins, FilterExpression};
+use lance_encoding::encoder::{
+    default_encoding_strategy, encode_batch, EncodedBatch, EncodingOptions,
+};
+use lance_encoding::version::LanceFileVersion;
+use snafu::location;
+
+use crate::v2::reader::EncodedBatchReaderExt;
+use crate::v2::writer::EncodedBatchWriteExt;
+use crate::writer::fields_in_batches;
+use crate::writer::statistics::{collect_statistics, StatisticsCollector};
+
+/// The schema metadata entry holding the index of the statistics global buffer
+pub(crate) const STATISTICS_META_KEY: &str = "lance:statistics";
+
+const NUM_ROWS_COLUMN: &str = "num_rows";
+
+// The statistics only use simple types, so they don't need any newer encodings
+const STATISTICS_FILE_VERSION: LanceFileVersion = LanceFileVersion::V2_0;
+
+/// Collects the statistics of each chunk as batches are written
+pub(crate) struct StatisticsWriter {
+    // The fields that have statistics, with the top-level fields that don't
+    // removed
+    schema: Schema,
+    collector: StatisticsCollector,
+    chunk_rows: u64,
+    // The rows of the current chunk, not yet summarized
+    pending: Vec<RecordBatch>,
+    pending_rows: u64,
+    chunk_sizes: Vec<u64>,
+}
+
+impl StatisticsWriter {
+    /// Create a writer for the fields of `schema` that are not in `excluded`
+    ///
+    /// Returns `None` if none of the fields support statistics.
+    pub(crate) fn try_new(
+        schema: &Schema,
+        excluded: &[&str],
+        chunk_rows: u64,
+    ) -> Result<Option<Self>> {
+        if chunk_rows == 0 {
+            return Err(Error::invalid_input(
+                "the number of rows per statistics chunk must be greater than 0",
+                location!(),
+            ));
+        }
+        let schema = Schema {
+            fields: schema
+                .fields
+                .iter()
+                .filter(|field| {
+                    !excluded.contains(&field.name.as_str())
+                        && !field.metadata.contains_key(BLOB_META_KEY)
+                })
+                .cloned()
+                .collect(),
+            metadata: Default::default(),
+        };
+        let Some(collector) = StatisticsCollector::try_new(&schema) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            schema,
+            collector,
+            chunk_rows,
+            pending: Vec::new(),
+            pending_rows: 0,
+            chunk_sizes: Vec::new(),
+        }))
+    }
+
+    pub(crate) fn append(&mut self, batch: &RecordBatch) -> Result<()> {
+        // Line the columns up with the schema
+        let columns = self
+            .schema
+            .fields
+            .iter()
+            .map(|field| {
+                let column = batch.column_by_name(&field.name).ok_or_else(|| {
+                    Error::invalid_input(
+                        format!(
+                            "Cannot write batch.  The batch was missing the column `{}`",
+                            field.name
+                        ),
+                        location!(),
+                    )
+                })?;
+                Ok((field.name.clone(), column.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let batch = RecordBatch::try_from_iter(columns)?;
+
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let num_rows =
+                (self.chunk_rows - self.pending_rows).min((batch.num_rows() - offset) as u64);
+            self.pending.push(batch.slice(offset, num_rows as usize));
+            self.pending_rows += num_rows;
+            offset += num_rows as usize;
+            if self.pending_rows == self.chunk_rows {
+                self.finish_chunk();
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_chunk(&mut self) {
+        if self.pending_rows == 0 {
+            return;
+        }
+        let batches = std::mem::take(&mut self.pending);
+        for (field, arrays) in fields_in_batches(&batches, &self.schema) {
+            if let Some(builder) = self.collector.get_builder(field.id) {
+                builder.append(collect_statistics(&arrays));
+            }
+        }
+        self.chunk_sizes.push(self.pending_rows);
+        self.pending_rows = 0;
+    }
+
+    /// Summarize the last chunk and return the statistics of all chunks
+    pub(crate) fn finish(mut self) -> Result<RecordBatch> {
+        self.finish_chunk();
+        let stats = self.collector.finish()?;
+        let num_rows: ArrayRef = Arc::new(UInt64Array::from(self.chunk_sizes));
+        let mut fields = vec![Arc::new(ArrowField::new(
+            NUM_ROWS_COLUMN,
+            DataType::UInt64,
+            false,
+        ))];
+        fields.extend(stats.schema().fields().iter().cloned());
+        let mut columns = vec![num_rows];
+        columns.extend(stats.columns().iter().cloned());
+        Ok(RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(fields)),
+            columns,
+        )?)
+    }
+}
+
+/// Serialize the statistics for storage in a global buffer
+pub(crate) async fn encode_statistics(stats: &RecordBatch) -> Result<Bytes> {
+    let schema = Arc::new(Schema::try_from(stats.schema().as_ref())?);
+    let encoding_strategy = default_encoding_strategy(STATISTICS_FILE_VERSION);
+    let encoded = encode_batch(
+        stats,
+        schema,
+        encoding_strategy.as_ref(),
+        &EncodingOptions::default(),
+    )
+    .await?;
+    encoded.try_to_self_described_lance(STATISTICS_FILE_VERSION)
+}
+
+/// The index of the global buffer holding the statistics, if the file has them
+pub(crate) fn statistics_buffer_index(schema: &Schema) -> Result<Option<u32>> {
+    schema
+        .metadata
+        .get(STATISTICS_META_KEY)
+        .map(|index| {
+            index.parse::<u32>().map_err(|_| {
+                Error::invalid_input(
+                    format!(
+                        "invalid statistics buffer index `{}` in file metadata",
+                        index
+                    ),
+                    location!(),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// The statistics of the row chunks of a file
+#[derive(Debug, Clone)]
+pub struct ChunkStatistics {
+    chunk_sizes: Vec<u64>,
+    stats: RecordBatch,
+}
+
+impl ChunkStatistics {
+    pub(crate) async fn decode(bytes: Bytes) -> Result<Self> {
+        let encoded = EncodedBatch::try_from_self_described_lance(bytes)?;
+        let mut stats = decode_batch(
+            &encoded,
+            &FilterExpression::no_filter(),
+            Arc::<DecoderPlugins>::default(),
+            false,
+            STATISTICS_FILE_VERSION,
+            None,
+        )
+        .await?;
+        let num_rows = stats
+            .column_by_name(NUM_ROWS_COLUMN)
+            .filter(|num_rows| num_rows.data_type() == &DataType::UInt64)
+            .ok_or_else(|| {
+                Error::invalid_input(
+                    "the statistics of the file have no number of rows per chunk",
+                    location!(),
+                )
+            })?;
+        let chunk_sizes = num_rows.as_primitive::<UInt64Type>().values().to_vec();
+        stats.remove_column(0);
+        Ok(Self { chunk_sizes, stats })
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.chunk_sizes.len()
+    }
+
+    /// The number of rows in each chunk
+    pub fn chunk_sizes(&self) -> &[u64] {
+        &self.chunk_sizes
+    }
+
+    /// The rows of each chunk, the chunks are contiguous and in order
+    pub fn chunk_ranges(&self) -> Vec<Range<u64>> {
+        let mut start = 0;
+        self.chunk_sizes
+            .iter()
+            .map(|size| {
+                let range = start..start + size;
+                start = range.end;
+                range
+            })
+            .collect()
+    }
+
+    /// The statistics of each field, with one row per chunk
+    ///
+    /// There is one struct column per field, named after the field id, with
+    /// `null_count`, `min_value` and `max_value` children.  Fields without
+    /// statistics have no column.
+    pub fn field_statistics(&self) -> &RecordBatch {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::types::{Int32Type, Int64Type};
+    use arrow_array::{Int32Array, RecordBatchIterator, StringArray, StructArray};
+    use arrow_schema::Fields;
+    use futures::TryStreamExt;
+    use lance_io::{utils::CachedFileSize, ReadBatchParams};
+
+    use super::*;
+    use crate::v2::reader::{FileReader, FileReaderOptions};
+    use crate::v2::testing::{test_cache, write_lance_file, FsFixture};
+    use crate::v2::writer::FileWriterOptions;
+
+    async fn open_file(fs: &FsFixture) -> FileReader {
+        let file_scheduler = fs
+            .scheduler
+            .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        FileReader::try_open(
+            file_scheduler,
+            None,
+            Arc::<DecoderPlugins>::default(),
+            &test_cache(),
+            FileReaderOptions::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chunk_statistics() {
+        let fs = FsFixture::default();
+        let point_fields = Fields::from(vec![ArrowField::new("x", DataType::Int32, true)]);
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("name", DataType::Utf8, true),
+            ArrowField::new("point", DataType::Struct(point_fields.clone()), true),
+        ]));
+        let batches = (0..3)
+            .map(|i| {
+                let ids = Int32Array::from_iter_values(i * 400..(i + 1) * 400);
+                let names = StringArray::from_iter(
+                    (i * 400..(i + 1) * 400)
+                        .map(|id| (id % 2 == 0).then(|| format!("name-{:04}", id))),
+                );
+                let points = StructArray::new(
+                    point_fields.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(
+                        (i * 400..(i + 1) * 400).map(|id| -id),
+                    ))],
+                    None,
+                );
+                Ok(RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(ids), Arc::new(names), Arc::new(points)],
+                )
+                .unwrap())
+            })
+            .collect::<Vec<_>>();
+        let data = RecordBatchIterator::new(batches, schema.clone());
+        let options = FileWriterOptions {
+            statistics_chunk_rows: Some(500),
+            ..Default::default()
+        };
+        write_lance_file(data, &fs, options).await;
+
+        let reader = open_file(&fs).await;
+        let stats = reader.read_statistics().await.unwrap().unwrap();
+        assert_eq!(stats.chunk_sizes(), &[500, 500, 200]);
+        assert_eq!(stats.chunk_ranges(), vec![0..500, 500..1000, 1000..1200]);
+
+        // Field ids are assigned in pre-order: id, name, point, point.x
+        let field_stats = stats.field_statistics();
+        let ids = field_stats.column_by_name("0").unwrap().as_struct();
+        assert_eq!(
+            ids.column_by_name("min_value")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![0, 500, 1000]
+        );
+        assert_eq!(
+            ids.column_by_name("max_value")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![499, 999, 1199]
+        );
+        let names = field_stats.column_by_name("1").unwrap().as_struct();
+        assert_eq!(
+            names
+                .column_by_name("null_count")
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![250, 250, 100]
+        );
+        assert_eq!(
+            names
+                .column_by_name("min_value")
+                .unwrap()
+                .as_string::<i32>()
+                .value(1),
+            "name-0500"
+        );
+        assert!(field_stats.column_by_name("2").is_none());
+        let xs = field_stats.column_by_name("3").unwrap().as_struct();
+        assert_eq!(
+            xs.column_by_name("max_value")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![0, -500, -1000]
+        );
+
+        // The data is not affected
+        let batches = reader
+            .read_stream(
+                ReadBatchParams::RangeFull,
+                1024,
+                16,
+                FilterExpression::no_filter(),
+            )
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            1200
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_statistics() {
+        let fs = FsFixture::default();
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let data = RecordBatchIterator::new(vec![Ok(batch)], schema.clone());
+        write_lance_file(data, &fs, FileWriterOptions::default()).await;
+        let reader = open_file(&fs).await;
+        assert!(reader.read_statistics().await.unwrap().is_none());
+    }
+}
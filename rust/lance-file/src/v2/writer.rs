@@ -39,11 +39,16 @@ use crate::format::pbfile;
 use crate::format::pbfile::DirectEncoding;
 use crate::format::MAGIC;
 
+use super::checksum::{checksum, encode_checksums, COLUMN_METADATA_CHECKSUMS_META_KEY};
+use super::encryption::{assign_column_keys, key_metadata, ColumnEncryptionOptions, DataKey};
+use super::statistics::{encode_statistics, StatisticsWriter, STATISTICS_META_KEY};
+
 /// Pages buffers are aligned to 64 bytes
 pub(crate) const PAGE_BUFFER_ALIGNMENT: usize = 64;
 const PAD_BUFFER: [u8; PAGE_BUFFER_ALIGNMENT] = [72; PAGE_BUFFER_ALIGNMENT];
 const MAX_PAGE_BYTES: usize = 32 * 1024 * 1024;
 const ENV_LANCE_FILE_WRITER_MAX_PAGE_BYTES: &str = "LANCE_FILE_WRITER_MAX_PAGE_BYTES";
+const ENV_LANCE_FILE_WRITER_CHECKSUMS: &str = "LANCE_FILE_WRITER_CHECKSUMS";
 
 #[derive(Debug, Clone, Default)]
 pub struct FileWriterOptions {
@@ -60,8 +65,20 @@ pub struct FileWriterOptions {
     /// falls behind and can't be interleaved with the I/O expensive flushing.
     ///
     /// The default will use 8MiB per column which should be reasonable for most cases.
-    // TODO: Do we need to be able to set this on a per-column basis?
+    /// The `lance-encoding:page-bytes` field metadata can be used to set the amount for
+    /// a single column.
     pub data_cache_bytes: Option<u64>,
+    /// How many rows of a column to buffer before writing a page, even if less than
+    /// the column's share of `data_cache_bytes` has been buffered
+    ///
+    /// Smaller pages make point lookups cheaper, especially for wide rows, while larger
+    /// pages are better for scans.  Batches are not split to respect this value so a page
+    /// can have more rows if the data arrives in larger batches.  The
+    /// `lance-encoding:page-rows` field metadata can be used to set this for a single
+    /// column.
+    ///
+    /// By default pages are only flushed based on their size in bytes.
+    pub rows_per_page: Option<u64>,
     /// A hint to indicate the max size of a page
     ///
     /// This hint can't always be respected.  A single value could be larger than this value
@@ -93,6 +110,24 @@ pub struct FileWriterOptions {
     /// versions may have more efficient encodings.  However, newer format versions will
     /// require more up-to-date readers to read the data.
     pub format_version: Option<LanceFileVersion>,
+    /// Columns to encrypt
+    ///
+    /// See [`super::encryption`] for details.  Blob columns cannot be encrypted.
+    pub encryption: Option<ColumnEncryptionOptions>,
+    /// Whether to record checksums of the page buffers, column buffers and column
+    /// metadata so that readers can detect corruption
+    ///
+    /// See [`super::checksum`] for details.  If not set, this is enabled by the
+    /// `LANCE_FILE_WRITER_CHECKSUMS` environment variable and disabled otherwise.
+    pub checksums: Option<bool>,
+    /// The number of rows in each chunk of the statistics written to the file
+    ///
+    /// If set, the null count, minimum and maximum of every scalar column are recorded
+    /// for every chunk of this many rows so that readers can skip chunks that cannot
+    /// match a filter.  See [`super::statistics`] for details.
+    ///
+    /// By default no statistics are written.
+    pub statistics_chunk_rows: Option<u64>,
 }
 
 pub struct FileWriter {
@@ -105,6 +140,12 @@ pub struct FileWriter {
     rows_written: u64,
     global_buffers: Vec<(u64, u64)>,
     schema_metadata: HashMap<String, String>,
+    // The data key for each column, if the column is encrypted
+    column_keys: Vec<Option<Arc<DataKey>>>,
+    // Each data key and the columns it protects
+    data_keys: Vec<(Arc<DataKey>, Vec<u32>)>,
+    checksums: bool,
+    statistics: Option<StatisticsWriter>,
     options: FileWriterOptions,
 }
 
@@ -113,6 +154,7 @@ fn initial_column_metadata() -> pbfile::ColumnMetadata {
         pages: Vec::new(),
         buffer_offsets: Vec::new(),
         buffer_sizes: Vec::new(),
+        buffer_checksums: Vec::new(),
         encoding: None,
     }
 }
@@ -150,6 +192,11 @@ impl FileWriter {
                 warn!("You have requested an unstable format version.  Files written with this format version may not be readable in the future!  This is a development feature and should only be used for experimentation and never for production data.");
             }
         }
+        let checksums = options.checksums.unwrap_or_else(|| {
+            std::env::var(ENV_LANCE_FILE_WRITER_CHECKSUMS)
+                .map(|val| val == "1" || val.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+        });
         Self {
             writer: object_writer,
             schema: None,
@@ -160,6 +207,10 @@ impl FileWriter {
             field_id_to_column_indices: Vec::new(),
             global_buffers: Vec::new(),
             schema_metadata: HashMap::new(),
+            column_keys: Vec::new(),
+            data_keys: Vec::new(),
+            checksums,
+            statistics: None,
             options,
         }
     }
@@ -194,14 +245,40 @@ impl FileWriter {
         self.options.format_version.unwrap_or_default()
     }
 
+    /// Writes a page or column buffer, encrypting it if the column is encrypted
+    ///
+    /// Returns the position and size of the buffer.  For encrypted buffers the size
+    /// is the size of the ciphertext, which excludes the tag and nonce.
+    async fn write_data_buffer(&mut self, column_idx: u32, buffer: &[u8]) -> Result<(u64, u64)> {
+        let position = self.writer.tell().await? as u64;
+        match self
+            .column_keys
+            .get(column_idx as usize)
+            .and_then(|key| key.as_ref())
+        {
+            Some(key) => {
+                let sealed = key.encrypt(buffer, position)?;
+                Self::do_write_buffer(&mut self.writer, &sealed).await?;
+            }
+            None => Self::do_write_buffer(&mut self.writer, buffer).await?,
+        }
+        Ok((position, buffer.len() as u64))
+    }
+
     async fn write_page(&mut self, encoded_page: EncodedPage) -> Result<()> {
         let buffers = encoded_page.data;
         let mut buffer_offsets = Vec::with_capacity(buffers.len());
         let mut buffer_sizes = Vec::with_capacity(buffers.len());
+        let mut buffer_checksums = Vec::new();
         for buffer in buffers {
-            buffer_offsets.push(self.writer.tell().await? as u64);
-            buffer_sizes.push(buffer.len() as u64);
-            Self::do_write_buffer(&mut self.writer, &buffer).await?;
+            let (offset, size) = self
+                .write_data_buffer(encoded_page.column_idx, &buffer)
+                .await?;
+            buffer_offsets.push(offset);
+            buffer_sizes.push(size);
+            if self.checksums {
+                buffer_checksums.push(checksum(&buffer));
+            }
         }
         let encoded_encoding = match encoded_page.description {
             PageEncoding::Legacy(array_encoding) => Any::from_msg(&array_encoding)?.encode_to_vec(),
@@ -217,6 +294,7 @@ impl FileWriter {
             }),
             length: encoded_page.num_rows,
             priority: encoded_page.row_number,
+            buffer_checksums,
         };
         self.column_metadata[encoded_page.column_idx as usize]
             .pages
@@ -311,6 +389,7 @@ impl FileWriter {
 
         let encoding_options = EncodingOptions {
             cache_bytes_per_column,
+            cache_rows_per_column: self.options.rows_per_page,
             max_page_bytes,
             keep_original_array,
             buffer_alignment: PAGE_BUFFER_ALIGNMENT as u64,
@@ -322,6 +401,26 @@ impl FileWriter {
         self.column_writers = encoder.field_encoders;
         self.column_metadata = vec![initial_column_metadata(); self.num_columns as usize];
         self.field_id_to_column_indices = encoder.field_id_to_column_index;
+        if let Some(encryption) = &self.options.encryption {
+            self.data_keys =
+                assign_column_keys(&schema, encryption, &self.field_id_to_column_indices)?;
+            self.column_keys = vec![None; self.num_columns as usize];
+            for (key, columns) in &self.data_keys {
+                for column_idx in columns {
+                    self.column_keys[*column_idx as usize] = Some(key.clone());
+                }
+            }
+        }
+        if let Some(chunk_rows) = self.options.statistics_chunk_rows {
+            // Statistics are stored in the clear so they would leak encrypted data
+            let encrypted = self
+                .options
+                .encryption
+                .iter()
+                .flat_map(|encryption| encryption.columns.keys().map(String::as_str))
+                .collect::<Vec<_>>();
+            self.statistics = StatisticsWriter::try_new(&schema, &encrypted, chunk_rows)?;
+        }
         self.schema_metadata
             .extend(std::mem::take(&mut schema.metadata));
         self.schema = Some(schema);
@@ -398,6 +497,9 @@ impl FileWriter {
         let mut external_buffers =
             OutOfLineBuffers::new(self.tell().await?, PAGE_BUFFER_ALIGNMENT as u64);
         let encoding_tasks = self.encode_batch(batch, &mut external_buffers)?;
+        if let Some(statistics) = &mut self.statistics {
+            statistics.append(batch)?;
+        }
         // Next, write external buffers
         for external_buffer in external_buffers.take_buffers() {
             Self::do_write_buffer(&mut self.writer, &external_buffer).await?;
@@ -420,23 +522,39 @@ impl FileWriter {
         Ok(())
     }
 
-    async fn write_column_metadata(
-        &mut self,
-        metadata: pbfile::ColumnMetadata,
-    ) -> Result<(u64, u64)> {
-        let metadata_bytes = metadata.encode_to_vec();
+    async fn write_column_metadata(&mut self, metadata_bytes: &[u8]) -> Result<(u64, u64)> {
         let position = self.writer.tell().await? as u64;
         let len = metadata_bytes.len() as u64;
-        self.writer.write_all(&metadata_bytes).await?;
+        self.writer.write_all(metadata_bytes).await?;
         Ok((position, len))
     }
 
-    async fn write_column_metadatas(&mut self) -> Result<Vec<(u64, u64)>> {
-        let mut metadatas = Vec::new();
-        std::mem::swap(&mut self.column_metadata, &mut metadatas);
+    /// Encodes the column metadatas, recording their checksums if enabled
+    ///
+    /// The checksums go in the schema metadata, so this must be called before the
+    /// schema is written.
+    fn encode_column_metadatas(&mut self) -> Vec<Vec<u8>> {
+        let encoded = std::mem::take(&mut self.column_metadata)
+            .into_iter()
+            .map(|metadata| metadata.encode_to_vec())
+            .collect::<Vec<_>>();
+        if self.checksums {
+            let checksums = encoded
+                .iter()
+                .map(|bytes| checksum(bytes))
+                .collect::<Vec<_>>();
+            self.schema_metadata.insert(
+                COLUMN_METADATA_CHECKSUMS_META_KEY.to_string(),
+                encode_checksums(&checksums),
+            );
+        }
+        encoded
+    }
+
+    async fn write_column_metadatas(&mut self, metadatas: Vec<Vec<u8>>) -> Result<Vec<(u64, u64)>> {
         let mut metadata_positions = Vec::with_capacity(metadatas.len());
         for metadata in metadatas {
-            metadata_positions.push(self.write_column_metadata(metadata).await?);
+            metadata_positions.push(self.write_column_metadata(&metadata).await?);
         }
         Ok(metadata_positions)
     }
@@ -469,6 +587,20 @@ impl FileWriter {
         Ok(gbo_table)
     }
 
+    async fn add_key_metadata(&mut self) -> Result<()> {
+        let Some(encryption) = &self.options.encryption else {
+            return Ok(());
+        };
+        for (key, columns) in &self.data_keys {
+            let wrapped_key = key.wrap(encryption.key_management.as_ref()).await?;
+            for column_idx in columns {
+                self.schema_metadata
+                    .extend(key_metadata(*column_idx, key.key_id(), &wrapped_key));
+            }
+        }
+        Ok(())
+    }
+
     /// Add a metadata entry to the schema
     ///
     /// This method is useful because sometimes the metadata is not known until after the
@@ -512,16 +644,16 @@ impl FileWriter {
                 for page in column.final_pages {
                     self.write_page(page).await?;
                 }
-                let column_metadata = &mut self.column_metadata[col_idx];
-                let mut buffer_pos = self.writer.tell().await? as u64;
                 for buffer in column.column_buffers {
-                    column_metadata.buffer_offsets.push(buffer_pos);
-                    let mut size = 0;
-                    Self::do_write_buffer(&mut self.writer, &buffer).await?;
-                    size += buffer.len() as u64;
-                    buffer_pos += size;
+                    let (offset, size) = self.write_data_buffer(col_idx as u32, &buffer).await?;
+                    let column_metadata = &mut self.column_metadata[col_idx];
+                    column_metadata.buffer_offsets.push(offset);
                     column_metadata.buffer_sizes.push(size);
+                    if self.checksums {
+                        column_metadata.buffer_checksums.push(checksum(&buffer));
+                    }
                 }
+                let column_metadata = &mut self.column_metadata[col_idx];
                 let encoded_encoding = Any::from_msg(&column.encoding)?.encode_to_vec();
                 column_metadata.encoding = Some(pbfile::Encoding {
                     location: Some(pbfile::encoding::Location::Direct(pbfile::DirectEncoding {
@@ -579,13 +711,24 @@ impl FileWriter {
 
         self.finish_writers().await?;
 
+        // 2. write the statistics, if enabled, and record the wrapped data keys of any
+        // encrypted columns and the checksums of the column metadatas
+        if let Some(statistics) = self.statistics.take() {
+            let statistics = encode_statistics(&statistics.finish()?).await?;
+            let index = self.add_global_buffer(statistics).await?;
+            self.schema_metadata
+                .insert(STATISTICS_META_KEY.to_string(), index.to_string());
+        }
+        self.add_key_metadata().await?;
+        let column_metadatas = self.encode_column_metadatas();
+
         // 3. write global buffers (we write the schema here)
         let global_buffer_offsets = self.write_global_buffers().await?;
         let num_global_buffers = global_buffer_offsets.len() as u32;
 
         // 4. write the column metadatas
         let column_metadata_start = self.writer.tell().await? as u64;
-        let metadata_positions = self.write_column_metadatas().await?;
+        let metadata_positions = self.write_column_metadatas(column_metadatas).await?;
 
         // 5. write the column metadata offset table
         let cmo_table_start = self.writer.tell().await? as u64;
@@ -696,6 +839,7 @@ fn concat_lance_footer(
                     }),
                     length: page_info.num_rows,
                     priority: page_info.priority,
+                    buffer_checksums: Vec::new(),
                 })
             })
             .collect::<Result<Vec<_>>>()?;
@@ -706,6 +850,7 @@ fn concat_lance_footer(
             pages,
             buffer_offsets,
             buffer_sizes,
+            buffer_checksums: Vec::new(),
             encoding: Some(pbfile::Encoding {
                 location: Some(pbfile::encoding::Location::Direct(pbfile::DirectEncoding {
                     encoding: encoded_col_encoding,
@@ -763,12 +908,13 @@ mod tests {
     use crate::v2::testing::FsFixture;
     use crate::v2::writer::{FileWriter, FileWriterOptions, ENV_LANCE_FILE_WRITER_MAX_PAGE_BYTES};
     use arrow_array::{types::Float64Type, RecordBatchReader};
-    use arrow_array::{RecordBatch, UInt64Array};
+    use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
     use arrow_schema::{DataType, Field, Schema};
     use lance_core::cache::LanceCache;
-    use lance_core::datatypes::Schema as LanceSchema;
+    use lance_core::datatypes::{Schema as LanceSchema, PAGE_ROWS_META_KEY};
     use lance_datagen::{array, gen, BatchCount, RowCount};
     use lance_encoding::decoder::DecoderPlugins;
+    use lance_encoding::version::LanceFileVersion;
     use lance_io::object_store::ObjectStore;
     use lance_io::utils::CachedFileSize;
     use object_store::path::Path;
@@ -884,7 +1030,13 @@ mod tests {
         let column_meta = file_reader.metadata();
 
         let mut total_page_num: u32 = 0;
-        for (col_idx, col_metadata) in column_meta.column_metadatas.iter().enumerate() {
+        for (col_idx, col_metadata) in column_meta
+            .column_metadatas
+            .all_metadata()
+            .unwrap()
+            .into_iter()
+            .enumerate()
+        {
             assert!(
                 !col_metadata.pages.is_empty(),
                 "Column {} has no pages",
@@ -961,7 +1113,12 @@ mod tests {
         .await
         .unwrap();
 
-        for col_metadata in file_reader.metadata().column_metadatas.iter() {
+        for col_metadata in file_reader
+            .metadata()
+            .column_metadatas
+            .all_metadata()
+            .unwrap()
+        {
             for page in col_metadata.pages.iter() {
                 let total_size: u64 = page.buffer_sizes.iter().sum();
                 assert!(
@@ -974,4 +1131,67 @@ mod tests {
 
         std::env::set_var(ENV_LANCE_FILE_WRITER_MAX_PAGE_BYTES, "");
     }
+
+    #[tokio::test]
+    async fn test_rows_per_page() {
+        // Column b overrides the number of rows per page with field metadata
+        let arrow_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::UInt64, false),
+            Field::new("b", DataType::UInt64, false)
+                .with_metadata([(PAGE_ROWS_META_KEY.to_string(), "250".to_string())].into()),
+        ]));
+        let lance_schema = LanceSchema::try_from(arrow_schema.as_ref()).unwrap();
+
+        for version in [LanceFileVersion::V2_0, LanceFileVersion::V2_1] {
+            let fs = FsFixture::default();
+            let mut writer = FileWriter::try_new(
+                fs.object_store.create(&fs.tmp_path).await.unwrap(),
+                lance_schema.clone(),
+                FileWriterOptions {
+                    rows_per_page: Some(500),
+                    format_version: Some(version),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            for i in 0..10 {
+                let values: ArrayRef =
+                    Arc::new(UInt64Array::from_iter_values(i * 100..(i + 1) * 100));
+                let batch =
+                    RecordBatch::try_new(arrow_schema.clone(), vec![values.clone(), values])
+                        .unwrap();
+                writer.write_batch(&batch).await.unwrap();
+            }
+            writer.finish().await.unwrap();
+
+            let file_scheduler = fs
+                .scheduler
+                .open_file(&fs.tmp_path, &CachedFileSize::unknown())
+                .await
+                .unwrap();
+            let file_reader = FileReader::try_open(
+                file_scheduler,
+                None,
+                Arc::<DecoderPlugins>::default(),
+                &LanceCache::no_cache(),
+                FileReaderOptions::default(),
+            )
+            .await
+            .unwrap();
+            let page_lengths = file_reader
+                .metadata()
+                .column_metadatas
+                .all_metadata()
+                .unwrap()
+                .into_iter()
+                .map(|column| column.pages.iter().map(|page| page.length).collect())
+                .collect::<Vec<Vec<u64>>>();
+            assert_eq!(
+                page_lengths,
+                vec![vec![500, 500], vec![250, 250, 250, 250]],
+                "{}",
+                version
+            );
+        }
+    }
 }
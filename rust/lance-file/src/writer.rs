@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
-mod statistics;
+pub(crate) mod statistics;
 
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -706,7 +706,7 @@ impl<M: ManifestProvider + Send + Sync> FileWriter<M> {
 ///
 /// This skips over nested arrays and fields within list arrays. It does walk
 /// over the children of structs.
-fn fields_in_batches<'a>(
+pub(crate) fn fields_in_batches<'a>(
     batches: &'a [RecordBatch],
     schema: &'a Schema,
 ) -> impl Iterator<Item = (&'a Field, Vec<&'a ArrayRef>)> {
@@ -8,7 +8,7 @@ use std::fmt::Debug;
 use std::{any::Any, ops::Bound, sync::Arc};
 
 use arrow::buffer::{OffsetBuffer, ScalarBuffer};
-use arrow_array::{ListArray, RecordBatch};
+use arrow_array::{ArrayRef, ListArray, RecordBatch};
 use arrow_schema::{Field, Schema};
 use async_trait::async_trait;
 use datafusion::functions::string::contains::ContainsFunc;
@@ -20,7 +20,7 @@ use datafusion_expr::expr::ScalarFunction;
 use datafusion_expr::Expr;
 use deepsize::DeepSizeOf;
 use inverted::query::{fill_fts_query_column, FtsQuery, FtsQueryNode, FtsSearchParams, MatchQuery};
-use lance_core::utils::mask::RowIdTreeMap;
+use lance_core::utils::mask::{RowIdMask, RowIdTreeMap};
 use lance_core::{Error, Result};
 use snafu::location;
 
@@ -41,7 +41,7 @@ pub use inverted::tokenizer::InvertedIndexParams;
 
 pub const LANCE_SCALAR_INDEX: &str = "__lance_scalar_index";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, DeepSizeOf)]
 pub enum ScalarIndexType {
     BTree,
     Bitmap,
@@ -625,4 +625,18 @@ pub trait ScalarIndex: Send + Sync + std::fmt::Debug + Index + DeepSizeOf {
         new_data: SendableRecordBatchStream,
         dest_store: &dyn IndexStore,
     ) -> Result<()>;
+
+    /// Count the rows for each distinct value in the index
+    ///
+    /// Rows that are not selected by `mask` (e.g. deleted rows) are not counted and values
+    /// with no remaining rows are omitted.  Nulls, if any, are counted as a group of their
+    /// own.  Returns the values and their counts, or None if the index can't enumerate
+    /// its values.
+    async fn value_counts(
+        &self,
+        _mask: &RowIdMask,
+        _metrics: &dyn MetricsCollector,
+    ) -> Result<Option<(ArrayRef, Vec<u64>)>> {
+        Ok(None)
+    }
 }
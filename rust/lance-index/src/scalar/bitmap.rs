@@ -10,14 +10,20 @@ use std::{
 };
 
 use arrow::array::BinaryBuilder;
-use arrow_array::{new_null_array, Array, BinaryArray, RecordBatch, UInt64Array};
+use arrow_array::{
+    new_empty_array, new_null_array, Array, ArrayRef, BinaryArray, RecordBatch, UInt64Array,
+};
 use arrow_schema::{DataType, Field, Schema};
 use async_trait::async_trait;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion_common::ScalarValue;
 use deepsize::DeepSizeOf;
 use futures::TryStreamExt;
-use lance_core::{error::LanceOptionExt, utils::mask::RowIdTreeMap, Error, Result};
+use lance_core::{
+    error::LanceOptionExt,
+    utils::mask::{RowIdMask, RowIdTreeMap},
+    Error, Result,
+};
 use roaring::RoaringBitmap;
 use serde::Serialize;
 use snafu::location;
@@ -341,6 +347,36 @@ impl ScalarIndex for BitmapIndex {
 
         do_train_bitmap_index(new_data, state, dest_store).await
     }
+
+    async fn value_counts(
+        &self,
+        mask: &RowIdMask,
+        metrics: &dyn MetricsCollector,
+    ) -> Result<Option<(ArrayRef, Vec<u64>)>> {
+        metrics.record_comparisons(self.index_map.len() + 1);
+        let null_value = ScalarValue::try_from(&self.value_type)?;
+        let groups = std::iter::once((&null_value, &self.null_map))
+            .chain(self.index_map.iter().map(|(key, bitmap)| (&key.0, bitmap)));
+
+        let mut values = Vec::new();
+        let mut counts = Vec::new();
+        for (value, bitmap) in groups {
+            let mut bitmap = bitmap.clone();
+            bitmap.mask(mask);
+            // The bitmaps only contain individual row ids so the length is always known
+            let count = bitmap.len().expect_ok()?;
+            if count > 0 {
+                values.push(value.clone());
+                counts.push(count);
+            }
+        }
+        let values = if values.is_empty() {
+            new_empty_array(&self.value_type)
+        } else {
+            ScalarValue::iter_to_array(values)?
+        };
+        Ok(Some((values, counts)))
+    }
 }
 
 fn get_batch_from_arrays(
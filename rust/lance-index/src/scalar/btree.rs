@@ -16,7 +16,10 @@ use super::{
 };
 use crate::frag_reuse::FragReuseIndex;
 use crate::{Index, IndexType};
-use arrow_array::{new_empty_array, Array, RecordBatch, UInt32Array};
+use arrow_array::{
+    cast::AsArray, new_empty_array, types::UInt64Type, Array, ArrayRef, RecordBatch, UInt32Array,
+};
+use arrow_ord::partition::partition;
 use arrow_schema::{DataType, Field, Schema, SortOptions};
 use async_trait::async_trait;
 use datafusion::physical_plan::{
@@ -33,7 +36,7 @@ use futures::{
 };
 use lance_core::{
     utils::{
-        mask::RowIdTreeMap,
+        mask::{RowIdMask, RowIdTreeMap},
         tokio::get_num_compute_intensive_cpus,
         tracing::{IO_TYPE_LOAD_SCALAR_PART, TRACE_IO_EVENTS},
     },
@@ -879,6 +882,56 @@ impl BTreeIndex {
             .boxed();
         Ok(RecordBatchStreamAdapter::new(schema, batches))
     }
+
+    /// Returns true if any of the indexed values are null
+    pub fn has_nulls(&self) -> bool {
+        !self.page_lookup.null_pages.is_empty()
+    }
+
+    /// Create a stream of the indexed values and their row ids, sorted by value
+    ///
+    /// The pages are stored in ascending order with nulls first.  If `descending` is
+    /// true the pages (and the rows within each page) are read in reverse, so the nulls
+    /// come last.
+    ///
+    /// The stream has the same schema as the pages (`values` and `row_ids`).  Pages
+    /// are read lazily so a consumer that only needs the first few rows will only
+    /// load the first few pages.  Row ids are not filtered for deletions.
+    pub async fn ordered_stream(&self, descending: bool) -> Result<SendableRecordBatchStream> {
+        let reader = self.store.open_index_file(BTREE_PAGES_NAME).await?;
+        let schema = self.sub_index.schema().clone();
+        let num_pages = reader.num_batches(self.batch_size).await;
+        let page_numbers: Vec<u32> = if descending {
+            (0..num_pages).rev().collect()
+        } else {
+            (0..num_pages).collect()
+        };
+        let batch_size = self.batch_size;
+        let fri = self.fri.clone();
+        let batches = stream::iter(page_numbers)
+            .map(move |page_number| {
+                let reader = reader.clone();
+                let fri = fri.clone();
+                async move {
+                    let mut page = reader
+                        .read_record_batch(page_number as u64, batch_size)
+                        .await?;
+                    if let Some(fri_ref) = fri.as_ref() {
+                        page = fri_ref.remap_row_ids_record_batch(page, 1)?;
+                    }
+                    if descending {
+                        let indices =
+                            UInt32Array::from_iter_values((0..page.num_rows() as u32).rev());
+                        page = arrow_select::take::take_record_batch(&page, &indices)?;
+                    }
+                    Ok::<_, Error>(page)
+                }
+                .map_err(DataFusionError::from)
+            })
+            .buffered(self.store.io_parallelism())
+            .boxed();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, batches)))
+    }
 }
 
 fn wrap_bound(bound: &Bound<ScalarValue>) -> Bound<OrderableScalarValue> {
@@ -1081,6 +1134,44 @@ impl ScalarIndex for BTreeIndex {
         )
         .await
     }
+
+    async fn value_counts(
+        &self,
+        mask: &RowIdMask,
+        metrics: &dyn MetricsCollector,
+    ) -> Result<Option<(ArrayRef, Vec<u64>)>> {
+        // The pages are sorted so equal values are adjacent (though a value may span pages)
+        let mut pages = self.ordered_stream(false).await?;
+        let mut values = Vec::<ScalarValue>::new();
+        let mut counts = Vec::<u64>::new();
+        while let Some(page) = pages.try_next().await? {
+            metrics.record_part_load();
+            let page_values = page.column(0);
+            let row_ids = page.column(1).as_primitive::<UInt64Type>().values();
+            for range in partition(&[page_values.clone()])?.ranges() {
+                let count = row_ids[range.clone()]
+                    .iter()
+                    .filter(|row_id| mask.selected(**row_id))
+                    .count() as u64;
+                if count == 0 {
+                    continue;
+                }
+                let value = ScalarValue::try_from_array(page_values, range.start)?;
+                if values.last() == Some(&value) {
+                    *counts.last_mut().unwrap() += count;
+                } else {
+                    values.push(value);
+                    counts.push(count);
+                }
+            }
+        }
+        let values = if values.is_empty() {
+            new_empty_array(self.sub_index.schema().field(0).data_type())
+        } else {
+            ScalarValue::iter_to_array(values)?
+        };
+        Ok(Some((values, counts)))
+    }
 }
 
 struct BatchStats {
@@ -1387,7 +1478,7 @@ mod tests {
     use std::{collections::HashMap, sync::Arc};
 
     use arrow::datatypes::{Float32Type, Float64Type, Int32Type, UInt64Type};
-    use arrow_array::FixedSizeListArray;
+    use arrow_array::{cast::AsArray, FixedSizeListArray};
     use arrow_schema::DataType;
     use datafusion::{
         execution::{SendableRecordBatchStream, TaskContext},
@@ -1499,6 +1590,59 @@ mod tests {
         assert_eq!(original_data, remapped_data);
     }
 
+    #[tokio::test]
+    async fn test_ordered_stream() {
+        let tmpdir = Arc::new(tempdir().unwrap());
+        let test_store = Arc::new(LanceIndexStore::new(
+            Arc::new(ObjectStore::local()),
+            Path::from_filesystem_path(tmpdir.path()).unwrap(),
+            Arc::new(LanceCache::no_cache()),
+        ));
+
+        // Values count down while row ids count up so the index order is the reverse of
+        // the row id order
+        let stream = gen()
+            .col("value", array::step_custom::<Int32Type>(1000, -1))
+            .col("_rowid", array::step::<UInt64Type>())
+            .into_df_stream(RowCount::from(100), BatchCount::from(10));
+        let data_source = Box::new(MockTrainingSource::from(stream));
+        let sub_index_trainer = FlatIndexMetadata::new(DataType::Int32);
+
+        train_btree_index(data_source, &sub_index_trainer, test_store.as_ref(), 100)
+            .await
+            .unwrap();
+
+        let index = BTreeIndex::load(test_store.clone(), None).await.unwrap();
+        assert!(!index.has_nulls());
+
+        for descending in [false, true] {
+            let batches = index
+                .ordered_stream(descending)
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(batches.len(), 10);
+            let row_ids = batches
+                .iter()
+                .flat_map(|batch| {
+                    batch
+                        .column(1)
+                        .as_primitive::<UInt64Type>()
+                        .values()
+                        .to_vec()
+                })
+                .collect::<Vec<_>>();
+            let expected = if descending {
+                (0..1000).collect::<Vec<u64>>()
+            } else {
+                (0..1000).rev().collect::<Vec<u64>>()
+            };
+            assert_eq!(row_ids, expected);
+        }
+    }
+
     #[tokio::test]
     async fn test_nan_ordering() {
         let tmpdir = Arc::new(tempdir().unwrap());
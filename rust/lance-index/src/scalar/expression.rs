@@ -782,10 +782,23 @@ impl ScalarIndexExpr {
                 }
             }
             Self::Query(search) => {
-                let index = index_loader
-                    .load_index(&search.column, &search.index_name, metrics)
-                    .await?;
-                let search_result = index.search(search.query.as_ref(), metrics).await?;
+                let search_fut = async {
+                    let index = index_loader
+                        .load_index(&search.column, &search.index_name, metrics)
+                        .await?;
+                    index.search(search.query.as_ref(), metrics).await
+                };
+                #[cfg(feature = "query-tracing")]
+                let search_fut = tracing::Instrument::instrument(
+                    search_fut,
+                    tracing::info_span!(
+                        target: lance_core::utils::tracing::TRACE_QUERY,
+                        "search_scalar_index",
+                        column = %search.column,
+                        index_name = %search.index_name,
+                    ),
+                );
+                let search_result = search_fut.await?;
                 match search_result {
                     SearchResult::Exact(matching_row_ids) => {
                         Ok(IndexExprResult::Exact(RowIdMask {
@@ -265,12 +265,14 @@ impl IndexStore for LanceIndexStore {
 
         let other_store = dest_store.as_any().downcast_ref::<Self>();
         match other_store {
-            Some(dest_store) if dest_store.object_store.scheme() == self.object_store.scheme() => {
-                // If both this store and the destination are lance stores we can use object_store's copy
-                // This does blindly assume that both stores are using the same underlying object_store
-                // but there is no easy way to verify this and it happens to always be true at the moment
+            Some(dest_store) => {
+                // If both this store and the destination are lance stores the file can be
+                // copied as is, server-side when they are in the same bucket
                 let dest_path = dest_store.index_dir.child(name);
-                self.object_store.copy(&path, &dest_path).await
+                dest_store
+                    .object_store
+                    .copy_from(&self.object_store, &path, &dest_path)
+                    .await
             }
             _ => {
                 let reader = self.open_index_file(name).await?;
@@ -14,6 +14,7 @@ use async_trait::async_trait;
 use datafusion::execution::SendableRecordBatchStream;
 use deepsize::DeepSizeOf;
 use ivf::storage::IvfModel;
+use lance_core::utils::cancellation::CancellationToken;
 use lance_core::{Result, ROW_ID_FIELD};
 use lance_io::object_store::ObjectStore;
 use lance_io::traits::Reader;
@@ -101,6 +102,10 @@ pub struct Query {
 
     /// Whether to use an ANN index if available
     pub use_index: bool,
+
+    /// If set, the search is aborted between partitions once this token is
+    /// cancelled or its deadline passes.
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 impl From<pb::VectorMetricType> for DistanceType {
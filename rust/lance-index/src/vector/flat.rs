@@ -68,7 +68,10 @@ pub async fn compute_distance(
             DataType::List(_) => {
                 let vectors = vectors.as_list();
                 let dists = multivec_distance(key.as_ref(), vectors, dt)?;
-                Arc::new(Float32Array::from(dists))
+                // Missing multivectors have no distance
+                Arc::new(Float32Array::from_iter(
+                    dists.into_iter().map(|d| (!d.is_nan()).then_some(d)),
+                ))
             }
             _ => {
                 unreachable!()
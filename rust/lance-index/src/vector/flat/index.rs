@@ -11,10 +11,9 @@ use arrow::array::AsArray;
 use arrow_array::{Array, ArrayRef, Float32Array, RecordBatch, UInt64Array};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use deepsize::DeepSizeOf;
-use itertools::Itertools;
 use lance_core::{Error, Result, ROW_ID_FIELD};
 use lance_file::reader::FileReader;
-use lance_linalg::distance::DistanceType;
+use lance_linalg::distance::{topk, DistanceType};
 use serde::{Deserialize, Serialize};
 use snafu::location;
 
@@ -22,7 +21,6 @@ use crate::{
     metrics::MetricsCollector,
     prefilter::PreFilter,
     vector::{
-        graph::{OrderedFloat, OrderedNode},
         quantizer::{Quantization, QuantizationType, Quantizer, QuantizerMetadata},
         storage::{DistCalculator, VectorStore},
         v3::subindex::IvfSubIndex,
@@ -84,49 +82,37 @@ impl IvfSubIndex for FlatIndex {
         prefilter: Arc<dyn PreFilter>,
         metrics: &dyn MetricsCollector,
     ) -> Result<RecordBatch> {
-        let is_range_query = params.lower_bound.is_some() || params.upper_bound.is_some();
         let dist_calc = storage.dist_calculator(query);
         metrics.record_comparisons(storage.len());
 
-        let res = match prefilter.is_empty() {
-            true => {
-                let iter = dist_calc
-                    .distance_all(k)
-                    .into_iter()
-                    .zip(0..storage.len() as u32)
-                    .map(|(dist, id)| OrderedNode::new(id, dist.into()));
-                if is_range_query {
-                    let lower_bound = params.lower_bound.unwrap_or(f32::MIN);
-                    let upper_bound = params.upper_bound.unwrap_or(f32::MAX);
-                    iter.filter(|r| lower_bound <= r.dist.0 && r.dist.0 < upper_bound)
-                        .sorted_unstable()
-                } else {
-                    iter.sorted_unstable()
-                }
-            }
-            false => {
-                let row_id_mask = prefilter.mask();
-                let iter = (0..storage.len())
-                    .filter(|&id| row_id_mask.selected(storage.row_id(id as u32)))
-                    .map(|id| OrderedNode {
-                        id: id as u32,
-                        dist: OrderedFloat(dist_calc.distance(id as u32)),
-                    });
-                if is_range_query {
-                    let lower_bound = params.lower_bound.unwrap_or(f32::MIN);
-                    let upper_bound = params.upper_bound.unwrap_or(f32::MAX);
-                    iter.filter(|r| lower_bound <= r.dist.0 && r.dist.0 < upper_bound)
-                        .sorted_unstable()
-                } else {
-                    iter.sorted_unstable()
-                }
-            }
+        let in_range = |(_, dist): &(u64, f32)| {
+            params.lower_bound.is_none_or(|lb| lb <= *dist)
+                && params.upper_bound.is_none_or(|ub| *dist < ub)
+        };
+        // Ties are broken by row id, so the results do not depend on the storage order
+        let res = if prefilter.is_empty() {
+            topk(
+                storage
+                    .row_ids()
+                    .copied()
+                    .zip(dist_calc.distance_all(k))
+                    .filter(in_range),
+                k,
+            )
+        } else {
+            let row_id_mask = prefilter.mask();
+            topk(
+                (0..storage.len() as u32)
+                    .map(|id| storage.row_id(id))
+                    .enumerate()
+                    .filter(|(_, row_id)| row_id_mask.selected(*row_id))
+                    .map(|(id, row_id)| (row_id, dist_calc.distance(id as u32)))
+                    .filter(in_range),
+                k,
+            )
         };
 
-        let (row_ids, dists): (Vec<_>, Vec<_>) = res
-            .take(k)
-            .map(|r| (storage.row_id(r.id), r.dist.0))
-            .unzip();
+        let (row_ids, dists): (Vec<_>, Vec<_>) = res.into_iter().unzip();
         let (row_ids, dists) = (UInt64Array::from(row_ids), Float32Array::from(dists));
 
         Ok(RecordBatch::try_new(
@@ -221,7 +207,11 @@ impl Quantization for FlatQuantizer {
     }
 
     fn quantize(&self, vectors: &dyn Array) -> Result<ArrayRef> {
-        Ok(vectors.slice(0, vectors.len()))
+        match vectors.as_fixed_size_list().value_type() {
+            DataType::Float32 => Ok(vectors.slice(0, vectors.len())),
+            // Float16 and Float64 vectors are stored as Float32
+            _ => Ok(arrow::compute::cast(vectors, self.field().data_type())?),
+        }
     }
 
     fn field(&self) -> Field {
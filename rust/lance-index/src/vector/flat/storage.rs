@@ -16,7 +16,7 @@ use arrow_array::{
     types::{Float32Type, UInt64Type},
     Array, ArrayRef, FixedSizeListArray, RecordBatch, UInt64Array,
 };
-use arrow_schema::SchemaRef;
+use arrow_schema::{DataType, SchemaRef};
 use deepsize::DeepSizeOf;
 use lance_core::{Error, Result, ROW_ID};
 use lance_file::reader::FileReader;
@@ -334,6 +334,12 @@ impl<'a> FlatDistanceCal<'a, Float32Type> {
         // TODO: to support other data types other than `f32`, make FlatDistanceCal a generic struct.
         let flat_array = vectors.values().as_primitive::<Float32Type>();
         let dimension = vectors.value_length() as usize;
+        // The vectors are stored as f32, but the query has the type of the column
+        let query = match query.data_type() {
+            DataType::Float32 => query,
+            _ => arrow::compute::cast(&query, &DataType::Float32)
+                .expect("a float query can always be cast to f32"),
+        };
         Self {
             vectors: flat_array.values(),
             query: query.as_primitive::<Float32Type>().values().to_vec(),
@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+use std::sync::Arc;
+
 use arrow_array::RecordBatch;
-use arrow_schema::Field;
+use arrow_schema::{DataType, Field};
 use lance_arrow::RecordBatchExt;
 use lance_core::Error;
 use snafu::location;
@@ -37,6 +39,19 @@ impl Transformer for FlatTransformer {
                 ),
                 location: location!(),
             })?;
+        // Float16 and Float64 vectors are stored as Float32, like FlatQuantizer does
+        let input_arr = match input_arr.data_type() {
+            DataType::FixedSizeList(item, dim)
+                if matches!(item.data_type(), DataType::Float16 | DataType::Float64) =>
+            {
+                let data_type = DataType::FixedSizeList(
+                    Arc::new(Field::new("item", DataType::Float32, true)),
+                    *dim,
+                );
+                arrow::compute::cast(input_arr, &data_type)?
+            }
+            _ => input_arr.clone(),
+        };
         let field = Field::new(
             FLAT_COLUMN,
             input_arr.data_type().clone(),
@@ -45,7 +60,7 @@ impl Transformer for FlatTransformer {
         // rename the column to FLAT_COLUMN
         let batch = batch
             .drop_column(&self.input_column)?
-            .try_with_column(field, input_arr.clone())?;
+            .try_with_column(field, input_arr)?;
         Ok(batch)
     }
 }
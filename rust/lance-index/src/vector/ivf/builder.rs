@@ -51,6 +51,24 @@ pub struct IvfBuildParams {
 
     /// Storage options used to load precomputed partitions.
     pub storage_options: Option<HashMap<String, String>>,
+
+    /// Train kmeans with mini-batches of this many vectors instead of full iterations.
+    ///
+    /// This cuts the training time on large samples, at the cost of slightly worse
+    /// partitions.  See [`lance_linalg::kmeans::KMeansParams::batch_size`].
+    pub kmeans_batch_size: Option<usize>,
+
+    /// Stop training kmeans once the relative change of its loss is below this tolerance.
+    ///
+    /// Defaults to the tolerance of [`lance_linalg::kmeans::KMeansParams`].
+    pub kmeans_tolerance: Option<f64>,
+
+    /// Seed of the training data sample and of the kmeans training, for
+    /// reproducible partitions.
+    ///
+    /// The partitions are only the same when the index is trained on the same
+    /// version of the dataset with the same parameters.
+    pub kmeans_seed: Option<u64>,
 }
 
 impl Default for IvfBuildParams {
@@ -66,6 +84,9 @@ impl Default for IvfBuildParams {
             shuffle_partition_batches: 1024 * 10,
             shuffle_partition_concurrency: 2,
             storage_options: None,
+            kmeans_batch_size: None,
+            kmeans_tolerance: None,
+            kmeans_seed: None,
         }
     }
 }
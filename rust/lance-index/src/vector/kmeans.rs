@@ -37,6 +37,25 @@ pub fn train_kmeans<T: ArrowPrimitiveType>(
     distance_type: DistanceType,
     sample_rate: usize,
 ) -> Result<KMeans>
+where
+    T::Native: Dot + L2 + Normalize,
+    PrimitiveArray<T>: From<Vec<T::Native>>,
+{
+    let params = KMeansParams::new(centroids, max_iterations, redos, distance_type);
+    train_kmeans_with_params(array, dimension, k, sample_rate, &params)
+}
+
+/// Train KMeans model with full [`KMeansParams`], e.g. to train with mini-batches or with
+/// a fixed seed, and returns the centroids of each cluster.
+///
+/// See [`train_kmeans`] for the other parameters.
+pub fn train_kmeans_with_params<T: ArrowPrimitiveType>(
+    array: &PrimitiveArray<T>,
+    dimension: usize,
+    k: usize,
+    sample_rate: usize,
+    params: &KMeansParams,
+) -> Result<KMeans>
 where
     T::Native: Dot + L2 + Normalize,
     PrimitiveArray<T>: From<Vec<T::Native>>,
@@ -63,8 +82,7 @@ where
         array.clone()
     };
 
-    let params = KMeansParams::new(centroids, max_iterations, redos, distance_type);
     let data = FixedSizeListArray::try_new_from_values(data, dimension as i32)?;
-    let model = KMeans::new_with_params(&data, k, &params)?;
+    let model = KMeans::new_with_params(&data, k, params)?;
     Ok(model)
 }
@@ -130,22 +130,43 @@ impl Transformer for KeepFiniteVectors {
             }
         };
 
-        let mut valid = Vec::with_capacity(batch.num_rows());
-        data.iter().enumerate().for_each(|(idx, arr)| {
-            if let Some(data) = arr {
-                let is_valid = match data.data_type() {
+        // Null vectors, and vectors with null or non-finite elements, are dropped
+        let vector_valid = data
+            .iter()
+            .map(|arr| {
+                arr.is_some_and(|data| match data.data_type() {
                     DataType::Float16 => is_all_finite::<Float16Type>(&data),
                     DataType::Float32 => is_all_finite::<Float32Type>(&data),
                     DataType::Float64 => is_all_finite::<Float64Type>(&data),
                     DataType::UInt8 => data.null_count() == 0,
                     DataType::Int8 => data.null_count() == 0,
                     _ => false,
-                };
-                if is_valid {
-                    valid.push(idx as u32);
-                }
-            };
-        });
+                })
+            })
+            .collect::<Vec<_>>();
+        let valid = match arr.data_type() {
+            // A multivector is kept only if it is not null and all of its vectors are valid
+            DataType::List(_) => {
+                let list = arr.as_list::<i32>();
+                list.offsets()
+                    .windows(2)
+                    .enumerate()
+                    .filter(|(idx, range)| {
+                        list.is_valid(*idx)
+                            && vector_valid[range[0] as usize..range[1] as usize]
+                                .iter()
+                                .all(|v| *v)
+                    })
+                    .map(|(idx, _)| idx as u32)
+                    .collect::<Vec<_>>()
+            }
+            _ => vector_valid
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| **v)
+                .map(|(idx, _)| idx as u32)
+                .collect::<Vec<_>>(),
+        };
         if valid.len() < batch.num_rows() {
             let indices = UInt32Array::from(valid);
             Ok(batch.take(&indices)?)
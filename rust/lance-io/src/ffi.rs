@@ -1,14 +1,94 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+//! Support for the C entry points of the Lance crates
+//!
+//! The file API in `lance-file` and the dataset APIs in `lance` share the
+//! runtime they block on and the last error of the calling thread, so an error
+//! raised by one can be read through the `*_last_error` function of the other.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::LazyLock;
+
 use arrow::ffi_stream::FFI_ArrowArrayStream;
 use arrow_array::RecordBatch;
 use arrow_schema::{ArrowError, SchemaRef};
 use futures::StreamExt;
-use lance_core::Result;
+use lance_core::{Error, Result};
+use snafu::location;
 
 use crate::stream::RecordBatchStream;
 
+/// The runtime C entry points run their work on
+///
+/// Calls block the calling thread on it, so they must not be made from within
+/// a Tokio runtime.
+#[cfg(not(target_arch = "wasm32"))]
+pub static RT: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime")
+});
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record the last error of the calling thread
+pub fn set_last_error(message: impl AsRef<str>) {
+    let message = CString::new(message.as_ref().replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// The message of the last error raised on the calling thread, or null
+///
+/// The pointer stays valid until the next error is recorded on the thread.
+pub fn last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Run `f`, returning `default` and recording the error if it fails or panics
+pub fn guard<T>(default: T, f: impl FnOnce() -> Result<T>) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(err)) => {
+            set_last_error(err.to_string());
+            default
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            set_last_error(format!("Panic in a Lance C API call: {}", message));
+            default
+        }
+    }
+}
+
+/// Borrow a NUL-terminated string, or `None` if `ptr` is null
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+pub unsafe fn c_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|e| Error::invalid_input(format!("Invalid UTF-8 string: {}", e), location!()))
+}
+
 #[pin_project::pin_project]
 struct RecordBatchIteratorAdaptor<S: RecordBatchStream> {
     schema: SchemaRef,
@@ -58,3 +138,30 @@ pub fn to_ffi_arrow_array_stream(
 
     Ok(reader)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(guard(-1, || Ok(1)), 1);
+
+        let rc = guard(-1, || Err(Error::invalid_input("bad input", location!())));
+        assert_eq!(rc, -1);
+        let message = unsafe { CStr::from_ptr(last_error()) };
+        assert!(message.to_str().unwrap().contains("bad input"));
+
+        let rc = guard(-1, || panic!("oh no"));
+        assert_eq!(rc, -1);
+        let message = unsafe { CStr::from_ptr(last_error()) };
+        assert!(message.to_str().unwrap().contains("oh no"));
+    }
+
+    #[test]
+    fn test_c_str() {
+        let value = CString::new("name").unwrap();
+        assert_eq!(unsafe { c_str(value.as_ptr()) }.unwrap(), Some("name"));
+        assert_eq!(unsafe { c_str(std::ptr::null()) }.unwrap(), None);
+    }
+}
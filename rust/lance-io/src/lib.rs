@@ -13,6 +13,7 @@ use lance_core::{Error, Result};
 
 pub mod encodings;
 pub mod ffi;
+#[cfg(feature = "fs")]
 pub mod local;
 pub mod object_reader;
 pub mod object_store;
@@ -21,6 +22,7 @@ pub mod scheduler;
 pub mod stream;
 #[cfg(test)]
 pub mod testing;
+pub mod throttle;
 pub mod traits;
 pub mod utils;
 
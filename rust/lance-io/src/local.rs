@@ -27,6 +27,9 @@ use tracing::instrument;
 use crate::object_store::DEFAULT_LOCAL_IO_PARALLELISM;
 use crate::traits::{Reader, Writer};
 
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
+
 /// Convert an [`object_store::path::Path`] to a [`std::path::Path`].
 pub fn to_local_path(path: &Path) -> String {
     if cfg!(windows) {
@@ -86,6 +89,9 @@ pub struct LocalObjectReader {
 
     /// Block size, in bytes.
     block_size: usize,
+
+    /// Whether to drop the data that is read from the OS page cache.
+    drop_cache: bool,
 }
 
 impl DeepSizeOf for LocalObjectReader {
@@ -112,6 +118,31 @@ impl LocalObjectReader {
         path: &Path,
         block_size: usize,
         known_size: Option<usize>,
+    ) -> Result<Box<dyn Reader>> {
+        Self::open_with_cache_policy(path, block_size, known_size, false).await
+    }
+
+    /// Open a local object reader whose reads don't stay in the OS page cache.
+    ///
+    /// This is meant for large, one-off sequential reads such as compaction,
+    /// which would otherwise evict the pages that queries keep coming back to.
+    /// On Linux, the pages of each range are dropped with
+    /// `posix_fadvise(POSIX_FADV_DONTNEED)` once they have been read.  On other
+    /// platforms this is the same as [`Self::open`].
+    #[instrument(level = "debug")]
+    pub async fn open_uncached(
+        path: &Path,
+        block_size: usize,
+        known_size: Option<usize>,
+    ) -> Result<Box<dyn Reader>> {
+        Self::open_with_cache_policy(path, block_size, known_size, true).await
+    }
+
+    async fn open_with_cache_policy(
+        path: &Path,
+        block_size: usize,
+        known_size: Option<usize>,
+        drop_cache: bool,
     ) -> Result<Box<dyn Reader>> {
         let path = path.clone();
         let local_path = to_local_path(&path);
@@ -129,6 +160,7 @@ impl LocalObjectReader {
                 block_size,
                 size,
                 path,
+                drop_cache,
             }) as Box<dyn Reader>)
         })
         .await?
@@ -171,6 +203,20 @@ impl Reader for LocalObjectReader {
     #[instrument(level = "debug", skip(self))]
     async fn get_range(&self, range: Range<usize>) -> object_store::Result<Bytes> {
         let file = self.file.clone();
+        let drop_cache = self.drop_cache;
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = uring::global() {
+            let bytes = ring
+                .read(file.clone(), range.start as u64..range.end as u64)
+                .await;
+            if drop_cache && bytes.is_ok() {
+                drop_from_page_cache(&file, &range);
+            }
+            return bytes.map_err(|err| object_store::Error::Generic {
+                store: "LocalFileSystem",
+                source: err.into(),
+            });
+        }
         tokio::task::spawn_blocking(move || {
             let mut buf = BytesMut::with_capacity(range.len());
             // Safety: `buf` is set with appropriate capacity above. It is
@@ -179,7 +225,10 @@ impl Reader for LocalObjectReader {
             #[cfg(unix)]
             file.read_exact_at(buf.as_mut(), range.start as u64)?;
             #[cfg(windows)]
-            read_exact_at(file, buf.as_mut(), range.start as u64)?;
+            read_exact_at(file.clone(), buf.as_mut(), range.start as u64)?;
+            if drop_cache {
+                drop_from_page_cache(&file, &range);
+            }
 
             Ok(buf.freeze())
         })
@@ -207,6 +256,33 @@ impl Reader for LocalObjectReader {
     }
 }
 
+/// Tell the OS that the pages backing `range` won't be needed again.
+///
+/// This is only a hint, failures are ignored.
+#[cfg(target_os = "linux")]
+fn drop_from_page_cache(file: &File, range: &Range<usize>) {
+    use std::os::unix::io::AsRawFd;
+
+    // Safety: the file descriptor is valid for as long as `file` is borrowed
+    let res = unsafe {
+        libc::posix_fadvise(
+            file.as_raw_fd(),
+            range.start as libc::off_t,
+            range.len() as libc::off_t,
+            libc::POSIX_FADV_DONTNEED,
+        )
+    };
+    if res != 0 {
+        log::debug!(
+            "posix_fadvise(DONTNEED) failed: {}",
+            std::io::Error::from_raw_os_error(res)
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_from_page_cache(_file: &File, _range: &Range<usize>) {}
+
 #[cfg(windows)]
 fn read_exact_at(file: Arc<File>, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
     let expected_len = buf.len();
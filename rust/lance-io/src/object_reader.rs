@@ -16,7 +16,9 @@ use object_store::{path::Path, GetOptions, GetResult, ObjectStore, Result as OSR
 use tokio::sync::OnceCell;
 use tracing::instrument;
 
-use crate::{object_store::DEFAULT_CLOUD_IO_PARALLELISM, traits::Reader};
+use crate::{
+    object_store::DEFAULT_CLOUD_IO_PARALLELISM, throttle::BandwidthLimiter, traits::Reader,
+};
 
 /// Object Reader
 ///
@@ -32,6 +34,8 @@ pub struct CloudObjectReader {
 
     block_size: usize,
     download_retry_count: usize,
+    // Set if the reader is used by background work and downloads are limited
+    download_limiter: Option<Arc<BandwidthLimiter>>,
 }
 
 impl DeepSizeOf for CloudObjectReader {
@@ -56,9 +60,25 @@ impl CloudObjectReader {
             size: OnceCell::new_with(known_size),
             block_size,
             download_retry_count,
+            download_limiter: None,
         })
     }
 
+    /// Limit the bandwidth of the reads, see [`crate::throttle`]
+    pub fn with_download_limiter(
+        mut self,
+        download_limiter: Option<Arc<BandwidthLimiter>>,
+    ) -> Self {
+        self.download_limiter = download_limiter;
+        self
+    }
+
+    async fn throttle(&self, num_bytes: usize) {
+        if let Some(limiter) = &self.download_limiter {
+            limiter.acquire(num_bytes as u64).await;
+        }
+    }
+
     // Retries for the initial request are handled by object store, but
     // there are no retries for failures that occur during the streaming
     // of the response body. Thus we add an outer retry loop here.
@@ -144,6 +164,7 @@ impl Reader for CloudObjectReader {
 
     #[instrument(level = "debug", skip(self))]
     async fn get_range(&self, range: Range<usize>) -> OSResult<Bytes> {
+        self.throttle(range.len()).await;
         self.do_get_with_outer_retry(
             || {
                 let options = GetOptions {
@@ -165,14 +186,18 @@ impl Reader for CloudObjectReader {
 
     #[instrument(level = "debug", skip_all)]
     async fn get_all(&self) -> OSResult<Bytes> {
-        self.do_get_with_outer_retry(
-            || {
-                self.object_store
-                    .get_opts(&self.path, GetOptions::default())
-            },
-            || "read_all".to_string(),
-        )
-        .await
+        let bytes = self
+            .do_get_with_outer_retry(
+                || {
+                    self.object_store
+                        .get_opts(&self.path, GetOptions::default())
+                },
+                || "read_all".to_string(),
+            )
+            .await?;
+        // The size isn't known up front, so the next read pays for this one
+        self.throttle(bytes.len()).await;
+        Ok(bytes)
     }
 }
 
@@ -215,13 +240,15 @@ impl SmallReader {
         path: Path,
         download_retry_count: usize,
         size: usize,
+        download_limiter: Option<Arc<BandwidthLimiter>>,
     ) -> Self {
         let path_ref = path.clone();
         let state = SmallReaderState::Loading(
             Box::pin(async move {
                 let object_reader =
                     CloudObjectReader::new(store, path_ref, 0, None, download_retry_count)
-                        .map_err(CloneableError)?;
+                        .map_err(CloneableError)?
+                        .with_download_limiter(download_limiter);
                 object_reader
                     .get_all()
                     .await
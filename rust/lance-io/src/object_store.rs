@@ -19,24 +19,44 @@ use futures::{FutureExt, Stream};
 use lance_core::error::LanceOptionExt;
 use lance_core::utils::parse::str_is_truthy;
 use list_retry::ListRetryStream;
+use metrics::{ObjectStoreMetrics, ObjectStoreMetricsExt};
 #[cfg(feature = "aws")]
 use object_store::aws::AwsCredentialProvider;
+#[cfg(feature = "azure")]
+use object_store::azure::AzureCredentialProvider;
 use object_store::DynObjectStore;
 use object_store::Error as ObjectStoreError;
 use object_store::{path::Path, ObjectMeta, ObjectStore as OSObjectStore};
+#[cfg(feature = "fs")]
 use providers::local::FileStoreProvider;
 use providers::memory::MemoryStoreProvider;
+use read_only::ObjectStoreReadOnlyExt;
+use retry::{RetryObjectStore, RetryPolicy};
 use shellexpand::tilde;
 use snafu::location;
 use tokio::io::AsyncWriteExt;
 use url::Url;
 
+#[cfg(feature = "fs")]
 use super::local::LocalObjectReader;
+use crate::throttle::{self, BandwidthLimits};
+#[cfg(feature = "fs")]
+pub mod disk_cache;
+// The key cache needs moka, which is not available on wasm
+#[cfg(not(target_arch = "wasm32"))]
+pub mod encryption;
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure", feature = "http"))]
+pub mod http_client;
+pub mod kms;
 mod list_retry;
+pub mod metrics;
 pub mod providers;
+pub mod read_only;
+pub mod retry;
 mod tracing;
 use crate::object_reader::SmallReader;
-use crate::object_writer::WriteResult;
+use crate::object_writer::{self, WriteResult};
+use crate::scheduler::IoPriorityClass;
 use crate::{object_reader::CloudObjectReader, object_writer::ObjectWriter, traits::Reader};
 use lance_core::{Error, Result};
 
@@ -49,7 +69,7 @@ pub const DEFAULT_LOCAL_IO_PARALLELISM: usize = 8;
 pub const DEFAULT_CLOUD_IO_PARALLELISM: usize = 64;
 
 const DEFAULT_LOCAL_BLOCK_SIZE: usize = 4 * 1024; // 4KB block size
-#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure", feature = "http"))]
 const DEFAULT_CLOUD_BLOCK_SIZE: usize = 64 * 1024; // 64KB block size
 
 lazy_static::lazy_static! {
@@ -108,6 +128,11 @@ pub struct ObjectStore {
     // Inner object store
     pub inner: Arc<dyn OSObjectStore>,
     scheme: String,
+    /// Identifies the bucket, container or file system of the store, so that
+    /// stores opened with different parameters can copy server-side between
+    /// each other, see [`Self::copy_from`]. None when the store can't be told
+    /// apart from others, e.g. in memory or behind a wrapper.
+    store_prefix: Option<String>,
     block_size: usize,
     max_iop_size: u64,
     /// Whether to use constant size upload parts for multipart uploads. This
@@ -117,8 +142,19 @@ pub struct ObjectStore {
     /// is true for object stores, but not for local filesystems.
     pub list_is_lexically_ordered: bool,
     io_parallelism: usize,
+    /// Number of concurrent IOPS for background work, if not derived from
+    /// `io_parallelism`.
+    background_io_parallelism: Option<usize>,
     /// Number of times to retry a failed download
     download_retry_count: usize,
+    /// Size of the first part of a multipart upload, if not the default.
+    upload_part_size: Option<usize>,
+    /// Number of parts of a multipart upload in flight at once, if not the
+    /// default.
+    upload_concurrency: Option<usize>,
+    /// Limits on the bandwidth of the transfers, set on the copies of the
+    /// store used by background work, see [`Self::for_background`].
+    bandwidth_limits: Option<BandwidthLimits>,
 }
 
 impl DeepSizeOf for ObjectStore {
@@ -174,6 +210,11 @@ pub struct ObjectStoreParams {
     pub s3_credentials_refresh_offset: Duration,
     #[cfg(feature = "aws")]
     pub aws_credentials: Option<AwsCredentialProvider>,
+    /// Credentials for Azure, taking precedence over the ones in the storage
+    /// options. See [`providers::azure::RefreshingAzureCredentialProvider`]
+    /// for credentials that expire.
+    #[cfg(feature = "azure")]
+    pub azure_credentials: Option<AzureCredentialProvider>,
     pub object_store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
     pub storage_options: Option<HashMap<String, String>>,
     /// Use constant size upload parts for multipart uploads. Only necessary
@@ -182,6 +223,33 @@ pub struct ObjectStoreParams {
     /// 50GB.
     pub use_constant_size_upload_parts: bool,
     pub list_is_lexically_ordered: Option<bool>,
+    /// Retry policy applied to every operation on the store. If not set, one
+    /// is created from the `retry_*` storage options, if any are given.
+    pub retry_policy: Option<Arc<dyn RetryPolicy>>,
+    /// Maximum number of concurrent IOPS that a single scheduler may issue
+    /// against the store. Defaults to a value that depends on the kind of
+    /// store. The `LANCE_IO_THREADS` environment variable takes precedence.
+    pub io_parallelism: Option<usize>,
+    /// Maximum number of concurrent IOPS that a single background job, such as
+    /// compaction or an index build, may issue against the store. Defaults to
+    /// half of the store's I/O parallelism.
+    pub background_io_parallelism: Option<usize>,
+    /// Reject every write to the store. Set when a dataset is opened read-only.
+    /// Fewer permissions are needed, e.g. public S3 buckets are read
+    /// without credentials if none are found.
+    pub read_only: bool,
+    /// Size in bytes of the parts of a multipart upload. Must be between 5MB
+    /// and 5GB. Unless constant size parts are used, later parts of very large
+    /// uploads may be bigger. Can also be set with the `upload_part_size`
+    /// storage option. Defaults to 5MB.
+    pub upload_part_size: Option<usize>,
+    /// Number of parts of a single multipart upload that are uploaded at once.
+    /// A writer buffers at most this many parts plus one in memory. Can also be
+    /// set with the `upload_concurrency` storage option. Defaults to 10.
+    pub upload_concurrency: Option<usize>,
+    /// Receives the requests, bytes transferred, latencies and retries of the
+    /// store, see [`metrics`].
+    pub metrics: Option<Arc<dyn ObjectStoreMetrics>>,
 }
 
 impl Default for ObjectStoreParams {
@@ -193,10 +261,75 @@ impl Default for ObjectStoreParams {
             s3_credentials_refresh_offset: Duration::from_secs(60),
             #[cfg(feature = "aws")]
             aws_credentials: None,
+            #[cfg(feature = "azure")]
+            azure_credentials: None,
             object_store_wrapper: None,
             storage_options: None,
             use_constant_size_upload_parts: false,
             list_is_lexically_ordered: None,
+            retry_policy: None,
+            io_parallelism: None,
+            background_io_parallelism: None,
+            read_only: false,
+            upload_part_size: None,
+            upload_concurrency: None,
+            metrics: None,
+        }
+    }
+}
+
+impl ObjectStoreParams {
+    /// The retry policy to apply, either given directly or through the
+    /// storage options.
+    pub fn retry_policy(&self) -> Option<Arc<dyn RetryPolicy>> {
+        self.retry_policy.clone().or_else(|| {
+            let options = StorageOptions(self.storage_options.clone().unwrap_or_default());
+            retry::BackoffRetryPolicy::from_storage_options(&options)
+                .map(|policy| Arc::new(policy) as Arc<dyn RetryPolicy>)
+        })
+    }
+
+    /// Wrap `store` to report to the metrics hook and retry failed requests,
+    /// as configured
+    pub(crate) fn apply_metrics_and_retries(
+        &self,
+        mut store: Arc<dyn OSObjectStore>,
+    ) -> Arc<dyn OSObjectStore> {
+        // Below the retries, so that every attempt is reported
+        if let Some(metrics) = &self.metrics {
+            store = store.with_metrics(metrics.clone());
+        }
+        if let Some(policy) = self.retry_policy() {
+            let mut retry = RetryObjectStore::new(store, policy);
+            if let Some(metrics) = &self.metrics {
+                retry = retry.with_metrics(metrics.clone());
+            }
+            store = Arc::new(retry);
+        }
+        store
+    }
+
+    /// The multipart upload part size, either given directly or through the
+    /// storage options.
+    pub fn upload_part_size(&self) -> Result<Option<usize>> {
+        let part_size = match self.upload_part_size {
+            Some(part_size) => Some(part_size),
+            None => StorageOptions(self.storage_options.clone().unwrap_or_default())
+                .upload_part_size()?,
+        };
+        if let Some(part_size) = part_size {
+            validate_upload_part_size(part_size)?;
+        }
+        Ok(part_size)
+    }
+
+    /// The multipart upload concurrency, either given directly or through the
+    /// storage options.
+    pub fn upload_concurrency(&self) -> Result<Option<usize>> {
+        match self.upload_concurrency {
+            Some(concurrency) => Ok(Some(concurrency)),
+            None => StorageOptions(self.storage_options.clone().unwrap_or_default())
+                .upload_concurrency(),
         }
     }
 }
@@ -216,6 +349,10 @@ impl std::hash::Hash for ObjectStoreParams {
         if let Some(aws_credentials) = &self.aws_credentials {
             Arc::as_ptr(aws_credentials).hash(state);
         }
+        #[cfg(feature = "azure")]
+        if let Some(azure_credentials) = &self.azure_credentials {
+            Arc::as_ptr(azure_credentials).hash(state);
+        }
         if let Some(wrapper) = &self.object_store_wrapper {
             Arc::as_ptr(wrapper).hash(state);
         }
@@ -227,6 +364,17 @@ impl std::hash::Hash for ObjectStoreParams {
         }
         self.use_constant_size_upload_parts.hash(state);
         self.list_is_lexically_ordered.hash(state);
+        if let Some(policy) = &self.retry_policy {
+            Arc::as_ptr(policy).hash(state);
+        }
+        self.io_parallelism.hash(state);
+        self.background_io_parallelism.hash(state);
+        self.read_only.hash(state);
+        self.upload_part_size.hash(state);
+        self.upload_concurrency.hash(state);
+        if let Some(metrics) = &self.metrics {
+            Arc::as_ptr(metrics).hash(state);
+        }
     }
 }
 
@@ -236,6 +384,12 @@ impl PartialEq for ObjectStoreParams {
     #[allow(deprecated)]
     fn eq(&self, other: &Self) -> bool {
         // For equality, we use pointer comparison for ObjectStore, S3 credentials, and wrapper
+        #[cfg(feature = "azure")]
+        if self.azure_credentials.as_ref().map(Arc::as_ptr)
+            != other.azure_credentials.as_ref().map(Arc::as_ptr)
+        {
+            return false;
+        }
         self.block_size == other.block_size
             && self
                 .object_store
@@ -253,6 +407,14 @@ impl PartialEq for ObjectStoreParams {
             && self.storage_options == other.storage_options
             && self.use_constant_size_upload_parts == other.use_constant_size_upload_parts
             && self.list_is_lexically_ordered == other.list_is_lexically_ordered
+            && self.retry_policy.as_ref().map(Arc::as_ptr)
+                == other.retry_policy.as_ref().map(Arc::as_ptr)
+            && self.io_parallelism == other.io_parallelism
+            && self.background_io_parallelism == other.background_io_parallelism
+            && self.read_only == other.read_only
+            && self.upload_part_size == other.upload_part_size
+            && self.upload_concurrency == other.upload_concurrency
+            && self.metrics.as_ref().map(Arc::as_ptr) == other.metrics.as_ref().map(Arc::as_ptr)
     }
 }
 
@@ -317,19 +479,29 @@ impl ObjectStore {
     ) -> Result<(Arc<Self>, Path)> {
         #[allow(deprecated)]
         if let Some((store, path)) = params.object_store.as_ref() {
-            let mut inner = store.clone();
+            let mut inner = params.apply_metrics_and_retries(store.clone());
             if let Some(wrapper) = params.object_store_wrapper.as_ref() {
                 inner = wrapper.wrap(inner);
             }
+            if params.read_only {
+                inner = inner.read_only();
+            }
             let store = Self {
                 inner,
                 scheme: path.scheme().to_string(),
+                store_prefix: None,
                 block_size: params.block_size.unwrap_or(64 * 1024),
                 max_iop_size: *DEFAULT_MAX_IOP_SIZE,
                 use_constant_size_upload_parts: params.use_constant_size_upload_parts,
                 list_is_lexically_ordered: params.list_is_lexically_ordered.unwrap_or_default(),
-                io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+                io_parallelism: params
+                    .io_parallelism
+                    .unwrap_or(DEFAULT_CLOUD_IO_PARALLELISM),
+                background_io_parallelism: params.background_io_parallelism,
                 download_retry_count: DEFAULT_DOWNLOAD_RETRY_COUNT,
+                upload_part_size: params.upload_part_size()?,
+                upload_concurrency: params.upload_concurrency()?,
+                bandwidth_limits: None,
             };
             let path = Path::from(path.path());
             return Ok((Arc::new(store), path));
@@ -355,6 +527,7 @@ impl ObjectStore {
     }
 
     /// Local object store.
+    #[cfg(feature = "fs")]
     pub fn local() -> Self {
         let provider = FileStoreProvider;
         provider
@@ -401,20 +574,92 @@ impl ObjectStore {
             .unwrap_or(self.io_parallelism)
     }
 
+    /// The number of concurrent IOPS a single scheduler of the given priority
+    /// class may issue.
+    pub fn io_parallelism_for(&self, priority_class: IoPriorityClass) -> usize {
+        match priority_class {
+            IoPriorityClass::Interactive => self.io_parallelism(),
+            IoPriorityClass::Background => std::env::var("LANCE_BACKGROUND_IO_THREADS")
+                .map(|val| val.parse::<usize>().unwrap())
+                .ok()
+                .or(self.background_io_parallelism)
+                .unwrap_or_else(|| (self.io_parallelism() / 2).max(1)),
+        }
+    }
+
+    /// Size in bytes of the first part of a multipart upload.
+    pub fn upload_part_size(&self) -> usize {
+        self.upload_part_size
+            .unwrap_or_else(object_writer::default_upload_part_size)
+    }
+
+    /// Number of parts of a multipart upload that are uploaded at once.
+    pub fn upload_concurrency(&self) -> usize {
+        self.upload_concurrency
+            .unwrap_or_else(object_writer::default_upload_concurrency)
+            .max(1)
+    }
+
+    /// A copy of this store for background work
+    ///
+    /// The files it opens and creates are read and written within the
+    /// process-wide background bandwidth limits, see [`crate::throttle`].
+    /// Local files are not limited.
+    pub fn for_background(&self) -> Self {
+        let mut store = self.clone();
+        if store.bandwidth_limits.is_none() && !store.is_local() {
+            store.bandwidth_limits = Some(throttle::background_bandwidth_limits());
+        }
+        store
+    }
+
+    /// A copy of this store whose files are read and written within `limits`
+    pub fn with_bandwidth_limits(&self, limits: BandwidthLimits) -> Self {
+        let mut store = self.clone();
+        store.bandwidth_limits = Some(limits);
+        store
+    }
+
+    /// The limits on the bandwidth of the store, none unless it is used by
+    /// background work
+    pub fn bandwidth_limits(&self) -> BandwidthLimits {
+        self.bandwidth_limits.clone().unwrap_or_default()
+    }
+
     /// Open a file for path.
     ///
     /// Parameters
     /// - ``path``: Absolute path to the file.
     pub async fn open(&self, path: &Path) -> Result<Box<dyn Reader>> {
         match self.scheme.as_str() {
+            #[cfg(feature = "fs")]
             "file" => LocalObjectReader::open(path, self.block_size, None).await,
-            _ => Ok(Box::new(CloudObjectReader::new(
-                self.inner.clone(),
-                path.clone(),
-                self.block_size,
-                None,
-                self.download_retry_count,
-            )?)),
+            _ => Ok(Box::new(
+                CloudObjectReader::new(
+                    self.inner.clone(),
+                    path.clone(),
+                    self.block_size,
+                    None,
+                    self.download_retry_count,
+                )?
+                .with_download_limiter(self.bandwidth_limits().download),
+            )),
+        }
+    }
+
+    /// Open a reader for a file with known size whose reads should not be kept
+    /// in the OS page cache.
+    ///
+    /// Only local files are affected, see [`LocalObjectReader::open_uncached`].
+    /// Other stores don't go through the page cache and behave like
+    /// [`Self::open_with_size`].
+    pub async fn open_uncached(&self, path: &Path, known_size: usize) -> Result<Box<dyn Reader>> {
+        match self.scheme.as_str() {
+            #[cfg(feature = "fs")]
+            "file" => {
+                LocalObjectReader::open_uncached(path, self.block_size, Some(known_size)).await
+            }
+            _ => self.open_with_size(path, known_size).await,
         }
     }
 
@@ -432,22 +677,28 @@ impl ObjectStore {
                 path.clone(),
                 self.download_retry_count,
                 known_size,
+                self.bandwidth_limits().download,
             )));
         }
 
         match self.scheme.as_str() {
+            #[cfg(feature = "fs")]
             "file" => LocalObjectReader::open(path, self.block_size, Some(known_size)).await,
-            _ => Ok(Box::new(CloudObjectReader::new(
-                self.inner.clone(),
-                path.clone(),
-                self.block_size,
-                Some(known_size),
-                self.download_retry_count,
-            )?)),
+            _ => Ok(Box::new(
+                CloudObjectReader::new(
+                    self.inner.clone(),
+                    path.clone(),
+                    self.block_size,
+                    Some(known_size),
+                    self.download_retry_count,
+                )?
+                .with_download_limiter(self.bandwidth_limits().download),
+            )),
         }
     }
 
     /// Create an [ObjectWriter] from local [std::path::Path]
+    #[cfg(feature = "fs")]
     pub async fn create_local_writer(path: &std::path::Path) -> Result<ObjectWriter> {
         let object_store = Self::local();
         let absolute_path = expand_path(path.to_string_lossy())?;
@@ -456,6 +707,7 @@ impl ObjectStore {
     }
 
     /// Open an [Reader] from local [std::path::Path]
+    #[cfg(feature = "fs")]
     pub async fn open_local(path: &std::path::Path) -> Result<Box<dyn Reader>> {
         let object_store = Self::local();
         let absolute_path = expand_path(path.to_string_lossy())?;
@@ -481,6 +733,7 @@ impl ObjectStore {
     }
 
     pub async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        #[cfg(feature = "fs")]
         if self.is_local() {
             // Use std::fs::copy for local filesystem to support cross-filesystem copies
             return super::local::copy_file(from, to);
@@ -488,6 +741,27 @@ impl ObjectStore {
         Ok(self.inner.copy(from, to).await?)
     }
 
+    /// Copy an object from `source` into this store.
+    ///
+    /// Within the same bucket, container or file system, the object is copied
+    /// server-side (a rewrite on GCS, `CopyObject` on S3) without downloading
+    /// it, even if the two stores were opened with different parameters.
+    /// Otherwise it is streamed through this process.
+    pub async fn copy_from(&self, source: &Self, from: &Path, to: &Path) -> Result<()> {
+        let same_store = Arc::ptr_eq(&self.inner, &source.inner)
+            || (self.store_prefix.is_some() && self.store_prefix == source.store_prefix);
+        if same_store {
+            return self.copy(from, to).await;
+        }
+        let mut stream = source.inner.get(from).await?.into_stream();
+        let mut writer = self.create(to).await?;
+        while let Some(bytes) = stream.try_next().await? {
+            writer.write_all(&bytes).await?;
+        }
+        writer.shutdown().await?;
+        Ok(())
+    }
+
     /// Read a directory (start from base directory) and returns all sub-paths in the directory.
     pub async fn read_dir(&self, dir_path: impl Into<Path>) -> Result<Vec<String>> {
         let path = dir_path.into();
@@ -524,6 +798,7 @@ impl ObjectStore {
         let path = dir_path.into();
         let path = Path::parse(&path)?;
 
+        #[cfg(feature = "fs")]
         if self.is_local() {
             // Local file system needs to delete directories as well.
             return super::local::remove_dir_all(&path);
@@ -660,6 +935,58 @@ impl StorageOptions {
             .unwrap_or(180)
     }
 
+    /// Whether the store supports conditional PUT (`If-None-Match: *`).
+    ///
+    /// This is the case for AWS S3 and most compatible stores. It can be
+    /// turned off by setting `aws_conditional_put` to `disabled`, either as an
+    /// option or through the environment.
+    pub fn conditional_put(&self) -> bool {
+        let value = self
+            .0
+            .iter()
+            .find(|(key, _)| {
+                key.eq_ignore_ascii_case("aws_conditional_put")
+                    || key.eq_ignore_ascii_case("conditional_put")
+            })
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var("AWS_CONDITIONAL_PUT").ok());
+        !matches!(value, Some(value) if value.eq_ignore_ascii_case("disabled"))
+    }
+
+    /// Whether commits may be made without any protection against concurrent
+    /// writers, when the store supports neither conditional PUT nor atomic
+    /// renames. Set with the `allow_unsafe_commit` option.
+    pub fn allow_unsafe_commit(&self) -> bool {
+        self.0.iter().any(|(key, value)| {
+            key.eq_ignore_ascii_case("allow_unsafe_commit") && str_is_truthy(value)
+        })
+    }
+
+    /// Size in bytes of the parts of a multipart upload, if set
+    pub fn upload_part_size(&self) -> Result<Option<usize>> {
+        self.parse_option("upload_part_size")
+    }
+
+    /// Number of parts of a multipart upload uploaded at once, if set
+    pub fn upload_concurrency(&self) -> Result<Option<usize>> {
+        self.parse_option("upload_concurrency")
+    }
+
+    fn parse_option(&self, name: &str) -> Result<Option<usize>> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| {
+                value.parse::<usize>().map_err(|_| {
+                    Error::invalid_input(
+                        format!("invalid value for storage option {}: {}", name, value),
+                        location!(),
+                    )
+                })
+            })
+            .transpose()
+    }
+
     pub fn get(&self, key: &str) -> Option<&String> {
         self.0.get(key)
     }
@@ -694,16 +1021,37 @@ impl ObjectStore {
         Self {
             inner: store,
             scheme: scheme.into(),
+            store_prefix: None,
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts,
             list_is_lexically_ordered,
             io_parallelism,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         }
     }
 }
 
+/// Check that a multipart upload part size is within the limits of S3 and GCS
+fn validate_upload_part_size(part_size: usize) -> Result<()> {
+    if !(object_writer::MIN_UPLOAD_PART_SIZE..=object_writer::MAX_UPLOAD_PART_SIZE)
+        .contains(&part_size)
+    {
+        return Err(Error::invalid_input(
+            format!(
+                "upload part size must be between 5MB and 5GB, got {} bytes",
+                part_size
+            ),
+            location!(),
+        ));
+    }
+    Ok(())
+}
+
 fn infer_block_size(scheme: &str) -> usize {
     // Block size: On local file systems, we use 4KB block size. On cloud
     // object stores, we use 64KB block size. This is generally the largest
@@ -843,6 +1191,45 @@ mod tests {
         test_block_size_used_test_helper(&uri, None, 4 * 1024).await;
     }
 
+    #[tokio::test]
+    async fn test_upload_options() {
+        let registry = Arc::new(ObjectStoreRegistry::default());
+        let params = ObjectStoreParams {
+            storage_options: Some(HashMap::from([
+                (
+                    "upload_part_size".to_string(),
+                    (8 * 1024 * 1024).to_string(),
+                ),
+                ("upload_concurrency".to_string(), "2".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let (store, _) = ObjectStore::from_uri_and_params(registry.clone(), "memory:///", &params)
+            .await
+            .unwrap();
+        assert_eq!(store.upload_part_size(), 8 * 1024 * 1024);
+        assert_eq!(store.upload_concurrency(), 2);
+
+        // The params take precedence over the storage options
+        let params = ObjectStoreParams {
+            upload_concurrency: Some(4),
+            ..params
+        };
+        let (store, _) = ObjectStore::from_uri_and_params(registry.clone(), "memory:///", &params)
+            .await
+            .unwrap();
+        assert_eq!(store.upload_concurrency(), 4);
+
+        let params = ObjectStoreParams {
+            upload_part_size: Some(1024),
+            ..Default::default()
+        };
+        let err = ObjectStore::from_uri_and_params(registry, "memory:///", &params)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("between 5MB and 5GB"), "{}", err);
+    }
+
     #[tokio::test]
     async fn test_relative_paths() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -969,6 +1356,39 @@ mod tests {
         assert_eq!(Arc::strong_count(&mock_inner_store), 2);
     }
 
+    #[tokio::test]
+    async fn test_metrics_option_is_used() {
+        #[derive(Debug, Default)]
+        struct RequestCounter(std::sync::atomic::AtomicUsize);
+
+        impl ObjectStoreMetrics for RequestCounter {
+            fn on_request(
+                &self,
+                _verb: metrics::Verb,
+                _latency: Duration,
+                _error: Option<retry::ErrorClass>,
+            ) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let counter = Arc::new(RequestCounter::default());
+        let params = ObjectStoreParams {
+            metrics: Some(counter.clone()),
+            ..Default::default()
+        };
+        let registry = Arc::new(ObjectStoreRegistry::default());
+        let (store, base) = ObjectStore::from_uri_and_params(registry, "memory:///", &params)
+            .await
+            .unwrap();
+        store
+            .put(&base.child("test"), b"test".as_slice())
+            .await
+            .unwrap();
+        store.read_one_all(&base.child("test")).await.unwrap();
+        assert!(counter.0.load(Ordering::Relaxed) >= 2);
+    }
+
     #[tokio::test]
     async fn test_local_paths() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -985,6 +1405,21 @@ mod tests {
         assert_eq!(buf.as_ref(), b"LOCAL");
     }
 
+    #[tokio::test]
+    async fn test_uncached_reads() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("test_file");
+        let data = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        std::fs::write(&file_path, &data).unwrap();
+
+        let path = Path::from_filesystem_path(&file_path).unwrap();
+        let obj_store = ObjectStore::local();
+        let reader = obj_store.open_uncached(&path, data.len()).await.unwrap();
+        assert_eq!(reader.size().await.unwrap(), data.len());
+        let buf = reader.get_range(10_000..60_000).await.unwrap();
+        assert_eq!(buf.as_ref(), &data[10_000..60_000]);
+    }
+
     #[tokio::test]
     async fn test_read_one() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -1118,4 +1553,86 @@ mod tests {
         let copied_content = std::fs::read(&dest_file).unwrap();
         assert_eq!(copied_content, b"test content");
     }
+
+    #[tokio::test]
+    async fn test_copy_from() {
+        let source = ObjectStore::memory();
+        let target = ObjectStore::memory();
+        let from = Path::from("a/file");
+        let to = Path::from("b/file");
+        source.put(&from, b"test content").await.unwrap();
+
+        // Different stores, streamed
+        target.copy_from(&source, &from, &to).await.unwrap();
+        assert_eq!(target.read_one_all(&to).await.unwrap(), "test content");
+        assert!(!source.exists(&to).await.unwrap());
+
+        // Same store, server-side
+        source.copy_from(&source, &from, &to).await.unwrap();
+        assert_eq!(source.read_one_all(&to).await.unwrap(), "test content");
+    }
+
+    #[test]
+    fn test_for_background() {
+        use crate::throttle::BandwidthLimiter;
+
+        // A store that is already limited keeps its limits
+        let limiter = Arc::new(BandwidthLimiter::new(1000));
+        let store = ObjectStore::memory().with_bandwidth_limits(BandwidthLimits {
+            download: Some(limiter.clone()),
+            upload: None,
+        });
+        let background = store.for_background();
+        assert!(Arc::ptr_eq(
+            &background.bandwidth_limits().download.unwrap(),
+            &limiter
+        ));
+        assert!(background.bandwidth_limits().upload.is_none());
+
+        // Local files are not limited
+        #[cfg(feature = "fs")]
+        {
+            let background = ObjectStore::local().for_background();
+            assert!(background.bandwidth_limits().download.is_none());
+            assert!(background.bandwidth_limits().upload.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_from_same_file_system() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let tmp_path = tmp_dir.path().to_str().unwrap();
+        let registry = Arc::new(ObjectStoreRegistry::default());
+
+        // Different parameters, so different stores of the same file system
+        let (source, base_path) =
+            ObjectStore::from_uri_and_params(registry.clone(), tmp_path, &Default::default())
+                .await
+                .unwrap();
+        let params = ObjectStoreParams {
+            block_size: Some(1024),
+            ..Default::default()
+        };
+        let (target, _) = ObjectStore::from_uri_and_params(registry.clone(), tmp_path, &params)
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&source.inner, &target.inner));
+        assert_eq!(source.store_prefix, target.store_prefix);
+
+        let from = base_path.child("a");
+        let to = base_path.child("b");
+        source.put(&from, b"test content").await.unwrap();
+        target.copy_from(&source, &from, &to).await.unwrap();
+        assert_eq!(target.read_one_all(&to).await.unwrap(), "test content");
+
+        // A wrapper may change the bytes, so copies go through it
+        let params = ObjectStoreParams {
+            object_store_wrapper: Some(Arc::new(ChainedWrappingObjectStore::new(vec![]))),
+            ..Default::default()
+        };
+        let (wrapped, _) = ObjectStore::from_uri_and_params(registry, tmp_path, &params)
+            .await
+            .unwrap();
+        assert_eq!(wrapped.store_prefix, None);
+    }
 }
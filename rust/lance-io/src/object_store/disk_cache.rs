@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! A persistent cache of byte ranges on local disk
+//!
+//! Wrapping a remote store with a [`DiskCache`] keeps every byte range that is
+//! read in a directory on local storage, ideally an SSD, so that repeated
+//! queries against the same dataset don't go back to S3 or GCS.
+//!
+//! ```ignore
+//! let cache = DiskCache::try_new("/mnt/nvme/lance-cache", 100 * 1024 * 1024 * 1024)?;
+//! let params = ObjectStoreParams {
+//!     object_store_wrapper: Some(Arc::new(cache)),
+//!     ..Default::default()
+//! };
+//! ```
+//!
+//! Ranges are keyed by the ETag of the object, so a cached range is never
+//! served for a different version of an object. The metadata of each path is
+//! looked up once and then remembered, which assumes objects are not replaced
+//! by other writers. This holds for Lance data, index and manifest files.
+//! Writes and deletes made through the wrapped store forget the metadata.
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use moka::notification::RemovalCause;
+use moka::policy::EvictionPolicy;
+use moka::sync::Cache;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result as OSResult,
+};
+
+use super::WrappingObjectStore;
+use lance_core::Result;
+
+/// Suffix of files that are still being written.
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Maximum number of paths whose metadata is remembered.
+const MAX_METAS: u64 = 100_000;
+
+/// A least-recently-used cache of byte ranges stored in a local directory.
+///
+/// Entries left in the directory by an earlier process are reused. Once the
+/// total size exceeds the capacity, the least recently used ranges are deleted.
+///
+/// Clones share the same entries, so one cache can wrap several stores.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    /// File name to size of every cached range.
+    entries: Cache<String, u64>,
+    metas: Cache<Path, ObjectMeta>,
+}
+
+impl DiskCache {
+    /// Create a cache in `dir` holding up to `capacity` bytes.
+    pub fn try_new(dir: impl Into<PathBuf>, capacity: u64) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        let eviction_dir = dir.clone();
+        let entries = Cache::builder()
+            .max_capacity(capacity)
+            .weigher(|_, size: &u64| (*size).try_into().unwrap_or(u32::MAX))
+            .eviction_policy(EvictionPolicy::lru())
+            .eviction_listener(move |name: Arc<String>, _, cause| {
+                if cause != RemovalCause::Replaced {
+                    if let Err(err) = std::fs::remove_file(eviction_dir.join(name.as_str())) {
+                        log::debug!("Failed to remove cached range {}: {}", name, err);
+                    }
+                }
+            })
+            .build();
+
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(TEMP_SUFFIX) {
+                // Left behind by a process that stopped mid-write.
+                std::fs::remove_file(entry.path())?;
+            } else {
+                entries.insert(name, entry.metadata()?.len());
+            }
+        }
+
+        Ok(Self {
+            dir,
+            entries,
+            metas: Cache::new(MAX_METAS),
+        })
+    }
+
+    /// Total size of the cached ranges, in bytes.
+    pub fn size(&self) -> u64 {
+        self.entries.run_pending_tasks();
+        self.entries.weighted_size()
+    }
+
+    fn file_name(etag: &str, range: &Range<u64>) -> String {
+        let etag = etag
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        format!("{}_{}_{}", etag, range.start, range.end)
+    }
+
+    async fn read(&self, name: &str) -> Option<Bytes> {
+        self.entries.get(name)?;
+        match tokio::fs::read(self.dir.join(name)).await {
+            Ok(data) => Some(data.into()),
+            Err(err) => {
+                log::debug!("Failed to read cached range {}: {}", name, err);
+                self.entries.invalidate(name);
+                None
+            }
+        }
+    }
+
+    async fn write(&self, name: String, data: &Bytes) {
+        let path = self.dir.join(&name);
+        let temp_path = self
+            .dir
+            .join(format!("{}.{}{}", name, rand::random::<u32>(), TEMP_SUFFIX));
+        let res = async {
+            tokio::fs::write(&temp_path, data).await?;
+            tokio::fs::rename(&temp_path, &path).await
+        }
+        .await;
+        match res {
+            Ok(()) => self.entries.insert(name, data.len() as u64),
+            Err(err) => {
+                log::warn!("Failed to write cached range {}: {}", name, err);
+                let _ = tokio::fs::remove_file(&temp_path).await;
+            }
+        }
+    }
+}
+
+impl WrappingObjectStore for DiskCache {
+    fn wrap(
+        &self,
+        original: Arc<dyn object_store::ObjectStore>,
+    ) -> Arc<dyn object_store::ObjectStore> {
+        Arc::new(DiskCachedObjectStore {
+            target: original,
+            cache: self.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct DiskCachedObjectStore {
+    target: Arc<dyn object_store::ObjectStore>,
+    cache: DiskCache,
+}
+
+impl std::fmt::Display for DiskCachedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DiskCachedObjectStore({})", self.target)
+    }
+}
+
+impl DiskCachedObjectStore {
+    async fn meta(&self, location: &Path) -> OSResult<ObjectMeta> {
+        if let Some(meta) = self.cache.metas.get(location) {
+            return Ok(meta);
+        }
+        let meta = self.target.head(location).await?;
+        self.cache.metas.insert(location.clone(), meta.clone());
+        Ok(meta)
+    }
+
+    async fn get_range_cached(&self, location: &Path, range: Range<u64>) -> OSResult<GetResult> {
+        let meta = self.meta(location).await?;
+        let Some(etag) = meta.e_tag.clone() else {
+            return self.get_uncached(location, range).await;
+        };
+        let name = DiskCache::file_name(&etag, &range);
+        let data = match self.cache.read(&name).await {
+            Some(data) => data,
+            None => {
+                let options = GetOptions {
+                    range: Some(range.clone().into()),
+                    if_match: Some(etag),
+                    ..Default::default()
+                };
+                let data = match self.target.get_opts(location, options).await {
+                    Ok(result) => result.bytes().await?,
+                    Err(object_store::Error::Precondition { .. }) => {
+                        // The object was replaced by another writer.
+                        self.forget(location);
+                        return self.get_uncached(location, range).await;
+                    }
+                    Err(err) => return Err(err),
+                };
+                self.cache.write(name, &data).await;
+                data
+            }
+        };
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(
+                futures::stream::once(futures::future::ready(Ok(data.clone()))).boxed(),
+            ),
+            meta,
+            range: range.start..range.start + data.len() as u64,
+            attributes: Default::default(),
+        })
+    }
+
+    async fn get_uncached(&self, location: &Path, range: Range<u64>) -> OSResult<GetResult> {
+        let options = GetOptions {
+            range: Some(range.into()),
+            ..Default::default()
+        };
+        self.target.get_opts(location, options).await
+    }
+
+    fn forget(&self, location: &Path) {
+        self.cache.metas.invalidate(location);
+    }
+}
+
+#[async_trait::async_trait]
+impl object_store::ObjectStore for DiskCachedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        self.forget(location);
+        self.target.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        self.forget(location);
+        self.target.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        // Only plain range reads are cached.
+        match &options {
+            GetOptions {
+                range: Some(GetRange::Bounded(range)),
+                if_match: None,
+                if_none_match: None,
+                if_modified_since: None,
+                if_unmodified_since: None,
+                version: None,
+                head: false,
+                ..
+            } => self.get_range_cached(location, range.clone()).await,
+            _ => self.target.get_opts(location, options).await,
+        }
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        self.forget(location);
+        self.target.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+        self.target.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(to);
+        self.target.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(from);
+        self.forget(to);
+        self.target.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(to);
+        self.target.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(from);
+        self.forget(to);
+        self.target.rename_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disk_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote: Arc<dyn object_store::ObjectStore> = Arc::new(InMemory::new());
+        let path = Path::from("data/file.lance");
+        remote
+            .put(&path, Bytes::from_static(b"0123456789").into())
+            .await
+            .unwrap();
+
+        let cache = DiskCache::try_new(dir.path(), 8).unwrap();
+        let store = cache.wrap(remote.clone());
+        assert_eq!(
+            store.get_range(&path, 2..5).await.unwrap(),
+            Bytes::from_static(b"234")
+        );
+        assert_eq!(cache.size(), 3);
+
+        // Served from disk, even once the object is gone.
+        remote.delete(&path).await.unwrap();
+        assert_eq!(
+            store.get_range(&path, 2..5).await.unwrap(),
+            Bytes::from_static(b"234")
+        );
+
+        // Entries survive a restart and the least recently used are evicted.
+        let cache = DiskCache::try_new(dir.path(), 8).unwrap();
+        assert_eq!(cache.size(), 3);
+        let store = cache.wrap(remote.clone());
+        remote
+            .put(&path, Bytes::from_static(b"abcdefghij").into())
+            .await
+            .unwrap();
+        store.get_range(&path, 0..4).await.unwrap();
+        store.get_range(&path, 4..8).await.unwrap();
+        assert_eq!(cache.size(), 8);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
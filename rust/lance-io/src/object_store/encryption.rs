@@ -0,0 +1,798 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Client-side encryption of objects
+//!
+//! Wrapping a store with an [`EncryptedStore`] encrypts every object before it
+//! is uploaded and decrypts it again on read, so the storage service only
+//! ever sees ciphertext.
+//!
+//! ```ignore
+//! let store = EncryptedStore::new(kms, "arn:aws:kms:us-east-1:111122223333:key/my-key");
+//! let params = ObjectStoreParams {
+//!     object_store_wrapper: Some(Arc::new(store)),
+//!     ..Default::default()
+//! };
+//! ```
+//!
+//! Each object gets its own random data key, which is encrypted ("wrapped") by
+//! a [`KeyManagementService`] and kept, along with the id of the master key,
+//! in a fixed size header at the start of the object.  The data follows as a sequence of AES-256-GCM chunks of
+//! [`CHUNK_SIZE`] bytes, so that byte ranges can be read and authenticated
+//! without downloading the whole object.  The nonce of each chunk is a random
+//! per-object prefix followed by the chunk index, and the index of the chunk
+//! and whether it is the last one are authenticated as well, so chunks can't
+//! be reordered and truncation is detected.
+//!
+//! All objects in the wrapped store are expected to be encrypted.  Like the
+//! [disk cache](super::disk_cache), the key and size of each path are
+//! remembered, so ranged reads don't need a HEAD request each time, which
+//! assumes objects are only replaced through the wrapped store.  Local files
+//! are read without going through the object store, so this only works for
+//! remote (and in-memory) stores.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
+use moka::sync::Cache;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result as OSResult, UploadPart,
+};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use snafu::location;
+
+pub use super::kms::KeyManagementService;
+use super::WrappingObjectStore;
+use lance_core::{Error, Result};
+
+/// Number of plaintext bytes in each encrypted chunk.
+pub const CHUNK_SIZE: u64 = 4096;
+/// Size of the header at the start of every object.
+pub const HEADER_SIZE: u64 = 512;
+
+const MAGIC: &[u8; 8] = b"LANCEENC";
+const VERSION: u8 = 1;
+const KEY_LEN: usize = 32;
+const TAG_SIZE: u64 = 16;
+const NONCE_PREFIX_SIZE: usize = 8;
+/// Number of objects whose data key and metadata are remembered.
+const MAX_CACHED_OBJECTS: u64 = 10_000;
+
+fn encryption_error(message: impl Into<String>) -> Error {
+    Error::Encryption {
+        message: message.into(),
+        location: location!(),
+    }
+}
+
+fn to_os_error(err: Error) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "EncryptedStore",
+        source: Box::new(err),
+    }
+}
+
+/// Whether an object of `encrypted_size` bytes can be a complete encrypted
+/// object.  The last chunk always holds at least its tag, so this catches
+/// objects that were cut off at a chunk boundary.
+fn is_complete_size(encrypted_size: u64) -> bool {
+    encrypted_size >= HEADER_SIZE + TAG_SIZE
+        && (encrypted_size - HEADER_SIZE) % (CHUNK_SIZE + TAG_SIZE) >= TAG_SIZE
+}
+
+/// Size of the plaintext of an object with `encrypted_size` bytes.
+pub fn plaintext_size(encrypted_size: u64) -> u64 {
+    // Every chunk but the last one is full, and the last one holds the
+    // remainder (possibly nothing).
+    let body = encrypted_size.saturating_sub(HEADER_SIZE + TAG_SIZE);
+    let full_chunks = body / (CHUNK_SIZE + TAG_SIZE);
+    full_chunks * CHUNK_SIZE + body % (CHUNK_SIZE + TAG_SIZE)
+}
+
+/// The data key and nonce prefix of one object.
+struct FileKey {
+    key: LessSafeKey,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+}
+
+impl std::fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the key material
+        f.debug_struct("FileKey").finish_non_exhaustive()
+    }
+}
+
+impl FileKey {
+    fn try_new(raw: &[u8], nonce_prefix: [u8; NONCE_PREFIX_SIZE]) -> Result<Self> {
+        let key =
+            UnboundKey::new(&AES_256_GCM, raw).map_err(|_| encryption_error("invalid data key"))?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            nonce_prefix,
+        })
+    }
+
+    /// Generate a new random key, also returning the raw data key
+    fn generate() -> Result<(Self, [u8; KEY_LEN])> {
+        let rng = SystemRandom::new();
+        let mut raw = [0; KEY_LEN];
+        let mut nonce_prefix = [0; NONCE_PREFIX_SIZE];
+        rng.fill(&mut raw)
+            .and_then(|_| rng.fill(&mut nonce_prefix))
+            .map_err(|_| encryption_error("failed to generate a data key"))?;
+        Ok((Self::try_new(&raw, nonce_prefix)?, raw))
+    }
+
+    fn nonce(&self, index: u64) -> Nonce {
+        let mut nonce = [0; NONCE_LEN];
+        nonce[..NONCE_PREFIX_SIZE].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_SIZE..].copy_from_slice(&(index as u32).to_be_bytes());
+        Nonce::assume_unique_for_key(nonce)
+    }
+
+    fn aad(index: u64, is_last: bool) -> Aad<[u8; 9]> {
+        let mut aad = [0; 9];
+        aad[..8].copy_from_slice(&index.to_le_bytes());
+        aad[8] = is_last as u8;
+        Aad::from(aad)
+    }
+
+    fn encrypt_chunk(&self, index: u64, is_last: bool, data: &[u8]) -> Result<Vec<u8>> {
+        // The nonce only has room for a 32-bit chunk index
+        if index > u32::MAX as u64 {
+            return Err(Error::invalid_input(
+                "object is too large to encrypt",
+                location!(),
+            ));
+        }
+        let mut sealed = Vec::with_capacity(data.len() + TAG_SIZE as usize);
+        sealed.extend_from_slice(data);
+        self.key
+            .seal_in_place_append_tag(self.nonce(index), Self::aad(index, is_last), &mut sealed)
+            .map_err(|_| encryption_error("failed to encrypt chunk"))?;
+        Ok(sealed)
+    }
+
+    fn decrypt_chunk(
+        &self,
+        path: &Path,
+        index: u64,
+        is_last: bool,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut in_out = data.to_vec();
+        let plaintext_len = self
+            .key
+            .open_in_place(self.nonce(index), Self::aad(index, is_last), &mut in_out)
+            .map_err(|_| {
+                encryption_error(format!(
+                    "failed to decrypt chunk {} of {}, the object may be corrupt",
+                    index, path
+                ))
+            })?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(in_out)
+    }
+
+    /// The header of an object, which records the wrapped data key
+    fn header(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Bytes> {
+        let mut header = BytesMut::with_capacity(HEADER_SIZE as usize);
+        header.put_slice(MAGIC);
+        header.put_u8(VERSION);
+        header.put_u32_le(CHUNK_SIZE as u32);
+        header.put_slice(&self.nonce_prefix);
+        for field in [key_id.as_bytes(), wrapped_key] {
+            header.put_u16_le(field.len().try_into().unwrap_or(u16::MAX));
+            header.put_slice(field);
+        }
+        if header.len() > HEADER_SIZE as usize {
+            return Err(Error::invalid_input(
+                format!(
+                    "key id and wrapped data key of {} bytes do not fit in the encryption header",
+                    key_id.len() + wrapped_key.len()
+                ),
+                location!(),
+            ));
+        }
+        header.resize(HEADER_SIZE as usize, 0);
+        Ok(header.freeze())
+    }
+
+    async fn from_header(
+        kms: &dyn KeyManagementService,
+        path: &Path,
+        mut header: &[u8],
+    ) -> Result<Self> {
+        let invalid = |message: &str| Error::corrupt_file(path.clone(), message, location!());
+        if header.len() != HEADER_SIZE as usize || !header.starts_with(MAGIC) {
+            return Err(invalid("not an encrypted object"));
+        }
+        header.advance(MAGIC.len());
+        if header.get_u8() != VERSION {
+            return Err(invalid("unsupported encryption version"));
+        }
+        if header.get_u32_le() as u64 != CHUNK_SIZE {
+            return Err(invalid("unsupported encryption chunk size"));
+        }
+        let mut nonce_prefix = [0; NONCE_PREFIX_SIZE];
+        header.copy_to_slice(&mut nonce_prefix);
+        let mut fields = [&[][..]; 2];
+        for field in fields.iter_mut() {
+            if header.remaining() < 2 {
+                return Err(invalid("truncated encryption header"));
+            }
+            let len = header.get_u16_le() as usize;
+            if header.remaining() < len {
+                return Err(invalid("truncated encryption header"));
+            }
+            *field = &header[..len];
+            header.advance(len);
+        }
+        let [key_id, wrapped_key] = fields;
+        let key_id = std::str::from_utf8(key_id).map_err(|_| invalid("invalid key id"))?;
+        let raw = kms.unwrap_key(key_id, wrapped_key).await?;
+        if raw.len() != KEY_LEN {
+            return Err(encryption_error(format!(
+                "the key management service returned a data key of {} bytes, expected {}",
+                raw.len(),
+                KEY_LEN
+            )));
+        }
+        Self::try_new(&raw, nonce_prefix)
+    }
+}
+
+/// Encrypts the chunks of an object as the plaintext comes in.
+struct ChunkEncryptor {
+    key: FileKey,
+    next_index: u64,
+    buffer: BytesMut,
+}
+
+impl ChunkEncryptor {
+    /// Add plaintext, returning the encrypted full chunks.
+    fn push(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let num_full = self.buffer.len() as u64 / CHUNK_SIZE;
+        let mut out = Vec::with_capacity((num_full * (CHUNK_SIZE + TAG_SIZE)) as usize);
+        for _ in 0..num_full {
+            let chunk = self.buffer.split_to(CHUNK_SIZE as usize);
+            out.extend(self.key.encrypt_chunk(self.next_index, false, &chunk)?);
+            self.next_index += 1;
+        }
+        Ok(out)
+    }
+
+    /// Encrypt the remaining plaintext as the last chunk.
+    fn finish(&mut self) -> Result<Vec<u8>> {
+        let chunk = self.buffer.split();
+        self.key.encrypt_chunk(self.next_index, true, &chunk)
+    }
+}
+
+/// A [`WrappingObjectStore`] that encrypts objects on the client.
+///
+/// See the [module documentation](self) for the format.
+#[derive(Debug, Clone)]
+pub struct EncryptedStore {
+    kms: Arc<dyn KeyManagementService>,
+    /// The master key that wraps the data keys of new objects.
+    key_id: String,
+    keys: Cache<Path, Arc<FileKey>>,
+    /// The metadata of the encrypted objects, which is needed to find the
+    /// chunks of a range.
+    metas: Cache<Path, ObjectMeta>,
+}
+
+impl EncryptedStore {
+    /// Encrypt new objects with data keys wrapped by the master key `key_id`.
+    ///
+    /// Existing objects are decrypted with whichever master key they were
+    /// written with, so the key can be rotated by changing `key_id`.
+    pub fn new(kms: Arc<dyn KeyManagementService>, key_id: impl Into<String>) -> Self {
+        Self {
+            kms,
+            key_id: key_id.into(),
+            keys: Cache::new(MAX_CACHED_OBJECTS),
+            metas: Cache::new(MAX_CACHED_OBJECTS),
+        }
+    }
+}
+
+impl EncryptedStore {
+    fn forget(&self, location: &Path) {
+        self.keys.invalidate(location);
+        self.metas.invalidate(location);
+    }
+}
+
+impl WrappingObjectStore for EncryptedStore {
+    fn wrap(
+        &self,
+        original: Arc<dyn object_store::ObjectStore>,
+    ) -> Arc<dyn object_store::ObjectStore> {
+        Arc::new(EncryptedObjectStore {
+            target: original,
+            encryption: self.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct EncryptedObjectStore {
+    target: Arc<dyn object_store::ObjectStore>,
+    encryption: EncryptedStore,
+}
+
+impl std::fmt::Display for EncryptedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptedObjectStore({})", self.target)
+    }
+}
+
+fn plaintext_meta(mut meta: ObjectMeta) -> ObjectMeta {
+    meta.size = plaintext_size(meta.size);
+    meta
+}
+
+impl EncryptedObjectStore {
+    async fn new_encryptor(&self, location: &Path) -> OSResult<(ChunkEncryptor, Bytes)> {
+        let encryption = &self.encryption;
+        let (key, raw_key) = FileKey::generate().map_err(to_os_error)?;
+        let wrapped_key = encryption
+            .kms
+            .wrap_key(&encryption.key_id, &raw_key)
+            .await
+            .map_err(to_os_error)?;
+        let header = key
+            .header(&encryption.key_id, &wrapped_key)
+            .map_err(to_os_error)?;
+        self.forget(location);
+        Ok((
+            ChunkEncryptor {
+                key,
+                next_index: 0,
+                buffer: BytesMut::new(),
+            },
+            header,
+        ))
+    }
+
+    async fn key(&self, location: &Path) -> OSResult<Arc<FileKey>> {
+        if let Some(key) = self.encryption.keys.get(location) {
+            return Ok(key);
+        }
+        let header = self.target.get_range(location, 0..HEADER_SIZE).await?;
+        let key = FileKey::from_header(self.encryption.kms.as_ref(), location, &header)
+            .await
+            .map_err(to_os_error)?;
+        let key = Arc::new(key);
+        self.encryption.keys.insert(location.clone(), key.clone());
+        Ok(key)
+    }
+
+    /// The metadata of the encrypted object, using a HEAD request only the
+    /// first time the object is read.
+    async fn meta(&self, location: &Path) -> OSResult<ObjectMeta> {
+        if let Some(meta) = self.encryption.metas.get(location) {
+            return Ok(meta);
+        }
+        let meta = self.target.head(location).await?;
+        self.encryption.metas.insert(location.clone(), meta.clone());
+        Ok(meta)
+    }
+
+    fn forget(&self, location: &Path) {
+        self.encryption.forget(location);
+    }
+
+    /// Read and decrypt a range of the plaintext.
+    async fn read_range(
+        &self,
+        location: &Path,
+        range: Range<u64>,
+        options: GetOptions,
+        meta: &ObjectMeta,
+    ) -> OSResult<Bytes> {
+        if !is_complete_size(meta.size) {
+            return Err(to_os_error(Error::corrupt_file(
+                location.clone(),
+                format!("encrypted object has an invalid size of {}", meta.size),
+                location!(),
+            )));
+        }
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+        let key = self.key(location).await?;
+        let size = plaintext_size(meta.size);
+        let last_index = size / CHUNK_SIZE;
+        let first = range.start / CHUNK_SIZE;
+        let last = (range.end - 1) / CHUNK_SIZE;
+        let encrypted_start = HEADER_SIZE + first * (CHUNK_SIZE + TAG_SIZE);
+        let encrypted_end = (HEADER_SIZE + (last + 1) * (CHUNK_SIZE + TAG_SIZE)).min(meta.size);
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(encrypted_start..encrypted_end)),
+            ..options
+        };
+        let encrypted = self
+            .target
+            .get_opts(location, options)
+            .await?
+            .bytes()
+            .await?;
+
+        let mut plaintext = Vec::with_capacity((range.end - range.start) as usize);
+        for (offset, chunk) in encrypted
+            .chunks((CHUNK_SIZE + TAG_SIZE) as usize)
+            .enumerate()
+        {
+            let index = first + offset as u64;
+            let decrypted = key
+                .decrypt_chunk(location, index, index == last_index, chunk)
+                .map_err(to_os_error)?;
+            plaintext.extend(decrypted);
+        }
+        let skip = (range.start - first * CHUNK_SIZE) as usize;
+        let len = (range.end - range.start) as usize;
+        if plaintext.len() < skip + len {
+            return Err(to_os_error(Error::corrupt_file(
+                location.clone(),
+                "encrypted object is truncated",
+                location!(),
+            )));
+        }
+        Ok(Bytes::from(plaintext).slice(skip..skip + len))
+    }
+}
+
+#[async_trait::async_trait]
+impl object_store::ObjectStore for EncryptedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        let (mut encryptor, header) = self.new_encryptor(location).await?;
+        let mut encrypted = header.to_vec();
+        for part in payload.iter() {
+            encrypted.extend(encryptor.push(part).map_err(to_os_error)?);
+        }
+        encrypted.extend(encryptor.finish().map_err(to_os_error)?);
+        let result = self
+            .target
+            .put_opts(location, Bytes::from(encrypted).into(), opts)
+            .await;
+        // A read while the object was being written may have remembered the
+        // old size
+        self.forget(location);
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        let (encryptor, header) = self.new_encryptor(location).await?;
+        let target = self.target.put_multipart_opts(location, opts).await?;
+        Ok(Box::new(EncryptedMultipartUpload {
+            target,
+            encryptor,
+            header: Some(header),
+            encryption: self.encryption.clone(),
+            location: location.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        if options.head {
+            return self
+                .target
+                .get_opts(location, options)
+                .await
+                .map(|result| GetResult {
+                    meta: plaintext_meta(result.meta),
+                    range: 0..0,
+                    ..result
+                });
+        }
+        let meta = self.meta(location).await?;
+        let size = plaintext_size(meta.size);
+        let range = match &options.range {
+            None => 0..size,
+            Some(GetRange::Bounded(range)) => range.start.min(size)..range.end.min(size),
+            Some(GetRange::Offset(offset)) => (*offset).min(size)..size,
+            Some(GetRange::Suffix(suffix)) => size.saturating_sub(*suffix)..size,
+        };
+        let data = self
+            .read_range(location, range.clone(), options, &meta)
+            .await?;
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(
+                futures::stream::once(futures::future::ready(Ok(data))).boxed(),
+            ),
+            meta: plaintext_meta(meta),
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    async fn head(&self, location: &Path) -> OSResult<ObjectMeta> {
+        self.target.head(location).await.map(plaintext_meta)
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        self.forget(location);
+        self.target.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target
+            .list(prefix)
+            .map(|meta| meta.map(plaintext_meta))
+            .boxed()
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target
+            .list_with_offset(prefix, offset)
+            .map(|meta| meta.map(plaintext_meta))
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+        let mut result = self.target.list_with_delimiter(prefix).await?;
+        result.objects = result.objects.into_iter().map(plaintext_meta).collect();
+        Ok(result)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(to);
+        self.target.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(from);
+        self.forget(to);
+        self.target.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(to);
+        self.target.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.forget(from);
+        self.forget(to);
+        self.target.rename_if_not_exists(from, to).await
+    }
+}
+
+#[derive(Debug)]
+struct EncryptedMultipartUpload {
+    target: Box<dyn MultipartUpload>,
+    encryptor: ChunkEncryptor,
+    // Sent with the first part
+    header: Option<Bytes>,
+    encryption: EncryptedStore,
+    location: Path,
+}
+
+impl std::fmt::Debug for ChunkEncryptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkEncryptor")
+            .field("next_index", &self.next_index)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl MultipartUpload for EncryptedMultipartUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        // Chunks are small, so a part of at least the minimum part size (5MiB on
+        // S3) still produces at least that many encrypted bytes.
+        let mut encrypted = self.header.take().map(Vec::from).unwrap_or_default();
+        for part in data.iter() {
+            match self.encryptor.push(part) {
+                Ok(chunks) => encrypted.extend(chunks),
+                Err(err) => return futures::future::ready(Err(to_os_error(err))).boxed(),
+            }
+        }
+        if encrypted.is_empty() {
+            return futures::future::ready(Ok(())).boxed();
+        }
+        self.target.put_part(Bytes::from(encrypted).into())
+    }
+
+    async fn complete(&mut self) -> OSResult<PutResult> {
+        let mut last = self.header.take().map(Vec::from).unwrap_or_default();
+        last.extend(self.encryptor.finish().map_err(to_os_error)?);
+        self.target.put_part(Bytes::from(last).into()).await?;
+        let result = self.target.complete().await;
+        self.encryption.forget(&self.location);
+        result
+    }
+
+    async fn abort(&mut self) -> OSResult<()> {
+        self.target.abort().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use object_store::memory::InMemory;
+    use object_store::{ObjectStore as _, WriteMultipart};
+
+    use super::super::metrics::{ObjectStoreMetrics, ObjectStoreMetricsExt, Verb};
+    use super::super::retry::ErrorClass;
+
+    use super::*;
+
+    /// Wraps keys by xor-ing them with the key id, for testing only
+    #[derive(Debug)]
+    struct XorKms;
+
+    #[async_trait::async_trait]
+    impl KeyManagementService for XorKms {
+        async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>> {
+            let mask = key_id.as_bytes();
+            Ok(data_key
+                .iter()
+                .enumerate()
+                .map(|(i, b)| b ^ mask[i % mask.len()])
+                .collect())
+        }
+
+        async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>> {
+            self.wrap_key(key_id, wrapped_key).await
+        }
+    }
+
+    fn wrapped() -> (
+        Arc<dyn object_store::ObjectStore>,
+        Arc<dyn object_store::ObjectStore>,
+    ) {
+        let remote: Arc<dyn object_store::ObjectStore> = Arc::new(InMemory::new());
+        let store = EncryptedStore::new(Arc::new(XorKms), "key").wrap(remote.clone());
+        (remote, store)
+    }
+
+    #[test]
+    fn test_plaintext_size() {
+        for size in [
+            0,
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            10 * CHUNK_SIZE,
+        ] {
+            let full_chunks = size / CHUNK_SIZE;
+            let encrypted = HEADER_SIZE
+                + full_chunks * (CHUNK_SIZE + TAG_SIZE)
+                + (size % CHUNK_SIZE + TAG_SIZE);
+            assert_eq!(plaintext_size(encrypted), size, "{}", size);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        let (remote, store) = wrapped();
+        let path = Path::from("data/file.lance");
+        let data = (0..20_000_u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        store
+            .put(&path, Bytes::from(data.clone()).into())
+            .await
+            .unwrap();
+
+        // The plaintext doesn't appear in the stored object
+        let raw = remote.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(&raw[..8], MAGIC);
+        assert!(!raw.windows(64).any(|w| w == &data[1000..1064]));
+
+        assert_eq!(store.head(&path).await.unwrap().size, data.len() as u64);
+        let all = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(all.as_ref(), data.as_slice());
+        for range in [0..1, 4000..4200, 4096..8192, 19_990..20_000] {
+            let bytes = store.get_range(&path, range.clone()).await.unwrap();
+            assert_eq!(
+                bytes.as_ref(),
+                &data[range.start as usize..range.end as usize]
+            );
+        }
+
+        // Tampering is detected
+        let mut tampered = raw.to_vec();
+        tampered[HEADER_SIZE as usize + 10] ^= 1;
+        remote
+            .put(&path, Bytes::from(tampered).into())
+            .await
+            .unwrap();
+        assert!(store.get_range(&path, 0..10).await.is_err());
+
+        // So is truncation, whether at a chunk boundary or not
+        for len in [
+            HEADER_SIZE + CHUNK_SIZE + TAG_SIZE,
+            HEADER_SIZE + CHUNK_SIZE + 100,
+        ] {
+            let truncated = raw.slice(..len as usize);
+            remote.put(&path, truncated.into()).await.unwrap();
+            assert!(store.get(&path).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ranged_reads_head_once() {
+        #[derive(Debug, Default)]
+        struct HeadCounter(AtomicUsize);
+
+        impl ObjectStoreMetrics for HeadCounter {
+            fn on_request(&self, verb: Verb, _latency: Duration, _error: Option<ErrorClass>) {
+                if verb == Verb::Head {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let heads = Arc::new(HeadCounter::default());
+        let remote = (Arc::new(InMemory::new()) as Arc<dyn object_store::ObjectStore>)
+            .with_metrics(heads.clone());
+        let store = EncryptedStore::new(Arc::new(XorKms), "key").wrap(remote);
+        let path = Path::from("data/file.lance");
+        let data = (0..20_000_u32).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+        store
+            .put(&path, Bytes::from(data.clone()).into())
+            .await
+            .unwrap();
+
+        for range in [0..10, 5000..9000, 19_000..20_000] {
+            let bytes = store.get_range(&path, range.clone()).await.unwrap();
+            assert_eq!(
+                bytes.as_ref(),
+                &data[range.start as usize..range.end as usize]
+            );
+        }
+        assert_eq!(heads.0.load(Ordering::Relaxed), 1);
+
+        // Replacing the object forgets its size
+        store
+            .put(&path, Bytes::from(data[..100].to_vec()).into())
+            .await
+            .unwrap();
+        let all = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(all.as_ref(), &data[..100]);
+        assert_eq!(heads.0.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_multipart() {
+        let (_, store) = wrapped();
+        let path = Path::from("data/multipart.lance");
+        let data = (0..50_000_u32).map(|i| (i % 13) as u8).collect::<Vec<_>>();
+        let upload = store.put_multipart(&path).await.unwrap();
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, 3000);
+        writer.write(&data);
+        writer.finish().await.unwrap();
+
+        assert_eq!(store.head(&path).await.unwrap().size, data.len() as u64);
+        let all = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(all.as_ref(), data.as_slice());
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! HTTP client settings shared by the cloud object stores
+//!
+//! All stores accept the client options of `object_store` as storage options,
+//! among them `pool_max_idle_per_host`, `pool_idle_timeout`, `timeout`,
+//! `connect_timeout`, `http1_only`, `http2_only`, `http2_keep_alive_interval`,
+//! `proxy_url`, `proxy_ca_certificate`, `proxy_excludes`,
+//! `allow_invalid_certificates` and `user_agent`.  On top of those Lance
+//! supports:
+//!
+//! * `http2`: if true, HTTP/2 is used when the server supports it.  Clients
+//!   only speak HTTP/1.1 by default.
+//! * `ca_bundle`: path of a PEM file with additional root certificates to
+//!   trust, e.g. the certificate of a TLS-intercepting proxy.  Also read from
+//!   the `LANCE_CA_BUNDLE` environment variable.
+//!
+//! Idle connections are kept alive with the `http2_keep_alive_interval`,
+//! `http2_keep_alive_timeout` and `http2_keep_alive_while_idle` options, and
+//! closed after `pool_idle_timeout`.
+
+use std::str::FromStr;
+
+use lance_core::utils::parse::str_is_truthy;
+use lance_core::{Error, Result};
+use object_store::{ClientConfigKey, ClientOptions};
+use snafu::location;
+
+use super::StorageOptions;
+
+const CA_BUNDLE_ENV: &str = "LANCE_CA_BUNDLE";
+
+impl StorageOptions {
+    fn find(&self, name: &str) -> Option<&String> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Contents of the `ca_bundle` file, if one is configured
+    fn ca_bundle(&self) -> Result<Option<Vec<u8>>> {
+        let path = match self.find("ca_bundle") {
+            Some(path) => path.clone(),
+            None => match std::env::var(CA_BUNDLE_ENV) {
+                Ok(path) => path,
+                Err(_) => return Ok(None),
+            },
+        };
+        std::fs::read(&path).map(Some).map_err(|err| {
+            Error::invalid_input(
+                format!("failed to read CA bundle {}: {}", path, err),
+                location!(),
+            )
+        })
+    }
+
+    /// Options for the HTTP client of a cloud object store
+    pub fn client_options(&self) -> Result<ClientOptions> {
+        let mut options = ClientOptions::new();
+        for (key, value) in &self.0 {
+            if let Ok(key) = ClientConfigKey::from_str(&key.to_ascii_lowercase()) {
+                options = options.with_config(key, value);
+            }
+        }
+        if self.find("http2").is_some_and(|value| str_is_truthy(value)) {
+            options = options.with_allow_http2();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(bundle) = self.ca_bundle()? {
+            let certificates =
+                object_store::Certificate::from_pem_bundle(&bundle).map_err(|err| {
+                    Error::invalid_input(format!("invalid CA bundle: {}", err), location!())
+                })?;
+            for certificate in certificates {
+                options = options.with_root_certificate(certificate);
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn options(pairs: &[(&str, &str)]) -> StorageOptions {
+        StorageOptions(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn test_client_options() {
+        let client_options = options(&[
+            ("pool_max_idle_per_host", "4"),
+            ("PROXY_URL", "http://proxy:3128"),
+            ("http2", "true"),
+            ("http2_keep_alive_interval", "30s"),
+            ("http2_keep_alive_while_idle", "true"),
+        ])
+        .client_options()
+        .unwrap();
+        let get = |key| client_options.get_config_value(&key);
+        assert_eq!(get(ClientConfigKey::PoolMaxIdlePerHost).unwrap(), "4");
+        assert_eq!(get(ClientConfigKey::ProxyUrl).unwrap(), "http://proxy:3128");
+        assert_eq!(get(ClientConfigKey::Http1Only).unwrap(), "false");
+        assert_eq!(get(ClientConfigKey::Http2KeepAliveInterval).unwrap(), "30s");
+        assert_eq!(
+            get(ClientConfigKey::Http2KeepAliveWhileIdle).unwrap(),
+            "true"
+        );
+
+        let client_options = options(&[]).client_options().unwrap();
+        assert_eq!(
+            client_options
+                .get_config_value(&ClientConfigKey::Http1Only)
+                .unwrap(),
+            "true"
+        );
+
+        let err = options(&[("ca_bundle", "/does/not/exist.pem")])
+            .client_options()
+            .unwrap_err();
+        assert!(err.to_string().contains("CA bundle"), "{}", err);
+    }
+}
@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Key management for encrypted objects and columns
+//!
+//! This is kept apart from [`super::encryption`], which is not available on
+//! wasm, so that encrypted columns can still be read there.
+
+use lance_core::Result;
+
+/// A key management service (KMS) that protects data keys
+///
+/// Data keys are never stored in plain form.  The writer asks the KMS to wrap
+/// each data key with the master key identified by `key_id` and the reader asks
+/// the KMS to unwrap it again.  Access control is up to the implementation: a
+/// caller that is not authorized to use a master key should get an error from
+/// [`Self::unwrap_key`].
+#[async_trait::async_trait]
+pub trait KeyManagementService: std::fmt::Debug + Send + Sync {
+    /// Encrypt `data_key` with the master key `key_id`
+    async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decrypt a data key previously returned by [`Self::wrap_key`]
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>>;
+}
@@ -7,6 +7,8 @@ use futures::stream::BoxStream;
 use futures::{Stream, StreamExt};
 use object_store::{path::Path, ObjectMeta, ObjectStore};
 
+use super::retry::ErrorClass;
+
 /// A stream that does outer retries on list operations.
 ///
 /// This is to handle request responses that ObjectStore doesn't handle, such as
@@ -38,13 +40,7 @@ impl ListRetryStream {
     }
 
     fn is_retryable(error: &object_store::Error) -> bool {
-        !matches!(
-            error,
-            object_store::Error::NotFound { .. }
-                | object_store::Error::InvalidPath { .. }
-                | object_store::Error::NotSupported { .. }
-                | object_store::Error::NotImplemented
-        )
+        ErrorClass::of(error) != ErrorClass::Permanent
     }
 }
 
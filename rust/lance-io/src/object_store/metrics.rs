@@ -0,0 +1,473 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Hooks for exporting object store metrics
+//!
+//! Applications implement [`ObjectStoreMetrics`] and set it in
+//! [`super::ObjectStoreParams::metrics`] to feed the requests Lance makes into
+//! their own metrics system, e.g. Prometheus or OpenTelemetry.  Every request
+//! sent to the store is reported, so a request that is retried is reported once
+//! per attempt, with the retries themselves reported separately.
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{Future, StreamExt};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, PutMultipartOpts, PutOptions,
+    PutPayload, PutResult, Result as OSResult, UploadPart,
+};
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use super::retry::ErrorClass;
+
+/// The kind of request made to an object store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verb {
+    Get,
+    Head,
+    Put,
+    CreateMultipart,
+    PutPart,
+    CompleteMultipart,
+    AbortMultipart,
+    List,
+    Delete,
+    Copy,
+    Rename,
+}
+
+impl Verb {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "get",
+            Self::Head => "head",
+            Self::Put => "put",
+            Self::CreateMultipart => "create_multipart",
+            Self::PutPart => "put_part",
+            Self::CompleteMultipart => "complete_multipart",
+            Self::AbortMultipart => "abort_multipart",
+            Self::List => "list",
+            Self::Delete => "delete",
+            Self::Copy => "copy",
+            Self::Rename => "rename",
+        }
+    }
+}
+
+impl std::fmt::Display for Verb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Receives the metrics of an object store
+///
+/// All methods do nothing by default.  They are called on the I/O path, so
+/// implementations should be cheap, e.g. update atomic counters or histograms.
+pub trait ObjectStoreMetrics: Debug + Send + Sync {
+    /// Called when a request finishes
+    ///
+    /// `error` is the class of the error if the request failed.  For gets the
+    /// latency is the time until the response started, and for listings it is
+    /// the time until the listing was consumed.
+    fn on_request(&self, _verb: Verb, _latency: Duration, _error: Option<ErrorClass>) {}
+
+    /// Called with the number of bytes returned by a successful get
+    fn on_bytes_read(&self, _num_bytes: u64) {}
+
+    /// Called with the number of bytes sent by a successful put or part upload
+    fn on_bytes_written(&self, _num_bytes: u64) {}
+
+    /// Called when a failed request is retried after `delay` by the store's
+    /// [`super::retry::RetryPolicy`]
+    fn on_retry(&self, _verb: Verb, _class: ErrorClass, _delay: Duration) {}
+
+    /// Called when the store rejected a request because of rate limiting
+    fn on_throttled(&self, _verb: Verb) {}
+}
+
+fn error_class<T>(result: &OSResult<T>) -> Option<ErrorClass> {
+    result.as_ref().err().map(ErrorClass::of)
+}
+
+fn record(
+    metrics: &dyn ObjectStoreMetrics,
+    verb: Verb,
+    latency: Duration,
+    class: Option<ErrorClass>,
+) {
+    if class == Some(ErrorClass::Throttled) {
+        metrics.on_throttled(verb);
+    }
+    metrics.on_request(verb, latency, class);
+}
+
+/// Reports a listing once its stream is dropped
+struct ListRecorder {
+    metrics: Arc<dyn ObjectStoreMetrics>,
+    start: Instant,
+    error: Option<ErrorClass>,
+}
+
+impl ListRecorder {
+    fn observe(
+        metrics: Arc<dyn ObjectStoreMetrics>,
+        stream: BoxStream<'static, OSResult<ObjectMeta>>,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        let mut recorder = Self {
+            metrics,
+            start: Instant::now(),
+            error: None,
+        };
+        // Calling a method moves the whole recorder into the closure, so it is
+        // dropped with the stream rather than when this function returns
+        stream.inspect(move |item| recorder.inspect(item)).boxed()
+    }
+
+    fn inspect(&mut self, item: &OSResult<ObjectMeta>) {
+        if let Err(err) = item {
+            self.error = Some(ErrorClass::of(err));
+        }
+    }
+}
+
+impl Drop for ListRecorder {
+    fn drop(&mut self) {
+        record(
+            self.metrics.as_ref(),
+            Verb::List,
+            self.start.elapsed(),
+            self.error,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct MetricsMultipartUpload {
+    target: Box<dyn MultipartUpload>,
+    metrics: Arc<dyn ObjectStoreMetrics>,
+}
+
+#[async_trait::async_trait]
+impl MultipartUpload for MetricsMultipartUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let num_bytes = data.content_length() as u64;
+        let fut = self.target.put_part(data);
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = fut.await;
+            record(
+                metrics.as_ref(),
+                Verb::PutPart,
+                start.elapsed(),
+                error_class(&result),
+            );
+            if result.is_ok() {
+                metrics.on_bytes_written(num_bytes);
+            }
+            result
+        })
+    }
+
+    async fn complete(&mut self) -> OSResult<PutResult> {
+        let start = Instant::now();
+        let result = self.target.complete().await;
+        record(
+            self.metrics.as_ref(),
+            Verb::CompleteMultipart,
+            start.elapsed(),
+            error_class(&result),
+        );
+        result
+    }
+
+    async fn abort(&mut self) -> OSResult<()> {
+        let start = Instant::now();
+        let result = self.target.abort().await;
+        record(
+            self.metrics.as_ref(),
+            Verb::AbortMultipart,
+            start.elapsed(),
+            error_class(&result),
+        );
+        result
+    }
+}
+
+/// An object store that reports each request to an [`ObjectStoreMetrics`]
+#[derive(Debug)]
+pub struct MetricsObjectStore {
+    target: Arc<dyn object_store::ObjectStore>,
+    metrics: Arc<dyn ObjectStoreMetrics>,
+}
+
+impl std::fmt::Display for MetricsObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("MetricsObjectStore({})", self.target))
+    }
+}
+
+impl MetricsObjectStore {
+    async fn observe<T>(&self, verb: Verb, fut: impl Future<Output = OSResult<T>>) -> OSResult<T> {
+        let start = Instant::now();
+        let result = fut.await;
+        record(
+            self.metrics.as_ref(),
+            verb,
+            start.elapsed(),
+            error_class(&result),
+        );
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl object_store::ObjectStore for MetricsObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        bytes: PutPayload,
+        opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        let num_bytes = bytes.content_length() as u64;
+        let result = self
+            .observe(Verb::Put, self.target.put_opts(location, bytes, opts))
+            .await?;
+        self.metrics.on_bytes_written(num_bytes);
+        Ok(result)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        let upload = self
+            .observe(
+                Verb::CreateMultipart,
+                self.target.put_multipart_opts(location, opts),
+            )
+            .await?;
+        Ok(Box::new(MetricsMultipartUpload {
+            target: upload,
+            metrics: self.metrics.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        let verb = if options.head { Verb::Head } else { Verb::Get };
+        let result = self
+            .observe(verb, self.target.get_opts(location, options))
+            .await?;
+        if verb == Verb::Get {
+            self.metrics
+                .on_bytes_read(result.range.end - result.range.start);
+        }
+        Ok(result)
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> OSResult<Bytes> {
+        let bytes = self
+            .observe(Verb::Get, self.target.get_range(location, range))
+            .await?;
+        self.metrics.on_bytes_read(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<u64>]) -> OSResult<Vec<Bytes>> {
+        let buffers = self
+            .observe(Verb::Get, self.target.get_ranges(location, ranges))
+            .await?;
+        self.metrics
+            .on_bytes_read(buffers.iter().map(|bytes| bytes.len() as u64).sum());
+        Ok(buffers)
+    }
+
+    async fn head(&self, location: &Path) -> OSResult<ObjectMeta> {
+        self.observe(Verb::Head, self.target.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        self.observe(Verb::Delete, self.target.delete(location))
+            .await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, OSResult<Path>>,
+    ) -> BoxStream<'a, OSResult<Path>> {
+        // Stores may batch deletes, so each deleted path is reported with the
+        // time since the previous one
+        let mut last = Instant::now();
+        self.target
+            .delete_stream(locations)
+            .inspect(move |result| {
+                record(
+                    self.metrics.as_ref(),
+                    Verb::Delete,
+                    last.elapsed(),
+                    error_class(result),
+                );
+                last = Instant::now();
+            })
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        ListRecorder::observe(self.metrics.clone(), self.target.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        ListRecorder::observe(
+            self.metrics.clone(),
+            self.target.list_with_offset(prefix, offset),
+        )
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+        self.observe(Verb::List, self.target.list_with_delimiter(prefix))
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.observe(Verb::Copy, self.target.copy(from, to)).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.observe(Verb::Rename, self.target.rename(from, to))
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.observe(Verb::Copy, self.target.copy_if_not_exists(from, to))
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.observe(Verb::Rename, self.target.rename_if_not_exists(from, to))
+            .await
+    }
+}
+
+pub trait ObjectStoreMetricsExt {
+    fn with_metrics(
+        self,
+        metrics: Arc<dyn ObjectStoreMetrics>,
+    ) -> Arc<dyn object_store::ObjectStore>;
+}
+
+impl ObjectStoreMetricsExt for Arc<dyn object_store::ObjectStore> {
+    fn with_metrics(
+        self,
+        metrics: Arc<dyn ObjectStoreMetrics>,
+    ) -> Arc<dyn object_store::ObjectStore> {
+        Arc::new(MetricsObjectStore {
+            target: self,
+            metrics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct CountingMetrics {
+        requests: Mutex<HashMap<(Verb, Option<ErrorClass>), usize>>,
+        bytes_read: Mutex<u64>,
+        bytes_written: Mutex<u64>,
+    }
+
+    impl CountingMetrics {
+        fn count(&self, verb: Verb, error: Option<ErrorClass>) -> usize {
+            *self
+                .requests
+                .lock()
+                .unwrap()
+                .get(&(verb, error))
+                .unwrap_or(&0)
+        }
+    }
+
+    impl ObjectStoreMetrics for CountingMetrics {
+        fn on_request(&self, verb: Verb, _latency: Duration, error: Option<ErrorClass>) {
+            *self
+                .requests
+                .lock()
+                .unwrap()
+                .entry((verb, error))
+                .or_default() += 1;
+        }
+
+        fn on_bytes_read(&self, num_bytes: u64) {
+            *self.bytes_read.lock().unwrap() += num_bytes;
+        }
+
+        fn on_bytes_written(&self, num_bytes: u64) {
+            *self.bytes_written.lock().unwrap() += num_bytes;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_store() {
+        let metrics = Arc::new(CountingMetrics::default());
+        let store = (Arc::new(InMemory::new()) as Arc<dyn object_store::ObjectStore>)
+            .with_metrics(metrics.clone());
+
+        let path = Path::from("data/0.lance");
+        store
+            .put(&path, Bytes::from_static(b"0123456789").into())
+            .await
+            .unwrap();
+        let mut upload = store
+            .put_multipart(&Path::from("data/1.lance"))
+            .await
+            .unwrap();
+        upload
+            .put_part(Bytes::from_static(b"abc").into())
+            .await
+            .unwrap();
+        upload.complete().await.unwrap();
+        assert_eq!(*metrics.bytes_written.lock().unwrap(), 13);
+
+        store.get_range(&path, 2..6).await.unwrap();
+        store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(*metrics.bytes_read.lock().unwrap(), 14);
+
+        store.head(&Path::from("missing")).await.unwrap_err();
+        // A listing is reported once its stream is done
+        let listing = store.list(None);
+        assert_eq!(metrics.count(Verb::List, None), 0);
+        let listed = listing.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(listed.len(), 2);
+
+        assert_eq!(metrics.count(Verb::Put, None), 1);
+        assert_eq!(metrics.count(Verb::CreateMultipart, None), 1);
+        assert_eq!(metrics.count(Verb::PutPart, None), 1);
+        assert_eq!(metrics.count(Verb::CompleteMultipart, None), 1);
+        assert_eq!(metrics.count(Verb::Get, None), 2);
+        assert_eq!(metrics.count(Verb::Head, Some(ErrorClass::Permanent)), 1);
+        assert_eq!(metrics.count(Verb::List, None), 1);
+    }
+}
@@ -10,7 +10,10 @@ use object_store::path::Path;
 use snafu::location;
 use url::Url;
 
-use super::{tracing::ObjectStoreTracingExt, ObjectStore, ObjectStoreParams};
+use super::{
+    read_only::ObjectStoreReadOnlyExt, tracing::ObjectStoreTracingExt, ObjectStore,
+    ObjectStoreParams,
+};
 use lance_core::error::{Error, LanceOptionExt, Result};
 
 #[cfg(feature = "aws")]
@@ -19,8 +22,13 @@ pub mod aws;
 pub mod azure;
 #[cfg(feature = "gcp")]
 pub mod gcp;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "fs")]
 pub mod local;
 pub mod memory;
+#[cfg(feature = "signed-url")]
+pub mod signed_url;
 
 #[async_trait::async_trait]
 pub trait ObjectStoreProvider: std::fmt::Debug + Sync + Send {
@@ -50,9 +58,13 @@ pub trait ObjectStoreProvider: std::fmt::Debug + Sync + Send {
 /// - `s3+ddb`: An S3 object store with DynamoDB for metadata.
 /// - `az`: An Azure Blob Storage object store.
 /// - `gs`: A Google Cloud Storage object store.
+/// - `http` / `https`: A read-only store for files served over HTTP.
 ///
 /// Use [`Self::empty()`] to create an empty registry, with no providers registered.
 ///
+/// Stores that need more than a URL to be created, such as
+/// `signed_url::SignedUrlStoreProvider`, are added with [`Self::insert()`].
+///
 /// The registry also caches object stores that are currently in use. It holds
 /// weak references to the object stores, so they are not held onto. If an object
 /// store is no longer in use, it will be removed from the cache on the next
@@ -194,9 +206,27 @@ impl ObjectStoreRegistry {
         let mut store = provider.new_store(base_path, params).await?;
 
         store.inner = store.inner.traced();
+        if let Some(io_parallelism) = params.io_parallelism {
+            store.io_parallelism = io_parallelism;
+        }
+        if params.background_io_parallelism.is_some() {
+            store.background_io_parallelism = params.background_io_parallelism;
+        }
+        store.upload_part_size = params.upload_part_size()?;
+        store.upload_concurrency = params.upload_concurrency()?;
+
+        store.inner = params.apply_metrics_and_retries(store.inner);
 
         if let Some(wrapper) = &params.object_store_wrapper {
             store.inner = wrapper.wrap(store.inner);
+            // A wrapper may change the bytes, e.g. encrypt them, so copies
+            // must go through it
+            store.store_prefix = None;
+        }
+
+        // Last, so that wrappers can't write either
+        if params.read_only {
+            store.inner = store.inner.read_only();
         }
 
         let store = Arc::new(store);
@@ -216,16 +246,19 @@ impl Default for ObjectStoreRegistry {
         let mut providers: HashMap<String, Arc<dyn ObjectStoreProvider>> = HashMap::new();
 
         providers.insert("memory".into(), Arc::new(memory::MemoryStoreProvider));
-        providers.insert("file".into(), Arc::new(local::FileStoreProvider));
-        // The "file" scheme has special optimized code paths that bypass
-        // the ObjectStore API for better performance. However, this can make it
-        // hard to test when using ObjectStore wrappers, such as IOTrackingStore.
-        // So we provide a "file-object-store" scheme that uses the ObjectStore API.
-        // The specialized code paths are differentiated by the scheme name.
-        providers.insert(
-            "file-object-store".into(),
-            Arc::new(local::FileStoreProvider),
-        );
+        #[cfg(feature = "fs")]
+        {
+            providers.insert("file".into(), Arc::new(local::FileStoreProvider));
+            // The "file" scheme has special optimized code paths that bypass
+            // the ObjectStore API for better performance. However, this can make it
+            // hard to test when using ObjectStore wrappers, such as IOTrackingStore.
+            // So we provide a "file-object-store" scheme that uses the ObjectStore API.
+            // The specialized code paths are differentiated by the scheme name.
+            providers.insert(
+                "file-object-store".into(),
+                Arc::new(local::FileStoreProvider),
+            );
+        }
 
         #[cfg(feature = "aws")]
         {
@@ -237,6 +270,12 @@ impl Default for ObjectStoreRegistry {
         providers.insert("az".into(), Arc::new(azure::AzureBlobStoreProvider));
         #[cfg(feature = "gcp")]
         providers.insert("gs".into(), Arc::new(gcp::GcsStoreProvider));
+        #[cfg(feature = "http")]
+        {
+            let http = Arc::new(http::HttpStoreProvider);
+            providers.insert("http".into(), http.clone());
+            providers.insert("https".into(), http);
+        }
         Self {
             providers: RwLock::new(providers),
             active_stores: RwLock::new(HashMap::new()),
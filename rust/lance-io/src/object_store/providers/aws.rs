@@ -29,6 +29,7 @@ use crate::object_store::{
     DEFAULT_CLOUD_IO_PARALLELISM, DEFAULT_MAX_IOP_SIZE,
 };
 use lance_core::error::{Error, Result};
+use lance_core::utils::parse::str_is_truthy;
 
 #[derive(Default, Debug)]
 pub struct AwsStoreProvider;
@@ -54,9 +55,17 @@ impl ObjectStoreProvider for AwsStoreProvider {
         };
 
         storage_options.with_env_s3();
+        let requester_pays = storage_options.requester_pays();
+        let encryption = storage_options.s3_encryption()?;
+        let client_options = storage_options.client_options()?;
 
         let mut storage_options = storage_options.as_s3_options();
-        let region = resolve_s3_region(&base_path, &storage_options).await?;
+        if requester_pays {
+            storage_options.insert(AmazonS3ConfigKey::RequestPayer, "true".to_string());
+        }
+        storage_options.extend(encryption);
+        let is_s3_express = check_s3_express(&base_path, &mut storage_options);
+        let region = resolve_s3_region(&base_path, &storage_options, &client_options).await?;
         let (aws_creds, region) = build_aws_credential(
             params.s3_credentials_refresh_offset,
             params.aws_credentials.clone(),
@@ -64,6 +73,18 @@ impl ObjectStoreProvider for AwsStoreProvider {
             region,
         )
         .await?;
+        // Public buckets can be read anonymously, so a read-only store doesn't
+        // need credentials. Only the default chain can come up empty.
+        if params.read_only
+            && params.aws_credentials.is_none()
+            && extract_static_s3_credentials(&storage_options).is_none()
+            && aws_creds.get_credential().await.is_err()
+        {
+            log::info!("No AWS credentials found, reading {} unsigned", base_path);
+            storage_options
+                .entry(AmazonS3ConfigKey::SkipSignature)
+                .or_insert_with(|| "true".to_string());
+        }
 
         // This will be default in next version of object store.
         // https://github.com/apache/arrow-rs/pull/7181
@@ -78,12 +99,20 @@ impl ObjectStoreProvider for AwsStoreProvider {
             .map(|endpoint| endpoint.contains("r2.cloudflarestorage.com"))
             .unwrap_or(false);
 
-        let is_s3_express = check_s3_express(&base_path, &mut storage_options);
-
         // before creating the OSObjectStore we need to rewrite the url to drop ddb related parts
         base_path.set_scheme("s3").unwrap();
         base_path.set_query(None);
 
+        // The same bucket name can be on another endpoint
+        let store_prefix = Some(format!(
+            "s3${}${}",
+            storage_options
+                .get(&AmazonS3ConfigKey::Endpoint)
+                .map(String::as_str)
+                .unwrap_or_default(),
+            base_path.authority()
+        ));
+
         // we can't use parse_url_opts here because we need to manually set the credentials provider
         let mut builder = AmazonS3Builder::new();
         for (key, value) in storage_options {
@@ -93,18 +122,24 @@ impl ObjectStoreProvider for AwsStoreProvider {
             .with_url(base_path.as_ref())
             .with_credentials(aws_creds)
             .with_retry(retry_config)
-            .with_region(region);
+            .with_region(region)
+            .with_client_options(client_options);
         let inner = Arc::new(builder.build()?);
 
         Ok(ObjectStore {
             inner,
             scheme: String::from(base_path.scheme()),
+            store_prefix,
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts,
             list_is_lexically_ordered: !is_s3_express,
             io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         })
     }
 }
@@ -123,19 +158,59 @@ fn check_s3_express(url: &Url, storage_options: &mut HashMap<AmazonS3ConfigKey,
     false
 }
 
+/// Region of an S3 Express One Zone directory bucket, from its name.
+///
+/// Directory buckets are named `{base}--{zone_id}--x-s3`, and the zone ID
+/// (e.g. `use1-az4`) starts with a short code for the region.  The object store
+/// uses the zone and region to build the zonal endpoint,
+/// `{bucket}.s3express-{zone_id}.{region}.amazonaws.com`.
+fn s3_express_region(bucket: &str) -> Option<&'static str> {
+    let zone_id = bucket.strip_suffix("--x-s3")?.rsplit("--").next()?;
+    let (region_code, _) = zone_id.split_once('-')?;
+    let region = match region_code {
+        "use1" => "us-east-1",
+        "use2" => "us-east-2",
+        "usw1" => "us-west-1",
+        "usw2" => "us-west-2",
+        "apne1" => "ap-northeast-1",
+        "aps1" => "ap-south-1",
+        "apse1" => "ap-southeast-1",
+        "apse2" => "ap-southeast-2",
+        "euw1" => "eu-west-1",
+        "eun1" => "eu-north-1",
+        "euc1" => "eu-central-1",
+        _ => return None,
+    };
+    Some(region)
+}
+
 /// Figure out the S3 region of the bucket.
 ///
 /// This resolves in order of precedence:
 /// 1. The region provided in the storage options
-/// 2. (If endpoint is not set), the region returned by the S3 API for the bucket
+/// 2. For S3 Express directory buckets, the region of the bucket's zone
+/// 3. (If endpoint is not set), the region returned by the S3 API for the bucket
 ///
-/// It can return None if no region is provided and the endpoint is set.
+/// It can return None if no region is provided and the endpoint is set, or
+/// if the region of a directory bucket isn't known.  The region is then taken
+/// from the environment.
 async fn resolve_s3_region(
     url: &Url,
     storage_options: &HashMap<AmazonS3ConfigKey, String>,
+    client_options: &ClientOptions,
 ) -> Result<Option<String>> {
+    let is_s3_express = matches!(
+        storage_options.get(&AmazonS3ConfigKey::S3Express),
+        Some(val) if val == "true"
+    );
     if let Some(region) = storage_options.get(&AmazonS3ConfigKey::Region) {
         Ok(Some(region.clone()))
+    } else if is_s3_express {
+        // Directory buckets can't be looked up through the regional endpoint
+        Ok(url
+            .host_str()
+            .and_then(s3_express_region)
+            .map(str::to_string))
     } else if storage_options.get(&AmazonS3ConfigKey::Endpoint).is_none() {
         // If no endpoint is set, we can assume this is AWS S3 and the region
         // can be resolved from the bucket.
@@ -146,15 +221,8 @@ async fn resolve_s3_region(
             )
         })?;
 
-        let mut client_options = ClientOptions::default();
-        for (key, value) in storage_options {
-            if let AmazonS3ConfigKey::Client(client_key) = key {
-                client_options = client_options.with_config(*client_key, value.clone());
-            }
-        }
-
         let bucket_region =
-            object_store::aws::resolve_bucket_region(bucket, &client_options).await?;
+            object_store::aws::resolve_bucket_region(bucket, client_options).await?;
         Ok(Some(bucket_region))
     } else {
         Ok(None)
@@ -315,6 +383,96 @@ impl CredentialProvider for AwsCredentialAdapter {
 }
 
 impl StorageOptions {
+    /// Server-side encryption settings for S3, from the storage options.
+    ///
+    /// * `sse_kms_key_id`: encrypt objects with this KMS key (SSE-KMS).
+    /// * `sse_kms_bucket_key`: whether to use an S3 bucket key with SSE-KMS.
+    /// * `sse_customer_key`: a base64 encoded 256-bit key that the objects are
+    ///   encrypted with (SSE-C).  The key is sent with every read and write.
+    ///
+    /// The encryption applies to every object written by the store, including
+    /// multipart uploads.  The object store's own `aws_server_side_encryption`,
+    /// `aws_sse_kms_key_id`, `aws_sse_bucket_key_enabled` and
+    /// `aws_sse_customer_key_base64` options are honored as well.
+    pub fn s3_encryption(&self) -> Result<Vec<(AmazonS3ConfigKey, String)>> {
+        let get = |key: &str| {
+            self.0
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone())
+        };
+        let kms_key_id = get("sse_kms_key_id");
+        let bucket_key = get("sse_kms_bucket_key");
+        let customer_key = get("sse_customer_key");
+        // object_store doesn't export the encryption keys, so parse them from their names
+        let encryption_key = |name: &str| AmazonS3ConfigKey::from_str(name).unwrap();
+        let mut options = Vec::new();
+        match (kms_key_id, customer_key) {
+            (Some(_), Some(_)) => {
+                return Err(Error::invalid_input(
+                    "Only one of sse_kms_key_id and sse_customer_key can be set",
+                    location!(),
+                ));
+            }
+            (Some(kms_key_id), None) => {
+                options.push((
+                    encryption_key("aws_server_side_encryption"),
+                    "aws:kms".to_string(),
+                ));
+                options.push((encryption_key("aws_sse_kms_key_id"), kms_key_id));
+                if let Some(bucket_key) = bucket_key {
+                    options.push((
+                        encryption_key("aws_sse_bucket_key_enabled"),
+                        str_is_truthy(&bucket_key).to_string(),
+                    ));
+                }
+            }
+            (None, Some(customer_key)) => {
+                // 32 bytes of base64 with padding
+                if customer_key.len() != 44 || !customer_key.ends_with('=') {
+                    return Err(Error::invalid_input(
+                        "sse_customer_key must be a base64 encoded 256-bit key",
+                        location!(),
+                    ));
+                }
+                options.push((
+                    encryption_key("aws_server_side_encryption"),
+                    "sse-c".to_string(),
+                ));
+                options.push((encryption_key("aws_sse_customer_key_base64"), customer_key));
+            }
+            (None, None) => {
+                if bucket_key.is_some() {
+                    return Err(Error::invalid_input(
+                        "sse_kms_bucket_key requires sse_kms_key_id",
+                        location!(),
+                    ));
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Whether the bucket is a requester-pays bucket.
+    ///
+    /// When set, the `x-amz-request-payer: requester` header is sent with every
+    /// request, so the caller's account is billed for the requests and data
+    /// transfer.  Set with the `requester_pays` storage option or the
+    /// `AWS_REQUESTER_PAYS` environment variable.  The object store's own
+    /// `aws_request_payer` option works as well.
+    pub fn requester_pays(&self) -> bool {
+        self.0
+            .iter()
+            .find(|(key, _)| {
+                key.eq_ignore_ascii_case("requester_pays")
+                    || key.eq_ignore_ascii_case("aws_requester_pays")
+            })
+            .map(|(_, value)| value.to_string())
+            .or_else(|| std::env::var("AWS_REQUESTER_PAYS").ok())
+            .map(|value| str_is_truthy(&value))
+            .unwrap_or(false)
+    }
+
     /// Add values from the environment to storage options
     pub fn with_env_s3(&mut self) {
         for (os_key, os_value) in std::env::vars_os() {
@@ -472,4 +630,111 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_s3_express_region() {
+        let cases = [
+            ("mybucket--use1-az4--x-s3", Some("us-east-1")),
+            ("my--bucket--usw2-az1--x-s3", Some("us-west-2")),
+            ("mybucket--apne1-az4--x-s3", Some("ap-northeast-1")),
+            ("mybucket--mars1-az1--x-s3", None),
+            ("mybucket", None),
+        ];
+        for (bucket, expected) in cases {
+            assert_eq!(s3_express_region(bucket), expected, "{}", bucket);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_s3_express_region_is_not_looked_up() {
+        let url = Url::parse("s3://mybucket--usw2-az1--x-s3/path").unwrap();
+        let mut options = HashMap::new();
+        assert!(check_s3_express(&url, &mut options));
+        let region = resolve_s3_region(&url, &options, &ClientOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(region.as_deref(), Some("us-west-2"));
+
+        // An explicit region wins
+        options.insert(AmazonS3ConfigKey::Region, "us-east-1".to_string());
+        let region = resolve_s3_region(&url, &options, &ClientOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(region.as_deref(), Some("us-east-1"));
+    }
+
+    #[test]
+    fn test_requester_pays_option() {
+        let options = StorageOptions(HashMap::from([(
+            "requester_pays".to_string(),
+            "true".to_string(),
+        )]));
+        assert!(options.requester_pays());
+        let options = StorageOptions(HashMap::from([(
+            "requester_pays".to_string(),
+            "false".to_string(),
+        )]));
+        assert!(!options.requester_pays());
+
+        // The object store's own option is passed through as well
+        let options = StorageOptions(HashMap::from([(
+            "aws_request_payer".to_string(),
+            "true".to_string(),
+        )]));
+        assert_eq!(
+            options
+                .as_s3_options()
+                .get(&AmazonS3ConfigKey::RequestPayer)
+                .map(String::as_str),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_s3_encryption_options() {
+        let options = |pairs: &[(&str, &str)]| {
+            StorageOptions(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        };
+        let key = |name: &str| AmazonS3ConfigKey::from_str(name).unwrap();
+        let sse = key("aws_server_side_encryption");
+
+        assert!(options(&[]).s3_encryption().unwrap().is_empty());
+
+        let kms = HashMap::<_, _>::from_iter(
+            options(&[("sse_kms_key_id", "my-key"), ("sse_kms_bucket_key", "true")])
+                .s3_encryption()
+                .unwrap(),
+        );
+        assert_eq!(kms[&sse], "aws:kms");
+        assert_eq!(kms[&key("aws_sse_kms_key_id")], "my-key");
+        assert_eq!(kms[&key("aws_sse_bucket_key_enabled")], "true");
+
+        let customer_key = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+        let ssec = HashMap::<_, _>::from_iter(
+            options(&[("sse_customer_key", customer_key)])
+                .s3_encryption()
+                .unwrap(),
+        );
+        assert_eq!(ssec[&sse], "sse-c");
+        assert_eq!(ssec[&key("aws_sse_customer_key_base64")], customer_key);
+
+        for invalid in [
+            options(&[
+                ("sse_kms_key_id", "my-key"),
+                ("sse_customer_key", customer_key),
+            ]),
+            options(&[("sse_customer_key", "too-short")]),
+            options(&[("sse_kms_bucket_key", "true")]),
+        ] {
+            assert!(matches!(
+                invalid.s3_encryption(),
+                Err(Error::InvalidInput { .. })
+            ));
+        }
+    }
 }
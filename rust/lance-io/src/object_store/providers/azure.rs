@@ -1,19 +1,34 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
-use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
+use chrono::{DateTime, NaiveDate, Utc};
 use object_store::{
-    azure::{AzureConfigKey, MicrosoftAzureBuilder},
-    RetryConfig,
+    azure::{AzureConfigKey, AzureCredential, MicrosoftAzureBuilder},
+    CredentialProvider, Result as ObjectStoreResult, RetryConfig,
 };
+use snafu::location;
+use tokio::sync::RwLock;
 use url::Url;
 
 use crate::object_store::{
     ObjectStore, ObjectStoreParams, ObjectStoreProvider, StorageOptions, DEFAULT_CLOUD_BLOCK_SIZE,
     DEFAULT_CLOUD_IO_PARALLELISM, DEFAULT_MAX_IOP_SIZE,
 };
-use lance_core::error::Result;
+use lance_core::error::{Error, Result};
+
+/// Environment variable with the path of a file holding a SAS token, see
+/// [`SasTokenFile`].
+pub const SAS_TOKEN_FILE_ENV: &str = "AZURE_STORAGE_SAS_TOKEN_FILE";
+
+/// How long before a credential expires it is refreshed by default.
+pub const DEFAULT_AZURE_CREDENTIALS_REFRESH_OFFSET: Duration = Duration::from_secs(300);
 
 #[derive(Default, Debug)]
 pub struct AzureBlobStoreProvider;
@@ -35,23 +50,47 @@ impl ObjectStoreProvider for AzureBlobStoreProvider {
         };
 
         storage_options.with_env_azure();
+        // The same container name can be in another account
+        let store_prefix = Some(format!(
+            "az${}${}",
+            storage_options
+                .as_azure_options()
+                .get(&AzureConfigKey::AccountName)
+                .map(String::as_str)
+                .unwrap_or_default(),
+            base_path.authority()
+        ));
         let mut builder = MicrosoftAzureBuilder::new()
             .with_url(base_path.as_ref())
             .with_retry(retry_config);
         for (key, value) in storage_options.as_azure_options() {
             builder = builder.with_config(key, value);
         }
+        if let Some(credentials) = params.azure_credentials.clone() {
+            builder = builder.with_credentials(credentials);
+        } else if let Some(path) = storage_options.azure_sas_token_file() {
+            builder = builder.with_credentials(Arc::new(RefreshingAzureCredentialProvider::new(
+                Arc::new(SasTokenFile::new(path)),
+                DEFAULT_AZURE_CREDENTIALS_REFRESH_OFFSET,
+            )));
+        }
+        builder = builder.with_client_options(storage_options.client_options()?);
         let inner = Arc::new(builder.build()?);
 
         Ok(ObjectStore {
             inner,
             scheme: String::from("az"),
+            store_prefix,
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts: false,
             list_is_lexically_ordered: true,
             io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         })
     }
 }
@@ -71,6 +110,15 @@ impl StorageOptions {
         }
     }
 
+    /// Path of a file holding a SAS token, from the `azure_sas_token_file`
+    /// option or the `AZURE_STORAGE_SAS_TOKEN_FILE` environment variable.
+    pub fn azure_sas_token_file(&self) -> Option<String> {
+        self.0
+            .get("azure_sas_token_file")
+            .cloned()
+            .or_else(|| std::env::var(SAS_TOKEN_FILE_ENV).ok())
+    }
+
     /// Subset of options relevant for azure storage
     pub fn as_azure_options(&self) -> HashMap<AzureConfigKey, String> {
         self.0
@@ -83,10 +131,263 @@ impl StorageOptions {
     }
 }
 
+/// An Azure credential and the time it stops being valid.
+#[derive(Debug)]
+pub struct ExpiringAzureCredential {
+    pub credential: AzureCredential,
+    /// `None` if the credential does not expire.
+    pub expires_at: Option<SystemTime>,
+}
+
+/// A source of Azure credentials that expire, such as rotating SAS tokens.
+///
+/// Wrap it in a [`RefreshingAzureCredentialProvider`] to use it with an object
+/// store.
+#[async_trait::async_trait]
+pub trait AzureCredentialSource: std::fmt::Debug + Send + Sync {
+    /// Fetch a fresh credential.
+    async fn fetch_credential(&self) -> Result<ExpiringAzureCredential>;
+}
+
+/// Caches the credential of an [`AzureCredentialSource`] and fetches a new one
+/// `refresh_offset` before it expires.
+///
+/// Credentials are requested for every request the store makes, so a long
+/// running scan picks up the new credential without failing. If fetching a
+/// new credential fails while the cached one is still valid, the cached one
+/// is used until it expires.
+#[derive(Debug)]
+pub struct RefreshingAzureCredentialProvider {
+    source: Arc<dyn AzureCredentialSource>,
+    refresh_offset: Duration,
+    cache: RwLock<Option<(Arc<AzureCredential>, Option<SystemTime>)>>,
+}
+
+impl RefreshingAzureCredentialProvider {
+    pub fn new(source: Arc<dyn AzureCredentialSource>, refresh_offset: Duration) -> Self {
+        Self {
+            source,
+            refresh_offset,
+            cache: RwLock::new(None),
+        }
+    }
+
+    fn cached(
+        cache: &Option<(Arc<AzureCredential>, Option<SystemTime>)>,
+        until: SystemTime,
+    ) -> Option<Arc<AzureCredential>> {
+        match cache {
+            Some((credential, None)) => Some(credential.clone()),
+            Some((credential, Some(expires_at))) if *expires_at > until => Some(credential.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialProvider for RefreshingAzureCredentialProvider {
+    type Credential = AzureCredential;
+
+    async fn get_credential(&self) -> ObjectStoreResult<Arc<AzureCredential>> {
+        let now = SystemTime::now();
+        let refresh_at = now + self.refresh_offset;
+        if let Some(credential) = Self::cached(&*self.cache.read().await, refresh_at) {
+            return Ok(credential);
+        }
+
+        let mut cache = self.cache.write().await;
+        // Another request may have refreshed the credential in the meantime
+        if let Some(credential) = Self::cached(&cache, refresh_at) {
+            return Ok(credential);
+        }
+        match self.source.fetch_credential().await {
+            Ok(fetched) => {
+                let credential = Arc::new(fetched.credential);
+                *cache = Some((credential.clone(), fetched.expires_at));
+                Ok(credential)
+            }
+            Err(err) => match Self::cached(&cache, now) {
+                Some(credential) => {
+                    log::warn!("Failed to refresh Azure credentials: {}", err);
+                    Ok(credential)
+                }
+                None => Err(object_store::Error::Generic {
+                    store: "MicrosoftAzure",
+                    source: Box::new(err),
+                }),
+            },
+        }
+    }
+}
+
+/// Reads a SAS token from a file, e.g. a secret that is mounted into a
+/// container and rotated by the platform.
+///
+/// The file is read again when the token is about to expire, based on its
+/// `se` (signed expiry) parameter. Tokens without one are read only once.
+#[derive(Debug)]
+pub struct SasTokenFile {
+    path: String,
+}
+
+impl SasTokenFile {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl AzureCredentialSource for SasTokenFile {
+    async fn fetch_credential(&self) -> Result<ExpiringAzureCredential> {
+        let token = std::fs::read_to_string(&self.path).map_err(|err| {
+            Error::io(
+                format!("failed to read SAS token file {}: {}", self.path, err),
+                location!(),
+            )
+        })?;
+        parse_sas_token(&token)
+    }
+}
+
+fn parse_sas_token(token: &str) -> Result<ExpiringAzureCredential> {
+    let token = token.trim();
+    let pairs = url::form_urlencoded::parse(token.strip_prefix('?').unwrap_or(token).as_bytes())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if !pairs.iter().any(|(key, _)| key == "sig") {
+        return Err(Error::invalid_input(
+            "SAS token has no signature (sig)",
+            location!(),
+        ));
+    }
+    let expires_at = pairs
+        .iter()
+        .find(|(key, _)| key == "se")
+        .map(|(_, expiry)| parse_sas_expiry(expiry))
+        .transpose()?;
+    Ok(ExpiringAzureCredential {
+        credential: AzureCredential::SASToken(pairs),
+        expires_at,
+    })
+}
+
+/// The signed expiry is either a UTC timestamp or a date.
+fn parse_sas_expiry(expiry: &str) -> Result<SystemTime> {
+    let expiry = DateTime::parse_from_rfc3339(expiry)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(expiry, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        })
+        .map_err(|err| {
+            Error::invalid_input(
+                format!("invalid SAS token expiry {}: {}", expiry, err),
+                location!(),
+            )
+        })?;
+    Ok(expiry.into())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use super::*;
 
+    #[test]
+    fn test_parse_sas_token() {
+        let parsed =
+            parse_sas_token("?sv=2022-11-02&se=2030-01-02T03:04:05Z&sp=rl&sig=abc%2Bdef\n")
+                .unwrap();
+        let AzureCredential::SASToken(pairs) = &parsed.credential else {
+            panic!("expected a SAS token");
+        };
+        assert!(pairs.contains(&("sig".to_string(), "abc+def".to_string())));
+        let expected: SystemTime = DateTime::parse_from_rfc3339("2030-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            .into();
+        assert_eq!(parsed.expires_at, Some(expected));
+
+        let parsed = parse_sas_token("sv=2022-11-02&se=2030-01-02&sig=abc").unwrap();
+        assert!(parsed.expires_at.is_some());
+        assert!(parse_sas_token("sv=2022-11-02&sig=abc")
+            .unwrap()
+            .expires_at
+            .is_none());
+        assert!(parse_sas_token("sv=2022-11-02").is_err());
+        assert!(parse_sas_token("se=tomorrow&sig=abc").is_err());
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingSource {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl AzureCredentialSource for CountingSource {
+        async fn fetch_credential(&self) -> Result<ExpiringAzureCredential> {
+            let fetches = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            if fetches > 2 {
+                return Err(Error::io("token service unavailable", location!()));
+            }
+            Ok(ExpiringAzureCredential {
+                credential: AzureCredential::BearerToken(format!("token-{}", fetches)),
+                // Expires within the refresh offset
+                expires_at: Some(SystemTime::now() + Duration::from_secs(60)),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refreshing_credentials() {
+        let source = Arc::new(CountingSource::default());
+        let provider = RefreshingAzureCredentialProvider::new(source.clone(), Duration::ZERO);
+        let credential = provider.get_credential().await.unwrap();
+        assert_eq!(
+            *credential,
+            AzureCredential::BearerToken("token-1".to_string())
+        );
+        provider.get_credential().await.unwrap();
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
+
+        // Refreshed before it expires
+        let provider = RefreshingAzureCredentialProvider::new(
+            source.clone(),
+            DEFAULT_AZURE_CREDENTIALS_REFRESH_OFFSET,
+        );
+        let credential = provider.get_credential().await.unwrap();
+        assert_eq!(
+            *credential,
+            AzureCredential::BearerToken("token-2".to_string())
+        );
+        // The refresh fails, but the cached credential is still valid
+        let credential = provider.get_credential().await.unwrap();
+        assert_eq!(
+            *credential,
+            AzureCredential::BearerToken("token-2".to_string())
+        );
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_workload_identity_options() {
+        let options = StorageOptions(HashMap::from([
+            (
+                "azure_federated_token_file".to_string(),
+                "/var/run/secrets/azure/tokens/azure-identity-token".to_string(),
+            ),
+            ("azure_client_id".to_string(), "client".to_string()),
+            ("azure_tenant_id".to_string(), "tenant".to_string()),
+            ("azure_sas_token_file".to_string(), "/token".to_string()),
+        ]));
+        let azure_options = options.as_azure_options();
+        assert!(azure_options.contains_key(&AzureConfigKey::FederatedTokenFile));
+        assert!(azure_options.contains_key(&AzureConfigKey::ClientId));
+        assert!(azure_options.contains_key(&AzureConfigKey::AuthorityId));
+        assert_eq!(options.azure_sas_token_file().as_deref(), Some("/token"));
+    }
+
     #[test]
     fn test_azure_store_path() {
         let provider = AzureBlobStoreProvider;
@@ -41,6 +41,7 @@ impl ObjectStoreProvider for GcsStoreProvider {
         for (key, value) in storage_options.as_gcs_options() {
             builder = builder.with_config(key, value);
         }
+        builder = builder.with_client_options(storage_options.client_options()?);
         let token_key = "google_storage_token";
         if let Some(storage_token) = storage_options.get(token_key) {
             let credential = GcpCredential {
@@ -54,12 +55,17 @@ impl ObjectStoreProvider for GcsStoreProvider {
         Ok(ObjectStore {
             inner,
             scheme: String::from("gs"),
+            store_prefix: Some(format!("gs${}", base_path.authority())),
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts: false,
             list_is_lexically_ordered: true,
             io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         })
     }
 }
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+use std::{sync::Arc, time::Duration};
+
+use object_store::{http::HttpBuilder, RetryConfig};
+use url::{Position, Url};
+
+use crate::object_store::{
+    ObjectStore, ObjectStoreParams, ObjectStoreProvider, StorageOptions, DEFAULT_CLOUD_BLOCK_SIZE,
+    DEFAULT_CLOUD_IO_PARALLELISM, DEFAULT_MAX_IOP_SIZE,
+};
+use lance_core::error::Result;
+
+/// Reads files from a plain HTTP server using range requests.
+///
+/// This only needs `GET` with `Range` headers, so any static file server or
+/// CDN works. Listing and writing require a WebDAV server, so in practice the
+/// store is only suitable for reading individual files.
+#[derive(Default, Debug)]
+pub struct HttpStoreProvider;
+
+#[async_trait::async_trait]
+impl ObjectStoreProvider for HttpStoreProvider {
+    async fn new_store(&self, base_path: Url, params: &ObjectStoreParams) -> Result<ObjectStore> {
+        let block_size = params.block_size.unwrap_or(DEFAULT_CLOUD_BLOCK_SIZE);
+        let storage_options = StorageOptions(params.storage_options.clone().unwrap_or_default());
+        let download_retry_count = storage_options.download_retry_count();
+
+        let retry_config = RetryConfig {
+            backoff: Default::default(),
+            max_retries: storage_options.client_max_retries(),
+            retry_timeout: Duration::from_secs(storage_options.client_retry_timeout()),
+        };
+        let client_options = storage_options
+            .client_options()?
+            .with_allow_http(base_path.scheme() == "http" || storage_options.allow_http());
+        // Paths are resolved against the origin, see `extract_path`.
+        let inner = HttpBuilder::new()
+            .with_url(&base_path[..Position::BeforePath])
+            .with_retry(retry_config)
+            .with_client_options(client_options)
+            .build()?;
+
+        Ok(ObjectStore {
+            inner: Arc::new(inner),
+            scheme: base_path.scheme().to_owned(),
+            store_prefix: None,
+            block_size,
+            max_iop_size: *DEFAULT_MAX_IOP_SIZE,
+            use_constant_size_upload_parts: false,
+            list_is_lexically_ordered: false,
+            io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
+            download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_http_store() {
+        let provider = HttpStoreProvider;
+        let url = Url::parse("https://example.com:8443/data/items.lance").unwrap();
+        let store = provider
+            .new_store(url.clone(), &ObjectStoreParams::default())
+            .await
+            .unwrap();
+        assert_eq!(store.scheme(), "https");
+        assert!(store.is_cloud());
+        assert_eq!(provider.extract_path(&url).as_ref(), "data/items.lance");
+    }
+}
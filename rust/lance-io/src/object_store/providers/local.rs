@@ -24,12 +24,17 @@ impl ObjectStoreProvider for FileStoreProvider {
         Ok(ObjectStore {
             inner: Arc::new(LocalFileSystem::new()),
             scheme: base_path.scheme().to_owned(),
+            store_prefix: Some("file".to_string()),
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts: false,
             list_is_lexically_ordered: false,
             io_parallelism: DEFAULT_LOCAL_IO_PARALLELISM,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         })
     }
 
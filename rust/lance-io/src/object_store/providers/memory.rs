@@ -25,12 +25,17 @@ impl ObjectStoreProvider for MemoryStoreProvider {
         Ok(ObjectStore {
             inner: Arc::new(InMemory::new()),
             scheme: String::from("memory"),
+            store_prefix: None,
             block_size,
             max_iop_size: *DEFAULT_MAX_IOP_SIZE,
             use_constant_size_upload_parts: false,
             list_is_lexically_ordered: true,
             io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
             download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
         })
     }
 
@@ -0,0 +1,621 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Access objects through pre-signed URLs
+//!
+//! Instead of holding credentials for the bucket, the store asks a
+//! [`UrlSigner`] for a URL for every request and sends a plain HTTP request to
+//! it.  The signer is typically a callback to a service that vends short lived
+//! S3, GCS or Azure SAS URLs after doing its own authorization, which is what
+//! browser, edge and zero-trust deployments need.  [`PresignedUrls`] serves a
+//! fixed set of URLs handed out up front.
+//!
+//! The provider isn't registered by default because it needs a signer.
+//! Register it for the scheme of the URIs that should go through it:
+//!
+//! ```ignore
+//! let registry = ObjectStoreRegistry::default();
+//! registry.insert("s3", Arc::new(SignedUrlStoreProvider::new(signer)));
+//! ```
+//!
+//! The signer is given the path of the object within the bucket.  A signed URL
+//! only grants access to a single object, so listing isn't possible and
+//! datasets have to be opened at a known version.  Pre-signed uploads are a
+//! single `PUT`, so multipart uploads are buffered in memory and sent in one
+//! request when they complete.  Conditional puts are sent with `If-None-Match`
+//! and `If-Match` headers, which the signer has to allow.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    Attributes, GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartUpload,
+    ObjectMeta, PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result as OSResult,
+    UploadPart,
+};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Method, StatusCode};
+use snafu::location;
+use url::Url;
+
+use crate::object_store::{
+    ObjectStore, ObjectStoreParams, ObjectStoreProvider, StorageOptions, DEFAULT_CLOUD_BLOCK_SIZE,
+    DEFAULT_CLOUD_IO_PARALLELISM, DEFAULT_MAX_IOP_SIZE,
+};
+use lance_core::{Error, Result};
+
+const STORE: &str = "SignedUrl";
+
+/// The kind of request a URL is signed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignedUrlMethod {
+    Get,
+    Head,
+    Put,
+    Delete,
+}
+
+impl SignedUrlMethod {
+    fn http_method(&self) -> Method {
+        match self {
+            Self::Get => Method::GET,
+            Self::Head => Method::HEAD,
+            Self::Put => Method::PUT,
+            Self::Delete => Method::DELETE,
+        }
+    }
+}
+
+/// Vends signed URLs for requests on objects
+#[async_trait::async_trait]
+pub trait UrlSigner: std::fmt::Debug + Send + Sync {
+    /// A URL that allows a `method` request on the object at `path`
+    ///
+    /// This is called for every request, implementations that call out to a
+    /// service should cache URLs until shortly before they expire.
+    async fn sign(&self, method: SignedUrlMethod, path: &Path) -> Result<Url>;
+}
+
+/// A fixed set of pre-signed URLs
+#[derive(Debug, Default)]
+pub struct PresignedUrls {
+    urls: HashMap<(SignedUrlMethod, Path), Url>,
+}
+
+impl PresignedUrls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the URL to use for `method` requests on `path`
+    pub fn with_url(mut self, method: SignedUrlMethod, path: impl Into<Path>, url: Url) -> Self {
+        self.urls.insert((method, path.into()), url);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl UrlSigner for PresignedUrls {
+    async fn sign(&self, method: SignedUrlMethod, path: &Path) -> Result<Url> {
+        self.urls
+            .get(&(method, path.clone()))
+            .cloned()
+            .ok_or_else(|| {
+                Error::invalid_input(
+                    format!("no pre-signed URL for {:?} of {}", method, path),
+                    location!(),
+                )
+            })
+    }
+}
+
+/// Creates stores that send every request to a URL from a [`UrlSigner`]
+#[derive(Debug)]
+pub struct SignedUrlStoreProvider {
+    signer: Arc<dyn UrlSigner>,
+}
+
+impl SignedUrlStoreProvider {
+    pub fn new(signer: Arc<dyn UrlSigner>) -> Self {
+        Self { signer }
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStoreProvider for SignedUrlStoreProvider {
+    async fn new_store(&self, base_path: Url, params: &ObjectStoreParams) -> Result<ObjectStore> {
+        let block_size = params.block_size.unwrap_or(DEFAULT_CLOUD_BLOCK_SIZE);
+        let storage_options = StorageOptions(params.storage_options.clone().unwrap_or_default());
+        let download_retry_count = storage_options.download_retry_count();
+
+        let inner = SignedUrlStore {
+            signer: self.signer.clone(),
+            client: reqwest::Client::new(),
+        };
+
+        Ok(ObjectStore {
+            inner: Arc::new(inner),
+            scheme: base_path.scheme().to_owned(),
+            store_prefix: None,
+            block_size,
+            max_iop_size: *DEFAULT_MAX_IOP_SIZE,
+            use_constant_size_upload_parts: params.use_constant_size_upload_parts,
+            list_is_lexically_ordered: false,
+            io_parallelism: DEFAULT_CLOUD_IO_PARALLELISM,
+            background_io_parallelism: None,
+            download_retry_count,
+            upload_part_size: None,
+            upload_concurrency: None,
+            bandwidth_limits: None,
+        })
+    }
+}
+
+fn not_supported(operation: &str) -> object_store::Error {
+    object_store::Error::NotSupported {
+        source: format!("{} is not possible with signed URLs", operation).into(),
+    }
+}
+
+fn request_error(err: reqwest::Error) -> object_store::Error {
+    object_store::Error::Generic {
+        store: STORE,
+        source: Box::new(err),
+    }
+}
+
+/// Map an unsuccessful response to an error
+fn status_error(path: &Path, status: StatusCode, mode: Option<&PutMode>) -> object_store::Error {
+    let path = path.to_string();
+    let source = format!("signed URL request failed with status {}", status).into();
+    match status {
+        StatusCode::NOT_FOUND => object_store::Error::NotFound { path, source },
+        StatusCode::NOT_MODIFIED => object_store::Error::NotModified { path, source },
+        StatusCode::PRECONDITION_FAILED if matches!(mode, Some(PutMode::Create)) => {
+            object_store::Error::AlreadyExists { path, source }
+        }
+        StatusCode::PRECONDITION_FAILED => object_store::Error::Precondition { path, source },
+        _ => object_store::Error::Generic {
+            store: STORE,
+            source,
+        },
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &header::HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Parse a `Content-Range` header of the form `bytes <start>-<end>/<size>`
+fn parse_content_range(value: &str) -> Option<(Range<u64>, u64)> {
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.parse::<u64>().ok()?;
+    let end = end.parse::<u64>().ok()?;
+    Some((start..end + 1, size.parse().ok()?))
+}
+
+/// The metadata of the object and the range returned in a response
+fn parse_response_meta(
+    path: &Path,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> OSResult<(ObjectMeta, Range<u64>)> {
+    let invalid = |message: String| object_store::Error::Generic {
+        store: STORE,
+        source: message.into(),
+    };
+    let (range, size) = if status == StatusCode::PARTIAL_CONTENT {
+        let value = header_str(headers, &header::CONTENT_RANGE)
+            .ok_or_else(|| invalid("partial response without a Content-Range header".into()))?;
+        parse_content_range(value)
+            .ok_or_else(|| invalid(format!("invalid Content-Range header `{}`", value)))?
+    } else {
+        let size = header_str(headers, &header::CONTENT_LENGTH)
+            .and_then(|value| value.parse::<u64>().ok())
+            .ok_or_else(|| invalid("response without a Content-Length header".into()))?;
+        (0..size, size)
+    };
+    let last_modified = header_str(headers, &header::LAST_MODIFIED)
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_default();
+    let meta = ObjectMeta {
+        location: path.clone(),
+        last_modified,
+        size,
+        e_tag: header_str(headers, &header::ETAG).map(String::from),
+        version: None,
+    };
+    Ok((meta, range))
+}
+
+/// An object store that sends every request to a signed URL
+#[derive(Debug, Clone)]
+struct SignedUrlStore {
+    signer: Arc<dyn UrlSigner>,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Display for SignedUrlStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SignedUrlStore({:?})", self.signer)
+    }
+}
+
+impl SignedUrlStore {
+    async fn request(
+        &self,
+        method: SignedUrlMethod,
+        location: &Path,
+    ) -> OSResult<reqwest::RequestBuilder> {
+        let url = self.signer.sign(method, location).await?;
+        Ok(self.client.request(method.http_method(), url))
+    }
+
+    async fn put(&self, location: &Path, data: Bytes, mode: PutMode) -> OSResult<PutResult> {
+        let mut request = self.request(SignedUrlMethod::Put, location).await?;
+        match &mode {
+            PutMode::Overwrite => {}
+            PutMode::Create => request = request.header(header::IF_NONE_MATCH, "*"),
+            PutMode::Update(version) => {
+                let e_tag = version
+                    .e_tag
+                    .as_ref()
+                    .ok_or_else(|| not_supported("an update without an ETag"))?;
+                request = request.header(header::IF_MATCH, e_tag);
+            }
+        }
+        let response = request.body(data).send().await.map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(status_error(location, response.status(), Some(&mode)));
+        }
+        Ok(PutResult {
+            e_tag: header_str(response.headers(), &header::ETAG).map(String::from),
+            version: None,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl object_store::ObjectStore for SignedUrlStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        self.put(location, payload.into(), opts.mode).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        Ok(Box::new(BufferedUpload {
+            store: self.clone(),
+            location: location.clone(),
+            parts: Vec::new(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        let method = if options.head {
+            SignedUrlMethod::Head
+        } else {
+            SignedUrlMethod::Get
+        };
+        let mut request = self.request(method, location).await?;
+        if let Some(range) = &options.range {
+            let value = match range {
+                GetRange::Bounded(range) if range.is_empty() => {
+                    return Err(object_store::Error::Generic {
+                        store: STORE,
+                        source: format!("empty range {:?} requested", range).into(),
+                    });
+                }
+                GetRange::Bounded(range) => format!("bytes={}-{}", range.start, range.end - 1),
+                GetRange::Offset(offset) => format!("bytes={}-", offset),
+                GetRange::Suffix(suffix) => format!("bytes=-{}", suffix),
+            };
+            request = request.header(header::RANGE, value);
+        }
+        if let Some(e_tag) = &options.if_match {
+            request = request.header(header::IF_MATCH, e_tag);
+        }
+        if let Some(e_tag) = &options.if_none_match {
+            request = request.header(header::IF_NONE_MATCH, e_tag);
+        }
+
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(location, status, None));
+        }
+        let (meta, range) = parse_response_meta(location, status, response.headers())?;
+        let stream = if options.head {
+            futures::stream::empty().boxed()
+        } else {
+            response.bytes_stream().map_err(request_error).boxed()
+        };
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range,
+            attributes: Attributes::default(),
+        })
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        let response = self
+            .request(SignedUrlMethod::Delete, location)
+            .await?
+            .send()
+            .await
+            .map_err(request_error)?;
+        // Deleting a missing object is not an error
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(status_error(location, response.status(), None))
+        }
+    }
+
+    fn list(&self, _prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        futures::stream::once(async { Err(not_supported("listing")) }).boxed()
+    }
+
+    async fn list_with_delimiter(&self, _prefix: Option<&Path>) -> OSResult<ListResult> {
+        Err(not_supported("listing"))
+    }
+
+    async fn copy(&self, _from: &Path, _to: &Path) -> OSResult<()> {
+        Err(not_supported("copying"))
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> OSResult<()> {
+        Err(not_supported("copying"))
+    }
+}
+
+/// Collects the parts of an upload and sends them in a single `PUT`
+#[derive(Debug)]
+struct BufferedUpload {
+    store: SignedUrlStore,
+    location: Path,
+    parts: Vec<PutPayload>,
+}
+
+#[async_trait::async_trait]
+impl MultipartUpload for BufferedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        // Parts are added in the order they are submitted
+        self.parts.push(data);
+        Box::pin(futures::future::ready(Ok(())))
+    }
+
+    async fn complete(&mut self) -> OSResult<PutResult> {
+        let parts = std::mem::take(&mut self.parts);
+        let mut data =
+            BytesMut::with_capacity(parts.iter().map(|part| part.content_length()).sum());
+        for part in parts {
+            for chunk in part.iter() {
+                data.extend_from_slice(chunk);
+            }
+        }
+        self.store
+            .put(&self.location, data.freeze(), PutMode::Overwrite)
+            .await
+    }
+
+    async fn abort(&mut self) -> OSResult<()> {
+        self.parts.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Signs every request for a path on a local server
+    #[derive(Debug)]
+    struct LocalSigner {
+        base: Url,
+    }
+
+    #[async_trait::async_trait]
+    impl UrlSigner for LocalSigner {
+        async fn sign(&self, _method: SignedUrlMethod, path: &Path) -> Result<Url> {
+            Ok(self.base.join(path.as_ref()).unwrap())
+        }
+    }
+
+    /// Serve objects over HTTP/1.1 from memory, closing the connection after
+    /// every response
+    fn spawn_server() -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let base = Url::parse(&format!("http://{}/", addr)).unwrap();
+        let objects = Mutex::new(HashMap::<String, Vec<u8>>::new());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                }
+
+                let mut objects = objects.lock().unwrap();
+                let (status, extra_headers, body) = match method.as_str() {
+                    "PUT" => {
+                        let length = headers["content-length"].parse().unwrap();
+                        let mut data = vec![0; length];
+                        reader.read_exact(&mut data).unwrap();
+                        if headers.get("if-none-match").map(String::as_str) == Some("*")
+                            && objects.contains_key(&path)
+                        {
+                            ("412 Precondition Failed", String::new(), Vec::new())
+                        } else {
+                            objects.insert(path, data);
+                            ("200 OK", String::new(), Vec::new())
+                        }
+                    }
+                    "GET" | "HEAD" => match objects.get(&path) {
+                        None => ("404 Not Found", String::new(), Vec::new()),
+                        Some(data) => match headers.get("range") {
+                            Some(range) => {
+                                let (start, end) = range
+                                    .strip_prefix("bytes=")
+                                    .and_then(|range| range.split_once('-'))
+                                    .unwrap();
+                                let start: usize = start.parse().unwrap();
+                                let end: usize = end.parse().unwrap();
+                                let range_header = format!(
+                                    "Content-Range: bytes {}-{}/{}\r\n",
+                                    start,
+                                    end,
+                                    data.len()
+                                );
+                                let body = data[start..=end].to_vec();
+                                ("206 Partial Content", range_header, body)
+                            }
+                            None => ("200 OK", String::new(), data.clone()),
+                        },
+                    },
+                    _ => ("405 Method Not Allowed", String::new(), Vec::new()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                    status,
+                    body.len(),
+                    extra_headers
+                )
+                .unwrap();
+                // HEAD responses have the length of the body they would have had
+                if method != "HEAD" {
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/1234"), Some((0..100, 1234)));
+        assert_eq!(
+            parse_content_range("bytes 1000-1233/1234"),
+            Some((1000..1234, 1234))
+        );
+        // Unknown total sizes are not accepted
+        assert_eq!(parse_content_range("bytes 0-99/*"), None);
+        assert_eq!(parse_content_range("items 0-99/1234"), None);
+    }
+
+    #[tokio::test]
+    async fn test_presigned_urls() {
+        let url =
+            Url::parse("https://bucket.s3.amazonaws.com/data/0.lance?X-Amz-Signature=abc").unwrap();
+        let signer =
+            PresignedUrls::new().with_url(SignedUrlMethod::Get, "data/0.lance", url.clone());
+        let path = Path::from("data/0.lance");
+        assert_eq!(signer.sign(SignedUrlMethod::Get, &path).await.unwrap(), url);
+        // URLs are only good for the method they were signed for
+        assert!(signer.sign(SignedUrlMethod::Put, &path).await.is_err());
+        assert!(signer
+            .sign(SignedUrlMethod::Get, &Path::from("data/1.lance"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_url_store() {
+        let provider = SignedUrlStoreProvider::new(Arc::new(PresignedUrls::new()));
+        let url = Url::parse("s3://bucket/data/items.lance").unwrap();
+        let store = provider
+            .new_store(url.clone(), &ObjectStoreParams::default())
+            .await
+            .unwrap();
+        assert_eq!(store.scheme(), "s3");
+        assert!(store.is_cloud());
+        assert_eq!(provider.extract_path(&url).as_ref(), "data/items.lance");
+
+        // Without a URL for the object, requests fail before anything is sent
+        let err = store.inner.head(&Path::from("data/items.lance")).await;
+        assert!(err.is_err());
+        let err = store.inner.list_with_delimiter(None).await.unwrap_err();
+        assert!(matches!(err, object_store::Error::NotSupported { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_signed_url_round_trip() {
+        let base = spawn_server();
+        let provider = SignedUrlStoreProvider::new(Arc::new(LocalSigner { base }));
+        let store = provider
+            .new_store(
+                Url::parse("s3://bucket/").unwrap(),
+                &ObjectStoreParams::default(),
+            )
+            .await
+            .unwrap();
+        let path = Path::from("data/0.lance");
+
+        let err = store.inner.head(&path).await.unwrap_err();
+        assert!(matches!(err, object_store::Error::NotFound { .. }));
+
+        let data = Bytes::from_static(b"0123456789");
+        store.inner.put(&path, data.clone().into()).await.unwrap();
+        let meta = store.inner.head(&path).await.unwrap();
+        assert_eq!(meta.size, 10);
+        let result = store.inner.get(&path).await.unwrap();
+        assert_eq!(result.meta.size, 10);
+        assert_eq!(result.bytes().await.unwrap(), data);
+        let range = store.inner.get_range(&path, 2..5).await.unwrap();
+        assert_eq!(range, data.slice(2..5));
+
+        let err = store
+            .inner
+            .put_opts(&path, data.clone().into(), PutMode::Create.into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, object_store::Error::AlreadyExists { .. }));
+
+        // Multipart uploads are sent as a single PUT when they complete
+        let mut upload = store.inner.put_multipart(&path).await.unwrap();
+        upload
+            .put_part(Bytes::from_static(b"abc").into())
+            .await
+            .unwrap();
+        upload
+            .put_part(Bytes::from_static(b"def").into())
+            .await
+            .unwrap();
+        upload.complete().await.unwrap();
+        let result = store.inner.get(&path).await.unwrap();
+        assert_eq!(result.bytes().await.unwrap(), Bytes::from_static(b"abcdef"));
+    }
+}
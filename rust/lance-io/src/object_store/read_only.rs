@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Wrapper around object_store that rejects all writes
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, PutMultipartOpts, PutOptions,
+    PutPayload, PutResult, Result as OSResult,
+};
+
+fn read_only_error(path: &Path) -> object_store::Error {
+    object_store::Error::PermissionDenied {
+        path: path.to_string(),
+        source: "the object store was opened read-only".into(),
+    }
+}
+
+/// Forwards reads and fails every put, copy and delete with
+/// [`object_store::Error::PermissionDenied`] before it reaches the store.
+#[derive(Debug)]
+pub struct ReadOnlyObjectStore {
+    target: Arc<dyn object_store::ObjectStore>,
+}
+
+impl std::fmt::Display for ReadOnlyObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("ReadOnlyObjectStore({})", self.target))
+    }
+}
+
+#[async_trait::async_trait]
+impl object_store::ObjectStore for ReadOnlyObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        _payload: PutPayload,
+        _opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        Err(read_only_error(location))
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        Err(read_only_error(location))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        self.target.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> OSResult<Bytes> {
+        self.target.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<u64>]) -> OSResult<Vec<Bytes>> {
+        self.target.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> OSResult<ObjectMeta> {
+        self.target.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        Err(read_only_error(location))
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+        self.target.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, _from: &Path, to: &Path) -> OSResult<()> {
+        Err(read_only_error(to))
+    }
+
+    async fn rename(&self, from: &Path, _to: &Path) -> OSResult<()> {
+        Err(read_only_error(from))
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, to: &Path) -> OSResult<()> {
+        Err(read_only_error(to))
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, _to: &Path) -> OSResult<()> {
+        Err(read_only_error(from))
+    }
+}
+
+pub trait ObjectStoreReadOnlyExt {
+    fn read_only(self) -> Arc<dyn object_store::ObjectStore>;
+}
+
+impl ObjectStoreReadOnlyExt for Arc<dyn object_store::ObjectStore> {
+    fn read_only(self) -> Arc<dyn object_store::ObjectStore> {
+        Arc::new(ReadOnlyObjectStore { target: self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_only_store() {
+        let memory: Arc<dyn object_store::ObjectStore> = Arc::new(InMemory::new());
+        let path = Path::from("data/0.lance");
+        memory
+            .put(&path, Bytes::from_static(b"lance").into())
+            .await
+            .unwrap();
+
+        let store = memory.clone().read_only();
+        assert_eq!(store.get_range(&path, 1..3).await.unwrap().as_ref(), b"an");
+        assert_eq!(
+            store
+                .list_with_delimiter(None)
+                .await
+                .unwrap()
+                .common_prefixes
+                .len(),
+            1
+        );
+
+        let other = Path::from("data/1.lance");
+        let is_denied =
+            |err: object_store::Error| matches!(err, object_store::Error::PermissionDenied { .. });
+        assert!(is_denied(
+            store.put(&other, Bytes::new().into()).await.unwrap_err()
+        ));
+        assert!(is_denied(store.put_multipart(&other).await.unwrap_err()));
+        assert!(is_denied(store.copy(&path, &other).await.unwrap_err()));
+        assert!(is_denied(store.rename(&path, &other).await.unwrap_err()));
+        assert!(is_denied(store.delete(&path).await.unwrap_err()));
+        // Nothing reached the underlying store
+        assert!(memory.head(&path).await.is_ok());
+        assert!(memory.head(&other).await.is_err());
+    }
+}
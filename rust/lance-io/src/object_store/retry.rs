@@ -0,0 +1,636 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Configurable retries for object store operations
+//!
+//! The clients in `object_store` already retry failed requests a fixed number
+//! of times. A [`RetryPolicy`] adds an outer retry loop around every operation
+//! with control over the number of attempts, the backoff between them, an
+//! overall deadline and which kinds of errors are retried at all.
+//!
+//! Conditional writes are never retried: if one succeeded but its response was
+//! lost, the retry would fail with a precondition error and the caller could not
+//! tell its own write from a concurrent one.
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::Future;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, PutMode, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result as OSResult,
+};
+use rand::Rng;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use super::metrics::{ObjectStoreMetrics, Verb};
+use super::StorageOptions;
+
+/// How an object store error should be treated by a retry loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The store is rate limiting requests (HTTP 429, S3 `SlowDown`, ...).
+    /// Retrying helps, but only after backing off.
+    ///
+    /// The clients of `object_store` do not expose the status of a failed request
+    /// and already back off on throttling responses themselves, so
+    /// [`ErrorClass::of`] reports their errors as [`ErrorClass::Transient`].
+    Throttled,
+    /// A network failure, timeout or server error that may succeed on retry.
+    Transient,
+    /// The request can never succeed as is, e.g. the object does not exist,
+    /// a precondition failed or the credentials were rejected.
+    Permanent,
+}
+
+impl ErrorClass {
+    pub fn of(error: &object_store::Error) -> Self {
+        use object_store::Error::*;
+        match error {
+            NotFound { .. }
+            | AlreadyExists { .. }
+            | Precondition { .. }
+            | NotModified { .. }
+            | InvalidPath { .. }
+            | NotSupported { .. }
+            | NotImplemented
+            | PermissionDenied { .. }
+            | Unauthenticated { .. }
+            | UnknownConfigurationKey { .. } => Self::Permanent,
+            Generic { source, .. } => Self::of_source(source.as_ref()),
+            _ => Self::Transient,
+        }
+    }
+
+    /// Classify the source of a [`object_store::Error::Generic`] by the first
+    /// I/O error in its chain, e.g. from the local file system.
+    fn of_source(source: &(dyn std::error::Error + 'static)) -> Self {
+        use std::io::ErrorKind;
+        let mut next = Some(source);
+        while let Some(error) = next {
+            if let Some(error) = error.downcast_ref::<std::io::Error>() {
+                return match error.kind() {
+                    ErrorKind::NotFound
+                    | ErrorKind::PermissionDenied
+                    | ErrorKind::AlreadyExists
+                    | ErrorKind::InvalidInput
+                    | ErrorKind::InvalidData
+                    | ErrorKind::Unsupported => Self::Permanent,
+                    _ => Self::Transient,
+                };
+            }
+            next = error.source();
+        }
+        Self::Transient
+    }
+}
+
+/// Decides whether and when a failed operation is retried.
+pub trait RetryPolicy: Debug + Send + Sync {
+    /// Returns how long to wait before the next attempt, or `None` to give up.
+    ///
+    /// `attempt` is the number of attempts made so far (starting at 1) and
+    /// `elapsed` is the time since the first attempt started.
+    fn next_delay(&self, attempt: usize, class: ErrorClass, elapsed: Duration) -> Option<Duration>;
+}
+
+/// Exponential backoff with jitter.
+///
+/// This can be configured with the storage options `retry_max_attempts`,
+/// `retry_base_delay_ms`, `retry_max_delay_ms`, `retry_jitter` and
+/// `retry_deadline_ms`.
+#[derive(Debug, Clone)]
+pub struct BackoffRetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: usize,
+    /// Delay before the first retry. It doubles on each further retry.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of the delay, between 0 and 1, that is randomized so that
+    /// clients failing together don't retry together.
+    pub jitter: f64,
+    /// Factor applied to the delay when the store is throttling requests.
+    pub throttle_multiplier: u32,
+    /// Whether to retry errors classified as [`ErrorClass::Transient`].
+    pub retry_transient: bool,
+    /// Whether to retry errors classified as [`ErrorClass::Throttled`].
+    pub retry_throttled: bool,
+    /// Give up once this much time has passed since the first attempt.
+    pub deadline: Option<Duration>,
+}
+
+impl Default for BackoffRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+            throttle_multiplier: 4,
+            retry_transient: true,
+            retry_throttled: true,
+            deadline: None,
+        }
+    }
+}
+
+impl BackoffRetryPolicy {
+    /// Create a policy from the `retry_*` storage options, or `None` if none
+    /// of them are set.
+    pub fn from_storage_options(options: &StorageOptions) -> Option<Self> {
+        fn get<T: std::str::FromStr>(options: &StorageOptions, key: &str) -> Option<T> {
+            options
+                .0
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .and_then(|(_, value)| value.parse().ok())
+        }
+        if !options
+            .0
+            .keys()
+            .any(|key| key.to_ascii_lowercase().starts_with("retry_"))
+        {
+            return None;
+        }
+        let default = Self::default();
+        Some(Self {
+            max_attempts: get(options, "retry_max_attempts").unwrap_or(default.max_attempts),
+            base_delay: get(options, "retry_base_delay_ms")
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            max_delay: get(options, "retry_max_delay_ms")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+            jitter: get(options, "retry_jitter").unwrap_or(default.jitter),
+            deadline: get(options, "retry_deadline_ms").map(Duration::from_millis),
+            ..default
+        })
+    }
+
+    fn backoff(&self, attempt: usize, class: ErrorClass) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16) as u32;
+        let mut delay = self.base_delay.saturating_mul(1 << exponent);
+        if class == ErrorClass::Throttled {
+            delay = delay.saturating_mul(self.throttle_multiplier);
+        }
+        let delay = delay.min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 - jitter * rand::thread_rng().gen::<f64>())
+    }
+}
+
+impl RetryPolicy for BackoffRetryPolicy {
+    fn next_delay(&self, attempt: usize, class: ErrorClass, elapsed: Duration) -> Option<Duration> {
+        let retry = match class {
+            ErrorClass::Throttled => self.retry_throttled,
+            ErrorClass::Transient => self.retry_transient,
+            ErrorClass::Permanent => false,
+        };
+        if !retry || attempt >= self.max_attempts {
+            return None;
+        }
+        let delay = self.backoff(attempt, class);
+        match self.deadline {
+            Some(deadline) if elapsed + delay >= deadline => None,
+            _ => Some(delay),
+        }
+    }
+}
+
+/// Run `op` until it succeeds or `policy` gives up.
+pub async fn with_retry<T, F, Fut>(policy: &dyn RetryPolicy, op: F) -> OSResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = OSResult<T>>,
+{
+    retry_loop(policy, |_, _| {}, op).await
+}
+
+async fn retry_loop<T, F, Fut>(
+    policy: &dyn RetryPolicy,
+    on_retry: impl Fn(ErrorClass, Duration),
+    mut op: F,
+) -> OSResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = OSResult<T>>,
+{
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let class = ErrorClass::of(&err);
+        match policy.next_delay(attempt, class, start.elapsed()) {
+            Some(delay) => {
+                log::debug!(
+                    "Retrying {:?} error after {:?} (attempt {}): {}",
+                    class,
+                    delay,
+                    attempt,
+                    err
+                );
+                on_retry(class, delay);
+                tokio::time::sleep(delay).await;
+            }
+            None => return Err(err),
+        }
+    }
+}
+
+/// An object store that retries failed operations according to a [`RetryPolicy`].
+///
+/// Starting a multipart upload is retried, but the returned upload is not
+/// wrapped, so its parts and completion are sent once:
+/// [`crate::object_writer::ObjectWriter`] retries failed parts itself.
+/// Conditional puts, `rename_if_not_exists` and `copy_if_not_exists` are passed
+/// through, so that their errors reach the commit handler as they are.
+#[derive(Debug)]
+pub struct RetryObjectStore {
+    target: Arc<dyn object_store::ObjectStore>,
+    policy: Arc<dyn RetryPolicy>,
+    metrics: Option<Arc<dyn ObjectStoreMetrics>>,
+}
+
+impl std::fmt::Display for RetryObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("RetryObjectStore({})", self.target))
+    }
+}
+
+impl RetryObjectStore {
+    pub fn new(target: Arc<dyn object_store::ObjectStore>, policy: Arc<dyn RetryPolicy>) -> Self {
+        Self {
+            target,
+            policy,
+            metrics: None,
+        }
+    }
+
+    /// Report retries to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn ObjectStoreMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn retry<T, Fut>(&self, verb: Verb, op: impl FnMut() -> Fut) -> OSResult<T>
+    where
+        Fut: Future<Output = OSResult<T>>,
+    {
+        let on_retry = |class, delay| {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_retry(verb, class, delay);
+            }
+        };
+        retry_loop(self.policy.as_ref(), on_retry, op).await
+    }
+}
+
+#[async_trait::async_trait]
+#[deny(clippy::missing_trait_methods)]
+impl object_store::ObjectStore for RetryObjectStore {
+    async fn put(&self, location: &Path, bytes: PutPayload) -> OSResult<PutResult> {
+        self.retry(Verb::Put, || self.target.put(location, bytes.clone()))
+            .await
+    }
+
+    async fn put_opts(
+        &self,
+        location: &Path,
+        bytes: PutPayload,
+        opts: PutOptions,
+    ) -> OSResult<PutResult> {
+        if !matches!(opts.mode, PutMode::Overwrite) {
+            return self.target.put_opts(location, bytes, opts).await;
+        }
+        self.retry(Verb::Put, || {
+            self.target.put_opts(location, bytes.clone(), opts.clone())
+        })
+        .await
+    }
+
+    async fn put_multipart(&self, location: &Path) -> OSResult<Box<dyn MultipartUpload>> {
+        self.retry(Verb::CreateMultipart, || {
+            self.target.put_multipart(location)
+        })
+        .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> OSResult<Box<dyn MultipartUpload>> {
+        self.retry(Verb::CreateMultipart, || {
+            self.target.put_multipart_opts(location, opts.clone())
+        })
+        .await
+    }
+
+    async fn get(&self, location: &Path) -> OSResult<GetResult> {
+        self.retry(Verb::Get, || self.target.get(location)).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+        self.retry(Verb::Get, || {
+            self.target.get_opts(location, options.clone())
+        })
+        .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> OSResult<Bytes> {
+        self.retry(Verb::Get, || self.target.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<u64>]) -> OSResult<Vec<Bytes>> {
+        self.retry(Verb::Get, || self.target.get_ranges(location, ranges))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> OSResult<ObjectMeta> {
+        self.retry(Verb::Head, || self.target.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> OSResult<()> {
+        self.retry(Verb::Delete, || self.target.delete(location))
+            .await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, OSResult<Path>>,
+    ) -> BoxStream<'a, OSResult<Path>> {
+        self.target.delete_stream(locations)
+    }
+
+    // Listings are resumed from the last returned key by `ListRetryStream`.
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, OSResult<ObjectMeta>> {
+        self.target.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+        self.retry(Verb::List, || self.target.list_with_delimiter(prefix))
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.retry(Verb::Copy, || self.target.copy(from, to)).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.retry(Verb::Rename, || self.target.rename(from, to))
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.target.rename_if_not_exists(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+        self.target.copy_if_not_exists(from, to).await
+    }
+}
+
+pub trait ObjectStoreRetryExt {
+    fn with_retry_policy(self, policy: Arc<dyn RetryPolicy>) -> Arc<dyn object_store::ObjectStore>;
+}
+
+impl ObjectStoreRetryExt for Arc<dyn object_store::ObjectStore> {
+    fn with_retry_policy(self, policy: Arc<dyn RetryPolicy>) -> Arc<dyn object_store::ObjectStore> {
+        Arc::new(RetryObjectStore::new(self, policy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore;
+
+    use super::*;
+
+    fn generic(message: &str) -> object_store::Error {
+        object_store::Error::Generic {
+            store: "test",
+            source: message.to_string().into(),
+        }
+    }
+
+    #[test]
+    fn test_classify_errors() {
+        assert_eq!(
+            ErrorClass::of(&object_store::Error::NotFound {
+                path: "a".into(),
+                source: "missing".into(),
+            }),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            ErrorClass::of(&generic("error decoding response body")),
+            ErrorClass::Transient
+        );
+        let io_error = |kind| object_store::Error::Generic {
+            store: "test",
+            source: Box::new(std::io::Error::new(kind, "io error")),
+        };
+        assert_eq!(
+            ErrorClass::of(&io_error(std::io::ErrorKind::PermissionDenied)),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            ErrorClass::of(&io_error(std::io::ErrorKind::ConnectionReset)),
+            ErrorClass::Transient
+        );
+    }
+
+    /// Applies every write, but fails the first `failures` of them as if the
+    /// response was lost.
+    #[derive(Debug)]
+    struct LostResponseStore {
+        inner: InMemory,
+        failures: AtomicUsize,
+    }
+
+    impl std::fmt::Display for LostResponseStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "LostResponseStore")
+        }
+    }
+
+    impl LostResponseStore {
+        fn lose_response<T>(&self, result: OSResult<T>) -> OSResult<T> {
+            let value = result?;
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if fail {
+                Err(generic("connection reset"))
+            } else {
+                Ok(value)
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl object_store::ObjectStore for LostResponseStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            bytes: PutPayload,
+            opts: PutOptions,
+        ) -> OSResult<PutResult> {
+            let result = self.inner.put_opts(location, bytes, opts).await;
+            self.lose_response(result)
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            opts: PutMultipartOpts,
+        ) -> OSResult<Box<dyn MultipartUpload>> {
+            let result = self.inner.put_multipart_opts(location, opts).await;
+            self.lose_response(result)
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> OSResult<GetResult> {
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> OSResult<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, OSResult<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> OSResult<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> OSResult<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> OSResult<()> {
+            let result = self.inner.copy_if_not_exists(from, to).await;
+            self.lose_response(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_writes_not_retried() {
+        let policy = BackoffRetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let target = Arc::new(LostResponseStore {
+            inner: InMemory::new(),
+            failures: AtomicUsize::new(usize::MAX),
+        });
+        let store = RetryObjectStore::new(target.clone(), Arc::new(policy));
+        let location = Path::from("_versions/1.manifest");
+
+        // The error of the lost response is returned rather than the
+        // `AlreadyExists` of a retry.
+        let err = store
+            .put_opts(&location, "a".into(), PutMode::Create.into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, object_store::Error::Generic { .. }));
+        let err = store
+            .copy_if_not_exists(&location, &Path::from("_versions/2.manifest"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, object_store::Error::Generic { .. }));
+
+        // Unconditional writes are still retried, and so is starting a
+        // multipart upload
+        target.failures.store(1, Ordering::SeqCst);
+        store.put(&location, "b".into()).await.unwrap();
+        target.failures.store(1, Ordering::SeqCst);
+        let mut upload = store.put_multipart(&location).await.unwrap();
+        upload.put_part("c".into()).await.unwrap();
+        upload.complete().await.unwrap();
+    }
+
+    #[test]
+    fn test_backoff_policy() {
+        let policy = BackoffRetryPolicy {
+            max_attempts: 3,
+            jitter: 0.0,
+            deadline: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let zero = Duration::ZERO;
+        assert_eq!(
+            policy.next_delay(1, ErrorClass::Transient, zero),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.next_delay(2, ErrorClass::Throttled, zero),
+            Some(Duration::from_millis(800))
+        );
+        assert_eq!(policy.next_delay(3, ErrorClass::Transient, zero), None);
+        assert_eq!(policy.next_delay(1, ErrorClass::Permanent, zero), None);
+        assert_eq!(
+            policy.next_delay(1, ErrorClass::Transient, Duration::from_millis(950)),
+            None
+        );
+
+        let options = StorageOptions(HashMap::from([(
+            "retry_max_attempts".to_string(),
+            "7".to_string(),
+        )]));
+        let policy = BackoffRetryPolicy::from_storage_options(&options).unwrap();
+        assert_eq!(policy.max_attempts, 7);
+        assert!(BackoffRetryPolicy::from_storage_options(&StorageOptions::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_with_retry() {
+        let policy = BackoffRetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let calls = AtomicUsize::new(0);
+        let result = with_retry(&policy, || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(generic("connection reset"))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let store = Arc::new(InMemory::new()) as Arc<dyn object_store::ObjectStore>;
+        let store = store.with_retry_policy(Arc::new(policy));
+        let err = store.head(&Path::from("missing")).await.unwrap_err();
+        assert!(matches!(err, object_store::Error::NotFound { .. }));
+    }
+}
@@ -7,6 +7,7 @@ use std::sync::{Arc, OnceLock};
 use std::task::Poll;
 
 use crate::object_store::ObjectStore as LanceObjectStore;
+use crate::throttle::BandwidthLimiter;
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::future::BoxFuture;
@@ -17,6 +18,7 @@ use rand::Rng;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::task::JoinSet;
 
+use lance_core::utils::tokio::spawn;
 use lance_core::{Error, Result};
 use tracing::Instrument;
 
@@ -25,8 +27,12 @@ use snafu::location;
 
 /// Start at 5MB.
 const INITIAL_UPLOAD_STEP: usize = 1024 * 1024 * 5;
+/// Minimum part size in GCS and S3
+pub(crate) const MIN_UPLOAD_PART_SIZE: usize = INITIAL_UPLOAD_STEP;
+/// Maximum part size in GCS and S3
+pub(crate) const MAX_UPLOAD_PART_SIZE: usize = 1024 * 1024 * 1024 * 5;
 
-fn max_upload_parallelism() -> usize {
+pub(crate) fn default_upload_concurrency() -> usize {
     static MAX_UPLOAD_PARALLELISM: OnceLock<usize> = OnceLock::new();
     *MAX_UPLOAD_PARALLELISM.get_or_init(|| {
         std::env::var("LANCE_UPLOAD_CONCURRENCY")
@@ -46,18 +52,16 @@ fn max_conn_reset_retries() -> u16 {
     })
 }
 
-fn initial_upload_size() -> usize {
+pub(crate) fn default_upload_part_size() -> usize {
     static LANCE_INITIAL_UPLOAD_SIZE: OnceLock<usize> = OnceLock::new();
     *LANCE_INITIAL_UPLOAD_SIZE.get_or_init(|| {
         std::env::var("LANCE_INITIAL_UPLOAD_SIZE")
             .ok()
             .and_then(|s| s.parse::<usize>().ok())
             .inspect(|size| {
-                if *size < INITIAL_UPLOAD_STEP {
-                    // Minimum part size in GCS and S3
+                if *size < MIN_UPLOAD_PART_SIZE {
                     panic!("LANCE_INITIAL_UPLOAD_SIZE must be at least 5MB");
-                } else if *size > 1024 * 1024 * 1024 * 5 {
-                    // Maximum part size in GCS and S3
+                } else if *size > MAX_UPLOAD_PART_SIZE {
                     panic!("LANCE_INITIAL_UPLOAD_SIZE must be at most 5GB");
                 }
             })
@@ -69,7 +73,9 @@ fn initial_upload_size() -> usize {
 ///
 /// If the object is small enough, the writer will upload the object in a single
 /// PUT request. If the object is larger, the writer will create a multipart
-/// upload and upload parts in parallel.
+/// upload and upload parts in parallel. The part size and the number of parts
+/// in flight are taken from the [`LanceObjectStore`], so at most
+/// `upload_concurrency + 1` parts are held in memory at once.
 ///
 /// This implements the `AsyncWrite` trait.
 pub struct ObjectWriter {
@@ -80,6 +86,10 @@ pub struct ObjectWriter {
     buffer: Vec<u8>,
     // TODO: use constant size to support R2
     use_constant_size_upload_parts: bool,
+    part_size: usize,
+    upload_concurrency: usize,
+    // Set if the writer is used by background work and uploads are limited
+    upload_limiter: Option<Arc<BandwidthLimiter>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -111,13 +121,21 @@ enum UploadState {
 
 /// Methods for state transitions.
 impl UploadState {
-    fn started_to_completing(&mut self, path: Arc<Path>, buffer: Vec<u8>) {
+    fn started_to_completing(
+        &mut self,
+        path: Arc<Path>,
+        buffer: Vec<u8>,
+        upload_limiter: Option<Arc<BandwidthLimiter>>,
+    ) {
         // To get owned self, we temporarily swap with Done.
         let this = std::mem::replace(self, Self::Done(WriteResult::default()));
         *self = match this {
             Self::Started(store) => {
                 let fut = async move {
                     let size = buffer.len();
+                    if let Some(limiter) = upload_limiter {
+                        limiter.acquire(size as u64).await;
+                    }
                     let res = store.put(&path, buffer.into()).await?;
                     Ok(WriteResult {
                         size,
@@ -161,20 +179,30 @@ impl ObjectWriter {
             cursor: 0,
             path: Arc::new(path.clone()),
             connection_resets: 0,
-            buffer: Vec::with_capacity(initial_upload_size()),
+            buffer: Vec::with_capacity(object_store.upload_part_size()),
             use_constant_size_upload_parts: object_store.use_constant_size_upload_parts,
+            part_size: object_store.upload_part_size(),
+            upload_concurrency: object_store.upload_concurrency(),
+            upload_limiter: object_store.bandwidth_limits().upload,
         })
     }
 
     /// Returns the contents of `buffer` as a `Bytes` object and resets `buffer`.
     /// The new capacity of `buffer` is determined by the current part index.
-    fn next_part_buffer(buffer: &mut Vec<u8>, part_idx: u16, constant_upload_size: bool) -> Bytes {
+    fn next_part_buffer(
+        buffer: &mut Vec<u8>,
+        part_idx: u16,
+        part_size: usize,
+        constant_upload_size: bool,
+    ) -> Bytes {
         let new_capacity = if constant_upload_size {
             // The store does not support variable part sizes, so use the initial size.
-            initial_upload_size()
+            part_size
         } else {
             // Increase the upload size every 100 parts. This gives maximum part size of 2.5TB.
-            initial_upload_size().max(((part_idx / 100) as usize + 1) * INITIAL_UPLOAD_STEP)
+            part_size
+                .max(((part_idx / 100) as usize + 1) * INITIAL_UPLOAD_STEP)
+                .min(MAX_UPLOAD_PART_SIZE)
         };
         let new_buffer = Vec::with_capacity(new_capacity);
         let part = std::mem::replace(buffer, new_buffer);
@@ -186,16 +214,22 @@ impl ObjectWriter {
         buffer: Bytes,
         part_idx: u16,
         sleep: Option<std::time::Duration>,
+        upload_limiter: Option<Arc<BandwidthLimiter>>,
     ) -> BoxFuture<'static, std::result::Result<(), UploadPutError>> {
         log::debug!(
             "MultipartUpload submitting part with {} bytes",
             buffer.len()
         );
+        // The request is only sent once the returned future is polled, so the
+        // limiter can hold it back
         let fut = upload.put_part(buffer.clone().into());
         Box::pin(async move {
             if let Some(sleep) = sleep {
                 tokio::time::sleep(sleep).await;
             }
+            if let Some(limiter) = upload_limiter {
+                limiter.acquire(buffer.len() as u64).await;
+            }
             fut.await.map_err(|source| UploadPutError {
                 part_idx,
                 buffer,
@@ -220,9 +254,16 @@ impl ObjectWriter {
                         let data = Self::next_part_buffer(
                             &mut mut_self.buffer,
                             0,
+                            mut_self.part_size,
                             mut_self.use_constant_size_upload_parts,
                         );
-                        futures.spawn(Self::put_part(upload.as_mut(), data, 0, None));
+                        futures.spawn(Self::put_part(
+                            upload.as_mut(),
+                            data,
+                            0,
+                            None,
+                            mut_self.upload_limiter.clone(),
+                        ));
 
                         mut_self.state = UploadState::InProgress {
                             part_idx: 1, // We just used 0
@@ -267,6 +308,7 @@ impl ObjectWriter {
                                         buffer,
                                         part_idx,
                                         Some(sleep_time),
+                                        mut_self.upload_limiter.clone(),
                                     ));
                                 } else {
                                     return Err(io::Error::new(
@@ -327,7 +369,7 @@ impl Drop for ObjectWriter {
             let state =
                 std::mem::replace(&mut self.state, UploadState::Done(WriteResult::default()));
             if let UploadState::InProgress { mut upload, .. } = state {
-                tokio::task::spawn(async move {
+                spawn(async move {
                     let _ = upload.abort().await;
                 });
             }
@@ -391,16 +433,22 @@ impl AsyncWrite for ObjectWriter {
                     futures,
                     ..
                 } => {
-                    // TODO: Make max concurrency configurable from storage options.
-                    if futures.len() < max_upload_parallelism() {
+                    if futures.len() < mut_self.upload_concurrency {
                         let data = Self::next_part_buffer(
                             &mut mut_self.buffer,
                             *part_idx,
+                            mut_self.part_size,
                             mut_self.use_constant_size_upload_parts,
                         );
                         futures.spawn(
-                            Self::put_part(upload.as_mut(), data, *part_idx, None)
-                                .instrument(tracing::Span::current()),
+                            Self::put_part(
+                                upload.as_mut(),
+                                data,
+                                *part_idx,
+                                None,
+                                mut_self.upload_limiter.clone(),
+                            )
+                            .instrument(tracing::Span::current()),
                         );
                         *part_idx += 1;
                     }
@@ -457,7 +505,8 @@ impl AsyncWrite for ObjectWriter {
                     // If we didn't start a multipart upload, we can just do a single put.
                     let part = std::mem::take(&mut mut_self.buffer);
                     let path = mut_self.path.clone();
-                    self.state.started_to_completing(path, part);
+                    let upload_limiter = mut_self.upload_limiter.clone();
+                    self.state.started_to_completing(path, part, upload_limiter);
                 }
                 UploadState::InProgress {
                     upload,
@@ -465,12 +514,18 @@ impl AsyncWrite for ObjectWriter {
                     part_idx,
                 } => {
                     // Flush final batch
-                    if !mut_self.buffer.is_empty() && futures.len() < max_upload_parallelism() {
+                    if !mut_self.buffer.is_empty() && futures.len() < mut_self.upload_concurrency {
                         // We can just use `take` since we don't need the buffer anymore.
                         let data = Bytes::from(std::mem::take(&mut mut_self.buffer));
                         futures.spawn(
-                            Self::put_part(upload.as_mut(), data, *part_idx, None)
-                                .instrument(tracing::Span::current()),
+                            Self::put_part(
+                                upload.as_mut(),
+                                data,
+                                *part_idx,
+                                None,
+                                mut_self.upload_limiter.clone(),
+                            )
+                            .instrument(tracing::Span::current()),
                         );
                         // We need to go back to beginning of loop to poll the
                         // new feature and get the waker registered on the ctx.
@@ -501,6 +556,7 @@ mod tests {
     use tokio::io::AsyncWriteExt;
 
     use super::*;
+    use crate::object_store::{ObjectStoreParams, ObjectStoreRegistry};
 
     #[tokio::test]
     async fn test_write() {
@@ -539,4 +595,36 @@ mod tests {
         let res = object_writer.shutdown().await.unwrap();
         assert_eq!(res.size, buf.len() * 5);
     }
+
+    #[tokio::test]
+    async fn test_write_parallel_parts() {
+        let params = ObjectStoreParams {
+            upload_part_size: Some(INITIAL_UPLOAD_STEP + 1),
+            upload_concurrency: Some(2),
+            ..Default::default()
+        };
+        let (store, _) = LanceObjectStore::from_uri_and_params(
+            Arc::new(ObjectStoreRegistry::default()),
+            "memory:///",
+            &params,
+        )
+        .await
+        .unwrap();
+
+        let path = Path::from("/parts");
+        let mut object_writer = ObjectWriter::new(&store, &path).await.unwrap();
+        let data = (0..INITIAL_UPLOAD_STEP * 4)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        for chunk in data.chunks(1024 * 1024) {
+            object_writer.write_all(chunk).await.unwrap();
+            // Parts are handed off once full
+            assert!(object_writer.buffer.len() <= INITIAL_UPLOAD_STEP + 1);
+        }
+        let res = object_writer.shutdown().await.unwrap();
+        assert_eq!(res.size, data.len());
+
+        let written = store.inner.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(written.as_ref(), data.as_slice());
+    }
 }
@@ -6,22 +6,32 @@ use futures::channel::oneshot;
 use futures::{FutureExt, TryFutureExt};
 use object_store::path::Path;
 use snafu::location;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt::Debug;
 use std::future::Future;
 use std::num::NonZero;
 use std::ops::Range;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
+use lance_core::utils::metrics;
+use lance_core::utils::tokio::spawn;
 use lance_core::{Error, Result};
 
 use crate::object_store::ObjectStore;
 use crate::traits::Reader;
 use crate::utils::CachedFileSize;
 
+mod coalesce;
+
+use coalesce::AdaptiveGap;
+pub use coalesce::CoalescingConfig;
+
 // Don't log backpressure warnings until at least this many seconds have passed
 const BACKPRESSURE_MIN: u64 = 5;
 // Don't log backpressure warnings more than once / minute
@@ -44,6 +54,9 @@ static BYTES_READ_COUNTER: AtomicU64 = AtomicU64::new(0);
 // Note: this only limits things that run through the scheduler.  It does not limit
 // IOPS from other sources like writing or commits.
 static DEFAULT_PROCESS_IOPS_LIMIT: i32 = 128;
+// Background I/O (compaction, index builds, ...) may only use part of the process
+// quota so that it can never take all of the IOPS away from interactive queries.
+static DEFAULT_PROCESS_BACKGROUND_IOPS_LIMIT: i32 = 64;
 
 pub fn iops_counter() -> u64 {
     IOPS_COUNTER.load(Ordering::Acquire)
@@ -105,16 +118,16 @@ impl IopsQuota {
     // By default, we throttle the number of scan IOPS across the entire process
     //
     // However, the user can disable this by setting the environment variable
-    // LANCE_PROCESS_IO_THREADS_LIMIT to zero (or a negative integer).
-    fn new() -> Self {
-        let initial_capacity = std::env::var("LANCE_PROCESS_IO_THREADS_LIMIT")
+    // (e.g. LANCE_PROCESS_IO_THREADS_LIMIT) to zero (or a negative integer).
+    fn from_env(var: &str, default: i32) -> Self {
+        let initial_capacity = std::env::var(var)
             .map(|s| {
                 s.parse::<i32>().unwrap_or_else(|_| {
-                    log::warn!("Ignoring invalid LANCE_PROCESS_IO_THREADS_LIMIT: {}", s);
-                    DEFAULT_PROCESS_IOPS_LIMIT
+                    log::warn!("Ignoring invalid {}: {}", var, s);
+                    default
                 })
             })
-            .unwrap_or(DEFAULT_PROCESS_IOPS_LIMIT);
+            .unwrap_or(default);
         let iops_avail = if initial_capacity <= 0 {
             None
         } else {
@@ -143,7 +156,28 @@ impl IopsQuota {
 }
 
 lazy_static::lazy_static! {
-    static ref IOPS_QUOTA: IopsQuota = IopsQuota::new();
+    static ref IOPS_QUOTA: IopsQuota =
+        IopsQuota::from_env("LANCE_PROCESS_IO_THREADS_LIMIT", DEFAULT_PROCESS_IOPS_LIMIT);
+    // Taken in addition to IOPS_QUOTA by background I/O
+    static ref BACKGROUND_IOPS_QUOTA: IopsQuota = IopsQuota::from_env(
+        "LANCE_PROCESS_BACKGROUND_IO_THREADS_LIMIT",
+        DEFAULT_PROCESS_BACKGROUND_IOPS_LIMIT
+    );
+}
+
+/// The class of work an I/O scheduler is reading data for.
+///
+/// Background reads are limited to a smaller share of the process-wide IOPS
+/// quota (see `LANCE_PROCESS_BACKGROUND_IO_THREADS_LIMIT`) and of the
+/// store's parallelism, so that long running jobs don't starve the reads of
+/// latency sensitive queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IoPriorityClass {
+    /// Reads for queries that a user is waiting on.
+    #[default]
+    Interactive,
+    /// Reads for maintenance work such as compaction or index builds.
+    Background,
 }
 
 // We want to allow requests that have a lower priority than any
@@ -279,14 +313,16 @@ impl IoQueueState {
 struct IoQueue {
     // Queue state
     state: Mutex<IoQueueState>,
+    priority_class: IoPriorityClass,
     // Used to signal new I/O requests have arrived that might potentially be runnable
     notify: Notify,
 }
 
 impl IoQueue {
-    fn new(io_capacity: u32, io_buffer_size: u64) -> Self {
+    fn new(io_capacity: u32, io_buffer_size: u64, priority_class: IoPriorityClass) -> Self {
         Self {
             state: Mutex::new(IoQueueState::new(io_capacity, io_buffer_size)),
+            priority_class,
             notify: Notify::new(),
         }
     }
@@ -312,13 +348,24 @@ impl IoQueue {
                 // If we then get a task to run, transfer the reservation
                 // to the task.  Otherwise, the reservation will be released
                 // when iop_res is dropped.
+                //
+                // Background queues must get a share of the background quota first
+                // so that they hold at most that many of the global reservations.
+                let mut background_res = match self.priority_class {
+                    IoPriorityClass::Background => Some(BACKGROUND_IOPS_QUOTA.acquire().await),
+                    IoPriorityClass::Interactive => None,
+                };
                 let mut iop_res = IOPS_QUOTA.acquire().await;
                 // Next, try and grab a reservation from the queue
                 let mut state = self.state.lock().unwrap();
-                if let Some(task) = state.next_task() {
+                if let Some(mut task) = state.next_task() {
                     // Reservation successfully acquired, we will release the global
                     // global reservation after task has run.
                     iop_res.forget();
+                    if let Some(background_res) = background_res.as_mut() {
+                        background_res.forget();
+                    }
+                    task.priority_class = self.priority_class;
                     return Some(task);
                 }
 
@@ -445,6 +492,11 @@ struct IoTask {
     to_read: Range<u64>,
     when_done: Box<dyn FnOnce(Result<Bytes>) + Send>,
     priority: u128,
+    priority_class: IoPriorityClass,
+    latency_observer: Option<Arc<AdaptiveGap>>,
+    /// The span that submitted the request, the read is traced as its child
+    #[cfg(feature = "query-tracing")]
+    span: tracing::Span,
 }
 
 impl Eq for IoTask {}
@@ -480,11 +532,42 @@ impl IoTask {
             let bytes_fut = self
                 .reader
                 .get_range(self.to_read.start as usize..self.to_read.end as usize);
+            #[cfg(feature = "query-tracing")]
+            let bytes_fut = tracing::Instrument::instrument(
+                bytes_fut,
+                tracing::info_span!(
+                    target: lance_core::utils::tracing::TRACE_QUERY,
+                    parent: &self.span,
+                    "read",
+                    path = %self.reader.path(),
+                    offset = self.to_read.start,
+                    num_bytes = self.num_bytes(),
+                ),
+            );
             IOPS_COUNTER.fetch_add(1, Ordering::Release);
             BYTES_READ_COUNTER.fetch_add(self.num_bytes(), Ordering::Release);
-            bytes_fut.await.map_err(Error::from)
+            metrics::increment_counter(metrics::IO_REQUESTS, &[], 1);
+            metrics::increment_counter(metrics::IO_BYTES_READ, &[], self.num_bytes());
+            let start = Instant::now();
+            let bytes = bytes_fut.await;
+            // Background reads wait on the bandwidth limit, which says nothing
+            // about the latency of the store
+            if self.priority_class == IoPriorityClass::Interactive {
+                if let (Some(observer), Ok(_)) = (&self.latency_observer, &bytes) {
+                    observer.observe(self.num_bytes(), start.elapsed());
+                }
+            }
+            metrics::record_histogram(
+                metrics::IO_READ_DURATION_SECONDS,
+                &[],
+                start.elapsed().as_secs_f64(),
+            );
+            bytes.map_err(Error::from)
         };
         IOPS_QUOTA.release();
+        if self.priority_class == IoPriorityClass::Background {
+            BACKGROUND_IOPS_QUOTA.release();
+        }
         (self.when_done)(bytes);
     }
 }
@@ -498,7 +581,7 @@ async fn run_io_loop(tasks: Arc<IoQueue>) {
         let next_task = tasks.pop().await;
         match next_task {
             Some(task) => {
-                tokio::spawn(task.run());
+                spawn(task.run());
             }
             None => {
                 // The sender has been dropped, we are done
@@ -513,6 +596,8 @@ struct StatsCollector {
     iops: AtomicU64,
     requests: AtomicU64,
     bytes_read: AtomicU64,
+    prefetch_hits: AtomicU64,
+    pages_decoded: AtomicU64,
 }
 
 impl StatsCollector {
@@ -521,9 +606,15 @@ impl StatsCollector {
             iops: AtomicU64::new(0),
             requests: AtomicU64::new(0),
             bytes_read: AtomicU64::new(0),
+            prefetch_hits: AtomicU64::new(0),
+            pages_decoded: AtomicU64::new(0),
         }
     }
 
+    fn prefetch_hits(&self) -> u64 {
+        self.prefetch_hits.load(Ordering::Relaxed)
+    }
+
     fn iops(&self) -> u64 {
         self.iops.load(Ordering::Relaxed)
     }
@@ -536,6 +627,10 @@ impl StatsCollector {
         self.requests.load(Ordering::Relaxed)
     }
 
+    fn pages_decoded(&self) -> u64 {
+        self.pages_decoded.load(Ordering::Relaxed)
+    }
+
     fn record_request(&self, request: &[Range<u64>]) {
         self.requests.fetch_add(1, Ordering::Relaxed);
         self.iops.fetch_add(request.len() as u64, Ordering::Relaxed);
@@ -550,6 +645,10 @@ pub struct ScanStats {
     pub iops: u64,
     pub requests: u64,
     pub bytes_read: u64,
+    /// Number of requested ranges that were served by an earlier prefetch
+    pub prefetch_hits: u64,
+    /// Number of pages scheduled for decoding by file readers using this scheduler
+    pub pages_decoded: u64,
 }
 
 impl ScanStats {
@@ -558,18 +657,50 @@ impl ScanStats {
             iops: stats.iops(),
             requests: stats.requests(),
             bytes_read: stats.bytes_read(),
+            prefetch_hits: stats.prefetch_hits(),
+            pages_decoded: stats.pages_decoded(),
         }
     }
 }
 
+/// Reads issued ahead of time, by path and range, waiting to be claimed by
+/// a request for exactly the same range.
+#[derive(Default)]
+struct PrefetchedRanges {
+    reads: HashMap<(Path, Range<u64>), oneshot::Receiver<Result<Bytes>>>,
+    /// The size of the reads, which hold on to their data until claimed
+    num_bytes: u64,
+}
+
+impl PrefetchedRanges {
+    fn take(
+        &mut self,
+        path: &Path,
+        range: &Range<u64>,
+    ) -> Option<oneshot::Receiver<Result<Bytes>>> {
+        let read = self.reads.remove(&(path.clone(), range.clone()))?;
+        self.num_bytes -= range.end - range.start;
+        Some(read)
+    }
+}
+
 /// An I/O scheduler which wraps an ObjectStore and throttles the amount of
 /// parallel I/O that can be run.
 ///
-/// TODO: This will also add coalescing
+/// Reads of the same file that are close together are merged into one request,
+/// see [`CoalescingConfig`].
 pub struct ScanScheduler {
     object_store: Arc<ObjectStore>,
     io_queue: Arc<IoQueue>,
     stats: Arc<StatsCollector>,
+    prefetched: Mutex<PrefetchedRanges>,
+    /// The most data that prefetched reads may hold before they are claimed
+    max_prefetched_bytes: u64,
+    uncached_reads: bool,
+    max_gap: u64,
+    max_request_size: u64,
+    adaptive_gap: Option<Arc<AdaptiveGap>>,
+    max_iops_per_file: Option<u32>,
 }
 
 impl Debug for ScanScheduler {
@@ -593,6 +724,21 @@ pub struct SchedulerConfig {
     /// This controls back pressure.  If data is not processed quickly enough then this
     /// buffer will fill up and the I/O loop will pause until the buffer is drained.
     pub io_buffer_size_bytes: u64,
+    /// The class of work the scheduler reads for.  This determines how many
+    /// IOPS it may have in flight.
+    pub priority_class: IoPriorityClass,
+    /// Keep the data that is read out of the OS page cache.  Only affects local
+    /// files, see [`crate::local::LocalObjectReader::open_uncached`].
+    pub uncached_reads: bool,
+    /// How nearby reads are merged into larger requests
+    pub coalescing: CoalescingConfig,
+    /// The most IOPS a single file may have in flight (unlimited if None)
+    ///
+    /// This is on top of the limit for the whole scheduler.  Capping it spreads the
+    /// I/O over more files, which helps on object stores where each request is slow
+    /// but many can run at once.  On local disks a single file can usually use all
+    /// of the I/O capacity.
+    pub max_iops_per_file: Option<u32>,
 }
 
 impl SchedulerConfig {
@@ -600,6 +746,10 @@ impl SchedulerConfig {
     pub fn default_for_testing() -> Self {
         Self {
             io_buffer_size_bytes: 256 * 1024 * 1024,
+            priority_class: IoPriorityClass::default(),
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         }
     }
 
@@ -608,8 +758,32 @@ impl SchedulerConfig {
     pub fn max_bandwidth(store: &ObjectStore) -> Self {
         Self {
             io_buffer_size_bytes: 32 * 1024 * 1024 * store.io_parallelism() as u64,
+            priority_class: IoPriorityClass::default(),
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         }
     }
+
+    pub fn with_priority_class(mut self, priority_class: IoPriorityClass) -> Self {
+        self.priority_class = priority_class;
+        self
+    }
+
+    pub fn with_uncached_reads(mut self, uncached_reads: bool) -> Self {
+        self.uncached_reads = uncached_reads;
+        self
+    }
+
+    pub fn with_coalescing(mut self, coalescing: CoalescingConfig) -> Self {
+        self.coalescing = coalescing;
+        self
+    }
+
+    pub fn with_max_iops_per_file(mut self, max_iops_per_file: Option<u32>) -> Self {
+        self.max_iops_per_file = max_iops_per_file;
+        self
+    }
 }
 
 impl ScanScheduler {
@@ -620,17 +794,46 @@ impl ScanScheduler {
     /// * object_store - the store to wrap
     /// * config - configuration settings for the scheduler
     pub fn new(object_store: Arc<ObjectStore>, config: SchedulerConfig) -> Arc<Self> {
-        let io_capacity = object_store.io_parallelism();
+        // Files opened by a background scheduler are read within the background
+        // bandwidth limits
+        let object_store = match config.priority_class {
+            IoPriorityClass::Background => Arc::new(object_store.for_background()),
+            IoPriorityClass::Interactive => object_store,
+        };
+        let io_capacity = object_store.io_parallelism_for(config.priority_class);
         let io_queue = Arc::new(IoQueue::new(
             io_capacity as u32,
             config.io_buffer_size_bytes,
+            config.priority_class,
         ));
+        let max_gap = config
+            .coalescing
+            .max_gap
+            .unwrap_or(object_store.block_size() as u64);
+        let max_request_size = config
+            .coalescing
+            .max_request_size
+            .unwrap_or(object_store.max_iop_size())
+            .max(1);
+        // Reading through a gap only pays off if it leaves room in the request
+        // for the data that was asked for
+        let adaptive_gap = config
+            .coalescing
+            .adaptive
+            .then(|| Arc::new(AdaptiveGap::new(max_gap, max_request_size / 2)));
         let scheduler = Self {
             object_store,
             io_queue: io_queue.clone(),
             stats: Arc::new(StatsCollector::new()),
+            prefetched: Mutex::new(PrefetchedRanges::default()),
+            max_prefetched_bytes: config.io_buffer_size_bytes,
+            uncached_reads: config.uncached_reads,
+            max_gap,
+            max_request_size,
+            adaptive_gap,
+            max_iops_per_file: config.max_iops_per_file.map(|max| max.max(1)),
         };
-        tokio::task::spawn(async move { run_io_loop(io_queue).await });
+        spawn(run_io_loop(io_queue));
         Arc::new(scheduler)
     }
 
@@ -657,18 +860,24 @@ impl ScanScheduler {
             }
             size
         };
-        let reader = self
-            .object_store
-            .open_with_size(path, file_size_bytes as usize)
-            .await?;
-        let block_size = self.object_store.block_size() as u64;
-        let max_iop_size = self.object_store.max_iop_size();
+        let reader = if self.uncached_reads {
+            self.object_store
+                .open_uncached(path, file_size_bytes as usize)
+                .await?
+        } else {
+            self.object_store
+                .open_with_size(path, file_size_bytes as usize)
+                .await?
+        };
         Ok(FileScheduler {
             reader: reader.into(),
-            block_size,
+            max_gap: self.max_gap,
             root: self.clone(),
             base_priority,
-            max_iop_size,
+            max_iop_size: self.max_request_size,
+            iops_avail: self
+                .max_iops_per_file
+                .map(|max| Arc::new(Semaphore::new(max as usize))),
         })
     }
 
@@ -712,6 +921,10 @@ impl ScanScheduler {
                 reader: reader.clone(),
                 to_read: iop,
                 priority,
+                priority_class: self.io_queue.priority_class,
+                latency_observer: self.adaptive_gap.clone(),
+                #[cfg(feature = "query-tracing")]
+                span: tracing::Span::current(),
                 when_done: Box::new(move |data| {
                     io_queue.on_iop_complete();
                     let mut dest = dest.lock().unwrap();
@@ -748,6 +961,24 @@ impl ScanScheduler {
         })
     }
 
+    /// Take the prefetched reads matching `request`, if any.
+    ///
+    /// The result has one entry per requested range.
+    fn take_prefetched(
+        &self,
+        path: &Path,
+        request: &[Range<u64>],
+    ) -> Vec<Option<oneshot::Receiver<Result<Bytes>>>> {
+        let mut prefetched = self.prefetched.lock().unwrap();
+        if prefetched.reads.is_empty() {
+            return request.iter().map(|_| None).collect();
+        }
+        request
+            .iter()
+            .map(|range| prefetched.take(path, range))
+            .collect()
+    }
+
     pub fn stats(&self) -> ScanStats {
         ScanStats::new(self.stats.as_ref())
     }
@@ -764,14 +995,16 @@ impl Drop for ScanScheduler {
 pub struct FileScheduler {
     reader: Arc<dyn Reader>,
     root: Arc<ScanScheduler>,
-    block_size: u64,
+    max_gap: u64,
     base_priority: u64,
     max_iop_size: u64,
+    /// Limits the IOPS in flight for this file, see [`SchedulerConfig::max_iops_per_file`]
+    iops_avail: Option<Arc<Semaphore>>,
 }
 
-fn is_close_together(range1: &Range<u64>, range2: &Range<u64>, block_size: u64) -> bool {
+fn is_close_together(range1: &Range<u64>, range2: &Range<u64>, max_gap: u64) -> bool {
     // Note that range1.end <= range2.start is possible (e.g. when decoding string arrays)
-    range2.start <= (range1.end + block_size)
+    range2.start <= (range1.end + max_gap)
 }
 
 fn is_overlapping(range1: &Range<u64>, range2: &Range<u64>) -> bool {
@@ -791,21 +1024,108 @@ impl FileScheduler {
     /// Each request has a backpressure ID which controls which backpressure throttle
     /// is applied to the request.  Requests made to the same backpressure throttle
     /// will be throttled together.
+    ///
+    /// Ranges that were registered with [`Self::prefetch`] are served from the
+    /// prefetched read, as long as the requested range is exactly the same.
     pub fn submit_request(
         &self,
         request: Vec<Range<u64>>,
         priority: u64,
+    ) -> impl Future<Output = Result<Vec<Bytes>>> + Send {
+        let prefetched = self.root.take_prefetched(self.reader.path(), &request);
+        if prefetched.iter().all(Option::is_none) {
+            return self.submit_to_queue(request, priority).left_future();
+        }
+
+        let num_hits = prefetched.iter().filter(|p| p.is_some()).count();
+        self.root
+            .stats
+            .prefetch_hits
+            .fetch_add(num_hits as u64, Ordering::Relaxed);
+        let remaining = request
+            .iter()
+            .zip(&prefetched)
+            .filter(|(_, prefetched)| prefetched.is_none())
+            .map(|(range, _)| range.clone())
+            .collect::<Vec<_>>();
+        let remaining_fut = if remaining.is_empty() {
+            None
+        } else {
+            Some(self.submit_to_queue(remaining, priority))
+        };
+
+        async move {
+            let mut remaining = match remaining_fut {
+                Some(fut) => fut.await?.into_iter(),
+                None => Vec::new().into_iter(),
+            };
+            let mut bytes = Vec::with_capacity(prefetched.len());
+            for prefetched in prefetched {
+                match prefetched {
+                    Some(rx) => bytes.push(rx.await.map_err(|_| Error::Internal {
+                        message: "prefetched read was cancelled".to_string(),
+                        location: location!(),
+                    })??),
+                    None => bytes.push(remaining.next().unwrap()),
+                }
+            }
+            Ok(bytes)
+        }
+        .right_future()
+    }
+
+    /// Start reading ranges that will be requested soon.
+    ///
+    /// The reads are queued right away with the given priority and kept until a
+    /// call to [`Self::submit_request`] asks for exactly the same range.  This
+    /// lets a scan issue the I/O for data it is about to need before the decoder
+    /// gets there.  A range that is already being prefetched is not read again.
+    ///
+    /// Prefetched data is held until it is claimed.  Once unclaimed reads add
+    /// up to the I/O buffer size of the scheduler, further ranges are skipped
+    /// and will be read when they are requested.
+    pub fn prefetch(&self, ranges: Vec<Range<u64>>, priority: u64) {
+        let path = self.reader.path().clone();
+        let mut prefetched = self.root.prefetched.lock().unwrap();
+        for range in ranges {
+            let key = (path.clone(), range.clone());
+            let num_bytes = range.end - range.start;
+            if prefetched.reads.contains_key(&key)
+                || prefetched.num_bytes + num_bytes > self.root.max_prefetched_bytes
+            {
+                continue;
+            }
+            let (tx, rx) = oneshot::channel();
+            let fut = self.submit_to_queue(vec![range], priority);
+            // Drive the read to completion so the bytes are released from the
+            // I/O buffer even if the range is never claimed.
+            spawn(async move {
+                let _ = tx.send(fut.await.map(|mut bytes| bytes.pop().unwrap()));
+            });
+            prefetched.reads.insert(key, rx);
+            prefetched.num_bytes += num_bytes;
+        }
+    }
+
+    fn submit_to_queue(
+        &self,
+        request: Vec<Range<u64>>,
+        priority: u64,
     ) -> impl Future<Output = Result<Vec<Bytes>>> + Send {
         // The final priority is a combination of the row offset and the file number
         let priority = ((self.base_priority as u128) << 64) + priority as u128;
 
+        let max_gap = match &self.root.adaptive_gap {
+            Some(adaptive_gap) => adaptive_gap.current(),
+            None => self.max_gap,
+        };
         let mut merged_requests = Vec::with_capacity(request.len());
 
         if !request.is_empty() {
             let mut curr_interval = request[0].clone();
 
             for req in request.iter().skip(1) {
-                if is_close_together(&curr_interval, req, self.block_size) {
+                if is_close_together(&curr_interval, req, max_gap) {
                     curr_interval.end = curr_interval.end.max(req.end);
                 } else {
                     merged_requests.push(curr_interval);
@@ -838,9 +1158,28 @@ impl FileScheduler {
 
         self.root.stats.record_request(&updated_requests);
 
-        let bytes_vec_fut =
-            self.root
-                .submit_request(self.reader.clone(), updated_requests.clone(), priority);
+        let bytes_vec_fut = match &self.iops_avail {
+            None => self
+                .root
+                .submit_request(self.reader.clone(), updated_requests.clone(), priority)
+                .left_future(),
+            Some(iops_avail) => {
+                // A request with more IOPS than the limit takes all of the permits
+                let num_permits = (updated_requests.len() as u32)
+                    .clamp(1, self.root.max_iops_per_file.unwrap_or(1));
+                let permits = iops_avail.clone().acquire_many_owned(num_permits);
+                let root = self.root.clone();
+                let reader = self.reader.clone();
+                let updated_requests = updated_requests.clone();
+                async move {
+                    // The semaphore is never closed
+                    let _permits = permits.await.unwrap();
+                    root.submit_request(reader, updated_requests, priority)
+                        .await
+                }
+                .right_future()
+            }
+        };
 
         let mut updated_index = 0;
         let mut final_bytes = Vec::with_capacity(request.len());
@@ -895,9 +1234,10 @@ impl FileScheduler {
         Self {
             reader: self.reader.clone(),
             root: self.root.clone(),
-            block_size: self.block_size,
+            max_gap: self.max_gap,
             max_iop_size: self.max_iop_size,
             base_priority: priority,
+            iops_avail: self.iops_avail.clone(),
         }
     }
 
@@ -916,6 +1256,17 @@ impl FileScheduler {
             .map_ok(|vec_bytes| vec_bytes.into_iter().next().unwrap())
     }
 
+    /// Records that `num_pages` pages of this file are being decoded
+    ///
+    /// The I/O scheduler does not know about pages so this is reported by the file reader
+    /// and only feeds [`ScanStats::pages_decoded`].
+    pub fn record_pages_decoded(&self, num_pages: u64) {
+        self.root
+            .stats
+            .pages_decoded
+            .fetch_add(num_pages, Ordering::Relaxed);
+    }
+
     /// Provides access to the underlying reader
     ///
     /// Do not use this for reading data as it will bypass any I/O scheduling!
@@ -1082,6 +1433,53 @@ mod tests {
         assert_eq!(11, scheduler.stats().iops);
     }
 
+    #[tokio::test]
+    async fn test_coalescing_config() {
+        let obj_store = Arc::new(ObjectStore::memory());
+        let path = Path::parse("foo").unwrap();
+        let some_data = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+        obj_store.put(&path, &some_data).await.unwrap();
+        let reads = vec![0..1_000, 1_100..2_000, 10_000..11_000, 30_000..31_000];
+
+        let read_with = |coalescing: CoalescingConfig| {
+            let obj_store = obj_store.clone();
+            let path = path.clone();
+            let reads = reads.clone();
+            let some_data = some_data.clone();
+            async move {
+                let config = SchedulerConfig::default_for_testing().with_coalescing(coalescing);
+                let scheduler = ScanScheduler::new(obj_store, config);
+                let file_scheduler = scheduler
+                    .open_file(&path, &CachedFileSize::unknown())
+                    .await
+                    .unwrap();
+                let bytes = file_scheduler
+                    .submit_request(reads.clone(), 0)
+                    .await
+                    .unwrap();
+                for (range, bytes) in reads.iter().zip(bytes) {
+                    assert_eq!(bytes, &some_data[range.start as usize..range.end as usize]);
+                }
+                scheduler.stats().iops
+            }
+        };
+
+        let defaults = CoalescingConfig {
+            max_gap: None,
+            max_request_size: None,
+            adaptive: false,
+        };
+        // By default only reads within the 4KiB block size of the store are merged
+        assert_eq!(read_with(defaults).await, 3);
+        assert_eq!(read_with(defaults.with_max_gap(0)).await, 4);
+        assert_eq!(read_with(defaults.with_max_gap(10_000)).await, 2);
+        // Merged requests are split again if they get too big
+        assert_eq!(
+            read_with(defaults.with_max_gap(100_000).with_max_request_size(8_000)).await,
+            4
+        );
+    }
+
     #[tokio::test]
     async fn test_priority() {
         let some_path = Path::parse("foo").unwrap();
@@ -1119,6 +1517,10 @@ mod tests {
 
         let config = SchedulerConfig {
             io_buffer_size_bytes: 1024 * 1024,
+            priority_class: IoPriorityClass::Interactive,
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         };
 
         let scan_scheduler = ScanScheduler::new(obj_store, config);
@@ -1208,6 +1610,10 @@ mod tests {
 
         let config = SchedulerConfig {
             io_buffer_size_bytes: 10,
+            priority_class: IoPriorityClass::Interactive,
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         };
 
         let scan_scheduler = ScanScheduler::new(obj_store.clone(), config);
@@ -1282,6 +1688,10 @@ mod tests {
         // Ensure deadlock prevention timeout can be disabled
         let config = SchedulerConfig {
             io_buffer_size_bytes: 10,
+            priority_class: IoPriorityClass::Interactive,
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         };
 
         let scan_scheduler = ScanScheduler::new(obj_store, config);
@@ -1313,6 +1723,10 @@ mod tests {
         // Only one request will be allowed in
         let config = SchedulerConfig {
             io_buffer_size_bytes: 1,
+            priority_class: IoPriorityClass::Interactive,
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         };
         let scan_scheduler = ScanScheduler::new(obj_store.clone(), config);
         let file_scheduler = scan_scheduler
@@ -1329,4 +1743,172 @@ mod tests {
             fut.await.unwrap();
         }
     }
+
+    #[tokio::test]
+    async fn test_max_iops_per_file() {
+        let some_path = Path::parse("foo").unwrap();
+        let base_store = Arc::new(InMemory::new());
+        base_store
+            .put(&some_path, vec![7; 10_000].into())
+            .await
+            .unwrap();
+
+        let in_flight = Arc::new(AtomicU64::new(0));
+        let max_in_flight = Arc::new(AtomicU64::new(0));
+        let mut obj_store = MockObjectStore::default();
+        let (in_flight_copy, max_in_flight_copy) = (in_flight.clone(), max_in_flight.clone());
+        obj_store
+            .expect_get_opts()
+            .returning(move |location, options| {
+                let base_store = base_store.clone();
+                let location = location.clone();
+                let in_flight = in_flight_copy.clone();
+                let max_in_flight = max_in_flight_copy.clone();
+                async move {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    base_store.get_opts(&location, options).await
+                }
+                .boxed()
+            });
+        let obj_store = Arc::new(ObjectStore::new(
+            Arc::new(obj_store),
+            Url::parse("mem://").unwrap(),
+            Some(500),
+            None,
+            false,
+            false,
+            16,
+            DEFAULT_DOWNLOAD_RETRY_COUNT,
+        ));
+
+        let config = SchedulerConfig::default_for_testing().with_max_iops_per_file(Some(2));
+        let scan_scheduler = ScanScheduler::new(obj_store, config);
+        let file_scheduler = scan_scheduler
+            .open_file(&some_path, &CachedFileSize::new(10_000))
+            .await
+            .unwrap();
+
+        let futs = (0..20).map(|idx| file_scheduler.submit_single(idx * 500..idx * 500 + 10, idx));
+        for bytes in futures::future::join_all(futs).await {
+            assert_eq!(bytes.unwrap(), vec![7; 10]);
+        }
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+
+        // A request with more IOPS than the limit still goes through
+        let ranges = (0..5)
+            .map(|idx| idx * 2000..idx * 2000 + 10)
+            .collect::<Vec<_>>();
+        let bytes = file_scheduler.submit_request(ranges, 0).await.unwrap();
+        assert_eq!(bytes.len(), 5);
+        assert_eq!(scan_scheduler.stats().iops, 25);
+    }
+
+    #[tokio::test]
+    async fn test_background_priority() {
+        let some_path = Path::parse("foo").unwrap();
+        let obj_store = Arc::new(ObjectStore::memory());
+        obj_store
+            .put(&some_path, vec![7; 1000].as_slice())
+            .await
+            .unwrap();
+
+        let interactive =
+            ScanScheduler::new(obj_store.clone(), SchedulerConfig::default_for_testing());
+        let background = ScanScheduler::new(
+            obj_store.clone(),
+            SchedulerConfig::default_for_testing().with_priority_class(IoPriorityClass::Background),
+        );
+        let interactive_iops = interactive.io_queue.state.lock().unwrap().iops_avail;
+        let background_iops = background.io_queue.state.lock().unwrap().iops_avail;
+        assert_eq!(interactive_iops as usize, obj_store.io_parallelism());
+        assert_eq!(
+            background_iops as usize,
+            obj_store.io_parallelism_for(IoPriorityClass::Background)
+        );
+        assert!(background_iops < interactive_iops);
+
+        let file_scheduler = background
+            .open_file(&some_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+        let futs = (0..100)
+            .map(|idx| file_scheduler.submit_single(idx * 10..idx * 10 + 10, idx))
+            .collect::<Vec<_>>();
+        for fut in futs {
+            assert_eq!(fut.await.unwrap(), vec![7; 10]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let some_path = Path::parse("foo").unwrap();
+        let obj_store = Arc::new(ObjectStore::memory());
+        let data = (0..100).collect::<Vec<u8>>();
+        obj_store.put(&some_path, data.as_slice()).await.unwrap();
+
+        let scan_scheduler =
+            ScanScheduler::new(obj_store.clone(), SchedulerConfig::default_for_testing());
+        let file_scheduler = scan_scheduler
+            .open_file(&some_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+
+        file_scheduler.prefetch(vec![10..20, 50..60], 0);
+        // Registering the same range twice does not read it twice
+        file_scheduler.prefetch(vec![10..20], 0);
+        assert_eq!(scan_scheduler.stats().iops, 2);
+
+        // Prefetched and regular ranges can be mixed in one request
+        let bytes = file_scheduler
+            .submit_request(vec![0..5, 10..20, 80..90], 0)
+            .await
+            .unwrap();
+        assert_eq!(bytes[0].as_ref(), &data[0..5]);
+        assert_eq!(bytes[1].as_ref(), &data[10..20]);
+        assert_eq!(bytes[2].as_ref(), &data[80..90]);
+        assert_eq!(scan_scheduler.stats().prefetch_hits, 1);
+
+        let bytes = file_scheduler.submit_single(50..60, 0).await.unwrap();
+        assert_eq!(bytes.as_ref(), &data[50..60]);
+        assert_eq!(scan_scheduler.stats().prefetch_hits, 2);
+
+        // A prefetched range is only served once
+        file_scheduler.submit_single(50..60, 0).await.unwrap();
+        assert_eq!(scan_scheduler.stats().prefetch_hits, 2);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_limit() {
+        let some_path = Path::parse("foo").unwrap();
+        let obj_store = Arc::new(ObjectStore::memory());
+        let data = (0..100).collect::<Vec<u8>>();
+        obj_store.put(&some_path, data.as_slice()).await.unwrap();
+
+        // Unclaimed prefetches may hold at most the I/O buffer size
+        let config = SchedulerConfig {
+            io_buffer_size_bytes: 25,
+            ..SchedulerConfig::default_for_testing()
+        };
+        let scan_scheduler = ScanScheduler::new(obj_store.clone(), config);
+        let file_scheduler = scan_scheduler
+            .open_file(&some_path, &CachedFileSize::unknown())
+            .await
+            .unwrap();
+
+        file_scheduler.prefetch(vec![0..10, 10..20, 20..30], 0);
+        assert_eq!(scan_scheduler.stats().iops, 2);
+
+        // Claiming a range makes room for more
+        let bytes = file_scheduler.submit_single(0..10, 0).await.unwrap();
+        assert_eq!(bytes.as_ref(), &data[0..10]);
+        file_scheduler.prefetch(vec![20..30], 0);
+        assert_eq!(scan_scheduler.stats().iops, 3);
+        let bytes = file_scheduler.submit_single(20..30, 0).await.unwrap();
+        assert_eq!(bytes.as_ref(), &data[20..30]);
+        assert_eq!(scan_scheduler.stats().prefetch_hits, 2);
+        assert_eq!(scan_scheduler.stats().iops, 3);
+    }
 }
@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Policy for merging nearby reads into a single request
+//!
+//! Reading through a gap between two ranges costs the time to transfer the
+//! gap, while making a separate request costs a round trip.  On a store with
+//! a high latency per request it pays to read through much larger gaps than
+//! on a local disk.  The gap can be fixed or, with [`CoalescingConfig::adaptive`],
+//! derived from the latency and bandwidth the scheduler observes.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lance_core::utils::parse::str_is_truthy;
+
+const ENV_MAX_GAP: &str = "LANCE_COALESCE_MAX_GAP";
+const ENV_MAX_REQUEST_SIZE: &str = "LANCE_COALESCE_MAX_REQUEST_SIZE";
+const ENV_ADAPTIVE: &str = "LANCE_ADAPTIVE_COALESCING";
+
+// Weight given to older samples each time a new one comes in
+const DECAY: f64 = 0.98;
+// Roughly the number of samples needed before the gap is adapted
+const MIN_WEIGHT: f64 = 8.0;
+// Used when all reads are about the same size, so that the bandwidth can't be
+// told apart from the latency
+const DEFAULT_BANDWIDTH: f64 = 64.0 * 1024.0 * 1024.0;
+
+fn from_env<T: std::str::FromStr>(var: &str) -> Option<T> {
+    let value = std::env::var(var).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            log::warn!("Ignoring invalid value for {}: {}", var, value);
+            None
+        }
+    }
+}
+
+/// How a scheduler merges nearby reads of the same file
+///
+/// The defaults can be set with the `LANCE_COALESCE_MAX_GAP`,
+/// `LANCE_COALESCE_MAX_REQUEST_SIZE` and `LANCE_ADAPTIVE_COALESCING`
+/// environment variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalescingConfig {
+    /// Reads at most this many bytes apart are merged into one request.
+    /// Defaults to the block size of the store.
+    pub max_gap: Option<u64>,
+    /// Requests larger than this are split, whether merged or not.  Defaults
+    /// to the maximum IOP size of the store.
+    pub max_request_size: Option<u64>,
+    /// Grow the gap beyond `max_gap` when requests are slow compared to the
+    /// bandwidth of the store, based on the reads made so far.
+    pub adaptive: bool,
+}
+
+impl Default for CoalescingConfig {
+    fn default() -> Self {
+        Self {
+            max_gap: from_env(ENV_MAX_GAP),
+            max_request_size: from_env(ENV_MAX_REQUEST_SIZE),
+            adaptive: std::env::var(ENV_ADAPTIVE).is_ok_and(|value| str_is_truthy(&value)),
+        }
+    }
+}
+
+impl CoalescingConfig {
+    pub fn with_max_gap(mut self, max_gap: u64) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    pub fn with_max_request_size(mut self, max_request_size: u64) -> Self {
+        self.max_request_size = Some(max_request_size);
+        self
+    }
+
+    pub fn with_adaptive(mut self, adaptive: bool) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+}
+
+/// Fits `duration = latency + bytes / bandwidth` to recent reads, weighting
+/// newer reads more
+#[derive(Debug, Default)]
+struct ReadCostModel {
+    weight: f64,
+    sum_bytes: f64,
+    sum_secs: f64,
+    sum_bytes_sq: f64,
+    sum_bytes_secs: f64,
+}
+
+impl ReadCostModel {
+    fn observe(&mut self, num_bytes: f64, secs: f64) {
+        self.weight = self.weight * DECAY + 1.0;
+        self.sum_bytes = self.sum_bytes * DECAY + num_bytes;
+        self.sum_secs = self.sum_secs * DECAY + secs;
+        self.sum_bytes_sq = self.sum_bytes_sq * DECAY + num_bytes * num_bytes;
+        self.sum_bytes_secs = self.sum_bytes_secs * DECAY + num_bytes * secs;
+    }
+
+    /// The number of bytes that can be transferred in the time of one round
+    /// trip, which is the largest gap worth reading through
+    fn break_even_gap(&self) -> Option<f64> {
+        if self.weight < MIN_WEIGHT {
+            return None;
+        }
+        let mean_bytes = self.sum_bytes / self.weight;
+        let mean_secs = self.sum_secs / self.weight;
+        let var_bytes = self.sum_bytes_sq / self.weight - mean_bytes * mean_bytes;
+        let cov = self.sum_bytes_secs / self.weight - mean_bytes * mean_secs;
+        // Only trust the fitted bandwidth if the read sizes vary enough
+        let secs_per_byte = if var_bytes > (mean_bytes * 0.1).powi(2) && cov > 0.0 {
+            cov / var_bytes
+        } else {
+            1.0 / DEFAULT_BANDWIDTH
+        };
+        let latency = mean_secs - secs_per_byte * mean_bytes;
+        (latency > 0.0).then_some(latency / secs_per_byte)
+    }
+}
+
+/// The gap used by a scheduler with adaptive coalescing
+#[derive(Debug)]
+pub(super) struct AdaptiveGap {
+    min_gap: u64,
+    max_gap: u64,
+    model: Mutex<ReadCostModel>,
+    current: AtomicU64,
+}
+
+impl AdaptiveGap {
+    pub(super) fn new(min_gap: u64, max_gap: u64) -> Self {
+        Self {
+            min_gap,
+            max_gap: max_gap.max(min_gap),
+            model: Mutex::default(),
+            current: AtomicU64::new(min_gap),
+        }
+    }
+
+    pub(super) fn current(&self) -> u64 {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn observe(&self, num_bytes: u64, elapsed: Duration) {
+        let mut model = self.model.lock().unwrap();
+        model.observe(num_bytes as f64, elapsed.as_secs_f64());
+        if let Some(gap) = model.break_even_gap() {
+            self.current.store(
+                (gap as u64).clamp(self.min_gap, self.max_gap),
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_gap() {
+        const MIB: u64 = 1024 * 1024;
+        let gap = AdaptiveGap::new(4096, 64 * MIB);
+        // 50ms per request and 100MiB/s
+        let duration =
+            |num_bytes: u64| Duration::from_secs_f64(0.05 + num_bytes as f64 / (100 * MIB) as f64);
+        for i in 0..4 {
+            let num_bytes = (i + 1) * 64 * 1024;
+            gap.observe(num_bytes, duration(num_bytes));
+        }
+        // Not enough samples yet
+        assert_eq!(gap.current(), 4096);
+        for i in 0..20 {
+            let num_bytes = (i % 5 + 1) * 256 * 1024;
+            gap.observe(num_bytes, duration(num_bytes));
+        }
+        let expected = 5 * MIB;
+        assert!(
+            gap.current().abs_diff(expected) < expected / 100,
+            "{}",
+            gap.current()
+        );
+
+        // A fast store with reads of the same size keeps the minimum gap
+        let gap = AdaptiveGap::new(4096, 64 * MIB);
+        for _ in 0..20 {
+            gap.observe(4096, Duration::from_micros(50));
+        }
+        assert_eq!(gap.current(), 4096);
+
+        // The gap never exceeds the maximum
+        let gap = AdaptiveGap::new(4096, MIB);
+        for _ in 0..20 {
+            gap.observe(4096, Duration::from_millis(200));
+        }
+        assert_eq!(gap.current(), MIB);
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Bandwidth limits for background operations
+//!
+//! Maintenance work such as compaction, cleanup and index builds can move a lot
+//! of data.  On a shared network link that can starve queries, and on cloud
+//! storage it can blow past an egress budget.  Rate limits for background
+//! downloads and uploads are configured process-wide, either with the
+//! `LANCE_BACKGROUND_DOWNLOAD_BYTES_PER_SEC` and
+//! `LANCE_BACKGROUND_UPLOAD_BYTES_PER_SEC` environment variables or with
+//! [`set_background_bandwidth_limits`].  There are no limits by default.
+//!
+//! I/O counts as background if it goes through a store returned by
+//! [`ObjectStore::for_background`], which background work passes down to
+//! everything it reads and writes, or a scheduler with the
+//! [`IoPriorityClass::Background`] class.  The limits apply to object stores
+//! only, local files are not throttled.
+//!
+//! [`ObjectStore::for_background`]: crate::object_store::ObjectStore::for_background
+//! [`IoPriorityClass::Background`]: crate::scheduler::IoPriorityClass::Background
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+const ENV_DOWNLOAD_LIMIT: &str = "LANCE_BACKGROUND_DOWNLOAD_BYTES_PER_SEC";
+const ENV_UPLOAD_LIMIT: &str = "LANCE_BACKGROUND_UPLOAD_BYTES_PER_SEC";
+
+/// A token bucket that limits the rate at which bytes are transferred
+///
+/// Up to one second worth of bytes can be used in a burst.  A transfer larger
+/// than that is let through once the bucket is full and then has to be paid
+/// back before the next transfer starts.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: u64,
+    // Bytes available and when that was last updated.  Negative while a large
+    // transfer is being paid back.
+    state: Mutex<(f64, Instant)>,
+    bytes_transferred: AtomicU64,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        Self {
+            bytes_per_sec,
+            state: Mutex::new((bytes_per_sec as f64, Instant::now())),
+            bytes_transferred: AtomicU64::new(0),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// The total number of bytes that went through the limiter
+    pub fn bytes_transferred(&self) -> u64 {
+        self.bytes_transferred.load(Ordering::Relaxed)
+    }
+
+    /// Take `num_bytes` from the bucket, returning how long to wait before the
+    /// transfer may start
+    fn reserve(&self, num_bytes: u64) -> Duration {
+        self.bytes_transferred
+            .fetch_add(num_bytes, Ordering::Relaxed);
+        let capacity = self.bytes_per_sec as f64;
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(state.1).as_secs_f64() * capacity;
+        let available = (state.0 + refill).min(capacity);
+        state.1 = now;
+        // Wait until the bucket is full or has enough for this transfer,
+        // whichever needs less
+        let needed = (num_bytes as f64).min(capacity);
+        let wait = if available >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - available) / capacity)
+        };
+        state.0 = available - num_bytes as f64;
+        wait
+    }
+
+    /// Wait until `num_bytes` may be transferred
+    pub async fn acquire(&self, num_bytes: u64) {
+        let wait = self.reserve(num_bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// The limits on the downloads and uploads of a store
+///
+/// The limiters are shared, so all the stores holding the same limits share
+/// their bandwidth.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthLimits {
+    pub download: Option<Arc<BandwidthLimiter>>,
+    pub upload: Option<Arc<BandwidthLimiter>>,
+}
+
+fn limit_from_env(var: &str) -> Option<Arc<BandwidthLimiter>> {
+    let value = std::env::var(var).ok()?;
+    match value.parse::<u64>() {
+        Ok(0) => None,
+        Ok(bytes_per_sec) => Some(Arc::new(BandwidthLimiter::new(bytes_per_sec))),
+        Err(err) => {
+            log::warn!("Ignoring invalid value for {}: {}", var, err);
+            None
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref BACKGROUND_LIMITS: RwLock<BandwidthLimits> = RwLock::new(BandwidthLimits {
+        download: limit_from_env(ENV_DOWNLOAD_LIMIT),
+        upload: limit_from_env(ENV_UPLOAD_LIMIT),
+    });
+}
+
+/// Set the process-wide bandwidth limits, in bytes per second, for background
+/// downloads and uploads
+///
+/// `None` removes a limit.  This replaces any limits set through the
+/// environment.  Background work that has already started keeps the limits
+/// it started with.
+pub fn set_background_bandwidth_limits(download: Option<u64>, upload: Option<u64>) {
+    let mut limits = BACKGROUND_LIMITS.write().unwrap();
+    limits.download = download.map(|limit| Arc::new(BandwidthLimiter::new(limit)));
+    limits.upload = upload.map(|limit| Arc::new(BandwidthLimiter::new(limit)));
+}
+
+/// The process-wide limits for background downloads and uploads
+pub fn background_bandwidth_limits() -> BandwidthLimits {
+    BACKGROUND_LIMITS.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandwidth_limiter() {
+        let limiter = BandwidthLimiter::new(1000);
+        // A full bucket allows a burst of up to one second
+        assert_eq!(limiter.reserve(600), Duration::ZERO);
+        let wait = limiter.reserve(600);
+        assert!(wait > Duration::from_millis(150), "{:?}", wait);
+        assert!(wait <= Duration::from_millis(200), "{:?}", wait);
+
+        // A transfer larger than the bucket goes once the bucket refills and
+        // is then paid back
+        let limiter = BandwidthLimiter::new(1000);
+        assert_eq!(limiter.reserve(5000), Duration::ZERO);
+        let wait = limiter.reserve(1);
+        assert!(wait > Duration::from_millis(3900), "{:?}", wait);
+        assert_eq!(limiter.bytes_transferred(), 5001);
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+//! These integration tests can only be run against real S3 buckets, they
+//! need AWS credentials in the environment.
+//!
+//! * `REQUESTER_PAYS_BUCKET`: a requester-pays bucket the credentials can read
+//!   and write.
+//! * `S3_EXPRESS_BUCKET`: an S3 Express One Zone directory bucket, named like
+//!   `name--use1-az4--x-s3`.
+#![cfg(feature = "s3-test")]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use lance_io::object_store::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use object_store::path::Path;
+use object_store::ObjectStore as _;
+use tokio::io::AsyncWriteExt;
+
+async fn get_store(bucket_var: &str, options: HashMap<String, String>) -> (Arc<ObjectStore>, Path) {
+    let bucket = std::env::var(bucket_var)
+        .unwrap_or_else(|_| panic!("{} must be set to run this test", bucket_var));
+    let params = ObjectStoreParams {
+        storage_options: Some(options),
+        ..Default::default()
+    };
+    ObjectStore::from_uri_and_params(
+        Arc::new(ObjectStoreRegistry::default()),
+        &format!("s3://{}/lance-io-test", bucket),
+        &params,
+    )
+    .await
+    .unwrap()
+}
+
+async fn round_trip(store: &ObjectStore, base: &Path) {
+    let path = base.child("round_trip");
+    let mut writer = store.create(&path).await.unwrap();
+    writer.write_all(b"hello world").await.unwrap();
+    writer.shutdown().await.unwrap();
+
+    let reader = store.open(&path).await.unwrap();
+    assert_eq!(reader.get_range(6..11).await.unwrap().as_ref(), b"world");
+    let listed = store
+        .inner
+        .list(Some(base))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(listed.iter().any(|meta| meta.location == path));
+
+    store.delete(&path).await.unwrap();
+    assert!(!store.exists(&path).await.unwrap());
+}
+
+#[ignore = "Must be run manually against a requester-pays bucket"]
+#[tokio::test]
+async fn test_requester_pays() {
+    let (store, base) = get_store(
+        "REQUESTER_PAYS_BUCKET",
+        HashMap::from([("requester_pays".to_string(), "true".to_string())]),
+    )
+    .await;
+    round_trip(&store, &base).await;
+
+    // Without the header, the bucket owner refuses the requests
+    let (store, base) = get_store("REQUESTER_PAYS_BUCKET", HashMap::new()).await;
+    let err = store.inner.head(&base.child("missing")).await.unwrap_err();
+    assert!(
+        !matches!(err, object_store::Error::NotFound { .. }),
+        "{}",
+        err
+    );
+}
+
+#[ignore = "Must be run manually against an S3 Express directory bucket"]
+#[tokio::test]
+async fn test_s3_express() {
+    // The region and zonal endpoint are derived from the bucket name, and
+    // requests are signed with session credentials.
+    let (store, base) = get_store("S3_EXPRESS_BUCKET", HashMap::new()).await;
+    assert!(!store.list_is_lexically_ordered);
+    round_trip(&store, &base).await;
+}
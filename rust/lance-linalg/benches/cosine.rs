@@ -7,7 +7,8 @@ use arrow_array::{
 };
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use lance_arrow::{bfloat16::BFloat16Type, ArrowFloatType, FloatArray};
-use lance_linalg::distance::cosine::{cosine_distance_batch, Cosine};
+use lance_linalg::distance::cosine::{cosine_distance_batch, cosine_fast_f32_impl, Cosine};
+use lance_linalg::distance::norm_l2;
 use num_traits::Float;
 
 #[cfg(target_os = "linux")]
@@ -68,6 +69,24 @@ fn bench_distance(c: &mut Criterion) {
     run_bench::<Float32Type>(c);
     run_bench::<Float64Type>(c);
 
+    // The Rust kernel, which the SVE kernel replaces on aarch64 with `fp16kernels`
+    const DIMENSION: usize = 1024;
+    const TOTAL: usize = 1024 * 1024; // 1M vectors
+    let key = generate_random_array_with_seed::<Float32Type>(DIMENSION, [0; 32]);
+    let target = generate_random_array_with_seed::<Float32Type>(TOTAL * DIMENSION, [42; 32]);
+    c.bench_function("Cosine(f32, rust)", |b| {
+        let x_norm = norm_l2(key.values());
+        b.iter(|| {
+            black_box(
+                target
+                    .values()
+                    .chunks_exact(DIMENSION)
+                    .map(|y| cosine_fast_f32_impl(key.values(), x_norm, y))
+                    .collect::<Vec<_>>(),
+            )
+        })
+    });
+
     let key: Float32Array = generate_random_array_with_seed::<Float32Type>(8, [0; 32]);
     let target = generate_random_array_with_seed::<Float32Type>(1024 * 1024 * 8, [42; 32]);
 
@@ -16,7 +16,7 @@ use num_traits::Float;
 #[cfg(target_os = "linux")]
 use pprof::criterion::{Output, PProfProfiler};
 
-use lance_linalg::distance::dot::{dot, dot_distance, Dot};
+use lance_linalg::distance::dot::{dot, dot_distance, dot_f32_impl, Dot};
 use lance_testing::datagen::generate_random_array_with_seed;
 use rand::Rng;
 
@@ -121,6 +121,22 @@ fn bench_distance(c: &mut Criterion) {
         });
     });
 
+    // The Rust kernel, which the SVE kernel replaces on aarch64 with `fp16kernels`
+    c.bench_function("Dot(f32, rust)", |b| {
+        let key = generate_random_array_with_seed::<Float32Type>(DIMENSION, [0; 32]);
+        let target = generate_random_array_with_seed::<Float32Type>(TOTAL * DIMENSION, [42; 32]);
+        b.iter(|| {
+            let x = key.values().as_ref();
+            black_box(
+                target
+                    .values()
+                    .chunks_exact(DIMENSION)
+                    .map(|y| dot_f32_impl(x, y))
+                    .collect::<Vec<_>>(),
+            )
+        });
+    });
+
     run_bench::<Float64Type>(c);
 }
 
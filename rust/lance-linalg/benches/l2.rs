@@ -15,7 +15,8 @@ use rand::Rng;
 use pprof::criterion::{Output, PProfProfiler};
 
 use lance_arrow::{ArrowFloatType, FloatArray};
-use lance_linalg::distance::{l2::l2, l2_distance_batch, l2_distance_uint_scalar, L2};
+use lance_linalg::distance::l2::{l2, l2_scalar as l2_kernel};
+use lance_linalg::distance::{l2_distance_batch, l2_distance_uint_scalar, L2};
 use lance_testing::datagen::generate_random_array_with_seed;
 
 const DIMENSION: usize = 1024;
@@ -80,6 +81,18 @@ fn bench_distance(c: &mut Criterion) {
             black_box(l2_distance_batch(key.values(), target.values(), DIMENSION).count());
         })
     });
+    // The Rust kernel, which the SVE kernel replaces on aarch64 with `fp16kernels`
+    c.bench_function("L2(f32, rust)", |b| {
+        b.iter(|| {
+            black_box(
+                target
+                    .values()
+                    .chunks_exact(DIMENSION)
+                    .map(|y| l2_kernel::<f32, f32, 16>(key.values(), y))
+                    .count(),
+            );
+        })
+    });
 
     run_bench::<Float64Type>(c);
 }
@@ -16,9 +16,11 @@ fn main() -> Result<(), String> {
     }
 
     // Let clippy know about our custom cfg attribute
-    println!("cargo::rustc-check-cfg=cfg(kernel_support, values(\"avx512\"))");
+    println!("cargo::rustc-check-cfg=cfg(kernel_support, values(\"avx512\", \"avx512vnni\"))");
 
     println!("cargo:rerun-if-changed=src/simd/f16.c");
+    println!("cargo:rerun-if-changed=src/simd/f32.c");
+    println!("cargo:rerun-if-changed=src/simd/i8.c");
 
     if cfg!(not(feature = "fp16kernels")) {
         println!(
@@ -46,6 +48,18 @@ fn main() -> Result<(), String> {
     } else if target_arch == "aarch64" && target_os == "linux" {
         // Build a version with NEON
         build_f16_with_flags("neon", &["-march=armv8.2-a+fp16"]).unwrap();
+        // Build versions with SVE, selected at runtime on CPUs that have it (e.g. Graviton 3
+        // and later).  The vector length is only known at runtime, so these are vector
+        // length agnostic, unlike the fixed width Rust kernels.  The f32 kernels are
+        // compared with the Rust ones by the `rust` cases of the distance benchmarks.
+        build_f16_with_flags("sve", &["-march=armv8.2-a+fp16+sve"]).unwrap();
+        build_kernels_with_flags(
+            "src/simd/f32.c",
+            "f32_sve",
+            "sve",
+            &["-march=armv8.2-a+sve"],
+        )
+        .unwrap();
     } else if target_arch == "x86_64" {
         // Build a version with AVX512
         if let Err(err) = build_f16_with_flags("avx512", &["-march=sapphirerapids", "-mavx512fp16"])
@@ -61,6 +75,20 @@ fn main() -> Result<(), String> {
             // generated the AVX512 version of the f16 kernels.
             println!("cargo:rustc-cfg=kernel_support=\"avx512\"");
         };
+        // Build the int8 kernels with AVX-512 VNNI (Cascade Lake and later)
+        if let Err(err) = build_kernels_with_flags(
+            "src/simd/i8.c",
+            "i8_avx512vnni",
+            "avx512vnni",
+            &["-mavx512f", "-mavx512bw", "-mavx512vnni", "-mbmi2"],
+        ) {
+            println!(
+                "cargo:warning=Skipping build of AVX-512 VNNI int8 kernels.  Compiler does not support AVX-512 VNNI.  Error: {}",
+                err
+            );
+        } else {
+            println!("cargo:rustc-cfg=kernel_support=\"avx512vnni\"");
+        }
         // Build a version with AVX
         // While GCC doesn't have support for _Float16 until GCC 12, clang
         // has support for __fp16 going back to at least clang 6.
@@ -80,13 +108,22 @@ fn main() -> Result<(), String> {
 }
 
 fn build_f16_with_flags(suffix: &str, flags: &[&str]) -> Result<(), cc::Error> {
+    build_kernels_with_flags("src/simd/f16.c", &format!("f16_{}", suffix), suffix, flags)
+}
+
+fn build_kernels_with_flags(
+    file: &str,
+    name: &str,
+    suffix: &str,
+    flags: &[&str],
+) -> Result<(), cc::Error> {
     let mut builder = cc::Build::new();
     builder
         // We use clang #pragma to yields better vectorization
         // See https://github.com/lancedb/lance/pull/2885
         // .compiler("clang")
         .std("c17")
-        .file("src/simd/f16.c")
+        .file(file)
         .flag("-ffast-math")
         .flag("-funroll-loops")
         .flag("-O3")
@@ -102,5 +139,5 @@ fn build_f16_with_flags(suffix: &str, flags: &[&str]) -> Result<(), cc::Error> {
         builder.flag(flag);
     }
 
-    builder.try_compile(&format!("f16_{}", suffix))
+    builder.try_compile(name)
 }
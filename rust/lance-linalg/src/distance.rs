@@ -8,12 +8,19 @@
 //! - `bf16, f16, f32, f64` types are supported.
 //! - SIMD is used when available, on `x86_64`, `aarch64` and `loongarch64`
 //!   architectures.
+//!
+//! # Nulls
+//!
+//! A null vector, or a vector with a null element, is missing: its distance is null
+//! in the batch functions, and it is skipped by searches and by index builds.  A query
+//! vector with null elements is rejected.
 
 use std::sync::Arc;
 
 use arrow_array::cast::AsArray;
 use arrow_array::types::{Float16Type, Float32Type, Float64Type, UInt8Type};
 use arrow_array::{Array, ArrowPrimitiveType, FixedSizeListArray, Float32Array, ListArray};
+use arrow_buffer::{BooleanBuffer, NullBuffer};
 use arrow_schema::{ArrowError, DataType};
 
 pub mod cosine;
@@ -21,6 +28,7 @@ pub mod dot;
 pub mod hamming;
 pub mod l2;
 pub mod norm_l2;
+pub mod topk;
 
 pub use cosine::*;
 use deepsize::DeepSizeOf;
@@ -28,6 +36,7 @@ pub use dot::*;
 use hamming::hamming_distance_arrow_batch;
 pub use l2::*;
 pub use norm_l2::*;
+pub use topk::{batch_topk, topk, topk_from_distances, TopK};
 
 use crate::Result;
 
@@ -52,7 +61,8 @@ pub type ArrowBatchDistanceFunc = fn(&dyn Array, &FixedSizeListArray) -> Result<
 impl DistanceType {
     /// Compute the distance from one vector to a batch of vectors.
     ///
-    /// This propagates nulls to the output.
+    /// The distance of a null vector, or a vector with null elements, is null.  Returns an
+    /// error if the query vector has null elements.
     pub fn arrow_batch_func(&self) -> ArrowBatchDistanceFunc {
         match self {
             Self::L2 => l2_distance_arrow_batch,
@@ -104,6 +114,40 @@ impl TryFrom<&str> for DistanceType {
     }
 }
 
+/// The validity of each vector of `vectors`, for the distances to them.
+///
+/// A vector is valid if it is not null and none of its elements are null, the others are
+/// treated as missing.
+pub fn vector_validity(vectors: &FixedSizeListArray) -> Option<NullBuffer> {
+    let Some(value_nulls) = vectors
+        .values()
+        .nulls()
+        .filter(|nulls| nulls.null_count() > 0)
+    else {
+        return vectors.nulls().cloned();
+    };
+    let dim = vectors.value_length() as usize;
+    let complete = BooleanBuffer::collect_bool(vectors.len(), |i| {
+        (i * dim..(i + 1) * dim).all(|j| value_nulls.is_valid(j))
+    });
+    NullBuffer::union(vectors.nulls(), Some(&NullBuffer::new(complete)))
+}
+
+/// Reject query vectors with null elements, which have no meaningful distance.
+pub(crate) fn check_query_nulls(query: &dyn Array) -> Result<()> {
+    if query.null_count() > 0 {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "The query vector has {} null elements",
+            query.null_count()
+        )));
+    }
+    Ok(())
+}
+
+/// Compute the distance from a multivector query to each multivector of `vectors`.
+///
+/// Missing vectors of a multivector are skipped; the distance of a null multivector, or of
+/// one without any valid vector, is `NaN`.
 pub fn multivec_distance(
     query: &dyn Array,
     vectors: &ListArray,
@@ -119,6 +163,7 @@ pub fn multivec_distance(
 
     // check the query vectors type first
     // because we don't want to check the vectors type for each vector
+    check_query_nulls(query)?;
     match query.data_type() {
         DataType::Float16 | DataType::Float32 | DataType::Float64 | DataType::UInt8 => {}
         _ => {
@@ -139,14 +184,10 @@ pub fn multivec_distance(
                         query
                             .chunks_exact(dim)
                             .map(|q| {
-                                multivector
-                                    .values()
-                                    .as_primitive::<UInt8Type>()
-                                    .values()
-                                    .chunks_exact(dim)
+                                valid_vectors::<UInt8Type>(multivector, dim)
                                     .map(|v| hamming::hamming(q, v))
                                     .min_by(|a, b| a.partial_cmp(b).unwrap())
-                                    .unwrap()
+                                    .unwrap_or(f32::NAN)
                             })
                             .sum()
                     }
@@ -157,12 +198,9 @@ pub fn multivec_distance(
                             dim,
                             distance_type,
                         ),
-                        DataType::Float32 => multivec_distance_impl::<Float32Type>(
-                            query,
-                            multivector,
-                            dim,
-                            distance_type,
-                        ),
+                        DataType::Float32 => {
+                            multivec_distance_f32(query, multivector, dim, distance_type)
+                        }
                         DataType::Float64 => multivec_distance_impl::<Float64Type>(
                             query,
                             multivector,
@@ -190,17 +228,86 @@ where
     T::Native: L2 + Cosine + Dot,
 {
     let query = query.as_primitive::<T>().values();
+    let vectors = valid_vectors::<T>(multivector, dim).collect::<Vec<_>>();
     query
         .chunks_exact(dim)
         .map(|q| {
-            multivector
-                .values()
-                .as_primitive::<T>()
-                .values()
-                .chunks_exact(dim)
+            vectors
+                .iter()
                 .map(|v| 1.0 - distance_type.func()(q, v))
                 .max_by(|a, b| a.total_cmp(b))
-                .unwrap()
+                .unwrap_or(f32::NAN)
         })
         .sum()
 }
+
+/// [`multivec_distance_impl`] for f32, which finds the nearest vector to all the query
+/// vectors at once with the blocked matrix products of [`batch_topk`].
+fn multivec_distance_f32(
+    query: &dyn Array,
+    multivector: &FixedSizeListArray,
+    dim: usize,
+    distance_type: DistanceType,
+) -> f32 {
+    let query = query.as_primitive::<Float32Type>().values();
+    let vectors = valid_vectors::<Float32Type>(multivector, dim)
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+    batch_topk(query, &vectors, dim, 1, distance_type)
+        .expect("the dimension is checked and hamming is handled by the caller")
+        .into_iter()
+        .map(|nearest| nearest.first().map_or(f32::NAN, |(_, dist)| 1.0 - dist))
+        .sum()
+}
+
+/// The vectors of a multivector, without the missing ones, see [`vector_validity`]
+fn valid_vectors<T: ArrowPrimitiveType>(
+    multivector: &FixedSizeListArray,
+    dim: usize,
+) -> impl Iterator<Item = &[T::Native]> {
+    let validity = vector_validity(multivector);
+    multivector
+        .values()
+        .as_primitive::<T>()
+        .values()
+        .chunks_exact(dim)
+        .enumerate()
+        .filter(move |(i, _)| validity.as_ref().is_none_or(|v| v.is_valid(*i)))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lance_arrow::FixedSizeListArrayExt;
+    use lance_testing::datagen::generate_random_array_with_seed;
+
+    #[test]
+    fn test_multivec_distance_f32() {
+        const DIM: usize = 16;
+        let query = generate_random_array_with_seed::<Float32Type>(8 * DIM, [1; 32]);
+        let vectors = generate_random_array_with_seed::<Float32Type>(300 * DIM, [2; 32]);
+        let multivector = FixedSizeListArray::try_new_from_values(vectors, DIM as i32).unwrap();
+
+        for distance_type in [DistanceType::L2, DistanceType::Cosine, DistanceType::Dot] {
+            // Many query vectors take the matrix product, a single one the direct path
+            for query in [query.clone(), query.slice(0, DIM)] {
+                let expected =
+                    multivec_distance_impl::<Float32Type>(&query, &multivector, DIM, distance_type);
+                let actual = multivec_distance_f32(&query, &multivector, DIM, distance_type);
+                assert!(
+                    (actual - expected).abs() < 1e-3,
+                    "{}: {} vs {}",
+                    distance_type,
+                    actual,
+                    expected
+                );
+            }
+        }
+
+        // No valid vectors
+        let empty = multivector.slice(0, 0);
+        assert!(multivec_distance_f32(&query, &empty, DIM, DistanceType::L2).is_nan());
+    }
+}
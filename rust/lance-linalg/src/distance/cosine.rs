@@ -19,8 +19,11 @@ use half::{bf16, f16};
 use lance_arrow::{ArrowFloatType, FixedSizeListArrayExt, FloatArray};
 #[cfg(feature = "fp16kernels")]
 use lance_core::utils::cpu::SimdSupport;
+#[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+use lance_core::utils::cpu::F32_SIMD_SUPPORT;
 use lance_core::utils::cpu::FP16_SIMD_SUPPORT;
 
+use super::{check_query_nulls, vector_validity};
 use super::{dot::dot, Normalize};
 use super::{norm_l2::norm_l2, Dot};
 use crate::simd::{
@@ -78,6 +81,8 @@ mod kernel {
     extern "C" {
         #[cfg(target_arch = "aarch64")]
         pub fn cosine_f16_neon(x: *const f16, x_norm: f32, y: *const f16, dimension: u32) -> f32;
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn cosine_f16_sve(x: *const f16, x_norm: f32, y: *const f16, dimension: u32) -> f32;
         #[cfg(all(kernel_support = "avx512", target_arch = "x86_64"))]
         pub fn cosine_f16_avx512(x: *const f16, x_norm: f32, y: *const f16, dimension: u32) -> f32;
         #[cfg(target_arch = "x86_64")]
@@ -86,12 +91,19 @@ mod kernel {
         pub fn cosine_f16_lsx(x: *const f16, x_norm: f32, y: *const f16, dimension: u32) -> f32;
         #[cfg(target_arch = "loongarch64")]
         pub fn cosine_f16_lasx(x: *const f16, x_norm: f32, y: *const f16, dimension: u32) -> f32;
+        // The `cosine_f32` function in f32.c, which is only compiled for SVE.
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn cosine_f32_sve(x: *const f32, x_norm: f32, y: *const f32, dimension: u32) -> f32;
     }
 }
 
 impl Cosine for f16 {
     fn cosine_fast(x: &[Self], x_norm: f32, y: &[Self]) -> f32 {
         match *FP16_SIMD_SUPPORT {
+            #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+            SimdSupport::Sve => unsafe {
+                kernel::cosine_f16_sve(x.as_ptr(), x_norm, y.as_ptr(), y.len() as u32)
+            },
             #[cfg(all(feature = "fp16kernels", target_arch = "aarch64"))]
             SimdSupport::Neon => unsafe {
                 kernel::cosine_f16_neon(x.as_ptr(), x_norm, y.as_ptr(), y.len() as u32)
@@ -143,34 +155,13 @@ mod f32 {
 impl Cosine for f32 {
     #[inline]
     fn cosine_fast(x: &[Self], x_norm: Self, other: &[Self]) -> f32 {
-        let dim = x.len();
-        let unrolled_len = dim / 16 * 16;
-        let mut y_norm16 = f32x16::zeros();
-        let mut xy16 = f32x16::zeros();
-        for i in (0..unrolled_len).step_by(16) {
-            unsafe {
-                let x = f32x16::load_unaligned(x.as_ptr().add(i));
-                let y = f32x16::load_unaligned(other.as_ptr().add(i));
-                xy16.multiply_add(x, y);
-                y_norm16.multiply_add(y, y);
-            }
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        if matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+            return unsafe {
+                kernel::cosine_f32_sve(x.as_ptr(), x_norm, other.as_ptr(), other.len() as u32)
+            };
         }
-        let aligned_len = dim / 8 * 8;
-        let mut y_norm8 = f32x8::zeros();
-        let mut xy8 = f32x8::zeros();
-        for i in (unrolled_len..aligned_len).step_by(8) {
-            unsafe {
-                let x = f32x8::load_unaligned(x.as_ptr().add(i));
-                let y = f32x8::load_unaligned(other.as_ptr().add(i));
-                xy8.multiply_add(x, y);
-                y_norm8.multiply_add(y, y);
-            }
-        }
-        let y_norm =
-            y_norm16.reduce_sum() + y_norm8.reduce_sum() + norm_l2(&other[aligned_len..]).powi(2);
-        let xy =
-            xy16.reduce_sum() + xy8.reduce_sum() + dot(&x[aligned_len..], &other[aligned_len..]);
-        1.0 - xy / x_norm / y_norm.sqrt()
+        cosine_fast_f32_impl(x, x_norm, other)
     }
 
     #[inline]
@@ -225,6 +216,40 @@ impl Cosine for f32 {
     }
 }
 
+/// The cosine distance of f32 vectors, with the SIMD width fixed at build time.
+///
+/// This is pub for test/benchmark only. use [cosine_distance] instead.
+#[inline]
+pub fn cosine_fast_f32_impl(x: &[f32], x_norm: f32, other: &[f32]) -> f32 {
+    let dim = x.len();
+    let unrolled_len = dim / 16 * 16;
+    let mut y_norm16 = f32x16::zeros();
+    let mut xy16 = f32x16::zeros();
+    for i in (0..unrolled_len).step_by(16) {
+        unsafe {
+            let x = f32x16::load_unaligned(x.as_ptr().add(i));
+            let y = f32x16::load_unaligned(other.as_ptr().add(i));
+            xy16.multiply_add(x, y);
+            y_norm16.multiply_add(y, y);
+        }
+    }
+    let aligned_len = dim / 8 * 8;
+    let mut y_norm8 = f32x8::zeros();
+    let mut xy8 = f32x8::zeros();
+    for i in (unrolled_len..aligned_len).step_by(8) {
+        unsafe {
+            let x = f32x8::load_unaligned(x.as_ptr().add(i));
+            let y = f32x8::load_unaligned(other.as_ptr().add(i));
+            xy8.multiply_add(x, y);
+            y_norm8.multiply_add(y, y);
+        }
+    }
+    let y_norm =
+        y_norm16.reduce_sum() + y_norm8.reduce_sum() + norm_l2(&other[aligned_len..]).powi(2);
+    let xy = xy16.reduce_sum() + xy8.reduce_sum() + dot(&x[aligned_len..], &other[aligned_len..]);
+    1.0 - xy / x_norm / y_norm.sqrt()
+}
+
 impl Cosine for f64 {}
 
 /// Fallback non-SIMD implementation
@@ -296,13 +321,13 @@ where
 
     Ok(Arc::new(Float32Array::new(
         dists.collect(),
-        to.nulls().cloned(),
+        vector_validity(to),
     )))
 }
 
 /// Compute Cosine distance between a vector and a batch of vectors.
 ///
-/// Null buffer of `to` is propagated to the returned array.
+/// The distance to a null vector of `to`, or to a vector with null elements, is null.
 ///
 /// Parameters
 ///
@@ -316,6 +341,7 @@ pub fn cosine_distance_arrow_batch(
     from: &dyn Array,
     to: &FixedSizeListArray,
 ) -> Result<Arc<Float32Array>> {
+    check_query_nulls(from)?;
     match *from.data_type() {
         DataType::Float16 => do_cosine_distance_arrow_batch::<Float16Type>(from.as_primitive(), to),
         DataType::Float32 => do_cosine_distance_arrow_batch::<Float32Type>(from.as_primitive(), to),
@@ -451,6 +477,26 @@ mod tests {
             do_cosine_test(&x, &y)?;
         }
 
+        // The SVE kernel must agree with the Rust kernel it replaces
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        #[test]
+        fn test_cosine_f32_sve((x, y) in arbitrary_vector_pair(arbitrary_f32, 4..4048)){
+            prop_assume!(norm_l2(&x) > 1e-10);
+            prop_assume!(norm_l2(&y) > 1e-10);
+            if !matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+                return Ok(());
+            }
+            let x_f64 = x.iter().map(|&v| v as f64).collect::<Vec<_>>();
+            let y_f64 = y.iter().map(|&v| v as f64).collect::<Vec<_>>();
+            let (_, max_error) = cosine_ref(&x_f64, &y_f64, 1e-6);
+            let x_norm = norm_l2(&x);
+            let sve = unsafe {
+                kernel::cosine_f32_sve(x.as_ptr(), x_norm, y.as_ptr(), y.len() as u32)
+            };
+            let expected = cosine_fast_f32_impl(&x, x_norm, &y);
+            prop_assert!(approx::relative_eq!(sve, expected, epsilon = 2.0 * max_error));
+        }
+
         #[test]
         fn test_cosine_f64((x, y) in arbitrary_vector_pair(arbitrary_f64, 4..4048)){
             prop_assume!(norm_l2(&x) > 1e-20);
@@ -7,6 +7,7 @@ use std::iter::Sum;
 use std::ops::AddAssign;
 use std::sync::Arc;
 
+use super::{check_query_nulls, vector_validity};
 use crate::Error;
 use arrow_array::types::{Float16Type, Float64Type, Int8Type};
 use arrow_array::{cast::AsArray, types::Float32Type, Array, FixedSizeListArray, Float32Array};
@@ -15,7 +16,15 @@ use half::{bf16, f16};
 use lance_arrow::{ArrowFloatType, FixedSizeListArrayExt, FloatArray};
 #[cfg(feature = "fp16kernels")]
 use lance_core::utils::cpu::SimdSupport;
+#[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+use lance_core::utils::cpu::F32_SIMD_SUPPORT;
 use lance_core::utils::cpu::FP16_SIMD_SUPPORT;
+#[cfg(all(
+    feature = "fp16kernels",
+    kernel_support = "avx512vnni",
+    target_arch = "x86_64"
+))]
+use lance_core::utils::cpu::INT8_SIMD_SUPPORT;
 use num_traits::{real::Real, AsPrimitive, Num};
 
 use crate::simd::{
@@ -94,6 +103,8 @@ mod kernel {
     extern "C" {
         #[cfg(target_arch = "aarch64")]
         pub fn dot_f16_neon(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn dot_f16_sve(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(all(kernel_support = "avx512", target_arch = "x86_64"))]
         pub fn dot_f16_avx512(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(target_arch = "x86_64")]
@@ -102,6 +113,12 @@ mod kernel {
         pub fn dot_f16_lsx(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(target_arch = "loongarch64")]
         pub fn dot_f16_lasx(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
+        // The `dot_f32` function in f32.c, which is only compiled for SVE.
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn dot_f32_sve(ptr1: *const f32, ptr2: *const f32, len: u32) -> f32;
+        // The `dot_i8` function in i8.c, which is only compiled for AVX-512 VNNI.
+        #[cfg(all(kernel_support = "avx512vnni", target_arch = "x86_64"))]
+        pub fn dot_i8_avx512vnni(ptr1: *const i8, ptr2: *const i8, len: u32) -> i32;
     }
 }
 
@@ -109,6 +126,10 @@ impl Dot for f16 {
     #[inline]
     fn dot(x: &[Self], y: &[Self]) -> f32 {
         match *FP16_SIMD_SUPPORT {
+            #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+            SimdSupport::Sve => unsafe {
+                kernel::dot_f16_sve(x.as_ptr(), y.as_ptr(), x.len() as u32)
+            },
             #[cfg(all(feature = "fp16kernels", target_arch = "aarch64"))]
             SimdSupport::Neon => unsafe {
                 kernel::dot_f16_neon(x.as_ptr(), y.as_ptr(), x.len() as u32)
@@ -141,59 +162,71 @@ impl Dot for f16 {
 impl Dot for f32 {
     #[inline]
     fn dot(x: &[Self], y: &[Self]) -> f32 {
-        // Manually unrolled 8 times to get enough registers.
-        // TODO: avx512 can unroll more
-        let x_unrolled_chunks = x.chunks_exact(64);
-        let y_unrolled_chunks = y.chunks_exact(64);
-
-        // 8 float32 SIMD
-        let x_aligned_chunks = x_unrolled_chunks.remainder().chunks_exact(8);
-        let y_aligned_chunks = y_unrolled_chunks.remainder().chunks_exact(8);
-
-        let sum = if x_aligned_chunks.remainder().is_empty() {
-            0.0
-        } else {
-            debug_assert_eq!(
-                x_aligned_chunks.remainder().len(),
-                y_aligned_chunks.remainder().len()
-            );
-            x_aligned_chunks
-                .remainder()
-                .iter()
-                .zip(y_aligned_chunks.remainder().iter())
-                .map(|(&x, &y)| x * y)
-                .sum()
-        };
-
-        let mut sum8 = f32x8::zeros();
-        x_aligned_chunks
-            .zip(y_aligned_chunks)
-            .for_each(|(x_chunk, y_chunk)| unsafe {
-                let x1 = f32x8::load_unaligned(x_chunk.as_ptr());
-                let y1 = f32x8::load_unaligned(y_chunk.as_ptr());
-                sum8 += x1 * y1;
-            });
-
-        let mut sum16 = f32x16::zeros();
-        x_unrolled_chunks
-            .zip(y_unrolled_chunks)
-            .for_each(|(x, y)| unsafe {
-                let x1 = f32x16::load_unaligned(x.as_ptr());
-                let x2 = f32x16::load_unaligned(x.as_ptr().add(16));
-                let x3 = f32x16::load_unaligned(x.as_ptr().add(32));
-                let x4 = f32x16::load_unaligned(x.as_ptr().add(48));
-
-                let y1 = f32x16::load_unaligned(y.as_ptr());
-                let y2 = f32x16::load_unaligned(y.as_ptr().add(16));
-                let y3 = f32x16::load_unaligned(y.as_ptr().add(32));
-                let y4 = f32x16::load_unaligned(y.as_ptr().add(48));
-
-                sum16 += (x1 * y1 + x2 * y2) + (x3 * y3 + x4 * y4);
-            });
-        sum16.reduce_sum() + sum8.reduce_sum() + sum
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        if matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+            return unsafe { kernel::dot_f32_sve(x.as_ptr(), y.as_ptr(), x.len() as u32) };
+        }
+        dot_f32_impl(x, y)
     }
 }
 
+/// The dot product of f32 vectors, with the SIMD width fixed at build time.
+///
+/// This is pub for test/benchmark only. use [dot] instead.
+#[inline]
+pub fn dot_f32_impl(x: &[f32], y: &[f32]) -> f32 {
+    // Manually unrolled 8 times to get enough registers.
+    // TODO: avx512 can unroll more
+    let x_unrolled_chunks = x.chunks_exact(64);
+    let y_unrolled_chunks = y.chunks_exact(64);
+
+    // 8 float32 SIMD
+    let x_aligned_chunks = x_unrolled_chunks.remainder().chunks_exact(8);
+    let y_aligned_chunks = y_unrolled_chunks.remainder().chunks_exact(8);
+
+    let sum = if x_aligned_chunks.remainder().is_empty() {
+        0.0
+    } else {
+        debug_assert_eq!(
+            x_aligned_chunks.remainder().len(),
+            y_aligned_chunks.remainder().len()
+        );
+        x_aligned_chunks
+            .remainder()
+            .iter()
+            .zip(y_aligned_chunks.remainder().iter())
+            .map(|(&x, &y)| x * y)
+            .sum()
+    };
+
+    let mut sum8 = f32x8::zeros();
+    x_aligned_chunks
+        .zip(y_aligned_chunks)
+        .for_each(|(x_chunk, y_chunk)| unsafe {
+            let x1 = f32x8::load_unaligned(x_chunk.as_ptr());
+            let y1 = f32x8::load_unaligned(y_chunk.as_ptr());
+            sum8 += x1 * y1;
+        });
+
+    let mut sum16 = f32x16::zeros();
+    x_unrolled_chunks
+        .zip(y_unrolled_chunks)
+        .for_each(|(x, y)| unsafe {
+            let x1 = f32x16::load_unaligned(x.as_ptr());
+            let x2 = f32x16::load_unaligned(x.as_ptr().add(16));
+            let x3 = f32x16::load_unaligned(x.as_ptr().add(32));
+            let x4 = f32x16::load_unaligned(x.as_ptr().add(48));
+
+            let y1 = f32x16::load_unaligned(y.as_ptr());
+            let y2 = f32x16::load_unaligned(y.as_ptr().add(16));
+            let y3 = f32x16::load_unaligned(y.as_ptr().add(32));
+            let y4 = f32x16::load_unaligned(y.as_ptr().add(48));
+
+            sum16 += (x1 * y1 + x2 * y2) + (x3 * y3 + x4 * y4);
+        });
+    sum16.reduce_sum() + sum8.reduce_sum() + sum
+}
+
 impl Dot for f64 {
     #[inline]
     fn dot(x: &[Self], y: &[Self]) -> f32 {
@@ -212,6 +245,26 @@ impl Dot for u8 {
     }
 }
 
+impl Dot for i8 {
+    #[inline]
+    fn dot(x: &[Self], y: &[Self]) -> f32 {
+        #[cfg(all(
+            feature = "fp16kernels",
+            kernel_support = "avx512vnni",
+            target_arch = "x86_64"
+        ))]
+        if matches!(*INT8_SIMD_SUPPORT, SimdSupport::Avx512) {
+            return unsafe { kernel::dot_i8_avx512vnni(x.as_ptr(), y.as_ptr(), x.len() as u32) }
+                as f32;
+        }
+        // Accumulating in i32 is exact and lets LLVM auto-vectorize.
+        x.iter()
+            .zip(y.iter())
+            .map(|(&x_i, &y_i)| x_i as i32 * y_i as i32)
+            .sum::<i32>() as f32
+    }
+}
+
 /// Negative dot product, to present the relative order of dot distance.
 pub fn dot_distance_batch<'a, T: Dot>(
     from: &'a [T],
@@ -251,13 +304,13 @@ where
 
     Ok(Arc::new(Float32Array::new(
         dists.collect(),
-        to.nulls().cloned(),
+        vector_validity(to),
     )))
 }
 
 /// Compute negative dot product distance between a vector and a batch of vectors.
 ///
-/// Null buffer of `to` is propagated to the returned array.
+/// The distance to a null vector of `to`, or to a vector with null elements, is null.
 ///
 /// Parameters
 ///
@@ -271,6 +324,7 @@ pub fn dot_distance_arrow_batch(
     from: &dyn Array,
     to: &FixedSizeListArray,
 ) -> Result<Arc<Float32Array>> {
+    check_query_nulls(from)?;
     let dimension = to.value_length() as usize;
     debug_assert_eq!(from.len(), dimension);
 
@@ -278,6 +332,19 @@ pub fn dot_distance_arrow_batch(
         DataType::Float16 => do_dot_distance_arrow_batch::<Float16Type>(from.as_primitive(), to),
         DataType::Float32 => do_dot_distance_arrow_batch::<Float32Type>(from.as_primitive(), to),
         DataType::Float64 => do_dot_distance_arrow_batch::<Float64Type>(from.as_primitive(), to),
+        DataType::Int8 if to.value_type() == DataType::Int8 => {
+            let from = from.as_primitive::<Int8Type>().values();
+            let dists = to
+                .values()
+                .as_primitive::<Int8Type>()
+                .values()
+                .chunks_exact(dimension)
+                .map(|v| dot_distance(from, v));
+            Ok(Arc::new(Float32Array::new(
+                dists.collect(),
+                vector_validity(to),
+            )))
+        }
         DataType::Int8 => do_dot_distance_arrow_batch::<Float32Type>(
             &from
                 .as_primitive::<Int8Type>()
@@ -384,5 +451,29 @@ mod tests {
         fn test_dot_f64((x, y) in arbitrary_vector_pair(arbitrary_f64, 4..4048)){
             do_dot_test(&x, &y)?;
         }
+
+        // The SVE kernel must agree with the Rust kernel it replaces
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        #[test]
+        fn test_dot_f32_sve((x, y) in arbitrary_vector_pair(arbitrary_f32, 4..4048)){
+            if !matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+                return Ok(());
+            }
+            let f64_x = x.iter().map(|&v| v as f64).collect::<Vec<f64>>();
+            let f64_y = y.iter().map(|&v| v as f64).collect::<Vec<f64>>();
+            let sve = unsafe { kernel::dot_f32_sve(x.as_ptr(), y.as_ptr(), x.len() as u32) };
+            let expected = dot_f32_impl(&x, &y);
+            // Both differ from the exact result by up to the max error
+            let max_error = 2.0 * max_error::<f32>(&f64_x, &f64_y);
+            prop_assert!(approx::relative_eq!(sve, expected, epsilon = max_error));
+        }
+
+        #[test]
+        fn test_dot_i8((x, y) in (4..4048_usize).prop_flat_map(|len| {
+            (prop::collection::vec(any::<i8>(), len), prop::collection::vec(any::<i8>(), len))
+        })) {
+            let expected = x.iter().zip(y.iter()).map(|(&a, &b)| a as i64 * b as i64).sum::<i64>();
+            prop_assert_eq!(dot(&x, &y), expected as f32);
+        }
     }
 }
@@ -5,6 +5,7 @@
 
 use std::sync::Arc;
 
+use super::{check_query_nulls, vector_validity};
 use crate::{Error, Result};
 use arrow_array::cast::AsArray;
 use arrow_array::types::UInt8Type;
@@ -66,6 +67,7 @@ pub fn hamming_distance_arrow_batch(
     from: &dyn Array,
     to: &FixedSizeListArray,
 ) -> Result<Arc<Float32Array>> {
+    check_query_nulls(from)?;
     let dists = match *from.data_type() {
         DataType::UInt8 => hamming_distance_batch(
             from.as_primitive::<UInt8Type>().values(),
@@ -82,7 +84,7 @@ pub fn hamming_distance_arrow_batch(
 
     Ok(Arc::new(Float32Array::new(
         dists.collect(),
-        to.nulls().cloned(),
+        vector_validity(to),
     )))
 }
 
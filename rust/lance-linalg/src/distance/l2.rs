@@ -18,9 +18,12 @@ use half::{bf16, f16};
 use lance_arrow::{ArrowFloatType, FixedSizeListArrayExt, FloatArray};
 #[cfg(feature = "fp16kernels")]
 use lance_core::utils::cpu::SimdSupport;
+#[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+use lance_core::utils::cpu::F32_SIMD_SUPPORT;
 use lance_core::utils::cpu::FP16_SIMD_SUPPORT;
 use num_traits::{AsPrimitive, Num};
 
+use super::{check_query_nulls, vector_validity};
 use crate::simd::{
     f32::{f32x16, f32x8},
     SIMD,
@@ -122,6 +125,8 @@ mod kernel {
     extern "C" {
         #[cfg(target_arch = "aarch64")]
         pub fn l2_f16_neon(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn l2_f16_sve(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(all(kernel_support = "avx512", target_arch = "x86_64"))]
         pub fn l2_f16_avx512(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(target_arch = "x86_64")]
@@ -130,6 +135,9 @@ mod kernel {
         pub fn l2_f16_lsx(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
         #[cfg(target_arch = "loongarch64")]
         pub fn l2_f16_lasx(ptr1: *const f16, ptr2: *const f16, len: u32) -> f32;
+        // The `l2_f32` function in f32.c, which is only compiled for SVE.
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn l2_f32_sve(ptr1: *const f32, ptr2: *const f32, len: u32) -> f32;
     }
 }
 
@@ -137,6 +145,10 @@ impl L2 for f16 {
     #[inline]
     fn l2(x: &[Self], y: &[Self]) -> f32 {
         match *FP16_SIMD_SUPPORT {
+            #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+            SimdSupport::Sve => unsafe {
+                kernel::l2_f16_sve(x.as_ptr(), y.as_ptr(), x.len() as u32)
+            },
             #[cfg(all(feature = "fp16kernels", target_arch = "aarch64"))]
             SimdSupport::Neon => unsafe {
                 kernel::l2_f16_neon(x.as_ptr(), y.as_ptr(), x.len() as u32)
@@ -169,6 +181,10 @@ impl L2 for f16 {
 impl L2 for f32 {
     #[inline]
     fn l2(x: &[Self], y: &[Self]) -> f32 {
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        if matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+            return unsafe { kernel::l2_f32_sve(x.as_ptr(), y.as_ptr(), x.len() as u32) };
+        }
         // 16 = 512 (avx512) / 8 bits / 4 (sizeof(f32))
         // See https://github.com/lancedb/lance/pull/2450.
         l2_scalar::<Self, Self, 16>(x, y)
@@ -269,13 +285,13 @@ where
 
     Ok(Arc::new(Float32Array::new(
         dists.collect(),
-        to.nulls().cloned(),
+        vector_validity(to),
     )))
 }
 
 /// Compute L2 distance between a vector and a batch of vectors.
 ///
-/// Null buffer of `to` is propagated to the returned array.
+/// The distance to a null vector of `to`, or to a vector with null elements, is null.
 ///
 /// Parameters
 ///
@@ -289,6 +305,7 @@ pub fn l2_distance_arrow_batch(
     from: &dyn Array,
     to: &FixedSizeListArray,
 ) -> Result<Arc<Float32Array>> {
+    check_query_nulls(from)?;
     match *from.data_type() {
         DataType::Float16 => do_l2_distance_arrow_batch::<Float16Type>(from.as_primitive(), to),
         DataType::Float32 => do_l2_distance_arrow_batch::<Float32Type>(from.as_primitive(), to),
@@ -342,6 +359,28 @@ mod tests {
         assert_eq!(distances, vec![32.0, 8.0, 0.0, 8.0]);
     }
 
+    #[test]
+    fn test_l2_distance_arrow_batch_nulls() {
+        let mat = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            vec![
+                Some(vec![Some(1.0), Some(2.0)]),
+                None,
+                Some(vec![Some(1.0), None]),
+                Some(vec![Some(3.0), Some(2.0)]),
+            ],
+            2,
+        );
+        let point = Float32Array::from(vec![1.0, 2.0]);
+        let distances = l2_distance_arrow_batch(&point, &mat).unwrap();
+        assert_eq!(
+            distances.iter().collect::<Vec<_>>(),
+            vec![Some(0.0), None, None, Some(4.0)]
+        );
+
+        let point = Float32Array::from(vec![Some(1.0), None]);
+        assert!(l2_distance_arrow_batch(&point, &mat).is_err());
+    }
+
     #[test]
     fn test_not_aligned() {
         let mat = (0..6)
@@ -469,6 +508,18 @@ mod tests {
         fn test_l2_distance_f64((x, y) in arbitrary_vector_pair(arbitrary_f64, 4..4048)){
             do_l2_test(&x, &y)?;
         }
+
+        // The SVE kernel must agree with the Rust kernel it replaces
+        #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+        #[test]
+        fn test_l2_distance_f32_sve((x, y) in arbitrary_vector_pair(arbitrary_f32, 4..4048)){
+            if !matches!(*F32_SIMD_SUPPORT, SimdSupport::Sve) {
+                return Ok(());
+            }
+            let sve = unsafe { kernel::l2_f32_sve(x.as_ptr(), y.as_ptr(), x.len() as u32) };
+            let expected = l2_scalar::<f32, f32, 16>(&x, &y);
+            prop_assert!(approx::relative_eq!(sve, expected, max_relative = 1e-6));
+        }
     }
 
     #[test]
@@ -25,6 +25,8 @@ mod kernel {
     extern "C" {
         #[cfg(target_arch = "aarch64")]
         pub fn norm_l2_f16_neon(ptr: *const f16, len: u32) -> f32;
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        pub fn norm_l2_f16_sve(ptr: *const f16, len: u32) -> f32;
         #[cfg(all(kernel_support = "avx512", target_arch = "x86_64"))]
         pub fn norm_l2_f16_avx512(ptr: *const f16, len: u32) -> f32;
         #[cfg(target_arch = "x86_64")]
@@ -47,6 +49,10 @@ impl Normalize for f16 {
     #[inline]
     fn norm_l2(vector: &[Self]) -> f32 {
         match *FP16_SIMD_SUPPORT {
+            #[cfg(all(feature = "fp16kernels", target_arch = "aarch64", target_os = "linux"))]
+            SimdSupport::Sve => unsafe {
+                kernel::norm_l2_f16_sve(vector.as_ptr(), vector.len() as u32)
+            },
             #[cfg(all(feature = "fp16kernels", target_arch = "aarch64"))]
             SimdSupport::Neon => unsafe {
                 kernel::norm_l2_f16_neon(vector.as_ptr(), vector.len() as u32)
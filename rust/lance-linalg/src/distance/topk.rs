@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Top-k selection and brute-force top-k search.
+//!
+//! [`TopK`] keeps the `k` nearest of a stream of candidates in a bounded heap.  Ties on
+//! the distance are broken by the id, e.g. the row id, so the results do not depend on
+//! the order the candidates are seen in, which differs between runs of parallel searches.
+//!
+//! The distances between a batch of queries and the vectors are computed from blocked
+//! matrix products, `Q * V^T`, and each block of distances is merged into a bounded heap
+//! per query as soon as it is computed, so the full distance matrix is never materialized.
+//! [`batch_topk`] finds the nearest vectors of a multivector to the query vectors, in the
+//! flat search and re-ranking of multivector columns.
+//!
+//! With the `blas` feature, the matrix products are computed by `cblas_sgemm` of the BLAS
+//! library linked into the final binary (e.g. OpenBLAS, MKL or Accelerate).
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use arrow_schema::ArrowError;
+
+use super::{dot::dot, norm_l2::norm_l2, DistanceType};
+use crate::Result;
+
+/// Number of vectors in each block of the matrix product.
+const VECTOR_BLOCK_SIZE: usize = 256;
+
+/// Number of queries in each tile of the fallback matrix product, so a tile of queries
+/// and a block of vectors stay in cache together.
+#[cfg(not(feature = "blas"))]
+const QUERY_TILE_SIZE: usize = 16;
+
+/// A candidate in the top-k heap, ordered by distance then by id.
+#[derive(Debug, Clone, Copy)]
+struct Candidate<I> {
+    dist: f32,
+    id: I,
+}
+
+impl<I: Ord> PartialEq for Candidate<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<I: Ord> Eq for Candidate<I> {}
+
+impl<I: Ord> PartialOrd for Candidate<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I: Ord> Ord for Candidate<I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist
+            .total_cmp(&other.dist)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+/// Keeps the `k` nearest candidates seen so far.
+///
+/// Candidates are ordered by distance, then by id, so among candidates at the same
+/// distance the ones with the smallest ids are kept.  `NaN` distances are skipped.
+#[derive(Debug, Clone)]
+pub struct TopK<I = u32> {
+    k: usize,
+    // A max-heap, so the farthest of the current top-k is at the top
+    heap: BinaryHeap<Candidate<I>>,
+    // The distance of the farthest candidate once there are k of them, so most
+    // candidates are rejected with a single comparison
+    threshold: f32,
+}
+
+impl<I: Ord + Copy> TopK<I> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+            threshold: f32::INFINITY,
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, id: I, dist: f32) {
+        if dist.is_nan() || dist > self.threshold {
+            return;
+        }
+        let candidate = Candidate { dist, id };
+        if self.heap.len() < self.k {
+            self.heap.push(candidate);
+        } else if let Some(mut farthest) = self.heap.peek_mut() {
+            if candidate < *farthest {
+                *farthest = candidate;
+            }
+        }
+        if self.heap.len() == self.k {
+            if let Some(farthest) = self.heap.peek() {
+                self.threshold = farthest.dist;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The candidates, nearest first.
+    pub fn into_sorted_vec(self) -> Vec<(I, f32)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.id, c.dist))
+            .collect()
+    }
+}
+
+impl<I: Ord + Copy> Extend<(I, f32)> for TopK<I> {
+    fn extend<T: IntoIterator<Item = (I, f32)>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|(id, dist)| self.push(id, dist));
+    }
+}
+
+/// Select the `k` nearest of (id, distance) pairs, nearest first, see [`TopK`].
+pub fn topk<I: Ord + Copy>(
+    candidates: impl IntoIterator<Item = (I, f32)>,
+    k: usize,
+) -> Vec<(I, f32)> {
+    let mut topk = TopK::new(k);
+    topk.extend(candidates);
+    topk.into_sorted_vec()
+}
+
+/// Select the `k` smallest distances, nearest first.
+///
+/// The id of each distance is its position in `distances`.  `NaN` distances are skipped.
+pub fn topk_from_distances(distances: impl IntoIterator<Item = f32>, k: usize) -> Vec<(u32, f32)> {
+    topk(
+        distances
+            .into_iter()
+            .enumerate()
+            .map(|(id, dist)| (id as u32, dist)),
+        k,
+    )
+}
+
+/// Compute `out = queries * block^T`, where `out` is `num_queries x num_vectors` in row major.
+#[cfg(not(feature = "blas"))]
+fn matmul_transposed(queries: &[f32], block: &[f32], dimension: usize, out: &mut [f32]) {
+    let num_vectors = block.len() / dimension;
+    for (query_tile, out_tile) in queries
+        .chunks(QUERY_TILE_SIZE * dimension)
+        .zip(out.chunks_mut(QUERY_TILE_SIZE * num_vectors))
+    {
+        for (query, out_row) in query_tile
+            .chunks_exact(dimension)
+            .zip(out_tile.chunks_exact_mut(num_vectors))
+        {
+            for (vector, out) in block.chunks_exact(dimension).zip(out_row.iter_mut()) {
+                *out = dot(query, vector);
+            }
+        }
+    }
+}
+
+/// Compute `out = queries * block^T`, where `out` is `num_queries x num_vectors` in row major.
+#[cfg(feature = "blas")]
+fn matmul_transposed(queries: &[f32], block: &[f32], dimension: usize, out: &mut [f32]) {
+    use cblas_sys::{cblas_sgemm, CBLAS_LAYOUT, CBLAS_TRANSPOSE};
+
+    let num_queries = queries.len() / dimension;
+    let num_vectors = block.len() / dimension;
+    unsafe {
+        cblas_sgemm(
+            CBLAS_LAYOUT::CblasRowMajor,
+            CBLAS_TRANSPOSE::CblasNoTrans,
+            CBLAS_TRANSPOSE::CblasTrans,
+            num_queries as i32,
+            num_vectors as i32,
+            dimension as i32,
+            1.0,
+            queries.as_ptr(),
+            dimension as i32,
+            block.as_ptr(),
+            dimension as i32,
+            0.0,
+            out.as_mut_ptr(),
+            num_vectors as i32,
+        );
+    }
+}
+
+/// Find the `k` nearest vectors to each query by brute force.
+///
+/// `queries` and `vectors` are row-major matrices of `dimension` columns.  Returns, for each
+/// query, up to `k` pairs of (row of `vectors`, distance), nearest first.  The distances are
+/// the same as [`DistanceType::func`], up to rounding.
+///
+/// [`DistanceType::Hamming`] is not supported.
+pub fn batch_topk(
+    queries: &[f32],
+    vectors: &[f32],
+    dimension: usize,
+    k: usize,
+    distance_type: DistanceType,
+) -> Result<Vec<Vec<(u32, f32)>>> {
+    if dimension == 0 || queries.len() % dimension != 0 || vectors.len() % dimension != 0 {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "batch_topk: queries ({}) and vectors ({}) must be multiples of the dimension ({})",
+            queries.len(),
+            vectors.len(),
+            dimension
+        )));
+    }
+    if distance_type == DistanceType::Hamming {
+        return Err(ArrowError::InvalidArgumentError(
+            "batch_topk: hamming distance is not supported for float vectors".to_string(),
+        ));
+    }
+    let num_queries = queries.len() / dimension;
+
+    // A single query does not benefit from the matrix product, and L2 would have to
+    // compute the norm of every vector on top of the dot products.
+    if num_queries == 1 {
+        let distance_fn = distance_type.func::<f32>();
+        return Ok(vec![topk_from_distances(
+            vectors
+                .chunks_exact(dimension)
+                .map(|vector| distance_fn(queries, vector)),
+            k,
+        )]);
+    }
+
+    let query_norms = queries
+        .chunks_exact(dimension)
+        .map(norm_l2)
+        .collect::<Vec<_>>();
+    let mut topks = (0..num_queries)
+        .map(|_| TopK::<u32>::new(k))
+        .collect::<Vec<_>>();
+    let mut products = vec![0.0; num_queries * VECTOR_BLOCK_SIZE];
+    for (block_idx, block) in vectors.chunks(VECTOR_BLOCK_SIZE * dimension).enumerate() {
+        let num_vectors = block.len() / dimension;
+        let first_id = (block_idx * VECTOR_BLOCK_SIZE) as u32;
+        let products = &mut products[..num_queries * num_vectors];
+        matmul_transposed(queries, block, dimension, products);
+
+        let vector_norms = match distance_type {
+            DistanceType::L2 | DistanceType::Cosine => block
+                .chunks_exact(dimension)
+                .map(norm_l2)
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        for ((topk, &query_norm), row) in topks
+            .iter_mut()
+            .zip(query_norms.iter())
+            .zip(products.chunks_exact(num_vectors))
+        {
+            for (i, &product) in row.iter().enumerate() {
+                let dist = match distance_type {
+                    // |q - v|^2 = |q|^2 + |v|^2 - 2 q.v, clamped as rounding can make it negative
+                    DistanceType::L2 => (query_norm * query_norm
+                        + vector_norms[i] * vector_norms[i]
+                        - 2.0 * product)
+                        .max(0.0),
+                    DistanceType::Cosine => 1.0 - product / (query_norm * vector_norms[i]),
+                    DistanceType::Dot => 1.0 - product,
+                    DistanceType::Hamming => unreachable!(),
+                };
+                topk.push(first_id + i as u32, dist);
+            }
+        }
+    }
+    Ok(topks.into_iter().map(TopK::into_sorted_vec).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::{cosine_distance, dot_distance, l2};
+    use lance_testing::datagen::generate_random_array_with_seed;
+
+    fn brute_force(
+        query: &[f32],
+        vectors: &[f32],
+        dimension: usize,
+        k: usize,
+        distance_fn: fn(&[f32], &[f32]) -> f32,
+    ) -> Vec<u32> {
+        let mut dists = vectors
+            .chunks_exact(dimension)
+            .map(|v| distance_fn(query, v))
+            .enumerate()
+            .collect::<Vec<_>>();
+        dists.sort_by(|a, b| a.1.total_cmp(&b.1));
+        dists.into_iter().take(k).map(|(id, _)| id as u32).collect()
+    }
+
+    #[test]
+    fn test_batch_topk() {
+        const DIM: usize = 32;
+        const K: usize = 10;
+        // More than one block of vectors, and not a multiple of the block size
+        let vectors =
+            generate_random_array_with_seed::<arrow_array::types::Float32Type>(1000 * DIM, [1; 32]);
+        let queries =
+            generate_random_array_with_seed::<arrow_array::types::Float32Type>(20 * DIM, [2; 32]);
+        let (vectors, queries) = (vectors.values(), queries.values());
+
+        for (distance_type, distance_fn) in [
+            (DistanceType::L2, l2 as fn(&[f32], &[f32]) -> f32),
+            (DistanceType::Cosine, cosine_distance),
+            (DistanceType::Dot, dot_distance),
+        ] {
+            let results = batch_topk(queries, vectors, DIM, K, distance_type).unwrap();
+            assert_eq!(results.len(), 20);
+            for (query, result) in queries.chunks_exact(DIM).zip(results.iter()) {
+                assert_eq!(result.len(), K);
+                let expected = brute_force(query, vectors, DIM, K, distance_fn);
+                let ids = result.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+                assert_eq!(ids, expected, "{}", distance_type);
+                for (id, dist) in result {
+                    let expected = distance_fn(
+                        query,
+                        &vectors[*id as usize * DIM..(*id as usize + 1) * DIM],
+                    );
+                    assert!((dist - expected).abs() < 1e-3, "{} vs {}", dist, expected);
+                }
+            }
+
+            // A single query takes the direct path
+            let single = batch_topk(&queries[..DIM], vectors, DIM, K, distance_type).unwrap();
+            assert_eq!(
+                single[0].iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+                results[0].iter().map(|(id, _)| *id).collect::<Vec<_>>()
+            );
+        }
+
+        // Fewer vectors than k
+        let results = batch_topk(queries, &vectors[..3 * DIM], DIM, K, DistanceType::L2).unwrap();
+        assert!(results.iter().all(|r| r.len() == 3));
+
+        assert!(batch_topk(queries, vectors, DIM, K, DistanceType::Hamming).is_err());
+        assert!(batch_topk(&queries[..DIM - 1], vectors, DIM, K, DistanceType::L2).is_err());
+    }
+
+    #[test]
+    fn test_topk_ties() {
+        // Ties are broken by id, whatever the order of the candidates
+        let candidates = vec![(7_u64, 1.0), (3, 2.0), (5, 1.0), (1, 1.0), (2, 0.5)];
+        let expected = vec![(2, 0.5), (1, 1.0), (5, 1.0)];
+        assert_eq!(topk(candidates.clone(), 3), expected);
+        assert_eq!(topk(candidates.into_iter().rev(), 3), expected);
+
+        assert!(topk(vec![(1_u64, 1.0)], 0).is_empty());
+    }
+
+    #[test]
+    fn test_topk_from_distances() {
+        let dists = vec![3.0, f32::NAN, 1.0, 2.0, 0.5];
+        assert_eq!(
+            topk_from_distances(dists, 3),
+            vec![(4, 0.5), (2, 1.0), (3, 2.0)]
+        );
+    }
+}
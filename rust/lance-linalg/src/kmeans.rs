@@ -68,6 +68,19 @@ pub struct KMeansParams {
 
     /// The metric to calculate distance.
     pub distance_type: DistanceType,
+
+    /// Seed of the random initialization and sampling, for reproducible training.
+    ///
+    /// If not set, the training is seeded from entropy.
+    pub seed: Option<u64>,
+
+    /// Train with mini-batches of this many vectors instead of full Lloyd iterations.
+    ///
+    /// Each iteration assigns a random mini-batch to the nearest centroids and moves
+    /// those centroids towards it, so an iteration costs `batch_size` instead of all the
+    /// vectors.  Training stops early once the smoothed loss of the mini-batches changes
+    /// by less than `tolerance`.  Only float vectors are trained with mini-batches.
+    pub batch_size: Option<usize>,
 }
 
 impl Default for KMeansParams {
@@ -78,6 +91,8 @@ impl Default for KMeansParams {
             redos: 1,
             init: KMeanInit::Random,
             distance_type: DistanceType::L2,
+            seed: None,
+            batch_size: None,
         }
     }
 }
@@ -101,6 +116,31 @@ impl KMeansParams {
             ..Default::default()
         }
     }
+
+    /// Stop the training once the relative change of the loss is less than `tolerance`.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Seed the training, see [`Self::seed`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Train with mini-batches, see [`Self::batch_size`].
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    fn rng(&self) -> SmallRng {
+        match self.seed {
+            Some(seed) => SmallRng::seed_from_u64(seed),
+            None => SmallRng::from_entropy(),
+        }
+    }
 }
 
 /// KMeans implementation for Apache Arrow Arrays.
@@ -154,9 +194,9 @@ fn split_clusters<T: Float + MulAssign>(
     cnts: &mut [u64],
     centroids: &mut [T],
     dim: usize,
+    rng: &mut impl Rng,
 ) {
     let eps = T::from(1.0 / 1024.0).unwrap();
-    let mut rng = SmallRng::from_entropy();
     for i in 0..cnts.len() {
         if cnts[i] == 0 {
             let mut j = 0;
@@ -229,6 +269,7 @@ pub trait KMeansAlgo<T: Num> {
         membership: &[Option<u32>],
         distance_type: DistanceType,
         loss: f64,
+        rng: &mut impl Rng,
     ) -> KMeans;
 }
 
@@ -285,6 +326,7 @@ where
         membership: &[Option<u32>],
         distance_type: DistanceType,
         loss: f64,
+        rng: &mut impl Rng,
     ) -> KMeans {
         let mut cluster_cnts = vec![0_u64; k];
         let mut centroids = vec![T::Native::zero(); k * dimension];
@@ -343,6 +385,7 @@ where
             &mut cluster_cnts,
             &mut centroids,
             dimension,
+            rng,
         );
 
         KMeans {
@@ -395,6 +438,7 @@ impl KMeansAlgo<u8> for KModeAlgo {
         membership: &[Option<u32>],
         distance_type: DistanceType,
         loss: f64,
+        _rng: &mut impl Rng,
     ) -> KMeans {
         assert_eq!(distance_type, DistanceType::Hamming);
 
@@ -521,15 +565,14 @@ impl KMeans {
         let mut best_kmeans = Self::empty(dimension, params.distance_type);
         let mut best_stddev = f32::MAX;
 
-        // TODO: use seed for Rng.
-        let rng = SmallRng::from_entropy();
+        let mut rng = params.rng();
         for redo in 1..=params.redos {
             let mut kmeans: Self = match &params.init {
                 KMeanInit::Random => Self::init_random::<T>(
                     data.values(),
                     dimension,
                     k,
-                    rng.clone(),
+                    &mut rng,
                     params.distance_type,
                 ),
                 KMeanInit::Incremental(centroids) => Self::with_centroids(
@@ -562,6 +605,7 @@ impl KMeans {
                     &membership,
                     params.distance_type,
                     last_loss,
+                    &mut rng,
                 );
                 last_membership = Some(membership);
                 if (loss - last_loss).abs() / last_loss < params.tolerance {
@@ -588,6 +632,125 @@ impl KMeans {
         Ok(best_kmeans)
     }
 
+    /// Train with mini-batches of `batch_size` vectors, see [`KMeansParams::batch_size`].
+    ///
+    /// Each centroid moves towards the vectors assigned to it with a learning rate of one
+    /// over the number of vectors assigned to it so far (Sculley, "Web-scale k-means
+    /// clustering", 2010).
+    fn train_mini_batch_kmeans<T: ArrowNumericType>(
+        data: &FixedSizeListArray,
+        k: usize,
+        batch_size: usize,
+        params: &KMeansParams,
+    ) -> Result<Self>
+    where
+        T::Native: Float + Dot + L2 + MulAssign + DivAssign + AddAssign + FromPrimitive + Sync,
+        PrimitiveArray<T>: From<Vec<T::Native>>,
+    {
+        let dimension = data.value_length() as usize;
+        let data = data
+            .values()
+            .as_primitive_opt::<T>()
+            .ok_or(Error::InvalidArgumentError(format!(
+                "KMeans: data must be {}, got: {}",
+                T::DATA_TYPE,
+                data.value_type()
+            )))?
+            .values();
+        let n = data.len() / dimension;
+
+        let mut best_kmeans = Self::empty(dimension, params.distance_type);
+        let mut best_stddev = f32::MAX;
+
+        let mut rng = params.rng();
+        // The loss of the mini-batches is noisy, so convergence is checked on its
+        // exponentially weighted average, as in scikit-learn.
+        let alpha = (2.0 * batch_size as f64 / (n + 1) as f64).min(1.0);
+        let mut batch = vec![T::Native::zero(); batch_size * dimension];
+        for redo in 1..=params.redos {
+            let mut centroids = match &params.init {
+                KMeanInit::Random => {
+                    Self::init_random::<T>(data, dimension, k, &mut rng, params.distance_type)
+                        .centroids
+                        .as_primitive::<T>()
+                        .values()
+                        .to_vec()
+                }
+                KMeanInit::Incremental(centroids) => {
+                    centroids.values().as_primitive::<T>().values().to_vec()
+                }
+            };
+            let mut counts = vec![0_u64; k];
+            let mut smoothed_loss: Option<f64> = None;
+            for i in 1..=params.max_iters {
+                if i % 10 == 0 {
+                    info!(
+                        "Mini-batch KMeans training: iteration {} / {}, redo={}",
+                        i, params.max_iters, redo
+                    );
+                };
+                for (vector, idx) in batch
+                    .chunks_exact_mut(dimension)
+                    .zip(rand::seq::index::sample(&mut rng, n, batch_size))
+                {
+                    vector.copy_from_slice(&data[idx * dimension..(idx + 1) * dimension]);
+                }
+                let (membership, loss) = KMeansAlgoFloat::<T>::compute_membership_and_loss(
+                    &centroids,
+                    &batch,
+                    dimension,
+                    params.distance_type,
+                );
+                for (vector, cluster_id) in batch.chunks_exact(dimension).zip(membership) {
+                    let Some(cluster_id) = cluster_id else {
+                        continue;
+                    };
+                    let cluster_id = cluster_id as usize;
+                    counts[cluster_id] += 1;
+                    let rate = T::Native::one() / T::Native::from_u64(counts[cluster_id]).unwrap();
+                    centroids[cluster_id * dimension..(cluster_id + 1) * dimension]
+                        .iter_mut()
+                        .zip(vector)
+                        .for_each(|(c, v)| *c += (*v - *c) * rate);
+                }
+
+                let loss = loss / batch_size as f64;
+                let last_loss = smoothed_loss;
+                let loss = last_loss.map_or(loss, |last| last * (1.0 - alpha) + loss * alpha);
+                smoothed_loss = Some(loss);
+                if let Some(last_loss) = last_loss {
+                    if (last_loss - loss).abs() / last_loss < params.tolerance {
+                        info!(
+                            "Mini-batch KMeans training: converged at iteration {} / {}, redo={}, loss={}",
+                            i, params.max_iters, redo, loss
+                        );
+                        break;
+                    }
+                }
+            }
+
+            // One pass over all the vectors, for the loss and the balance of the clusters
+            let (membership, loss) = KMeansAlgoFloat::<T>::compute_membership_and_loss(
+                &centroids,
+                data,
+                dimension,
+                params.distance_type,
+            );
+            let stddev = hist_stddev(k, &membership);
+            if stddev < best_stddev {
+                best_stddev = stddev;
+                best_kmeans = Self {
+                    centroids: Arc::new(PrimitiveArray::<T>::from(centroids)),
+                    dimension,
+                    distance_type: params.distance_type,
+                    loss,
+                };
+            }
+        }
+
+        Ok(best_kmeans)
+    }
+
     /// Train a [`KMeans`] model with full parameters.
     ///
     /// If the DistanceType is `Cosine`, the input vectors will be normalized with each iteration.
@@ -606,6 +769,28 @@ impl KMeans {
             ));
         }
 
+        // Mini-batches only pay off if they are smaller than the data
+        if let Some(batch_size) = params.batch_size.filter(|&b| b > 0 && b < n) {
+            match data.value_type() {
+                DataType::Float16 => {
+                    return Self::train_mini_batch_kmeans::<Float16Type>(
+                        data, k, batch_size, params,
+                    )
+                }
+                DataType::Float32 => {
+                    return Self::train_mini_batch_kmeans::<Float32Type>(
+                        data, k, batch_size, params,
+                    )
+                }
+                DataType::Float64 => {
+                    return Self::train_mini_batch_kmeans::<Float64Type>(
+                        data, k, batch_size, params,
+                    )
+                }
+                _ => {}
+            }
+        }
+
         match (data.value_type(), params.distance_type) {
             (DataType::Float16, _) => {
                 Self::train_kmeans::<Float16Type, KMeansAlgoFloat<Float16Type>>(data, k, params)
@@ -961,4 +1146,45 @@ mod tests {
         assert_eq!(kmeans.dimension, DIM);
         assert_eq!(kmeans.centroids.data_type(), &DataType::UInt8);
     }
+
+    #[test]
+    fn test_train_mini_batch() {
+        const DIM: usize = 8;
+        const K: usize = 16;
+        let values = lance_testing::datagen::generate_random_array_with_seed::<Float32Type>(
+            DIM * K * 512,
+            [7; 32],
+        );
+        let fsl = FixedSizeListArray::try_new_from_values(values, DIM as i32).unwrap();
+
+        let full_params = KMeansParams::default().with_seed(42);
+        let full = KMeans::new_with_params(&fsl, K, &full_params).unwrap();
+
+        let params = KMeansParams {
+            max_iters: 200,
+            ..Default::default()
+        }
+        .with_seed(42)
+        .with_batch_size(256);
+        let mini_batch = KMeans::new_with_params(&fsl, K, &params).unwrap();
+        assert_eq!(mini_batch.centroids.len(), K * DIM);
+        assert!(
+            mini_batch.loss < full.loss * 1.2,
+            "mini-batch loss {} vs full loss {}",
+            mini_batch.loss,
+            full.loss
+        );
+
+        // The same seed trains the same model
+        let again = KMeans::new_with_params(&fsl, K, &params).unwrap();
+        assert_eq!(
+            mini_batch.centroids.as_primitive::<Float32Type>(),
+            again.centroids.as_primitive::<Float32Type>()
+        );
+        let again = KMeans::new_with_params(&fsl, K, &full_params).unwrap();
+        assert_eq!(
+            full.centroids.as_primitive::<Float32Type>(),
+            again.centroids.as_primitive::<Float32Type>()
+        );
+    }
 }
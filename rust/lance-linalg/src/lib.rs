@@ -12,6 +12,7 @@ mod clustering;
 pub mod distance;
 pub mod kernels;
 pub mod kmeans;
+pub mod preprocess;
 pub mod simd;
 
 #[cfg(test)]
@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Preprocessing of embeddings: L2 normalization, mean-centering and slicing of dimensions.
+//!
+//! The kernels work on the flat, row-major values of a batch of vectors, in place where
+//! possible.  The norms are computed by the same SIMD kernels as the distances, and the
+//! element-wise loops work on fixed size lanes so they are vectorized by the compiler.
+//!
+//! The `*_fsl` functions apply the kernels to a [`FixedSizeListArray`] of float vectors.
+//! Null vectors are kept null, and do not count towards the mean.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::{
+    cast::AsArray,
+    types::{Float16Type, Float32Type, Float64Type},
+    Array, ArrowPrimitiveType, FixedSizeListArray, PrimitiveArray,
+};
+use arrow_schema::{ArrowError, DataType, Field};
+use half::f16;
+use num_traits::{AsPrimitive, Float};
+
+use crate::distance::norm_l2::{norm_l2, Normalize};
+use crate::distance::vector_validity;
+use crate::kernels::normalize_fsl;
+use crate::Result;
+
+const LANES: usize = 16;
+
+/// A float type that the preprocessing kernels support.
+pub trait Preprocess: Normalize + Float + AsPrimitive<f64> {}
+
+impl Preprocess for f16 {}
+impl Preprocess for f32 {}
+impl Preprocess for f64 {}
+
+#[inline]
+fn scale<T: Float>(vector: &mut [T], factor: T) {
+    let mut chunks = vector.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for x in chunk.iter_mut() {
+            *x = *x * factor;
+        }
+    }
+    for x in chunks.into_remainder() {
+        *x = *x * factor;
+    }
+}
+
+#[inline]
+fn subtract<T: Float>(vector: &mut [T], other: &[T]) {
+    let mut chunks = vector.chunks_exact_mut(LANES);
+    let mut other_chunks = other.chunks_exact(LANES);
+    for (chunk, other) in (&mut chunks).zip(&mut other_chunks) {
+        for i in 0..LANES {
+            chunk[i] = chunk[i] - other[i];
+        }
+    }
+    for (x, &y) in chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(other_chunks.remainder())
+    {
+        *x = *x - y;
+    }
+}
+
+/// L2 normalize each vector of `vectors` in place.
+///
+/// `vectors` are the row-major values of vectors of `dimension` elements.  Vectors with a
+/// norm of zero are left as they are, instead of becoming `NaN`.
+pub fn l2_normalize<T: Preprocess>(vectors: &mut [T], dimension: usize) {
+    debug_assert_eq!(vectors.len() % dimension, 0);
+    for vector in vectors.chunks_exact_mut(dimension) {
+        let norm = norm_l2(vector);
+        if norm > 0.0 {
+            scale(vector, T::one() / T::from(norm).unwrap());
+        }
+    }
+}
+
+fn mean_of<'a, T: Preprocess + 'a>(
+    vectors: impl Iterator<Item = &'a [T]>,
+    dimension: usize,
+) -> Vec<T> {
+    // Accumulate in f64, so the mean of many vectors does not lose precision
+    let mut sums = vec![0.0_f64; dimension];
+    let mut count = 0_usize;
+    for vector in vectors {
+        for (sum, &x) in sums.iter_mut().zip(vector) {
+            *sum += x.as_();
+        }
+        count += 1;
+    }
+    sums.into_iter()
+        .map(|sum| T::from(sum / count.max(1) as f64).unwrap())
+        .collect()
+}
+
+/// The mean vector of `vectors`, the row-major values of vectors of `dimension` elements.
+///
+/// The mean of no vectors is the zero vector.
+pub fn mean<T: Preprocess>(vectors: &[T], dimension: usize) -> Vec<T> {
+    debug_assert_eq!(vectors.len() % dimension, 0);
+    mean_of(vectors.chunks_exact(dimension), dimension)
+}
+
+/// Subtract `mean` from each vector of `vectors` in place.
+///
+/// `vectors` are the row-major values of vectors of the dimension of `mean`.
+pub fn center<T: Preprocess>(vectors: &mut [T], mean: &[T]) {
+    debug_assert_eq!(vectors.len() % mean.len(), 0);
+    for vector in vectors.chunks_exact_mut(mean.len()) {
+        subtract(vector, mean);
+    }
+}
+
+/// Keep the dimensions `range` of each vector of `vectors`.
+///
+/// This is how Matryoshka embeddings are shortened; they usually need to be normalized
+/// again afterwards.
+///
+/// # Panics
+///
+/// If `range` is not within `0..dimension`.
+pub fn slice_dimensions<T: Copy>(vectors: &[T], dimension: usize, range: Range<usize>) -> Vec<T> {
+    debug_assert_eq!(vectors.len() % dimension, 0);
+    assert!(
+        range.start <= range.end && range.end <= dimension,
+        "dimensions {:?} are out of the vector dimension {}",
+        range,
+        dimension
+    );
+    let mut sliced = Vec::with_capacity(vectors.len() / dimension * range.len());
+    for vector in vectors.chunks_exact(dimension) {
+        sliced.extend_from_slice(&vector[range.clone()]);
+    }
+    sliced
+}
+
+fn unsupported_type(fsl: &FixedSizeListArray) -> ArrowError {
+    ArrowError::InvalidArgumentError(format!(
+        "Preprocessing only supports float vectors, got: {}",
+        fsl.value_type()
+    ))
+}
+
+fn with_values<T: ArrowPrimitiveType>(
+    fsl: &FixedSizeListArray,
+    values: Vec<T::Native>,
+    dimension: usize,
+) -> Result<FixedSizeListArray> {
+    FixedSizeListArray::try_new(
+        Arc::new(Field::new("item", T::DATA_TYPE, true)),
+        dimension as i32,
+        Arc::new(PrimitiveArray::<T>::from_iter_values(values)),
+        fsl.nulls().cloned(),
+    )
+}
+
+/// The row-major values of the vectors of `fsl`.
+fn values_of<T: ArrowPrimitiveType>(fsl: &FixedSizeListArray) -> &[T::Native] {
+    let dim = fsl.value_length() as usize;
+    &fsl.values().as_primitive::<T>().values()[..fsl.len() * dim]
+}
+
+/// L2 normalize each vector of a [`FixedSizeListArray`] with [`normalize_fsl`].
+///
+/// Null vectors are kept null.  Unlike [`l2_normalize`], a zero vector has no direction, so
+/// its values become `NaN`.
+pub fn l2_normalize_fsl(fsl: &FixedSizeListArray) -> Result<FixedSizeListArray> {
+    let (field, dimension, values, _) = normalize_fsl(fsl)?.into_parts();
+    FixedSizeListArray::try_new(field, dimension, values, fsl.nulls().cloned())
+}
+
+fn do_mean_fsl<T: ArrowPrimitiveType>(fsl: &FixedSizeListArray) -> Vec<T::Native>
+where
+    T::Native: Preprocess,
+{
+    let dim = fsl.value_length() as usize;
+    let validity = vector_validity(fsl);
+    mean_of(
+        values_of::<T>(fsl)
+            .chunks_exact(dim)
+            .enumerate()
+            .filter(|(i, _)| validity.as_ref().is_none_or(|v| v.is_valid(*i)))
+            .map(|(_, vector)| vector),
+        dim,
+    )
+}
+
+/// The mean vector of a [`FixedSizeListArray`], as an array of its value type.
+///
+/// Null vectors, and vectors with null elements, are skipped.
+pub fn mean_fsl(fsl: &FixedSizeListArray) -> Result<Arc<dyn Array>> {
+    Ok(match fsl.value_type() {
+        DataType::Float16 => Arc::new(PrimitiveArray::<Float16Type>::from_iter_values(
+            do_mean_fsl::<Float16Type>(fsl),
+        )),
+        DataType::Float32 => Arc::new(PrimitiveArray::<Float32Type>::from_iter_values(
+            do_mean_fsl::<Float32Type>(fsl),
+        )),
+        DataType::Float64 => Arc::new(PrimitiveArray::<Float64Type>::from_iter_values(
+            do_mean_fsl::<Float64Type>(fsl),
+        )),
+        _ => return Err(unsupported_type(fsl)),
+    })
+}
+
+fn do_center_fsl<T: ArrowPrimitiveType>(
+    fsl: &FixedSizeListArray,
+    mean: &dyn Array,
+) -> Result<FixedSizeListArray>
+where
+    T::Native: Preprocess,
+{
+    let dim = fsl.value_length() as usize;
+    let mean = mean
+        .as_primitive_opt::<T>()
+        .filter(|mean| mean.len() == dim && mean.null_count() == 0)
+        .ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!(
+                "The mean must be {} non-null values of {}, got {} values of {}",
+                dim,
+                T::DATA_TYPE,
+                mean.len(),
+                mean.data_type()
+            ))
+        })?;
+    let mut values = values_of::<T>(fsl).to_vec();
+    center(&mut values, mean.values());
+    with_values::<T>(fsl, values, dim)
+}
+
+/// Subtract `mean` from each vector of a [`FixedSizeListArray`].
+///
+/// `mean` must have the value type and the dimension of the vectors, e.g. the result of
+/// [`mean_fsl`] over a sample of the data.
+pub fn center_fsl(fsl: &FixedSizeListArray, mean: &dyn Array) -> Result<FixedSizeListArray> {
+    match fsl.value_type() {
+        DataType::Float16 => do_center_fsl::<Float16Type>(fsl, mean),
+        DataType::Float32 => do_center_fsl::<Float32Type>(fsl, mean),
+        DataType::Float64 => do_center_fsl::<Float64Type>(fsl, mean),
+        _ => Err(unsupported_type(fsl)),
+    }
+}
+
+fn do_slice_dimensions_fsl<T: ArrowPrimitiveType>(
+    fsl: &FixedSizeListArray,
+    range: Range<usize>,
+) -> Result<FixedSizeListArray> {
+    let dim = fsl.value_length() as usize;
+    let values = slice_dimensions(values_of::<T>(fsl), dim, range.clone());
+    with_values::<T>(fsl, values, range.len())
+}
+
+/// Keep the dimensions `range` of each vector of a [`FixedSizeListArray`], see
+/// [`slice_dimensions`].
+pub fn slice_dimensions_fsl(
+    fsl: &FixedSizeListArray,
+    range: Range<usize>,
+) -> Result<FixedSizeListArray> {
+    let dim = fsl.value_length() as usize;
+    if range.is_empty() || range.end > dim {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Dimensions {:?} are empty or out of the vector dimension {}",
+            range, dim
+        )));
+    }
+    match fsl.value_type() {
+        DataType::Float16 => do_slice_dimensions_fsl::<Float16Type>(fsl, range),
+        DataType::Float32 => do_slice_dimensions_fsl::<Float32Type>(fsl, range),
+        DataType::Float64 => do_slice_dimensions_fsl::<Float64Type>(fsl, range),
+        _ => Err(unsupported_type(fsl)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::assert_relative_eq;
+    use arrow_array::{Float32Array, Float64Array};
+
+    #[test]
+    fn test_l2_normalize() {
+        let mut vectors = (0..100).map(|v| v as f32).collect::<Vec<_>>();
+        vectors[..20].fill(0.0);
+        l2_normalize(&mut vectors, 20);
+        // The zero vector is left as it is
+        assert!(vectors[..20].iter().all(|&v| v == 0.0));
+        for vector in vectors[20..].chunks_exact(20) {
+            assert_relative_eq!(norm_l2(vector), 1.0, epsilon = 1e-6);
+        }
+
+        let fsl = FixedSizeListArray::from_iter_primitive::<Float16Type, _, _>(
+            vec![
+                Some(vec![Some(f16::from_f32(3.0)), Some(f16::from_f32(4.0))]),
+                None,
+            ],
+            2,
+        );
+        let normalized = l2_normalize_fsl(&fsl).unwrap();
+        assert_eq!(normalized.null_count(), 1);
+        let values = normalized.values().as_primitive::<Float16Type>();
+        assert_relative_eq!(values.value(0).to_f32(), 0.6, epsilon = 1e-3);
+        assert_relative_eq!(values.value(1).to_f32(), 0.8, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_mean_and_center() {
+        let vectors = vec![1.0_f64, 10.0, 3.0, 20.0, 5.0, 30.0];
+        let mean = mean(&vectors, 2);
+        assert_eq!(mean, vec![3.0, 20.0]);
+        let mut centered = vectors.clone();
+        center(&mut centered, &mean);
+        assert_eq!(centered, vec![-2.0, -10.0, 0.0, 0.0, 2.0, 10.0]);
+
+        let fsl = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            vec![
+                Some(vec![Some(1.0), Some(2.0)]),
+                None,
+                Some(vec![Some(3.0), None]),
+                Some(vec![Some(3.0), Some(4.0)]),
+            ],
+            2,
+        );
+        let mean = mean_fsl(&fsl).unwrap();
+        assert_eq!(mean.as_primitive::<Float32Type>().values(), &[2.0, 3.0]);
+        let centered = center_fsl(&fsl, &mean).unwrap();
+        assert_eq!(centered.nulls(), fsl.nulls());
+        assert_eq!(
+            centered.value(3).as_primitive::<Float32Type>().values(),
+            &[1.0, 1.0]
+        );
+
+        // The mean must match the vectors
+        assert!(center_fsl(&fsl, &Float32Array::from(vec![1.0])).is_err());
+        assert!(center_fsl(&fsl, &Float64Array::from(vec![1.0, 2.0])).is_err());
+    }
+
+    #[test]
+    fn test_slice_dimensions() {
+        let vectors = (0..12).collect::<Vec<i32>>();
+        assert_eq!(slice_dimensions(&vectors, 4, 1..3), vec![1, 2, 5, 6, 9, 10]);
+
+        let fsl = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            (0..3).map(|i| Some((0..4).map(move |j| Some((i * 4 + j) as f32)))),
+            4,
+        );
+        let sliced = slice_dimensions_fsl(&fsl.slice(1, 2), 0..2).unwrap();
+        assert_eq!(sliced.value_length(), 2);
+        assert_eq!(
+            sliced.values().as_primitive::<Float32Type>().values(),
+            &[4.0, 5.0, 8.0, 9.0]
+        );
+        assert!(slice_dimensions_fsl(&fsl, 2..5).is_err());
+    }
+}
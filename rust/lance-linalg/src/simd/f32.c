@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+// f32 distance kernels for targets whose SIMD width is only known at runtime,
+// such as SVE. Other targets use the Rust implementations, which the compiler
+// vectorizes for the target at build time.
+
+#include <stddef.h>
+#include <stdint.h>
+#include <math.h>
+
+// Because we might be compiling this library multiple times, we need to
+// add a suffix to each of the function names.
+#define FUNC_CAT_INNER(A, B) A##B
+#define FUNC_CAT(A, B) FUNC_CAT_INNER(A, B)
+#define FUNC(N) FUNC_CAT(N, SUFFIX)
+
+float FUNC(dot_f32)(const float *x, const float *y, uint32_t dimension) {
+  float sum = 0;
+
+#pragma clang loop unroll(enable) interleave(enable) vectorize(enable)
+  for (uint32_t i = 0; i < dimension; i++) {
+    sum += x[i] * y[i];
+  }
+  return sum;
+}
+
+float FUNC(l2_f32)(const float *x, const float *y, uint32_t dimension) {
+  float sum = 0.0;
+
+#pragma clang loop unroll(enable) interleave(enable) vectorize(enable)
+  for (uint32_t i = 0; i < dimension; i++) {
+    float s = x[i] - y[i];
+    sum += s * s;
+  }
+  return sum;
+}
+
+float FUNC(cosine_f32)(const float *x, float x_norm, const float *y, uint32_t dimension) {
+  float dot = 0.0;
+  float l2_y = 0.0;
+
+#pragma clang loop unroll(enable) interleave(enable) vectorize(enable)
+  for (uint32_t i = 0; i < dimension; i++) {
+    dot += x[i] * y[i];
+    l2_y += y[i] * y[i];
+  }
+
+  return 1.0 - dot / (x_norm * sqrtf(l2_y));
+}
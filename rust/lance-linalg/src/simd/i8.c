@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+// int8 dot product using AVX-512 VNNI.
+
+#include <stddef.h>
+#include <stdint.h>
+#include <immintrin.h>
+
+// Because we might be compiling this library multiple times, we need to
+// add a suffix to each of the function names.
+#define FUNC_CAT_INNER(A, B) A##B
+#define FUNC_CAT(A, B) FUNC_CAT_INNER(A, B)
+#define FUNC(N) FUNC_CAT(N, SUFFIX)
+
+/// @brief Dot product of two int8 vectors.
+///
+/// VPDPBUSD multiplies unsigned bytes by signed bytes, so x is offset by 128
+/// to make it unsigned: (x + 128) * y = x * y + 128 * y.  The 128 * y term is
+/// accumulated separately, also with VPDPBUSD, and subtracted at the end.
+///
+/// @param x A int8 vector
+/// @param y A int8 vector
+/// @param dimension The dimension of the vectors
+/// @return The dot product of the two vectors.
+int32_t FUNC(dot_i8)(const int8_t *x, const int8_t *y, uint32_t dimension) {
+  const __m512i offset = _mm512_set1_epi8((char) 0x80);
+  __m512i sum = _mm512_setzero_si512();
+  __m512i correction = _mm512_setzero_si512();
+
+  uint32_t i = 0;
+  for (; i + 64 <= dimension; i += 64) {
+    __m512i x_i = _mm512_loadu_si512((const void *) (x + i));
+    __m512i y_i = _mm512_loadu_si512((const void *) (y + i));
+    sum = _mm512_dpbusd_epi32(sum, _mm512_xor_si512(x_i, offset), y_i);
+    correction = _mm512_dpbusd_epi32(correction, offset, y_i);
+  }
+
+  if (i < dimension) {
+    // The masked out lanes of y are zero, so they add nothing to either sum
+    __mmask64 mask = _bzhi_u64(~0ULL, dimension - i);
+    __m512i x_i = _mm512_maskz_loadu_epi8(mask, (const void *) (x + i));
+    __m512i y_i = _mm512_maskz_loadu_epi8(mask, (const void *) (y + i));
+    sum = _mm512_dpbusd_epi32(sum, _mm512_xor_si512(x_i, offset), y_i);
+    correction = _mm512_dpbusd_epi32(correction, offset, y_i);
+  }
+
+  return _mm512_reduce_add_epi32(_mm512_sub_epi32(sum, correction));
+}
@@ -92,6 +92,9 @@ pub struct Manifest {
 
     /// Blob dataset version
     pub blob_dataset_version: Option<u64>,
+
+    /// Properties of the transaction that created this version.
+    pub transaction_properties: HashMap<String, String>,
 }
 
 // We use the most significant bit to indicate that a transaction is detached
@@ -143,6 +146,7 @@ impl Manifest {
             data_storage_format,
             config: HashMap::new(),
             blob_dataset_version,
+            transaction_properties: HashMap::new(),
         }
     }
 
@@ -176,6 +180,7 @@ impl Manifest {
             data_storage_format: previous.data_storage_format.clone(),
             config: previous.config.clone(),
             blob_dataset_version,
+            transaction_properties: HashMap::new(),
         }
     }
 
@@ -556,6 +561,7 @@ impl TryFrom<pb::Manifest> for Manifest {
             } else {
                 Some(p.blob_dataset_version)
             },
+            transaction_properties: p.transaction_properties,
         })
     }
 }
@@ -600,6 +606,7 @@ impl From<&Manifest> for pb::Manifest {
             }),
             config: m.config.clone(),
             blob_dataset_version: m.blob_dataset_version.unwrap_or_default(),
+            transaction_properties: m.transaction_properties.clone(),
         }
     }
 }
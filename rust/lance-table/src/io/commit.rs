@@ -15,8 +15,11 @@
 //! renames the temporary path to the final path if no object already exists
 //! at the final path. This is an atomic operation in most object stores, but
 //! not in AWS S3. So for AWS S3, the default commit handler is
-//! [UnsafeCommitHandler], which writes the manifest to the final path without
-//! any checks.
+//! [ConditionalPutCommitHandler], which writes the manifest with a conditional
+//! PUT. For S3 compatible stores without conditional PUT, commits go through
+//! DynamoDB with the `s3+ddb://` scheme, or, if the `allow_unsafe_commit`
+//! storage option is set, through [UnsafeCommitHandler], which writes the
+//! manifest to the final path without any checks.
 //!
 //! When providing your own commit handler, most often you are implementing in
 //! terms of a lock. The trait [CommitLock] can be implemented as a simpler
@@ -45,22 +48,26 @@ use url::Url;
 #[cfg(feature = "dynamodb")]
 pub mod dynamodb;
 pub mod external_manifest;
+pub mod queue;
 
 use lance_core::{Error, Result};
-use lance_io::object_store::{ObjectStore, ObjectStoreExt, ObjectStoreParams};
+use lance_io::object_store::{ObjectStore, ObjectStoreExt, ObjectStoreParams, StorageOptions};
 
 #[cfg(feature = "dynamodb")]
 use {
-    self::external_manifest::{ExternalManifestCommitHandler, ExternalManifestStore},
+    self::external_manifest::{
+        ExternalManifestCommitHandler, ExternalManifestStore, ReadOnlyExternalManifestStore,
+    },
     aws_credential_types::provider::error::CredentialsError,
     aws_credential_types::provider::ProvideCredentials,
-    lance_io::object_store::{providers::aws::build_aws_credential, StorageOptions},
+    lance_io::object_store::providers::aws::build_aws_credential,
     object_store::aws::AmazonS3ConfigKey,
     object_store::aws::AwsCredentialProvider,
     std::borrow::Cow,
     std::time::{Duration, SystemTime},
 };
 
+use self::queue::CommitQueue;
 use crate::format::{is_detached_version, Index, Manifest};
 
 const VERSIONS_DIR: &str = "_versions";
@@ -558,6 +565,14 @@ pub trait CommitHandler: Debug + Send + Sync {
     async fn delete(&self, _base_path: &Path) -> Result<()> {
         Ok(())
     }
+
+    /// The queue writers must wait in before entering the commit loop.
+    ///
+    /// By default there is no queue and concurrent writers resolve conflicts
+    /// by retrying with backoff. See [queue::QueuedCommitHandler].
+    fn commit_queue(&self) -> Option<&CommitQueue> {
+        None
+    }
 }
 
 async fn default_resolve_version(
@@ -670,8 +685,7 @@ async fn build_dynamodb_external_store(
 
 pub async fn commit_handler_from_url(
     url_or_path: &str,
-    // This looks unused if dynamodb feature disabled
-    #[allow(unused_variables)] options: &Option<ObjectStoreParams>,
+    options: &Option<ObjectStoreParams>,
 ) -> Result<Arc<dyn CommitHandler>> {
     let local_handler: Arc<dyn CommitHandler> = if cfg!(windows) {
         Arc::new(RenameCommitHandler)
@@ -692,7 +706,31 @@ pub async fn commit_handler_from_url(
 
     match url.scheme() {
         "file" | "file-object-store" => Ok(local_handler),
-        "s3" | "gs" | "az" | "memory" => Ok(Arc::new(ConditionalPutCommitHandler)),
+        "s3" => {
+            let storage_options = StorageOptions(
+                options
+                    .as_ref()
+                    .and_then(|options| options.storage_options.clone())
+                    .unwrap_or_default(),
+            );
+            if storage_options.conditional_put() {
+                Ok(Arc::new(ConditionalPutCommitHandler))
+            } else if storage_options.allow_unsafe_commit() {
+                // Without conditional PUT or an external store there is no
+                // way to detect concurrent commits.
+                Ok(Arc::new(UnsafeCommitHandler))
+            } else {
+                Err(Error::InvalidInput {
+                    source: "conditional PUT is disabled, so concurrent commits to `s3://` \
+                             would overwrite each other. Use the `s3+ddb://` scheme to commit \
+                             through DynamoDB, or set the `allow_unsafe_commit` storage option \
+                             if there is only ever a single writer"
+                        .into(),
+                    location: location!(),
+                })
+            }
+        }
+        "gs" | "az" | "memory" => Ok(Arc::new(ConditionalPutCommitHandler)),
         #[cfg(not(feature = "dynamodb"))]
         "s3+ddb" => Err(Error::InvalidInput {
             source: "`s3+ddb://` scheme requires `dynamodb` feature to be enabled".into(),
@@ -745,15 +783,21 @@ pub async fn commit_handler_from_url(
             )
             .await?;
 
+            let mut external_manifest_store = build_dynamodb_external_store(
+                table_name,
+                aws_creds.clone(),
+                &region,
+                dynamo_endpoint,
+                "lancedb",
+            )
+            .await?;
+            if options.read_only {
+                external_manifest_store =
+                    Arc::new(ReadOnlyExternalManifestStore::new(external_manifest_store));
+            }
+
             Ok(Arc::new(ExternalManifestCommitHandler {
-                external_manifest_store: build_dynamodb_external_store(
-                    table_name,
-                    aws_creds.clone(),
-                    &region,
-                    dynamo_endpoint,
-                    "lancedb",
-                )
-                .await?,
+                external_manifest_store,
             }))
         }
         _ => Ok(Arc::new(UnsafeCommitHandler)),
@@ -1040,6 +1084,20 @@ impl CommitHandler for ConditionalPutCommitHandler {
                 ObjectStoreError::AlreadyExists { .. } | ObjectStoreError::Precondition { .. } => {
                     CommitError::CommitConflict
                 }
+                ObjectStoreError::NotImplemented | ObjectStoreError::NotSupported { .. } => {
+                    CommitError::OtherError(Error::NotSupported {
+                        source: format!(
+                            "object store does not support conditional PUT ({}). Use the \
+                             `s3+ddb://` scheme to commit through DynamoDB, or set the \
+                             `aws_conditional_put` storage option to `disabled` and the \
+                             `allow_unsafe_commit` storage option if there is only ever a \
+                             single writer",
+                            err
+                        )
+                        .into(),
+                        location: location!(),
+                    })
+                }
                 _ => CommitError::OtherError(err.into()),
             })?;
 
@@ -1073,6 +1131,8 @@ impl Default for CommitConfig {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
 
     #[test]
@@ -1120,6 +1180,36 @@ mod tests {
         assert_eq!(ManifestNamingScheme::detect_scheme("something else"), None);
     }
 
+    #[tokio::test]
+    async fn test_s3_commit_handler_selection() {
+        let handler = commit_handler_from_url("s3://bucket/table", &None)
+            .await
+            .unwrap();
+        assert_eq!(format!("{:?}", handler), "ConditionalPutCommitHandler");
+
+        // Disabling conditional PUT requires opting in to unsafe commits
+        let mut storage_options =
+            HashMap::from([("aws_conditional_put".to_string(), "disabled".to_string())]);
+        let params = ObjectStoreParams {
+            storage_options: Some(storage_options.clone()),
+            ..Default::default()
+        };
+        let err = commit_handler_from_url("s3://bucket/table", &Some(params))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("s3+ddb://"), "{}", err);
+
+        storage_options.insert("allow_unsafe_commit".to_string(), "true".to_string());
+        let params = ObjectStoreParams {
+            storage_options: Some(storage_options),
+            ..Default::default()
+        };
+        let handler = commit_handler_from_url("s3://bucket/table", &Some(params))
+            .await
+            .unwrap();
+        assert_eq!(format!("{:?}", handler), "UnsafeCommitHandler");
+    }
+
     #[tokio::test]
     async fn test_manifest_naming_migration() {
         let object_store = ObjectStore::memory();
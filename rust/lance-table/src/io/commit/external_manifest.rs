@@ -112,6 +112,81 @@ pub trait ExternalManifestStore: std::fmt::Debug + Send + Sync {
     }
 }
 
+/// Wraps an [`ExternalManifestStore`] for datasets opened read-only
+///
+/// Lookups are forwarded and every put or delete fails with
+/// [`Error::ReadOnly`], so loading a dataset never records manifests in the
+/// external store.
+#[derive(Debug)]
+pub struct ReadOnlyExternalManifestStore {
+    inner: Arc<dyn ExternalManifestStore>,
+}
+
+impl ReadOnlyExternalManifestStore {
+    pub fn new(inner: Arc<dyn ExternalManifestStore>) -> Self {
+        Self { inner }
+    }
+
+    fn read_only_error(base_uri: &str) -> Error {
+        Error::ReadOnly {
+            message: format!("cannot update the external manifest store for {}", base_uri),
+            location: location!(),
+        }
+    }
+}
+
+#[async_trait]
+impl ExternalManifestStore for ReadOnlyExternalManifestStore {
+    async fn get(&self, base_uri: &str, version: u64) -> Result<String> {
+        self.inner.get(base_uri, version).await
+    }
+
+    async fn get_manifest_location(
+        &self,
+        base_uri: &str,
+        version: u64,
+    ) -> Result<ManifestLocation> {
+        self.inner.get_manifest_location(base_uri, version).await
+    }
+
+    async fn get_latest_version(&self, base_uri: &str) -> Result<Option<(u64, String)>> {
+        self.inner.get_latest_version(base_uri).await
+    }
+
+    async fn get_latest_manifest_location(
+        &self,
+        base_uri: &str,
+    ) -> Result<Option<ManifestLocation>> {
+        self.inner.get_latest_manifest_location(base_uri).await
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        base_uri: &str,
+        _version: u64,
+        _path: &str,
+        _size: u64,
+        _e_tag: Option<String>,
+    ) -> Result<()> {
+        Err(Self::read_only_error(base_uri))
+    }
+
+    async fn put_if_exists(
+        &self,
+        base_uri: &str,
+        _version: u64,
+        _path: &str,
+        _size: u64,
+        _e_tag: Option<String>,
+    ) -> Result<()> {
+        Err(Self::read_only_error(base_uri))
+    }
+
+    async fn delete(&self, base_uri: &str) -> Result<()> {
+        Err(Self::read_only_error(base_uri))
+    }
+}
+
 pub(crate) fn detect_naming_scheme_from_path(path: &Path) -> Result<ManifestNamingScheme> {
     path.filename()
         .and_then(|name| {
@@ -165,6 +240,17 @@ impl ExternalManifestCommitHandler {
         {
             Ok(_) => true,
             Err(ObjectStoreError::NotFound { .. }) => false, // Another writer beat us to it.
+            // A reader without write access (or a read-only store) leaves this
+            // to the next writer. The staging manifest is complete, so read it.
+            Err(ObjectStoreError::PermissionDenied { .. }) => {
+                return Ok(ManifestLocation {
+                    version,
+                    path: staging_manifest_path.clone(),
+                    size: Some(size),
+                    naming_scheme,
+                    e_tag,
+                });
+            }
             Err(e) => return Err(e.into()),
         };
 
@@ -305,11 +391,12 @@ impl CommitHandler for ExternalManifestCommitHandler {
                                 e_tag.clone(),
                             )
                             .await;
-                        if let Err(e) = res {
-                            warn!(
+                        match res {
+                            Ok(()) | Err(Error::ReadOnly { .. }) => {}
+                            Err(e) => warn!(
                                 "could not update external manifest store during load, with error: {}",
                                 e
-                            );
+                            ),
                         }
                         let naming_scheme =
                             ManifestNamingScheme::detect_scheme_staging(path.filename().unwrap());
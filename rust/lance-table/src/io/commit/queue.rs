@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Fair queueing of concurrent writers.
+//!
+//! Without coordination, concurrent writers race to write the next manifest
+//! and the losers retry with backoff. Under heavy contention some writers can
+//! starve and eventually fail with `TooMuchWriteContention`. A [CommitQueue]
+//! hands out leases in first-come, first-served order so that only one writer
+//! is inside the commit loop at a time. Waiting is bounded by a configurable
+//! timeout.
+//!
+//! The queue is attached to a dataset by wrapping its commit handler in a
+//! [QueuedCommitHandler]. All writers that share that handler are serialized.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::stream::BoxStream;
+use lance_core::{Error, Result};
+use lance_io::object_store::ObjectStore;
+use object_store::{path::Path, ObjectStore as OSObjectStore};
+use snafu::location;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::{CommitError, CommitHandler, ManifestLocation, ManifestNamingScheme, ManifestWriter};
+use crate::format::{Index, Manifest};
+
+/// A point-in-time snapshot of the queue counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitQueueMetrics {
+    /// Number of writers currently waiting for a lease.
+    pub num_waiting: usize,
+    /// Whether a lease is currently held.
+    pub is_held: bool,
+    /// Total number of leases granted.
+    pub num_granted: u64,
+    /// Total number of writers that gave up after waiting for `max_wait`.
+    pub num_timed_out: u64,
+    /// Sum of the time every granted writer spent waiting.
+    pub total_wait: Duration,
+    /// The longest time a granted writer spent waiting.
+    pub max_wait: Duration,
+}
+
+#[derive(Debug, Default)]
+struct QueueCounters {
+    num_waiting: AtomicUsize,
+    num_granted: AtomicU64,
+    num_timed_out: AtomicU64,
+    total_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+}
+
+/// A FIFO queue that serializes writers of a single dataset.
+///
+/// Leases are granted in the order they were requested. A writer that cannot
+/// obtain a lease within `max_wait` receives a
+/// [`Error::TooMuchWriteContention`] error instead of waiting forever.
+#[derive(Debug)]
+pub struct CommitQueue {
+    // A single-permit semaphore. Tokio semaphores are fair, so waiters are
+    // served in FIFO order.
+    semaphore: Arc<Semaphore>,
+    max_wait: Option<Duration>,
+    counters: Arc<QueueCounters>,
+}
+
+impl CommitQueue {
+    /// Create a new queue. If `max_wait` is `None`, writers wait indefinitely.
+    pub fn new(max_wait: Option<Duration>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(1)),
+            max_wait,
+            counters: Arc::default(),
+        }
+    }
+
+    /// The maximum time a writer waits for a lease.
+    pub fn max_wait(&self) -> Option<Duration> {
+        self.max_wait
+    }
+
+    /// Wait for this writer's turn to commit.
+    ///
+    /// The returned lease must be held for the duration of the commit. It is
+    /// released when dropped.
+    pub async fn acquire(&self) -> Result<CommitQueueLease> {
+        let start = Instant::now();
+        self.counters.num_waiting.fetch_add(1, Ordering::Relaxed);
+        let acquire = self.semaphore.clone().acquire_owned();
+        let permit = match self.max_wait {
+            Some(max_wait) => tokio::time::timeout(max_wait, acquire).await.ok(),
+            None => Some(acquire.await),
+        };
+        self.counters.num_waiting.fetch_sub(1, Ordering::Relaxed);
+
+        let Some(permit) = permit else {
+            self.counters.num_timed_out.fetch_add(1, Ordering::Relaxed);
+            return Err(Error::TooMuchWriteContention {
+                message: format!(
+                    "Timed out after {:?} waiting in the commit queue",
+                    start.elapsed()
+                ),
+                location: location!(),
+            });
+        };
+        // The semaphore is never closed.
+        let permit = permit.map_err(|err| Error::Internal {
+            message: format!("Commit queue was closed: {}", err),
+            location: location!(),
+        })?;
+
+        let waited = start.elapsed().as_micros() as u64;
+        self.counters.num_granted.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .total_wait_micros
+            .fetch_add(waited, Ordering::Relaxed);
+        self.counters
+            .max_wait_micros
+            .fetch_max(waited, Ordering::Relaxed);
+
+        Ok(CommitQueueLease { _permit: permit })
+    }
+
+    /// Snapshot the queue counters.
+    pub fn metrics(&self) -> CommitQueueMetrics {
+        CommitQueueMetrics {
+            num_waiting: self.counters.num_waiting.load(Ordering::Relaxed),
+            is_held: self.semaphore.available_permits() == 0,
+            num_granted: self.counters.num_granted.load(Ordering::Relaxed),
+            num_timed_out: self.counters.num_timed_out.load(Ordering::Relaxed),
+            total_wait: Duration::from_micros(
+                self.counters.total_wait_micros.load(Ordering::Relaxed),
+            ),
+            max_wait: Duration::from_micros(self.counters.max_wait_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl Default for CommitQueue {
+    fn default() -> Self {
+        Self::new(Some(Duration::from_secs(60)))
+    }
+}
+
+/// Exclusive right to run the commit loop. Released on drop.
+#[derive(Debug)]
+pub struct CommitQueueLease {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// A commit handler that serializes writers through a [CommitQueue].
+///
+/// All operations are delegated to the wrapped handler. The queue is exposed
+/// through [CommitHandler::commit_queue] so the commit loop can take a lease
+/// before it starts resolving conflicts.
+#[derive(Debug)]
+pub struct QueuedCommitHandler {
+    inner: Arc<dyn CommitHandler>,
+    queue: CommitQueue,
+}
+
+impl QueuedCommitHandler {
+    pub fn new(inner: Arc<dyn CommitHandler>, queue: CommitQueue) -> Self {
+        Self { inner, queue }
+    }
+
+    /// Snapshot the metrics of the underlying queue.
+    pub fn metrics(&self) -> CommitQueueMetrics {
+        self.queue.metrics()
+    }
+}
+
+#[async_trait::async_trait]
+impl CommitHandler for QueuedCommitHandler {
+    async fn resolve_latest_location(
+        &self,
+        base_path: &Path,
+        object_store: &ObjectStore,
+    ) -> Result<ManifestLocation> {
+        self.inner
+            .resolve_latest_location(base_path, object_store)
+            .await
+    }
+
+    async fn resolve_version_location(
+        &self,
+        base_path: &Path,
+        version: u64,
+        object_store: &dyn OSObjectStore,
+    ) -> Result<ManifestLocation> {
+        self.inner
+            .resolve_version_location(base_path, version, object_store)
+            .await
+    }
+
+    fn list_manifest_locations<'a>(
+        &self,
+        base_path: &Path,
+        object_store: &'a ObjectStore,
+        sorted_descending: bool,
+    ) -> BoxStream<'a, Result<ManifestLocation>> {
+        self.inner
+            .list_manifest_locations(base_path, object_store, sorted_descending)
+    }
+
+    fn commit_queue(&self) -> Option<&CommitQueue> {
+        Some(&self.queue)
+    }
+
+    async fn commit(
+        &self,
+        manifest: &mut Manifest,
+        indices: Option<Vec<Index>>,
+        base_path: &Path,
+        object_store: &ObjectStore,
+        manifest_writer: ManifestWriter,
+        naming_scheme: ManifestNamingScheme,
+    ) -> std::result::Result<ManifestLocation, CommitError> {
+        self.inner
+            .commit(
+                manifest,
+                indices,
+                base_path,
+                object_store,
+                manifest_writer,
+                naming_scheme,
+            )
+            .await
+    }
+
+    async fn delete(&self, base_path: &Path) -> Result<()> {
+        self.inner.delete(base_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_is_fifo() {
+        let queue = Arc::new(CommitQueue::new(None));
+        let lease = queue.acquire().await.unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        for i in 0..4 {
+            let writer_queue = queue.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _lease = writer_queue.acquire().await.unwrap();
+                order.lock().unwrap().push(i);
+            }));
+            // Make sure each writer is enqueued before the next one.
+            while queue.metrics().num_waiting < i + 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        assert_eq!(queue.metrics().num_waiting, 4);
+        assert!(queue.metrics().is_held);
+        drop(lease);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3]);
+        let metrics = queue.metrics();
+        assert_eq!(metrics.num_granted, 5);
+        assert_eq!(metrics.num_waiting, 0);
+        assert!(!metrics.is_held);
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout() {
+        let queue = CommitQueue::new(Some(Duration::from_millis(10)));
+        let _lease = queue.acquire().await.unwrap();
+
+        let err = queue.acquire().await.unwrap_err();
+        assert!(matches!(err, Error::TooMuchWriteContention { .. }));
+        let metrics = queue.metrics();
+        assert_eq!(metrics.num_timed_out, 1);
+        assert_eq!(metrics.num_granted, 1);
+    }
+}
@@ -16,7 +16,7 @@ use lance_encoding::decoder::{DecoderPlugins, FilterExpression};
 use lance_file::v2::reader::{FileReader, FileReaderOptions};
 use lance_file::v2::LanceEncodingsIo;
 use lance_file::version::LanceFileVersion;
-use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
+use lance_io::scheduler::{CoalescingConfig, IoPriorityClass, ScanScheduler, SchedulerConfig};
 use lance_io::utils::CachedFileSize;
 use lance_io::ReadBatchParams;
 use object_store::path::Path;
@@ -229,6 +229,10 @@ async fn create_file_reader(dataset: &Dataset, file_path: &Path) -> FileReader {
         dataset.object_store.clone(),
         SchedulerConfig {
             io_buffer_size_bytes: 2 * 1024 * 1024 * 1024,
+            priority_class: IoPriorityClass::Interactive,
+            uncached_reads: false,
+            coalescing: CoalescingConfig::default(),
+            max_iops_per_file: None,
         },
     );
     let file = scheduler
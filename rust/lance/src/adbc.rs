@@ -0,0 +1,567 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! An [ADBC](https://arrow.apache.org/adbc/) driver for Lance datasets.
+//!
+//! A database is configured with the URI of a dataset (the standard `uri`
+//! option). Each connection opens the latest version of the dataset and
+//! registers it as a DataFusion table, so statements are plain SQL:
+//!
+//! ```ignore
+//! let mut driver = LanceDriver::default();
+//! let database = driver.new_database_with_opts([(
+//!     OptionDatabase::Uri,
+//!     OptionValue::String("s3://bucket/items.lance".into()),
+//! )])?;
+//! let mut connection = database.new_connection()?;
+//! let mut statement = connection.new_statement()?;
+//! statement.set_sql_query("SELECT count(*) FROM items")?;
+//! let reader = statement.execute()?;
+//! ```
+//!
+//! The table is named after the last segment of the URI, without the `.lance`
+//! extension, unless the `lance.table_name` option is set. Options prefixed
+//! with `lance.storage.` are passed to the object store.
+//!
+//! The driver is read-only and runs in autocommit mode. ADBC is a blocking
+//! API, so the driver owns a Tokio runtime and must not be called from within
+//! an async context.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use adbc_core::error::{Error as AdbcError, Result as AdbcResult, Status as AdbcStatus};
+use adbc_core::options::{
+    InfoCode, ObjectDepth, OptionConnection, OptionDatabase, OptionStatement, OptionValue,
+};
+use adbc_core::{Connection, Database, Driver, Optionable, PartitionedResult, Statement};
+use arrow_array::{RecordBatch, RecordBatchReader, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::execution::context::SessionContext;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::StreamExt;
+use tokio::runtime::Runtime;
+
+use crate::datafusion::LanceTableProvider;
+use crate::dataset::builder::DatasetBuilder;
+use crate::Error;
+
+/// Database option overriding the name of the registered table.
+pub const TABLE_NAME_OPTION: &str = "lance.table_name";
+/// Prefix of database options that are passed to the object store.
+pub const STORAGE_OPTION_PREFIX: &str = "lance.storage.";
+
+fn not_implemented(what: &str) -> AdbcError {
+    AdbcError::with_message_and_status(
+        format!("{} is not supported by the Lance driver", what),
+        AdbcStatus::NotImplemented,
+    )
+}
+
+fn not_found(key: impl AsRef<str>) -> AdbcError {
+    AdbcError::with_message_and_status(
+        format!("Option {} is not set", key.as_ref()),
+        AdbcStatus::NotFound,
+    )
+}
+
+fn string_option(key: impl AsRef<str>, value: OptionValue) -> AdbcResult<String> {
+    match value {
+        OptionValue::String(value) => Ok(value),
+        _ => Err(AdbcError::with_message_and_status(
+            format!("Option {} must be a string", key.as_ref()),
+            AdbcStatus::InvalidArguments,
+        )),
+    }
+}
+
+fn to_adbc(err: Error) -> AdbcError {
+    let status = match &err {
+        Error::DatasetNotFound { .. } => AdbcStatus::NotFound,
+        Error::InvalidInput { .. } => AdbcStatus::InvalidArguments,
+        Error::NotSupported { .. } => AdbcStatus::NotImplemented,
+        _ => AdbcStatus::Internal,
+    };
+    AdbcError::with_message_and_status(err.to_string(), status)
+}
+
+fn df_to_adbc(err: datafusion::error::DataFusionError) -> AdbcError {
+    AdbcError::with_message_and_status(err.to_string(), AdbcStatus::InvalidArguments)
+}
+
+/// Entry point of the driver.
+pub struct LanceDriver {
+    runtime: Arc<Runtime>,
+}
+
+impl Default for LanceDriver {
+    fn default() -> Self {
+        Self {
+            runtime: Arc::new(Runtime::new().expect("Failed to create Tokio runtime")),
+        }
+    }
+}
+
+impl Driver for LanceDriver {
+    type DatabaseType = LanceDatabase;
+
+    fn new_database(&mut self) -> AdbcResult<Self::DatabaseType> {
+        self.new_database_with_opts([])
+    }
+
+    fn new_database_with_opts(
+        &mut self,
+        opts: impl IntoIterator<Item = (OptionDatabase, OptionValue)>,
+    ) -> AdbcResult<Self::DatabaseType> {
+        let mut database = LanceDatabase {
+            runtime: self.runtime.clone(),
+            uri: None,
+            table_name: None,
+            storage_options: HashMap::new(),
+        };
+        for (key, value) in opts {
+            database.set_option(key, value)?;
+        }
+        Ok(database)
+    }
+}
+
+/// A Lance dataset, identified by its URI.
+pub struct LanceDatabase {
+    runtime: Arc<Runtime>,
+    uri: Option<String>,
+    table_name: Option<String>,
+    storage_options: HashMap<String, String>,
+}
+
+impl LanceDatabase {
+    fn table_name(&self, uri: &str) -> String {
+        self.table_name.clone().unwrap_or_else(|| {
+            let name = uri.trim_end_matches('/').rsplit('/').next().unwrap_or(uri);
+            name.strip_suffix(".lance").unwrap_or(name).to_string()
+        })
+    }
+}
+
+impl Optionable for LanceDatabase {
+    type Option = OptionDatabase;
+
+    fn set_option(&mut self, key: Self::Option, value: OptionValue) -> AdbcResult<()> {
+        match key {
+            OptionDatabase::Uri => self.uri = Some(string_option(&key, value)?),
+            OptionDatabase::Other(ref name) if name == TABLE_NAME_OPTION => {
+                self.table_name = Some(string_option(&key, value)?)
+            }
+            OptionDatabase::Other(ref name) if name.starts_with(STORAGE_OPTION_PREFIX) => {
+                let storage_key = name[STORAGE_OPTION_PREFIX.len()..].to_string();
+                self.storage_options
+                    .insert(storage_key, string_option(&key, value)?);
+            }
+            _ => return Err(not_implemented(&format!("Option {}", key.as_ref()))),
+        }
+        Ok(())
+    }
+
+    fn get_option_string(&self, key: Self::Option) -> AdbcResult<String> {
+        match &key {
+            OptionDatabase::Uri => self.uri.clone(),
+            OptionDatabase::Other(name) if name == TABLE_NAME_OPTION => self.table_name.clone(),
+            OptionDatabase::Other(name) if name.starts_with(STORAGE_OPTION_PREFIX) => self
+                .storage_options
+                .get(&name[STORAGE_OPTION_PREFIX.len()..])
+                .cloned(),
+            _ => None,
+        }
+        .ok_or_else(|| not_found(&key))
+    }
+
+    fn get_option_bytes(&self, key: Self::Option) -> AdbcResult<Vec<u8>> {
+        Err(not_found(key))
+    }
+
+    fn get_option_int(&self, key: Self::Option) -> AdbcResult<i64> {
+        Err(not_found(key))
+    }
+
+    fn get_option_double(&self, key: Self::Option) -> AdbcResult<f64> {
+        Err(not_found(key))
+    }
+}
+
+impl Database for LanceDatabase {
+    type ConnectionType = LanceConnection;
+
+    fn new_connection(&self) -> AdbcResult<Self::ConnectionType> {
+        self.new_connection_with_opts([])
+    }
+
+    fn new_connection_with_opts(
+        &self,
+        opts: impl IntoIterator<Item = (OptionConnection, OptionValue)>,
+    ) -> AdbcResult<Self::ConnectionType> {
+        let uri = self.uri.clone().ok_or_else(|| {
+            AdbcError::with_message_and_status(
+                "The uri option must be set before connecting",
+                AdbcStatus::InvalidState,
+            )
+        })?;
+        let dataset = self
+            .runtime
+            .block_on(
+                DatasetBuilder::from_uri(&uri)
+                    .with_storage_options(self.storage_options.clone())
+                    .load(),
+            )
+            .map_err(to_adbc)?;
+        let table_name = self.table_name(&uri);
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            table_name.as_str(),
+            Arc::new(LanceTableProvider::new(Arc::new(dataset), false, false)),
+        )
+        .map_err(df_to_adbc)?;
+
+        let mut connection = LanceConnection {
+            runtime: self.runtime.clone(),
+            ctx,
+            table_name,
+        };
+        for (key, value) in opts {
+            connection.set_option(key, value)?;
+        }
+        Ok(connection)
+    }
+}
+
+/// A SQL session over the dataset of a [`LanceDatabase`].
+pub struct LanceConnection {
+    runtime: Arc<Runtime>,
+    ctx: SessionContext,
+    table_name: String,
+}
+
+impl Optionable for LanceConnection {
+    type Option = OptionConnection;
+
+    fn set_option(&mut self, key: Self::Option, value: OptionValue) -> AdbcResult<()> {
+        match (&key, &value) {
+            // Autocommit is the only mode supported.
+            (OptionConnection::AutoCommit, OptionValue::String(v)) if v == "true" => Ok(()),
+            (OptionConnection::ReadOnly, OptionValue::String(v)) if v == "true" => Ok(()),
+            _ => Err(not_implemented(&format!("Option {}", key.as_ref()))),
+        }
+    }
+
+    fn get_option_string(&self, key: Self::Option) -> AdbcResult<String> {
+        match key {
+            OptionConnection::AutoCommit | OptionConnection::ReadOnly => Ok("true".to_string()),
+            _ => Err(not_found(key)),
+        }
+    }
+
+    fn get_option_bytes(&self, key: Self::Option) -> AdbcResult<Vec<u8>> {
+        Err(not_found(key))
+    }
+
+    fn get_option_int(&self, key: Self::Option) -> AdbcResult<i64> {
+        Err(not_found(key))
+    }
+
+    fn get_option_double(&self, key: Self::Option) -> AdbcResult<f64> {
+        Err(not_found(key))
+    }
+}
+
+impl Connection for LanceConnection {
+    type StatementType = LanceStatement;
+
+    fn new_statement(&mut self) -> AdbcResult<Self::StatementType> {
+        Ok(LanceStatement {
+            runtime: self.runtime.clone(),
+            ctx: self.ctx.clone(),
+            query: None,
+        })
+    }
+
+    fn cancel(&mut self) -> AdbcResult<()> {
+        Err(not_implemented("Cancelling a connection"))
+    }
+
+    fn get_info(
+        &self,
+        _codes: Option<HashSet<InfoCode>>,
+    ) -> AdbcResult<impl RecordBatchReader + Send> {
+        Err::<StreamReader, _>(not_implemented("GetInfo"))
+    }
+
+    fn get_objects(
+        &self,
+        _depth: ObjectDepth,
+        _catalog: Option<&str>,
+        _db_schema: Option<&str>,
+        _table_name: Option<&str>,
+        _table_type: Option<Vec<&str>>,
+        _column_name: Option<&str>,
+    ) -> AdbcResult<impl RecordBatchReader + Send> {
+        Err::<StreamReader, _>(not_implemented("GetObjects"))
+    }
+
+    fn get_table_schema(
+        &self,
+        _catalog: Option<&str>,
+        _db_schema: Option<&str>,
+        table_name: &str,
+    ) -> AdbcResult<ArrowSchema> {
+        if table_name != self.table_name {
+            return Err(AdbcError::with_message_and_status(
+                format!("Table {} does not exist", table_name),
+                AdbcStatus::NotFound,
+            ));
+        }
+        let table = self
+            .runtime
+            .block_on(self.ctx.table_provider(table_name))
+            .map_err(df_to_adbc)?;
+        Ok(table.schema().as_ref().clone())
+    }
+
+    fn get_table_types(&self) -> AdbcResult<impl RecordBatchReader + Send> {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "table_type",
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec!["TABLE"]))],
+        )
+        .map_err(|e| AdbcError::with_message_and_status(e.to_string(), AdbcStatus::Internal))?;
+        Ok(arrow_array::RecordBatchIterator::new(
+            vec![Ok(batch)],
+            schema,
+        ))
+    }
+
+    fn get_statistic_names(&self) -> AdbcResult<impl RecordBatchReader + Send> {
+        Err::<StreamReader, _>(not_implemented("GetStatisticNames"))
+    }
+
+    fn get_statistics(
+        &self,
+        _catalog: Option<&str>,
+        _db_schema: Option<&str>,
+        _table_name: Option<&str>,
+        _approximate: bool,
+    ) -> AdbcResult<impl RecordBatchReader + Send> {
+        Err::<StreamReader, _>(not_implemented("GetStatistics"))
+    }
+
+    fn commit(&mut self) -> AdbcResult<()> {
+        Err(not_implemented("Transactions"))
+    }
+
+    fn rollback(&mut self) -> AdbcResult<()> {
+        Err(not_implemented("Transactions"))
+    }
+
+    fn read_partition(
+        &self,
+        _partition: impl AsRef<[u8]>,
+    ) -> AdbcResult<impl RecordBatchReader + Send> {
+        Err::<StreamReader, _>(not_implemented("Partitioned results"))
+    }
+}
+
+/// A SQL query against a [`LanceConnection`].
+pub struct LanceStatement {
+    runtime: Arc<Runtime>,
+    ctx: SessionContext,
+    query: Option<String>,
+}
+
+impl LanceStatement {
+    fn query(&self) -> AdbcResult<&str> {
+        self.query.as_deref().ok_or_else(|| {
+            AdbcError::with_message_and_status(
+                "No SQL query was set on the statement",
+                AdbcStatus::InvalidState,
+            )
+        })
+    }
+}
+
+impl Optionable for LanceStatement {
+    type Option = OptionStatement;
+
+    fn set_option(&mut self, key: Self::Option, _value: OptionValue) -> AdbcResult<()> {
+        Err(not_implemented(&format!("Option {}", key.as_ref())))
+    }
+
+    fn get_option_string(&self, key: Self::Option) -> AdbcResult<String> {
+        Err(not_found(key))
+    }
+
+    fn get_option_bytes(&self, key: Self::Option) -> AdbcResult<Vec<u8>> {
+        Err(not_found(key))
+    }
+
+    fn get_option_int(&self, key: Self::Option) -> AdbcResult<i64> {
+        Err(not_found(key))
+    }
+
+    fn get_option_double(&self, key: Self::Option) -> AdbcResult<f64> {
+        Err(not_found(key))
+    }
+}
+
+impl Statement for LanceStatement {
+    fn bind(&mut self, _batch: RecordBatch) -> AdbcResult<()> {
+        Err(not_implemented("Binding parameters"))
+    }
+
+    fn bind_stream(&mut self, _reader: Box<dyn RecordBatchReader + Send>) -> AdbcResult<()> {
+        Err(not_implemented("Binding parameters"))
+    }
+
+    fn execute(&mut self) -> AdbcResult<impl RecordBatchReader + Send> {
+        let query = self.query()?;
+        let stream = self
+            .runtime
+            .block_on(async {
+                let df = self.ctx.sql(query).await?;
+                df.execute_stream().await
+            })
+            .map_err(df_to_adbc)?;
+        Ok(StreamReader {
+            runtime: self.runtime.clone(),
+            stream,
+        })
+    }
+
+    fn execute_update(&mut self) -> AdbcResult<Option<i64>> {
+        Err(not_implemented("Updating a dataset"))
+    }
+
+    fn execute_schema(&mut self) -> AdbcResult<ArrowSchema> {
+        let query = self.query()?;
+        let df = self
+            .runtime
+            .block_on(self.ctx.sql(query))
+            .map_err(df_to_adbc)?;
+        Ok(df.schema().as_arrow().clone())
+    }
+
+    fn execute_partitions(&mut self) -> AdbcResult<PartitionedResult> {
+        Err(not_implemented("Partitioned results"))
+    }
+
+    fn get_parameter_schema(&self) -> AdbcResult<ArrowSchema> {
+        Err(not_implemented("Binding parameters"))
+    }
+
+    fn prepare(&mut self) -> AdbcResult<()> {
+        // Validate the query; it is planned again on execution.
+        self.execute_schema().map(|_| ())
+    }
+
+    fn set_sql_query(&mut self, query: impl AsRef<str>) -> AdbcResult<()> {
+        self.query = Some(query.as_ref().to_string());
+        Ok(())
+    }
+
+    fn set_substrait_plan(&mut self, _plan: impl AsRef<[u8]>) -> AdbcResult<()> {
+        Err(not_implemented("Substrait plans"))
+    }
+
+    fn cancel(&mut self) -> AdbcResult<()> {
+        Err(not_implemented("Cancelling a statement"))
+    }
+}
+
+/// Blocking reader over the results of a query.
+struct StreamReader {
+    runtime: Arc<Runtime>,
+    stream: SendableRecordBatchStream,
+}
+
+impl Iterator for StreamReader {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime
+            .block_on(self.stream.next())
+            .map(|batch| batch.map_err(|e| ArrowError::ExternalError(Box::new(e))))
+    }
+}
+
+impl RecordBatchReader for StreamReader {
+    fn schema(&self) -> SchemaRef {
+        self.stream.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, Int64Array, RecordBatchIterator};
+
+    use crate::Dataset;
+
+    #[test]
+    fn test_adbc_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = format!("{}/items.lance", dir.path().to_str().unwrap());
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "id",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        Runtime::new()
+            .unwrap()
+            .block_on(Dataset::write(
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+                &uri,
+                None,
+            ))
+            .unwrap();
+
+        let mut driver = LanceDriver::default();
+        let database = driver
+            .new_database_with_opts([(OptionDatabase::Uri, OptionValue::String(uri))])
+            .unwrap();
+        let mut connection = database.new_connection().unwrap();
+        assert_eq!(
+            connection.get_table_schema(None, None, "items").unwrap(),
+            *schema
+        );
+
+        let mut statement = connection.new_statement().unwrap();
+        statement
+            .set_sql_query("SELECT count(*) AS n FROM items WHERE id >= 90")
+            .unwrap();
+        let batches = statement
+            .execute()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            batches[0]
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .value(0),
+            10
+        );
+
+        statement.set_sql_query("SELECT * FROM missing").unwrap();
+        let err = statement.prepare().unwrap_err();
+        assert_eq!(err.status, AdbcStatus::InvalidArguments);
+    }
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+#![allow(clippy::print_stdout)]
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+
+use lance::dataset::dump::{dump_deletion_files, dump_fragments, dump_indices, dump_manifest};
+use lance::dataset::Dataset;
+use lance::Result;
+
+/// Dump the metadata of a dataset, for support and debugging
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Args)]
+struct Target {
+    /// The URI of the dataset.
+    uri: String,
+
+    /// The version to dump, defaults to the latest version.
+    #[arg(short, long)]
+    version: Option<u64>,
+
+    /// Print JSON instead of text.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Dump the manifest: version information, settings and schema
+    Manifest(Target),
+
+    /// Dump the fragments with their data files and deletion files
+    Fragments(Target),
+
+    /// Dump the deletion files
+    Deletions(Target),
+
+    /// Dump the index metadata
+    Indices(Target),
+}
+
+impl Commands {
+    fn target(&self) -> &Target {
+        match self {
+            Self::Manifest(target)
+            | Self::Fragments(target)
+            | Self::Deletions(target)
+            | Self::Indices(target) => target,
+        }
+    }
+}
+
+fn print_items<T: Serialize + std::fmt::Display>(items: &[T], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(items)?);
+    } else {
+        for item in items {
+            println!("{}", item);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Cli::parse();
+    let target = args.command.target();
+
+    let mut dataset = Dataset::open(&target.uri).await?;
+    if let Some(version) = target.version {
+        dataset = dataset.checkout_version(version).await?;
+    }
+
+    match &args.command {
+        Commands::Manifest(_) => {
+            let manifest = dump_manifest(&dataset);
+            if target.json {
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+            } else {
+                print!("{}", manifest);
+            }
+            Ok(())
+        }
+        Commands::Fragments(_) => print_items(&dump_fragments(&dataset), target.json),
+        Commands::Deletions(_) => print_items(&dump_deletion_files(&dataset), target.json),
+        Commands::Indices(_) => print_items(&dump_indices(&dataset).await?, target.json),
+    }
+}
@@ -50,31 +50,41 @@ use tracing::{info, instrument};
 mod blob;
 pub mod builder;
 pub mod cleanup;
+pub mod dump;
+#[cfg(feature = "parquet")]
+pub mod export;
 pub mod fragment;
 mod hash_joiner;
+pub mod history;
 pub mod index;
 pub mod optimize;
 pub mod progress;
 pub mod refs;
+pub mod replicate;
 pub(crate) mod rowids;
 pub mod scanner;
 mod schema_evolution;
+pub mod sorted_projection;
 pub mod statistics;
 mod take;
 pub mod transaction;
 pub mod updater;
 mod utils;
+pub mod verify;
 mod write;
 
 use self::builder::DatasetBuilder;
-use self::cleanup::RemovalStats;
+use self::cleanup::{CleanupPolicy, CleanupReport, RemovalStats};
 use self::fragment::FileFragment;
 use self::refs::Tags;
+use self::replicate::{ReplicateOptions, ReplicateSummary};
 use self::scanner::{DatasetRecordBatchStream, Scanner};
 use self::transaction::{Operation, Transaction};
+use self::verify::{VerifyOptions, VerifyReport};
 use self::write::write_fragments_internal;
 use crate::datatypes::Schema;
 use crate::error::box_error;
+use crate::index::cache::EncodedIndexSection;
 use crate::io::commit::{
     commit_detached_transaction, commit_new_dataset, commit_transaction,
     detect_overlapping_fragments, manifest_cache_key, read_transaction_file,
@@ -83,7 +93,7 @@ use crate::io::commit::{
 use crate::session::Session;
 use crate::utils::temporal::{timestamp_to_nanos, utc_now, SystemTime};
 use crate::{Error, Result};
-pub use blob::BlobFile;
+pub use blob::{BlobFile, BlobHandle};
 use hash_joiner::HashJoiner;
 pub use lance_core::ROW_ID;
 use lance_table::feature_flags::{apply_feature_flags, can_read_dataset};
@@ -91,15 +101,23 @@ pub use schema_evolution::{
     BatchInfo, BatchUDF, ColumnAlteration, NewColumnTransform, UDFCheckpointStore,
 };
 pub use take::TakeBuilder;
+#[cfg(feature = "avro")]
+pub use write::avro::{read_avro, AvroReadOptions};
+#[cfg(feature = "parquet")]
+pub use write::import::{
+    delta::{import_delta, DeltaImportParams},
+    import_parquet, ParquetImportParams, ParquetImportSummary,
+};
 pub use write::merge_insert::{
     MergeInsertBuilder, MergeInsertJob, MergeStats, UncommittedMergeInsert, WhenMatched,
     WhenNotMatched, WhenNotMatchedBySource,
 };
+pub use write::text::{read_csv, read_json, CsvReadOptions, JsonReadOptions};
 pub use write::update::{UpdateBuilder, UpdateJob};
 #[allow(deprecated)]
 pub use write::{
-    write_fragments, AutoCleanupParams, CommitBuilder, InsertBuilder, WriteDestination, WriteMode,
-    WriteParams,
+    write_fragments, AutoCleanupParams, CommitBuilder, InsertBuilder, PageSize, WriteDestination,
+    WriteMode, WriteParams,
 };
 
 const INDICES_DIR: &str = "_indices";
@@ -132,6 +150,9 @@ pub struct Dataset {
 
     // These are references to session caches, but with the dataset URI as a prefix.
     pub(crate) metadata_cache: Arc<LanceCache>,
+
+    /// Set if the dataset was opened with [`ReadParams::read_only`]
+    pub(crate) read_only: bool,
 }
 
 impl std::fmt::Debug for Dataset {
@@ -155,6 +176,9 @@ pub struct Version {
     pub timestamp: DateTime<Utc>,
 
     /// Key-value pairs of metadata.
+    ///
+    /// These are the properties attached to the transaction that created
+    /// this version, see [`CommitBuilder::with_transaction_properties`].
     pub metadata: BTreeMap<String, String>,
 }
 
@@ -164,7 +188,7 @@ impl From<&Manifest> for Version {
         Self {
             version: m.version,
             timestamp: m.timestamp(),
-            metadata: BTreeMap::default(),
+            metadata: history::committer_metadata(&m.transaction_properties),
         }
     }
 }
@@ -204,6 +228,16 @@ pub struct ReadParams {
     /// If a custom object store is provided (via store_params.object_store) then this
     /// must also be provided.
     pub commit_handler: Option<Arc<dyn CommitHandler>>,
+
+    /// Open the dataset read-only.
+    ///
+    /// Every API that would modify the dataset (writes, deletes, index builds,
+    /// compaction, cleanup, tags, ...) fails with [`Error::ReadOnly`], and the
+    /// object store rejects any write that slips past those checks. Loading the
+    /// dataset never writes either, e.g. it won't finalize manifests staged by
+    /// an external manifest store. Since nothing is written, credentials only
+    /// need read access, and public S3 buckets can be read without any.
+    pub read_only: bool,
 }
 
 impl ReadParams {
@@ -236,6 +270,12 @@ impl ReadParams {
         self
     }
 
+    /// Open the dataset read-only, see [`Self::read_only`].
+    pub fn read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
     /// Use the explicit locking to resolve the latest version
     pub fn set_commit_lock<T: CommitLock + Send + Sync + 'static>(&mut self, lock: Arc<T>) {
         self.commit_handler = Some(Arc::new(lock));
@@ -250,6 +290,7 @@ impl Default for ReadParams {
             session: None,
             store_options: None,
             commit_handler: None,
+            read_only: false,
         }
     }
 }
@@ -372,21 +413,26 @@ impl Dataset {
             return Ok(self.clone());
         }
 
-        let manifest = Self::load_manifest(
-            self.object_store.as_ref(),
-            &manifest_location,
-            &base_path,
-            self.session.as_ref(),
-        )
-        .await?;
+        let manifest = self
+            .metadata_cache
+            .get_or_insert(manifest_cache_key(&manifest_location), |_| {
+                Self::load_manifest(
+                    self.object_store.as_ref(),
+                    &manifest_location,
+                    &base_path,
+                    self.session.as_ref(),
+                )
+            })
+            .await?;
         Self::checkout_manifest(
             self.object_store.clone(),
             base_path,
             self.uri.clone(),
-            Arc::new(manifest),
+            manifest,
             manifest_location,
             self.session.clone(),
             self.commit_handler.clone(),
+            self.read_only,
         )
     }
 
@@ -458,8 +504,9 @@ impl Dataset {
             });
         }
 
-        // If indices were also the last block, we can take the opportunity to
-        // decode them now and cache them.
+        // If indices were also in the last block, keep their encoded section so
+        // they can be decoded on first use without another read.  Decoding them
+        // here would make opening a dataset with many indices slow.
         if let Some(index_offset) = manifest.index_section {
             if manifest_size - index_offset <= last_block.len() {
                 let offset_in_block = last_block.len() - (manifest_size - index_offset);
@@ -468,16 +515,10 @@ impl Dataset {
                         as usize;
                 let message_data =
                     &last_block[offset_in_block + 4..offset_in_block + 4 + message_len];
-                let section = lance_table::format::pb::IndexSection::decode(message_data)?;
-                let indices = section
-                    .indices
-                    .into_iter()
-                    .map(Index::try_from)
-                    .collect::<Result<Vec<_>>>()?;
-                session.index_cache.insert_metadata(
+                session.index_cache.insert_encoded_metadata(
                     base_path.as_ref(),
                     manifest_location.version,
-                    Arc::new(indices),
+                    Arc::new(EncodedIndexSection(message_data.to_vec())),
                 );
             }
         }
@@ -498,12 +539,14 @@ impl Dataset {
         manifest_location: ManifestLocation,
         session: Arc<Session>,
         commit_handler: Arc<dyn CommitHandler>,
+        read_only: bool,
     ) -> Result<Self> {
         let tags = Tags::new(
             object_store.clone(),
             commit_handler.clone(),
             base_path.clone(),
-        );
+        )
+        .with_read_only(read_only);
         let metadata_cache = Arc::new(session.metadata_cache.with_key_prefix(&uri));
         Ok(Self {
             object_store,
@@ -515,9 +558,26 @@ impl Dataset {
             session,
             tags,
             metadata_cache,
+            read_only,
         })
     }
 
+    /// Whether the dataset was opened read-only, see [`ReadParams::read_only`]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fail with [`Error::ReadOnly`] if the dataset was opened read-only
+    pub(crate) fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly {
+                message: format!("cannot {} a dataset opened read-only", operation),
+                location: location!(),
+            });
+        }
+        Ok(())
+    }
+
     /// Write to or Create a [Dataset] with a stream of [RecordBatch]s.
     ///
     /// `dest` can be a `&str`, `object_store::path::Path` or `Arc<Dataset>`.
@@ -598,6 +658,7 @@ impl Dataset {
                 blob_manifest_location,
                 self.session.clone(),
                 self.commit_handler.clone(),
+                self.read_only,
             )?;
             Ok(Some(Arc::new(blobs_dataset)))
         } else {
@@ -659,6 +720,7 @@ impl Dataset {
 
     /// Restore the currently checked out version of the dataset as the latest version.
     pub async fn restore(&mut self) -> Result<()> {
+        self.ensure_writable("restore")?;
         let (latest_manifest, _) = self.latest_manifest().await?;
         let latest_version = latest_manifest.version;
 
@@ -714,6 +776,61 @@ impl Dataset {
         .boxed()
     }
 
+    /// Removes old versions according to `policy` and returns a report of
+    /// the removed files.
+    ///
+    /// Set [`CleanupPolicy::dry_run`] to preview a cleanup without deleting
+    /// anything.
+    #[instrument(level = "debug", skip(self))]
+    pub fn cleanup_with_policy(&self, policy: CleanupPolicy) -> BoxFuture<Result<CleanupReport>> {
+        cleanup::cleanup_with_policy(self, policy).boxed()
+    }
+
+    /// Check this version of the dataset against the files in storage.
+    ///
+    /// See [`verify::verify_dataset`] for the checks that are made.
+    pub async fn verify(&self, options: &VerifyOptions) -> Result<VerifyReport> {
+        verify::verify_dataset(self, options).await
+    }
+
+    /// Copy this version of the dataset to `target_uri` and commit it there.
+    ///
+    /// The copy can be resumed by calling this again, see [`replicate`].
+    pub async fn replicate_to(
+        &self,
+        target_uri: &str,
+        options: &ReplicateOptions,
+    ) -> Result<ReplicateSummary> {
+        replicate::replicate_dataset(self, target_uri, options).await
+    }
+
+    /// Export this version of the dataset to Parquet files under `uri`.
+    ///
+    /// Vector columns are written as `FixedSizeList`, see [`export`] for how
+    /// other Lance types are mapped.
+    #[cfg(feature = "parquet")]
+    pub async fn export_parquet(
+        &self,
+        uri: &str,
+        params: &export::ParquetExportParams,
+    ) -> Result<export::ParquetExportSummary> {
+        export::export_parquet(self, uri, params).await
+    }
+
+    /// Export this version of the dataset as an Iceberg table at `uri`.
+    ///
+    /// The data is written as Parquet files together with the Iceberg
+    /// metadata describing them, see [`export::iceberg`]. Requires the `avro`
+    /// feature as well, for the manifests.
+    #[cfg(all(feature = "parquet", feature = "avro"))]
+    pub async fn export_iceberg(
+        &self,
+        uri: &str,
+        params: &export::ParquetExportParams,
+    ) -> Result<export::iceberg::IcebergExportSummary> {
+        export::iceberg::export_iceberg(self, uri, params).await
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn do_commit(
         base_uri: WriteDestination<'_>,
@@ -992,6 +1109,24 @@ impl Dataset {
         blob::take_blobs(self, &row_addrs, column.as_ref()).await
     }
 
+    /// Get a [BlobHandle] for each row of a scanned batch.
+    ///
+    /// Scans return the position and size of each blob instead of its bytes.  The batch
+    /// must contain the blob column, as returned by the scan, and the row addresses (see
+    /// [`Scanner::with_row_address`]).  No blob data is read, null blobs have no handle.
+    pub fn blob_handles(
+        &self,
+        batch: &RecordBatch,
+        column: impl AsRef<str>,
+    ) -> Result<Vec<Option<BlobHandle>>> {
+        blob::blob_handles(self, batch, column.as_ref())
+    }
+
+    /// Open a [BlobHandle] to read the bytes of the blob on demand.
+    pub fn open_blob(self: &Arc<Self>, handle: &BlobHandle) -> BlobFile {
+        BlobFile::from_handle(self.clone(), handle)
+    }
+
     /// Get a stream of batches based on iterator of ranges of row numbers.
     ///
     /// This is an experimental API. It may change at any time.
@@ -1004,16 +1139,28 @@ impl Dataset {
         take::take_scan(self, row_ranges, projection, batch_readahead)
     }
 
-    /// Sample `n` rows from the dataset.
-    pub(crate) async fn sample(&self, n: usize, projection: &Schema) -> Result<RecordBatch> {
+    /// Sample `n` rows from the dataset, reproducibly if a `seed` is given.
+    pub(crate) async fn sample(
+        &self,
+        n: usize,
+        projection: &Schema,
+        seed: Option<u64>,
+    ) -> Result<RecordBatch> {
+        use rand::rngs::SmallRng;
         use rand::seq::IteratorRandom;
+        use rand::SeedableRng;
         let num_rows = self.count_rows(None).await?;
-        let ids = (0..num_rows as u64).choose_multiple(&mut rand::thread_rng(), n);
+        let mut rng = match seed {
+            Some(seed) => SmallRng::seed_from_u64(seed),
+            None => SmallRng::from_entropy(),
+        };
+        let ids = (0..num_rows as u64).choose_multiple(&mut rng, n);
         self.take(&ids, projection.clone()).await
     }
 
     /// Delete rows based on a predicate.
     pub async fn delete(&mut self, predicate: &str) -> Result<()> {
+        self.ensure_writable("delete rows from")?;
         write::delete::delete(self, predicate).await
     }
 
@@ -1029,6 +1176,16 @@ impl Dataset {
         &self.object_store
     }
 
+    /// A copy of the dataset for background work, such as compaction or index
+    /// builds, whose I/O stays within the background bandwidth limits, see
+    /// [`lance_io::throttle`]
+    pub(crate) fn for_background(&self) -> Self {
+        Self {
+            object_store: Arc::new(self.object_store.for_background()),
+            ..self.clone()
+        }
+    }
+
     pub fn data_dir(&self) -> Path {
         self.base.child(DATA_DIR)
     }
@@ -1509,6 +1666,7 @@ pub(crate) fn load_new_transactions(dataset: &Dataset) -> NewTransactionResult<'
                         location,
                         dataset.session(),
                         dataset.commit_handler.clone(),
+                        dataset.read_only,
                     )?;
                     let object_store = dataset_version.object_store();
                     let path = dataset_version
@@ -1543,6 +1701,7 @@ pub(crate) fn load_new_transactions(dataset: &Dataset) -> NewTransactionResult<'
                 location,
                 dataset.session(),
                 dataset.commit_handler.clone(),
+                dataset.read_only,
             )
         } else {
             // If we didn't get the latest manifest, we can still return the dataset
@@ -1560,6 +1719,44 @@ pub(crate) fn load_new_transactions(dataset: &Dataset) -> NewTransactionResult<'
     }
 }
 
+/// The number of most recent versions searched for an earlier commit of a
+/// transaction id, see [`Transaction::with_transaction_id`].
+pub(crate) const TRANSACTION_ID_LOOKBACK: usize = 100;
+
+/// Find the latest version, among the [`TRANSACTION_ID_LOOKBACK`] most recent ones,
+/// that was committed by a transaction with the id `transaction_id`.
+pub(crate) async fn find_committed_transaction(
+    dataset: &Dataset,
+    transaction_id: &str,
+) -> Result<Option<u64>> {
+    let io_parallelism = dataset.object_store().io_parallelism();
+    let mut versions = dataset
+        .commit_handler
+        .list_manifest_locations(&dataset.base, dataset.object_store(), true)
+        .take(TRANSACTION_ID_LOOKBACK)
+        .map_ok(move |location| async move {
+            let manifest = dataset
+                .metadata_cache
+                .get_or_insert(manifest_cache_key(&location), |_| {
+                    Dataset::load_manifest(
+                        dataset.object_store(),
+                        &location,
+                        &dataset.base,
+                        dataset.session.as_ref(),
+                    )
+                })
+                .await?;
+            let committed = manifest
+                .transaction_properties
+                .get(history::TRANSACTION_ID_KEY)
+                .is_some_and(|id| id == transaction_id);
+            Ok(committed.then_some(manifest.version))
+        })
+        .try_buffered(io_parallelism)
+        .try_filter_map(|version| futures::future::ready(Ok(version)));
+    versions.try_next().await
+}
+
 /// # Schema Evolution
 ///
 /// Lance datasets support evolving the schema. Several operations are
@@ -1584,6 +1781,7 @@ impl Dataset {
         read_columns: Option<Vec<String>>,
         batch_size: Option<u32>,
     ) -> Result<()> {
+        self.ensure_writable("add columns to")?;
         schema_evolution::add_columns(self, transforms, read_columns, batch_size).await
     }
 
@@ -1596,6 +1794,7 @@ impl Dataset {
     /// it, call [optimize::compact_files()] and then
     /// [cleanup::cleanup_old_versions()] on the dataset.
     pub async fn alter_columns(&mut self, alterations: &[ColumnAlteration]) -> Result<()> {
+        self.ensure_writable("alter columns of")?;
         schema_evolution::alter_columns(self, alterations).await
     }
 
@@ -1711,6 +1910,7 @@ impl Dataset {
         left_on: &str,
         right_on: &str,
     ) -> Result<()> {
+        self.ensure_writable("merge into")?;
         let stream = Box::new(stream);
         self.merge_impl(stream, left_on, right_on).await
     }
@@ -1778,6 +1978,16 @@ impl Dataset {
         })
         .await
     }
+
+    /// Enable stable row ids on a dataset that was created without them.
+    ///
+    /// Row ids are assigned to the existing rows in fragment order and stored
+    /// in the fragment metadata. No data files are rewritten. The dataset
+    /// must not have any indices, since those refer to row addresses; drop
+    /// them before migrating and recreate them afterwards.
+    pub async fn enable_stable_row_ids(&mut self) -> Result<()> {
+        self.update_op(Operation::EnableStableRowIds).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -2204,11 +2414,10 @@ mod tests {
 
         let get_iops = || io_stats.lock().unwrap().read_iops;
 
-        // There should be only two IOPS:
-        // 1. List _versions directory to get the latest manifest location
-        // 2. Read the manifest file. (The manifest is small enough to be read in one go.
-        //    Larger manifests would result in more IOPS.)
-        assert_eq!(get_iops(), 2);
+        // There should be only one IOP, to list the _versions directory to get the
+        // latest manifest location.  The manifest itself was cached in the session
+        // when it was written.
+        assert_eq!(get_iops(), 1);
     }
 
     #[rstest]
@@ -3728,6 +3937,67 @@ mod tests {
         assert!(fragments[0].metadata.deletion_file.is_some());
     }
 
+    #[tokio::test]
+    async fn test_read_only() {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::UInt32,
+            false,
+        )]));
+        let data = || {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(UInt32Array::from_iter_values(0..100))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let mut dataset = Dataset::write(data(), test_uri, None).await.unwrap();
+        dataset.tags.create("v1", 1).await.unwrap();
+        assert!(!dataset.is_read_only());
+
+        let mut dataset = DatasetBuilder::from_uri(test_uri)
+            .with_read_only(true)
+            .load()
+            .await
+            .unwrap();
+        assert!(dataset.is_read_only());
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 100);
+
+        let assert_read_only = |result: Result<()>| match result {
+            Err(Error::ReadOnly { .. }) => {}
+            other => panic!("Expected read-only error, got {:?}", other),
+        };
+        assert_read_only(dataset.append(data(), None).await);
+        assert_read_only(dataset.delete("i > 50").await);
+        assert_read_only(dataset.restore().await);
+        assert_read_only(dataset.tags.create("v2", 1).await);
+        assert_read_only(dataset.tags.delete("v1").await);
+        assert_read_only(
+            cleanup::cleanup_with_policy(&dataset, cleanup::CleanupPolicy::new(Utc::now()))
+                .await
+                .map(|_| ()),
+        );
+        // Anything that bypasses the checks is stopped by the object store
+        assert!(dataset
+            .object_store
+            .put(&dataset.base.child("stray.txt"), b"stray")
+            .await
+            .is_err());
+
+        // Versions checked out from a read-only dataset are read-only too
+        let dataset = dataset.checkout_version("v1").await.unwrap();
+        assert!(dataset.is_read_only());
+
+        let dataset = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(dataset.version().version, 1);
+        assert_eq!(dataset.tags.list().await.unwrap().len(), 1);
+        assert!(!test_dir.path().join("stray.txt").exists());
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_update_config() {
@@ -3831,6 +4101,75 @@ mod tests {
         assert_eq!(manifest_before.fragments, manifest_after.fragments);
     }
 
+    #[tokio::test]
+    async fn test_transaction_properties() {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::UInt32,
+            false,
+        )]));
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let data = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let properties = HashMap::from([
+            ("job_id".to_string(), "job-1".to_string()),
+            ("author".to_string(), "alice".to_string()),
+        ]);
+        let reader = RecordBatchIterator::new(vec![Ok(data.clone())], schema.clone());
+        let dataset = Dataset::write(
+            reader,
+            test_uri,
+            Some(WriteParams {
+                transaction_properties: Some(Arc::new(properties.clone())),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        // Properties are not inherited by later versions
+        let transaction = InsertBuilder::new(Arc::new(dataset.clone()))
+            .with_params(&WriteParams {
+                mode: WriteMode::Append,
+                ..Default::default()
+            })
+            .execute_uncommitted(vec![data])
+            .await
+            .unwrap();
+        let dataset = CommitBuilder::new(Arc::new(dataset))
+            .with_transaction_properties(HashMap::from([(
+                "git_sha".to_string(),
+                "abc123".to_string(),
+            )]))
+            .execute(transaction)
+            .await
+            .unwrap();
+
+        let versions = dataset.versions().await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(
+            versions[0].metadata,
+            properties.into_iter().collect::<BTreeMap<_, _>>()
+        );
+        assert_eq!(
+            versions[1].metadata,
+            BTreeMap::from([("git_sha".to_string(), "abc123".to_string())])
+        );
+        assert_eq!(dataset.version().metadata, versions[1].metadata);
+
+        // Properties survive a round trip through the transaction file
+        let transaction = dataset.read_transaction().await.unwrap().unwrap();
+        assert_eq!(
+            transaction.transaction_properties.unwrap().get("git_sha"),
+            Some(&"abc123".to_string())
+        );
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_tag(
@@ -6357,6 +6696,40 @@ mod tests {
         assert_eq!(registry.active_stores().len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_session_manifest_cache() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("a", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(10), BatchCount::from(1));
+        let mut dataset = Dataset::write(data, test_uri, None).await.unwrap();
+        dataset.delete("a < 5").await.unwrap();
+
+        let session = Arc::new(Session::default());
+        let open = || {
+            DatasetBuilder::from_uri(test_uri)
+                .with_session(session.clone())
+                .load()
+        };
+        open().await.unwrap();
+        let stats = session.metadata_cache_stats();
+        assert_eq!(stats.hits, 0);
+
+        // The second open reuses the manifest read by the first one
+        let dataset = open().await.unwrap();
+        assert_eq!(session.metadata_cache_stats().hits, stats.hits + 1);
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 5);
+
+        let hits = session.metadata_cache_stats().hits;
+        dataset.checkout_version(1).await.unwrap();
+        let first = dataset.checkout_version(1).await.unwrap();
+        assert_eq!(session.metadata_cache_stats().hits, hits + 1);
+        assert_eq!(first.count_rows(None).await.unwrap(), 10);
+
+        assert!(Arc::ptr_eq(&Session::shared(), &Session::shared()));
+    }
+
     #[tokio::test]
     async fn test_migrate_v2_manifest_paths() {
         let tmp_dir = tempdir().unwrap();
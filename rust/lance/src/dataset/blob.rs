@@ -1,10 +1,16 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
-use std::{future::Future, ops::DerefMut, sync::Arc};
+use std::{
+    collections::HashMap,
+    future::Future,
+    ops::{DerefMut, Range},
+    sync::Arc,
+};
 
 use arrow::array::AsArray;
 use arrow::datatypes::UInt64Type;
+use arrow_array::{Array, RecordBatch};
 use arrow_schema::DataType;
 use datafusion::execution::SendableRecordBatchStream;
 use futures::StreamExt;
@@ -12,16 +18,16 @@ use object_store::path::Path;
 use snafu::location;
 use tokio::sync::Mutex;
 
-use super::Dataset;
+use super::{Dataset, DATA_DIR};
 use crate::io::exec::{ShareableRecordBatchStream, ShareableRecordBatchStreamAdapter};
 use lance_core::{
-    datatypes::{Schema, StorageClass, BLOB_META_KEY},
+    datatypes::{Field, Schema, StorageClass, BLOB_META_KEY},
     error::CloneableResult,
     utils::{
         address::RowAddress,
         futures::{Capacity, SharedStreamExt},
     },
-    Error, Result,
+    Error, Result, ROW_ADDR,
 };
 use lance_io::traits::Reader;
 
@@ -36,6 +42,23 @@ enum ReaderState {
     Closed,
 }
 
+/// A lightweight reference to a blob in a dataset
+///
+/// Handles are created from scan results without reading any blob data, see
+/// [`Dataset::blob_handles`].  The bytes are read on demand by opening the handle with
+/// [`Dataset::open_blob`], or directly from the data file with range reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobHandle {
+    /// The URI of the data file that contains the blob
+    pub uri: String,
+    /// The path of the data file in the object store of the dataset
+    pub data_file: Path,
+    /// The offset of the blob in the data file
+    pub position: u64,
+    /// The size of the blob in bytes
+    pub size: u64,
+}
+
 /// A file-like object that represents a blob in a dataset
 #[derive(Debug)]
 pub struct BlobFile {
@@ -70,6 +93,19 @@ impl BlobFile {
         }
     }
 
+    /// Open a blob from a handle
+    ///
+    /// See [`crate::dataset::Dataset::open_blob`]
+    pub fn from_handle(dataset: Arc<Dataset>, handle: &BlobHandle) -> Self {
+        Self {
+            dataset,
+            data_file: handle.data_file.clone(),
+            position: handle.position,
+            size: handle.size,
+            reader: Arc::new(Mutex::new(ReaderState::Uninitialized(0))),
+        }
+    }
+
     /// Close the blob file, releasing any associated resources
     pub async fn close(&self) -> Result<()> {
         let mut reader = self.reader.lock().await;
@@ -143,6 +179,27 @@ impl BlobFile {
         .await
     }
 
+    /// Read a range of bytes, relative to the start of the blob
+    ///
+    /// This neither uses nor moves the cursor, so ranges can be read in any order.
+    pub async fn read_range(&self, range: Range<u64>) -> Result<bytes::Bytes> {
+        if range.start > range.end || range.end > self.size {
+            return Err(Error::invalid_input(
+                format!(
+                    "cannot read the range {:?} from a blob of {} bytes",
+                    range, self.size
+                ),
+                location!(),
+            ));
+        }
+        let start = (self.position + range.start) as usize;
+        let end = (self.position + range.end) as usize;
+        self.do_with_reader(|cursor, reader| async move {
+            Ok((cursor, reader.get_range(start..end).await?))
+        })
+        .await
+    }
+
     /// Seek to a new cursor position in the file
     pub async fn seek(&self, new_cursor: u64) -> Result<()> {
         let mut reader = self.reader.lock().await;
@@ -181,6 +238,16 @@ impl BlobFile {
     }
 }
 
+fn check_blob_field(field: &Field, column: &str) -> Result<()> {
+    if field.data_type() != DataType::LargeBinary || !field.metadata.contains_key(BLOB_META_KEY) {
+        return Err(Error::InvalidInput {
+            location: location!(),
+            source: format!("the column '{}' is not a blob column", column).into(),
+        });
+    }
+    Ok(())
+}
+
 pub(super) async fn take_blobs(
     dataset: &Arc<Dataset>,
     row_ids: &[u64],
@@ -189,14 +256,7 @@ pub(super) async fn take_blobs(
     let projection = dataset.schema().project(&[column])?;
     let blob_field = &projection.fields[0];
     let blob_field_id = blob_field.id;
-    if blob_field.data_type() != DataType::LargeBinary
-        || !projection.fields[0].metadata.contains_key(BLOB_META_KEY)
-    {
-        return Err(Error::InvalidInput {
-            location: location!(),
-            source: format!("the column '{}' is not a blob column", column).into(),
-        });
-    }
+    check_blob_field(blob_field, column)?;
     let description_and_addr = dataset
         .take_builder(row_ids, projection)?
         .with_row_address(true)
@@ -229,6 +289,91 @@ pub(super) async fn take_blobs(
         .collect())
 }
 
+pub(super) fn blob_handles(
+    dataset: &Dataset,
+    batch: &RecordBatch,
+    column: &str,
+) -> Result<Vec<Option<BlobHandle>>> {
+    let blob_field = dataset.schema().field(column).ok_or_else(|| {
+        Error::invalid_input(
+            format!("the column '{}' does not exist in the dataset", column),
+            location!(),
+        )
+    })?;
+    check_blob_field(blob_field, column)?;
+    let descriptions = batch.column_by_name(column).ok_or_else(|| {
+        Error::invalid_input(
+            format!("the batch does not contain the column '{}'", column),
+            location!(),
+        )
+    })?;
+    let Some(descriptions) = descriptions.as_struct_opt() else {
+        return Err(Error::invalid_input(
+            format!(
+                "the column '{}' must contain the blob descriptions returned by a scan but has type {}",
+                column,
+                descriptions.data_type()
+            ),
+            location!(),
+        ));
+    };
+    let row_addrs = batch
+        .column_by_name(ROW_ADDR)
+        .ok_or_else(|| {
+            Error::invalid_input(
+                "the batch must contain the row addresses, scan with the row address to get them",
+                location!(),
+            )
+        })?
+        .as_primitive::<UInt64Type>();
+    let positions = descriptions.column(0).as_primitive::<UInt64Type>();
+    let sizes = descriptions.column(1).as_primitive::<UInt64Type>();
+
+    let mut data_files = HashMap::new();
+    let mut handles = Vec::with_capacity(batch.num_rows());
+    for idx in 0..batch.num_rows() {
+        if descriptions.is_null(idx) || positions.is_null(idx) || sizes.is_null(idx) {
+            handles.push(None);
+            continue;
+        }
+        let fragment_id = RowAddress::from(row_addrs.value(idx)).fragment_id();
+        if !data_files.contains_key(&fragment_id) {
+            let relative_path = dataset
+                .get_fragment(fragment_id as usize)
+                .and_then(|fragment| {
+                    fragment
+                        .data_file_for_field(blob_field.id as u32)
+                        .map(|data_file| data_file.path.clone())
+                })
+                .ok_or_else(|| {
+                    Error::invalid_input(
+                        format!(
+                            "the fragment {} has no data file for the column '{}'",
+                            fragment_id, column
+                        ),
+                        location!(),
+                    )
+                })?;
+            let uri = format!(
+                "{}/{}/{}",
+                dataset.uri().trim_end_matches('/'),
+                DATA_DIR,
+                relative_path
+            );
+            let data_file = dataset.data_dir().child(relative_path);
+            data_files.insert(fragment_id, (uri, data_file));
+        }
+        let (uri, data_file) = &data_files[&fragment_id];
+        handles.push(Some(BlobHandle {
+            uri: uri.clone(),
+            data_file: data_file.clone(),
+            position: positions.value(idx),
+            size: sizes.value(idx),
+        }));
+    }
+    Ok(handles)
+}
+
 pub trait BlobStreamExt: Sized {
     /// Splits a stream into a regular portion (the first stream)
     /// and a blob portion (the second stream)
@@ -402,6 +547,45 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    pub async fn test_blob_handles() {
+        let fixture = BlobTestFixture::new().await;
+
+        // Scans return the position and size of the blobs instead of their bytes
+        let batch = fixture
+            .dataset
+            .scan()
+            .project(&["blobs"])
+            .unwrap()
+            .filter("filterme >= 50")
+            .unwrap()
+            .with_row_address()
+            .try_into_batch()
+            .await
+            .unwrap();
+        assert!(batch.column(0).as_struct_opt().is_some());
+
+        let handles = fixture.dataset.blob_handles(&batch, "blobs").unwrap();
+        assert_eq!(handles.len(), 50);
+        let handle = handles[17].as_ref().unwrap();
+        assert!(handle.uri.starts_with(fixture.dataset.uri()));
+        assert!(handle.uri.ends_with(handle.data_file.filename().unwrap()));
+
+        let expected = fixture.data[6].column(1).as_binary::<i64>().value(7);
+        let blob = fixture.dataset.open_blob(handle);
+        assert_eq!(blob.size(), expected.len() as u64);
+        assert_eq!(&blob.read_range(10..20).await.unwrap(), &expected[10..20]);
+        assert_eq!(&blob.read_range(0..5).await.unwrap(), &expected[0..5]);
+        // Range reads don't move the cursor
+        assert_eq!(blob.tell().await.unwrap(), 0);
+        assert_eq!(&blob.read().await.unwrap(), expected);
+        assert!(blob.read_range(0..blob.size() + 1).await.is_err());
+
+        // The row addresses are required
+        let batch = batch.project(&[0]).unwrap();
+        assert!(fixture.dataset.blob_handles(&batch, "blobs").is_err());
+    }
+
     #[tokio::test]
     pub async fn test_take_blob_id_not_exist() {
         let fixture = BlobTestFixture::new().await;
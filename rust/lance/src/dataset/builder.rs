@@ -2,7 +2,9 @@
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use lance_core::utils::parse::str_is_truthy;
 use lance_file::datatypes::populate_schema_dictionary;
+use lance_index::DatasetIndexExt;
 use lance_io::object_store::{
     ObjectStore, ObjectStoreParams, StorageOptions, DEFAULT_CLOUD_IO_PARALLELISM,
 };
@@ -20,9 +22,17 @@ use super::refs::{Ref, Tags};
 use super::{ReadParams, WriteParams, DEFAULT_INDEX_CACHE_SIZE, DEFAULT_METADATA_CACHE_SIZE};
 use crate::{
     error::{Error, Result},
+    io::commit::manifest_cache_key,
     session::Session,
     Dataset,
 };
+
+/// Datasets opened without a session use [`Session::shared`] if the
+/// `LANCE_SHARED_SESSION` environment variable is set. The cache sizes given
+/// to the builder are ignored then.
+fn use_shared_session() -> bool {
+    std::env::var("LANCE_SHARED_SESSION").is_ok_and(|value| str_is_truthy(&value))
+}
 /// builder for loading a [`Dataset`].
 #[derive(Debug, Clone)]
 pub struct DatasetBuilder {
@@ -31,6 +41,9 @@ pub struct DatasetBuilder {
     /// Metadata cache size for the fragment metadata. If it is zero, metadata
     /// cache is disabled.
     metadata_cache_size_bytes: usize,
+    /// Whether to load the index metadata when the dataset is opened, instead of
+    /// on first use.
+    prefetch_index_metadata: bool,
     /// Optional pre-loaded manifest to avoid loading it again.
     manifest: Option<Manifest>,
     session: Option<Arc<Session>>,
@@ -45,6 +58,7 @@ impl DatasetBuilder {
         Self {
             index_cache_size: DEFAULT_INDEX_CACHE_SIZE,
             metadata_cache_size_bytes: DEFAULT_METADATA_CACHE_SIZE,
+            prefetch_index_metadata: false,
             table_uri: table_uri.as_ref().to_string(),
             options: ObjectStoreParams::default(),
             commit_handler: None,
@@ -58,6 +72,12 @@ impl DatasetBuilder {
 // Much of this builder is directly inspired from the to delta-rs table builder implementation
 // https://github.com/delta-io/delta-rs/main/crates/deltalake-core/src/table/builder.rs
 impl DatasetBuilder {
+    /// Open the dataset read-only, see [`ReadParams::read_only`].
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.options.read_only = read_only;
+        self
+    }
+
     /// Set the cache size for indices. Set to zero, to disable the cache.
     pub fn with_index_cache_size(mut self, cache_size: usize) -> Self {
         self.index_cache_size = cache_size;
@@ -71,6 +91,16 @@ impl DatasetBuilder {
         self
     }
 
+    /// Load the index metadata when the dataset is opened.
+    ///
+    /// By default the index metadata is loaded on first use, so opening a dataset
+    /// with many indices stays fast. Prefetching moves that cost to the open, which
+    /// is useful when the dataset will be queried through its indices right away.
+    pub fn with_index_metadata_prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch_index_metadata = prefetch;
+        self
+    }
+
     /// Set the cache size for the file metadata. Set to zero to disable this cache.
     #[deprecated(
         since = "0.30.0",
@@ -122,6 +152,17 @@ impl DatasetBuilder {
         self
     }
 
+    /// Sets the azure credentials provider.
+    /// This only applies to azure object store.
+    #[cfg(feature = "azure")]
+    pub fn with_azure_credentials_provider(
+        mut self,
+        credentials: object_store::azure::AzureCredentialProvider,
+    ) -> Self {
+        self.options.azure_credentials = Some(credentials);
+        self
+    }
+
     /// Directly set the object store to use.
     #[deprecated(note = "Implement an ObjectStoreProvider instead")]
     #[allow(deprecated)]
@@ -190,6 +231,10 @@ impl DatasetBuilder {
             self.commit_handler = Some(commit_handler);
         }
 
+        if read_params.read_only {
+            self.options.read_only = true;
+        }
+
         self
     }
 
@@ -273,12 +318,14 @@ impl DatasetBuilder {
     pub async fn load(mut self) -> Result<Dataset> {
         let session = match self.session.as_ref() {
             Some(session) => session.clone(),
+            None if use_shared_session() => Session::shared(),
             None => Arc::new(Session::new(
                 self.index_cache_size,
                 self.metadata_cache_size_bytes,
                 Default::default(),
             )),
         };
+        self.options = session.config().store_params(&self.options);
 
         let mut version: Option<u64> = None;
         let cloned_ref = self.version.clone();
@@ -287,6 +334,8 @@ impl DatasetBuilder {
         // How do we detect which version scheme is in use?
 
         let manifest = self.manifest.take();
+        let read_only = self.options.read_only;
+        let prefetch_index_metadata = self.prefetch_index_metadata;
 
         let (object_store, base_path, commit_handler) = self.build_object_store().await?;
 
@@ -312,7 +361,7 @@ impl DatasetBuilder {
                 let reader = object_store.open(&location.path).await?;
                 populate_schema_dictionary(&mut manifest.schema, reader.as_ref()).await?;
             }
-            (manifest, location)
+            (Arc::new(manifest), location)
         } else {
             let manifest_location = match version {
                 Some(version) => {
@@ -330,24 +379,35 @@ impl DatasetBuilder {
                     })?,
             };
 
-            let manifest = Dataset::load_manifest(
-                &object_store,
-                &manifest_location,
-                &base_path,
-                session.as_ref(),
-            )
-            .await?;
+            // Datasets opened with the same session share the manifests they read
+            let manifest = session
+                .metadata_cache
+                .with_key_prefix(&table_uri)
+                .get_or_insert(manifest_cache_key(&manifest_location), |_| {
+                    Dataset::load_manifest(
+                        &object_store,
+                        &manifest_location,
+                        &base_path,
+                        session.as_ref(),
+                    )
+                })
+                .await?;
             (manifest, manifest_location)
         };
 
-        Dataset::checkout_manifest(
+        let dataset = Dataset::checkout_manifest(
             object_store,
             base_path,
             table_uri,
-            Arc::new(manifest),
+            manifest,
             location,
             session,
             commit_handler,
-        )
+            read_only,
+        )?;
+        if prefetch_index_metadata {
+            dataset.load_indices().await?;
+        }
+        Ok(dataset)
     }
 }
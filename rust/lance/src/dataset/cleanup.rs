@@ -77,6 +77,105 @@ pub struct RemovalStats {
     pub old_versions: u64,
 }
 
+/// Options controlling which versions and files a cleanup removes.
+#[derive(Clone, Debug)]
+pub struct CleanupPolicy {
+    /// Cleanup all versions before this time
+    pub before: DateTime<Utc>,
+    /// If true, delete unverified data files even if they are recent
+    pub delete_unverified: bool,
+    /// If true, return an Error if a tagged version is old
+    pub error_if_tagged_old_versions: bool,
+    /// If true, nothing is deleted. The returned [CleanupReport] describes
+    /// what would have been removed.
+    pub dry_run: bool,
+    /// Rules limiting how long tags protect the versions they reference.
+    ///
+    /// The first rule matching a tag applies. Tags that match no rule protect
+    /// their version forever.
+    pub tag_retention: Vec<TagRetention>,
+}
+
+impl CleanupPolicy {
+    pub fn new(before: DateTime<Utc>) -> Self {
+        Self {
+            before,
+            delete_unverified: false,
+            error_if_tagged_old_versions: true,
+            dry_run: false,
+            tag_retention: Vec::new(),
+        }
+    }
+
+    /// The time before which a version referenced by `tag` is no longer
+    /// protected. `None` means the version is protected forever.
+    fn tag_protected_since(&self, tag: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.tag_retention
+            .iter()
+            .find(|rule| rule.matches(tag))
+            .and_then(|rule| rule.keep_for)
+            .map(|keep_for| now - keep_for)
+    }
+}
+
+/// A retention window for tagged versions.
+///
+/// By default a tag keeps the version it references forever. A rule can
+/// instead let the tag expire once the tagged version is older than
+/// `keep_for`. An expired tag no longer protects its version, which is then
+/// subject to the regular [CleanupPolicy::before] threshold. If the version is
+/// removed, the tag is removed with it.
+#[derive(Clone, Debug)]
+pub struct TagRetention {
+    /// The tag name this rule applies to. A trailing `*` matches every tag
+    /// with the preceding prefix, e.g. `nightly-*`.
+    pub pattern: String,
+    /// How long the tagged version is protected, measured from the time the
+    /// version was created. `None` protects it forever.
+    pub keep_for: Option<TimeDelta>,
+}
+
+impl TagRetention {
+    pub fn new(pattern: impl Into<String>, keep_for: Option<TimeDelta>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            keep_for,
+        }
+    }
+
+    fn matches(&self, tag: &str) -> bool {
+        match self.pattern.strip_suffix('*') {
+            Some(prefix) => tag.starts_with(prefix),
+            None => tag == self.pattern,
+        }
+    }
+}
+
+/// A description of the files removed (or, in a dry run, that would be
+/// removed) by a cleanup.
+#[derive(Clone, Debug, Default)]
+pub struct CleanupReport {
+    /// Versions whose manifests are removed and which can no longer be
+    /// checked out, in ascending order.
+    pub unreachable_versions: Vec<u64>,
+    /// Paths of all removed files, including manifests.
+    pub removed_files: Vec<Path>,
+    /// Total size of the removed files.
+    pub bytes_removed: u64,
+    /// Tags whose retention window expired and whose version was removed.
+    /// These tags are removed as well.
+    pub expired_tags: Vec<String>,
+}
+
+impl From<&CleanupReport> for RemovalStats {
+    fn from(report: &CleanupReport) -> Self {
+        Self {
+            bytes_removed: report.bytes_removed,
+            old_versions: report.unreachable_versions.len() as u64,
+        }
+    }
+}
+
 fn remove_prefix(path: &Path, prefix: &Path) -> Path {
     let relative_parts = path.prefix_match(prefix);
     if relative_parts.is_none() {
@@ -88,18 +187,14 @@ fn remove_prefix(path: &Path, prefix: &Path) -> Path {
 #[derive(Clone, Debug)]
 struct CleanupTask<'a> {
     dataset: &'a Dataset,
-    /// Cleanup all versions before this time
-    before: DateTime<Utc>,
-    /// If true, delete unverified data files even if they are recent
-    delete_unverified: bool,
-    /// If true, return an Error if a tagged version is old
-    error_if_old_versions_tagged: bool,
+    policy: CleanupPolicy,
 }
 
 /// Information about the dataset that we learn by inspecting all of the manifests
 #[derive(Clone, Debug, Default)]
 struct CleanupInspection {
     old_manifests: Vec<Path>,
+    old_versions: Vec<u64>,
     /// Referenced files are part of our working set
     referenced_files: ReferencedFiles,
     /// Verified files may or may not be part of the working set but they are
@@ -115,21 +210,11 @@ struct CleanupInspection {
 const UNVERIFIED_THRESHOLD_DAYS: i64 = 7;
 
 impl<'a> CleanupTask<'a> {
-    fn new(
-        dataset: &'a Dataset,
-        before: DateTime<Utc>,
-        delete_unverified: bool,
-        error_if_old_versions_tagged: bool,
-    ) -> Self {
-        Self {
-            dataset,
-            before,
-            delete_unverified,
-            error_if_old_versions_tagged,
-        }
+    fn new(dataset: &'a Dataset, policy: CleanupPolicy) -> Self {
+        Self { dataset, policy }
     }
 
-    async fn run(self) -> Result<RemovalStats> {
+    async fn run(self) -> Result<CleanupReport> {
         // First we process all manifest files in parallel to figure
         // out which files are referenced by valid manifests
 
@@ -138,27 +223,57 @@ impl<'a> CleanupTask<'a> {
         // or clean around the manifest
 
         let tags = self.dataset.tags.list().await?;
-        let tagged_versions: HashSet<u64> = tags
-            .values()
-            .map(|tag_content| tag_content.version)
-            .collect();
+        // For each tagged version, the time before which it is no longer
+        // protected. If several tags reference the same version, the most
+        // protective one wins.
+        let now = utc_now();
+        let mut tagged_versions: HashMap<u64, Option<DateTime<Utc>>> = HashMap::new();
+        for (name, tag_content) in tags.iter() {
+            let protected_since = self.policy.tag_protected_since(name, now);
+            tagged_versions
+                .entry(tag_content.version)
+                .and_modify(|current| {
+                    *current = match (*current, protected_since) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        _ => None,
+                    }
+                })
+                .or_insert(protected_since);
+        }
 
         let inspection = self.process_manifests(&tagged_versions).await?;
 
-        if self.error_if_old_versions_tagged && !inspection.tagged_old_versions.is_empty() {
+        if self.policy.error_if_tagged_old_versions && !inspection.tagged_old_versions.is_empty() {
             return Err(tagged_old_versions_cleanup_error(
                 &tags,
                 &inspection.tagged_old_versions,
             ));
         }
 
-        self.delete_unreferenced_files(inspection).await
+        let mut expired_tags = tags
+            .into_iter()
+            .filter(|(_, contents)| inspection.old_versions.contains(&contents.version))
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        expired_tags.sort();
+
+        let mut report = self.delete_unreferenced_files(inspection).await?;
+
+        if !self.policy.dry_run {
+            let mut refs = self.dataset.tags.clone();
+            for tag in &expired_tags {
+                refs.delete(tag).await?;
+            }
+        }
+        report.expired_tags = expired_tags;
+
+        Ok(report)
     }
 
     #[instrument(level = "debug", skip_all)]
     async fn process_manifests(
         &'a self,
-        tagged_versions: &HashSet<u64>,
+        tagged_versions: &HashMap<u64, Option<DateTime<Utc>>>,
     ) -> Result<CleanupInspection> {
         let inspection = Mutex::new(CleanupInspection::default());
         self.dataset
@@ -175,7 +290,7 @@ impl<'a> CleanupTask<'a> {
         &self,
         location: ManifestLocation,
         inspection: &Mutex<CleanupInspection>,
-        tagged_versions: &HashSet<u64>,
+        tagged_versions: &HashMap<u64, Option<DateTime<Utc>>>,
     ) -> Result<()> {
         // TODO: We can't cleanup invalid manifests.  There is no way to distinguish
         // between an invalid manifest and a temporary I/O error.  It's also not safe
@@ -188,11 +303,16 @@ impl<'a> CleanupTask<'a> {
         let dataset_version = self.dataset.version().version;
 
         // Don't delete the latest version, even if it is old. Don't delete tagged versions,
-        // regardless of age. Don't delete manifests if their version is newer than the dataset
-        // version.  These are either in-progress or newly added since we started.
+        // regardless of age, unless the retention window of every tag has expired. Don't delete
+        // manifests if their version is newer than the dataset version.  These are either
+        // in-progress or newly added since we started.
         let is_latest = dataset_version <= manifest.version;
-        let is_tagged = tagged_versions.contains(&manifest.version);
-        let in_working_set = is_latest || manifest.timestamp() >= self.before || is_tagged;
+        let is_tagged = match tagged_versions.get(&manifest.version) {
+            Some(Some(protected_since)) => manifest.timestamp() >= *protected_since,
+            Some(None) => true,
+            None => false,
+        };
+        let in_working_set = is_latest || manifest.timestamp() >= self.policy.before || is_tagged;
         let indexes =
             read_manifest_indexes(&self.dataset.object_store, &location, &manifest).await?;
 
@@ -206,6 +326,7 @@ impl<'a> CleanupTask<'a> {
         self.process_manifest(&manifest, &indexes, in_working_set, &mut inspection)?;
         if !in_working_set {
             inspection.old_manifests.push(location.path.clone());
+            inspection.old_versions.push(manifest.version);
         }
         Ok(())
     }
@@ -253,34 +374,35 @@ impl<'a> CleanupTask<'a> {
         Ok(())
     }
 
-    #[instrument(level = "debug", skip_all, fields(old_versions = inspection.old_manifests.len(), bytes_removed = tracing::field::Empty))]
+    #[instrument(level = "debug", skip_all, fields(old_versions = inspection.old_manifests.len(), bytes_removed = tracing::field::Empty, dry_run = self.policy.dry_run))]
     async fn delete_unreferenced_files(
         &self,
         inspection: CleanupInspection,
-    ) -> Result<RemovalStats> {
-        let removal_stats = Mutex::new(RemovalStats::default());
+    ) -> Result<CleanupReport> {
+        let report = Mutex::new(CleanupReport::default());
         let verification_threshold = utc_now()
             - TimeDelta::try_days(UNVERIFIED_THRESHOLD_DAYS).expect("TimeDelta::try_days");
         let unreferenced_paths = self
             .dataset
             .object_store
-            .read_dir_all(&self.dataset.base, Some(self.before))
+            .read_dir_all(&self.dataset.base, Some(self.policy.before))
             .try_filter_map(|obj_meta| {
                 // If a file is new-ish then it might be part of an ongoing operation and so we only
                 // delete it if we can verify it is part of an old version.
-                let maybe_in_progress =
-                    !self.delete_unverified && obj_meta.last_modified >= verification_threshold;
+                let maybe_in_progress = !self.policy.delete_unverified
+                    && obj_meta.last_modified >= verification_threshold;
                 let path_to_remove =
                     self.path_if_not_referenced(obj_meta.location, maybe_in_progress, &inspection);
-                if matches!(path_to_remove, Ok(Some(..))) {
-                    removal_stats.lock().unwrap().bytes_removed += obj_meta.size;
+                if let Ok(Some(path)) = &path_to_remove {
+                    let mut report = report.lock().unwrap();
+                    report.bytes_removed += obj_meta.size;
+                    report.removed_files.push(path.clone());
                 }
                 future::ready(path_to_remove)
             })
             .boxed();
 
         let old_manifests = inspection.old_manifests.clone();
-        let num_old_manifests = old_manifests.len();
 
         // Ideally this collect shouldn't be needed here but it seems necessary
         // to avoid https://github.com/rust-lang/rust/issues/102211
@@ -293,31 +415,47 @@ impl<'a> CleanupTask<'a> {
             .try_fold(0, |acc, size| async move { Ok(acc + (size)) })
             .await;
 
-        let old_manifests_stream = stream::iter(old_manifests)
+        let old_manifests_stream = stream::iter(old_manifests.clone())
             .map(|path| {
-                info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE, r#type=AUDIT_TYPE_MANIFEST, path = path.to_string());
+                self.audit_removal(AUDIT_MODE_DELETE, AUDIT_TYPE_MANIFEST, &path);
                 Ok(path)
             })
             .boxed();
         let all_paths_to_remove =
             stream::iter(vec![unreferenced_paths, old_manifests_stream]).flatten();
 
-        let delete_fut = self
-            .dataset
-            .object_store
-            .remove_stream(all_paths_to_remove.boxed())
-            .try_for_each(|_| future::ready(Ok(())));
-
-        delete_fut.await?;
+        if self.policy.dry_run {
+            // Still drive the stream so the report is filled in.
+            all_paths_to_remove
+                .try_for_each(|_| future::ready(Ok(())))
+                .await?;
+        } else {
+            self.dataset
+                .object_store
+                .remove_stream(all_paths_to_remove.boxed())
+                .try_for_each(|_| future::ready(Ok(())))
+                .await?;
+        }
 
-        let mut removal_stats = removal_stats.into_inner().unwrap();
-        removal_stats.old_versions = num_old_manifests as u64;
-        removal_stats.bytes_removed += manifest_bytes_removed?;
+        let mut report = report.into_inner().unwrap();
+        report.unreachable_versions = inspection.old_versions;
+        report.unreachable_versions.sort_unstable();
+        report.removed_files.extend(old_manifests);
+        report.bytes_removed += manifest_bytes_removed?;
 
         let span = Span::current();
-        span.record("bytes_removed", removal_stats.bytes_removed);
+        span.record("bytes_removed", report.bytes_removed);
 
-        Ok(removal_stats)
+        Ok(report)
+    }
+
+    /// Record the removal of a file in the audit log.
+    ///
+    /// Nothing is recorded for dry runs, since nothing is removed.
+    fn audit_removal(&self, mode: &str, file_type: &str, path: &Path) {
+        if !self.policy.dry_run {
+            info!(target: TRACE_FILE_AUDIT, mode=mode, r#type=file_type, path = path.to_string());
+        }
     }
 
     fn path_if_not_referenced(
@@ -349,14 +487,14 @@ impl<'a> CleanupTask<'a> {
                 {
                     return Ok(None);
                 } else if !maybe_in_progress {
-                    info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE_UNVERIFIED, r#type=AUDIT_TYPE_INDEX, path = path.to_string());
+                    self.audit_removal(AUDIT_MODE_DELETE_UNVERIFIED, AUDIT_TYPE_INDEX, &path);
                     return Ok(Some(path));
                 } else if inspection
                     .verified_files
                     .index_uuids
                     .contains(uuid.as_ref())
                 {
-                    info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE, r#type=AUDIT_TYPE_INDEX, path = path.to_string());
+                    self.audit_removal(AUDIT_MODE_DELETE, AUDIT_TYPE_INDEX, &path);
                     return Ok(Some(path));
                 }
             } else {
@@ -373,14 +511,14 @@ impl<'a> CleanupTask<'a> {
                     {
                         Ok(None)
                     } else if !maybe_in_progress {
-                        info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE_UNVERIFIED, r#type=AUDIT_TYPE_DATA, path = path.to_string());
+                        self.audit_removal(AUDIT_MODE_DELETE_UNVERIFIED, AUDIT_TYPE_DATA, &path);
                         Ok(Some(path))
                     } else if inspection
                         .verified_files
                         .data_paths
                         .contains(&relative_path)
                     {
-                        info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE, r#type=AUDIT_TYPE_DATA, path = path.to_string());
+                        self.audit_removal(AUDIT_MODE_DELETE, AUDIT_TYPE_DATA, &path);
                         Ok(Some(path))
                     } else {
                         Ok(None)
@@ -403,14 +541,18 @@ impl<'a> CleanupTask<'a> {
                     {
                         Ok(None)
                     } else if !maybe_in_progress {
-                        info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE_UNVERIFIED, r#type=AUDIT_TYPE_DELETION, path = path.to_string());
+                        self.audit_removal(
+                            AUDIT_MODE_DELETE_UNVERIFIED,
+                            AUDIT_TYPE_DELETION,
+                            &path,
+                        );
                         Ok(Some(path))
                     } else if inspection
                         .verified_files
                         .delete_paths
                         .contains(&relative_path)
                     {
-                        info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_DELETE, r#type=AUDIT_TYPE_DELETION, path = path.to_string());
+                        self.audit_removal(AUDIT_MODE_DELETE, AUDIT_TYPE_DELETION, &path);
                         Ok(Some(path))
                     } else {
                         Ok(None)
@@ -461,13 +603,28 @@ pub async fn cleanup_old_versions(
     delete_unverified: Option<bool>,
     error_if_tagged_old_versions: Option<bool>,
 ) -> Result<RemovalStats> {
-    let cleanup = CleanupTask::new(
-        dataset,
-        before,
-        delete_unverified.unwrap_or(false),
-        error_if_tagged_old_versions.unwrap_or(true),
-    );
-    cleanup.run().await
+    let policy = CleanupPolicy {
+        delete_unverified: delete_unverified.unwrap_or(false),
+        error_if_tagged_old_versions: error_if_tagged_old_versions.unwrap_or(true),
+        ..CleanupPolicy::new(before)
+    };
+    let report = cleanup_with_policy(dataset, policy).await?;
+    Ok(RemovalStats::from(&report))
+}
+
+/// Runs a cleanup according to `policy` and reports what was removed.
+///
+/// If [CleanupPolicy::dry_run] is set then no files are deleted and the
+/// report describes what a real cleanup with the same policy would remove.
+/// Files written between the dry run and a subsequent cleanup may change
+/// the outcome.
+pub async fn cleanup_with_policy(
+    dataset: &Dataset,
+    policy: CleanupPolicy,
+) -> Result<CleanupReport> {
+    dataset.ensure_writable("clean up")?;
+    let dataset = dataset.for_background();
+    CleanupTask::new(&dataset, policy).run().await
 }
 
 /// If the dataset config has `lance.auto_cleanup` parameters set,
@@ -870,6 +1027,52 @@ mod tests {
         assert_gt!(after_count.num_tx_files, 0);
     }
 
+    #[tokio::test]
+    async fn cleanup_dry_run() {
+        let fixture = MockDatasetFixture::try_new().unwrap();
+        fixture.create_some_data().await.unwrap();
+        fixture.overwrite_some_data().await.unwrap();
+        fixture.overwrite_some_data().await.unwrap();
+
+        fixture
+            .clock
+            .set_system_time(TimeDelta::try_days(10).unwrap());
+
+        let before = utc_now() - TimeDelta::try_days(8).unwrap();
+        let before_count = fixture.count_files().await.unwrap();
+
+        let db = fixture.open().await.unwrap();
+        let report = db
+            .cleanup_with_policy(CleanupPolicy {
+                dry_run: true,
+                ..CleanupPolicy::new(before)
+            })
+            .await
+            .unwrap();
+
+        // Nothing was deleted
+        assert_eq!(fixture.count_files().await.unwrap(), before_count);
+        assert_eq!(report.unreachable_versions, vec![1, 2]);
+        assert_eq!(
+            report
+                .removed_files
+                .iter()
+                .filter(|path| path.extension() == Some("manifest"))
+                .count(),
+            2
+        );
+
+        // A real cleanup removes exactly what was reported
+        let removed = fixture.run_cleanup(before).await.unwrap();
+        let after_count = fixture.count_files().await.unwrap();
+        assert_eq!(removed.old_versions, 2);
+        assert_eq!(removed.bytes_removed, report.bytes_removed);
+        assert_eq!(
+            before_count.num_bytes - after_count.num_bytes,
+            report.bytes_removed
+        );
+    }
+
     #[tokio::test]
     async fn do_not_cleanup_newer_data() {
         // Even though an old manifest is removed the data files should
@@ -952,6 +1155,54 @@ mod tests {
         assert_eq!(removed.old_versions, 2);
     }
 
+    #[tokio::test]
+    async fn cleanup_tag_retention() {
+        let fixture = MockDatasetFixture::try_new().unwrap();
+        fixture.create_some_data().await.unwrap();
+        fixture.overwrite_some_data().await.unwrap();
+        fixture.overwrite_some_data().await.unwrap();
+
+        let mut dataset = *(fixture.open().await.unwrap());
+        dataset.tags.create("nightly-1", 1).await.unwrap();
+        dataset.tags.create("release-1", 2).await.unwrap();
+
+        fixture
+            .clock
+            .set_system_time(TimeDelta::try_days(10).unwrap());
+
+        let policy = CleanupPolicy {
+            tag_retention: vec![
+                TagRetention::new("nightly-*", Some(TimeDelta::try_days(5).unwrap())),
+                TagRetention::new("release-1", None),
+            ],
+            error_if_tagged_old_versions: false,
+            ..CleanupPolicy::new(utc_now() - TimeDelta::try_days(8).unwrap())
+        };
+
+        let db = fixture.open().await.unwrap();
+        let report = db
+            .cleanup_with_policy(CleanupPolicy {
+                dry_run: true,
+                ..policy.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.unreachable_versions, vec![1]);
+        assert_eq!(report.expired_tags, vec!["nightly-1".to_string()]);
+        // Dry runs leave tags alone
+        assert_eq!(dataset.tags.list().await.unwrap().len(), 2);
+
+        let report = db.cleanup_with_policy(policy).await.unwrap();
+        assert_eq!(report.unreachable_versions, vec![1]);
+        assert_eq!(report.expired_tags, vec!["nightly-1".to_string()]);
+
+        let tags = dataset.tags.list().await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags["release-1"].version, 2);
+        assert!(dataset.checkout_version(1).await.is_err());
+        dataset.checkout_version(2).await.unwrap();
+    }
+
     #[tokio::test]
     async fn cleanup_around_tagged_old_versions() {
         // We should not clean up old versions that are tagged.
@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Human readable and JSON dumps of a dataset's metadata, for support and debugging.
+//!
+//! Each function describes one part of the checked out version of a dataset:
+//!
+//! * [`dump_manifest`]: the manifest's version information, settings and schema.
+//! * [`dump_fragments`]: the fragments, with their data files and deletion files.
+//! * [`dump_deletion_files`]: just the deletion files.
+//! * [`dump_indices`]: the index metadata.
+//!
+//! The results implement [`std::fmt::Display`] for reading and [`serde::Serialize`] for
+//! JSON output.  The `lance-cli` binary prints them for any version of a dataset.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use lance_core::datatypes::Field;
+use lance_core::Result;
+use lance_index::DatasetIndexExt;
+use lance_table::format::{DeletionFile, DeletionFileType, Fragment};
+use lance_table::io::deletion::deletion_file_path;
+use object_store::path::Path;
+use serde::Serialize;
+
+use crate::Dataset;
+
+/// The manifest of a version, without the fragments, see [`dump_manifest`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestDump {
+    pub uri: String,
+    pub version: u64,
+    /// When the version was committed, in RFC 3339 format
+    pub timestamp: String,
+    pub tag: Option<String>,
+    /// The library and version that wrote the manifest
+    pub writer_version: Option<String>,
+    pub data_storage_version: String,
+    pub reader_feature_flags: u64,
+    pub writer_feature_flags: u64,
+    pub max_fragment_id: Option<u32>,
+    pub next_row_id: u64,
+    pub num_fragments: usize,
+    pub transaction_file: Option<String>,
+    pub blob_dataset_version: Option<u64>,
+    pub config: BTreeMap<String, String>,
+    pub transaction_properties: BTreeMap<String, String>,
+    pub schema: Vec<FieldDump>,
+}
+
+/// A field of the schema, nested fields are listed after their parent
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDump {
+    pub id: i32,
+    pub parent_id: i32,
+    /// The path of the field, with the names of the parents separated by `.`
+    pub path: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+/// A fragment, see [`dump_fragments`]
+#[derive(Debug, Clone, Serialize)]
+pub struct FragmentDump {
+    pub id: u64,
+    pub physical_rows: Option<usize>,
+    pub files: Vec<DataFileDump>,
+    pub deletion_file: Option<DeletionFileDump>,
+    /// Whether the fragment stores row ids (datasets with stable row ids)
+    pub has_row_ids: bool,
+}
+
+/// A data file of a fragment
+#[derive(Debug, Clone, Serialize)]
+pub struct DataFileDump {
+    /// The path, relative to the `data` directory
+    pub path: String,
+    pub fields: Vec<i32>,
+    pub file_version: String,
+    /// The size recorded in the manifest, if any
+    pub size_bytes: Option<u64>,
+}
+
+/// A deletion file, see [`dump_deletion_files`]
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionFileDump {
+    pub fragment_id: u64,
+    /// The path, relative to the dataset root
+    pub path: String,
+    pub file_type: String,
+    pub read_version: u64,
+    pub num_deleted_rows: Option<usize>,
+}
+
+/// The metadata of an index, see [`dump_indices`]
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexDump {
+    pub name: String,
+    pub uuid: String,
+    pub columns: Vec<String>,
+    /// The version of the dataset the index was last updated at
+    pub dataset_version: u64,
+    /// The fragments covered by the index, if known
+    pub fragment_ids: Option<Vec<u32>>,
+    /// The type URL of the index details, which identifies the type of index
+    pub details_type: Option<String>,
+    pub index_version: i32,
+    pub created_at: Option<String>,
+}
+
+fn field_dumps(fields: &[Field], prefix: &str, dumps: &mut Vec<FieldDump>) {
+    for field in fields {
+        let path = if prefix.is_empty() {
+            field.name.clone()
+        } else {
+            format!("{}.{}", prefix, field.name)
+        };
+        dumps.push(FieldDump {
+            id: field.id,
+            parent_id: field.parent_id,
+            path: path.clone(),
+            data_type: field.data_type().to_string(),
+            nullable: field.nullable,
+        });
+        field_dumps(&field.children, &path, dumps);
+    }
+}
+
+fn deletion_file_dump(fragment_id: u64, deletion_file: &DeletionFile) -> DeletionFileDump {
+    DeletionFileDump {
+        fragment_id,
+        path: deletion_file_path(&Path::default(), fragment_id, deletion_file).to_string(),
+        file_type: match deletion_file.file_type {
+            DeletionFileType::Array => "array",
+            DeletionFileType::Bitmap => "bitmap",
+        }
+        .to_string(),
+        read_version: deletion_file.read_version,
+        num_deleted_rows: deletion_file.num_deleted_rows,
+    }
+}
+
+fn fragment_dump(fragment: &Fragment) -> FragmentDump {
+    FragmentDump {
+        id: fragment.id,
+        physical_rows: fragment.physical_rows,
+        files: fragment
+            .files
+            .iter()
+            .map(|file| DataFileDump {
+                path: file.path.clone(),
+                fields: file.fields.clone(),
+                file_version: format!("{}.{}", file.file_major_version, file.file_minor_version),
+                size_bytes: file.file_size_bytes.get().map(u64::from),
+            })
+            .collect(),
+        deletion_file: fragment
+            .deletion_file
+            .as_ref()
+            .map(|deletion_file| deletion_file_dump(fragment.id, deletion_file)),
+        has_row_ids: fragment.row_id_meta.is_some(),
+    }
+}
+
+/// Describe the manifest of the checked out version
+pub fn dump_manifest(dataset: &Dataset) -> ManifestDump {
+    let manifest = dataset.manifest();
+    let mut schema = Vec::new();
+    field_dumps(&manifest.schema.fields, "", &mut schema);
+    ManifestDump {
+        uri: dataset.uri().to_string(),
+        version: manifest.version,
+        timestamp: manifest.timestamp().to_rfc3339(),
+        tag: manifest.tag.clone(),
+        writer_version: manifest
+            .writer_version
+            .as_ref()
+            .map(|writer| format!("{} {}", writer.library, writer.version)),
+        data_storage_version: manifest.data_storage_format.version.clone(),
+        reader_feature_flags: manifest.reader_feature_flags,
+        writer_feature_flags: manifest.writer_feature_flags,
+        max_fragment_id: manifest.max_fragment_id,
+        next_row_id: manifest.next_row_id,
+        num_fragments: manifest.fragments.len(),
+        transaction_file: manifest.transaction_file.clone(),
+        blob_dataset_version: manifest.blob_dataset_version,
+        config: manifest.config.clone().into_iter().collect(),
+        transaction_properties: manifest
+            .transaction_properties
+            .clone()
+            .into_iter()
+            .collect(),
+        schema,
+    }
+}
+
+/// Describe the fragments of the checked out version
+pub fn dump_fragments(dataset: &Dataset) -> Vec<FragmentDump> {
+    dataset
+        .manifest()
+        .fragments
+        .iter()
+        .map(fragment_dump)
+        .collect()
+}
+
+/// Describe the deletion files of the checked out version
+pub fn dump_deletion_files(dataset: &Dataset) -> Vec<DeletionFileDump> {
+    dataset
+        .manifest()
+        .fragments
+        .iter()
+        .filter_map(|fragment| {
+            fragment
+                .deletion_file
+                .as_ref()
+                .map(|deletion_file| deletion_file_dump(fragment.id, deletion_file))
+        })
+        .collect()
+}
+
+/// Describe the indices of the checked out version
+pub async fn dump_indices(dataset: &Dataset) -> Result<Vec<IndexDump>> {
+    let schema = dataset.schema();
+    Ok(dataset
+        .load_indices()
+        .await?
+        .iter()
+        .map(|index| IndexDump {
+            name: index.name.clone(),
+            uuid: index.uuid.to_string(),
+            columns: index
+                .fields
+                .iter()
+                .map(|id| {
+                    schema
+                        .field_by_id(*id)
+                        .map(|field| field.name.clone())
+                        .unwrap_or_else(|| format!("<field {}>", id))
+                })
+                .collect(),
+            dataset_version: index.dataset_version,
+            fragment_ids: index
+                .fragment_bitmap
+                .as_ref()
+                .map(|bitmap| bitmap.iter().collect()),
+            details_type: index
+                .index_details
+                .as_ref()
+                .map(|details| details.type_url.clone()),
+            index_version: index.index_version,
+            created_at: index.created_at.map(|created_at| created_at.to_rfc3339()),
+        })
+        .collect())
+}
+
+fn or_none<T: fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+impl fmt::Display for ManifestDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Dataset: {}", self.uri)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Timestamp: {}", self.timestamp)?;
+        writeln!(f, "Tag: {}", or_none(&self.tag))?;
+        writeln!(f, "Writer: {}", or_none(&self.writer_version))?;
+        writeln!(f, "Data storage version: {}", self.data_storage_version)?;
+        writeln!(
+            f,
+            "Feature flags: reader={:#x}, writer={:#x}",
+            self.reader_feature_flags, self.writer_feature_flags
+        )?;
+        writeln!(f, "Fragments: {}", self.num_fragments)?;
+        writeln!(f, "Max fragment id: {}", or_none(&self.max_fragment_id))?;
+        writeln!(f, "Next row id: {}", self.next_row_id)?;
+        writeln!(f, "Transaction file: {}", or_none(&self.transaction_file))?;
+        writeln!(
+            f,
+            "Blob dataset version: {}",
+            or_none(&self.blob_dataset_version)
+        )?;
+        for (title, values) in [
+            ("Config", &self.config),
+            ("Transaction properties", &self.transaction_properties),
+        ] {
+            writeln!(f, "{}:", title)?;
+            for (key, value) in values {
+                writeln!(f, "  {} = {}", key, value)?;
+            }
+        }
+        writeln!(f, "Schema:")?;
+        for field in &self.schema {
+            writeln!(
+                f,
+                "  [{}] {}: {}{}",
+                field.id,
+                field.path,
+                field.data_type,
+                if field.nullable { "" } else { " not null" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FragmentDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Fragment {}: physical_rows={}, row_ids={}",
+            self.id,
+            or_none(&self.physical_rows),
+            self.has_row_ids
+        )?;
+        for file in &self.files {
+            writeln!(
+                f,
+                "  data file {} (format {}, size {}, fields {:?})",
+                file.path,
+                file.file_version,
+                or_none(&file.size_bytes),
+                file.fields
+            )?;
+        }
+        if let Some(deletion_file) = &self.deletion_file {
+            writeln!(f, "  {}", deletion_file)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DeletionFileDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deletion file {} ({}, fragment {}, read_version {}, deleted rows {})",
+            self.path,
+            self.file_type,
+            self.fragment_id,
+            self.read_version,
+            or_none(&self.num_deleted_rows)
+        )
+    }
+}
+
+impl fmt::Display for IndexDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Index {} ({})", self.name, self.uuid)?;
+        writeln!(f, "  columns: {}", self.columns.join(", "))?;
+        writeln!(f, "  type: {}", or_none(&self.details_type))?;
+        writeln!(f, "  index version: {}", self.index_version)?;
+        writeln!(f, "  dataset version: {}", self.dataset_version)?;
+        writeln!(f, "  created at: {}", or_none(&self.created_at))?;
+        match &self.fragment_ids {
+            Some(fragment_ids) => writeln!(f, "  fragments: {:?}", fragment_ids),
+            None => writeln!(f, "  fragments: unknown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+    use lance_index::scalar::ScalarIndexParams;
+    use lance_index::IndexType;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::dataset::WriteParams;
+
+    #[tokio::test]
+    async fn test_dump() {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let mut dataset = Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            test_uri,
+            Some(WriteParams {
+                max_rows_per_file: 50,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        dataset.delete("i < 10").await.unwrap();
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::BTree,
+                Some("i_idx".to_string()),
+                &ScalarIndexParams::default(),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let manifest = dump_manifest(&dataset);
+        assert_eq!(manifest.version, 3);
+        assert_eq!(manifest.num_fragments, 2);
+        assert_eq!(manifest.schema.len(), 1);
+        assert_eq!(manifest.schema[0].path, "i");
+        assert!(manifest.to_string().contains("[0] i: Int32 not null"));
+
+        let fragments = dump_fragments(&dataset);
+        assert_eq!(fragments.len(), 2);
+        assert_eq!(fragments[0].physical_rows, Some(50));
+        assert_eq!(fragments[0].files.len(), 1);
+        assert!(fragments[1].deletion_file.is_none());
+
+        let deletion_files = dump_deletion_files(&dataset);
+        assert_eq!(deletion_files.len(), 1);
+        assert_eq!(deletion_files[0].fragment_id, 0);
+        assert_eq!(deletion_files[0].num_deleted_rows, Some(10));
+        assert!(deletion_files[0].path.starts_with("_deletions/"));
+
+        let indices = dump_indices(&dataset).await.unwrap();
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices[0].name, "i_idx");
+        assert_eq!(indices[0].columns, vec!["i".to_string()]);
+        assert_eq!(indices[0].fragment_ids, Some(vec![0, 1]));
+
+        let json = serde_json::to_value(&fragments).unwrap();
+        assert_eq!(json[0]["deletion_file"]["num_deleted_rows"], 10);
+    }
+}
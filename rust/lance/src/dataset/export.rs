@@ -0,0 +1,634 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Export a dataset version to Parquet files.
+//!
+//! Lance-specific types are mapped to types that Parquet readers understand:
+//!
+//! * bfloat16 values are widened to float32.
+//! * Vector columns are written as `FixedSizeList`. The Arrow schema is embedded
+//!   in the file metadata so Arrow-based readers restore the fixed size.
+//! * Lance field metadata (such as encoding hints) is dropped.
+//! * Blob columns are not exported, since they are stored out of line.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use arrow_array::{
+    Array, ArrayRef, FixedSizeBinaryArray, FixedSizeListArray, Float32Array, RecordBatch,
+    UInt32Array,
+};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use futures::TryStreamExt;
+use lance_arrow::bfloat16::{is_bfloat16_field, BFloat16Array};
+use lance_core::datatypes::StorageClass;
+use lance_core::{Error, Result};
+use lance_io::object_store::{ObjectStore, ObjectStoreParams};
+use log::warn;
+use object_store::path::Path;
+use parquet::arrow::async_writer::ParquetObjectWriter;
+use parquet::arrow::AsyncArrowWriter;
+use snafu::location;
+
+use super::Dataset;
+
+#[cfg(feature = "avro")]
+pub mod iceberg;
+
+/// Value used for the directory of rows whose partition value is null.
+pub const NULL_PARTITION_VALUE: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Parameters for [`Dataset::export_parquet`].
+#[derive(Debug, Clone)]
+pub struct ParquetExportParams {
+    /// Columns used to partition the output into Hive-style directories
+    /// (`col=value/`). Partition columns are not written to the files.
+    ///
+    /// Values are escaped the way Hive does, so `/`, `=` and `%` in a value
+    /// become `%2F`, `%3D` and `%25`.
+    pub partition_by: Vec<String>,
+
+    /// Maximum number of files written at the same time.
+    ///
+    /// When rows arrive for a partition without an open file and this many
+    /// files are open, the file that was written to least recently is closed,
+    /// and later rows of its partition go to a new file. Each open file buffers
+    /// a row group in memory.
+    pub max_open_files: usize,
+
+    /// Target maximum size of each Parquet file in bytes. A new file is
+    /// started once the current one reaches this size, so files may be
+    /// slightly larger.
+    pub max_file_size: usize,
+
+    /// Number of rows read from the dataset per batch.
+    pub batch_size: usize,
+
+    /// Parameters used to access the destination.
+    pub store_params: Option<ObjectStoreParams>,
+}
+
+impl Default for ParquetExportParams {
+    fn default() -> Self {
+        Self {
+            partition_by: Vec::new(),
+            max_open_files: 64,
+            max_file_size: 512 * 1024 * 1024,
+            batch_size: 8192,
+            store_params: None,
+        }
+    }
+}
+
+/// A Parquet file written by [`Dataset::export_parquet`].
+#[derive(Debug, Clone)]
+pub struct ExportedFile {
+    /// Path of the file, relative to the export location.
+    pub path: String,
+    /// Number of rows in the file.
+    pub num_rows: u64,
+    /// Size of the file in bytes.
+    pub size_bytes: u64,
+}
+
+/// The outcome of [`Dataset::export_parquet`].
+#[derive(Debug, Clone, Default)]
+pub struct ParquetExportSummary {
+    /// The files that were written.
+    pub files: Vec<ExportedFile>,
+    /// Number of rows exported.
+    pub num_rows: u64,
+    /// Columns that were left out because they cannot be represented in Parquet.
+    pub skipped_columns: Vec<String>,
+}
+
+struct OpenFile {
+    path: Path,
+    writer: AsyncArrowWriter<ParquetObjectWriter>,
+    /// Index of the file in [`ParquetExportSummary::files`].
+    index: usize,
+}
+
+struct PartitionWriter {
+    dir: Path,
+    file: Option<OpenFile>,
+    num_files: usize,
+    /// When rows were last written to the partition, used to pick the file to
+    /// close when too many are open.
+    last_write: u64,
+}
+
+impl OpenFile {
+    async fn close(
+        self,
+        object_store: &ObjectStore,
+        summary: &mut ParquetExportSummary,
+    ) -> Result<()> {
+        self.writer.close().await.map_err(parquet_error)?;
+        summary.files[self.index].size_bytes = object_store.size(&self.path).await?;
+        Ok(())
+    }
+}
+
+pub(super) async fn export_parquet(
+    dataset: &Dataset,
+    uri: &str,
+    params: &ParquetExportParams,
+) -> Result<ParquetExportSummary> {
+    let mut summary = ParquetExportSummary::default();
+    let mut projection = Vec::new();
+    for field in dataset.schema().fields.iter() {
+        if field.storage_class() == StorageClass::Blob {
+            summary.skipped_columns.push(field.name.clone());
+        } else {
+            projection.push(field.name.clone());
+        }
+    }
+    if !summary.skipped_columns.is_empty() {
+        warn!(
+            "Blob columns {:?} are not exported to Parquet",
+            summary.skipped_columns
+        );
+    }
+    if params.max_open_files == 0 {
+        return Err(Error::invalid_input(
+            "max_open_files must be at least 1",
+            location!(),
+        ));
+    }
+    for column in &params.partition_by {
+        if !projection.contains(column) {
+            return Err(Error::invalid_input(
+                format!("Partition column {} is not in the dataset", column),
+                location!(),
+            ));
+        }
+    }
+
+    let mut scanner = dataset.scan();
+    scanner.project(&projection)?.batch_size(params.batch_size);
+    let mut stream = scanner.try_into_stream().await?;
+
+    let (object_store, base_path) = ObjectStore::from_uri_and_params(
+        dataset.session.store_registry(),
+        uri,
+        &params.store_params.clone().unwrap_or_default(),
+    )
+    .await?;
+
+    let mut file_schema = None;
+    let mut writers: HashMap<String, PartitionWriter> = HashMap::new();
+    let mut num_open = 0;
+    let mut num_writes = 0;
+    while let Some(batch) = stream.try_next().await? {
+        let batch = export_batch(&batch)?;
+        summary.num_rows += batch.num_rows() as u64;
+        for (dir, rows) in partition_batch(&batch, &params.partition_by)? {
+            let rows = match rows {
+                Some(indices) => arrow_select::take::take_record_batch(&batch, &indices)?,
+                None => batch.clone(),
+            };
+            let rows = rows.project(&data_columns(&batch.schema(), &params.partition_by))?;
+            let schema = file_schema.get_or_insert_with(|| rows.schema()).clone();
+
+            let is_open = writers.get(&dir).is_some_and(|p| p.file.is_some());
+            if !is_open && num_open >= params.max_open_files {
+                let file = writers
+                    .values_mut()
+                    .filter(|p| p.file.is_some())
+                    .min_by_key(|p| p.last_write)
+                    .and_then(|p| p.file.take())
+                    .unwrap();
+                file.close(&object_store, &mut summary).await?;
+                num_open -= 1;
+            }
+
+            let partition = match writers.entry(dir) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    // The directory names are escaped already, so they are
+                    // parsed rather than encoded again
+                    let dir = if entry.key().is_empty() {
+                        base_path.clone()
+                    } else {
+                        Path::parse(format!("{}/{}", base_path, entry.key()))?
+                    };
+                    entry.insert(PartitionWriter {
+                        dir,
+                        file: None,
+                        num_files: 0,
+                        last_write: 0,
+                    })
+                }
+            };
+            num_writes += 1;
+            partition.last_write = num_writes;
+            if partition.file.is_none() {
+                let path = partition
+                    .dir
+                    .child(format!("part-{:05}.parquet", partition.num_files));
+                partition.num_files += 1;
+                let relative = path
+                    .prefix_match(&base_path)
+                    .map(|parts| parts.map(|p| p.as_ref().to_string()).collect::<Vec<_>>())
+                    .unwrap_or_default()
+                    .join("/");
+                summary.files.push(ExportedFile {
+                    path: relative,
+                    num_rows: 0,
+                    size_bytes: 0,
+                });
+                let object_writer =
+                    ParquetObjectWriter::new(object_store.inner.clone(), path.clone());
+                partition.file = Some(OpenFile {
+                    path,
+                    writer: AsyncArrowWriter::try_new(object_writer, schema, None)
+                        .map_err(parquet_error)?,
+                    index: summary.files.len() - 1,
+                });
+                num_open += 1;
+            }
+
+            let file = partition.file.as_mut().unwrap();
+            file.writer.write(&rows).await.map_err(parquet_error)?;
+            summary.files[file.index].num_rows += rows.num_rows() as u64;
+            if file.writer.bytes_written() + file.writer.in_progress_size() >= params.max_file_size
+            {
+                let file = partition.file.take().unwrap();
+                file.close(&object_store, &mut summary).await?;
+                num_open -= 1;
+            }
+        }
+    }
+
+    for partition in writers.into_values() {
+        if let Some(file) = partition.file {
+            file.close(&object_store, &mut summary).await?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Escape a partition value for use in a directory name, like Hive does.
+fn escape_partition_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Indices of the columns that are written to the files.
+fn data_columns(schema: &ArrowSchema, partition_by: &[String]) -> Vec<usize> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| !partition_by.contains(field.name()))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Split a batch by the values of the partition columns.
+///
+/// Returns the partition directory of each group together with the rows in it,
+/// or `None` if the whole batch belongs to the same partition.
+fn partition_batch(
+    batch: &RecordBatch,
+    partition_by: &[String],
+) -> Result<Vec<(String, Option<UInt32Array>)>> {
+    if partition_by.is_empty() {
+        return Ok(vec![(String::new(), None)]);
+    }
+    let options = FormatOptions::default();
+    let formatters = partition_by
+        .iter()
+        .map(|name| {
+            let column = batch.column_by_name(name).unwrap();
+            Ok((
+                name,
+                column,
+                ArrayFormatter::try_new(column.as_ref(), &options)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut order = Vec::new();
+    let mut groups: HashMap<String, Vec<u32>> = HashMap::new();
+    for row in 0..batch.num_rows() {
+        let dir = formatters
+            .iter()
+            .map(|(name, column, formatter)| {
+                if column.is_null(row) {
+                    format!("{}={}", name, NULL_PARTITION_VALUE)
+                } else {
+                    let value = formatter.value(row).to_string();
+                    format!("{}={}", name, escape_partition_value(&value))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        groups
+            .entry(dir)
+            .or_insert_with_key(|dir| {
+                order.push(dir.clone());
+                Vec::new()
+            })
+            .push(row as u32);
+    }
+
+    if order.len() == 1 {
+        return Ok(vec![(order.pop().unwrap(), None)]);
+    }
+    Ok(order
+        .into_iter()
+        .map(|dir| {
+            let rows = groups.remove(&dir).unwrap();
+            (dir, Some(UInt32Array::from(rows)))
+        })
+        .collect())
+}
+
+/// Map the columns of a batch to types that can be stored in Parquet.
+fn export_batch(batch: &RecordBatch) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| export_field(f))
+        .collect::<Vec<_>>();
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| export_column(field, column))
+        .collect::<Result<Vec<_>>>()?;
+    let schema: SchemaRef = Arc::new(ArrowSchema::new(fields));
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// The schema of the exported files for the given dataset columns.
+pub(super) fn export_schema(schema: &ArrowSchema) -> ArrowSchema {
+    ArrowSchema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|f| export_field(f))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn export_field(field: &ArrowField) -> ArrowField {
+    if is_bfloat16_field(field) {
+        return ArrowField::new(field.name(), DataType::Float32, field.is_nullable());
+    }
+    let field = field.clone().with_metadata(
+        field
+            .metadata()
+            .iter()
+            .filter(|(key, _)| !key.starts_with("lance"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    );
+    match field.data_type() {
+        DataType::FixedSizeList(child, size) => {
+            let child = Arc::new(export_field(child));
+            field.with_data_type(DataType::FixedSizeList(child, *size))
+        }
+        _ => field,
+    }
+}
+
+fn export_column(field: &ArrowField, column: &ArrayRef) -> Result<ArrayRef> {
+    if is_bfloat16_field(field) {
+        let values = column
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .ok_or_else(|| Error::Internal {
+                message: format!("Expected bfloat16 storage for field {}", field.name()),
+                location: location!(),
+            })?;
+        let values = BFloat16Array::try_from(values.clone())?;
+        let values = values
+            .iter()
+            .map(|v| v.map(|v| v.to_f32()))
+            .collect::<Float32Array>();
+        return Ok(Arc::new(values));
+    }
+
+    match field.data_type() {
+        DataType::FixedSizeList(child, size) => {
+            let list = column
+                .as_any()
+                .downcast_ref::<FixedSizeListArray>()
+                .unwrap();
+            let values = export_column(child, list.values())?;
+            let list = FixedSizeListArray::try_new(
+                Arc::new(export_field(child)),
+                *size,
+                values,
+                list.nulls().cloned(),
+            )?;
+            Ok(Arc::new(list))
+        }
+        _ => Ok(column.clone()),
+    }
+}
+
+fn parquet_error(err: parquet::errors::ParquetError) -> Error {
+    Error::io(
+        format!("Failed to write Parquet file: {}", err),
+        location!(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, RecordBatchIterator, StringArray};
+    use half::bf16;
+    use lance_arrow::bfloat16::{ARROW_EXT_NAME_KEY, BFLOAT16_EXT_NAME};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use crate::dataset::WriteParams;
+
+    #[tokio::test]
+    async fn test_export_parquet() {
+        let bf16_field = ArrowField::new("item", DataType::FixedSizeBinary(2), true).with_metadata(
+            [(
+                ARROW_EXT_NAME_KEY.to_string(),
+                BFLOAT16_EXT_NAME.to_string(),
+            )]
+            .into(),
+        );
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("category", DataType::Utf8, true),
+            ArrowField::new(
+                "vector",
+                DataType::FixedSizeList(Arc::new(bf16_field.clone()), 2),
+                true,
+            ),
+        ]));
+        let vectors = BFloat16Array::from_iter_values((0..20).map(|v| bf16::from_f32(v as f32)));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(StringArray::from_iter((0..10).map(|i| match i % 3 {
+                    0 => Some("a"),
+                    1 => Some("b"),
+                    _ => None,
+                }))),
+                Arc::new(
+                    FixedSizeListArray::try_new(
+                        Arc::new(bf16_field),
+                        2,
+                        Arc::new(vectors.into_inner()),
+                        None,
+                    )
+                    .unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = Dataset::write(reader, "memory://", Some(WriteParams::default()))
+            .await
+            .unwrap();
+
+        let out_dir = tempfile::tempdir().unwrap();
+        let params = ParquetExportParams {
+            partition_by: vec!["category".to_string()],
+            ..Default::default()
+        };
+        let summary = dataset
+            .export_parquet(out_dir.path().to_str().unwrap(), &params)
+            .await
+            .unwrap();
+        assert_eq!(summary.num_rows, 10);
+        assert_eq!(summary.files.len(), 3);
+        let null_file = summary
+            .files
+            .iter()
+            .find(|f| {
+                f.path
+                    .starts_with(&format!("category={}/", NULL_PARTITION_VALUE))
+            })
+            .unwrap();
+        assert_eq!(null_file.num_rows, 3);
+        assert!(null_file.size_bytes > 0);
+
+        let null_dir = out_dir
+            .path()
+            .join(format!("category={}", NULL_PARTITION_VALUE));
+        let file = std::fs::File::open(null_dir.join("part-00000.parquet")).unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), 2);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &Int32Array::from(vec![2, 5, 8]) as &dyn Array
+        );
+        let vector = batch.column(1);
+        assert_eq!(
+            vector.data_type(),
+            &DataType::FixedSizeList(
+                Arc::new(ArrowField::new("item", DataType::Float32, true)),
+                2
+            )
+        );
+        let values = vector
+            .as_any()
+            .downcast_ref::<FixedSizeListArray>()
+            .unwrap();
+        assert_eq!(
+            values.value(0).as_ref(),
+            &Float32Array::from(vec![4.0, 5.0]) as &dyn Array
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_parquet_partitions() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("category", DataType::Utf8, false),
+        ]));
+        let categories = ["a/b", "x=y", "50%"];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..6)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..6).map(|i| categories[i % 3]),
+                )),
+            ],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = Dataset::write(reader, "memory://", None).await.unwrap();
+
+        // Rows arrive one at a time, alternating between the partitions, so
+        // every row ends up in a file of its own with a single open file
+        let out_dir = tempfile::tempdir().unwrap();
+        let params = ParquetExportParams {
+            partition_by: vec!["category".to_string()],
+            max_open_files: 1,
+            batch_size: 1,
+            ..Default::default()
+        };
+        let summary = dataset
+            .export_parquet(out_dir.path().to_str().unwrap(), &params)
+            .await
+            .unwrap();
+        assert_eq!(summary.num_rows, 6);
+        let mut paths = summary
+            .files
+            .iter()
+            .map(|f| {
+                assert_eq!(f.num_rows, 1);
+                f.path.as_str()
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "category=50%25/part-00000.parquet",
+                "category=50%25/part-00001.parquet",
+                "category=a%2Fb/part-00000.parquet",
+                "category=a%2Fb/part-00001.parquet",
+                "category=x%3Dy/part-00000.parquet",
+                "category=x%3Dy/part-00001.parquet",
+            ]
+        );
+        assert!(out_dir
+            .path()
+            .join("category=a%2Fb")
+            .join("part-00001.parquet")
+            .exists());
+
+        let params = ParquetExportParams {
+            max_open_files: 0,
+            ..Default::default()
+        };
+        let err = dataset
+            .export_parquet(out_dir.path().to_str().unwrap(), &params)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }));
+    }
+}
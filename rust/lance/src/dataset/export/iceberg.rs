@@ -0,0 +1,544 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Export a dataset version as an Iceberg table.
+//!
+//! The data is exported to Parquet under `data/` (Iceberg readers have no way
+//! to read Lance files) and a single-snapshot, format version 2 table is
+//! described under `metadata/`:
+//!
+//! * `v1.metadata.json`, the table metadata. The snapshot id is the Lance
+//!   version that was exported.
+//! * `snap-<id>-1-<uuid>.avro`, the manifest list.
+//! * `<uuid>-m0.avro`, the manifest listing every data file.
+//! * `version-hint.text`, so the table can be loaded by path.
+//!
+//! The Parquet files carry no Iceberg field ids, so a default name mapping is
+//! recorded in the table properties. The table is unpartitioned.
+
+use std::sync::Arc;
+
+use apache_avro::types::Value;
+use apache_avro::{Schema as AvroSchema, Writer};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, TimeUnit};
+use lance_core::datatypes::StorageClass;
+use lance_core::{Error, Result};
+use lance_io::object_store::ObjectStore;
+use serde_json::{json, Value as JsonValue};
+use snafu::location;
+
+use super::{export_parquet, export_schema, ExportedFile, ParquetExportParams};
+use crate::Dataset;
+
+/// Name of the table property holding the Lance version that was exported.
+pub const LANCE_VERSION_PROPERTY: &str = "lance.version";
+
+/// The outcome of [`Dataset::export_iceberg`].
+#[derive(Debug, Clone)]
+pub struct IcebergExportSummary {
+    /// Location of the table metadata file.
+    pub metadata_location: String,
+    /// Id of the Iceberg snapshot, equal to the exported Lance version.
+    pub snapshot_id: i64,
+    /// The data files, relative to the `data/` directory of the table.
+    pub data_files: Vec<ExportedFile>,
+    /// Columns that were left out because they cannot be represented in Parquet.
+    pub skipped_columns: Vec<String>,
+}
+
+pub(in crate::dataset) async fn export_iceberg(
+    dataset: &Dataset,
+    uri: &str,
+    params: &ParquetExportParams,
+) -> Result<IcebergExportSummary> {
+    if !params.partition_by.is_empty() {
+        return Err(Error::NotSupported {
+            source: "Partitioned Iceberg exports are not supported".into(),
+            location: location!(),
+        });
+    }
+    let location = uri.trim_end_matches('/');
+    let data_location = format!("{}/data", location);
+    let metadata_location = format!("{}/metadata", location);
+
+    // Check that the schema can be represented before writing any data.
+    let arrow_schema = ArrowSchema::from(dataset.schema());
+    let fields = dataset
+        .schema()
+        .fields
+        .iter()
+        .zip(arrow_schema.fields())
+        .filter(|(field, _)| field.storage_class() != StorageClass::Blob)
+        .map(|(_, field)| field.clone())
+        .collect::<Vec<_>>();
+    let (schema_fields, name_mapping, last_column_id) =
+        iceberg_schema(&export_schema(&ArrowSchema::new(fields)))?;
+
+    let export = export_parquet(dataset, &data_location, params).await?;
+
+    let (object_store, metadata_path) = ObjectStore::from_uri_and_params(
+        dataset.session.store_registry(),
+        &metadata_location,
+        &params.store_params.clone().unwrap_or_default(),
+    )
+    .await?;
+
+    let snapshot_id = dataset.version().version as i64;
+    let timestamp_ms = dataset.manifest.timestamp().timestamp_millis();
+    let commit_uuid = uuid::Uuid::new_v4();
+
+    // Manifest
+    let entries = export
+        .files
+        .iter()
+        .map(|file| {
+            record([
+                // status: ADDED
+                ("status", Value::Int(1)),
+                ("snapshot_id", optional_long(snapshot_id)),
+                ("sequence_number", optional_long(1)),
+                ("file_sequence_number", optional_long(1)),
+                (
+                    "data_file",
+                    record([
+                        // content: DATA
+                        ("content", Value::Int(0)),
+                        (
+                            "file_path",
+                            Value::String(format!("{}/{}", data_location, file.path)),
+                        ),
+                        ("file_format", Value::String("PARQUET".to_string())),
+                        ("partition", Value::Record(vec![])),
+                        ("record_count", Value::Long(file.num_rows as i64)),
+                        ("file_size_in_bytes", Value::Long(file.size_bytes as i64)),
+                    ]),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let schema_json = json!({
+        "type": "struct",
+        "schema-id": 0,
+        "fields": schema_fields,
+    });
+    let manifest = write_avro(
+        MANIFEST_ENTRY_SCHEMA,
+        &[
+            ("schema", schema_json.to_string()),
+            ("schema-id", "0".to_string()),
+            ("partition-spec", "[]".to_string()),
+            ("partition-spec-id", "0".to_string()),
+            ("format-version", "2".to_string()),
+            ("content", "data".to_string()),
+        ],
+        entries,
+    )?;
+    let manifest_name = format!("{}-m0.avro", commit_uuid);
+    object_store
+        .put(&metadata_path.child(manifest_name.as_str()), &manifest)
+        .await?;
+
+    // Manifest list
+    let num_files = export.files.len() as i64;
+    let num_rows = export.num_rows as i64;
+    let manifest_file = record([
+        (
+            "manifest_path",
+            Value::String(format!("{}/{}", metadata_location, manifest_name)),
+        ),
+        ("manifest_length", Value::Long(manifest.len() as i64)),
+        ("partition_spec_id", Value::Int(0)),
+        // content: DATA
+        ("content", Value::Int(0)),
+        ("sequence_number", Value::Long(1)),
+        ("min_sequence_number", Value::Long(1)),
+        ("added_snapshot_id", Value::Long(snapshot_id)),
+        ("added_files_count", Value::Int(num_files as i32)),
+        ("existing_files_count", Value::Int(0)),
+        ("deleted_files_count", Value::Int(0)),
+        ("added_rows_count", Value::Long(num_rows)),
+        ("existing_rows_count", Value::Long(0)),
+        ("deleted_rows_count", Value::Long(0)),
+    ]);
+    let manifest_list = write_avro(
+        MANIFEST_FILE_SCHEMA,
+        &[
+            ("snapshot-id", snapshot_id.to_string()),
+            ("parent-snapshot-id", "null".to_string()),
+            ("sequence-number", "1".to_string()),
+            ("format-version", "2".to_string()),
+        ],
+        vec![manifest_file],
+    )?;
+    let manifest_list_name = format!("snap-{}-1-{}.avro", snapshot_id, commit_uuid);
+    object_store
+        .put(
+            &metadata_path.child(manifest_list_name.as_str()),
+            &manifest_list,
+        )
+        .await?;
+
+    // Table metadata
+    let table_metadata = json!({
+        "format-version": 2,
+        "table-uuid": uuid::Uuid::new_v4().to_string(),
+        "location": location,
+        "last-sequence-number": 1,
+        "last-updated-ms": timestamp_ms,
+        "last-column-id": last_column_id,
+        "current-schema-id": 0,
+        "schemas": [schema_json],
+        "default-spec-id": 0,
+        "partition-specs": [{"spec-id": 0, "fields": []}],
+        "last-partition-id": 999,
+        "default-sort-order-id": 0,
+        "sort-orders": [{"order-id": 0, "fields": []}],
+        "properties": {
+            "schema.name-mapping.default": JsonValue::Array(name_mapping).to_string(),
+            LANCE_VERSION_PROPERTY: snapshot_id.to_string(),
+        },
+        "current-snapshot-id": snapshot_id,
+        "refs": {"main": {"snapshot-id": snapshot_id, "type": "branch"}},
+        "snapshots": [{
+            "snapshot-id": snapshot_id,
+            "sequence-number": 1,
+            "timestamp-ms": timestamp_ms,
+            "summary": {
+                "operation": "append",
+                "added-data-files": num_files.to_string(),
+                "added-records": num_rows.to_string(),
+                "total-data-files": num_files.to_string(),
+                "total-records": num_rows.to_string(),
+                "total-delete-files": "0",
+                "total-position-deletes": "0",
+                "total-equality-deletes": "0",
+            },
+            "manifest-list": format!("{}/{}", metadata_location, manifest_list_name),
+            "schema-id": 0,
+        }],
+        "snapshot-log": [{"timestamp-ms": timestamp_ms, "snapshot-id": snapshot_id}],
+        "metadata-log": [],
+    });
+    object_store
+        .put(
+            &metadata_path.child("v1.metadata.json"),
+            serde_json::to_string_pretty(&table_metadata)?.as_bytes(),
+        )
+        .await?;
+    object_store
+        .put(&metadata_path.child("version-hint.text"), b"1")
+        .await?;
+
+    Ok(IcebergExportSummary {
+        metadata_location: format!("{}/v1.metadata.json", metadata_location),
+        snapshot_id,
+        data_files: export.files,
+        skipped_columns: export.skipped_columns,
+    })
+}
+
+/// Assigns Iceberg field ids in the order fields are visited.
+struct FieldIds(i32);
+
+impl FieldIds {
+    fn next(&mut self) -> i32 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+/// Convert the schema of the exported files to Iceberg schema fields.
+///
+/// Returns the fields, the matching name mapping and the highest assigned id.
+fn iceberg_schema(schema: &ArrowSchema) -> Result<(Vec<JsonValue>, Vec<JsonValue>, i32)> {
+    let mut ids = FieldIds(0);
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| (f, ids.next()))
+        .collect::<Vec<_>>();
+    let mut iceberg_fields = Vec::with_capacity(fields.len());
+    let mut mapping = Vec::with_capacity(fields.len());
+    for (field, id) in fields {
+        let (field, field_mapping) = iceberg_field(field, id, &mut ids)?;
+        iceberg_fields.push(field);
+        mapping.push(field_mapping);
+    }
+    Ok((iceberg_fields, mapping, ids.0))
+}
+
+fn iceberg_field(
+    field: &ArrowField,
+    id: i32,
+    ids: &mut FieldIds,
+) -> Result<(JsonValue, JsonValue)> {
+    let (data_type, nested_mapping) = iceberg_type(field, ids)?;
+    let mut mapping = json!({"field-id": id, "names": [field.name()]});
+    if let Some(nested) = nested_mapping {
+        mapping["fields"] = JsonValue::Array(nested);
+    }
+    Ok((
+        json!({
+            "id": id,
+            "name": field.name(),
+            "required": !field.is_nullable(),
+            "type": data_type,
+        }),
+        mapping,
+    ))
+}
+
+fn iceberg_type(
+    field: &ArrowField,
+    ids: &mut FieldIds,
+) -> Result<(JsonValue, Option<Vec<JsonValue>>)> {
+    let primitive = match field.data_type() {
+        DataType::Boolean => "boolean".to_string(),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
+            "int".to_string()
+        }
+        DataType::Int64 | DataType::UInt32 => "long".to_string(),
+        DataType::Float32 => "float".to_string(),
+        DataType::Float64 => "double".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "string".to_string(),
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => "binary".to_string(),
+        DataType::FixedSizeBinary(size) => format!("fixed[{}]", size),
+        DataType::Date32 => "date".to_string(),
+        DataType::Time64(TimeUnit::Microsecond) => "time".to_string(),
+        DataType::Timestamp(TimeUnit::Microsecond, None) => "timestamp".to_string(),
+        DataType::Timestamp(TimeUnit::Microsecond, Some(_)) => "timestamptz".to_string(),
+        DataType::Decimal128(precision, scale) => format!("decimal({}, {})", precision, scale),
+        DataType::List(child) | DataType::LargeList(child) | DataType::FixedSizeList(child, _) => {
+            let element_id = ids.next();
+            let (element, nested) = iceberg_type(child, ids)?;
+            // Parquet writers name the list element either `element` or `item`.
+            let mut mapping = json!({"field-id": element_id, "names": ["element", child.name()]});
+            if let Some(nested) = nested {
+                mapping["fields"] = JsonValue::Array(nested);
+            }
+            return Ok((
+                json!({
+                    "type": "list",
+                    "element-id": element_id,
+                    "element": element,
+                    "element-required": !child.is_nullable(),
+                }),
+                Some(vec![mapping]),
+            ));
+        }
+        DataType::Struct(children) => {
+            let children = children.iter().map(|f| (f, ids.next())).collect::<Vec<_>>();
+            let mut fields = Vec::with_capacity(children.len());
+            let mut mapping = Vec::with_capacity(children.len());
+            for (child, id) in children {
+                let (child, child_mapping) = iceberg_field(child, id, ids)?;
+                fields.push(child);
+                mapping.push(child_mapping);
+            }
+            return Ok((json!({"type": "struct", "fields": fields}), Some(mapping)));
+        }
+        other => {
+            return Err(Error::NotSupported {
+                source: format!(
+                    "Column {} of type {} cannot be exported to Iceberg",
+                    field.name(),
+                    other
+                )
+                .into(),
+                location: location!(),
+            })
+        }
+    };
+    Ok((JsonValue::String(primitive), None))
+}
+
+const MANIFEST_ENTRY_SCHEMA: &str = r#"{
+  "type": "record",
+  "name": "manifest_entry",
+  "fields": [
+    {"name": "status", "type": "int", "field-id": 0},
+    {"name": "snapshot_id", "type": ["null", "long"], "default": null, "field-id": 1},
+    {"name": "sequence_number", "type": ["null", "long"], "default": null, "field-id": 3},
+    {"name": "file_sequence_number", "type": ["null", "long"], "default": null, "field-id": 4},
+    {"name": "data_file", "field-id": 2, "type": {
+      "type": "record",
+      "name": "r2",
+      "fields": [
+        {"name": "content", "type": "int", "field-id": 134},
+        {"name": "file_path", "type": "string", "field-id": 100},
+        {"name": "file_format", "type": "string", "field-id": 101},
+        {"name": "partition", "type": {"type": "record", "name": "r102", "fields": []}, "field-id": 102},
+        {"name": "record_count", "type": "long", "field-id": 103},
+        {"name": "file_size_in_bytes", "type": "long", "field-id": 104}
+      ]
+    }}
+  ]
+}"#;
+
+const MANIFEST_FILE_SCHEMA: &str = r#"{
+  "type": "record",
+  "name": "manifest_file",
+  "fields": [
+    {"name": "manifest_path", "type": "string", "field-id": 500},
+    {"name": "manifest_length", "type": "long", "field-id": 501},
+    {"name": "partition_spec_id", "type": "int", "field-id": 502},
+    {"name": "content", "type": "int", "field-id": 517},
+    {"name": "sequence_number", "type": "long", "field-id": 515},
+    {"name": "min_sequence_number", "type": "long", "field-id": 516},
+    {"name": "added_snapshot_id", "type": "long", "field-id": 503},
+    {"name": "added_files_count", "type": "int", "field-id": 504},
+    {"name": "existing_files_count", "type": "int", "field-id": 505},
+    {"name": "deleted_files_count", "type": "int", "field-id": 506},
+    {"name": "added_rows_count", "type": "long", "field-id": 512},
+    {"name": "existing_rows_count", "type": "long", "field-id": 513},
+    {"name": "deleted_rows_count", "type": "long", "field-id": 514}
+  ]
+}"#;
+
+/// An Avro record, with the fields in schema order.
+fn record<const N: usize>(fields: [(&str, Value); N]) -> Value {
+    Value::Record(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+/// A value of a `["null", "long"]` union.
+fn optional_long(value: i64) -> Value {
+    Value::Union(1, Box::new(Value::Long(value)))
+}
+
+/// Encode records as an Avro object container file, with the Iceberg
+/// properties in the file metadata.
+fn write_avro(schema: &str, metadata: &[(&str, String)], records: Vec<Value>) -> Result<Vec<u8>> {
+    let schema = AvroSchema::parse_str(schema).map_err(avro_error)?;
+    let mut writer = Writer::new(&schema, Vec::new());
+    for (key, value) in metadata {
+        writer
+            .add_user_metadata(key.to_string(), value)
+            .map_err(avro_error)?;
+    }
+    writer.extend(records).map_err(avro_error)?;
+    writer.into_inner().map_err(avro_error)
+}
+
+fn avro_error(err: apache_avro::Error) -> Error {
+    Error::io(
+        format!("Failed to write Iceberg manifest: {}", err),
+        location!(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use arrow_array::types::Int32Type;
+    use arrow_array::{Int32Array, ListArray, RecordBatch, RecordBatchIterator, StringArray};
+
+    use crate::dataset::WriteParams;
+
+    /// Read the records of an Avro file, as maps from field name to value.
+    fn read_avro(path: &str) -> Vec<HashMap<String, Value>> {
+        let file = std::fs::File::open(path).unwrap();
+        apache_avro::Reader::new(file)
+            .unwrap()
+            .map(|record| match record.unwrap() {
+                Value::Record(fields) => fields.into_iter().collect(),
+                other => panic!("expected a record, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_export_iceberg() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new(
+                "tags",
+                DataType::List(Arc::new(ArrowField::new("item", DataType::Int32, true))),
+                true,
+            ),
+            ArrowField::new("name", DataType::Utf8, true),
+        ]));
+        let tags = ListArray::from_iter_primitive::<Int32Type, _, _>(
+            (0..10).map(|i| Some(vec![Some(i), None])),
+        );
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(tags),
+                Arc::new(StringArray::from_iter_values(
+                    (0..10).map(|i| i.to_string()),
+                )),
+            ],
+        )
+        .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let dataset = Dataset::write(reader, "memory://", Some(WriteParams::default()))
+            .await
+            .unwrap();
+
+        let out_dir = tempfile::tempdir().unwrap();
+        let uri = out_dir.path().to_str().unwrap();
+        let summary = dataset
+            .export_iceberg(uri, &ParquetExportParams::default())
+            .await
+            .unwrap();
+        assert_eq!(summary.snapshot_id, 1);
+        assert_eq!(summary.data_files.len(), 1);
+
+        let metadata: JsonValue =
+            serde_json::from_slice(&std::fs::read(&summary.metadata_location).unwrap()).unwrap();
+        assert_eq!(metadata["current-snapshot-id"], 1);
+        assert_eq!(metadata["last-column-id"], 4);
+        let fields = &metadata["schemas"][0]["fields"];
+        assert_eq!(fields[0]["type"], "int");
+        assert_eq!(fields[1]["type"]["element-id"], 4);
+        assert_eq!(fields[2]["id"], 3);
+        let snapshot = &metadata["snapshots"][0];
+        assert_eq!(snapshot["summary"]["total-records"], "10");
+
+        let manifest_list = read_avro(snapshot["manifest-list"].as_str().unwrap());
+        assert_eq!(manifest_list.len(), 1);
+        let manifest_file = &manifest_list[0];
+        assert_eq!(manifest_file["added_rows_count"], Value::Long(10));
+        let Value::String(manifest_path) = &manifest_file["manifest_path"] else {
+            panic!("manifest_path is not a string");
+        };
+        assert!(manifest_path.ends_with("-m0.avro"));
+
+        let manifest = read_avro(manifest_path);
+        assert_eq!(manifest.len(), 1);
+        let Value::Record(data_file) = &manifest[0]["data_file"] else {
+            panic!("data_file is not a record");
+        };
+        let data_file = data_file.iter().cloned().collect::<HashMap<_, _>>();
+        assert_eq!(data_file["record_count"], Value::Long(10));
+
+        let data_path = format!("{}/data/{}", uri, summary.data_files[0].path);
+        assert_eq!(data_file["file_path"], Value::String(data_path.clone()));
+        assert!(std::path::Path::new(&data_path).exists());
+        assert_eq!(
+            std::fs::read_to_string(out_dir.path().join("metadata/version-hint.text")).unwrap(),
+            "1"
+        );
+
+        let err = dataset
+            .export_iceberg(
+                uri,
+                &ParquetExportParams {
+                    partition_by: vec!["name".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotSupported { .. }));
+    }
+}
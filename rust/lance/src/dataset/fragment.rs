@@ -18,7 +18,7 @@ use arrow_array::{
 use arrow_schema::Schema as ArrowSchema;
 use datafusion::logical_expr::Expr;
 use datafusion::scalar::ScalarValue;
-use futures::future::try_join_all;
+use futures::future::{try_join_all, BoxFuture};
 use futures::{join, stream, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
 use lance_core::datatypes::{OnMissing, OnTypeMismatch, SchemaCompareOptions};
 use lance_core::utils::deletion::DeletionVector;
@@ -30,6 +30,7 @@ use lance_datafusion::utils::StreamingWriteSource;
 use lance_encoding::decoder::DecoderPlugins;
 use lance_file::reader::{read_batch, FileReader};
 use lance_file::v2::reader::{CachedFileMetadata, FileReaderOptions, ReaderProjection};
+use lance_file::v2::statistics::ChunkStatistics;
 use lance_file::v2::LanceEncodingsIo;
 use lance_file::version::LanceFileVersion;
 use lance_file::{determine_file_version, v2};
@@ -109,7 +110,11 @@ pub trait GenericFileReader: std::fmt::Debug + Send + Sync {
     fn projection(&self) -> &Arc<Schema>;
 
     /// Update storage statistics (ignored by v1 reader)
-    fn update_storage_stats(&self, field_stats: &mut HashMap<u32, FieldStatistics>);
+    fn update_storage_stats(&self, field_stats: &mut HashMap<u32, FieldStatistics>) -> Result<()>;
+
+    /// Read the statistics of the row chunks of the file, if it has any (ignored by v1
+    /// reader)
+    fn read_chunk_statistics(&self) -> BoxFuture<'static, Result<Option<ChunkStatistics>>>;
 
     // Helper functions to fallback to the legacy implementation while we
     // slowly migrate functionality over to the generic reader
@@ -266,8 +271,14 @@ impl GenericFileReader for V1Reader {
         self.reader.len() as u32
     }
 
-    fn update_storage_stats(&self, _field_stats: &mut HashMap<u32, FieldStatistics>) {
+    fn update_storage_stats(&self, _field_stats: &mut HashMap<u32, FieldStatistics>) -> Result<()> {
         // No-op for v1 files
+        Ok(())
+    }
+
+    fn read_chunk_statistics(&self) -> BoxFuture<'static, Result<Option<ChunkStatistics>>> {
+        // v1 files have page statistics instead, see legacy_read_page_stats
+        futures::future::ready(Ok(None)).boxed()
     }
 
     fn clone_box(&self) -> Box<dyn GenericFileReader> {
@@ -423,6 +434,23 @@ mod v2_adapter {
                 self.reader.clone()
             };
 
+            // A take that fits in one batch, e.g. the candidates of a vector search that are
+            // fetched for re-ranking, is a single point lookup
+            if indices.len() <= batch_size as usize {
+                let indices = indices
+                    .values()
+                    .iter()
+                    .map(|&i| i as u64)
+                    .collect::<Vec<_>>();
+                let num_rows = indices.len() as u32;
+                let task = async move { reader.take_rows(&indices, Some(projection)).await };
+                return Ok(stream::iter([ReadBatchTask {
+                    task: task.boxed(),
+                    num_rows,
+                }])
+                .boxed());
+            }
+
             Ok(reader
                 .read_tasks(
                     ReadBatchParams::Indices(indices),
@@ -437,8 +465,11 @@ mod v2_adapter {
                 .boxed())
         }
 
-        fn update_storage_stats(&self, field_stats: &mut HashMap<u32, FieldStatistics>) {
-            let file_statistics = self.reader.file_statistics();
+        fn update_storage_stats(
+            &self,
+            field_stats: &mut HashMap<u32, FieldStatistics>,
+        ) -> Result<()> {
+            let file_statistics = self.reader.file_statistics()?;
             let column_idx_to_field_id = self
                 .field_id_to_column_idx
                 .iter()
@@ -458,6 +489,12 @@ mod v2_adapter {
                     field_stats.bytes_on_disk += stats.size_bytes;
                 }
             }
+            Ok(())
+        }
+
+        fn read_chunk_statistics(&self) -> BoxFuture<'static, Result<Option<ChunkStatistics>>> {
+            let reader = self.reader.clone();
+            async move { reader.read_statistics().await }.boxed()
         }
 
         fn projection(&self) -> &Arc<Schema> {
@@ -566,8 +603,13 @@ impl GenericFileReader for NullReader {
         self.read_ranges_tasks(vec![0..num_rows].into(), batch_size, projection)
     }
 
-    fn update_storage_stats(&self, _field_stats: &mut HashMap<u32, FieldStatistics>) {
+    fn update_storage_stats(&self, _field_stats: &mut HashMap<u32, FieldStatistics>) -> Result<()> {
         // No-op for null reader
+        Ok(())
+    }
+
+    fn read_chunk_statistics(&self) -> BoxFuture<'static, Result<Option<ChunkStatistics>>> {
+        futures::future::ready(Ok(None)).boxed()
     }
 
     fn projection(&self) -> &Arc<Schema> {
@@ -770,7 +812,7 @@ impl FileFragment {
             )
             .await?
         {
-            reader.update_storage_stats(field_stats);
+            reader.update_storage_stats(field_stats)?;
         }
         Ok(())
     }
@@ -1093,6 +1135,25 @@ impl FileFragment {
         Ok(reader.len() as usize)
     }
 
+    /// Read the number of rows in each data file, in the same order as
+    /// [`Fragment::files`].
+    pub(crate) async fn data_file_lengths(&self) -> Result<Vec<usize>> {
+        let get_lengths = self.metadata.files.iter().map(|data_file| async move {
+            let reader = self
+                .open_reader(data_file, None, &FragReadConfig::default())
+                .await?
+                .ok_or_else(|| {
+                    Error::corrupt_file(
+                        self.dataset.data_dir().child(data_file.path.clone()),
+                        "did not have any fields in common with the dataset schema",
+                        location!(),
+                    )
+                })?;
+            Result::Ok(reader.len() as usize)
+        });
+        try_join_all(get_lengths).await
+    }
+
     /// Validate the fragment
     ///
     /// Verifies:
@@ -1154,21 +1215,7 @@ impl FileFragment {
             data_file.validate(&self.dataset.data_dir())?;
         }
 
-        let get_lengths = self.metadata.files.iter().map(|data_file| async move {
-            let reader = self
-                .open_reader(data_file, None, &FragReadConfig::default())
-                .await?
-                .ok_or_else(|| {
-                    Error::corrupt_file(
-                        self.dataset.data_dir().child(data_file.path.clone()),
-                        "did not have any fields in common with the dataset schema",
-                        location!(),
-                    )
-                })?;
-            Result::Ok(reader.len() as usize)
-        });
-        let get_lengths = try_join_all(get_lengths);
-
+        let get_lengths = self.data_file_lengths();
         let deletion_vector = self.get_deletion_vector();
 
         let (get_lengths, deletion_vector) = join!(get_lengths, deletion_vector);
@@ -1317,6 +1364,43 @@ impl FileFragment {
         Ok(Some(deletion_vector))
     }
 
+    /// Start reading the metadata of the fragment's data files ahead of
+    /// [`Self::open`].
+    ///
+    /// Only data files that are not legacy files, have columns in `projection`
+    /// and whose metadata is not cached yet are read.  The reads are claimed when
+    /// the fragment is later opened with the same `scan_scheduler`.
+    pub async fn prefetch_metadata(
+        &self,
+        projection: &Schema,
+        scan_scheduler: &Arc<ScanScheduler>,
+        reader_priority: u32,
+    ) -> Result<()> {
+        for data_file in &self.metadata.files {
+            if data_file.is_legacy_file()
+                || projection
+                    .intersection_ignore_types(&data_file.schema(self.dataset.schema()))?
+                    .fields
+                    .is_empty()
+            {
+                continue;
+            }
+            let path = self.dataset.data_dir().child(data_file.path.as_str());
+            if self
+                .dataset
+                .metadata_cache
+                .contains::<CachedFileMetadata>(&path.to_string())
+            {
+                continue;
+            }
+            let file_scheduler = scan_scheduler
+                .open_file_with_priority(&path, reader_priority as u64, &data_file.file_size_bytes)
+                .await?;
+            v2::reader::FileReader::prefetch_metadata(&file_scheduler).await?;
+        }
+        Ok(())
+    }
+
     /// Get the file metadata for this fragment, using the cache if available.
     async fn get_file_metadata(
         &self,
@@ -1897,6 +1981,21 @@ impl FragmentReader {
         }
     }
 
+    /// Read the chunk statistics of the data files, for the files that have them
+    ///
+    /// Every data file of a fragment has the same rows, so the chunk ranges of
+    /// all files are offsets into the fragment.  The chunks of different files
+    /// don't necessarily line up.
+    pub(crate) async fn read_chunk_statistics(&self) -> Result<Vec<ChunkStatistics>> {
+        let stats = try_join_all(
+            self.readers
+                .iter()
+                .map(|reader| reader.read_chunk_statistics()),
+        )
+        .await?;
+        Ok(stats.into_iter().flatten().collect())
+    }
+
     /// Read a batch of rows from the fragment, with a subset of columns.
     ///
     /// Note: the projection must be a subset of the schema the reader was created with.
@@ -103,6 +103,8 @@ impl<'a> FragmentCreateBuilder<'a> {
             schema,
             FileWriterOptions {
                 format_version: params.data_storage_version,
+                statistics_chunk_rows: params.statistics_chunk_rows,
+                checksums: params.checksums,
                 ..Default::default()
             },
         )?;
@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! The commit history of a dataset
+//!
+//! [`Dataset::history`] describes what each version changed, for auditing and for
+//! debugging write pipelines.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, TryStreamExt};
+use lance_table::format::{Fragment, Manifest};
+use lance_table::io::manifest::read_manifest;
+
+use super::Dataset;
+use crate::io::commit::read_transaction_file;
+use crate::Result;
+
+/// The transaction property that records how long the commit of a version took
+///
+/// It is written by the commit and is not returned as committer metadata.
+pub(crate) const COMMIT_DURATION_KEY: &str = "lance:commit_duration_ms";
+
+/// The transaction property that holds the id given by the writer, see
+/// [`crate::dataset::transaction::Transaction::with_transaction_id`]
+pub(crate) const TRANSACTION_ID_KEY: &str = "lance:transaction_id";
+
+/// The transaction properties that Lance writes for itself
+const INTERNAL_KEYS: [&str; 2] = [COMMIT_DURATION_KEY, TRANSACTION_ID_KEY];
+
+/// The transaction properties set by the committer, without the internal ones
+pub(crate) fn committer_metadata(properties: &HashMap<String, String>) -> BTreeMap<String, String> {
+    properties
+        .iter()
+        .filter(|(key, _)| !INTERNAL_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// What a version of the dataset changed, see [`Dataset::history`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitRecord {
+    /// The version created by the commit
+    pub version: u64,
+    /// The version the changes are relative to
+    ///
+    /// This is the previous version that still exists, which is `version - 1` unless
+    /// versions have been cleaned up, or `None` for the oldest version.
+    pub previous_version: Option<u64>,
+    /// When the version was committed, in UTC
+    pub timestamp: DateTime<Utc>,
+    /// The name of the operation, e.g. `Append` or `Delete`
+    ///
+    /// This is `None` for versions written without a transaction file.
+    pub operation: Option<String>,
+    /// The number of rows in fragments that were added
+    pub rows_added: u64,
+    /// The number of rows that were deleted or were in fragments that were removed
+    pub rows_deleted: u64,
+    /// The data files added, relative to the `data` directory
+    pub files_added: Vec<String>,
+    /// The data files removed, relative to the `data` directory
+    pub files_removed: Vec<String>,
+    /// The transaction properties set by the committer, such as a job id or author,
+    /// see [`super::CommitBuilder::with_transaction_properties`]
+    pub metadata: BTreeMap<String, String>,
+    /// The id the writer gave the transaction, see
+    /// [`crate::dataset::transaction::Transaction::with_transaction_id`]
+    pub transaction_id: Option<String>,
+    /// How long the commit took, including retries
+    ///
+    /// This is `None` if it was not recorded, e.g. for the first version of a dataset.
+    pub commit_duration: Option<Duration>,
+}
+
+impl CommitRecord {
+    fn new(manifest: &Manifest, previous: Option<&Manifest>, operation: Option<String>) -> Self {
+        let live_rows = |fragment: &Fragment| {
+            let deleted = fragment
+                .deletion_file
+                .as_ref()
+                .and_then(|deletion_file| deletion_file.num_deleted_rows)
+                .unwrap_or(0);
+            fragment.physical_rows.unwrap_or(0).saturating_sub(deleted) as u64
+        };
+
+        // Fragment ids start over after an overwrite, so a fragment is only the same one
+        // if its data files are too
+        let same_fragment = |a: &Fragment, b: &Fragment| {
+            a.id == b.id
+                && a.files
+                    .iter()
+                    .map(|file| &file.path)
+                    .eq(b.files.iter().map(|file| &file.path))
+        };
+        let previous_fragments = previous
+            .map(|previous| {
+                previous
+                    .fragments
+                    .iter()
+                    .map(|fragment| (fragment.id, fragment))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let mut rows_added = 0;
+        let mut rows_deleted = 0;
+        let mut kept_fragments = HashSet::new();
+        for fragment in manifest.fragments.iter() {
+            match previous_fragments
+                .get(&fragment.id)
+                .filter(|previous| same_fragment(previous, fragment))
+            {
+                Some(previous) => {
+                    kept_fragments.insert(fragment.id);
+                    rows_deleted += live_rows(previous).saturating_sub(live_rows(fragment))
+                }
+                None => rows_added += live_rows(fragment),
+            }
+        }
+        rows_deleted += previous_fragments
+            .values()
+            .filter(|fragment| !kept_fragments.contains(&fragment.id))
+            .map(|fragment| live_rows(fragment))
+            .sum::<u64>();
+
+        let data_files = |manifest: &Manifest| {
+            manifest
+                .fragments
+                .iter()
+                .flat_map(|fragment| fragment.files.iter().map(|file| file.path.clone()))
+                .collect::<HashSet<_>>()
+        };
+        let files = data_files(manifest);
+        let previous_files = previous.map(data_files).unwrap_or_default();
+        let mut files_added = files
+            .difference(&previous_files)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut files_removed = previous_files
+            .difference(&files)
+            .cloned()
+            .collect::<Vec<_>>();
+        files_added.sort();
+        files_removed.sort();
+
+        let commit_duration = manifest
+            .transaction_properties
+            .get(COMMIT_DURATION_KEY)
+            .and_then(|millis| millis.parse().ok())
+            .map(Duration::from_millis);
+        let transaction_id = manifest
+            .transaction_properties
+            .get(TRANSACTION_ID_KEY)
+            .cloned();
+
+        Self {
+            version: manifest.version,
+            previous_version: previous.map(|previous| previous.version),
+            timestamp: manifest.timestamp(),
+            operation,
+            rows_added,
+            rows_deleted,
+            files_added,
+            files_removed,
+            metadata: committer_metadata(&manifest.transaction_properties),
+            transaction_id,
+            commit_duration,
+        }
+    }
+}
+
+impl Dataset {
+    /// Describe what each version of the dataset changed, oldest first
+    ///
+    /// Each [`CommitRecord`] has the operation, the rows and data files added and removed,
+    /// the committer's transaction properties, the transaction id and how long the commit
+    /// took.  Only versions
+    /// that have not been cleaned up are listed.
+    pub async fn history(&self) -> Result<Vec<CommitRecord>> {
+        let mut manifests = self
+            .commit_handler
+            .list_manifest_locations(&self.base, &self.object_store, false)
+            .map_ok(|location| async move {
+                read_manifest(&self.object_store, &location.path, location.size).await
+            })
+            .try_buffer_unordered(self.object_store.io_parallelism())
+            .try_collect::<Vec<_>>()
+            .await?;
+        manifests.sort_by_key(|manifest| manifest.version);
+
+        let operations = futures::stream::iter(manifests.iter())
+            .map(|manifest| async move {
+                match &manifest.transaction_file {
+                    Some(path) => read_transaction_file(&self.object_store, &self.base, path)
+                        .await
+                        .map(|transaction| Some(transaction.operation.name().to_string())),
+                    None => Ok(None),
+                }
+            })
+            .buffered(self.object_store.io_parallelism())
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(manifests
+            .iter()
+            .zip(operations)
+            .enumerate()
+            .map(|(idx, (manifest, operation))| {
+                let previous = idx.checked_sub(1).map(|idx| &manifests[idx]);
+                CommitRecord::new(manifest, previous, operation)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+
+    use super::*;
+    use crate::dataset::{CommitBuilder, InsertBuilder, WriteMode, WriteParams};
+
+    #[tokio::test]
+    async fn test_history() {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = |range: std::ops::Range<i32>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(range))],
+            )
+            .unwrap()
+        };
+        let batches = |range: std::ops::Range<i32>| {
+            RecordBatchIterator::new(vec![Ok(batch(range))], schema.clone())
+        };
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let mut dataset = Dataset::write(batches(0..100), test_uri, None)
+            .await
+            .unwrap();
+        dataset
+            .append(
+                batches(100..150),
+                Some(WriteParams {
+                    mode: WriteMode::Append,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        dataset.delete("i < 10").await.unwrap();
+
+        // Overwrite with committer metadata
+        let dataset = Arc::new(dataset);
+        let transaction = InsertBuilder::new(dataset.clone())
+            .with_params(&WriteParams {
+                mode: WriteMode::Overwrite,
+                ..Default::default()
+            })
+            .execute_uncommitted(vec![batch(0..20)])
+            .await
+            .unwrap();
+        let dataset = CommitBuilder::new(dataset)
+            .with_transaction_properties(HashMap::from([(
+                "job".to_string(),
+                "nightly".to_string(),
+            )]))
+            .execute(transaction.with_transaction_id("overwrite-1"))
+            .await
+            .unwrap();
+
+        let history = dataset.history().await.unwrap();
+        assert_eq!(
+            history.iter().map(|r| r.version).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+
+        assert_eq!(history[0].previous_version, None);
+        assert_eq!(history[0].operation.as_deref(), Some("Overwrite"));
+        assert_eq!(history[0].rows_added, 100);
+        assert_eq!(history[0].files_added.len(), 1);
+
+        assert_eq!(history[1].previous_version, Some(1));
+        assert_eq!(history[1].operation.as_deref(), Some("Append"));
+        assert_eq!((history[1].rows_added, history[1].rows_deleted), (50, 0));
+        assert_eq!(history[1].files_added.len(), 1);
+        assert!(history[1].files_removed.is_empty());
+        assert!(history[1].commit_duration.is_some());
+
+        assert_eq!(history[2].operation.as_deref(), Some("Delete"));
+        assert_eq!((history[2].rows_added, history[2].rows_deleted), (0, 10));
+        assert!(history[2].files_added.is_empty());
+
+        assert_eq!(history[3].operation.as_deref(), Some("Overwrite"));
+        assert_eq!((history[3].rows_added, history[3].rows_deleted), (20, 140));
+        assert_eq!(history[3].files_removed.len(), 2);
+        assert_eq!(
+            history[3].metadata,
+            BTreeMap::from([("job".to_string(), "nightly".to_string())])
+        );
+        assert_eq!(history[3].transaction_id.as_deref(), Some("overwrite-1"));
+        assert_eq!(history[2].transaction_id, None);
+
+        // The recorded duration and transaction id are not committer metadata
+        let versions = dataset.versions().await.unwrap();
+        assert!(versions.iter().all(|v| INTERNAL_KEYS
+            .iter()
+            .all(|key| !v.metadata.contains_key(*key))));
+        assert_eq!(versions[3].metadata, history[3].metadata);
+    }
+}
@@ -17,6 +17,10 @@
 //!    compaction.
 //! 2. If a fragment has a higher percentage of deleted rows than the provided
 //!    threshold.
+//! 3. If [CompactionOptions::rebalance_fragments] is set and a fragment has
+//!    more rows than the target number of rows per fragment. The fragment is
+//!    split so that downstream work (parallel scans, distributed index builds)
+//!    gets evenly sized units.
 //!
 //! In addition to the rules above there may be restrictions due to indexes.
 //! When a fragment is compacted its row ids change and any index that contained
@@ -87,16 +91,18 @@ use std::sync::{Arc, RwLock};
 
 use crate::io::commit::{commit_transaction, migrate_fragments};
 use crate::Dataset;
-use crate::Result;
+use crate::{Error, Result};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use futures::{StreamExt, TryStreamExt};
 use lance_core::utils::tokio::get_num_compute_intensive_cpus;
 use lance_index::frag_reuse::FragReuseGroup;
 use lance_index::DatasetIndexExt;
+use lance_io::scheduler::IoPriorityClass;
 use lance_table::format::{Fragment, RowIdMeta};
 use roaring::{RoaringBitmap, RoaringTreemap};
 use serde::{Deserialize, Serialize};
+use snafu::location;
 
 use super::fragment::FileFragment;
 use super::index::DatasetIndexRemapperOptions;
@@ -152,6 +158,20 @@ pub struct CompactionOptions {
     /// not be remapped during this compaction operation. Instead, the fragment reuse index
     /// is updated and will be used to perform remapping later.
     pub defer_index_remap: bool,
+    /// Whether to re-balance row counts across fragments. Defaults to false.
+    ///
+    /// When enabled, fragments with more live rows than
+    /// `target_rows_per_fragment` are split, and every rewritten group of
+    /// fragments is written out as evenly sized fragments instead of full
+    /// fragments followed by a small remainder. The data itself is unchanged.
+    pub rebalance_fragments: bool,
+    /// Whether to keep the data read from the input fragments out of the OS
+    /// page cache. Defaults to false.
+    ///
+    /// Compaction reads every input fragment once, so caching it only pushes
+    /// out the pages that queries are using. This only affects datasets on the
+    /// local file system.
+    pub uncached_reads: bool,
 }
 
 impl Default for CompactionOptions {
@@ -166,6 +186,8 @@ impl Default for CompactionOptions {
             max_bytes_per_file: None,
             batch_size: None,
             defer_index_remap: false,
+            rebalance_fragments: false,
+            uncached_reads: false,
         }
     }
 }
@@ -217,6 +239,7 @@ pub async fn compact_files(
     mut options: CompactionOptions,
     remap_options: Option<Arc<dyn IndexRemapperOptions>>, // These will be deprecated later
 ) -> Result<CompactionMetrics> {
+    dataset.ensure_writable("compact")?;
     options.validate();
 
     let compaction_plan: CompactionPlan = plan_compaction(dataset, &options).await?;
@@ -508,6 +531,11 @@ pub async fn plan_compaction(
             // Only want to compact if their are neighbors to compact such that
             // we can get a larger fragment.
             Some(CompactionCandidacy::CompactWithNeighbors)
+        } else if options.rebalance_fragments
+            && metrics.num_rows() > options.target_rows_per_fragment
+        {
+            // Oversized fragment, split it even if it has no neighbors.
+            Some(CompactionCandidacy::CompactItself)
         } else {
             // Not a candidate
             None
@@ -632,6 +660,26 @@ async fn reserve_fragment_ids(
     Ok(())
 }
 
+/// The number of rows per file that splits `num_rows` into the fewest files
+/// of at most `target_rows` rows, with all files (but the last) the same size.
+fn balanced_rows_per_file(num_rows: usize, target_rows: usize) -> usize {
+    let target_rows = target_rows.max(1);
+    let num_files = num_rows.div_ceil(target_rows).max(1);
+    num_rows.div_ceil(num_files).max(1)
+}
+
+/// The physical rows of a fragment, which [`migrate_fragments`] fills in for
+/// fragments written by old versions of Lance.
+fn physical_rows(fragment: &Fragment) -> Result<usize> {
+    fragment.physical_rows.ok_or_else(|| Error::Internal {
+        message: format!(
+            "Fragment {} has no physical row count to compact",
+            fragment.id
+        ),
+        location: location!(),
+    })
+}
+
 /// Rewrite the files in a single task.
 ///
 /// This assumes that the dataset is the correct read version to be compacted.
@@ -653,6 +701,10 @@ async fn rewrite_files(
         });
     }
 
+    // Rewriting the files is what moves most of the data, keep it within the
+    // background bandwidth limits
+    let dataset = Cow::<Dataset>::Owned(dataset.for_background());
+
     let previous_writer_version = &dataset.manifest.writer_version;
     // The versions of Lance prior to when we started writing the writer version
     // sometimes wrote incorrect `Fragment.physical_rows` values, so we should
@@ -666,8 +718,8 @@ async fn rewrite_files(
     let fragments = migrate_fragments(dataset.as_ref(), &task.fragments, recompute_stats).await?;
     let num_rows = fragments
         .iter()
-        .map(|f| f.physical_rows.unwrap() as u64)
-        .sum::<u64>();
+        .map(|f| physical_rows(f).map(|rows| rows as u64))
+        .sum::<Result<u64>>()?;
     // If we aren't using move-stable row ids, then we need to remap indices.
     let needs_remapping = !dataset.manifest.uses_move_stable_row_ids();
     let mut scanner = dataset.scan();
@@ -684,7 +736,9 @@ async fn rewrite_files(
     );
     scanner
         .with_fragments(fragments.clone())
-        .scan_in_order(true);
+        .scan_in_order(true)
+        .io_priority(IoPriorityClass::Background)
+        .uncached_reads(options.uncached_reads);
     let (row_ids, reader) = if needs_remapping {
         let row_ids = Arc::new(RwLock::new(RoaringTreemap::new()));
         scanner.with_row_id();
@@ -709,8 +763,17 @@ async fn rewrite_files(
     });
     let reader = Box::pin(RecordBatchStreamAdapter::new(schema, reader));
 
+    let max_rows_per_file = if options.rebalance_fragments {
+        let live_rows = fragments
+            .iter()
+            .map(|f| f.num_rows().map_or_else(|| physical_rows(f), Ok))
+            .sum::<Result<usize>>()?;
+        balanced_rows_per_file(live_rows, options.target_rows_per_fragment)
+    } else {
+        options.target_rows_per_fragment
+    };
     let mut params = WriteParams {
-        max_rows_per_file: options.target_rows_per_fragment,
+        max_rows_per_file,
         max_rows_per_group: options.max_rows_per_group,
         mode: WriteMode::Append,
         ..Default::default()
@@ -990,6 +1053,7 @@ mod tests {
     use lance_index::vector::ivf::IvfBuildParams;
     use lance_index::vector::pq::PQBuildParams;
     use lance_index::IndexType;
+    use lance_io::throttle::{BandwidthLimiter, BandwidthLimits};
     use lance_linalg::distance::{DistanceType, MetricType};
     use lance_table::io::manifest::read_manifest_indexes;
     use lance_testing::datagen::{BatchGenerator, IncrementingInt32, RandomVector};
@@ -1224,6 +1288,56 @@ mod tests {
         assert_eq!(plan.tasks().len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_rebalance_fragments() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        // One oversized fragment followed by a small one
+        let data = sample_data();
+        let reader = RecordBatchIterator::new(vec![Ok(data.slice(0, 9_000))], data.schema());
+        let write_params = WriteParams {
+            max_rows_per_file: 9_000,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(reader, test_uri, Some(write_params))
+            .await
+            .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(data.slice(9_000, 1_000))], data.schema());
+        dataset.append(reader, None).await.unwrap();
+
+        // Without re-balancing the large fragment is left alone
+        let options = CompactionOptions {
+            target_rows_per_fragment: 4_000,
+            ..Default::default()
+        };
+        let plan = plan_compaction(&dataset, &options).await.unwrap();
+        assert_eq!(plan.tasks().len(), 0);
+
+        let options = CompactionOptions {
+            rebalance_fragments: true,
+            ..options
+        };
+        let plan = plan_compaction(&dataset, &options).await.unwrap();
+        assert_eq!(plan.tasks().len(), 1);
+        assert_eq!(plan.tasks()[0].fragments.len(), 2);
+
+        let metrics = compact_files(&mut dataset, options, None).await.unwrap();
+        assert_eq!(metrics.fragments_removed, 2);
+        assert_eq!(metrics.fragments_added, 3);
+
+        let row_counts = dataset
+            .get_fragments()
+            .iter()
+            .map(|f| f.metadata.num_rows().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(row_counts, vec![3_334, 3_334, 3_332]);
+
+        // Data is unchanged
+        let batch = dataset.scan().try_into_batch().await.unwrap();
+        assert_eq!(batch.column(0), data.column(0));
+    }
+
     fn row_ids(frag_idx: u32, offsets: Range<u32>) -> Range<u64> {
         let start = RowAddress::new_from_parts(frag_idx, offsets.start);
         let end = RowAddress::new_from_parts(frag_idx, offsets.end);
@@ -3419,4 +3533,37 @@ mod tests {
             plan
         );
     }
+
+    #[tokio::test]
+    async fn test_compaction_bandwidth_limits() {
+        let mut dataset = lance_datagen::gen()
+            .col("i", lance_datagen::array::step::<Int32Type>())
+            .into_ram_dataset(FragmentCount::from(4), FragmentRowCount::from(1000))
+            .await
+            .unwrap();
+        // The files are rewritten in tasks of their own, the limits have to go
+        // with the store
+        let download = Arc::new(BandwidthLimiter::new(u64::MAX));
+        let upload = Arc::new(BandwidthLimiter::new(u64::MAX));
+        dataset.object_store =
+            Arc::new(dataset.object_store.with_bandwidth_limits(BandwidthLimits {
+                download: Some(download.clone()),
+                upload: Some(upload.clone()),
+            }));
+
+        compact_files(&mut dataset, CompactionOptions::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(dataset.get_fragments().len(), 1);
+
+        let mut data_size = 0;
+        for fragment in dataset.get_fragments() {
+            for file in &fragment.metadata().files {
+                let path = dataset.data_dir().child(file.path.as_str());
+                data_size += dataset.object_store.size(&path).await.unwrap();
+            }
+        }
+        assert!(download.bytes_transferred() > 0);
+        assert!(upload.bytes_transferred() >= data_size);
+    }
 }
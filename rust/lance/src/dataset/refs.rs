@@ -9,6 +9,7 @@ use lance_io::object_store::ObjectStore;
 use lance_table::io::commit::CommitHandler;
 use object_store::path::Path;
 use serde::{Deserialize, Serialize};
+use snafu::location;
 use std::sync::Arc;
 
 use crate::{Error, Result};
@@ -39,6 +40,7 @@ pub struct Tags {
     object_store: Arc<ObjectStore>,
     commit_handler: Arc<dyn CommitHandler>,
     base: Path,
+    read_only: bool,
 }
 
 impl Tags {
@@ -51,9 +53,26 @@ impl Tags {
             object_store,
             commit_handler,
             base,
+            read_only: false,
         }
     }
 
+    /// Reject changes to tags, for datasets opened read-only
+    pub(crate) fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly {
+                message: "cannot change the tags of a dataset opened read-only".to_string(),
+                location: location!(),
+            });
+        }
+        Ok(())
+    }
+
     async fn fetch_tags(&self) -> Result<Vec<(String, TagContents)>> {
         let base_path = base_tags_path(&self.base);
         let tag_files = self.object_store().read_dir(base_path).await?;
@@ -120,6 +139,7 @@ impl Tags {
     }
 
     pub async fn create(&mut self, tag: &str, version: u64) -> Result<()> {
+        self.ensure_writable()?;
         check_valid_ref(tag)?;
 
         let tag_file = tag_path(&self.base, tag);
@@ -162,6 +182,7 @@ impl Tags {
     }
 
     pub async fn delete(&mut self, tag: &str) -> Result<()> {
+        self.ensure_writable()?;
         check_valid_ref(tag)?;
 
         let tag_file = tag_path(&self.base, tag);
@@ -176,6 +197,7 @@ impl Tags {
     }
 
     pub async fn update(&mut self, tag: &str, version: u64) -> Result<()> {
+        self.ensure_writable()?;
         check_valid_ref(tag)?;
 
         let tag_file = tag_path(&self.base, tag);
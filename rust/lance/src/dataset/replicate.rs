@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Copy a dataset version to another location, e.g. a bucket in another region.
+//!
+//! All files referenced by the version are copied first: data files, deletion
+//! files, index files, the transaction file and, recursively, the blob dataset.
+//! The manifest is committed at the target last, so the target only becomes
+//! visible once all of its files are in place. Files are copied server-side
+//! when the target is in the same store as the dataset, e.g. the same bucket,
+//! see [`ObjectStore::copy_from`].
+//!
+//! A replication that was interrupted can be resumed by running it again.
+//! Files that already exist at the target with the expected size are not
+//! copied a second time. Replicating a newer version to the same target later
+//! on only copies the files that were added since.
+
+use std::sync::Arc;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use lance_core::{Error, Result};
+use lance_index::DatasetIndexExt;
+use lance_io::object_store::{ObjectStore, ObjectStoreParams};
+use lance_table::io::commit::{commit_handler_from_url, CommitError, CommitHandler};
+use lance_table::io::deletion::deletion_file_path;
+use lance_table::io::manifest::read_manifest;
+use object_store::path::Path;
+use snafu::location;
+
+use super::{write_manifest_file_to_path, Dataset, BLOB_DIR};
+
+/// Options for [`Dataset::replicate_to`].
+#[derive(Debug, Clone)]
+pub struct ReplicateOptions {
+    /// Parameters used to access the target.
+    pub store_params: Option<ObjectStoreParams>,
+    /// Commit handler of the target. By default it is derived from the target
+    /// uri, the same way as when opening a dataset.
+    pub commit_handler: Option<Arc<dyn CommitHandler>>,
+    /// Number of files copied at the same time. Defaults to the I/O
+    /// parallelism of the target store.
+    pub max_concurrency: Option<usize>,
+    /// Check the size of every copied file at the target against the source.
+    /// Defaults to true.
+    pub verify: bool,
+}
+
+impl Default for ReplicateOptions {
+    fn default() -> Self {
+        Self {
+            store_params: None,
+            commit_handler: None,
+            max_concurrency: None,
+            verify: true,
+        }
+    }
+}
+
+/// What [`Dataset::replicate_to`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicateSummary {
+    /// The version committed at the target.
+    pub version: u64,
+    /// Number of files copied to the target.
+    pub files_copied: usize,
+    /// Number of files that already existed at the target.
+    pub files_skipped: usize,
+    /// Number of bytes copied to the target.
+    pub bytes_copied: u64,
+}
+
+impl ReplicateSummary {
+    fn add(&mut self, other: &Self) {
+        self.files_copied += other.files_copied;
+        self.files_skipped += other.files_skipped;
+        self.bytes_copied += other.bytes_copied;
+    }
+}
+
+/// A file to copy, relative to the dataset root.
+struct ReplicatedFile {
+    path: Path,
+    size: Option<u64>,
+}
+
+fn join(base: &Path, relative: &Path) -> Path {
+    Path::from_iter(base.parts().chain(relative.parts()))
+}
+
+fn relative_to(path: &Path, base: &Path) -> Result<Path> {
+    let parts = path.prefix_match(base).ok_or_else(|| Error::Internal {
+        message: format!("{} is not under the dataset root {}", path, base),
+        location: location!(),
+    })?;
+    Ok(Path::from_iter(parts))
+}
+
+pub(super) async fn replicate_dataset(
+    dataset: &Dataset,
+    target_uri: &str,
+    options: &ReplicateOptions,
+) -> Result<ReplicateSummary> {
+    let (target_store, target_base) = ObjectStore::from_uri_and_params(
+        dataset.session.store_registry(),
+        target_uri,
+        &options.store_params.clone().unwrap_or_default(),
+    )
+    .await?;
+    let commit_handler = match &options.commit_handler {
+        Some(commit_handler) => commit_handler.clone(),
+        None => commit_handler_from_url(target_uri, &options.store_params).await?,
+    };
+    let target = Target {
+        store: &target_store,
+        commit_handler: commit_handler.as_ref(),
+        options,
+    };
+    target.replicate(dataset, &target_base).await
+}
+
+struct Target<'a> {
+    store: &'a ObjectStore,
+    commit_handler: &'a dyn CommitHandler,
+    options: &'a ReplicateOptions,
+}
+
+impl Target<'_> {
+    async fn replicate(&self, dataset: &Dataset, base: &Path) -> Result<ReplicateSummary> {
+        let mut summary = ReplicateSummary::default();
+        if let Some(blobs) = dataset.blobs_dataset().await? {
+            let blobs_summary =
+                Box::pin(self.replicate(blobs.as_ref(), &base.child(BLOB_DIR))).await?;
+            summary.add(&blobs_summary);
+        }
+
+        let files = referenced_files(dataset).await?;
+        let concurrency = self
+            .options
+            .max_concurrency
+            .unwrap_or_else(|| self.store.io_parallelism())
+            .max(1);
+        let copied = stream::iter(files)
+            .map(|file| self.copy_file(dataset, base, file))
+            .buffer_unordered(concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for bytes in copied {
+            match bytes {
+                Some(bytes) => {
+                    summary.files_copied += 1;
+                    summary.bytes_copied += bytes;
+                }
+                None => summary.files_skipped += 1,
+            }
+        }
+
+        self.commit(dataset, base).await?;
+        summary.version = dataset.manifest.version;
+        Ok(summary)
+    }
+
+    /// Copy a file unless the target already has it, and return the number of
+    /// bytes copied.
+    async fn copy_file(
+        &self,
+        dataset: &Dataset,
+        base: &Path,
+        file: ReplicatedFile,
+    ) -> Result<Option<u64>> {
+        let source_path = join(&dataset.base, &file.path);
+        let target_path = join(base, &file.path);
+        let size = match file.size {
+            Some(size) => size,
+            None => dataset.object_store.size(&source_path).await?,
+        };
+        match self.store.inner.head(&target_path).await {
+            Ok(meta) if meta.size == size => return Ok(None),
+            Ok(_) | Err(object_store::Error::NotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        self.store
+            .copy_from(&dataset.object_store, &source_path, &target_path)
+            .await?;
+
+        if self.options.verify {
+            let copied_size = self.store.size(&target_path).await?;
+            if copied_size != size {
+                return Err(Error::corrupt_file(
+                    target_path,
+                    format!(
+                        "replicated file has {} bytes but the source has {}",
+                        copied_size, size
+                    ),
+                    location!(),
+                ));
+            }
+        }
+        Ok(Some(size))
+    }
+
+    async fn commit(&self, dataset: &Dataset, base: &Path) -> Result<()> {
+        let indices = dataset.load_indices().await?;
+        let indices = if indices.is_empty() {
+            None
+        } else {
+            Some(indices.as_ref().clone())
+        };
+        let mut manifest = dataset.manifest.as_ref().clone();
+        let result = self
+            .commit_handler
+            .commit(
+                &mut manifest,
+                indices,
+                base,
+                self.store,
+                write_manifest_file_to_path,
+                dataset.manifest_location.naming_scheme,
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(()),
+            // The version may have been committed by an earlier run that was
+            // interrupted before it returned.
+            Err(CommitError::CommitConflict) => {
+                let location = self
+                    .commit_handler
+                    .resolve_version_location(base, manifest.version, &self.store.inner)
+                    .await?;
+                let existing = read_manifest(self.store, &location.path, location.size).await?;
+                if existing.fragments == manifest.fragments && existing.schema == manifest.schema {
+                    Ok(())
+                } else {
+                    Err(Error::invalid_input(
+                        format!(
+                            "the target already has a different version {}",
+                            manifest.version
+                        ),
+                        location!(),
+                    ))
+                }
+            }
+            Err(CommitError::OtherError(err)) => Err(err),
+        }
+    }
+}
+
+/// All files of the checked out version, except the manifest.
+async fn referenced_files(dataset: &Dataset) -> Result<Vec<ReplicatedFile>> {
+    let mut files = Vec::new();
+    for fragment in dataset.manifest.fragments.iter() {
+        for data_file in &fragment.files {
+            let path = dataset.data_dir().child(data_file.path.as_str());
+            files.push(ReplicatedFile {
+                path: relative_to(&path, &dataset.base)?,
+                size: data_file.file_size_bytes.get().map(u64::from),
+            });
+        }
+        if let Some(deletion_file) = &fragment.deletion_file {
+            let path = deletion_file_path(&dataset.base, fragment.id, deletion_file);
+            files.push(ReplicatedFile {
+                path: relative_to(&path, &dataset.base)?,
+                size: None,
+            });
+        }
+    }
+
+    if let Some(transaction_file) = &dataset.manifest.transaction_file {
+        files.push(ReplicatedFile {
+            path: Path::from_iter(["_transactions", transaction_file.as_str()]),
+            size: None,
+        });
+    }
+
+    for index in dataset.load_indices().await?.iter() {
+        let index_dir = dataset.indices_dir().child(index.uuid.to_string());
+        let index_files = dataset
+            .object_store
+            .read_dir_all(&index_dir, None)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for meta in index_files {
+            files.push(ReplicatedFile {
+                path: relative_to(&meta.location, &dataset.base)?,
+                size: Some(meta.size),
+            });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator, RecordBatchReader};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+    use lance_index::{scalar::ScalarIndexParams, IndexType};
+
+    use crate::dataset::WriteParams;
+
+    fn test_data(start: i32) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(start..start + 100))],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    #[tokio::test]
+    async fn test_replicate_to() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source_uri = source_dir.path().to_str().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let target_uri = target_dir.path().to_str().unwrap();
+
+        let write_params = WriteParams {
+            max_rows_per_file: 50,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(test_data(0), source_uri, Some(write_params))
+            .await
+            .unwrap();
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::Scalar,
+                Some("i_idx".to_string()),
+                &ScalarIndexParams::default(),
+                false,
+            )
+            .await
+            .unwrap();
+        dataset.delete("i < 10").await.unwrap();
+
+        let options = ReplicateOptions::default();
+        let summary = dataset.replicate_to(target_uri, &options).await.unwrap();
+        assert_eq!(summary.version, dataset.version().version);
+        assert_eq!(summary.files_skipped, 0);
+        assert!(summary.bytes_copied > 0);
+
+        let replica = Dataset::open(target_uri).await.unwrap();
+        assert_eq!(replica.version().version, dataset.version().version);
+        assert_eq!(replica.count_rows(None).await.unwrap(), 90);
+        assert_eq!(replica.load_indices().await.unwrap().len(), 1);
+        assert!(replica
+            .verify(&Default::default())
+            .await
+            .unwrap()
+            .is_healthy());
+
+        // Running again copies nothing
+        let summary = dataset.replicate_to(target_uri, &options).await.unwrap();
+        assert_eq!(summary.files_copied, 0);
+
+        // A newer version only copies the new files, and a lost file is
+        // copied again
+        let fragment = &replica.manifest.fragments[0];
+        let data_path = replica.data_dir().child(fragment.files[0].path.as_str());
+        replica.object_store.delete(&data_path).await.unwrap();
+        dataset.append(test_data(100), None).await.unwrap();
+        let summary = dataset.replicate_to(target_uri, &options).await.unwrap();
+        // The new data file, transaction file and the lost data file
+        assert_eq!(summary.files_copied, 3);
+
+        let replica = Dataset::open(target_uri).await.unwrap();
+        assert_eq!(replica.version().version, dataset.version().version);
+        assert_eq!(replica.count_rows(None).await.unwrap(), 190);
+    }
+}
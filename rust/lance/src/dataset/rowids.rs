@@ -421,5 +421,73 @@ mod test {
         assert_eq!(index.get(5), Some(RowAddress::new_from_parts(1, 0)));
     }
 
+    #[tokio::test]
+    async fn test_enable_stable_row_ids() {
+        let batch = sequence_batch(0..6);
+        let reader = RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema());
+        let write_params = WriteParams {
+            max_rows_per_file: 2,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(reader, "memory://", Some(write_params))
+            .await
+            .unwrap();
+        assert!(!dataset.manifest().uses_move_stable_row_ids());
+        dataset.delete("id = 3").await.unwrap();
+
+        // Indices refer to row addresses, so they must be dropped first.
+        dataset
+            .create_index(
+                &["id"],
+                IndexType::Scalar,
+                Some("id_idx".into()),
+                &ScalarIndexParams::default(),
+                false,
+            )
+            .await
+            .unwrap();
+        let err = dataset.enable_stable_row_ids().await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        dataset.drop_index("id_idx").await.unwrap();
+
+        let data_files = dataset
+            .get_fragments()
+            .iter()
+            .map(|f| f.metadata().files.clone())
+            .collect::<Vec<_>>();
+        dataset.enable_stable_row_ids().await.unwrap();
+        assert!(dataset.manifest().uses_move_stable_row_ids());
+        assert_eq!(dataset.manifest().next_row_id, 6);
+        // Only fragment metadata changes.
+        let migrated_files = dataset
+            .get_fragments()
+            .iter()
+            .map(|f| f.metadata().files.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(data_files, migrated_files);
+
+        let err = dataset.enable_stable_row_ids().await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        // New rows continue from the assigned ids.
+        let batch = sequence_batch(6..8);
+        let reader = RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema());
+        dataset.append(reader, None).await.unwrap();
+        assert_eq!(dataset.manifest().next_row_id, 8);
+
+        let mut scan = dataset.scan();
+        scan.with_row_id();
+        let result = scan.try_into_batch().await.unwrap();
+        let row_ids = result[ROW_ID]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        let expected = vec![0, 1, 2, 4, 5, 6, 7].into();
+        assert_eq!(row_ids, &expected);
+
+        let index = get_row_id_index(&dataset).await.unwrap().unwrap();
+        assert_eq!(index.get(4), Some(RowAddress::new_from_parts(2, 0)));
+    }
+
     // TODO: query / scan / take after deletion, compaction, then deletion
 }
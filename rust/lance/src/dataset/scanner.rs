@@ -4,6 +4,7 @@
 use std::collections::HashMap;
 use std::ops::Range;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
@@ -15,12 +16,13 @@ use async_recursion::async_recursion;
 use datafusion::common::{DFSchema, SchemaExt};
 use datafusion::functions_aggregate;
 use datafusion::functions_aggregate::count::count_udaf;
-use datafusion::logical_expr::{col, lit, Expr};
+use datafusion::logical_expr::{col, lit, Expr, Operator};
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
 use datafusion::physical_plan::expressions;
 use datafusion::physical_plan::projection::ProjectionExec as DFProjectionExec;
 use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy},
     display::DisplayableExecutionPlan,
@@ -41,38 +43,56 @@ use futures::{FutureExt, TryStreamExt};
 use lance_arrow::floats::{coerce_float_vector, FloatType};
 use lance_arrow::DataTypeExt;
 use lance_core::datatypes::{Field, OnMissing, Projection};
+use lance_core::utils::cancellation::CancellationToken;
+use lance_core::utils::futures::FinallyStreamExt;
+use lance_core::utils::mask::RowIdTreeMap;
+use lance_core::utils::metrics;
 use lance_core::utils::tokio::get_num_compute_intensive_cpus;
 use lance_core::{ROW_ADDR, ROW_ADDR_FIELD, ROW_ID, ROW_ID_FIELD};
-use lance_datafusion::exec::{analyze_plan, execute_plan, LanceExecutionOptions};
+use lance_datafusion::chunker::byte_sized_stream;
+use lance_datafusion::exec::{
+    analyze_plan, analyze_plan_metrics, execute_plan, plan_summary_counts, LanceExecutionOptions,
+    PlanNodeMetrics,
+};
 use lance_datafusion::projection::ProjectionPlan;
+use lance_datafusion::sql::{parse_sql_column_path, parse_sql_projection};
+use lance_datafusion::utils::{cancellable_stream, FRAGMENTS_SCANNED_METRIC};
 use lance_index::scalar::expression::PlannerIndexExt;
 use lance_index::scalar::inverted::query::{
     fill_fts_query_column, FtsQuery, FtsSearchParams, MatchQuery,
 };
 use lance_index::scalar::inverted::SCORE_COL;
-use lance_index::scalar::{FullTextSearchQuery, ScalarIndexType};
+use lance_index::scalar::{btree::BTreeIndex, FullTextSearchQuery, ScalarIndexType};
 use lance_index::vector::{Query, DIST_COL};
 use lance_index::ScalarIndexCriteria;
 use lance_index::{metrics::NoOpMetricsCollector, scalar::inverted::FTS_SCHEMA};
 use lance_index::{scalar::expression::ScalarIndexExpr, DatasetIndexExt};
+use lance_io::scheduler::IoPriorityClass;
 use lance_io::stream::RecordBatchStream;
 use lance_linalg::distance::MetricType;
 use lance_table::format::{Fragment, Index};
 use roaring::RoaringBitmap;
-use tracing::{info_span, instrument, Span};
+use tracing::{info_span, instrument, Instrument, Span};
 
+use super::sorted_projection::SortedProjection;
 use super::Dataset;
 use crate::index::scalar::detect_scalar_index_type;
 use crate::index::vector::utils::{get_vector_dim, get_vector_type};
 use crate::index::DatasetIndexInternalExt;
 use crate::io::exec::fts::{BoostQueryExec, FlatMatchQueryExec, MatchQueryExec, PhraseQueryExec};
 use crate::io::exec::knn::MultivectorScoringExec;
-use crate::io::exec::scalar_index::{MaterializeIndexExec, ScalarIndexExec};
+use crate::io::exec::scalar_index::{
+    IndexValueCountExec, MaterializeIndexExec, OrderedIndexScanExec, ScalarIndexCountExec,
+    ScalarIndexExec,
+};
 use crate::io::exec::{get_physical_optimizer, LanceFilterExec, LanceScanConfig};
 use crate::io::exec::{
-    knn::new_knn_exec, project, AddRowAddrExec, FilterPlan, KNNVectorDistanceExec,
-    LancePushdownScanExec, LanceScanExec, Planner, PreFilterSource, ScanConfig, TakeExec,
+    knn::new_knn_exec, project, AddRowAddrExec, AllowListFilterExec, AllowListPrefilterExec,
+    FilterPlan, KNNVectorDistanceExec, LancePushdownScanExec, LanceScanExec, Planner,
+    PreFilterSource, ScanConfig, SortedProjectionScanExec, TakeExec,
 };
+use crate::session::slow_query_log::QueryDescription;
+use crate::utils::query_tracing::query_span;
 use crate::{datatypes::Schema, io::exec::fts::BooleanQueryExec};
 use crate::{Error, Result};
 use snafu::location;
@@ -95,6 +115,9 @@ lazy_static::lazy_static! {
     pub static ref DEFAULT_FRAGMENT_READAHEAD: Option<usize> = std::env::var("LANCE_DEFAULT_FRAGMENT_READAHEAD")
         .map(|val| Some(val.parse().unwrap())).unwrap_or(None);
 
+    pub static ref DEFAULT_PREFETCH_DEPTH: usize = std::env::var("LANCE_DEFAULT_PREFETCH_DEPTH")
+        .map(|val| val.parse().unwrap()).unwrap_or(0);
+
     pub static ref DEFAULT_XTR_OVERFETCH: u32 = std::env::var("LANCE_XTR_OVERFETCH")
         .map(|val| val.parse().unwrap()).unwrap_or(10);
 }
@@ -111,6 +134,7 @@ lazy_static::lazy_static! {
 ///
 /// Floats are sorted using the IEEE 754 total ordering
 /// Strings are sorted using UTF-8 lexicographic order (i.e. we sort the binary)
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ColumnOrdering {
     pub ascending: bool,
     pub nulls_first: bool,
@@ -181,19 +205,99 @@ pub enum MaterializationStyle {
     AllLate,
     /// All columns will be fetched with early materialization where possible
     AllEarly,
-    /// All columns will be fetched with late materialization except for the specified columns
+    /// All columns will be fetched with early materialization except for the specified columns
     AllEarlyExcept(Vec<u32>),
+    /// All columns will be fetched with late materialization except for the specified columns
+    AllLateExcept(Vec<u32>),
+    /// Like [`Self::Heuristic`] but with the expected fraction of rows (0-1) that pass the filter
+    ///
+    /// The more rows pass the filter, the wider a column can be and still be cheaper to
+    /// fetch early.  The width of variable-width columns (e.g. strings) is not known when
+    /// planning so they are assumed to be [`VARIABLE_WIDTH_ESTIMATE`] bytes wide.
+    ///
+    /// Use this when a filter is moderately selective, e.g. a filter that selects 10% of the
+    /// rows is much cheaper to evaluate with short string columns fetched early.
+    ExpectedSelectivity(f64),
+}
+
+/// The assumed width, in bytes, of a variable-width value for
+/// [`MaterializationStyle::ExpectedSelectivity`]
+pub const VARIABLE_WIDTH_ESTIMATE: usize = 64;
+
+/// The name of a system column with the version that last wrote each row
+///
+/// Rows and fragments don't record the version that wrote them, so the column can't be
+/// provided.  Filters and projections that refer to it get a clear error rather than a
+/// missing column.
+const ROW_VERSION: &str = "_version";
+
+fn check_row_version_unsupported(columns: &[String], schema: &Schema) -> Result<()> {
+    if schema.field(ROW_VERSION).is_none() && columns.iter().any(|column| column == ROW_VERSION) {
+        return Err(Error::NotSupported {
+            source: format!(
+                "The {} system column is not supported because rows don't record the \
+                 version that wrote them.  Use Dataset::checkout_version or \
+                 Dataset::history to read the data of earlier versions.",
+                ROW_VERSION
+            )
+            .into(),
+            location: location!(),
+        });
+    }
+    Ok(())
+}
+
+/// Replaces the error of a projection that refers to [`ROW_VERSION`] with one that
+/// explains why the column is missing
+fn explain_projection_error(
+    err: Error,
+    schema: &Schema,
+    columns: &[(impl AsRef<str>, impl AsRef<str>)],
+) -> Error {
+    let planner = Planner::new(Arc::new(schema.into()));
+    let referenced = columns
+        .iter()
+        .filter_map(|(_, expr)| planner.parse_expr(expr.as_ref()).ok())
+        .flat_map(|expr| Planner::column_names_in_expr(&expr))
+        .collect::<Vec<_>>();
+    check_row_version_unsupported(&referenced, schema)
+        .err()
+        .unwrap_or(err)
 }
 
 impl MaterializationStyle {
-    pub fn all_early_except(columns: &[impl AsRef<str>], schema: &Schema) -> Result<Self> {
-        let field_ids = schema
+    fn field_ids(columns: &[impl AsRef<str>], schema: &Schema) -> Result<Vec<u32>> {
+        Ok(schema
             .project(columns)?
             .field_ids()
             .into_iter()
             .map(|id| id as u32)
-            .collect();
-        Ok(Self::AllEarlyExcept(field_ids))
+            .collect())
+    }
+
+    /// Fetch the given columns late and all other columns early
+    pub fn all_early_except(columns: &[impl AsRef<str>], schema: &Schema) -> Result<Self> {
+        Ok(Self::AllEarlyExcept(Self::field_ids(columns, schema)?))
+    }
+
+    /// Fetch the given columns early and all other columns late
+    pub fn all_late_except(columns: &[impl AsRef<str>], schema: &Schema) -> Result<Self> {
+        Ok(Self::AllLateExcept(Self::field_ids(columns, schema)?))
+    }
+
+    /// Pick the materialization of each column based on the expected fraction of rows
+    /// that pass the filter
+    pub fn expected_selectivity(selectivity: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&selectivity) {
+            return Err(Error::invalid_input(
+                format!(
+                    "the expected selectivity must be between 0 and 1 but got {}",
+                    selectivity
+                ),
+                location!(),
+            ));
+        }
+        Ok(Self::ExpectedSelectivity(selectivity))
     }
 }
 
@@ -225,6 +329,10 @@ impl LanceFilter {
                 let schema = Arc::new(ArrowSchema::from(full_schema));
                 let planner = Planner::new(schema);
                 let filter = planner.parse_filter(sql)?;
+                check_row_version_unsupported(
+                    &Planner::column_names_in_expr(&filter),
+                    full_schema,
+                )?;
                 planner.optimize_expr(filter).map_err(|e| {
                     Error::invalid_input(
                         format!("Error optimizing sql filter: {sql} ({e})"),
@@ -257,6 +365,89 @@ impl LanceFilter {
     }
 }
 
+/// A snapshot of how far a scan has progressed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// The number of fragments the scan has finished reading
+    pub fragments_completed: usize,
+    /// The number of rows the scan has returned so far
+    pub rows_produced: usize,
+    /// The number of bytes read from storage so far
+    pub bytes_read: usize,
+}
+
+/// Called with the progress of a scan each time it returns a batch, and once
+/// more when it finishes
+///
+/// To stop a scan that is over budget, cancel its [`CancellationToken`] from the
+/// callback.
+pub type ScanProgressCallback = Arc<dyn Fn(&ScanProgress) + Send + Sync>;
+
+/// Report the progress of `plan` to `callback` as `batches` are returned
+fn progress_stream(
+    batches: SendableRecordBatchStream,
+    plan: Arc<dyn ExecutionPlan>,
+    callback: ScanProgressCallback,
+) -> SendableRecordBatchStream {
+    let schema = batches.schema();
+    let report = Arc::new(move |rows_produced: usize| {
+        let counts = plan_summary_counts(plan.as_ref());
+        callback(&ScanProgress {
+            fragments_completed: counts
+                .all_counts
+                .get(FRAGMENTS_SCANNED_METRIC)
+                .copied()
+                .unwrap_or(0),
+            rows_produced,
+            bytes_read: counts.bytes_read,
+        });
+    });
+    let rows_produced = Arc::new(AtomicUsize::new(0));
+    let on_batch = {
+        let report = report.clone();
+        let rows_produced = rows_produced.clone();
+        move |batch: &RecordBatch| {
+            let num_rows = batch.num_rows();
+            report(rows_produced.fetch_add(num_rows, Ordering::Relaxed) + num_rows);
+        }
+    };
+    // The last I/O and fragment counts are only recorded once the plan finishes so
+    // make a final report after the end of the stream
+    let batches = batches.inspect_ok(on_batch).chain(
+        futures::stream::once(async move { report(rows_produced.load(Ordering::Relaxed)) })
+            .filter_map(|_| std::future::ready(None)),
+    );
+    Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+}
+
+/// Report the rows and duration of a query to the metrics recorder, see
+/// [`lance_core::utils::metrics`]
+fn metrics_stream(batches: SendableRecordBatchStream) -> SendableRecordBatchStream {
+    let schema = batches.schema();
+    let start = std::time::Instant::now();
+    let rows_produced = Arc::new(AtomicUsize::new(0));
+    let batches = batches
+        .inspect_ok({
+            let rows_produced = rows_produced.clone();
+            move |batch: &RecordBatch| {
+                rows_produced.fetch_add(batch.num_rows(), Ordering::Relaxed);
+            }
+        })
+        .finally(move || {
+            metrics::increment_counter(
+                metrics::SCAN_ROWS,
+                &[],
+                rows_produced.load(Ordering::Relaxed) as u64,
+            );
+            metrics::record_histogram(
+                metrics::SCAN_DURATION_SECONDS,
+                &[],
+                start.elapsed().as_secs_f64(),
+            );
+        });
+    Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+}
+
 /// Dataset Scanner
 ///
 /// ```rust,ignore
@@ -278,6 +469,9 @@ pub struct Scanner {
     /// If true then the filter will be applied before an index scan
     prefilter: bool,
 
+    /// If set, only these row ids are candidates for a vector search
+    allowed_row_ids: Option<Arc<RowIdTreeMap>>,
+
     /// Materialization style controls when columns are fetched
     materialization_style: MaterializationStyle,
 
@@ -290,15 +484,34 @@ pub struct Scanner {
     /// The batch size controls the maximum size of rows to return for each read.
     batch_size: Option<usize>,
 
+    /// If set, output batches are split or combined to hold roughly this many bytes
+    batch_size_bytes: Option<usize>,
+
     /// Number of batches to prefetch
     batch_readahead: usize,
 
     /// Number of fragments to read concurrently
     fragment_readahead: Option<usize>,
 
+    /// Number of IOPS each data file may have in flight
+    fragment_io_parallelism: Option<usize>,
+
     /// Number of bytes to allow to queue up in the I/O buffer
     io_buffer_size: Option<u64>,
 
+    /// Priority class of the I/O issued by the scan
+    io_priority: IoPriorityClass,
+
+    /// If set, the total memory budget of the scan, split between the I/O buffer and
+    /// the DataFusion memory pool
+    memory_limit: Option<u64>,
+
+    /// Number of upcoming fragments to prefetch metadata for
+    prefetch_depth: Option<usize>,
+
+    /// Whether to keep the data read from local files out of the page cache
+    uncached_reads: bool,
+
     limit: Option<i64>,
     offset: Option<i64>,
 
@@ -312,6 +525,15 @@ pub struct Scanner {
     /// will always be unordered since we are just going to reorder it anyways.
     ordering: Option<Vec<ColumnOrdering>>,
 
+    /// The name of a sorted projection to read ordered scans from, if possible
+    sorted_projection: Option<String>,
+
+    /// If true, duplicate rows are removed from the output
+    distinct: bool,
+
+    /// If set, the output is the number of rows for each value of this column
+    count_by: Option<String>,
+
     nearest: Option<Query>,
 
     /// If false, do not use any scalar indices for the scan
@@ -337,6 +559,10 @@ pub struct Scanner {
     /// This field is ignored if `ordering` is defined
     ordered: bool,
 
+    /// If true, rows are always returned in fragment order and then row offset order,
+    /// regardless of the plan, and ties in `ordering` are broken the same way
+    deterministic_order: bool,
+
     /// If set, this scanner serves only these fragments.
     fragments: Option<Vec<Fragment>>,
 
@@ -354,6 +580,15 @@ pub struct Scanner {
     /// If set, this callback will be called after the scan with summary statistics
     scan_stats_callback: Option<ExecutionStatsCallback>,
 
+    /// If set, the query is aborted once this token is cancelled or its deadline passes
+    cancellation_token: Option<CancellationToken>,
+
+    /// Identifies the query in its tracing span
+    query_id: Option<String>,
+
+    /// If set, this callback is called with the progress of the scan as batches are returned
+    progress_callback: Option<ScanProgressCallback>,
+
     /// Whether the result returned by the scanner must be of the size of the batch_size.
     /// By default, it is false.
     /// Mainly, if the result is returned strictly according to the batch_size,
@@ -368,6 +603,86 @@ fn escape_column_name(name: &str) -> String {
         .join(".")
 }
 
+// Expand a struct field into (output name, expression) pairs for each of its leaves
+fn flatten_struct_leaves(
+    field: &Field,
+    name: String,
+    expr: String,
+    columns: &mut Vec<(String, String)>,
+) {
+    if !matches!(field.data_type(), DataType::Struct(_)) {
+        columns.push((name, expr));
+        return;
+    }
+    for child in &field.children {
+        flatten_struct_leaves(
+            child,
+            format!("{}.{}", name, child.name),
+            format!("{}.`{}`", expr, child.name),
+            columns,
+        );
+    }
+}
+
+fn literal_value(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Literal(ScalarValue::UInt64(Some(value)), _) => Some(*value as i128),
+        Expr::Literal(ScalarValue::Int64(Some(value)), _) => Some(*value as i128),
+        Expr::Literal(ScalarValue::UInt32(Some(value)), _) => Some(*value as i128),
+        Expr::Literal(ScalarValue::Int32(Some(value)), _) => Some(*value as i128),
+        _ => None,
+    }
+}
+
+/// Returns false if `expr` can't match any row with an address in `addresses`
+///
+/// `columns` are the columns that hold row addresses.  This is conservative, any
+/// part of the expression that isn't a comparison of one of those columns with a
+/// literal is assumed to match.
+fn may_match_addresses(expr: &Expr, columns: &[&str], addresses: &Range<i128>) -> bool {
+    let is_address =
+        |expr: &Expr| matches!(expr, Expr::Column(column) if columns.contains(&column.name()));
+    match expr {
+        Expr::BinaryExpr(binary) => {
+            let (op, value) = match binary.op {
+                Operator::And => {
+                    return may_match_addresses(&binary.left, columns, addresses)
+                        && may_match_addresses(&binary.right, columns, addresses)
+                }
+                Operator::Or => {
+                    return may_match_addresses(&binary.left, columns, addresses)
+                        || may_match_addresses(&binary.right, columns, addresses)
+                }
+                op if is_address(&binary.left) => (Some(op), literal_value(&binary.right)),
+                op if is_address(&binary.right) => (op.swap(), literal_value(&binary.left)),
+                _ => return true,
+            };
+            match (op, value) {
+                (Some(Operator::Eq), Some(value)) => addresses.contains(&value),
+                (Some(Operator::Lt), Some(value)) => addresses.start < value,
+                (Some(Operator::LtEq), Some(value)) => addresses.start <= value,
+                (Some(Operator::Gt), Some(value)) => addresses.end - 1 > value,
+                (Some(Operator::GtEq), Some(value)) => addresses.end > value,
+                _ => true,
+            }
+        }
+        Expr::InList(in_list) if !in_list.negated && is_address(&in_list.expr) => {
+            in_list.list.iter().any(|value| {
+                literal_value(value)
+                    .map(|value| addresses.contains(&value))
+                    .unwrap_or(true)
+            })
+        }
+        Expr::Between(between) if !between.negated && is_address(&between.expr) => {
+            match (literal_value(&between.low), literal_value(&between.high)) {
+                (Some(low), Some(high)) => low < addresses.end && high >= addresses.start,
+                _ => true,
+            }
+        }
+        _ => true,
+    }
+}
+
 impl Scanner {
     pub fn new(dataset: Arc<Dataset>) -> Self {
         // By default, we only scan the local schema
@@ -379,26 +694,40 @@ impl Scanner {
             dataset,
             projection_plan,
             prefilter: false,
+            allowed_row_ids: None,
             materialization_style: MaterializationStyle::Heuristic,
             filter: None,
             full_text_query: None,
             batch_size: None,
+            batch_size_bytes: None,
             batch_readahead: get_num_compute_intensive_cpus(),
             fragment_readahead: None,
+            fragment_io_parallelism: None,
             io_buffer_size: None,
+            io_priority: IoPriorityClass::default(),
+            memory_limit: None,
+            prefetch_depth: None,
+            uncached_reads: false,
             limit: None,
             offset: None,
             ordering: None,
+            sorted_projection: None,
+            distinct: false,
+            count_by: None,
             nearest: None,
             use_stats: true,
             with_row_id: false,
             with_row_address: false,
             ordered: true,
+            deterministic_order: false,
             fragments: None,
             fast_search: false,
             use_scalar_index: true,
             include_deleted_rows: false,
             scan_stats_callback: None,
+            cancellation_token: None,
+            query_id: None,
+            progress_callback: None,
             strict_batch_size: false,
         }
     }
@@ -452,6 +781,10 @@ impl Scanner {
     /// Projection.
     ///
     /// Only select the specified columns. If not specified, all columns will be scanned.
+    ///
+    /// Nested fields are selected with dotted paths, e.g. `meta.author`.  A field inside the
+    /// items of a list, e.g. `docs.item.title`, selects a list of that field's values per row,
+    /// and only that field is read from storage.
     pub fn project<T: AsRef<str>>(&mut self, columns: &[T]) -> Result<&mut Self> {
         self.project_with_transform(
             &columns
@@ -468,9 +801,15 @@ impl Scanner {
         &mut self,
         columns: &[(impl AsRef<str>, impl AsRef<str>)],
     ) -> Result<&mut Self> {
-        let base_schema = self.scan_output_schema(self.dataset.schema(), true)?;
+        let mut base_schema = self.scan_output_schema(self.dataset.schema(), true)?;
+        // Row addresses can be projected even if they weren't requested
+        if base_schema.field(ROW_ADDR).is_none() {
+            base_schema =
+                Arc::new(base_schema.merge(&ArrowSchema::new(vec![ROW_ADDR_FIELD.clone()]))?);
+        }
         self.projection_plan =
-            ProjectionPlan::try_new(&base_schema, columns, /*load_blobs=*/ false)?;
+            ProjectionPlan::try_new(&base_schema, columns, /*load_blobs=*/ false)
+                .map_err(|err| explain_projection_error(err, &base_schema, columns))?;
         if self.projection_plan.sibling_schema.is_some() {
             return Err(Error::NotSupported {
                 source: "Scanning columns with non-default storage class is not yet supported"
@@ -481,6 +820,50 @@ impl Scanner {
         Ok(self)
     }
 
+    /// Projection with SQL expressions
+    ///
+    /// Each item is a column or a SQL expression over the columns of the dataset, with an
+    /// optional alias, e.g. `price * qty AS total` or `lower(name)`.  The expressions are
+    /// evaluated as part of the scan, after the columns they reference are read.  Items
+    /// without an alias are named after the column, or the text of the expression.
+    pub fn project_expressions<T: AsRef<str>>(&mut self, items: &[T]) -> Result<&mut Self> {
+        let columns = items
+            .iter()
+            .map(|item| parse_sql_projection(item.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+        self.project_with_transform(&columns)
+    }
+
+    /// Projection with SQL expressions, flattening struct columns into their leaves
+    ///
+    /// This is the same as [`Self::project_expressions`] except that any item that refers
+    /// to a struct by path, e.g. `doc` or `doc.meta AS m`, is replaced by one column per leaf
+    /// field.  The leaf columns are named by their path below the item's output name, e.g.
+    /// `doc.meta.lang` or `m.lang`.  Only the referenced leaves are read from storage.
+    pub fn project_flattened<T: AsRef<str>>(&mut self, items: &[T]) -> Result<&mut Self> {
+        let schema = self.dataset.schema();
+        let mut columns = Vec::with_capacity(items.len());
+        for item in items {
+            let (name, expr) = parse_sql_projection(item.as_ref())?;
+            let field = parse_sql_column_path(&expr)?.and_then(|path| {
+                let (first, rest) = path.split_first()?;
+                let rest = rest.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                schema
+                    .fields
+                    .iter()
+                    .find(|f| &f.name == first)?
+                    .sub_field(&rest)
+            });
+            match field {
+                Some(field) if matches!(field.data_type(), DataType::Struct(_)) => {
+                    flatten_struct_leaves(field, name, expr, &mut columns);
+                }
+                _ => columns.push((name, expr)),
+            }
+        }
+        self.project_with_transform(&columns)
+    }
+
     /// Should the filter run before the vector index is applied
     ///
     /// If true then the filter will be applied before the vector index.  This
@@ -494,12 +877,64 @@ impl Scanner {
         self
     }
 
+    /// Only consider these row ids as candidates for the vector search
+    ///
+    /// This is meant for allow lists computed outside of Lance (e.g. by an access control
+    /// system).  The row ids are applied as a prefilter bitmap during the index search,
+    /// which is much cheaper than an `_rowid IN (...)` filter over a large list.  The
+    /// allow list is always applied before the search, regardless of [`Self::prefilter`],
+    /// and it is combined with the filter, if any.
+    ///
+    /// This requires a vector search, see [`Self::nearest`].
+    pub fn allowed_row_ids(&mut self, row_ids: RowIdTreeMap) -> &mut Self {
+        self.allowed_row_ids = Some(Arc::new(row_ids));
+        self
+    }
+
+    /// Set the id of the query, to find it in traces
+    ///
+    /// With the `query-tracing` feature the query runs in a span (with target
+    /// [`lance_core::utils::tracing::TRACE_QUERY`]) that records this id, the dataset URI
+    /// and the version.  The fragment scans, index searches and reads of the query are
+    /// children of that span.  If no id is set a random one is used.
+    pub fn query_id(&mut self, query_id: impl Into<String>) -> &mut Self {
+        self.query_id = Some(query_id.into());
+        self
+    }
+
     /// Set the callback to be called after the scan with summary statistics
     pub fn scan_stats_callback(&mut self, callback: ExecutionStatsCallback) -> &mut Self {
         self.scan_stats_callback = Some(callback);
         self
     }
 
+    /// Set a token that can be used to abort the query
+    ///
+    /// The token is checked before each batch is read and before each vector index
+    /// partition is searched.  Once it is cancelled, or its deadline passes, the
+    /// stream returns [`Error::Cancelled`] or [`Error::Timeout`] and ends, even if
+    /// it is waiting on I/O at the time.  Services can use this to stop work for
+    /// abandoned requests and to enforce per-query time limits.
+    pub fn cancellation_token(&mut self, token: CancellationToken) -> &mut Self {
+        if let Some(q) = self.nearest.as_mut() {
+            q.cancellation_token = Some(token.clone());
+        }
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Set a callback to observe the progress of the scan
+    ///
+    /// The callback is called with the fragments completed, rows produced and
+    /// bytes read so far each time the stream returns a batch, and once more
+    /// when the stream ends.  This can be used to render progress for long
+    /// running exports, or to enforce a cost limit by cancelling the scan's
+    /// [`CancellationToken`] from the callback.
+    pub fn progress_callback(&mut self, callback: ScanProgressCallback) -> &mut Self {
+        self.progress_callback = Some(callback);
+        self
+    }
+
     /// Set the materialization style for the scan
     ///
     /// This controls when columns are fetched from storage.  The default should work
@@ -531,6 +966,12 @@ impl Scanner {
     ///
     /// Once the filter is applied, Lance will create an optimized I/O plan for filtering.
     ///
+    /// The filter may refer to the `_rowid` and `_rowaddr` system columns even if they
+    /// are not returned.  Fragments that can't match a predicate on the row address
+    /// (e.g. `_rowaddr > x` to resume a scan) are skipped entirely.  There is no
+    /// `_version` column, because rows don't record the version that wrote them, and
+    /// filters or projections that refer to it return [`Error::NotSupported`].
+    ///
     pub fn filter(&mut self, filter: &str) -> Result<&mut Self> {
         self.filter = Some(LanceFilter::Sql(filter.to_string()));
         Ok(self)
@@ -586,6 +1027,22 @@ impl Scanner {
         self
     }
 
+    /// Set the target size, in bytes, of the output batches.
+    ///
+    /// The number of rows that fits in a batch depends on the schema.  A fixed row count
+    /// can give tiny batches for narrow schemas and huge batches for wide ones (e.g. large
+    /// strings or blobs).  When this is set, batches that are too large are sliced and
+    /// batches that are too small are combined so each batch holds roughly `bytes` of
+    /// data.  The size of a row is estimated from the in-memory size of the batch it was
+    /// decoded in.  A batch always has at least one row.
+    ///
+    /// This replaces the row count given by [`Self::batch_size`] for the output and takes
+    /// precedence over [`Self::strict_batch_size`].
+    pub fn batch_size_bytes(&mut self, bytes: usize) -> &mut Self {
+        self.batch_size_bytes = Some(bytes);
+        self
+    }
+
     /// Include deleted rows
     ///
     /// These are rows that have been deleted from the dataset but are still present in the
@@ -624,6 +1081,60 @@ impl Scanner {
         self
     }
 
+    /// Set a memory budget, in bytes, for the scan.
+    ///
+    /// Without a budget, large queries can run out of memory: the I/O buffer defaults
+    /// to 2GiB and sorts hold their entire input in RAM.  When a budget is set, half
+    /// of it is used as the I/O buffer (unless [`Self::io_buffer_size`] is set
+    /// explicitly) and the scan waits for decoded data to be consumed before reading
+    /// more.  The other half is given to DataFusion's memory pool, which tracks the
+    /// state of sorts and top-k searches.  Sorts that exceed the pool spill to disk
+    /// instead of failing.
+    ///
+    /// DataFusion reserves some memory up front for merging spilled sorts (10MiB by
+    /// default) so budgets of a few tens of MiB are the practical minimum for scans
+    /// with an ordering.  As with [`Self::io_buffer_size`], batches are not tracked
+    /// after they are returned to the caller.
+    pub fn memory_limit(&mut self, bytes: u64) -> &mut Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Set the priority class of the scan's I/O.
+    ///
+    /// Scans for maintenance jobs such as compaction should use
+    /// [`IoPriorityClass::Background`] so that they do not starve interactive
+    /// queries of IOPS.  The default is [`IoPriorityClass::Interactive`].
+    pub fn io_priority(&mut self, priority: IoPriorityClass) -> &mut Self {
+        self.io_priority = priority;
+        self
+    }
+
+    /// Set how many fragments past the ones currently being read should have
+    /// their file metadata fetched ahead of time.
+    ///
+    /// On remote storage, opening a fragment costs a round trip before any data
+    /// can be read.  Prefetching hides that latency so the I/O queue does not
+    /// go idle between fragments.  The default is 0 (no prefetching), which can
+    /// be changed with the `LANCE_DEFAULT_PREFETCH_DEPTH` environment variable.
+    ///
+    /// This only applies to scans of datasets with v2 files.
+    pub fn prefetch_depth(&mut self, depth: usize) -> &mut Self {
+        self.prefetch_depth = Some(depth);
+        self
+    }
+
+    /// Set whether data read from local files should be kept out of the OS
+    /// page cache.
+    ///
+    /// Large one-off scans, like the ones done by compaction, would otherwise
+    /// evict the pages that queries keep coming back to.  This has no effect on
+    /// remote object stores or on datasets with legacy (v1) files.
+    pub fn uncached_reads(&mut self, uncached_reads: bool) -> &mut Self {
+        self.uncached_reads = uncached_reads;
+        self
+    }
+
     /// Set the prefetch size.
     pub fn batch_readahead(&mut self, nbatches: usize) -> &mut Self {
         self.batch_readahead = nbatches;
@@ -632,12 +1143,25 @@ impl Scanner {
 
     /// Set the fragment readahead.
     ///
-    /// This is only used if ``scan_in_order`` is set to false.
+    /// This is the number of fragments that are read concurrently.  On object stores
+    /// (e.g. S3) each request has a high latency and reading many fragments at once,
+    /// combined with [`Self::fragment_io_parallelism`], keeps more requests in flight.
+    /// On local NVMe disks a few fragments are usually enough to saturate the disk.
     pub fn fragment_readahead(&mut self, nfragments: usize) -> &mut Self {
         self.fragment_readahead = Some(nfragments);
         self
     }
 
+    /// Set the number of IOPS each data file of a fragment may have in flight
+    ///
+    /// By default a single file may use all of the I/O capacity of the scan.  Capping
+    /// this spreads the requests across the fragments being read, see
+    /// [`Self::fragment_readahead`].  Only applies to v2 files.
+    pub fn fragment_io_parallelism(&mut self, niops: usize) -> &mut Self {
+        self.fragment_io_parallelism = Some(niops);
+        self
+    }
+
     /// Set whether to read data in order (default: true)
     ///
     /// A scan will always read from the disk concurrently.  If this property
@@ -656,6 +1180,22 @@ impl Scanner {
         self
     }
 
+    /// Guarantee a stable row order across runs (default: false)
+    ///
+    /// When set, rows are returned in fragment order and, within a fragment, in row
+    /// offset order, even though fragments are still read in parallel.  If an ordering
+    /// is defined (using [Self::order_by]) then rows that tie are returned in row address
+    /// order.  This makes dataset iteration reproducible (e.g. for ML training) and lets
+    /// exports from different runs be diffed.
+    ///
+    /// Scalar indices are not used to answer the filter in this mode since their
+    /// results don't follow the fragment order.  Vector searches, full text searches,
+    /// [Self::distinct_on] and [Self::count_rows_by] can't be combined with it.
+    pub fn deterministic_order(&mut self, deterministic: bool) -> &mut Self {
+        self.deterministic_order = deterministic;
+        self
+    }
+
     /// Set whether to use scalar index.
     ///
     /// By default, scalar indices will be used to optimize a query if available.
@@ -683,6 +1223,12 @@ impl Scanner {
     /// only the provided number of rows will be returned. These can be set
     /// independently. For example, setting offset to 10 and limit to None will
     /// skip the first 10 rows and return the rest of the rows in the dataset.
+    ///
+    /// Skipped rows are not read when there is no filter or sort (the scan seeks using
+    /// the row counts of the fragments and pages), when the filter is answered
+    /// exactly by scalar indices (the offset is applied to the matching row ids) or
+    /// when an ordered scan is read from a sorted projection, see
+    /// [`Self::use_sorted_projection`].
     pub fn limit(&mut self, limit: Option<i64>, offset: Option<i64>) -> Result<&mut Self> {
         if limit.unwrap_or_default() < 0 {
             return Err(Error::invalid_input(
@@ -706,6 +1252,9 @@ impl Scanner {
     /// Find k-nearest neighbor within the vector column.
     /// the query can be a Float16Array, Float32Array, Float64Array, UInt8Array,
     /// or a ListArray/FixedSizeListArray of the above types.
+    ///
+    /// The query must not have null elements.  Rows whose vector is null, or has null
+    /// elements, are never returned.
     pub fn nearest(&mut self, column: &str, q: &dyn Array, k: usize) -> Result<&mut Self> {
         if !self.prefilter {
             // We can allow fragment scan if the input to nearest is a prefilter.
@@ -788,13 +1337,25 @@ impl Scanner {
                 q.slice(0, q.len())
             }
         };
+        if q.null_count() > 0 {
+            return Err(Error::invalid_input(
+                format!(
+                    "Query vector must not contain nulls, found {} null elements",
+                    q.null_count()
+                ),
+                location!(),
+            ));
+        }
 
         let key = match element_type {
             dt if dt == *q.data_type() => q,
-            dt if dt.is_floating() => coerce_float_vector(
-                q.as_any().downcast_ref::<Float32Array>().unwrap(),
-                FloatType::try_from(&dt)?,
-            )?,
+            dt if dt.is_floating() && q.data_type().is_floating() => {
+                match q.as_any().downcast_ref::<Float32Array>() {
+                    Some(q) => coerce_float_vector(q, FloatType::try_from(&dt)?)?,
+                    // e.g. a Float64 query on a Float32 column, or the other way around
+                    None => arrow::compute::cast(&q, &dt)?,
+                }
+            }
             _ => {
                 return Err(Error::invalid_input(
                     format!(
@@ -820,6 +1381,7 @@ impl Scanner {
             refine_factor: None,
             metric_type: MetricType::L2,
             use_index: true,
+            cancellation_token: self.cancellation_token.clone(),
         });
         Ok(self)
     }
@@ -955,6 +1517,50 @@ impl Scanner {
         Ok(self)
     }
 
+    /// Read ordered scans from the sorted projection called `name`
+    ///
+    /// See [`Dataset::create_sorted_projection`].  The projection is used when the scan is
+    /// ordered by the projection's ordering (or its exact reverse), has no filter and only
+    /// needs columns stored in the projection.  The limit and offset are then applied by
+    /// reading just those rows of the projection, so paginated queries don't sort the
+    /// dataset on every request.  If the projection can't be used, e.g. because the dataset
+    /// changed since it was created, the scan falls back to a sort.
+    pub fn use_sorted_projection(&mut self, name: &str) -> &mut Self {
+        self.sorted_projection = Some(name.to_string());
+        self
+    }
+
+    /// Only return the distinct combinations of values of the given columns
+    ///
+    /// This replaces the projection with `columns` and removes duplicate rows with a hash
+    /// aggregation as part of the scan, so only one row per distinct key is kept in memory.
+    /// The aggregation spills to disk if a [`Self::memory_limit`] is set and exceeded.  Any
+    /// limit / offset is applied to the distinct rows.
+    ///
+    /// This can't be combined with row ids, an ordering, or a vector or full text search.
+    pub fn distinct_on<T: AsRef<str>>(&mut self, columns: &[T]) -> Result<&mut Self> {
+        self.project(columns)?;
+        self.distinct = true;
+        Ok(self)
+    }
+
+    /// Count the rows for each distinct value of `column`
+    ///
+    /// This replaces the projection with `column` and adds a `count_rows` column with the
+    /// number of rows that have each value.  If there is no filter and the column has a
+    /// bitmap or btree index covering every fragment then the counts are computed from the
+    /// index and the deletion files without reading any data pages.  Otherwise the column
+    /// is scanned and grouped with a hash aggregation.  Any limit / offset is applied to
+    /// the groups.
+    ///
+    /// This can't be combined with [`Self::distinct_on`], row ids, an ordering, or a vector
+    /// or full text search.
+    pub fn count_rows_by(&mut self, column: &str) -> Result<&mut Self> {
+        self.project(&[column])?;
+        self.count_by = Some(column.to_string());
+        Ok(self)
+    }
+
     /// Set whether to use the index if available
     pub fn use_index(&mut self, use_index: bool) -> &mut Self {
         if let Some(q) = self.nearest.as_mut() {
@@ -992,7 +1598,7 @@ impl Scanner {
     /// Fetches the currently set filter
     ///
     /// Note that this forces the filter to be evaluated and the result will depend on
-    /// the current state of the scanner (e.g. if with_row_id has been called then _rowid
+    /// the current state of the scanner (e.g. if a vector search has been set then _distance
     /// will be available for filtering but not otherwise) and so you may want to call this
     /// after setting all other options.
     pub fn get_filter(&self) -> Result<Option<Expr>> {
@@ -1030,17 +1636,67 @@ impl Scanner {
     }
 
     pub(crate) fn scan_input_schema(&self) -> Result<Arc<Schema>> {
-        let extra_columns = self.get_extra_columns(false);
+        let mut extra_columns = self.get_extra_columns(false);
+        // The system columns can be filtered on even if they aren't returned
+        for system_field in [&*ROW_ID_FIELD, &*ROW_ADDR_FIELD] {
+            if extra_columns
+                .iter()
+                .all(|f| f.name() != system_field.name())
+            {
+                extra_columns.push(system_field.clone());
+            }
+        }
 
-        if !extra_columns.is_empty() {
-            let physical_schema = self
-                .dataset
-                .schema()
-                .merge(&ArrowSchema::new(extra_columns))?;
-            Ok(Arc::new(physical_schema))
-        } else {
-            Ok(Arc::new(self.dataset.schema().clone()))
+        let physical_schema = self
+            .dataset
+            .schema()
+            .merge(&ArrowSchema::new(extra_columns))?;
+        Ok(Arc::new(physical_schema))
+    }
+
+    /// True if the projection or the filter refer to the system column `name`
+    fn references_system_column(&self, filter_plan: &FilterPlan, name: &str) -> bool {
+        self.projection_plan.physical_schema.field(name).is_some()
+            || filter_plan
+                .refine_columns()
+                .iter()
+                .any(|column| column == name)
+    }
+
+    /// True if the plan needs row addresses, whether or not they are returned
+    fn needs_row_address(&self, filter_plan: &FilterPlan) -> bool {
+        self.references_system_column(filter_plan, ROW_ADDR)
+            // Ties in a deterministic ordering are broken by row address
+            || (self.deterministic_order && self.ordering.is_some())
+    }
+
+    /// The fragments that may contain rows matching the filter's predicates on row
+    /// addresses
+    ///
+    /// Returns None if no fragment can be ruled out.  Row ids are row addresses
+    /// unless the dataset uses stable row ids, in which case only `_rowaddr` is used.
+    fn fragments_matching_addresses(&self, filter_plan: &FilterPlan) -> Option<Vec<Fragment>> {
+        let refine_expr = filter_plan.refine_expr.as_ref()?;
+        let mut columns = vec![ROW_ADDR];
+        if !self.dataset.manifest.uses_move_stable_row_ids() {
+            columns.push(ROW_ID);
         }
+        let fragments = self
+            .fragments
+            .as_ref()
+            .unwrap_or_else(|| self.dataset.fragments().as_ref());
+        let matching = fragments
+            .iter()
+            .filter(|fragment| {
+                let Some(num_rows) = fragment.physical_rows else {
+                    return true;
+                };
+                let start = (fragment.id as i128) << 32;
+                may_match_addresses(refine_expr, &columns, &(start..start + num_rows as i128))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        (matching.len() < fragments.len()).then_some(matching)
     }
 
     /// The output schema from the initial scan stage of a plan
@@ -1112,17 +1768,40 @@ impl Scanner {
     pub fn try_into_stream(&self) -> BoxFuture<Result<DatasetRecordBatchStream>> {
         // Future intentionally boxed here to avoid large futures on the stack
         async move {
+            let start = std::time::Instant::now();
             let plan = self.create_plan().await?;
 
-            Ok(DatasetRecordBatchStream::new(execute_plan(
-                plan,
-                LanceExecutionOptions {
+            let mut stream = execute_plan(
+                plan.clone(),
+                self.execution_options(LanceExecutionOptions {
                     batch_size: self.batch_size,
                     execution_stats_callback: self.scan_stats_callback.clone(),
                     ..Default::default()
-                },
-            )?))
+                }),
+            )?;
+            if let Some(bytes) = self.batch_size_bytes {
+                stream = byte_sized_stream(stream, bytes);
+            }
+            if let Some(callback) = &self.progress_callback {
+                stream = progress_stream(stream, plan.clone(), callback.clone());
+            }
+            if let Some(slow_query_log) = self.dataset.session.slow_query_log() {
+                let description = QueryDescription {
+                    dataset_uri: self.dataset.uri().to_string(),
+                    version: self.dataset.manifest.version,
+                    query_id: self.query_id.clone(),
+                    filter: self.filter_description(),
+                };
+                stream = slow_query_log.watch(stream, plan, start, description);
+            }
+            if let Some(token) = &self.cancellation_token {
+                stream = cancellable_stream(stream, token.clone());
+            }
+            metrics::increment_counter(metrics::SCANS, &[], 1);
+            stream = metrics_stream(stream);
+            Ok(DatasetRecordBatchStream::new(stream))
         }
+        .instrument(query_span(&self.dataset, self.query_id.as_deref()))
         .boxed()
     }
 
@@ -1137,7 +1816,27 @@ impl Scanner {
             options.execution_stats_callback = self.scan_stats_callback.clone();
         }
 
-        execute_plan(plan, options)
+        let mut stream = execute_plan(plan.clone(), self.execution_options(options))?;
+        if let Some(callback) = &self.progress_callback {
+            stream = progress_stream(stream, plan, callback.clone());
+        }
+        Ok(match &self.cancellation_token {
+            Some(token) => cancellable_stream(stream, token.clone()),
+            None => stream,
+        })
+    }
+
+    /// Apply the scan's memory budget, if any, to the execution options
+    ///
+    /// Options that were already set by the caller are left alone.
+    fn execution_options(&self, mut options: LanceExecutionOptions) -> LanceExecutionOptions {
+        if let Some(memory_limit) = self.memory_limit {
+            options.use_spilling = true;
+            if options.mem_pool_size.is_none() {
+                options.mem_pool_size = Some(memory_limit - memory_limit / 2);
+            }
+        }
+        options
     }
 
     pub async fn try_into_batch(&self) -> Result<RecordBatch> {
@@ -1170,6 +1869,10 @@ impl Scanner {
                 );
             }
 
+            if let Some(plan) = self.create_index_count_plan().await? {
+                return Ok(plan);
+            }
+
             let plan = self.create_plan().await?;
             // Datafusion interprets COUNT(*) as COUNT(1)
             let one = Arc::new(Literal::new(ScalarValue::UInt8(Some(1))));
@@ -1196,6 +1899,75 @@ impl Scanner {
         .boxed()
     }
 
+    /// Create a count plan that is answered by scalar indices, if possible
+    ///
+    /// This applies when the entire filter can be answered exactly by scalar indices.  The
+    /// count then comes from the index results and the deletion files without reading any
+    /// data.  Fragments that the indices don't cover yet are scanned and filtered.
+    async fn create_index_count_plan(&self) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        if self.filter.is_none()
+            || !self.use_scalar_index
+            || self.nearest.is_some()
+            || self.full_text_query.is_some()
+            || self.limit.is_some()
+            || self.offset.is_some()
+            || self.include_deleted_rows
+        {
+            return Ok(None);
+        }
+
+        let filter_plan = self.create_filter_plan(true).await?;
+        let Some(index_expr) = filter_plan.index_query.as_ref() else {
+            return Ok(None);
+        };
+        if filter_plan.has_refine() || index_expr.needs_recheck() {
+            return Ok(None);
+        }
+
+        let (relevant_frags, missing_frags) = self.partition_frags_by_coverage(index_expr).await?;
+        let residual = if missing_frags.is_empty() {
+            None
+        } else {
+            let filter = filter_plan.full_expr.as_ref().unwrap();
+            let filter_cols = Planner::column_names_in_expr(filter);
+            let scan_schema = self
+                .dataset
+                .empty_projection()
+                .union_columns(filter_cols, OnMissing::Error)?
+                .into_schema_ref();
+            let planner = Planner::new(Arc::new(scan_schema.as_ref().into()));
+            let optimized_filter = planner.optimize_expr(filter.clone())?;
+            let scan = self.scan_fragments(
+                false,
+                false,
+                false,
+                scan_schema,
+                missing_frags.into(),
+                None,
+                false,
+            );
+            Some(Arc::new(LanceFilterExec::try_new(optimized_filter, scan)?)
+                as Arc<dyn ExecutionPlan>)
+        };
+
+        Ok(Some(Arc::new(ScalarIndexCountExec::new(
+            self.dataset.clone(),
+            index_expr.clone(),
+            Arc::new(relevant_frags),
+            residual,
+        ))))
+    }
+
+    fn filter_description(&self) -> Option<String> {
+        self.filter.as_ref().map(|filter| match filter {
+            LanceFilter::Sql(sql) => sql.clone(),
+            LanceFilter::Datafusion(expr) => expr.to_string(),
+            LanceFilter::Substrait(bytes) => {
+                format!("<substrait expression of {} bytes>", bytes.len())
+            }
+        })
+    }
+
     /// Scan and return the number of matching rows
     ///
     /// Note: calling [`Dataset::count_rows`] can be more efficient than calling this method
@@ -1223,6 +1995,7 @@ impl Scanner {
                 Ok(0)
             }
         }
+        .instrument(query_span(&self.dataset, self.query_id.as_deref()))
         .boxed()
     }
 
@@ -1246,20 +2019,35 @@ impl Scanner {
     //
     // This means, for cloud storage, a field is "narrow" if there are 1KB of data per row and
     // for local disk a field is "narrow" if there are 10 bytes of data per row.
+    //
+    // If the user tells us the expected selectivity then we use that instead and, since the
+    // cutoff can then be much larger, we also estimate the width of variable-width fields.
     fn is_early_field(&self, field: &Field) -> bool {
         match self.materialization_style {
             MaterializationStyle::AllEarly => true,
             MaterializationStyle::AllLate => false,
             MaterializationStyle::AllEarlyExcept(ref cols) => !cols.contains(&(field.id as u32)),
+            MaterializationStyle::AllLateExcept(ref cols) => cols.contains(&(field.id as u32)),
             MaterializationStyle::Heuristic => {
                 let byte_width = field.data_type().byte_width_opt();
-                let is_cloud = self.dataset.object_store().is_cloud();
-                if is_cloud {
-                    byte_width.is_some_and(|bw| bw < 1000)
-                } else {
-                    byte_width.is_some_and(|bw| bw < 10)
-                }
+                byte_width.is_some_and(|bw| bw < self.bytes_per_iop() / 1000)
             }
+            MaterializationStyle::ExpectedSelectivity(selectivity) => {
+                let byte_width = field
+                    .data_type()
+                    .byte_width_opt()
+                    .unwrap_or(VARIABLE_WIDTH_ESTIMATE);
+                (byte_width as f64) < selectivity * self.bytes_per_iop() as f64
+            }
+        }
+    }
+
+    // The number of bytes we could read in the time it takes to make one more IOP
+    fn bytes_per_iop(&self) -> usize {
+        if self.dataset.object_store().is_cloud() {
+            1_000_000
+        } else {
+            10_000
         }
     }
 
@@ -1297,9 +2085,48 @@ impl Scanner {
             .union_schema(&filter_schema))
     }
 
-    /// Create [`ExecutionPlan`] for Scan.
-    ///
-    /// An ExecutionPlan is a graph of operators that can be executed.
+    /// Split the filter, if any, into the part that can be answered by scalar indices
+    /// and the part that must be evaluated against the data
+    async fn create_filter_plan(&self, use_scalar_index: bool) -> Result<FilterPlan> {
+        let Some(filter) = self.filter.as_ref() else {
+            return Ok(FilterPlan::default());
+        };
+
+        let filter_schema = self.scan_input_schema()?;
+        let planner = Planner::new(Arc::new(filter_schema.as_ref().into()));
+
+        let filter = filter.to_datafusion(self.dataset.schema(), filter_schema.as_ref())?;
+        let index_info = self.dataset.scalar_index_info().await?;
+        let filter_plan =
+            planner.create_filter_plan(filter.clone(), &index_info, use_scalar_index)?;
+
+        // This tests if any of the fragments are missing the physical_rows property (old style)
+        // If they are then we cannot use scalar indices
+        if filter_plan.index_query.is_some() {
+            let fragments = if let Some(fragments) = self.fragments.as_ref() {
+                fragments
+            } else {
+                self.dataset.fragments()
+            };
+            let mut has_missing_row_count = false;
+            for frag in fragments {
+                if frag.physical_rows.is_none() {
+                    has_missing_row_count = true;
+                    break;
+                }
+            }
+            if has_missing_row_count {
+                // We need row counts to use scalar indices.  If we don't have them then
+                // fallback to a non-indexed filter
+                return planner.create_filter_plan(filter.clone(), &index_info, false);
+            }
+        }
+        Ok(filter_plan)
+    }
+
+    /// Create [`ExecutionPlan`] for Scan.
+    ///
+    /// An ExecutionPlan is a graph of operators that can be executed.
     ///
     /// The following plans are supported:
     ///
@@ -1380,68 +2207,65 @@ impl Scanner {
             });
         }
 
-        // Scalar indices are only used when prefiltering
-        let use_scalar_index = self.use_scalar_index && (self.prefilter || self.nearest.is_none());
+        if self.deterministic_order
+            && (self.nearest.is_some() || self.full_text_query.is_some() || self.is_aggregate())
+        {
+            return Err(Error::invalid_input(
+                "deterministic_order can't be combined with a vector or full text search, distinct_on or count_rows_by",
+                location!(),
+            ));
+        }
 
-        let filter_schema = self.scan_input_schema()?;
-        let planner = Planner::new(Arc::new(filter_schema.as_ref().into()));
+        if self.allowed_row_ids.is_some() && self.nearest.is_none() {
+            return Err(Error::invalid_input(
+                "allowed_row_ids can only be used with a vector search",
+                location!(),
+            ));
+        }
 
-        let mut filter_plan = if let Some(filter) = self.filter.as_ref() {
-            let filter = filter.to_datafusion(self.dataset.schema(), filter_schema.as_ref())?;
-            let index_info = self.dataset.scalar_index_info().await?;
-            let filter_plan =
-                planner.create_filter_plan(filter.clone(), &index_info, use_scalar_index)?;
+        if let Some(plan) = self.create_index_value_count_plan().await? {
+            return Ok(plan);
+        }
 
-            // This tests if any of the fragments are missing the physical_rows property (old style)
-            // If they are then we cannot use scalar indices
-            if filter_plan.index_query.is_some() {
-                let fragments = if let Some(fragments) = self.fragments.as_ref() {
-                    fragments
-                } else {
-                    self.dataset.fragments()
-                };
-                let mut has_missing_row_count = false;
-                for frag in fragments {
-                    if frag.physical_rows.is_none() {
-                        has_missing_row_count = true;
-                        break;
+        // Scalar indices are only used when prefiltering, and their results are not in
+        // fragment order
+        let use_scalar_index = self.use_scalar_index
+            && !self.deterministic_order
+            && (self.prefilter || self.nearest.is_none());
+
+        let mut filter_plan = self.create_filter_plan(use_scalar_index).await?;
+
+        let scan_range =
+            if filter_plan.has_any_filter() || self.is_aggregate() || self.ordering.is_some() {
+                // If there is a filter, an aggregation or a sort we can't pushdown limit / offset
+                // because the rows to skip aren't the first rows of the scan
+                None
+            } else {
+                match (self.limit, self.offset) {
+                    (None, None) => None,
+                    (Some(limit), None) => {
+                        let num_rows = self.dataset.count_all_rows().await? as i64;
+                        Some(0..limit.min(num_rows) as u64)
+                    }
+                    (None, Some(offset)) => {
+                        let num_rows = self.dataset.count_all_rows().await? as i64;
+                        Some(offset.min(num_rows) as u64..num_rows as u64)
+                    }
+                    (Some(limit), Some(offset)) => {
+                        let num_rows = self.dataset.count_all_rows().await? as i64;
+                        Some(offset.min(num_rows) as u64..(offset + limit).min(num_rows) as u64)
                     }
                 }
-                if has_missing_row_count {
-                    // We need row counts to use scalar indices.  If we don't have them then
-                    // fallback to a non-indexed filter
-                    planner.create_filter_plan(filter.clone(), &index_info, false)?
-                } else {
-                    filter_plan
-                }
-            } else {
-                filter_plan
-            }
-        } else {
-            FilterPlan::default()
-        };
-
-        let scan_range = if filter_plan.has_any_filter() {
-            // If there is a filter we can't pushdown limit / offset
+            };
+        let mut use_limit_node = true;
+        let sorted_projection = self.sorted_projection_source(&filter_plan).await?;
+        let ordered_index = if sorted_projection.is_some() {
             None
         } else {
-            match (self.limit, self.offset) {
-                (None, None) => None,
-                (Some(limit), None) => {
-                    let num_rows = self.dataset.count_all_rows().await? as i64;
-                    Some(0..limit.min(num_rows) as u64)
-                }
-                (None, Some(offset)) => {
-                    let num_rows = self.dataset.count_all_rows().await? as i64;
-                    Some(offset.min(num_rows) as u64..num_rows as u64)
-                }
-                (Some(limit), Some(offset)) => {
-                    let num_rows = self.dataset.count_all_rows().await? as i64;
-                    Some(offset.min(num_rows) as u64..(offset + limit).min(num_rows) as u64)
-                }
-            }
+            self.ordered_index(&filter_plan).await?
         };
-        let mut use_limit_node = true;
+        // The source is already in the requested order, no need to sort
+        let ordered_by_index = ordered_index.is_some() || sorted_projection.is_some();
 
         // Stage 1: source (either an (K|A)NN search, full text search or or a (full|indexed) scan)
         let mut plan: Arc<dyn ExecutionPlan> = match (&self.nearest, &self.full_text_query) {
@@ -1511,12 +2335,37 @@ impl Scanner {
                     filter_plan.index_query.is_some(),
                     filter_plan.refine_expr.is_some(),
                 ) {
+                    _ if sorted_projection.is_some() => {
+                        // The source reads just the rows of the page, in order
+                        let (projection, reverse) = sorted_projection.unwrap();
+                        use_limit_node = false;
+                        self.sorted_projection_scan(projection, reverse)?
+                    }
+                    _ if ordered_index.is_some() => {
+                        // The source is the row ids in index order, the filter and ordering
+                        // columns are taken below
+                        let (index, descending) = ordered_index.unwrap();
+                        self.ordered_index_scan(index, descending)?
+                    }
                     (true, false) => {
                         let projection = self
                             .dataset
                             .empty_projection()
                             .union_schema(&self.projection_plan.physical_schema);
-                        self.scalar_indexed_scan(projection, &filter_plan).await?
+                        let push_down_limit = self.ordering.is_none()
+                            && !self.is_aggregate()
+                            && (self.limit.unwrap_or(0) > 0 || self.offset.is_some());
+                        let (plan, limit_applied) = self
+                            .scalar_indexed_scan_with_limit(
+                                projection,
+                                &filter_plan,
+                                push_down_limit,
+                            )
+                            .await?;
+                        if limit_applied {
+                            use_limit_node = false;
+                        }
+                        plan
                     }
                     // TODO: support combined pushdown and scalar index scan
                     (true, true) => {
@@ -1529,12 +2378,21 @@ impl Scanner {
                         self.scalar_indexed_scan(eager_projection, &filter_plan)
                             .await?
                     }
-                    (false, true) if use_stats && self.batch_size.is_none() => {
+                    (false, true)
+                        if use_stats
+                            && self.batch_size.is_none()
+                            && !self.references_system_column(&filter_plan, ROW_ID)
+                            && !self.needs_row_address(&filter_plan) =>
+                    {
                         self.pushdown_scan(false, filter_plan.refine_expr.take().unwrap())?
                     }
                     (false, _) => {
                         // The source is a full scan of the table
-                        let with_row_id = filter_plan.has_refine() || self.with_row_id;
+                        let with_row_id = filter_plan.has_refine()
+                            || self.with_row_id
+                            || self.references_system_column(&filter_plan, ROW_ID);
+                        let with_row_address =
+                            self.with_row_address || self.needs_row_address(&filter_plan);
                         let eager_schema = if filter_plan.has_refine() {
                             // If there is a filter then only load the filter columns in the
                             // initial scan.  We will `take` the remaining columns later
@@ -1544,8 +2402,13 @@ impl Scanner {
                             )?
                             .into_schema_ref()
                         } else {
-                            // If there is no filter we eagerly load everything
-                            self.projection_plan.physical_schema.clone()
+                            // If there is no filter we eagerly load everything, the system
+                            // columns are added by the scan itself
+                            let mut schema = self.projection_plan.physical_schema.as_ref().clone();
+                            schema
+                                .fields
+                                .retain(|field| field.name != ROW_ID && field.name != ROW_ADDR);
+                            Arc::new(schema)
                         };
                         if scan_range.is_some() && !self.dataset.is_legacy_storage() {
                             // If this is a v2 dataset with no filter then we can pushdown
@@ -1553,13 +2416,26 @@ impl Scanner {
                             // so we don't apply it twice)
                             use_limit_node = false;
                         }
-                        self.scan(
-                            with_row_id,
-                            self.with_row_address,
-                            self.include_deleted_rows,
-                            scan_range,
-                            eager_schema,
-                        )
+                        if let Some(fragments) = self.fragments_matching_addresses(&filter_plan) {
+                            // The filter rules out some fragments by row address so skip them
+                            self.scan_fragments(
+                                with_row_id,
+                                with_row_address,
+                                self.include_deleted_rows,
+                                eager_schema,
+                                Arc::new(fragments),
+                                scan_range,
+                                self.scan_fragments_in_order(),
+                            )
+                        } else {
+                            self.scan(
+                                with_row_id,
+                                with_row_address,
+                                self.include_deleted_rows,
+                                scan_range,
+                                eager_schema,
+                            )
+                        }
                     }
                 }
             }
@@ -1571,6 +2447,13 @@ impl Scanner {
             }
         };
 
+        // The filter or projection may refer to row addresses even if they weren't requested
+        if self.needs_row_address(&filter_plan)
+            && plan.schema().column_with_name(ROW_ADDR).is_none()
+        {
+            plan = Arc::new(AddRowAddrExec::try_new(plan, self.dataset.clone(), 0)?);
+        }
+
         // Stage 1.5 load columns needed for stages 2 & 3
         // Calculate the schema needed for the filter and ordering.
         let mut pre_filter_projection = self.dataset.empty_projection();
@@ -1587,7 +2470,7 @@ impl Scanner {
         // maybe we wait until after the filter to take the ordering columns?  Maybe it would be better to
         // grab the ordering column in the initial scan (if it is eager) and if it isn't then we should
         // take it after the filtering phase, if any (we already have a take there).
-        if let Some(ordering) = &self.ordering {
+        if let Some(ordering) = self.ordering.as_ref().filter(|_| !ordered_by_index) {
             pre_filter_projection = pre_filter_projection.union_columns(
                 ordering.iter().map(|col| &col.column_name),
                 OnMissing::Error,
@@ -1603,8 +2486,8 @@ impl Scanner {
             plan = Arc::new(LanceFilterExec::try_new(refine_expr, plan)?);
         }
 
-        // Stage 3: sort
-        if let Some(ordering) = &self.ordering {
+        // Stage 3: sort (not needed if the source is already in order)
+        if let Some(ordering) = self.ordering.as_ref().filter(|_| !ordered_by_index) {
             let ordering_columns = ordering.iter().map(|col| &col.column_name);
             let projection_with_ordering = self
                 .dataset
@@ -1612,7 +2495,7 @@ impl Scanner {
                 .union_columns(ordering_columns, OnMissing::Error)?;
             // We haven't loaded the sort column yet so take it now
             plan = self.take(plan, projection_with_ordering)?;
-            let col_exprs = ordering
+            let mut col_exprs = ordering
                 .iter()
                 .map(|col| {
                     Ok(PhysicalSortExpr {
@@ -1624,11 +2507,21 @@ impl Scanner {
                     })
                 })
                 .collect::<Result<Vec<_>>>()?;
+            if self.deterministic_order {
+                // Break ties by row address so they come back in fragment order
+                col_exprs.push(PhysicalSortExpr {
+                    expr: expressions::col(ROW_ADDR, plan.schema().as_ref())?,
+                    options: SortOptions::default(),
+                });
+            }
             plan = Arc::new(SortExec::new(LexOrdering::new(col_exprs), plan));
         }
 
-        // Stage 4: limit / offset
-        if use_limit_node && (self.limit.unwrap_or(0) > 0 || self.offset.is_some()) {
+        // Stage 4: limit / offset (applied after the aggregation, if any)
+        if use_limit_node
+            && !self.is_aggregate()
+            && (self.limit.unwrap_or(0) > 0 || self.offset.is_some())
+        {
             plan = self.limit_node(plan);
         }
 
@@ -1650,6 +2543,14 @@ impl Scanner {
         // Stage 7: final projection
         plan = Arc::new(DFProjectionExec::try_new(self.output_expr()?, plan)?);
 
+        // Stage 8: deduplicate / aggregate
+        if self.is_aggregate() {
+            plan = self.aggregate_node(plan)?;
+            if self.limit.unwrap_or(0) > 0 || self.offset.is_some() {
+                plan = self.limit_node(plan);
+            }
+        }
+
         let optimizer = get_physical_optimizer();
         let options = Default::default();
         for rule in optimizer.rules {
@@ -2222,6 +3123,7 @@ impl Scanner {
                     vector_scan_projection.into_schema_ref(),
                 )
             };
+            plan = self.allow_list_filter(plan)?;
             if let Some(refine_expr) = &filter_plan.refine_expr {
                 plan = Arc::new(LanceFilterExec::try_new(refine_expr.clone(), plan)?);
             }
@@ -2285,6 +3187,7 @@ impl Scanner {
                 // in a deterministic order.
                 false,
             );
+            scan_node = self.allow_list_filter(scan_node)?;
 
             if let Some(expr) = filter_plan.full_expr.as_ref() {
                 // If there is a prefilter we need to manually apply it to the new data
@@ -2401,6 +3304,172 @@ impl Scanner {
         ))
     }
 
+    /// Find a btree index that can drive the scan in the requested order
+    ///
+    /// Returns the index and whether it should be read in descending order.  This is only
+    /// possible when ordering by a single column that has a btree index covering every
+    /// fragment.  The index is sorted with nulls first, so the requested null placement must
+    /// match the direction (nulls first ascending, nulls last descending) unless the column
+    /// has no nulls.
+    async fn ordered_index(&self, filter_plan: &FilterPlan) -> Result<Option<(Index, bool)>> {
+        let Some([ordering]) = self.ordering.as_deref() else {
+            return Ok(None);
+        };
+        if !self.use_scalar_index
+            || self.deterministic_order
+            || self.include_deleted_rows
+            || self.fragments.is_some()
+            || self.nearest.is_some()
+            || self.full_text_query.is_some()
+            || filter_plan.index_query.is_some()
+        {
+            return Ok(None);
+        }
+        let Some(index) = self
+            .dataset
+            .load_scalar_index(
+                ScalarIndexCriteria::default()
+                    .for_column(&ordering.column_name)
+                    .with_type(ScalarIndexType::BTree),
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let Some(fragment_bitmap) = index.fragment_bitmap.as_ref() else {
+            return Ok(None);
+        };
+        if self
+            .dataset
+            .fragments()
+            .iter()
+            .any(|frag| !fragment_bitmap.contains(frag.id as u32))
+        {
+            return Ok(None);
+        }
+        let descending = !ordering.ascending;
+        if ordering.nulls_first == descending {
+            let scalar_index = self
+                .dataset
+                .open_scalar_index(
+                    &ordering.column_name,
+                    &index.uuid.to_string(),
+                    &NoOpMetricsCollector,
+                )
+                .await?;
+            let has_nulls = scalar_index
+                .as_any()
+                .downcast_ref::<BTreeIndex>()
+                .is_none_or(|btree| btree.has_nulls());
+            if has_nulls {
+                return Ok(None);
+            }
+        }
+        Ok(Some((index, descending)))
+    }
+
+    /// Find out if the scan can be read from the requested sorted projection
+    ///
+    /// Returns the projection and whether it has to be read backwards.  This requires an
+    /// ordering that matches the projection, no filter or aggregate, and every column of the
+    /// output to be in the projection.  The projection must also be current, i.e. the
+    /// dataset has not changed since it was created.
+    async fn sorted_projection_source(
+        &self,
+        filter_plan: &FilterPlan,
+    ) -> Result<Option<(Arc<SortedProjection>, bool)>> {
+        let (Some(name), Some(ordering)) = (&self.sorted_projection, &self.ordering) else {
+            return Ok(None);
+        };
+        if filter_plan.has_any_filter()
+            || self.is_aggregate()
+            || self.deterministic_order
+            || self.include_deleted_rows
+            || self.fragments.is_some()
+            || self.nearest.is_some()
+            || self.full_text_query.is_some()
+        {
+            return Ok(None);
+        }
+        let projection = SortedProjection::open(&self.dataset, name).await?;
+        if !projection.is_current(&self.dataset) {
+            log::debug!(
+                "Not using sorted projection {} because the dataset has changed since it was created",
+                name
+            );
+            return Ok(None);
+        }
+        let Some(reverse) = projection.read_direction(ordering) else {
+            return Ok(None);
+        };
+        let columns = projection.columns();
+        let missing_column = self
+            .projection_plan
+            .physical_schema
+            .fields
+            .iter()
+            .any(|field| field.name != ROW_ADDR && !columns.contains(&field.name.as_str()));
+        if missing_column {
+            return Ok(None);
+        }
+        Ok(Some((Arc::new(projection), reverse)))
+    }
+
+    // Read the page (limit / offset) of the scan from a sorted projection
+    fn sorted_projection_scan(
+        &self,
+        projection: Arc<SortedProjection>,
+        reverse: bool,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let num_rows = projection.num_rows();
+        let offset = (self.offset.unwrap_or(0) as u64).min(num_rows);
+        let end = match self.limit {
+            Some(limit) if limit > 0 || self.offset.is_some() => {
+                (offset + limit as u64).min(num_rows)
+            }
+            _ => num_rows,
+        };
+        let range = if reverse {
+            num_rows - end..num_rows - offset
+        } else {
+            offset..end
+        };
+        let mut columns = self
+            .projection_plan
+            .physical_schema
+            .fields
+            .iter()
+            .filter(|field| field.name != ROW_ADDR)
+            .map(|field| field.name.clone())
+            .collect::<Vec<_>>();
+        // The row address is computed from the row id later in the plan
+        if self.with_row_address && !columns.iter().any(|column| column == ROW_ID) {
+            columns.push(ROW_ID.to_string());
+        }
+        Ok(Arc::new(SortedProjectionScanExec::try_new(
+            projection,
+            columns,
+            range,
+            reverse,
+            self.get_batch_size(),
+        )?))
+    }
+
+    // Read the row ids from a btree index in order, the remaining columns are taken later
+    fn ordered_index_scan(&self, index: Index, descending: bool) -> Result<Arc<dyn ExecutionPlan>> {
+        let column = self.ordering.as_ref().unwrap()[0].column_name.clone();
+        let mut plan: Arc<dyn ExecutionPlan> = Arc::new(OrderedIndexScanExec::new(
+            self.dataset.clone(),
+            column,
+            index,
+            descending,
+        ));
+        if self.with_row_address {
+            plan = Arc::new(AddRowAddrExec::try_new(plan, self.dataset.clone(), 0)?);
+        }
+        Ok(plan)
+    }
+
     // First perform a lookup in a scalar index for ids and then perform a take on the
     // target fragments with those ids
     async fn scalar_indexed_scan(
@@ -2408,6 +3477,22 @@ impl Scanner {
         projection: Projection,
         filter_plan: &FilterPlan,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        let (plan, _) = self
+            .scalar_indexed_scan_with_limit(projection, filter_plan, false)
+            .await?;
+        Ok(plan)
+    }
+
+    // Like `scalar_indexed_scan` but, if `push_down_limit` is set, the limit / offset is applied
+    // to the row ids from the index so skipped rows are never read.  This is only possible when
+    // the index results are exact and cover every fragment.  Returns true if the limit / offset
+    // was applied.
+    async fn scalar_indexed_scan_with_limit(
+        &self,
+        projection: Projection,
+        filter_plan: &FilterPlan,
+        push_down_limit: bool,
+    ) -> Result<(Arc<dyn ExecutionPlan>, bool)> {
         // One or more scalar indices cover this data and there is a filter which is
         // compatible with the indices.  Use that filter to perform a take instead of
         // a full scan.
@@ -2427,6 +3512,27 @@ impl Scanner {
             Arc::new(relevant_frags),
         ));
 
+        // The index yields exactly the matching rows (deleted rows are already removed) so we
+        // can skip straight to the offset before taking any data
+        let limit_applied = push_down_limit
+            && !needs_recheck
+            && filter_plan.refine_expr.is_none()
+            && missing_frags.is_empty();
+        if limit_applied {
+            plan = self.limit_node(plan);
+        }
+
+        // Row addresses are added before the take so the refine filter can use them
+        let with_row_address = self.with_row_address
+            || projection.with_row_addr
+            || filter_plan
+                .refine_columns()
+                .iter()
+                .any(|column| column == ROW_ADDR);
+        if with_row_address {
+            plan = Arc::new(AddRowAddrExec::try_new(plan, self.dataset.clone(), 0)?);
+        }
+
         let refine_expr = filter_plan.refine_expr.as_ref();
 
         // If all we want is the row ids then we can skip the take.  However, if there is a refine
@@ -2464,10 +3570,6 @@ impl Scanner {
             plan = Arc::new(LanceFilterExec::try_new(optimized_filter, plan)?);
         }
 
-        if self.with_row_address {
-            plan = Arc::new(AddRowAddrExec::try_new(plan, self.dataset.clone(), 0)?);
-        }
-
         let new_data_path: Option<Arc<dyn ExecutionPlan>> = if !missing_frags.is_empty() {
             // If there is new data then we need this:
             //
@@ -2492,7 +3594,7 @@ impl Scanner {
 
             let new_data_scan = self.scan_fragments(
                 true,
-                self.with_row_address,
+                with_row_address,
                 false,
                 scan_schema,
                 missing_frags.into(),
@@ -2513,14 +3615,18 @@ impl Scanner {
                 Arc::new(unioned),
                 datafusion::physical_plan::Partitioning::RoundRobinBatch(1),
             )?;
-            Ok(Arc::new(unioned))
+            Ok((Arc::new(unioned), limit_applied))
         } else {
-            Ok(plan)
+            Ok((plan, limit_applied))
         }
     }
 
     fn get_io_buffer_size(&self) -> u64 {
-        self.io_buffer_size.unwrap_or(*DEFAULT_IO_BUFFER_SIZE)
+        match (self.io_buffer_size, self.memory_limit) {
+            (Some(io_buffer_size), _) => io_buffer_size,
+            (None, Some(memory_limit)) => (memory_limit / 2).min(*DEFAULT_IO_BUFFER_SIZE),
+            (None, None) => *DEFAULT_IO_BUFFER_SIZE,
+        }
     }
 
     /// Create an Execution plan with a scan node
@@ -2540,12 +3646,6 @@ impl Scanner {
         } else {
             self.dataset.fragments().clone()
         };
-        let ordered = if self.ordering.is_some() || self.nearest.is_some() {
-            // If we are sorting the results there is no need to scan in order
-            false
-        } else {
-            self.ordered
-        };
         self.scan_fragments(
             with_row_id,
             with_row_address,
@@ -2553,10 +3653,21 @@ impl Scanner {
             projection,
             fragments,
             range,
-            ordered,
+            self.scan_fragments_in_order(),
         )
     }
 
+    fn scan_fragments_in_order(&self) -> bool {
+        if self.deterministic_order {
+            true
+        } else if self.ordering.is_some() || self.nearest.is_some() {
+            // If we are sorting the results there is no need to scan in order
+            false
+        } else {
+            self.ordered
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn scan_fragments(
         &self,
@@ -2572,12 +3683,17 @@ impl Scanner {
             batch_size: self.get_batch_size(),
             batch_readahead: self.batch_readahead,
             fragment_readahead: self.fragment_readahead,
+            fragment_io_parallelism: self.fragment_io_parallelism,
             io_buffer_size: self.get_io_buffer_size(),
+            io_priority: self.io_priority,
+            prefetch_depth: self.prefetch_depth.unwrap_or(*DEFAULT_PREFETCH_DEPTH),
+            uncached_reads: self.uncached_reads,
             with_row_id,
             with_row_address,
             with_make_deletions_null,
             ordered_output: ordered,
             strict_batch_size: self.strict_batch_size,
+            cancellation_token: self.cancellation_token.clone(),
         };
         Arc::new(LanceScanExec::new(
             self.dataset.clone(),
@@ -2601,7 +3717,7 @@ impl Scanner {
             with_row_id: self.with_row_id,
             with_row_address: self.with_row_address,
             make_deletions_null,
-            ordered_output: self.ordered,
+            ordered_output: self.ordered || self.deterministic_order,
         };
 
         let fragments = if let Some(fragment) = self.fragments.as_ref() {
@@ -2849,8 +3965,17 @@ impl Scanner {
                 // of the filter columns to determine the valid row ids.
 
                 let columns_in_filter = Planner::column_names_in_expr(refine_expr);
-                let filter_schema = Arc::new(self.dataset.schema().project(&columns_in_filter)?);
-                let filter_input = self.scan(true, false, true, None, filter_schema);
+                let filter_projection = self
+                    .dataset
+                    .empty_projection()
+                    .union_columns(&columns_in_filter, OnMissing::Error)?;
+                let filter_input = self.scan(
+                    true,
+                    filter_projection.with_row_addr,
+                    true,
+                    None,
+                    filter_projection.into_schema_ref(),
+                );
                 let filtered_row_ids =
                     Arc::new(LanceFilterExec::try_new(refine_expr.clone(), filter_input)?);
                 PreFilterSource::FilteredRowIds(filtered_row_ids)
@@ -2860,9 +3985,26 @@ impl Scanner {
             (_, _, false, _) => PreFilterSource::None,
         };
 
+        if let Some(allowed) = self.allowed_row_ids.as_ref() {
+            return Ok(PreFilterSource::ScalarIndexQuery(Arc::new(
+                AllowListPrefilterExec::new(allowed.clone(), prefilter_source),
+            )));
+        }
+
         Ok(prefilter_source)
     }
 
+    /// Drop the rows that are not in [`Self::allowed_row_ids`], for searches without an index
+    fn allow_list_filter(&self, plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        match self.allowed_row_ids.as_ref() {
+            Some(allowed) => Ok(Arc::new(AllowListFilterExec::try_new(
+                plan,
+                allowed.clone(),
+            )?)),
+            None => Ok(plan),
+        }
+    }
+
     /// Take row indices produced by input plan from the dataset (with projection)
     fn take(
         &self,
@@ -2883,6 +4025,134 @@ impl Scanner {
         }
     }
 
+    fn is_aggregate(&self) -> bool {
+        self.distinct || self.count_by.is_some()
+    }
+
+    fn validate_aggregate(&self) -> Result<()> {
+        if self.distinct && self.count_by.is_some() {
+            return Err(Error::invalid_input(
+                "distinct_on and count_rows_by can't be combined",
+                location!(),
+            ));
+        }
+        if self.with_row_id
+            || self.with_row_address
+            || self.ordering.is_some()
+            || self.nearest.is_some()
+            || self.full_text_query.is_some()
+        {
+            let method = if self.distinct {
+                "distinct_on"
+            } else {
+                "count_rows_by"
+            };
+            return Err(Error::invalid_input(
+                format!("{} can't be combined with row ids, an ordering, or a vector or full text search", method),
+                location!(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Group on every output column, either to remove duplicate rows or to count the rows
+    // of each group
+    fn aggregate_node(&self, plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        self.validate_aggregate()?;
+        let schema = plan.schema();
+        let group_expr = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                Ok((
+                    expressions::col(field.name(), schema.as_ref())?,
+                    field.name().clone(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut aggr_expr = vec![];
+        if self.count_by.is_some() {
+            let one: Arc<dyn PhysicalExpr> = Arc::new(Literal::new(ScalarValue::UInt8(Some(1))));
+            aggr_expr.push(Arc::new(
+                AggregateExprBuilder::new(count_udaf(), vec![one])
+                    .schema(schema.clone())
+                    .alias("count_rows")
+                    .build()?,
+            ));
+        }
+        let filter_expr = vec![None; aggr_expr.len()];
+        Ok(Arc::new(AggregateExec::try_new(
+            AggregateMode::Single,
+            PhysicalGroupBy::new_single(group_expr),
+            aggr_expr,
+            filter_expr,
+            plan,
+            schema,
+        )?))
+    }
+
+    /// Create a plan that counts the values of the `count_rows_by` column with an index
+    ///
+    /// This applies when there is no filter and the column has a bitmap or btree index
+    /// covering every fragment.
+    async fn create_index_value_count_plan(&self) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(column) = self.count_by.as_ref() else {
+            return Ok(None);
+        };
+        self.validate_aggregate()?;
+        if self.filter.is_some()
+            || !self.use_scalar_index
+            || self.include_deleted_rows
+            || self.fragments.is_some()
+        {
+            return Ok(None);
+        }
+        let mut index = None;
+        for index_type in [ScalarIndexType::Bitmap, ScalarIndexType::BTree] {
+            index = self
+                .dataset
+                .load_scalar_index(
+                    ScalarIndexCriteria::default()
+                        .for_column(column)
+                        .with_type(index_type),
+                )
+                .await?;
+            if index.is_some() {
+                break;
+            }
+        }
+        let Some(index) = index else {
+            return Ok(None);
+        };
+        let covers_all = index.fragment_bitmap.as_ref().is_some_and(|bitmap| {
+            self.dataset
+                .fragments()
+                .iter()
+                .all(|frag| bitmap.contains(frag.id as u32))
+        });
+        if !covers_all {
+            return Ok(None);
+        }
+
+        let field = self.dataset.schema().field(column).ok_or_else(|| {
+            Error::invalid_input(format!("Column {} not found", column), location!())
+        })?;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::from(field).with_name(column),
+            ArrowField::new("count_rows", DataType::Int64, false),
+        ]));
+        let mut plan: Arc<dyn ExecutionPlan> = Arc::new(IndexValueCountExec::new(
+            self.dataset.clone(),
+            column.clone(),
+            index,
+            schema,
+        ));
+        if self.limit.unwrap_or(0) > 0 || self.offset.is_some() {
+            plan = self.limit_node(plan);
+        }
+        Ok(Some(plan))
+    }
+
     /// Global offset-limit of the result of the input plan
     fn limit_node(&self, plan: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
         Arc::new(GlobalLimitExec::new(
@@ -2898,10 +4168,30 @@ impl Scanner {
 
         analyze_plan(
             plan,
-            LanceExecutionOptions {
+            self.execution_options(LanceExecutionOptions {
                 batch_size: self.batch_size,
                 ..Default::default()
-            },
+            }),
+        )
+        .await
+    }
+
+    /// Run the query and return the metrics of each node of its plan as a tree
+    ///
+    /// This is the structured form of [`Self::analyze_plan`].  Each node reports the rows
+    /// it produced and the time it spent along with what it read from storage, how many
+    /// reads were served by prefetched data, the pages it decoded and the index partitions
+    /// it searched.
+    #[instrument(level = "info", skip(self))]
+    pub async fn analyze_plan_metrics(&self) -> Result<PlanNodeMetrics> {
+        let plan = self.create_plan().await?;
+
+        analyze_plan_metrics(
+            plan,
+            self.execution_options(LanceExecutionOptions {
+                batch_size: self.batch_size,
+                ..Default::default()
+            }),
         )
         .await
     }
@@ -2913,6 +4203,18 @@ impl Scanner {
 
         Ok(format!("{}", display.indent(verbose)))
     }
+
+    /// Explain the plan used by [`Self::count_rows`]
+    ///
+    /// This shows whether the count is answered by scalar indices (`ScalarIndexCount`) and
+    /// which fragments, if any, still have to be scanned.
+    #[instrument(level = "info", skip(self))]
+    pub async fn explain_count_plan(&self, verbose: bool) -> Result<String> {
+        let plan = self.create_count_plan().await?;
+        let display = DisplayableExecutionPlan::new(plan.as_ref());
+
+        Ok(format!("{}", display.indent(verbose)))
+    }
 }
 
 /// [`DatasetRecordBatchStream`] wraps the dataset into a [`RecordBatchStream`] for
@@ -2945,9 +4247,16 @@ impl Stream for DatasetRecordBatchStream {
         let mut this = self.project();
         let _guard = this.span.enter();
         match this.exec_node.poll_next_unpin(cx) {
-            Poll::Ready(result) => {
-                Poll::Ready(result.map(|r| r.map_err(|e| Error::io(e.to_string(), location!()))))
-            }
+            Poll::Ready(result) => Poll::Ready(result.map(|r| {
+                r.map_err(|e| {
+                    let message = e.to_string();
+                    // Keep aborted queries distinguishable from failed ones
+                    match Error::from(e) {
+                        err @ (Error::Cancelled { .. } | Error::Timeout { .. }) => err,
+                        _ => Error::io(message, location!()),
+                    }
+                })
+            })),
             Poll::Pending => Poll::Pending,
         }
     }
@@ -3143,18 +4452,20 @@ mod test {
     use std::vec;
 
     use arrow::array::as_primitive_array;
-    use arrow::datatypes::Int32Type;
+    use arrow::datatypes::{Int32Type, Int64Type};
     use arrow_array::cast::AsArray;
     use arrow_array::types::{Float32Type, UInt64Type};
     use arrow_array::{
-        ArrayRef, FixedSizeListArray, Float16Array, Int32Array, LargeStringArray, PrimitiveArray,
-        RecordBatchIterator, StringArray, StructArray,
+        ArrayRef, FixedSizeListArray, Float16Array, Float64Array, Int32Array, LargeStringArray,
+        PrimitiveArray, RecordBatchIterator, StringArray, StructArray,
     };
     use arrow_ord::sort::sort_to_indices;
     use arrow_select::take;
     use datafusion::logical_expr::{col, lit};
     use half::f16;
-    use lance_datagen::{array, gen, BatchCount, ByteCount, Dimension, RowCount};
+    use lance_datagen::{
+        array, gen, ArrayGeneratorExt, BatchCount, ByteCount, Dimension, RowCount,
+    };
     use lance_file::version::LanceFileVersion;
     use lance_index::scalar::inverted::query::{MatchQuery, PhraseQuery};
     use lance_index::vector::hnsw::builder::HnswBuildParams;
@@ -3172,6 +4483,7 @@ mod test {
     use crate::arrow::*;
     use crate::dataset::optimize::{compact_files, CompactionOptions};
     use crate::dataset::scanner::test_dataset::TestVectorDataset;
+    use crate::dataset::sorted_projection::SortedProjectionParams;
     use crate::dataset::WriteMode;
     use crate::dataset::WriteParams;
     use crate::index::vector::{StageParams, VectorIndexParams};
@@ -3230,7 +4542,544 @@ mod test {
         }
     }
 
-    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_batch_size_bytes() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .col("s", array::rand_utf8(ByteCount::from(1000), false))
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(10));
+        Dataset::write(data, test_uri, None).await.unwrap();
+        let dataset = Dataset::open(test_uri).await.unwrap();
+
+        // Each row is ~1KB so a 100KB target is ~100 rows
+        let batches = dataset
+            .scan()
+            .batch_size_bytes(100_000)
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            10_000
+        );
+        for batch in &batches[..batches.len() - 1] {
+            assert!(
+                (50..=150).contains(&batch.num_rows()),
+                "{}",
+                batch.num_rows()
+            );
+        }
+
+        // Narrow rows are combined beyond the row batch size
+        let batches = dataset
+            .scan()
+            .project(&["i"])
+            .unwrap()
+            .batch_size(1024)
+            .batch_size_bytes(1_000_000)
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .into_reader_rows(RowCount::from(100), BatchCount::from(10));
+        Dataset::write(data, test_uri, None).await.unwrap();
+        let dataset = Dataset::open(test_uri).await.unwrap();
+
+        let token = CancellationToken::new();
+        let mut stream = dataset
+            .scan()
+            .batch_size(100)
+            .cancellation_token(token.clone())
+            .try_into_stream()
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().num_rows(), 100);
+        token.cancel();
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(Error::Cancelled { .. })
+        ));
+        assert!(stream.next().await.is_none());
+
+        // An expired deadline fails the query with a timeout
+        let token = CancellationToken::with_deadline(std::time::Instant::now());
+        let err = dataset
+            .scan()
+            .cancellation_token(token)
+            .order_by(Some(vec![ColumnOrdering::desc_nulls_first(
+                "i".to_string(),
+            )]))
+            .unwrap()
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{}", err);
+
+        // Without cancellation the token is transparent
+        let batches = dataset
+            .scan()
+            .cancellation_token(CancellationToken::new())
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            1000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress_callback() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .into_reader_rows(RowCount::from(100), BatchCount::from(10));
+        let write_params = WriteParams {
+            max_rows_per_file: 100,
+            ..Default::default()
+        };
+        Dataset::write(data, test_uri, Some(write_params))
+            .await
+            .unwrap();
+        let dataset = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(dataset.get_fragments().len(), 10);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let callback_reports = reports.clone();
+        let batches = dataset
+            .scan()
+            .batch_size(50)
+            .progress_callback(Arc::new(move |progress| {
+                callback_reports.lock().unwrap().push(progress.clone())
+            }))
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), batches.len() + 1);
+        for pair in reports.windows(2) {
+            assert!(pair[0].rows_produced <= pair[1].rows_produced);
+            assert!(pair[0].fragments_completed <= pair[1].fragments_completed);
+            assert!(pair[0].bytes_read <= pair[1].bytes_read);
+        }
+        let last = reports.last().unwrap();
+        assert_eq!(last.rows_produced, 1000);
+        assert_eq!(last.fragments_completed, 10);
+        assert!(last.bytes_read > 0);
+
+        // The callback can enforce a limit by cancelling the scan
+        let token = CancellationToken::new();
+        let callback_token = token.clone();
+        let err = dataset
+            .scan()
+            .batch_size(100)
+            .cancellation_token(token)
+            .progress_callback(Arc::new(move |progress| {
+                if progress.rows_produced >= 300 {
+                    callback_token.cancel();
+                }
+            }))
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Cancelled { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_plan_metrics() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .col("s", array::rand_utf8(ByteCount::from(10), false))
+            .into_reader_rows(RowCount::from(100), BatchCount::from(10));
+        let write_params = WriteParams {
+            max_rows_per_file: 500,
+            ..Default::default()
+        };
+        Dataset::write(data, test_uri, Some(write_params))
+            .await
+            .unwrap();
+        let dataset = Dataset::open(test_uri).await.unwrap();
+
+        let root = dataset
+            .scan()
+            .filter("i < 250")
+            .unwrap()
+            .project(&["s"])
+            .unwrap()
+            .analyze_plan_metrics()
+            .await
+            .unwrap();
+        assert_eq!(root.output_rows, 250);
+        assert!(!root.description.is_empty());
+
+        fn flatten(node: &PlanNodeMetrics) -> Vec<&PlanNodeMetrics> {
+            std::iter::once(node)
+                .chain(node.children.iter().flat_map(flatten))
+                .collect()
+        }
+        let nodes = flatten(&root);
+        assert!(nodes.len() > 1);
+        let readers = nodes
+            .iter()
+            .filter(|node| node.bytes_read() > 0)
+            .collect::<Vec<_>>();
+        assert!(!readers.is_empty());
+        for reader in readers {
+            assert!(reader.pages_decoded() > 0);
+            assert!(reader.output_rows > 0);
+            assert!(reader.cache_hit_rate().is_some());
+            assert_eq!(reader.partitions_probed(), 0);
+        }
+        // Only the nodes reading from storage report I/O
+        assert!(nodes
+            .iter()
+            .filter(|node| node.bytes_read() == 0)
+            .all(|node| node.pages_decoded() == 0 && node.cache_hit_rate().is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_filter_system_columns() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .into_reader_rows(RowCount::from(100), BatchCount::from(4));
+        let write_params = WriteParams {
+            max_rows_per_file: 100,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(data, test_uri, Some(write_params))
+            .await
+            .unwrap();
+
+        // Returns the values of `i` and the number of fragments scanned
+        async fn scan_with_filter(dataset: &Dataset, filter: &str) -> (Vec<i64>, usize) {
+            let fragments_scanned = Arc::new(AtomicUsize::new(0));
+            let stats_fragments_scanned = fragments_scanned.clone();
+            let batch = dataset
+                .scan()
+                .filter(filter)
+                .unwrap()
+                .scan_stats_callback(Arc::new(move |stats| {
+                    let count = stats.all_counts.get(FRAGMENTS_SCANNED_METRIC).copied();
+                    stats_fragments_scanned.store(count.unwrap_or(0), Ordering::Relaxed);
+                }))
+                .try_into_batch()
+                .await
+                .unwrap();
+            let mut values = batch["i"].as_primitive::<Int64Type>().values().to_vec();
+            values.sort();
+            (values, fragments_scanned.load(Ordering::Relaxed))
+        }
+
+        // Resume a scan from a row address
+        let (values, fragments_scanned) =
+            scan_with_filter(&dataset, &format!("_rowaddr >= {}", 2_u64 << 32)).await;
+        assert_eq!(values, (200..400).collect::<Vec<_>>());
+        assert_eq!(fragments_scanned, 2);
+
+        let (values, fragments_scanned) =
+            scan_with_filter(&dataset, &format!("_rowid IN (5, {})", (1_u64 << 32) + 10)).await;
+        assert_eq!(values, vec![5, 110]);
+        assert_eq!(fragments_scanned, 2);
+
+        // A predicate on other columns can match any fragment
+        let filter = format!(
+            "_rowaddr BETWEEN {} AND {} OR i = 399",
+            1_u64 << 32,
+            (1_u64 << 32) + 4
+        );
+        let (values, fragments_scanned) = scan_with_filter(&dataset, &filter).await;
+        assert_eq!(values, vec![100, 101, 102, 103, 104, 399]);
+        assert_eq!(fragments_scanned, 4);
+
+        // System columns can be projected without asking for them
+        let batch = dataset
+            .scan()
+            .project(&["_rowaddr", "i"])
+            .unwrap()
+            .filter("i >= 101 AND i < 103")
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["_rowaddr", "i"]);
+        assert_eq!(
+            batch["_rowaddr"].as_primitive::<UInt64Type>().values(),
+            &[(1 << 32) + 1, (1 << 32) + 2]
+        );
+
+        // The system columns can refine an indexed filter
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::Scalar,
+                None,
+                &ScalarIndexParams::default(),
+                true,
+            )
+            .await
+            .unwrap();
+        let filter = format!("i > 150 AND _rowaddr < {}", 3_u64 << 32);
+        let (values, _) = scan_with_filter(&dataset, &filter).await;
+        assert_eq!(values, (151..300).collect::<Vec<_>>());
+
+        // Rows don't record their version, so `_version` is rejected up front
+        let err = dataset
+            .scan()
+            .filter("_version > 1 OR i < 10")
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotSupported { .. }), "{}", err);
+        let err = dataset.scan().project(&["i", "_version"]).err().unwrap();
+        assert!(matches!(err, Error::NotSupported { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_order() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("i", DataType::Int64, false),
+            ArrowField::new("k", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from_iter_values(0..400)),
+                Arc::new(Int64Array::from_iter_values((0..400).map(|i| i % 3))),
+            ],
+        )
+        .unwrap();
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let write_params = WriteParams {
+            max_rows_per_file: 100,
+            max_rows_per_group: 10,
+            ..Default::default()
+        };
+        let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
+        let mut dataset = Dataset::write(batches, test_uri, Some(write_params))
+            .await
+            .unwrap();
+        // Without the deterministic order the filter would be answered by the index
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::Scalar,
+                None,
+                &ScalarIndexParams::default(),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let batch = dataset
+            .scan()
+            .scan_in_order(false)
+            .fragment_readahead(4)
+            .batch_size(10)
+            .filter("i % 2 = 0 OR i > 300")
+            .unwrap()
+            .deterministic_order(true)
+            .try_into_batch()
+            .await
+            .unwrap();
+        let expected = (0..400)
+            .filter(|i| i % 2 == 0 || *i > 300)
+            .collect::<Vec<i64>>();
+        assert_eq!(batch["i"].as_primitive::<Int64Type>().values(), &expected);
+
+        // Ties in the ordering are broken by row address
+        let batch = dataset
+            .scan()
+            .order_by(Some(vec![ColumnOrdering::asc_nulls_first("k".to_string())]))
+            .unwrap()
+            .deterministic_order(true)
+            .try_into_batch()
+            .await
+            .unwrap();
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["i", "k"]);
+        let mut expected = (0..400).collect::<Vec<i64>>();
+        expected.sort_by_key(|i| i % 3);
+        assert_eq!(batch["i"].as_primitive::<Int64Type>().values(), &expected);
+
+        let err = dataset
+            .scan()
+            .distinct_on(&["k"])
+            .unwrap()
+            .deterministic_order(true)
+            .try_into_batch()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_offset_skips_rows() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .into_reader_rows(RowCount::from(100), BatchCount::from(4));
+        let write_params = WriteParams {
+            max_rows_per_file: 100,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(data, test_uri, Some(write_params))
+            .await
+            .unwrap();
+        dataset.delete("i < 50").await.unwrap();
+
+        // Returns the values of `i`, the plan and the number of fragments scanned
+        async fn scan_page(
+            dataset: &Dataset,
+            filter: Option<&str>,
+            ordering: Option<Vec<ColumnOrdering>>,
+            limit: i64,
+            offset: i64,
+        ) -> (Vec<i64>, String, usize) {
+            let fragments_scanned = Arc::new(AtomicUsize::new(0));
+            let stats_fragments_scanned = fragments_scanned.clone();
+            let mut scan = dataset.scan();
+            scan.order_by(ordering)
+                .unwrap()
+                .limit(Some(limit), Some(offset))
+                .unwrap()
+                .scan_stats_callback(Arc::new(move |stats| {
+                    let count = stats.all_counts.get(FRAGMENTS_SCANNED_METRIC).copied();
+                    stats_fragments_scanned.store(count.unwrap_or(0), Ordering::Relaxed);
+                }));
+            if let Some(filter) = filter {
+                scan.filter(filter).unwrap();
+            }
+            let plan = scan.explain_plan(false).await.unwrap();
+            let batch = scan.try_into_batch().await.unwrap();
+            let values = batch["i"].as_primitive::<Int64Type>().values().to_vec();
+            (values, plan, fragments_scanned.load(Ordering::Relaxed))
+        }
+
+        // Without a filter the scan seeks past the deleted rows and the skipped fragments
+        let (values, plan, fragments_scanned) = scan_page(&dataset, None, None, 10, 200).await;
+        assert_eq!(values, (250..260).collect::<Vec<_>>());
+        assert!(!plan.contains("GlobalLimitExec"), "{}", plan);
+        assert_eq!(fragments_scanned, 1);
+
+        // A sort needs every row before the offset can be applied
+        let ordering = vec![ColumnOrdering::desc_nulls_first("i".to_string())];
+        let (values, _, _) = scan_page(&dataset, None, Some(ordering), 5, 10).await;
+        assert_eq!(values, (385..390).rev().collect::<Vec<_>>());
+
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::Scalar,
+                None,
+                &ScalarIndexParams::default(),
+                true,
+            )
+            .await
+            .unwrap();
+
+        // An exact index result applies the offset to the row ids before taking any data
+        let (values, plan, _) = scan_page(&dataset, Some("i >= 100"), None, 5, 20).await;
+        assert_eq!(values, (120..125).collect::<Vec<_>>());
+        let take = plan.find("Take").unwrap();
+        let limit = plan.find("GlobalLimitExec: skip=20, fetch=5").unwrap();
+        assert!(take < limit, "{}", plan);
+
+        // Rows that still need filtering can't be skipped up front
+        let (values, plan, _) =
+            scan_page(&dataset, Some("i >= 100 AND i % 2 = 0"), None, 5, 20).await;
+        assert_eq!(values, (140..150).step_by(2).collect::<Vec<_>>());
+        let limit = plan.find("GlobalLimitExec: skip=20, fetch=5").unwrap();
+        assert!(limit < plan.find("Filter").unwrap(), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int64Type>())
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(10));
+        Dataset::write(data, test_uri, None).await.unwrap();
+        let dataset = Dataset::open(test_uri).await.unwrap();
+
+        let mut scan = dataset.scan();
+        scan.memory_limit(64 * 1024 * 1024);
+        assert_eq!(scan.get_io_buffer_size(), 32 * 1024 * 1024);
+        let options = scan.execution_options(LanceExecutionOptions::default());
+        assert!(options.use_spilling);
+        assert_eq!(options.mem_pool_size, Some(32 * 1024 * 1024));
+
+        // An explicit I/O buffer size wins over the budget
+        scan.io_buffer_size(1024 * 1024);
+        assert_eq!(scan.get_io_buffer_size(), 1024 * 1024);
+
+        let batches = scan
+            .order_by(Some(vec![ColumnOrdering::desc_nulls_first(
+                "i".to_string(),
+            )]))
+            .unwrap()
+            .try_into_stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let values = concat_batches(&batches[0].schema(), &batches).unwrap();
+        let values = values["i"].as_primitive::<Int64Type>();
+        assert_eq!(values.len(), 10_000);
+        assert!(values.values().windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[cfg(not(windows))]
     #[tokio::test]
     async fn test_local_object_store() {
         let schema = Arc::new(ArrowSchema::new(vec![
@@ -3280,6 +5129,34 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_scan_with_prefetch() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let data = gen()
+            .col("i", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(10), BatchCount::from(10));
+        Dataset::write(
+            data,
+            test_uri,
+            Some(WriteParams {
+                max_rows_per_file: 10,
+                data_storage_version: Some(LanceFileVersion::Stable),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        let dataset = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(dataset.get_fragments().len(), 10);
+        let mut scan = dataset.scan();
+        scan.fragment_readahead(1).prefetch_depth(3);
+        let batch = scan.try_into_batch().await.unwrap();
+        let values = batch["i"].as_primitive::<Int32Type>().values();
+        assert_eq!(values.to_vec(), (0..100).collect::<Vec<_>>());
+    }
+
     #[tokio::test]
     async fn test_filter_parsing() -> Result<()> {
         let test_ds = TestVectorDataset::new(LanceFileVersion::Stable, false).await?;
@@ -3386,6 +5263,32 @@ mod test {
         assert_eq!(expected_i, actual_i);
     }
 
+    #[tokio::test]
+    async fn test_knn_f64_query() {
+        let test_ds = TestVectorDataset::new(LanceFileVersion::Stable, false)
+            .await
+            .unwrap();
+        let dataset = &test_ds.dataset;
+
+        // The query is cast to the Float32 vector column
+        let key: Float64Array = (32..64).map(|v| v as f64).collect();
+        let batch = dataset
+            .scan()
+            .nearest("vec", &key, 5)
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+
+        let actual_i: BTreeSet<i32> = batch["i"]
+            .as_primitive::<Int32Type>()
+            .values()
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(actual_i, BTreeSet::from_iter(vec![1, 81, 161, 241, 321]));
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_knn_with_new_data(
@@ -3606,6 +5509,68 @@ mod test {
         }
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_knn_allowed_row_ids(#[values(false, true)] use_index: bool) {
+        let mut test_ds = TestVectorDataset::new(LanceFileVersion::Stable, false)
+            .await
+            .unwrap();
+        test_ds.make_vector_index().await.unwrap();
+        let dataset = &test_ds.dataset;
+
+        // Allow the rows where i is even
+        let batch = dataset
+            .scan()
+            .project(&["i"])
+            .unwrap()
+            .with_row_id()
+            .filter("i % 2 = 0")
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        let allowed = RowIdTreeMap::from_iter(
+            batch[ROW_ID]
+                .as_primitive::<UInt64Type>()
+                .values()
+                .iter()
+                .copied(),
+        );
+
+        let key: Float32Array = (32..64).map(|v| v as f32).collect();
+        for filter in [None, Some("i > 100")] {
+            let mut scan = dataset.scan();
+            scan.project(&["i"]).unwrap();
+            scan.nearest("vec", &key, 5).unwrap();
+            scan.use_index(use_index);
+            scan.allowed_row_ids(allowed.clone());
+            if let Some(filter) = filter {
+                scan.filter(filter).unwrap().prefilter(true);
+            }
+
+            let plan = scan.explain_plan(false).await.unwrap();
+            if use_index {
+                assert!(plan.contains("AllowListPrefilter"), "{}", plan);
+            } else {
+                assert!(plan.contains("AllowListFilter"), "{}", plan);
+            }
+
+            let batch = scan.try_into_batch().await.unwrap();
+            assert_eq!(batch.num_rows(), 5);
+            for i in batch["i"].as_primitive::<Int32Type>().values() {
+                assert_eq!(i % 2, 0);
+                if filter.is_some() {
+                    assert!(*i > 100);
+                }
+            }
+        }
+
+        // The allow list only applies to vector searches
+        let mut scan = dataset.scan();
+        scan.allowed_row_ids(allowed);
+        assert!(scan.try_into_batch().await.is_err());
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_knn_with_filter(
@@ -4585,6 +6550,49 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_project_expressions() {
+        let fixture = TestVectorDataset::new(LanceFileVersion::Stable, false)
+            .await
+            .unwrap();
+
+        let batch = fixture
+            .dataset
+            .scan()
+            .project_expressions(&["i", "i * 2 AS double", "upper(s)"])
+            .unwrap()
+            .filter("i < 5")
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["i", "double", "upper(s)"]);
+        // The literal may widen the result so compare as i64
+        let double = arrow::compute::cast(&batch["double"], &DataType::Int64).unwrap();
+        assert_eq!(
+            double.as_primitive::<Int64Type>().values().to_vec(),
+            vec![0, 2, 4, 6, 8]
+        );
+        let upper = batch["upper(s)"].as_string::<i32>();
+        assert_eq!(upper.value(0), "S-0");
+        assert_eq!(upper.value(4), "S-4");
+
+        let err = fixture
+            .dataset
+            .scan()
+            .project_expressions(&["i AS x", "s AS x"])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Duplicate column name"), "{}", err);
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_column_casting_function(
@@ -5161,6 +7169,258 @@ mod test {
         assert_plan_node_equals(exec_plan, expected).await
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_count_rows_with_scalar_index(#[values(false, true)] stable_row_ids: bool) {
+        let mut fixture = TestVectorDataset::new(LanceFileVersion::Stable, stable_row_ids)
+            .await
+            .unwrap();
+        fixture.make_scalar_index().await.unwrap();
+        fixture.dataset.delete("i < 20").await.unwrap();
+
+        async fn count(dataset: &Dataset, filter: &str, use_index: bool) -> (u64, String) {
+            let mut scan = dataset.scan();
+            scan.project(&Vec::<String>::default())
+                .unwrap()
+                .with_row_id()
+                .use_scalar_index(use_index)
+                .filter(filter)
+                .unwrap();
+            (
+                scan.count_rows().await.unwrap(),
+                scan.explain_count_plan(false).await.unwrap(),
+            )
+        }
+
+        let (num_rows, plan) = count(&fixture.dataset, "i >= 100", true).await;
+        assert_eq!(num_rows, 300);
+        assert!(plan.starts_with("ScalarIndexCount"), "{}", plan);
+        assert!(!plan.contains("LanceScan"), "{}", plan);
+
+        // New data is not covered by the index and is counted by a residual scan
+        fixture.append_new_data().await.unwrap();
+        for filter in ["i >= 100", "i < 50", "i = 405", "i >= 100 AND i < 200"] {
+            let (num_rows, plan) = count(&fixture.dataset, filter, true).await;
+            assert!(plan.starts_with("ScalarIndexCount"), "{}", plan);
+            assert!(plan.contains("LanceScan"), "{}", plan);
+            let (expected, fallback_plan) = count(&fixture.dataset, filter, false).await;
+            assert!(
+                fallback_plan.starts_with("AggregateExec"),
+                "{}",
+                fallback_plan
+            );
+            assert_eq!(num_rows, expected, "{}", filter);
+        }
+
+        // Residual predicates on unindexed columns fall back to a scan
+        let (num_rows, plan) = count(&fixture.dataset, "i >= 100 AND s != 's-150'", true).await;
+        assert_eq!(num_rows, 309);
+        assert!(plan.starts_with("AggregateExec"), "{}", plan);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_ordered_scan_with_btree_index(#[values(false, true)] stable_row_ids: bool) {
+        let mut fixture = TestVectorDataset::new(LanceFileVersion::Stable, stable_row_ids)
+            .await
+            .unwrap();
+        fixture.make_scalar_index().await.unwrap();
+        fixture.dataset.delete("i >= 390").await.unwrap();
+
+        async fn ordered(
+            dataset: &Dataset,
+            ordering: ColumnOrdering,
+            filter: Option<&str>,
+            use_index: bool,
+        ) -> (Vec<i32>, String) {
+            let mut scan = dataset.scan();
+            scan.project(&["i", "s"])
+                .unwrap()
+                .use_scalar_index(use_index)
+                .order_by(Some(vec![ordering]))
+                .unwrap()
+                .limit(Some(10), None)
+                .unwrap();
+            if let Some(filter) = filter {
+                scan.filter(filter).unwrap();
+            }
+            let plan = scan.explain_plan(false).await.unwrap();
+            let batch = scan.try_into_batch().await.unwrap();
+            let values = batch["i"].as_primitive::<Int32Type>().values().to_vec();
+            (values, plan)
+        }
+
+        let cases: [(fn(String) -> ColumnOrdering, Option<&str>); 4] = [
+            (ColumnOrdering::asc_nulls_first, None),
+            (ColumnOrdering::desc_nulls_last, None),
+            // There are no nulls so the null placement doesn't matter
+            (ColumnOrdering::desc_nulls_first, None),
+            // Filters that can't use the index are applied to the rows as they are taken
+            (ColumnOrdering::desc_nulls_last, Some("i % 3 = 0")),
+        ];
+        for (ordering, filter) in cases {
+            let (values, plan) =
+                ordered(&fixture.dataset, ordering("i".to_string()), filter, true).await;
+            assert!(plan.contains("OrderedIndexScan"), "{}", plan);
+            assert!(!plan.contains("SortExec"), "{}", plan);
+            let (expected, fallback_plan) =
+                ordered(&fixture.dataset, ordering("i".to_string()), filter, false).await;
+            assert!(fallback_plan.contains("SortExec"), "{}", fallback_plan);
+            assert_eq!(values, expected, "{:?}", filter);
+        }
+
+        let (values, _) = ordered(
+            &fixture.dataset,
+            ColumnOrdering::desc_nulls_last("i".to_string()),
+            None,
+            true,
+        )
+        .await;
+        assert_eq!(values, (380..390).rev().collect::<Vec<_>>());
+
+        // New data is not covered by the index so we fall back to a sort
+        fixture.append_new_data().await.unwrap();
+        let (_, plan) = ordered(
+            &fixture.dataset,
+            ColumnOrdering::asc_nulls_first("i".to_string()),
+            None,
+            true,
+        )
+        .await;
+        assert!(plan.contains("SortExec"), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_order_by_with_btree_index() {
+        let mut fixture = TestVectorDataset::new(LanceFileVersion::Stable, false)
+            .await
+            .unwrap();
+        fixture.make_scalar_index().await.unwrap();
+
+        // The nearest rows are sorted by the requested column, not by distance
+        let key: Float32Array = (32..64).map(|v| v as f32).collect();
+        let mut scan = fixture.dataset.scan();
+        scan.nearest("vec", &key, 10)
+            .unwrap()
+            .order_by(Some(vec![ColumnOrdering::desc_nulls_last("i".to_string())]))
+            .unwrap();
+        let plan = scan.explain_plan(false).await.unwrap();
+        assert!(!plan.contains("OrderedIndexScan"), "{}", plan);
+        assert!(plan.contains("SortExec"), "{}", plan);
+
+        let batch = scan.try_into_batch().await.unwrap();
+        assert_eq!(batch.num_rows(), 10);
+        let values = batch["i"].as_primitive::<Int32Type>().values().to_vec();
+        let mut expected = values.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(values, expected);
+    }
+
+    #[tokio::test]
+    async fn test_sorted_projection_pagination() {
+        let mut fixture = TestVectorDataset::new(LanceFileVersion::Stable, false)
+            .await
+            .unwrap();
+        fixture
+            .dataset
+            .create_sorted_projection(
+                "by_i",
+                &SortedProjectionParams::new(
+                    vec![ColumnOrdering::asc_nulls_first("i".to_string())],
+                    vec!["s".to_string()],
+                ),
+            )
+            .await
+            .unwrap();
+
+        async fn page(
+            dataset: &Dataset,
+            ordering: ColumnOrdering,
+            offset: i64,
+            use_projection: bool,
+        ) -> (Vec<i32>, Vec<String>, String) {
+            let mut scan = dataset.scan();
+            scan.project(&["i", "s"])
+                .unwrap()
+                .order_by(Some(vec![ordering]))
+                .unwrap()
+                .limit(Some(7), Some(offset))
+                .unwrap();
+            if use_projection {
+                scan.use_sorted_projection("by_i");
+            }
+            let plan = scan.explain_plan(false).await.unwrap();
+            let batch = scan.try_into_batch().await.unwrap();
+            let i = batch["i"].as_primitive::<Int32Type>().values().to_vec();
+            let s = batch["s"]
+                .as_string::<i32>()
+                .iter()
+                .map(|s| s.unwrap().to_string())
+                .collect();
+            (i, s, plan)
+        }
+
+        let orderings: [fn(String) -> ColumnOrdering; 2] = [
+            ColumnOrdering::asc_nulls_first,
+            ColumnOrdering::desc_nulls_last,
+        ];
+        for ordering in orderings {
+            // The last page is partial and the one after it is empty
+            for offset in [0, 21, 396, 400] {
+                let (i, s, plan) =
+                    page(&fixture.dataset, ordering("i".to_string()), offset, true).await;
+                assert!(plan.contains("SortedProjectionScan"), "{}", plan);
+                assert!(!plan.contains("SortExec"), "{}", plan);
+                let (expected_i, expected_s, _) =
+                    page(&fixture.dataset, ordering("i".to_string()), offset, false).await;
+                assert_eq!(i, expected_i, "offset {}", offset);
+                assert_eq!(s, expected_s, "offset {}", offset);
+            }
+        }
+
+        let (i, _, _) = page(
+            &fixture.dataset,
+            ColumnOrdering::desc_nulls_last("i".to_string()),
+            7,
+            true,
+        )
+        .await;
+        assert_eq!(i, (386..393).rev().collect::<Vec<_>>());
+
+        // Columns that aren't in the projection need a sort
+        let mut scan = fixture.dataset.scan();
+        scan.project(&["i", "vec"])
+            .unwrap()
+            .order_by(Some(vec![ColumnOrdering::asc_nulls_first("i".to_string())]))
+            .unwrap()
+            .use_sorted_projection("by_i");
+        let plan = scan.explain_plan(false).await.unwrap();
+        assert!(plan.contains("SortExec"), "{}", plan);
+
+        // The projection is stale once the dataset changes
+        fixture.append_new_data().await.unwrap();
+        let (_, _, plan) = page(
+            &fixture.dataset,
+            ColumnOrdering::asc_nulls_first("i".to_string()),
+            0,
+            true,
+        )
+        .await;
+        assert!(!plan.contains("SortedProjectionScan"), "{}", plan);
+        assert!(plan.contains("SortExec"), "{}", plan);
+
+        fixture
+            .dataset
+            .drop_sorted_projection("by_i")
+            .await
+            .unwrap();
+        let mut scan = fixture.dataset.scan();
+        scan.order_by(Some(vec![ColumnOrdering::asc_nulls_first("i".to_string())]))
+            .unwrap()
+            .use_sorted_projection("by_i");
+        assert!(scan.try_into_batch().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_count_plan() {
         // A count rows operation should load the minimal amount of data
@@ -5469,6 +7729,296 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_project_flattened() {
+        let meta_type = DataType::Struct(
+            vec![
+                ArrowField::new("lang", DataType::Utf8, true),
+                ArrowField::new("year", DataType::Int32, true),
+            ]
+            .into(),
+        );
+        let doc_type = DataType::Struct(
+            vec![
+                ArrowField::new("title", DataType::Utf8, true),
+                ArrowField::new("meta", meta_type, true),
+            ]
+            .into(),
+        );
+        let data = gen()
+            .col("doc", array::rand_type(&doc_type))
+            .col("id", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(100), BatchCount::from(2));
+        let dataset = Dataset::write(data, "memory://test", None).await.unwrap();
+
+        let full = dataset.scan().try_into_batch().await.unwrap();
+        let doc = full["doc"].as_struct();
+        let meta = doc.column_by_name("meta").unwrap().as_struct();
+
+        let flattened = dataset
+            .scan()
+            .project_flattened(&["id", "doc.title AS title", "doc.meta", "doc.meta AS m"])
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        let names = flattened
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "id",
+                "title",
+                "doc.meta.lang",
+                "doc.meta.year",
+                "m.lang",
+                "m.year"
+            ]
+        );
+        assert_eq!(&flattened["title"], doc.column_by_name("title").unwrap());
+        assert_eq!(
+            &flattened["doc.meta.lang"],
+            meta.column_by_name("lang").unwrap()
+        );
+        assert_eq!(&flattened["m.year"], meta.column_by_name("year").unwrap());
+
+        // A whole struct column is flattened all the way down
+        let flattened = dataset
+            .scan()
+            .project_flattened(&["doc"])
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        assert_eq!(flattened.num_columns(), 3);
+        assert!(flattened.column_by_name("doc.meta.year").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_distinct_on() {
+        let data = gen()
+            .col("a", array::cycle::<Int32Type>(vec![0, 1, 2, 3]))
+            .col("b", array::cycle_utf8_literals(&["x", "y"]))
+            .col("c", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(10));
+        let dataset = Dataset::write(data, "memory://test", None).await.unwrap();
+
+        async fn distinct(
+            dataset: &Dataset,
+            columns: &[&str],
+            filter: Option<&str>,
+            limit: Option<i64>,
+        ) -> Vec<(i32, String)> {
+            let mut scan = dataset.scan();
+            scan.distinct_on(columns)
+                .unwrap()
+                .limit(limit, None)
+                .unwrap()
+                // Deduplication runs within the memory budget
+                .memory_limit(1024 * 1024);
+            if let Some(filter) = filter {
+                scan.filter(filter).unwrap();
+            }
+            let batch = scan.try_into_batch().await.unwrap();
+            let mut rows = (0..batch.num_rows())
+                .map(|i| {
+                    let a = batch
+                        .column_by_name("a")
+                        .map(|a| a.as_primitive::<Int32Type>().value(i))
+                        .unwrap_or(-1);
+                    let b = batch
+                        .column_by_name("b")
+                        .map(|b| b.as_string::<i32>().value(i).to_string())
+                        .unwrap_or_default();
+                    (a, b)
+                })
+                .collect::<Vec<_>>();
+            rows.sort();
+            rows
+        }
+
+        let expected = vec![
+            (0, "x".to_string()),
+            (1, "y".to_string()),
+            (2, "x".to_string()),
+            (3, "y".to_string()),
+        ];
+        assert_eq!(distinct(&dataset, &["a", "b"], None, None).await, expected);
+        assert_eq!(
+            distinct(&dataset, &["b"], None, None).await,
+            vec![(-1, "x".to_string()), (-1, "y".to_string())]
+        );
+        assert_eq!(
+            distinct(&dataset, &["a", "b"], Some("a < 2"), None).await,
+            expected[..2].to_vec()
+        );
+        assert_eq!(distinct(&dataset, &["a"], None, Some(3)).await.len(), 3);
+
+        let mut scan = dataset.scan();
+        scan.distinct_on(&["a"]).unwrap().with_row_id();
+        let err = scan.try_into_batch().await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_count_rows_by(
+        #[values(IndexType::Bitmap, IndexType::BTree)] index_type: IndexType,
+    ) {
+        let data = gen()
+            .col(
+                "a",
+                array::cycle::<Int32Type>(vec![0, 1, 2, 3]).with_nulls(&[false, false, true]),
+            )
+            .col("c", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(10));
+        let mut dataset = Dataset::write(
+            data,
+            "memory://test",
+            Some(WriteParams {
+                max_rows_per_file: 2000,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        dataset
+            .create_index(
+                &["a"],
+                index_type,
+                None,
+                &ScalarIndexParams::default(),
+                true,
+            )
+            .await
+            .unwrap();
+        dataset.delete("c < 100").await.unwrap();
+
+        async fn count_by(
+            dataset: &Dataset,
+            filter: Option<&str>,
+            use_index: bool,
+        ) -> (Vec<(Option<i32>, i64)>, String) {
+            let mut scan = dataset.scan();
+            scan.count_rows_by("a").unwrap().use_scalar_index(use_index);
+            if let Some(filter) = filter {
+                scan.filter(filter).unwrap();
+            }
+            let plan = scan.explain_plan(false).await.unwrap();
+            let batch = scan.try_into_batch().await.unwrap();
+            assert_eq!(batch.schema().field(1).name(), "count_rows");
+            let values = batch["a"].as_primitive::<Int32Type>();
+            let counts = batch["count_rows"].as_primitive::<Int64Type>();
+            let mut groups = values
+                .iter()
+                .zip(counts.values().iter().copied())
+                .collect::<Vec<_>>();
+            groups.sort();
+            (groups, plan)
+        }
+
+        let (groups, plan) = count_by(&dataset, None, true).await;
+        assert!(plan.contains("IndexValueCount"), "{}", plan);
+        let (expected, fallback_plan) = count_by(&dataset, None, false).await;
+        assert!(fallback_plan.contains("AggregateExec"), "{}", fallback_plan);
+        assert_eq!(groups, expected);
+        assert_eq!(groups.iter().map(|(_, count)| count).sum::<i64>(), 9900);
+        assert_eq!(groups[0].0, None);
+
+        // Filters are applied by scanning and grouping
+        let (groups, plan) = count_by(&dataset, Some("c >= 500"), true).await;
+        assert!(plan.contains("AggregateExec"), "{}", plan);
+        assert_eq!(groups.iter().map(|(_, count)| count).sum::<i64>(), 9500);
+
+        // New data is not covered by the index so it is scanned and grouped
+        let data = gen()
+            .col("a", array::cycle::<Int32Type>(vec![7]))
+            .col("c", array::step::<Int32Type>())
+            .into_reader_rows(RowCount::from(10), BatchCount::from(1));
+        dataset.append(data, None).await.unwrap();
+        let (groups, plan) = count_by(&dataset, None, true).await;
+        assert!(plan.contains("AggregateExec"), "{}", plan);
+        assert!(groups.contains(&(Some(7), 10)));
+    }
+
+    #[tokio::test]
+    async fn test_project_list_struct_leaf() {
+        let item_fields = vec![
+            ArrowField::new("a", DataType::Int32, true),
+            ArrowField::new("b", DataType::Utf8, true),
+        ];
+        let docs_type = DataType::List(Arc::new(ArrowField::new(
+            "item",
+            DataType::Struct(item_fields.into()),
+            true,
+        )));
+        let data = gen()
+            .col("docs", array::rand_type(&docs_type))
+            .into_reader_rows(RowCount::from(1000), BatchCount::from(10));
+
+        let (io_stats_wrapper, io_stats) = IoTrackingStore::new_wrapper();
+        let dataset = Dataset::write(
+            data,
+            "memory://test",
+            Some(WriteParams {
+                store_params: Some(ObjectStoreParams {
+                    object_store_wrapper: Some(io_stats_wrapper),
+                    ..Default::default()
+                }),
+                data_storage_version: Some(LanceFileVersion::V2_1),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let get_bytes = || io_stats.lock().unwrap().read_bytes;
+
+        let start_bytes = get_bytes();
+        let projected = dataset
+            .scan()
+            .project(&["docs.item.a"])
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        let projected_bytes = get_bytes() - start_bytes;
+
+        let start_bytes = get_bytes();
+        let full = dataset
+            .scan()
+            .project(&["docs"])
+            .unwrap()
+            .try_into_batch()
+            .await
+            .unwrap();
+        let full_bytes = get_bytes() - start_bytes;
+
+        // The pages of `b` are never read
+        assert!(projected_bytes < full_bytes);
+
+        // Each row gets the values of `a` in its items, and nothing else
+        let full_docs = full.column_by_name("docs").unwrap().as_list::<i32>();
+        let docs = projected
+            .column_by_name("docs.item.a")
+            .unwrap()
+            .as_list::<i32>();
+        assert_eq!(
+            docs.data_type(),
+            &DataType::List(Arc::new(ArrowField::new("item", DataType::Int32, true)))
+        );
+        assert_eq!(docs.len(), full_docs.len());
+        for row in 0..docs.len() {
+            assert_eq!(docs.is_null(row), full_docs.is_null(row));
+            assert_eq!(docs.value_length(row), full_docs.value_length(row));
+        }
+        let full_values = full_docs.values().as_struct().column_by_name("a").unwrap();
+        assert_eq!(docs.values(), full_values);
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_plans(
@@ -5569,6 +8119,40 @@ mod test {
         )
         .await?;
 
+        assert_plan_equals(
+            &dataset.dataset,
+            |scan| {
+                scan.use_stats(false)
+                    .materialization_style(
+                        MaterializationStyle::all_late_except(&["vec"], lance_schema).unwrap(),
+                    )
+                    .filter("s IS NOT NULL")
+            },
+            "ProjectionExec: expr=[i@3 as i, s@0 as s, vec@1 as vec]
+  Take: columns=\"s, vec, _rowid, (i)\"
+    CoalesceBatchesExec: target_batch_size=8192
+      FilterExec: s@0 IS NOT NULL
+        LanceScan: uri..., projection=[s, vec], row_id=true, row_addr=false, ordered=true",
+        )
+        .await?;
+
+        // With half of the rows passing the filter, even the vector column is worth
+        // fetching early
+        assert_plan_equals(
+            &dataset.dataset,
+            |scan| {
+                scan.use_stats(false)
+                    .materialization_style(MaterializationStyle::expected_selectivity(0.5)?)
+                    .filter("s IS NOT NULL")
+            },
+            "ProjectionExec: expr=[i@0 as i, s@1 as s, vec@2 as vec]
+  FilterExec: s@1 IS NOT NULL
+    LanceScan: uri..., projection=[i, s, vec], row_id=true, row_addr=false, ordered=true",
+        )
+        .await?;
+
+        assert!(MaterializationStyle::expected_selectivity(1.5).is_err());
+
         assert_plan_equals(
             &dataset.dataset,
             |scan| Ok(scan.project(&["s"])?.with_row_id().scan_in_order(false)),
@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Sorted projections
+//!
+//! A sorted projection is a copy of some of the columns of a dataset, sorted by one or more
+//! of those columns.  Paginated queries (e.g. `ORDER BY ts DESC LIMIT 50 OFFSET n`) can read
+//! their page straight from the projection instead of sorting the dataset on every request,
+//! see [`crate::dataset::scanner::Scanner::use_sorted_projection`].
+//!
+//! A projection is stored as a single Lance file, with the projected columns and the row ids,
+//! in `_sorted_projections/<name>`.  Projections are not versioned with the dataset.  Instead,
+//! a projection records the fragments it was built from and is only used while the dataset
+//! still has exactly those fragments.  Any append, delete, update or compaction makes the
+//! projection stale until it is recreated.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use lance_core::{Error, Result, ROW_ID};
+use lance_index::scalar::{lance_format::LanceIndexStore, IndexReader, IndexStore};
+use lance_io::stream::RecordBatchStream;
+use lance_table::format::Fragment;
+use object_store::path::Path;
+use serde::{Deserialize, Serialize};
+use snafu::location;
+
+use super::scanner::ColumnOrdering;
+use super::Dataset;
+
+/// The directory, relative to the dataset root, where sorted projections are stored
+pub const SORTED_PROJECTIONS_DIR: &str = "_sorted_projections";
+
+const DATA_FILE_NAME: &str = "data.lance";
+const ORDERING_META_KEY: &str = "lance:sorted_projection:ordering";
+const FRAGMENTS_META_KEY: &str = "lance:sorted_projection:fragments";
+
+/// Parameters for [`Dataset::create_sorted_projection`]
+#[derive(Debug, Clone)]
+pub struct SortedProjectionParams {
+    /// The order of the rows in the projection
+    pub ordering: Vec<ColumnOrdering>,
+    /// The columns to store in the projection, in addition to the ordering columns
+    pub columns: Vec<String>,
+}
+
+impl SortedProjectionParams {
+    pub fn new(ordering: Vec<ColumnOrdering>, columns: Vec<String>) -> Self {
+        Self { ordering, columns }
+    }
+}
+
+/// The serialized form of a [`ColumnOrdering`]
+#[derive(Serialize, Deserialize)]
+struct StoredOrdering {
+    column: String,
+    ascending: bool,
+    nulls_first: bool,
+}
+
+/// A sorted projection that has been opened for reading
+pub struct SortedProjection {
+    name: String,
+    ordering: Vec<ColumnOrdering>,
+    fragments: Vec<Fragment>,
+    reader: Arc<dyn IndexReader>,
+}
+
+impl std::fmt::Debug for SortedProjection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SortedProjection")
+            .field("name", &self.name)
+            .field("ordering", &self.ordering)
+            .field("num_rows", &self.num_rows())
+            .finish()
+    }
+}
+
+impl SortedProjection {
+    fn store(dataset: &Dataset, name: &str) -> LanceIndexStore {
+        LanceIndexStore::new(
+            dataset.object_store.clone(),
+            dataset.sorted_projection_dir(name),
+            dataset.metadata_cache.clone(),
+        )
+    }
+
+    /// Open the sorted projection called `name`
+    pub async fn open(dataset: &Dataset, name: &str) -> Result<Self> {
+        let data_path = dataset.sorted_projection_dir(name).child(DATA_FILE_NAME);
+        if !dataset.object_store.exists(&data_path).await? {
+            return Err(Error::invalid_input(
+                format!("Sorted projection {} does not exist", name),
+                location!(),
+            ));
+        }
+        let reader = Self::store(dataset, name)
+            .open_index_file(DATA_FILE_NAME)
+            .await?;
+        let metadata = &reader.schema().metadata;
+        let get_metadata = |key: &str| {
+            metadata.get(key).ok_or_else(|| Error::Internal {
+                message: format!("Sorted projection {} is missing the {} metadata", name, key),
+                location: location!(),
+            })
+        };
+        let ordering =
+            serde_json::from_str::<Vec<StoredOrdering>>(get_metadata(ORDERING_META_KEY)?)?
+                .into_iter()
+                .map(|ordering| ColumnOrdering {
+                    ascending: ordering.ascending,
+                    nulls_first: ordering.nulls_first,
+                    column_name: ordering.column,
+                })
+                .collect();
+        let fragments = serde_json::from_str(get_metadata(FRAGMENTS_META_KEY)?)?;
+        Ok(Self {
+            name: name.to_string(),
+            ordering,
+            fragments,
+            reader,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The order of the rows in the projection
+    pub fn ordering(&self) -> &[ColumnOrdering] {
+        &self.ordering
+    }
+
+    /// The columns stored in the projection, including the row id
+    pub fn columns(&self) -> Vec<&str> {
+        self.reader
+            .schema()
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect()
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.reader.num_rows() as u64
+    }
+
+    /// True if the dataset has not changed since the projection was created
+    pub fn is_current(&self, dataset: &Dataset) -> bool {
+        self.fragments.as_slice() == dataset.fragments().as_slice()
+    }
+
+    /// How to read the projection to get the rows in `ordering`
+    ///
+    /// Returns `Some(false)` if the projection is in `ordering`, `Some(true)` if it has to be
+    /// read backwards (`ordering` is the exact reverse of the projection's ordering, which
+    /// flips both the direction and the placement of nulls) and `None` otherwise.
+    pub(crate) fn read_direction(&self, ordering: &[ColumnOrdering]) -> Option<bool> {
+        if ordering.len() != self.ordering.len() {
+            return None;
+        }
+        let same_columns = ordering
+            .iter()
+            .zip(&self.ordering)
+            .all(|(lhs, rhs)| lhs.column_name == rhs.column_name);
+        if !same_columns {
+            return None;
+        }
+        if ordering
+            .iter()
+            .zip(&self.ordering)
+            .all(|(lhs, rhs)| lhs.ascending == rhs.ascending && lhs.nulls_first == rhs.nulls_first)
+        {
+            Some(false)
+        } else if ordering
+            .iter()
+            .zip(&self.ordering)
+            .all(|(lhs, rhs)| lhs.ascending != rhs.ascending && lhs.nulls_first != rhs.nulls_first)
+        {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn reader(&self) -> &Arc<dyn IndexReader> {
+        &self.reader
+    }
+}
+
+impl Dataset {
+    pub(crate) fn sorted_projection_dir(&self, name: &str) -> Path {
+        self.base.child(SORTED_PROJECTIONS_DIR).child(name)
+    }
+
+    /// Create a sorted projection called `name`, replacing any existing projection with that
+    /// name
+    ///
+    /// The projection stores the ordering columns and `params.columns`, sorted by
+    /// `params.ordering`.  It is only used while the dataset is unchanged, so it should be
+    /// recreated after the dataset is modified.
+    pub async fn create_sorted_projection(
+        &self,
+        name: &str,
+        params: &SortedProjectionParams,
+    ) -> Result<()> {
+        if params.ordering.is_empty() {
+            return Err(Error::invalid_input(
+                "A sorted projection needs at least one ordering column",
+                location!(),
+            ));
+        }
+        let mut columns = params
+            .ordering
+            .iter()
+            .map(|ordering| ordering.column_name.as_str())
+            .collect::<Vec<_>>();
+        for column in &params.columns {
+            if column == ROW_ID {
+                continue;
+            }
+            if !columns.contains(&column.as_str()) {
+                columns.push(column);
+            }
+        }
+
+        let mut scanner = self.scan();
+        scanner
+            .project(&columns)?
+            .with_row_id()
+            .order_by(Some(params.ordering.clone()))?;
+        let mut batches = scanner.try_into_stream().await?;
+
+        let store = SortedProjection::store(self, name);
+        let mut writer = store
+            .new_index_file(DATA_FILE_NAME, batches.schema())
+            .await?;
+        while let Some(batch) = batches.try_next().await? {
+            writer.write_record_batch(batch).await?;
+        }
+
+        let ordering = params
+            .ordering
+            .iter()
+            .map(|ordering| StoredOrdering {
+                column: ordering.column_name.clone(),
+                ascending: ordering.ascending,
+                nulls_first: ordering.nulls_first,
+            })
+            .collect::<Vec<_>>();
+        let metadata = HashMap::from([
+            (
+                ORDERING_META_KEY.to_string(),
+                serde_json::to_string(&ordering)?,
+            ),
+            (
+                FRAGMENTS_META_KEY.to_string(),
+                serde_json::to_string(self.fragments().as_slice())?,
+            ),
+        ]);
+        writer.finish_with_metadata(metadata).await
+    }
+
+    /// Delete the sorted projection called `name`
+    pub async fn drop_sorted_projection(&self, name: &str) -> Result<()> {
+        self.object_store
+            .remove_dir_all(self.sorted_projection_dir(name))
+            .await
+    }
+}
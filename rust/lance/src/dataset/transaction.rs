@@ -44,12 +44,18 @@
 //! 3️⃣ DataReplacement on a column without index is compatible with any operation AS LONG AS
 //! the operation does not modify the region of the column being replaced.
 //!
+//! EnableStableRowIds assigns row ids to whatever fragments exist when it is
+//! committed, so it is compatible with concurrent changes to the fragment list.
+//! It conflicts with index creation, overwrite and restore. Operations that
+//! carry fragments read before the migration must retry.
+//!
 
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
 
+use super::history::TRANSACTION_ID_KEY;
 use super::ManifestWriteConfig;
 use crate::utils::temporal::timestamp_to_nanos;
 use deepsize::DeepSizeOf;
@@ -91,6 +97,11 @@ pub struct Transaction {
     /// If this is `None`, then the blobs dataset was not modified
     pub blobs_op: Option<Operation>,
     pub tag: Option<String>,
+    /// Arbitrary key-value properties, such as a job id, git sha, or author.
+    ///
+    /// These are persisted in the manifest of the version created by this
+    /// transaction and are returned by [`crate::Dataset::versions`].
+    pub transaction_properties: Option<Arc<HashMap<String, String>>>,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -216,6 +227,14 @@ pub enum Operation {
         schema_metadata: Option<HashMap<String, String>>,
         field_metadata: Option<HashMap<u32, HashMap<String, String>>>,
     },
+
+    /// Assign stable row ids to every fragment of a dataset that was created
+    /// without them. Only fragment metadata is changed, data files are not
+    /// rewritten.
+    ///
+    /// Indices store row addresses rather than row ids, so this is rejected
+    /// while the dataset has any indices.
+    EnableStableRowIds,
 }
 
 impl std::fmt::Display for Operation {
@@ -233,6 +252,7 @@ impl std::fmt::Display for Operation {
             Self::Project { .. } => write!(f, "Project"),
             Self::UpdateConfig { .. } => write!(f, "UpdateConfig"),
             Self::DataReplacement { .. } => write!(f, "DataReplacement"),
+            Self::EnableStableRowIds => write!(f, "EnableStableRowIds"),
         }
     }
 }
@@ -373,6 +393,7 @@ impl PartialEq for Operation {
                 Self::DataReplacement { replacements: a },
                 Self::DataReplacement { replacements: b },
             ) => a.len() == b.len() && a.iter().all(|r| b.contains(r)),
+            (Self::EnableStableRowIds, Self::EnableStableRowIds) => true,
             // Handle all remaining combinations.
             // We spell out all combinations explicitly to prevent
             // us accidentally handling a new case in the wrong way.
@@ -783,6 +804,80 @@ impl PartialEq for Operation {
             (Self::DataReplacement { .. }, Self::UpdateConfig { .. }) => {
                 std::mem::discriminant(self) == std::mem::discriminant(other)
             }
+
+            (Self::Append { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Delete { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Overwrite { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::CreateIndex { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Rewrite { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Merge { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Restore { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::ReserveFragments { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Update { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::Project { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::UpdateConfig { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::DataReplacement { .. }, Self::EnableStableRowIds) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+
+            (Self::EnableStableRowIds, Self::Append { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Delete { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Overwrite { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::CreateIndex { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Rewrite { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Merge { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Restore { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::ReserveFragments { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Update { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::Project { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::UpdateConfig { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
+            (Self::EnableStableRowIds, Self::DataReplacement { .. }) => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+            }
         }
     }
 }
@@ -910,6 +1005,7 @@ impl Operation {
             Self::Project { .. } => "Project",
             Self::UpdateConfig { .. } => "UpdateConfig",
             Self::DataReplacement { .. } => "DataReplacement",
+            Self::EnableStableRowIds => "EnableStableRowIds",
         }
     }
 }
@@ -923,6 +1019,7 @@ impl Transaction {
             operation,
             blobs_op: None,
             tag: None,
+            transaction_properties: None,
         }
     }
 
@@ -930,6 +1027,44 @@ impl Transaction {
         Self { blobs_op, ..self }
     }
 
+    pub fn with_transaction_properties(
+        self,
+        transaction_properties: Option<Arc<HashMap<String, String>>>,
+    ) -> Self {
+        Self {
+            transaction_properties,
+            ..self
+        }
+    }
+
+    /// Make the commit of this transaction idempotent with a unique id, e.g.
+    /// the id of the job step that writes it.
+    ///
+    /// If a transaction with the same id committed one of the latest 100 versions,
+    /// or a version since the read version of this one, committing this one is a
+    /// no-op that returns the version of the earlier commit. This prevents
+    /// duplicate writes when a step is retried after its commit succeeded, even
+    /// if the retry reopened the dataset. The id is stored in the transaction
+    /// properties, which are persisted in the manifest.
+    pub fn with_transaction_id(mut self, transaction_id: impl Into<String>) -> Self {
+        let mut properties = self
+            .transaction_properties
+            .as_deref()
+            .cloned()
+            .unwrap_or_default();
+        properties.insert(TRANSACTION_ID_KEY.to_string(), transaction_id.into());
+        self.transaction_properties = Some(Arc::new(properties));
+        self
+    }
+
+    /// The id given with [`Self::with_transaction_id`], if any.
+    pub fn transaction_id(&self) -> Option<&str> {
+        self.transaction_properties
+            .as_ref()?
+            .get(TRANSACTION_ID_KEY)
+            .map(String::as_str)
+    }
+
     pub fn new(
         read_version: u64,
         operation: Operation,
@@ -943,9 +1078,17 @@ impl Transaction {
             operation,
             blobs_op,
             tag,
+            transaction_properties: None,
         }
     }
 
+    fn properties_for_manifest(&self) -> HashMap<String, String> {
+        self.transaction_properties
+            .as_ref()
+            .map(|props| props.as_ref().clone())
+            .unwrap_or_default()
+    }
+
     fn fragments_with_ids<'a, T>(
         new_fragments: T,
         fragment_id: &'a mut u64,
@@ -986,6 +1129,7 @@ impl Transaction {
     }
 
     pub(crate) async fn restore_old_manifest(
+        &self,
         object_store: &ObjectStore,
         commit_handler: &dyn CommitHandler,
         base_path: &Path,
@@ -999,6 +1143,7 @@ impl Transaction {
         let mut manifest = read_manifest(object_store, &location.path, location.size).await?;
         manifest.set_timestamp(timestamp_to_nanos(config.timestamp));
         manifest.transaction_file = Some(tx_path.to_string());
+        manifest.transaction_properties = self.properties_for_manifest();
         let indices = read_manifest_indexes(object_store, &location, &manifest).await?;
         Ok((manifest, indices))
     }
@@ -1015,6 +1160,7 @@ impl Transaction {
         new_blob_version: Option<u64>,
     ) -> Result<(Manifest, Vec<Index>)> {
         if config.use_move_stable_row_ids
+            && !matches!(self.operation, Operation::EnableStableRowIds)
             && current_manifest
                 .map(|m| !m.uses_move_stable_row_ids())
                 .unwrap_or_default()
@@ -1056,6 +1202,15 @@ impl Transaction {
         let mut next_row_id = {
             // Only use row ids if the feature flag is set already or
             match (current_manifest, config.use_move_stable_row_ids) {
+                (Some(manifest), _) if matches!(self.operation, Operation::EnableStableRowIds) => {
+                    if manifest.uses_move_stable_row_ids() {
+                        return Err(Error::invalid_input(
+                            "Dataset already uses stable row ids",
+                            location!(),
+                        ));
+                    }
+                    Some(0)
+                }
                 (Some(manifest), _)
                     if manifest.reader_feature_flags & FLAG_MOVE_STABLE_ROW_IDS != 0 =>
                 {
@@ -1341,6 +1496,22 @@ impl Transaction {
 
                 final_fragments.extend(unmodified_fragments);
             }
+            Operation::EnableStableRowIds => {
+                if !final_indices.is_empty() {
+                    return Err(Error::invalid_input(
+                        format!(
+                            "Cannot enable stable row ids on a dataset with indices, found {} index(es). Drop the indices first and recreate them afterwards.",
+                            final_indices.len()
+                        ),
+                        location!(),
+                    ));
+                }
+                let mut fragments = maybe_existing_fragments?.clone();
+                if let Some(next_row_id) = &mut next_row_id {
+                    Self::assign_row_ids(next_row_id, fragments.as_mut_slice())?;
+                }
+                final_fragments.extend(fragments);
+            }
         };
 
         // If a fragment was reserved then it may not belong at the end of the fragments list.
@@ -1382,6 +1553,7 @@ impl Transaction {
         };
 
         manifest.tag.clone_from(&self.tag);
+        manifest.transaction_properties = self.properties_for_manifest();
 
         if config.auto_set_feature_flags {
             apply_feature_flags(&mut manifest, config.use_move_stable_row_ids)?;
@@ -1832,6 +2004,9 @@ impl TryFrom<pb::Transaction> for Transaction {
                     .map(DataReplacementGroup::try_from)
                     .collect::<Result<Vec<_>>>()?,
             },
+            Some(pb::transaction::Operation::EnableStableRowIds(
+                pb::transaction::EnableStableRowIds {},
+            )) => Operation::EnableStableRowIds,
             None => {
                 return Err(Error::Internal {
                     message: "Transaction message did not contain an operation".to_string(),
@@ -1883,6 +2058,11 @@ impl TryFrom<pb::Transaction> for Transaction {
             } else {
                 Some(message.tag.clone())
             },
+            transaction_properties: if message.transaction_properties.is_empty() {
+                None
+            } else {
+                Some(Arc::new(message.transaction_properties.clone()))
+            },
         })
     }
 }
@@ -2059,6 +2239,9 @@ impl From<&Transaction> for pb::Transaction {
                         .collect(),
                 })
             }
+            Operation::EnableStableRowIds => pb::transaction::Operation::EnableStableRowIds(
+                pb::transaction::EnableStableRowIds {},
+            ),
         };
 
         let blob_operation = value.blobs_op.as_ref().map(|op| match op {
@@ -2090,6 +2273,11 @@ impl From<&Transaction> for pb::Transaction {
             operation: Some(operation),
             blob_operation,
             tag: value.tag.clone().unwrap_or("".to_string()),
+            transaction_properties: value
+                .transaction_properties
+                .as_ref()
+                .map(|props| props.as_ref().clone())
+                .unwrap_or_default(),
         }
     }
 }
@@ -0,0 +1,614 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Consistency checks between a dataset's manifest and its files.
+//!
+//! [`Dataset::validate`] fails on the first problem it finds. [`verify_dataset`]
+//! instead walks the whole version and collects every problem into a
+//! [`VerifyReport`], so that a damaged dataset can be assessed (and repaired)
+//! in one pass. The following is checked:
+//!
+//! * Every data file, deletion file and index directory referenced by the
+//!   manifest exists.
+//! * Data files have the size recorded in the manifest.
+//! * Data files have the number of rows recorded in the manifest, and deletion
+//!   files have the recorded number of deleted rows.
+//! * Optionally, the pages and column metadata of data files match their
+//!   checksums. Checksums are only recorded when enabled with
+//!   [`WriteParams::checksums`](crate::dataset::WriteParams::checksums), files
+//!   without them only get the size check.
+//! * No deletion files exist that this version does not reference.
+//! * Every fragment is covered by each index.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use lance_core::cache::LanceCache;
+use lance_core::{Error, Result};
+use lance_encoding::decoder::{DecoderPlugins, FilterExpression};
+use lance_file::v2::reader::{FileReader, FileReaderOptions};
+use lance_index::frag_reuse::FRAG_REUSE_INDEX_NAME;
+use lance_index::DatasetIndexExt;
+use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
+use lance_io::ReadBatchParams;
+use lance_table::format::DataFile;
+use lance_table::io::deletion::deletion_file_path;
+use object_store::path::Path;
+use object_store::ObjectStore as _;
+use roaring::RoaringBitmap;
+use uuid::Uuid;
+
+use super::fragment::FileFragment;
+use crate::Dataset;
+
+/// Options to be passed to [verify_dataset].
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// Open every data file and deletion file to compare row counts with the
+    /// manifest. This reads the footer of every data file. Defaults to true.
+    pub check_row_counts: bool,
+    /// List the deletion directory for files this version does not reference.
+    /// Defaults to true.
+    pub check_orphaned_files: bool,
+    /// Read every data file in full and verify the checksums of its pages and
+    /// column metadata. This reads all of the data. Files written without
+    /// checksums are not checked. Defaults to false.
+    pub check_checksums: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            check_row_counts: true,
+            check_orphaned_files: true,
+            check_checksums: false,
+        }
+    }
+}
+
+/// How serious a [VerifyIssue] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VerifySeverity {
+    /// The dataset is readable, but not in its best shape.
+    Warning,
+    /// Some data can not be read, or may be read incorrectly.
+    Error,
+}
+
+/// A single problem found by [verify_dataset].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyIssue {
+    /// A data file referenced by the manifest does not exist.
+    MissingDataFile { fragment_id: u64, path: Path },
+    /// A data file does not have the size recorded in the manifest.
+    FileSizeMismatch {
+        fragment_id: u64,
+        path: Path,
+        expected: u64,
+        actual: u64,
+    },
+    /// A data file does not have the number of rows recorded in the manifest.
+    RowCountMismatch {
+        fragment_id: u64,
+        path: Path,
+        expected: usize,
+        actual: usize,
+    },
+    /// The content of a data file does not match its checksums.
+    ChecksumMismatch {
+        fragment_id: u64,
+        path: Path,
+        message: String,
+    },
+    /// The data or deletion files of a fragment could not be read.
+    UnreadableFragment { fragment_id: u64, message: String },
+    /// A deletion file referenced by the manifest does not exist.
+    MissingDeletionFile { fragment_id: u64, path: Path },
+    /// A deletion file does not contain the number of deleted rows recorded in
+    /// the manifest.
+    DeletionCountMismatch {
+        fragment_id: u64,
+        path: Path,
+        expected: usize,
+        actual: usize,
+    },
+    /// A deletion file that is not referenced by this version.
+    ///
+    /// Older versions may still reference it, so this is only a warning.
+    OrphanedDeletionFile { path: Path },
+    /// The files of an index referenced by the manifest do not exist.
+    MissingIndexFiles { index_name: String, uuid: Uuid },
+    /// Fragments that are not covered by an index.
+    IndexCoverageGap {
+        index_name: String,
+        fragment_ids: Vec<u64>,
+    },
+}
+
+impl VerifyIssue {
+    pub fn severity(&self) -> VerifySeverity {
+        match self {
+            Self::OrphanedDeletionFile { .. } | Self::IndexCoverageGap { .. } => {
+                VerifySeverity::Warning
+            }
+            _ => VerifySeverity::Error,
+        }
+    }
+
+    /// A suggestion on how to repair the dataset.
+    pub fn repair_suggestion(&self) -> &'static str {
+        match self {
+            Self::MissingDataFile { .. } | Self::UnreadableFragment { .. } => {
+                "Restore the file from a backup, or check out an earlier version \
+                 and restore it. If the data is lost, delete the fragment."
+            }
+            Self::FileSizeMismatch { .. }
+            | Self::RowCountMismatch { .. }
+            | Self::ChecksumMismatch { .. } => {
+                "The file was modified or truncated after it was written. Restore \
+                 it from a backup, or check out an earlier version and restore it."
+            }
+            Self::MissingDeletionFile { .. } | Self::DeletionCountMismatch { .. } => {
+                "Restore the deletion file from a backup, or check out an earlier \
+                 version and re-apply the deletes."
+            }
+            Self::OrphanedDeletionFile { .. } => {
+                "If no retained version references the file, it is left over from \
+                 a failed write and will be removed by cleanup_old_versions."
+            }
+            Self::MissingIndexFiles { .. } => "Drop the index and create it again.",
+            Self::IndexCoverageGap { .. } => {
+                "Run optimize_indices to add the new fragments to the index."
+            }
+        }
+    }
+}
+
+/// The result of [verify_dataset].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// The version that was verified.
+    pub version: u64,
+    /// The number of fragments that were checked.
+    pub num_fragments: usize,
+    /// Every problem found, in the order it was found.
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    /// Whether no issues of [VerifySeverity::Error] were found.
+    pub fn is_healthy(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Issues of [VerifySeverity::Error].
+    pub fn errors(&self) -> impl Iterator<Item = &VerifyIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == VerifySeverity::Error)
+    }
+
+    /// Issues of [VerifySeverity::Warning].
+    pub fn warnings(&self) -> impl Iterator<Item = &VerifyIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == VerifySeverity::Warning)
+    }
+}
+
+/// Check the checked-out version of a dataset against the files in storage.
+///
+/// Problems with the dataset are returned in the report. An error is only
+/// returned if the storage itself can not be accessed.
+pub async fn verify_dataset(dataset: &Dataset, options: &VerifyOptions) -> Result<VerifyReport> {
+    let fragments = dataset.get_fragments();
+    let num_fragments = fragments.len();
+
+    let mut issues = stream::iter(fragments)
+        .map(|fragment| verify_fragment(fragment, options))
+        .buffered(dataset.object_store.io_parallelism())
+        .try_concat()
+        .await?;
+
+    if options.check_orphaned_files {
+        issues.extend(find_orphaned_deletion_files(dataset).await?);
+    }
+    issues.extend(verify_indices(dataset).await?);
+
+    Ok(VerifyReport {
+        version: dataset.manifest.version,
+        num_fragments,
+        issues,
+    })
+}
+
+/// The size of a file, or `None` if it does not exist.
+async fn file_size(dataset: &Dataset, path: &Path) -> Result<Option<u64>> {
+    match dataset.object_store.inner.head(path).await {
+        Ok(meta) => Ok(Some(meta.size)),
+        Err(object_store::Error::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn verify_fragment(
+    fragment: FileFragment,
+    options: &VerifyOptions,
+) -> Result<Vec<VerifyIssue>> {
+    let dataset = fragment.dataset();
+    let metadata = fragment.metadata();
+    let fragment_id = metadata.id;
+    let mut issues = Vec::new();
+
+    let mut all_files_exist = true;
+    for data_file in &metadata.files {
+        let path = dataset.data_dir().child(data_file.path.as_str());
+        match file_size(dataset, &path).await? {
+            None => {
+                all_files_exist = false;
+                issues.push(VerifyIssue::MissingDataFile { fragment_id, path });
+            }
+            Some(actual) => {
+                if let Some(expected) = data_file.file_size_bytes.get() {
+                    if expected.get() != actual {
+                        issues.push(VerifyIssue::FileSizeMismatch {
+                            fragment_id,
+                            path,
+                            expected: expected.get(),
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if options.check_row_counts && all_files_exist {
+        match fragment.data_file_lengths().await {
+            Ok(lengths) => {
+                if let Some(expected) = metadata.physical_rows {
+                    for (data_file, actual) in metadata.files.iter().zip(lengths) {
+                        if actual != expected {
+                            issues.push(VerifyIssue::RowCountMismatch {
+                                fragment_id,
+                                path: dataset.data_dir().child(data_file.path.as_str()),
+                                expected,
+                                actual,
+                            });
+                        }
+                    }
+                }
+            }
+            Err(err) => issues.push(VerifyIssue::UnreadableFragment {
+                fragment_id,
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    if options.check_checksums && all_files_exist {
+        for data_file in &metadata.files {
+            if data_file.is_legacy_file() {
+                continue;
+            }
+            let path = dataset.data_dir().child(data_file.path.as_str());
+            match verify_checksums(dataset, data_file, &path).await {
+                Ok(()) => {}
+                Err(Error::CorruptFile { source, .. }) => {
+                    issues.push(VerifyIssue::ChecksumMismatch {
+                        fragment_id,
+                        path,
+                        message: source.to_string(),
+                    })
+                }
+                Err(err) => issues.push(VerifyIssue::UnreadableFragment {
+                    fragment_id,
+                    message: err.to_string(),
+                }),
+            }
+        }
+    }
+
+    if let Some(deletion_file) = &metadata.deletion_file {
+        let path = deletion_file_path(&dataset.base, fragment_id, deletion_file);
+        if file_size(dataset, &path).await?.is_none() {
+            issues.push(VerifyIssue::MissingDeletionFile { fragment_id, path });
+        } else if let (true, Some(expected)) =
+            (options.check_row_counts, deletion_file.num_deleted_rows)
+        {
+            match fragment.get_deletion_vector().await {
+                Ok(deletion_vector) => {
+                    let actual = deletion_vector.map(|dv| dv.len()).unwrap_or(0);
+                    if actual != expected {
+                        issues.push(VerifyIssue::DeletionCountMismatch {
+                            fragment_id,
+                            path,
+                            expected,
+                            actual,
+                        });
+                    }
+                }
+                Err(err) => issues.push(VerifyIssue::UnreadableFragment {
+                    fragment_id,
+                    message: err.to_string(),
+                }),
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Read all of a data file, verifying the checksums recorded in it.
+async fn verify_checksums(dataset: &Dataset, data_file: &DataFile, path: &Path) -> Result<()> {
+    let scheduler = ScanScheduler::new(
+        dataset.object_store.clone(),
+        SchedulerConfig::max_bandwidth(&dataset.object_store),
+    );
+    let file_scheduler = scheduler
+        .open_file(path, &data_file.file_size_bytes)
+        .await?;
+    let reader = FileReader::try_open(
+        file_scheduler,
+        None,
+        Arc::<DecoderPlugins>::default(),
+        &LanceCache::no_cache(),
+        FileReaderOptions::default().with_verify_checksums(true),
+    )
+    .await?;
+    let mut batches = reader.read_stream(
+        ReadBatchParams::RangeFull,
+        1024,
+        16,
+        FilterExpression::no_filter(),
+    )?;
+    while batches.try_next().await?.is_some() {}
+    Ok(())
+}
+
+async fn find_orphaned_deletion_files(dataset: &Dataset) -> Result<Vec<VerifyIssue>> {
+    let referenced = dataset
+        .manifest
+        .fragments
+        .iter()
+        .filter_map(|fragment| {
+            fragment
+                .deletion_file
+                .as_ref()
+                .map(|deletion_file| deletion_file_path(&dataset.base, fragment.id, deletion_file))
+        })
+        .collect::<HashSet<_>>();
+
+    dataset
+        .object_store
+        .read_dir_all(&dataset.base.child("_deletions"), None)
+        .try_filter_map(|meta| {
+            let issue = (!referenced.contains(&meta.location)).then_some(
+                VerifyIssue::OrphanedDeletionFile {
+                    path: meta.location,
+                },
+            );
+            futures::future::ready(Ok(issue))
+        })
+        .try_collect()
+        .await
+}
+
+async fn verify_indices(dataset: &Dataset) -> Result<Vec<VerifyIssue>> {
+    let indices = dataset.load_indices().await?;
+    let mut issues = Vec::new();
+
+    // An index can be made of several deltas, which together cover the dataset.
+    let mut coverage: BTreeMap<&str, Option<RoaringBitmap>> = BTreeMap::new();
+    for index in indices.iter() {
+        if index.name == FRAG_REUSE_INDEX_NAME {
+            continue;
+        }
+
+        let index_dir = dataset.indices_dir().child(index.uuid.to_string());
+        let has_files = dataset
+            .object_store
+            .read_dir_all(&index_dir, None)
+            .try_next()
+            .await?
+            .is_some();
+        if !has_files {
+            issues.push(VerifyIssue::MissingIndexFiles {
+                index_name: index.name.clone(),
+                uuid: index.uuid,
+            });
+        }
+
+        let covered = coverage
+            .entry(index.name.as_str())
+            .or_insert_with(|| Some(RoaringBitmap::new()));
+        match (covered.as_mut(), &index.fragment_bitmap) {
+            (Some(covered), Some(bitmap)) => *covered |= bitmap,
+            // Old indices don't record which fragments they cover.
+            _ => *covered = None,
+        }
+    }
+
+    for (index_name, covered) in coverage {
+        let Some(covered) = covered else {
+            continue;
+        };
+        let fragment_ids = dataset
+            .manifest
+            .fragments
+            .iter()
+            .map(|fragment| fragment.id)
+            .filter(|id| !covered.contains(*id as u32))
+            .collect::<Vec<_>>();
+        if !fragment_ids.is_empty() {
+            issues.push(VerifyIssue::IndexCoverageGap {
+                index_name: index_name.to_string(),
+                fragment_ids,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use arrow_array::{
+        Int32Array, RecordBatch, RecordBatchIterator, RecordBatchReader, StringArray,
+    };
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+    use lance_index::{scalar::ScalarIndexParams, IndexType};
+
+    use crate::dataset::WriteParams;
+
+    fn test_data(start: i32) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(start..start + 100))],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    #[tokio::test]
+    async fn test_verify_dataset() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let write_params = WriteParams {
+            max_rows_per_file: 50,
+            ..Default::default()
+        };
+        let mut dataset = Dataset::write(test_data(0), test_uri, Some(write_params))
+            .await
+            .unwrap();
+        dataset
+            .create_index(
+                &["i"],
+                IndexType::Scalar,
+                Some("i_idx".to_string()),
+                &ScalarIndexParams::default(),
+                false,
+            )
+            .await
+            .unwrap();
+        dataset.delete("i < 10").await.unwrap();
+
+        let report = dataset.verify(&VerifyOptions::default()).await.unwrap();
+        assert_eq!(report.num_fragments, 2);
+        assert_eq!(report.issues, vec![]);
+        assert!(report.is_healthy());
+
+        // New data is not covered by the index.
+        dataset.append(test_data(100), None).await.unwrap();
+        let report = dataset.verify(&VerifyOptions::default()).await.unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(
+            report.issues,
+            vec![VerifyIssue::IndexCoverageGap {
+                index_name: "i_idx".to_string(),
+                fragment_ids: vec![2],
+            }]
+        );
+
+        // Remove a data file and the deletion file.
+        let fragment = &dataset.manifest.fragments[0];
+        let data_path = dataset.data_dir().child(fragment.files[0].path.as_str());
+        let deletion_path = deletion_file_path(
+            &dataset.base,
+            fragment.id,
+            fragment.deletion_file.as_ref().unwrap(),
+        );
+        dataset.object_store.delete(&data_path).await.unwrap();
+        dataset.object_store.delete(&deletion_path).await.unwrap();
+
+        let report = dataset.verify(&VerifyOptions::default()).await.unwrap();
+        assert!(!report.is_healthy());
+        assert_eq!(
+            report.errors().cloned().collect::<Vec<_>>(),
+            vec![
+                VerifyIssue::MissingDataFile {
+                    fragment_id: 0,
+                    path: data_path,
+                },
+                VerifyIssue::MissingDeletionFile {
+                    fragment_id: 0,
+                    path: deletion_path,
+                },
+            ]
+        );
+        assert_eq!(report.warnings().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksums() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "text",
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from_iter_values(
+                (0..100).map(|i| format!("needle-{:04}", i)),
+            ))],
+        )
+        .unwrap();
+        let write_params = WriteParams {
+            checksums: Some(true),
+            ..Default::default()
+        };
+        let dataset = Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            test_uri,
+            Some(write_params),
+        )
+        .await
+        .unwrap();
+
+        let options = VerifyOptions {
+            check_checksums: true,
+            ..Default::default()
+        };
+        let report = dataset.verify(&options).await.unwrap();
+        assert_eq!(report.issues, vec![]);
+
+        // Flip a bit in the data, which keeps the size of the file
+        let file_name = dataset.manifest.fragments[0].files[0].path.clone();
+        let data_path = dataset.data_dir().child(file_name.as_str());
+        let file_path = test_dir.path().join("data").join(file_name);
+        let mut bytes = std::fs::read(&file_path).unwrap();
+        let pos = bytes
+            .windows(b"needle-0050".len())
+            .position(|w| w == b"needle-0050")
+            .unwrap();
+        bytes[pos] ^= 1;
+        std::fs::write(&file_path, &bytes).unwrap();
+
+        // Only a full read notices
+        let report = dataset.verify(&VerifyOptions::default()).await.unwrap();
+        assert!(report.is_healthy());
+        let report = dataset.verify(&options).await.unwrap();
+        assert!(!report.is_healthy());
+        assert!(
+            matches!(
+                &report.issues[..],
+                [VerifyIssue::ChecksumMismatch { fragment_id: 0, path, .. }] if *path == data_path
+            ),
+            "{:?}",
+            report.issues
+        );
+    }
+}
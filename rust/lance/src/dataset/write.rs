@@ -1,23 +1,29 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+use std::collections::HashMap;
 use std::num::NonZero;
 use std::sync::Arc;
+use std::time::Instant;
 
 use arrow_array::RecordBatch;
 use chrono::TimeDelta;
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::stream::BoxStream;
 use futures::{Stream, StreamExt, TryStreamExt};
 use lance_core::datatypes::{
     NullabilityComparison, OnMissing, OnTypeMismatch, SchemaCompareOptions, StorageClass,
+    COMPRESSION_LEVEL_META_KEY, PAGE_BYTES_META_KEY, PAGE_ROWS_META_KEY,
 };
 use lance_core::error::LanceOptionExt;
+use lance_core::utils::metrics;
 use lance_core::utils::tracing::{AUDIT_MODE_CREATE, AUDIT_TYPE_DATA, TRACE_FILE_AUDIT};
 use lance_core::{datatypes::Schema, Error, Result};
 use lance_datafusion::chunker::{break_stream, chunk_stream};
 use lance_datafusion::spill::{create_replay_spill, SpillReceiver, SpillSender};
 use lance_datafusion::utils::StreamingWriteSource;
+use lance_encoding::encodings::physical::block::CompressionConfig;
 use lance_file::v2;
 use lance_file::v2::writer::FileWriterOptions;
 use lance_file::version::LanceFileVersion;
@@ -28,6 +34,7 @@ use lance_table::io::commit::{commit_handler_from_url, CommitHandler};
 use lance_table::io::manifest::ManifestDescribing;
 use object_store::path::Path;
 use snafu::location;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::{info, instrument};
 use uuid::Uuid;
 
@@ -39,10 +46,16 @@ use super::progress::{NoopFragmentWriteProgress, WriteFragmentProgress};
 use super::transaction::Transaction;
 use super::DATA_DIR;
 
+#[cfg(feature = "avro")]
+pub mod avro;
+mod checkpoint;
 mod commit;
 pub mod delete;
+#[cfg(feature = "parquet")]
+pub mod import;
 mod insert;
 pub mod merge_insert;
+pub mod text;
 pub mod update;
 
 pub use commit::CommitBuilder;
@@ -201,6 +214,100 @@ pub struct WriteParams {
     /// to set lance.auto_cleanup.interval and lance.auto_cleanup.older_than.
     /// Both parameters must be set to invoke autocleaning.
     pub auto_cleanup: Option<AutoCleanupParams>,
+
+    /// Key-value properties to attach to the transaction, such as a job id,
+    /// git sha, or author. These are persisted in the new version and are
+    /// returned by [`super::Dataset::versions`].
+    pub transaction_properties: Option<Arc<HashMap<String, String>>>,
+
+    /// A unique id, such as the id of the job step, that makes the commit of the
+    /// write idempotent, see [`super::transaction::Transaction::with_transaction_id`].
+    /// The data of a duplicate write is still written, and its files are left to
+    /// be cleaned up.
+    pub transaction_id: Option<String>,
+
+    /// The compression to use for some columns, by column name. Nested columns
+    /// are referred to by their path, e.g. `"a.b"`.
+    ///
+    /// This overrides the `lance-encoding:compression` and
+    /// `lance-encoding:compression-level` metadata of those fields. When a
+    /// dataset is created or overwritten the choice is kept in the schema and
+    /// also applies to later appends.
+    pub column_compression: Option<HashMap<String, CompressionConfig>>,
+
+    /// The page size targets of all columns, unless a field sets them with the
+    /// `lance-encoding:page-bytes` and `lance-encoding:page-rows` metadata.
+    ///
+    /// Only applies to files of version 2.0 and later.
+    pub page_size: Option<PageSize>,
+
+    /// The page size targets of some columns, by column name. Nested columns
+    /// are referred to by their path, e.g. `"a.b"`.
+    ///
+    /// This overrides `page_size` and the metadata of those fields. Like
+    /// `column_compression`, the choice is kept in the schema when a dataset is
+    /// created or overwritten.
+    pub column_page_size: Option<HashMap<String, PageSize>>,
+
+    /// Record the null count, minimum and maximum of the scalar columns of each
+    /// chunk of this many rows in the data files.
+    ///
+    /// Scans with a filter use the statistics to skip the chunks that cannot
+    /// match. Only applies to files of version 2.0 and later, see
+    /// [`lance_file::v2::statistics`]. By default no statistics are written.
+    pub statistics_chunk_rows: Option<u64>,
+
+    /// Record checksums of the pages and metadata in the data files.
+    ///
+    /// They are checked by [`crate::dataset::verify::VerifyOptions::check_checksums`]
+    /// and by readers that enable verification. Only applies to files of
+    /// version 2.0 and later, see [`lance_file::v2::checksum`]. If not set,
+    /// this follows the `LANCE_FILE_WRITER_CHECKSUMS` environment variable.
+    pub checksums: Option<bool>,
+
+    /// Bytes of data that may be read from the source ahead of the writer.
+    ///
+    /// The source is then read on a separate task while earlier batches are
+    /// encoded and uploaded, and is paused whenever this many bytes are waiting
+    /// to be written. A single chunk larger than this is still read on its own.
+    /// If not set, the source is only read when the writer is ready for more.
+    pub max_in_flight_bytes: Option<usize>,
+}
+
+/// Targets for the size of the pages of a column.
+///
+/// The writer buffers the data of each column and writes a page as soon as
+/// either target is reached. Small pages make point lookups of wide rows
+/// cheaper, large pages are better for scans. Incoming batches are not split,
+/// so a page can be larger than the targets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageSize {
+    /// Unencoded bytes to buffer before writing a page. Defaults to the share
+    /// of the column in the writer's cache, 8 MiB per column.
+    pub bytes: Option<u64>,
+    /// Rows to buffer before writing a page, even if fewer bytes were buffered.
+    pub rows: Option<u64>,
+}
+
+impl PageSize {
+    pub fn with_bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    pub fn with_rows(mut self, rows: u64) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    fn to_field_metadata(self) -> impl Iterator<Item = (&'static str, u64)> {
+        [
+            (PAGE_BYTES_META_KEY, self.bytes),
+            (PAGE_ROWS_META_KEY, self.rows),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+    }
 }
 
 impl Default for WriteParams {
@@ -220,6 +327,14 @@ impl Default for WriteParams {
             enable_v2_manifest_paths: false,
             session: None,
             auto_cleanup: Some(AutoCleanupParams::default()),
+            transaction_properties: None,
+            transaction_id: None,
+            column_compression: None,
+            page_size: None,
+            column_page_size: None,
+            statistics_chunk_rows: None,
+            checksums: None,
+            max_in_flight_bytes: None,
         }
     }
 }
@@ -274,7 +389,7 @@ pub async fn do_write_fragments(
     params: WriteParams,
     storage_version: LanceFileVersion,
 ) -> Result<Vec<Fragment>> {
-    let mut buffered_reader = if storage_version == LanceFileVersion::Legacy {
+    let buffered_reader = if storage_version == LanceFileVersion::Legacy {
         // In v1 we split the stream into row group sized batches
         chunk_stream(data, params.max_rows_per_group)
     } else {
@@ -284,13 +399,28 @@ pub async fn do_write_fragments(
             .map_ok(|batch| vec![batch])
             .boxed()
     };
+    let mut buffered_reader = match params.max_in_flight_bytes {
+        Some(max_bytes) => read_ahead(buffered_reader, max_bytes),
+        None => buffered_reader.map_ok(|chunk| (chunk, None)).boxed(),
+    };
 
-    let writer_generator = WriterGenerator::new(object_store, base_dir, schema, storage_version);
+    let writer_generator = WriterGenerator::new(
+        object_store,
+        base_dir,
+        schema,
+        storage_version,
+        FileWriterOptions {
+            statistics_chunk_rows: params.statistics_chunk_rows,
+            checksums: params.checksums,
+            ..Default::default()
+        },
+    );
     let mut writer: Option<Box<dyn GenericWriter>> = None;
     let mut num_rows_in_current_file = 0;
     let mut fragments = Vec::new();
     while let Some(batch_chunk) = buffered_reader.next().await {
-        let batch_chunk = batch_chunk?;
+        // The permit of the chunk is released once it has been written
+        let (batch_chunk, _permit) = batch_chunk?;
 
         if writer.is_none() {
             let (new_writer, new_fragment) = writer_generator.new_writer().await?;
@@ -310,10 +440,10 @@ pub async fn do_write_fragments(
             let (num_rows, data_file) = writer.take().unwrap().finish().await?;
             info!(target: TRACE_FILE_AUDIT, mode=AUDIT_MODE_CREATE, r#type=AUDIT_TYPE_DATA, path = &data_file.path);
             debug_assert_eq!(num_rows, num_rows_in_current_file);
-            params.progress.complete(fragments.last().unwrap()).await?;
             let last_fragment = fragments.last_mut().unwrap();
             last_fragment.physical_rows = Some(num_rows as usize);
             last_fragment.files.push(data_file);
+            params.progress.complete(last_fragment).await?;
             num_rows_in_current_file = 0;
         }
     }
@@ -325,11 +455,68 @@ pub async fn do_write_fragments(
         let last_fragment = fragments.last_mut().unwrap();
         last_fragment.physical_rows = Some(num_rows as usize);
         last_fragment.files.push(data_file);
+        params.progress.complete(last_fragment).await?;
     }
 
     Ok(fragments)
 }
 
+/// Read the chunks of `stream` on a separate task, ahead of their consumer.
+///
+/// Each chunk comes with a permit for its bytes, and the task waits before
+/// reading further while the permits of `max_bytes` are held.
+fn read_ahead(
+    mut stream: BoxStream<'static, Result<Vec<RecordBatch>>>,
+    max_bytes: usize,
+) -> BoxStream<'static, Result<(Vec<RecordBatch>, Option<OwnedSemaphorePermit>)>> {
+    // Permits are acquired in bulk as a u32
+    let max_bytes = max_bytes.clamp(1, u32::MAX as usize);
+    let semaphore = Arc::new(Semaphore::new(max_bytes));
+    let (tx, rx) = tokio::sync::mpsc::channel(READ_AHEAD_CHUNKS);
+    let reader = tokio::task::spawn(async move {
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => {
+                    let bytes = chunk
+                        .iter()
+                        .map(|batch| batch.get_array_memory_size())
+                        .sum::<usize>()
+                        .clamp(1, max_bytes);
+                    let permit = semaphore
+                        .clone()
+                        .acquire_many_owned(bytes as u32)
+                        .await
+                        .expect("the semaphore is never closed");
+                    Ok((chunk, Some(permit)))
+                }
+                Err(err) => Err(err),
+            };
+            let failed = chunk.is_err();
+            // Stop once the consumer is gone or after an error
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+    // Stop reading the source if the consumer is dropped early
+    let reader = AbortOnDrop(reader);
+    futures::stream::unfold((rx, reader), |(mut rx, reader)| async move {
+        rx.recv().await.map(|chunk| (chunk, (rx, reader)))
+    })
+    .boxed()
+}
+
+/// The maximum number of chunks read ahead, whatever their size.
+const READ_AHEAD_CHUNKS: usize = 16;
+
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 pub struct WrittenFragments {
     /// The fragments written to the dataset (and the schema)
     pub default: (Vec<Fragment>, Schema),
@@ -405,6 +592,7 @@ pub async fn write_fragments_internal(
         // from the user or the default.
         (schema, params.storage_version_or_default())
     };
+    let schema = apply_column_settings(schema, &params)?;
 
     let data_schema = schema.project_by_schema(
         data.schema().as_ref(),
@@ -424,6 +612,7 @@ pub async fn write_fragments_internal(
         enable_v2_manifest_paths: true,
         max_bytes_per_file: params.max_bytes_per_file,
         max_rows_per_file: params.max_rows_per_file,
+        max_in_flight_bytes: params.max_in_flight_bytes,
         ..Default::default()
     };
 
@@ -434,6 +623,7 @@ pub async fn write_fragments_internal(
         ));
     }
 
+    let start = Instant::now();
     let frag_schema = schema.retain_storage_class(StorageClass::Default);
     let fragments_fut = do_write_fragments(
         object_store.clone(),
@@ -464,9 +654,91 @@ pub async fn write_fragments_internal(
         ((fragments, frag_schema), None)
     };
 
+    let num_rows = default
+        .0
+        .iter()
+        .map(|fragment| fragment.physical_rows.unwrap_or(0) as u64)
+        .sum();
+    let num_files = default
+        .0
+        .iter()
+        .map(|fragment| fragment.files.len() as u64)
+        .sum();
+    metrics::increment_counter(metrics::WRITE_ROWS, &[], num_rows);
+    metrics::increment_counter(metrics::WRITE_FILES, &[], num_files);
+    metrics::record_histogram(
+        metrics::WRITE_DURATION_SECONDS,
+        &[],
+        start.elapsed().as_secs_f64(),
+    );
+
     Ok(WrittenFragments { default, blob })
 }
 
+/// Set the field metadata for the compression and page size options of `params`
+pub(super) fn apply_column_settings(schema: Schema, params: &WriteParams) -> Result<Schema> {
+    let schema = apply_column_compression(schema, params.column_compression.as_ref())?;
+    apply_page_size(schema, params.page_size, params.column_page_size.as_ref())
+}
+
+/// Set the compression field metadata of the columns in `column_compression`
+fn apply_column_compression(
+    mut schema: Schema,
+    column_compression: Option<&HashMap<String, CompressionConfig>>,
+) -> Result<Schema> {
+    for (column, compression) in column_compression.into_iter().flatten() {
+        let field_id = schema
+            .field(column)
+            .ok_or_else(|| {
+                Error::invalid_input(
+                    format!("Cannot set the compression of unknown column {}", column),
+                    location!(),
+                )
+            })?
+            .id;
+        let field = schema.mut_field_by_id(field_id).expect_ok()?;
+        field.metadata.remove(COMPRESSION_LEVEL_META_KEY);
+        field.metadata.extend(compression.to_field_metadata());
+    }
+    Ok(schema)
+}
+
+/// Set the page size field metadata from `page_size` and `column_page_size`
+fn apply_page_size(
+    mut schema: Schema,
+    page_size: Option<PageSize>,
+    column_page_size: Option<&HashMap<String, PageSize>>,
+) -> Result<Schema> {
+    if let Some(page_size) = page_size {
+        let field_ids = schema.fields_pre_order().map(|f| f.id).collect::<Vec<_>>();
+        for field_id in field_ids {
+            let field = schema.mut_field_by_id(field_id).expect_ok()?;
+            for (key, value) in page_size.to_field_metadata() {
+                field
+                    .metadata
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+        }
+    }
+    for (column, page_size) in column_page_size.into_iter().flatten() {
+        let field_id = schema
+            .field(column)
+            .ok_or_else(|| {
+                Error::invalid_input(
+                    format!("Cannot set the page size of unknown column {}", column),
+                    location!(),
+                )
+            })?
+            .id;
+        let field = schema.mut_field_by_id(field_id).expect_ok()?;
+        for (key, value) in page_size.to_field_metadata() {
+            field.metadata.insert(key.to_string(), value.to_string());
+        }
+    }
+    Ok(schema)
+}
+
 #[async_trait::async_trait]
 pub trait GenericWriter: Send {
     /// Write the given batches to the file
@@ -549,6 +821,25 @@ pub async fn open_writer(
     schema: &Schema,
     base_dir: &Path,
     storage_version: LanceFileVersion,
+) -> Result<Box<dyn GenericWriter>> {
+    open_writer_with_options(
+        object_store,
+        schema,
+        base_dir,
+        storage_version,
+        FileWriterOptions::default(),
+    )
+    .await
+}
+
+/// Like [`open_writer`], with options for the writers of files of version 2.0
+/// and later. The format version of `options` is ignored.
+pub async fn open_writer_with_options(
+    object_store: &ObjectStore,
+    schema: &Schema,
+    base_dir: &Path,
+    storage_version: LanceFileVersion,
+    options: FileWriterOptions,
 ) -> Result<Box<dyn GenericWriter>> {
     let filename = format!("{}.lance", Uuid::new_v4());
 
@@ -572,7 +863,7 @@ pub async fn open_writer(
             schema.clone(),
             FileWriterOptions {
                 format_version: Some(storage_version),
-                ..Default::default()
+                ..options
             },
         )?;
         let writer_adapter = V2WriterAdapter {
@@ -590,6 +881,7 @@ struct WriterGenerator {
     base_dir: Path,
     schema: Schema,
     storage_version: LanceFileVersion,
+    file_options: FileWriterOptions,
 }
 
 impl WriterGenerator {
@@ -598,12 +890,14 @@ impl WriterGenerator {
         base_dir: &Path,
         schema: &Schema,
         storage_version: LanceFileVersion,
+        file_options: FileWriterOptions,
     ) -> Self {
         Self {
             object_store,
             base_dir: base_dir.clone(),
             schema: schema.clone(),
             storage_version,
+            file_options,
         }
     }
 
@@ -611,11 +905,12 @@ impl WriterGenerator {
         // Use temporary ID 0; will assign ID later.
         let fragment = Fragment::new(0);
 
-        let writer = open_writer(
+        let writer = open_writer_with_options(
             &self.object_store,
             &self.schema,
             &self.base_dir,
             self.storage_version,
+            self.file_options.clone(),
         )
         .await?;
 
@@ -765,13 +1060,17 @@ impl Iterator for SpillStreamIter {
 mod tests {
     use super::*;
 
-    use arrow_array::{Int32Array, RecordBatchReader, StructArray};
+    use arrow_array::{
+        ArrayRef, Int32Array, RecordBatchIterator, RecordBatchReader, StringArray, StructArray,
+    };
     use arrow_schema::{DataType, Field as ArrowField, Fields, Schema as ArrowSchema};
     use datafusion::{error::DataFusionError, physical_plan::stream::RecordBatchStreamAdapter};
     use futures::TryStreamExt;
     use lance_datagen::{array, gen, BatchCount, RowCount};
+    use lance_encoding::encodings::physical::block::CompressionScheme;
     use lance_file::reader::FileReader;
     use lance_io::traits::Reader;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[tokio::test]
     async fn test_chunking_large_batches() {
@@ -817,6 +1116,63 @@ mod tests {
         assert_eq!(chunks[3][1].num_rows(), 2);
     }
 
+    #[tokio::test]
+    async fn test_read_ahead() {
+        let batches = (0..10)
+            .map(|i| {
+                RecordBatch::try_from_iter(vec![(
+                    "a",
+                    Arc::new(Int32Array::from_iter_values(i * 100..(i + 1) * 100)) as ArrayRef,
+                )])
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let batch_size = batches[0].get_array_memory_size();
+        // Bytes read from the source and not released by the consumer yet
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let source = {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            futures::stream::iter(batches.clone())
+                .inspect(move |batch| {
+                    let size = batch.get_array_memory_size();
+                    let bytes = in_flight.fetch_add(size, Ordering::SeqCst) + size;
+                    max_in_flight.fetch_max(bytes, Ordering::SeqCst);
+                })
+                .map(|batch| Ok::<_, Error>(vec![batch]))
+                .boxed()
+        };
+        let release = |chunk: Vec<RecordBatch>, permit| {
+            let size = chunk
+                .iter()
+                .map(|batch| batch.get_array_memory_size())
+                .sum::<usize>();
+            in_flight.fetch_sub(size, Ordering::SeqCst);
+            drop(permit);
+            chunk
+        };
+
+        let mut chunks = read_ahead(source, 2 * batch_size);
+        let (first, permit) = chunks.next().await.unwrap().unwrap();
+        assert_eq!(first, vec![batches[0].clone()]);
+        // The first two chunks hold the permits, the third waits for one
+        while in_flight.load(Ordering::SeqCst) < 3 * batch_size {
+            tokio::task::yield_now().await;
+        }
+        release(first, permit);
+
+        let mut rest = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            let (chunk, permit) = chunk.unwrap();
+            rest.push(release(chunk, permit));
+        }
+        assert_eq!(rest.concat(), batches[1..]);
+        // Never more than the limit plus the chunk waiting for its permit
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3 * batch_size);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+
     #[tokio::test]
     async fn test_chunking_small_batches() {
         // Create a stream of 10 batches of 3 rows
@@ -979,6 +1335,113 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_column_compression() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("a", DataType::Int32, false),
+            ArrowField::new("b", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..1000).map(|i| format!("value {}", i % 10)),
+                )),
+            ],
+        )
+        .unwrap();
+        let reader = || RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+        let compression = CompressionConfig::new(CompressionScheme::Zstd, Some(9));
+        let write_params = |column: &str| WriteParams {
+            data_storage_version: Some(LanceFileVersion::V2_1),
+            column_compression: Some(HashMap::from([(column.to_string(), compression)])),
+            ..Default::default()
+        };
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let err = Dataset::write(reader(), test_uri, Some(write_params("c")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        let dataset = Dataset::write(reader(), test_uri, Some(write_params("b")))
+            .await
+            .unwrap();
+        // The choice is kept in the schema
+        let field = dataset.schema().field("b").unwrap();
+        assert_eq!(
+            CompressionConfig::from_field_metadata(&field.metadata),
+            Some(compression)
+        );
+        assert!(dataset.schema().field("a").unwrap().metadata.is_empty());
+
+        let scanned = dataset.scan().try_into_batch().await.unwrap();
+        assert_eq!(scanned.columns(), batch.columns());
+    }
+
+    #[tokio::test]
+    async fn test_page_size() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("a", DataType::Int32, false),
+            ArrowField::new("b", DataType::Utf8, false)
+                .with_metadata([(PAGE_BYTES_META_KEY.to_string(), "4096".to_string())].into()),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..1000).map(|i| format!("value {}", i)),
+                )),
+            ],
+        )
+        .unwrap();
+        let reader = || RecordBatchIterator::new(vec![Ok(batch.clone())], schema.clone());
+        let write_params = |column: &str| WriteParams {
+            data_storage_version: Some(LanceFileVersion::V2_1),
+            max_rows_per_group: 100,
+            page_size: Some(PageSize::default().with_bytes(1024 * 1024).with_rows(200)),
+            column_page_size: Some(HashMap::from([(
+                column.to_string(),
+                PageSize::default().with_rows(100),
+            )])),
+            ..Default::default()
+        };
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let err = Dataset::write(reader(), test_uri, Some(write_params("c")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+
+        let dataset = Dataset::write(reader(), test_uri, Some(write_params("a")))
+            .await
+            .unwrap();
+        let metadata = |column: &str| dataset.schema().field(column).unwrap().metadata.clone();
+        // The column setting wins over the global one, which doesn't replace
+        // the field metadata
+        assert_eq!(
+            metadata("a"),
+            HashMap::from([
+                (PAGE_BYTES_META_KEY.to_string(), "1048576".to_string()),
+                (PAGE_ROWS_META_KEY.to_string(), "100".to_string()),
+            ])
+        );
+        assert_eq!(
+            metadata("b"),
+            HashMap::from([
+                (PAGE_BYTES_META_KEY.to_string(), "4096".to_string()),
+                (PAGE_ROWS_META_KEY.to_string(), "200".to_string()),
+            ])
+        );
+
+        let scanned = dataset.scan().try_into_batch().await.unwrap();
+        assert_eq!(scanned.columns(), batch.columns());
+    }
+
     #[tokio::test]
     async fn test_file_v1_schema_order() {
         // Create a schema where fields ids are not in order and contain holes.
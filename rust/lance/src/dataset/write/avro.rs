@@ -0,0 +1,529 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Streaming reader for Avro object container files.
+//!
+//! The writer schema of the file is mapped to Arrow as follows:
+//!
+//! | Avro                          | Arrow                                 |
+//! |-------------------------------|---------------------------------------|
+//! | `["null", T]` / `[T, "null"]` | nullable `T`                          |
+//! | `record`                      | `Struct`                              |
+//! | `array`                       | `List`                                |
+//! | `enum`, `uuid`                | `Utf8`                                |
+//! | `fixed`                       | `FixedSizeBinary`                     |
+//! | `decimal`                     | `Decimal128` (precision up to 38)     |
+//! | `date`                        | `Date32`                              |
+//! | `time-millis` / `time-micros` | `Time32(ms)` / `Time64(us)`           |
+//! | `timestamp-*`                 | `Timestamp` with a `UTC` time zone    |
+//! | `local-timestamp-*`           | `Timestamp` without a time zone       |
+//!
+//! Maps, durations and unions of several non-null types are not supported.
+//! Files are decoded on a blocking thread while they are downloaded, so memory
+//! use is bounded by the batch size.
+
+use std::sync::Arc;
+
+use apache_avro::types::Value;
+use apache_avro::Schema as AvroSchema;
+use arrow_array::builder::BooleanBufferBuilder;
+use arrow_array::types::{
+    Date32Type, Float32Type, Float64Type, Int32Type, Int64Type, Time32MillisecondType,
+    Time64MicrosecondType, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType,
+};
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Decimal128Array, FixedSizeBinaryArray, ListArray,
+    NullArray, PrimitiveArray, RecordBatch, StringArray, StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field, Fields, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance_core::{Error, Result};
+use lance_io::object_store::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use snafu::location;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+/// Options for [`read_avro`].
+#[derive(Debug, Clone)]
+pub struct AvroReadOptions {
+    /// Number of rows in each batch.
+    pub batch_size: usize,
+    pub store_params: Option<ObjectStoreParams>,
+}
+
+impl Default for AvroReadOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 8192,
+            store_params: None,
+        }
+    }
+}
+
+/// Read an Avro object container file as a stream of record batches.
+pub async fn read_avro(uri: &str, options: &AvroReadOptions) -> Result<SendableRecordBatchStream> {
+    let (store, path) = ObjectStore::from_uri_and_params(
+        Arc::new(ObjectStoreRegistry::default()),
+        uri,
+        &options.store_params.clone().unwrap_or_default(),
+    )
+    .await?;
+    let input = store
+        .inner
+        .get(&path)
+        .await?
+        .into_stream()
+        .map_err(std::io::Error::other);
+    let input = SyncIoBridge::new(StreamReader::new(input));
+
+    let batch_size = options.batch_size.max(1);
+    let (schema_tx, schema_rx) = tokio::sync::oneshot::channel();
+    let (batch_tx, batch_rx) = tokio::sync::mpsc::channel(2);
+    tokio::task::spawn_blocking(move || {
+        let reader = match apache_avro::Reader::new(input) {
+            Ok(reader) => reader,
+            Err(e) => {
+                let _ = schema_tx.send(Err(avro_error(e)));
+                return;
+            }
+        };
+        let schema = match arrow_schema(reader.writer_schema()) {
+            Ok(schema) => Arc::new(schema),
+            Err(e) => {
+                let _ = schema_tx.send(Err(e));
+                return;
+            }
+        };
+        if schema_tx.send(Ok(schema.clone())).is_err() {
+            return;
+        }
+
+        let mut rows = Vec::with_capacity(batch_size);
+        for value in reader {
+            match value {
+                Ok(value) => rows.push(value),
+                Err(e) => {
+                    let _ = batch_tx.blocking_send(Err(avro_error(e)));
+                    return;
+                }
+            }
+            if rows.len() == batch_size {
+                let batch = record_batch(&schema, &rows);
+                rows.clear();
+                if batch_tx.blocking_send(batch).is_err() {
+                    return;
+                }
+            }
+        }
+        if !rows.is_empty() {
+            let _ = batch_tx.blocking_send(record_batch(&schema, &rows));
+        }
+    });
+
+    let schema: SchemaRef = schema_rx.await.map_err(|_| Error::Internal {
+        message: "Avro decoder exited before reading the schema".to_string(),
+        location: location!(),
+    })??;
+    let batches = futures::stream::unfold(batch_rx, |mut rx| async move {
+        rx.recv().await.map(|batch| (batch, rx))
+    });
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        schema,
+        batches.map_err(DataFusionError::from),
+    )))
+}
+
+fn avro_error(err: apache_avro::Error) -> Error {
+    Error::io(format!("Failed to read Avro file: {}", err), location!())
+}
+
+fn not_supported(what: impl std::fmt::Display) -> Error {
+    Error::NotSupported {
+        source: format!("Avro {} cannot be converted to Arrow", what).into(),
+        location: location!(),
+    }
+}
+
+/// Map the writer schema of a file to Arrow. The top-level type must be a record.
+fn arrow_schema(schema: &AvroSchema) -> Result<ArrowSchema> {
+    match arrow_type(schema)? {
+        (DataType::Struct(fields), _) => Ok(ArrowSchema::new(fields)),
+        _ => Err(Error::invalid_input(
+            "The schema of an Avro file must be a record",
+            location!(),
+        )),
+    }
+}
+
+/// Returns the Arrow type and whether the type is nullable.
+fn arrow_type(schema: &AvroSchema) -> Result<(DataType, bool)> {
+    let data_type = match schema {
+        AvroSchema::Null => return Ok((DataType::Null, true)),
+        AvroSchema::Boolean => DataType::Boolean,
+        AvroSchema::Int => DataType::Int32,
+        AvroSchema::Long => DataType::Int64,
+        AvroSchema::Float => DataType::Float32,
+        AvroSchema::Double => DataType::Float64,
+        AvroSchema::Bytes => DataType::Binary,
+        AvroSchema::String | AvroSchema::Uuid | AvroSchema::Enum(_) => DataType::Utf8,
+        AvroSchema::Fixed(fixed) => DataType::FixedSizeBinary(fixed.size as i32),
+        AvroSchema::Decimal(decimal) => {
+            if decimal.precision > 38 {
+                return Err(not_supported(format!(
+                    "decimal with precision {}",
+                    decimal.precision
+                )));
+            }
+            DataType::Decimal128(decimal.precision as u8, decimal.scale as i8)
+        }
+        AvroSchema::Date => DataType::Date32,
+        AvroSchema::TimeMillis => DataType::Time32(TimeUnit::Millisecond),
+        AvroSchema::TimeMicros => DataType::Time64(TimeUnit::Microsecond),
+        AvroSchema::TimestampMillis => {
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        }
+        AvroSchema::TimestampMicros => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        }
+        AvroSchema::TimestampNanos => DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        AvroSchema::LocalTimestampMillis => DataType::Timestamp(TimeUnit::Millisecond, None),
+        AvroSchema::LocalTimestampMicros => DataType::Timestamp(TimeUnit::Microsecond, None),
+        AvroSchema::LocalTimestampNanos => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        AvroSchema::Array(array) => {
+            let (item, nullable) = arrow_type(&array.items)?;
+            DataType::List(Arc::new(Field::new("item", item, nullable)))
+        }
+        AvroSchema::Record(record) => DataType::Struct(
+            record
+                .fields
+                .iter()
+                .map(|field| {
+                    let (data_type, nullable) = arrow_type(&field.schema)?;
+                    Ok(Field::new(&field.name, data_type, nullable))
+                })
+                .collect::<Result<Fields>>()?,
+        ),
+        AvroSchema::Union(union) => {
+            let variants = union
+                .variants()
+                .iter()
+                .filter(|s| !matches!(s, AvroSchema::Null))
+                .collect::<Vec<_>>();
+            if variants.len() != 1 {
+                return Err(not_supported("unions of several non-null types"));
+            }
+            let (data_type, _) = arrow_type(variants[0])?;
+            return Ok((data_type, true));
+        }
+        AvroSchema::Map(_) => return Err(not_supported("map")),
+        other => return Err(not_supported(format!("type {:?}", other))),
+    };
+    Ok((data_type, false))
+}
+
+fn record_batch(schema: &SchemaRef, rows: &[Value]) -> Result<RecordBatch> {
+    let rows = rows.iter().map(Some).collect::<Vec<_>>();
+    let array = to_array(&DataType::Struct(schema.fields().clone()), &rows)?;
+    let array = array
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .expect("record is decoded as a struct");
+    Ok(RecordBatch::from(array))
+}
+
+/// Strip the union wrapper of a value, returning `None` for nulls.
+fn unwrap_union(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Union(_, inner) => unwrap_union(inner),
+        Value::Null => None,
+        other => Some(other),
+    }
+}
+
+fn unexpected(value: &Value, data_type: &DataType) -> Error {
+    Error::invalid_input(
+        format!("Unexpected Avro value {:?} for type {}", value, data_type),
+        location!(),
+    )
+}
+
+macro_rules! primitive {
+    ($arrow_type:ty, $values:expr, $data_type:expr, $($pattern:pat => $value:expr),+) => {{
+        let array = $values
+            .iter()
+            .map(|value| match value {
+                None => Ok(None),
+                $(Some($pattern) => Ok(Some($value)),)+
+                Some(other) => Err(unexpected(other, $data_type)),
+            })
+            .collect::<Result<PrimitiveArray<$arrow_type>>>()?;
+        Arc::new(array.with_data_type($data_type.clone())) as ArrayRef
+    }};
+}
+
+/// Convert a column of Avro values to an array of the given type.
+fn to_array(data_type: &DataType, values: &[Option<&Value>]) -> Result<ArrayRef> {
+    let values = values
+        .iter()
+        .map(|v| v.and_then(unwrap_union))
+        .collect::<Vec<_>>();
+    let array = match data_type {
+        DataType::Null => Arc::new(NullArray::new(values.len())) as ArrayRef,
+        DataType::Boolean => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    None => Ok(None),
+                    Some(Value::Boolean(v)) => Ok(Some(*v)),
+                    Some(other) => Err(unexpected(other, data_type)),
+                })
+                .collect::<Result<BooleanArray>>()?,
+        ),
+        DataType::Int32 => primitive!(Int32Type, values, data_type, Value::Int(v) => *v),
+        DataType::Int64 => primitive!(Int64Type, values, data_type, Value::Long(v) => *v),
+        DataType::Float32 => primitive!(Float32Type, values, data_type, Value::Float(v) => *v),
+        DataType::Float64 => primitive!(Float64Type, values, data_type, Value::Double(v) => *v),
+        DataType::Date32 => primitive!(Date32Type, values, data_type, Value::Date(v) => *v),
+        DataType::Time32(_) => {
+            primitive!(Time32MillisecondType, values, data_type, Value::TimeMillis(v) => *v)
+        }
+        DataType::Time64(_) => {
+            primitive!(Time64MicrosecondType, values, data_type, Value::TimeMicros(v) => *v)
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => primitive!(
+            TimestampMillisecondType, values, data_type,
+            Value::TimestampMillis(v) => *v,
+            Value::LocalTimestampMillis(v) => *v
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => primitive!(
+            TimestampMicrosecondType, values, data_type,
+            Value::TimestampMicros(v) => *v,
+            Value::LocalTimestampMicros(v) => *v
+        ),
+        DataType::Timestamp(_, _) => primitive!(
+            TimestampNanosecondType, values, data_type,
+            Value::TimestampNanos(v) => *v,
+            Value::LocalTimestampNanos(v) => *v
+        ),
+        DataType::Utf8 => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    None => Ok(None),
+                    Some(Value::String(v)) | Some(Value::Enum(_, v)) => Ok(Some(v.clone())),
+                    Some(Value::Uuid(v)) => Ok(Some(v.to_string())),
+                    Some(other) => Err(unexpected(other, data_type)),
+                })
+                .collect::<Result<StringArray>>()?,
+        ),
+        DataType::Binary => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    None => Ok(None),
+                    Some(Value::Bytes(v)) => Ok(Some(v.as_slice())),
+                    Some(other) => Err(unexpected(other, data_type)),
+                })
+                .collect::<Result<BinaryArray>>()?,
+        ),
+        DataType::FixedSizeBinary(size) => {
+            let bytes = values
+                .iter()
+                .map(|value| match value {
+                    None => Ok(None),
+                    Some(Value::Fixed(_, v)) => Ok(Some(v.as_slice())),
+                    Some(other) => Err(unexpected(other, data_type)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                bytes.into_iter(),
+                *size,
+            )?)
+        }
+        DataType::Decimal128(precision, scale) => {
+            let array = values
+                .iter()
+                .map(|value| match value {
+                    None => Ok(None),
+                    Some(Value::Decimal(v)) => {
+                        let bytes = Vec::<u8>::try_from(v).map_err(avro_error)?;
+                        decimal_from_be_bytes(&bytes).map(Some)
+                    }
+                    Some(other) => Err(unexpected(other, data_type)),
+                })
+                .collect::<Result<Decimal128Array>>()?;
+            Arc::new(array.with_precision_and_scale(*precision, *scale)?)
+        }
+        DataType::List(item) => {
+            let mut lengths = Vec::with_capacity(values.len());
+            let mut nulls = BooleanBufferBuilder::new(values.len());
+            let mut items = Vec::new();
+            for value in &values {
+                match value {
+                    None => {
+                        lengths.push(0);
+                        nulls.append(false);
+                    }
+                    Some(Value::Array(children)) => {
+                        lengths.push(children.len());
+                        nulls.append(true);
+                        items.extend(children.iter().map(Some));
+                    }
+                    Some(other) => return Err(unexpected(other, data_type)),
+                }
+            }
+            let child = to_array(item.data_type(), &items)?;
+            Arc::new(ListArray::try_new(
+                item.clone(),
+                OffsetBuffer::from_lengths(lengths),
+                child,
+                Some(NullBuffer::new(nulls.finish())),
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let mut nulls = BooleanBufferBuilder::new(values.len());
+            let mut columns = vec![Vec::with_capacity(values.len()); fields.len()];
+            for value in &values {
+                match value {
+                    None => {
+                        nulls.append(false);
+                        columns.iter_mut().for_each(|column| column.push(None));
+                    }
+                    Some(Value::Record(record)) => {
+                        nulls.append(true);
+                        // Values are in writer schema order.
+                        for (column, (_, child)) in columns.iter_mut().zip(record) {
+                            column.push(Some(child));
+                        }
+                    }
+                    Some(other) => return Err(unexpected(other, data_type)),
+                }
+            }
+            let children = fields
+                .iter()
+                .zip(columns)
+                .map(|(field, column)| to_array(field.data_type(), &column))
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                children,
+                Some(NullBuffer::new(nulls.finish())),
+            )?)
+        }
+        other => return Err(not_supported(format!("value of type {}", other))),
+    };
+    Ok(array)
+}
+
+/// Decode a big-endian two's complement integer of at most 16 bytes.
+fn decimal_from_be_bytes(bytes: &[u8]) -> Result<i128> {
+    if bytes.len() > 16 {
+        return Err(not_supported("decimal wider than 128 bits"));
+    }
+    let fill = if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Ok(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use apache_avro::{Decimal, Writer};
+    use arrow_array::{Array, Int64Array, TimestampMillisecondArray};
+
+    use crate::Dataset;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "event",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "key", "type": ["null", "string"]},
+            {"name": "amount", "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}},
+            {"name": "ts", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "tags", "type": {"type": "array", "items": "string"}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_decimal_from_be_bytes() {
+        assert_eq!(decimal_from_be_bytes(&[0x01, 0x00]).unwrap(), 256);
+        assert_eq!(decimal_from_be_bytes(&[0xff]).unwrap(), -1);
+        assert_eq!(decimal_from_be_bytes(&[0xff, 0x38]).unwrap(), -200);
+        assert_eq!(decimal_from_be_bytes(&[]).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_avro() {
+        let schema = AvroSchema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        for i in 0..25_i64 {
+            let key = if i % 5 == 0 {
+                Value::Union(0, Box::new(Value::Null))
+            } else {
+                Value::Union(1, Box::new(Value::String(format!("k{}", i))))
+            };
+            let amount = Decimal::from((i as i16 * -150).to_be_bytes().to_vec());
+            writer
+                .append(Value::Record(vec![
+                    ("id".to_string(), Value::Long(i)),
+                    ("key".to_string(), key),
+                    ("amount".to_string(), Value::Decimal(amount)),
+                    (
+                        "ts".to_string(),
+                        Value::TimestampMillis(1_700_000_000_000 + i),
+                    ),
+                    (
+                        "tags".to_string(),
+                        Value::Array(vec![Value::String("a".to_string()); i as usize % 3]),
+                    ),
+                ]))
+                .unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.avro");
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+
+        let options = AvroReadOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let stream = read_avro(path.to_str().unwrap(), &options).await.unwrap();
+        let schema = stream.schema();
+        assert_eq!(schema.field(2).data_type(), &DataType::Decimal128(10, 2));
+        assert!(schema.field(1).is_nullable());
+        assert!(!schema.field(0).is_nullable());
+
+        let uri = dir.path().join("dataset").to_str().unwrap().to_string();
+        let dataset = Dataset::write(stream, &uri, None).await.unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 25);
+        assert_eq!(dataset.get_fragments().len(), 1);
+
+        let batch = dataset.scan().try_into_batch().await.unwrap();
+        let ids = batch["id"].as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(ids.value(24), 24);
+        assert_eq!(batch["key"].null_count(), 5);
+        let amounts = batch["amount"]
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(amounts.value_as_string(3), "-4.50");
+        let ts = batch["ts"]
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(ts.value(1), 1_700_000_000_001);
+        let tags = batch["tags"].as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(tags.value_length(2), 2);
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Checkpoints of resumable writes.
+//!
+//! A write with a checkpoint records each fragment it completes in
+//! `_checkpoints/{name}.json` under the dataset directory. When the write fails and is
+//! run again with the same checkpoint, the rows of the recorded fragments are skipped
+//! from the source and the fragments are committed together with the rest of the data.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance_io::object_store::ObjectStore;
+use lance_table::format::Fragment;
+use object_store::path::Path;
+use serde::{Deserialize, Serialize};
+use snafu::location;
+use tokio::sync::Mutex;
+
+use crate::dataset::progress::WriteFragmentProgress;
+use crate::{Error, Result};
+
+const CHECKPOINTS_DIR: &str = "_checkpoints";
+
+/// The fragments completed by a write, which hold the first `num_rows` rows of its source.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct WriteCheckpoint {
+    pub fragments: Vec<Fragment>,
+    pub num_rows: u64,
+}
+
+impl WriteCheckpoint {
+    pub fn path(base_path: &Path, name: &str) -> Path {
+        base_path
+            .child(CHECKPOINTS_DIR)
+            .child(format!("{name}.json"))
+    }
+
+    /// Load the checkpoint at `path`, or an empty one if there is none.
+    pub async fn load(object_store: &ObjectStore, path: &Path) -> Result<Self> {
+        if !object_store.exists(path).await? {
+            return Ok(Self::default());
+        }
+        let bytes = object_store.read_one_all(path).await?;
+        serde_json::from_slice(&bytes).map_err(|err| {
+            Error::corrupt_file(
+                path.clone(),
+                format!("invalid write checkpoint: {err}"),
+                location!(),
+            )
+        })
+    }
+
+    async fn save(&self, object_store: &ObjectStore, path: &Path) -> Result<()> {
+        let bytes = serde_json::to_vec(self).map_err(|err| Error::Internal {
+            message: format!("failed to serialize write checkpoint: {err}"),
+            location: location!(),
+        })?;
+        object_store.put(path, &bytes).await?;
+        Ok(())
+    }
+}
+
+/// Skip the first `num_rows` rows of `stream`, which were written before the checkpoint.
+pub(crate) fn skip_rows(
+    stream: SendableRecordBatchStream,
+    num_rows: u64,
+) -> SendableRecordBatchStream {
+    let schema = stream.schema();
+    let mut remaining = num_rows as usize;
+    let stream = stream.try_filter_map(move |batch| {
+        let skip = remaining.min(batch.num_rows());
+        remaining -= skip;
+        let batch = (skip < batch.num_rows()).then(|| batch.slice(skip, batch.num_rows() - skip));
+        std::future::ready(Ok(batch))
+    });
+    Box::pin(RecordBatchStreamAdapter::new(schema, stream))
+}
+
+/// Saves the checkpoint whenever a fragment is completed, then reports to `inner`.
+#[derive(Debug)]
+pub(crate) struct CheckpointProgress {
+    inner: Arc<dyn WriteFragmentProgress>,
+    object_store: Arc<ObjectStore>,
+    path: Path,
+    checkpoint: Mutex<WriteCheckpoint>,
+}
+
+impl CheckpointProgress {
+    pub fn new(
+        inner: Arc<dyn WriteFragmentProgress>,
+        object_store: Arc<ObjectStore>,
+        path: Path,
+        checkpoint: WriteCheckpoint,
+    ) -> Self {
+        Self {
+            inner,
+            object_store,
+            path,
+            checkpoint: Mutex::new(checkpoint),
+        }
+    }
+}
+
+#[async_trait]
+impl WriteFragmentProgress for CheckpointProgress {
+    async fn begin(&self, fragment: &Fragment) -> Result<()> {
+        self.inner.begin(fragment).await
+    }
+
+    async fn complete(&self, fragment: &Fragment) -> Result<()> {
+        {
+            let mut checkpoint = self.checkpoint.lock().await;
+            checkpoint.fragments.push(fragment.clone());
+            checkpoint.num_rows += fragment.physical_rows.unwrap_or_default() as u64;
+            checkpoint.save(&self.object_store, &self.path).await?;
+        }
+        self.inner.complete(fragment).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{ArrowError, DataType, Field as ArrowField, Schema as ArrowSchema};
+    use tempfile::tempdir;
+
+    use crate::dataset::{InsertBuilder, WriteParams};
+    use crate::Dataset;
+
+    #[tokio::test]
+    async fn test_resume_write() {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "a",
+            DataType::Int32,
+            false,
+        )]));
+        let batches = (0..5)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(
+                        i * 100..(i + 1) * 100,
+                    ))],
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let params = WriteParams {
+            max_rows_per_file: 100,
+            ..Default::default()
+        };
+
+        // The source fails after three batches
+        let failing = batches[..3]
+            .to_vec()
+            .into_iter()
+            .map(Ok)
+            .chain(std::iter::once(Err(ArrowError::ComputeError(
+                "source failed".to_string(),
+            ))));
+        InsertBuilder::new(test_uri)
+            .with_params(&params)
+            .with_checkpoint("ingest")
+            .execute_stream(RecordBatchIterator::new(failing, schema.clone()))
+            .await
+            .unwrap_err();
+        assert!(Dataset::open(test_uri).await.is_err());
+
+        let (object_store, base_path) = ObjectStore::from_uri(test_uri).await.unwrap();
+        let path = WriteCheckpoint::path(&base_path, "ingest");
+        let checkpoint = WriteCheckpoint::load(&object_store, &path).await.unwrap();
+        assert_eq!(checkpoint.fragments.len(), 3);
+        assert_eq!(checkpoint.num_rows, 300);
+
+        // Resuming writes the last two batches only
+        let dataset = InsertBuilder::new(test_uri)
+            .with_params(&params)
+            .with_checkpoint("ingest")
+            .execute_stream(RecordBatchIterator::new(
+                batches.clone().into_iter().map(Ok),
+                schema.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(dataset.get_fragments().len(), 5);
+        let data = dataset.scan().try_into_batch().await.unwrap();
+        assert_eq!(
+            data,
+            arrow_select::concat::concat_batches(&schema, &batches).unwrap()
+        );
+        assert!(!object_store.exists(&path).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_empty_source() {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "a",
+            DataType::Int32,
+            false,
+        )]));
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        // No fragment is written, so there is no checkpoint to remove
+        let dataset = InsertBuilder::new(test_uri)
+            .with_checkpoint("ingest")
+            .execute_stream(RecordBatchIterator::new(vec![], schema))
+            .await
+            .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 0);
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use lance_core::utils::mask::RowIdTreeMap;
@@ -42,6 +43,8 @@ pub struct CommitBuilder<'a> {
     detached: bool,
     commit_config: CommitConfig,
     affected_rows: Option<RowIdTreeMap>,
+    transaction_properties: Option<Arc<HashMap<String, String>>>,
+    transaction_id: Option<String>,
 }
 
 impl<'a> CommitBuilder<'a> {
@@ -58,6 +61,8 @@ impl<'a> CommitBuilder<'a> {
             detached: false,
             commit_config: Default::default(),
             affected_rows: None,
+            transaction_properties: None,
+            transaction_id: None,
         }
     }
 
@@ -146,6 +151,13 @@ impl<'a> CommitBuilder<'a> {
     /// Set the maximum number of retries for commit operations.
     ///
     /// If a commit operation fails, it will be retried up to `max_retries` times.
+    ///
+    /// When other transactions were committed since the read version, and they
+    /// are compatible with this one, e.g. concurrent appends, the transaction is
+    /// rebased onto the latest version and committed without surfacing an error.
+    /// Only conflicting transactions return a
+    /// [`crate::Error::RetryableCommitConflict`] or [`crate::Error::CommitConflict`].
+    /// The default is 20.
     pub fn with_max_retries(mut self, max_retries: u32) -> Self {
         self.commit_config.num_retries = max_retries;
         self
@@ -158,7 +170,45 @@ impl<'a> CommitBuilder<'a> {
         self
     }
 
-    pub async fn execute(self, transaction: Transaction) -> Result<Dataset> {
+    /// Attach key-value properties to the transaction, such as a job id,
+    /// git sha, or author.
+    ///
+    /// The properties are stored in the manifest of the new version and are
+    /// returned in [`crate::dataset::Version::metadata`] by
+    /// [`Dataset::versions`]. They are merged into any properties already set
+    /// on the transaction, replacing those with the same keys.
+    pub fn with_transaction_properties(
+        mut self,
+        transaction_properties: HashMap<String, String>,
+    ) -> Self {
+        self.transaction_properties = Some(Arc::new(transaction_properties));
+        self
+    }
+
+    /// Make the commit idempotent, see [`Transaction::with_transaction_id`].
+    pub fn with_transaction_id(mut self, transaction_id: impl Into<String>) -> Self {
+        self.transaction_id = Some(transaction_id.into());
+        self
+    }
+
+    pub async fn execute(self, mut transaction: Transaction) -> Result<Dataset> {
+        if let Some(transaction_properties) = &self.transaction_properties {
+            let mut properties = transaction
+                .transaction_properties
+                .as_deref()
+                .cloned()
+                .unwrap_or_default();
+            properties.extend(
+                transaction_properties
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+            transaction.transaction_properties = Some(Arc::new(properties));
+        }
+        if let Some(transaction_id) = &self.transaction_id {
+            transaction = transaction.with_transaction_id(transaction_id);
+        }
+
         let session = self
             .session
             .or_else(|| self.dest.dataset().map(|ds| ds.session.clone()))
@@ -346,6 +396,7 @@ impl<'a> CommitBuilder<'a> {
                 commit_handler,
                 tags,
                 metadata_cache,
+                read_only: false,
             }),
         }
     }
@@ -404,6 +455,7 @@ impl<'a> CommitBuilder<'a> {
             read_version,
             blobs_op,
             tag: None,
+            transaction_properties: None,
         };
         let dataset = self.execute(merged.clone()).await?;
         Ok(BatchCommitResult { dataset, merged })
@@ -424,15 +476,19 @@ mod tests {
     use arrow::array::{Int32Array, RecordBatch};
     use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
     use lance_io::{object_store::ChainedWrappingObjectStore, utils::CachedFileSize};
-    use lance_table::format::{DataFile, Fragment};
+    use lance_table::format::{DataFile, Fragment, Index, Manifest};
+    use lance_table::io::commit::{CommitError, ManifestLocation, ManifestWriter};
     use std::time::Duration;
 
+    use object_store::path::Path;
     use object_store::throttle::ThrottleConfig;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
+    use crate::dataset::history::TRANSACTION_ID_KEY;
     use crate::utils::test::ThrottledStoreWrapper;
 
     use crate::{
-        dataset::{InsertBuilder, WriteParams},
+        dataset::{InsertBuilder, WriteMode, WriteParams},
         utils::test::StatsHolder,
     };
 
@@ -464,6 +520,7 @@ mod tests {
             read_version,
             blobs_op: None,
             tag: None,
+            transaction_properties: None,
         }
     }
 
@@ -545,10 +602,11 @@ mod tests {
             .unwrap();
         assert_eq!(new_ds.manifest().version, 7);
         // Session should still be re-used
-        // However, the dataset needs to be loaded and the read version checked out,
-        // so an additional 4 IOPs are needed.
+        // However, the dataset needs to be loaded and the read version checked out.
+        // The manifests are cached in the session, so only 2 additional IOPs are
+        // needed to find them.
         let (reads, writes) = get_new_iops();
-        assert_eq!(reads, 5);
+        assert_eq!(reads, 3);
         assert_eq!(writes, 2);
 
         // Commit transaction with URI and new session. Re-use the store
@@ -616,6 +674,88 @@ mod tests {
         assert_eq!(io_stats.num_hops, 3);
     }
 
+    #[tokio::test]
+    async fn test_idempotent_commit() {
+        let data = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![ArrowField::new(
+                "a",
+                DataType::Int32,
+                false,
+            )])),
+            vec![Arc::new(Int32Array::from(vec![0; 5]))],
+        )
+        .unwrap();
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let dataset = Arc::new(
+            InsertBuilder::new(test_uri)
+                .execute(vec![data])
+                .await
+                .unwrap(),
+        );
+        let read_version = dataset.manifest().version;
+
+        let committed = CommitBuilder::new(dataset.clone())
+            .with_transaction_id("step-1")
+            .execute(sample_transaction(read_version))
+            .await
+            .unwrap();
+        assert_eq!(committed.manifest().version, read_version + 1);
+
+        // A retry of the same step is a no-op
+        let retried = CommitBuilder::new(dataset.clone())
+            .execute(sample_transaction(read_version).with_transaction_id("step-1"))
+            .await
+            .unwrap();
+        assert_eq!(retried.manifest().version, read_version + 1);
+        assert_eq!(retried.get_fragments().len(), 2);
+
+        let other = CommitBuilder::new(dataset)
+            .with_transaction_id("step-2")
+            .execute(sample_transaction(read_version))
+            .await
+            .unwrap();
+        assert_eq!(other.manifest().version, read_version + 2);
+        assert_eq!(other.get_fragments().len(), 3);
+
+        // A retry that reopened the dataset reads a newer version than the
+        // original commit, which is still found
+        let reopened = Arc::new(Dataset::open(test_uri).await.unwrap());
+        let retried = CommitBuilder::new(reopened.clone())
+            .with_transaction_properties(HashMap::from([(
+                "job_id".to_string(),
+                "job-1".to_string(),
+            )]))
+            .with_transaction_id("step-1")
+            .execute(sample_transaction(reopened.manifest().version))
+            .await
+            .unwrap();
+        assert_eq!(retried.manifest().version, read_version + 1);
+        let latest = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(latest.manifest().version, read_version + 2);
+
+        // Properties are merged with the transaction id set on the transaction
+        let committed = CommitBuilder::new(reopened.clone())
+            .with_transaction_properties(HashMap::from([(
+                "job_id".to_string(),
+                "job-1".to_string(),
+            )]))
+            .execute(sample_transaction(reopened.manifest().version).with_transaction_id("step-3"))
+            .await
+            .unwrap();
+        assert_eq!(committed.manifest().version, read_version + 3);
+        let properties = &committed.manifest().transaction_properties;
+        assert_eq!(properties.get("job_id").map(String::as_str), Some("job-1"));
+        assert_eq!(
+            properties.get(TRANSACTION_ID_KEY).map(String::as_str),
+            Some("step-3")
+        );
+        // The id is internal, so it isn't part of the version's metadata
+        let metadata = committed.version().metadata;
+        assert_eq!(metadata.get("job_id").map(String::as_str), Some("job-1"));
+        assert!(!metadata.contains_key(TRANSACTION_ID_KEY));
+    }
+
     #[tokio::test]
     #[rstest::rstest]
     async fn test_commit_conflict_iops(#[values(true, false)] use_cache: bool) {
@@ -698,6 +838,116 @@ mod tests {
         assert_eq!(io_stats.write_iops, 2); // txn + manifest
     }
 
+    /// Loses the race for the next version a number of times, as if another
+    /// writer had committed it first.
+    #[derive(Debug)]
+    struct RacingCommitHandler {
+        inner: Arc<dyn CommitHandler>,
+        lost_races: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl CommitHandler for RacingCommitHandler {
+        async fn commit(
+            &self,
+            manifest: &mut Manifest,
+            indices: Option<Vec<Index>>,
+            base_path: &Path,
+            object_store: &ObjectStore,
+            manifest_writer: ManifestWriter,
+            naming_scheme: ManifestNamingScheme,
+        ) -> std::result::Result<ManifestLocation, CommitError> {
+            let lost = self
+                .lost_races
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if lost {
+                return Err(CommitError::CommitConflict);
+            }
+            self.inner
+                .commit(
+                    manifest,
+                    indices,
+                    base_path,
+                    object_store,
+                    manifest_writer,
+                    naming_scheme,
+                )
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_append_rebase() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![ArrowField::new(
+                "a",
+                DataType::Int32,
+                false,
+            )])),
+            vec![Arc::new(Int32Array::from(vec![0; 5]))],
+        )
+        .unwrap();
+        let dataset = InsertBuilder::new(test_uri)
+            .execute(vec![batch.clone()])
+            .await
+            .unwrap();
+        let dataset = Arc::new(dataset);
+        let append_params = WriteParams {
+            mode: WriteMode::Append,
+            ..Default::default()
+        };
+        let append = || async {
+            InsertBuilder::new(dataset.clone())
+                .with_params(&append_params)
+                .execute_uncommitted(vec![batch.clone()])
+                .await
+        };
+
+        // Both appends are based on version 1, the second is rebased onto the first
+        let first = append().await.unwrap();
+        let second = append().await.unwrap();
+        CommitBuilder::new(dataset.clone())
+            .execute(first)
+            .await
+            .unwrap();
+        let committed = CommitBuilder::new(dataset.clone())
+            .with_max_retries(1)
+            .execute(second)
+            .await
+            .unwrap();
+        assert_eq!(committed.manifest.version, 3);
+        assert_eq!(committed.count_rows(None).await.unwrap(), 15);
+
+        // Losing the race for the next version is retried up to max_retries times
+        let racing = |lost_races| {
+            Arc::new(RacingCommitHandler {
+                inner: dataset.commit_handler.clone(),
+                lost_races: AtomicU32::new(lost_races),
+            })
+        };
+        let committed = CommitBuilder::new(test_uri)
+            .with_commit_handler(racing(2))
+            .with_max_retries(3)
+            .execute(append().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(committed.manifest.version, 4);
+        assert_eq!(committed.count_rows(None).await.unwrap(), 20);
+
+        let err = CommitBuilder::new(test_uri)
+            .with_commit_handler(racing(3))
+            .with_max_retries(3)
+            .execute(append().await.unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::CommitConflict { .. }), "{:?}", err);
+        let dataset = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(dataset.manifest.version, 4);
+    }
+
     #[tokio::test]
     async fn test_commit_batch() {
         // Create a dataset
@@ -735,6 +985,7 @@ mod tests {
             read_version: 1,
             blobs_op: None,
             tag: None,
+            transaction_properties: None,
         };
         let res = CommitBuilder::new(dataset.clone())
             .execute_batch(vec![update_transaction])
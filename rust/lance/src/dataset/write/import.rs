@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Import a directory of Parquet files into a Lance dataset.
+//!
+//! Each Parquet file is streamed into Lance data files and committed as its own
+//! append, so large directories can be migrated incrementally. Several files
+//! are converted concurrently, and the source path of each file is recorded in
+//! the transaction properties of the version that added it. An interrupted
+//! import can be restarted and will skip the files that were already committed.
+//!
+//! Resuming relies on the version history of the dataset, so old versions
+//! should not be cleaned up until the import has finished.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow_array::RecordBatchIterator;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{StreamExt, TryStreamExt};
+use lance_core::utils::tokio::get_num_compute_intensive_cpus;
+use lance_core::{Error, Result};
+use lance_io::object_store::{ObjectStore, ObjectStoreParams};
+use object_store::ObjectMeta;
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use snafu::location;
+use tracing::info;
+
+use super::commit::CommitBuilder;
+use super::insert::InsertBuilder;
+use super::{WriteMode, WriteParams};
+use crate::dataset::builder::DatasetBuilder;
+use crate::dataset::transaction::Operation;
+use crate::Dataset;
+
+pub mod delta;
+
+/// Transaction property recording which Parquet file a version was imported from.
+pub const IMPORT_SOURCE_KEY: &str = "lance.import.source";
+
+/// Parameters for [`import_parquet`].
+#[derive(Debug, Clone)]
+pub struct ParquetImportParams {
+    /// Parameters used to write the Lance data files.
+    ///
+    /// The `mode` is ignored: the dataset is created if it doesn't exist yet
+    /// and appended to otherwise.
+    pub write_params: WriteParams,
+
+    /// Parameters used to access the source directory. If not set, the
+    /// store parameters of `write_params` are used.
+    pub source_store_params: Option<ObjectStoreParams>,
+
+    /// Number of Parquet files converted concurrently.
+    pub parallelism: usize,
+
+    /// Number of rows in each batch read from the Parquet files.
+    pub batch_size: usize,
+}
+
+impl Default for ParquetImportParams {
+    fn default() -> Self {
+        Self {
+            write_params: WriteParams::default(),
+            source_store_params: None,
+            parallelism: get_num_compute_intensive_cpus(),
+            batch_size: 8192,
+        }
+    }
+}
+
+/// The outcome of [`import_parquet`].
+#[derive(Clone)]
+pub struct ParquetImportSummary {
+    /// The dataset after the last committed file.
+    pub dataset: Dataset,
+    /// Paths of the files imported by this call, in commit order.
+    pub imported_files: Vec<String>,
+    /// Paths of the files that had been imported by an earlier call.
+    pub skipped_files: Vec<String>,
+    /// Number of rows imported by this call.
+    pub num_rows: u64,
+}
+
+/// Import all Parquet files found under `source_uri` into the dataset at `dest_uri`.
+///
+/// The directory is listed recursively and files are imported in path order.
+/// All files must have a schema compatible with the dataset. If the dataset
+/// does not exist, it is created with the schema of the first file.
+pub async fn import_parquet(
+    source_uri: &str,
+    dest_uri: &str,
+    params: &ParquetImportParams,
+) -> Result<ParquetImportSummary> {
+    let write_params = &params.write_params;
+    let source_store_params = params
+        .source_store_params
+        .clone()
+        .or_else(|| write_params.store_params.clone())
+        .unwrap_or_default();
+    let (source_store, source_path) = ObjectStore::from_uri_and_params(
+        write_params.store_registry(),
+        source_uri,
+        &source_store_params,
+    )
+    .await?;
+
+    let mut files = source_store
+        .read_dir_all(&source_path, None)
+        .try_filter(|meta| futures::future::ready(meta.location.as_ref().ends_with(".parquet")))
+        .try_collect::<Vec<_>>()
+        .await?;
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+    if files.is_empty() {
+        return Err(Error::invalid_input(
+            format!("No Parquet files found in {}", source_uri),
+            location!(),
+        ));
+    }
+
+    let dataset = match load_dataset(dest_uri, write_params).await? {
+        Some(dataset) => dataset,
+        None => create_dataset(&source_store, &files[0], dest_uri, params).await?,
+    };
+
+    let already_imported = imported_sources(&dataset).await?;
+    let (skipped, pending): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|meta| already_imported.contains(meta.location.as_ref()));
+    let skipped_files = skipped
+        .into_iter()
+        .map(|meta| meta.location.to_string())
+        .collect::<Vec<_>>();
+    if !skipped_files.is_empty() {
+        info!(
+            "Skipping {} Parquet file(s) imported by a previous run",
+            skipped_files.len()
+        );
+    }
+
+    let append_params = WriteParams {
+        mode: WriteMode::Append,
+        ..write_params.clone()
+    };
+    let read_version = Arc::new(dataset);
+    let mut converted = futures::stream::iter(pending)
+        .map(|meta| {
+            let source_store = source_store.clone();
+            let read_version = read_version.clone();
+            let append_params = &append_params;
+            async move {
+                let stream = open_parquet(&source_store, &meta, params.batch_size).await?;
+                let transaction = InsertBuilder::new(read_version)
+                    .with_params(append_params)
+                    .execute_uncommitted_stream(stream)
+                    .await?;
+                Result::Ok((meta.location.to_string(), transaction))
+            }
+        })
+        // Files are written in parallel but committed in path order, which keeps the row
+        // order of the dataset the same from one run to the next
+        .buffered(params.parallelism.max(1));
+
+    let mut dataset = read_version.clone();
+    let mut imported_files = Vec::new();
+    let mut num_rows = 0;
+    // Commit each file as soon as it is written so that an interrupted import
+    // keeps the progress made so far.
+    while let Some((source, transaction)) = converted.try_next().await? {
+        if let Operation::Append { fragments } = &transaction.operation {
+            num_rows += fragments
+                .iter()
+                .map(|f| f.physical_rows.unwrap_or_default() as u64)
+                .sum::<u64>();
+        }
+        let mut commit_builder = CommitBuilder::new(dataset.clone()).with_transaction_properties(
+            HashMap::from([(IMPORT_SOURCE_KEY.to_string(), source.clone())]),
+        );
+        if let Some(store_params) = append_params.store_params.as_ref() {
+            commit_builder = commit_builder.with_store_params(store_params.clone());
+        }
+        dataset = Arc::new(commit_builder.execute(transaction).await?);
+        imported_files.push(source);
+    }
+
+    Ok(ParquetImportSummary {
+        dataset: Arc::unwrap_or_clone(dataset),
+        imported_files,
+        skipped_files,
+        num_rows,
+    })
+}
+
+async fn load_dataset(uri: &str, params: &WriteParams) -> Result<Option<Dataset>> {
+    let mut builder = DatasetBuilder::from_uri(uri);
+    if let Some(storage_options) = params
+        .store_params
+        .as_ref()
+        .and_then(|p| p.storage_options.clone())
+    {
+        builder = builder.with_storage_options(storage_options);
+    }
+    if let Some(session) = params.session.as_ref() {
+        builder = builder.with_session(session.clone());
+    }
+    match builder.load().await {
+        Ok(dataset) => Ok(Some(dataset)),
+        Err(Error::DatasetNotFound { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Create an empty dataset with the schema of the given Parquet file.
+async fn create_dataset(
+    source_store: &ObjectStore,
+    meta: &ObjectMeta,
+    uri: &str,
+    params: &ParquetImportParams,
+) -> Result<Dataset> {
+    let schema = open_parquet(source_store, meta, params.batch_size)
+        .await?
+        .schema();
+    let reader = RecordBatchIterator::new(vec![], schema);
+    let write_params = WriteParams {
+        mode: WriteMode::Create,
+        ..params.write_params.clone()
+    };
+    Dataset::write(reader, uri, Some(write_params)).await
+}
+
+/// Collect the source files recorded by previous imports.
+async fn imported_sources(dataset: &Dataset) -> Result<HashSet<String>> {
+    Ok(dataset
+        .versions()
+        .await?
+        .into_iter()
+        .filter_map(|version| version.metadata.get(IMPORT_SOURCE_KEY).cloned())
+        .collect())
+}
+
+async fn open_parquet(
+    store: &ObjectStore,
+    meta: &ObjectMeta,
+    batch_size: usize,
+) -> Result<SendableRecordBatchStream> {
+    let reader = ParquetObjectReader::new(store.inner.clone(), meta.location.clone())
+        .with_file_size(meta.size);
+    let stream = ParquetRecordBatchStreamBuilder::new(reader)
+        .await
+        .map_err(|e| parquet_error(meta, e))?
+        .with_batch_size(batch_size)
+        .build()
+        .map_err(|e| parquet_error(meta, e))?;
+    let schema = stream.schema().clone();
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        schema,
+        stream.map_err(|e| DataFusionError::External(Box::new(e))),
+    )))
+}
+
+fn parquet_error(meta: &ObjectMeta, err: parquet::errors::ParquetError) -> Error {
+    Error::io(
+        format!("Failed to read Parquet file {}: {}", meta.location, err),
+        location!(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int64Array, RecordBatch};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+    use parquet::arrow::ArrowWriter;
+
+    fn write_parquet(dir: &std::path::Path, name: &str, values: std::ops::Range<i64>) {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "id",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(values))],
+        )
+        .unwrap();
+        let file = std::fs::File::create(dir.join(name)).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_parquet() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let dest_dir = tempfile::tempdir().unwrap();
+        let source_uri = source_dir.path().to_str().unwrap();
+        let dest_uri = dest_dir.path().to_str().unwrap();
+        write_parquet(source_dir.path(), "a.parquet", 0..100);
+        write_parquet(source_dir.path(), "b.parquet", 100..250);
+        std::fs::write(source_dir.path().join("_SUCCESS"), b"").unwrap();
+
+        let params = ParquetImportParams {
+            parallelism: 2,
+            ..Default::default()
+        };
+        let summary = import_parquet(source_uri, dest_uri, &params).await.unwrap();
+        assert_eq!(summary.imported_files.len(), 2);
+        // Committed in path order, whichever file is written first
+        assert!(summary.imported_files[0].ends_with("a.parquet"));
+        assert!(summary.imported_files[1].ends_with("b.parquet"));
+        assert!(summary.skipped_files.is_empty());
+        assert_eq!(summary.num_rows, 250);
+        assert_eq!(summary.dataset.count_rows(None).await.unwrap(), 250);
+
+        // Re-running only picks up the new file.
+        write_parquet(source_dir.path(), "c.parquet", 250..300);
+        let summary = import_parquet(source_uri, dest_uri, &params).await.unwrap();
+        assert_eq!(summary.imported_files.len(), 1);
+        assert!(summary.imported_files[0].ends_with("c.parquet"));
+        assert_eq!(summary.skipped_files.len(), 2);
+        assert_eq!(summary.dataset.count_rows(None).await.unwrap(), 300);
+    }
+}
@@ -0,0 +1,780 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Import a Delta Lake table into a new Lance dataset.
+//!
+//! The transaction log in `_delta_log` is replayed (starting from the latest
+//! checkpoint, if there is one) to find the data files that make up the
+//! requested table version. Those Parquet files are then converted to Lance
+//! fragments and committed as the first data of the new dataset.
+//!
+//! Partition columns are not stored in Delta data files. By default they are
+//! restored from the partition values recorded in the log and appended to
+//! each row.
+//!
+//! Tables using deletion vectors or column mapping are not supported yet.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::{new_null_array, ArrayRef, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use chrono::Utc;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{StreamExt, TryStreamExt};
+use lance_core::utils::tokio::get_num_compute_intensive_cpus;
+use lance_core::{Error, Result};
+use lance_io::object_store::{ObjectStore, ObjectStoreParams};
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use serde::Deserialize;
+use snafu::location;
+
+use super::{open_parquet, parquet_error};
+use crate::dataset::transaction::{Operation, Transaction};
+use crate::dataset::write::commit::CommitBuilder;
+use crate::dataset::write::insert::InsertBuilder;
+use crate::dataset::{WriteMode, WriteParams};
+use crate::Dataset;
+
+/// Transaction property recording the Delta table version that was imported.
+pub const DELTA_VERSION_KEY: &str = "lance.import.delta.version";
+
+const DELTA_LOG_DIR: &str = "_delta_log";
+
+/// Parameters for [`import_delta`].
+#[derive(Debug, Clone)]
+pub struct DeltaImportParams {
+    /// Parameters used to write the Lance dataset. The `mode` is ignored,
+    /// the destination must not exist yet.
+    pub write_params: WriteParams,
+
+    /// Parameters used to access the Delta table. If not set, the store
+    /// parameters of `write_params` are used.
+    pub source_store_params: Option<ObjectStoreParams>,
+
+    /// Version of the Delta table to import. Defaults to the latest version.
+    pub version: Option<u64>,
+
+    /// Whether to add the partition columns to the Lance dataset.
+    pub preserve_partition_columns: bool,
+
+    /// Number of data files converted concurrently.
+    pub parallelism: usize,
+
+    /// Number of rows in each batch read from the data files.
+    pub batch_size: usize,
+}
+
+impl Default for DeltaImportParams {
+    fn default() -> Self {
+        Self {
+            write_params: WriteParams::default(),
+            source_store_params: None,
+            version: None,
+            preserve_partition_columns: true,
+            parallelism: get_num_compute_intensive_cpus(),
+            batch_size: 8192,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Action {
+    add: Option<AddAction>,
+    remove: Option<RemoveAction>,
+    meta_data: Option<MetadataAction>,
+    protocol: Option<ProtocolAction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddAction {
+    path: String,
+    #[serde(default)]
+    partition_values: HashMap<String, Option<String>>,
+    size: u64,
+    deletion_vector: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoveAction {
+    path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataAction {
+    schema_string: String,
+    #[serde(default)]
+    partition_columns: Vec<String>,
+    #[serde(default)]
+    configuration: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtocolAction {
+    #[serde(default)]
+    reader_features: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LastCheckpoint {
+    version: u64,
+    parts: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaSchema {
+    fields: Vec<DeltaField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeltaField {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: serde_json::Value,
+    nullable: bool,
+}
+
+/// The state of a Delta table at a given version.
+#[derive(Debug, Default)]
+struct Snapshot {
+    version: u64,
+    files: HashMap<String, AddAction>,
+    metadata: Option<MetadataAction>,
+}
+
+impl Snapshot {
+    fn apply(&mut self, action: Action) -> Result<()> {
+        if let Some(protocol) = action.protocol {
+            let features = protocol.reader_features.unwrap_or_default();
+            if let Some(feature) = features
+                .iter()
+                .find(|f| f.as_str() == "deletionVectors" || f.as_str() == "columnMapping")
+            {
+                return Err(Error::NotSupported {
+                    source: format!("Delta tables using {} cannot be imported", feature).into(),
+                    location: location!(),
+                });
+            }
+        }
+        if let Some(metadata) = action.meta_data {
+            self.metadata = Some(metadata);
+        }
+        if let Some(remove) = action.remove {
+            self.files.remove(&remove.path);
+        }
+        if let Some(add) = action.add {
+            self.files.insert(add.path.clone(), add);
+        }
+        Ok(())
+    }
+
+    fn apply_json(&mut self, data: &[u8]) -> Result<()> {
+        for line in data.split(|b| *b == b'\n') {
+            if line.iter().all(|b| b.is_ascii_whitespace()) {
+                continue;
+            }
+            let action: Action = serde_json::from_slice(line)?;
+            self.apply(action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Import the Delta Lake table at `source_uri` into a new dataset at `dest_uri`.
+pub async fn import_delta(
+    source_uri: &str,
+    dest_uri: &str,
+    params: &DeltaImportParams,
+) -> Result<Dataset> {
+    let write_params = &params.write_params;
+    let source_store_params = params
+        .source_store_params
+        .clone()
+        .or_else(|| write_params.store_params.clone())
+        .unwrap_or_default();
+    let (source_store, table_path) = ObjectStore::from_uri_and_params(
+        write_params.store_registry(),
+        source_uri,
+        &source_store_params,
+    )
+    .await?;
+
+    let snapshot = load_snapshot(&source_store, &table_path, params.version).await?;
+    let metadata = snapshot.metadata.as_ref().ok_or_else(|| {
+        Error::corrupt_file(
+            table_path.child(DELTA_LOG_DIR),
+            "Delta log does not contain table metadata",
+            location!(),
+        )
+    })?;
+    if let Some(Some(mode)) = metadata.configuration.get("delta.columnMapping.mode") {
+        if mode != "none" {
+            return Err(Error::NotSupported {
+                source: "Delta tables using column mapping cannot be imported".into(),
+                location: location!(),
+            });
+        }
+    }
+
+    let partition_fields = if params.preserve_partition_columns {
+        partition_fields(metadata)?
+    } else {
+        Vec::new()
+    };
+
+    let mut files = snapshot.files.into_values().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    if files.is_empty() {
+        return Err(Error::invalid_input(
+            format!("Delta table {} has no data files", source_uri),
+            location!(),
+        ));
+    }
+    let files = files
+        .into_iter()
+        .map(|add| {
+            if add.deletion_vector.is_some() {
+                return Err(Error::NotSupported {
+                    source: "Delta tables using deletion vectors cannot be imported".into(),
+                    location: location!(),
+                });
+            }
+            if add.path.contains("://") {
+                return Err(Error::NotSupported {
+                    source: format!(
+                        "Delta data file {} is outside of the table directory",
+                        add.path
+                    )
+                    .into(),
+                    location: location!(),
+                });
+            }
+            let relative = Path::from_url_path(&add.path)?;
+            let meta = ObjectMeta {
+                location: table_path.parts().chain(relative.parts()).collect(),
+                last_modified: Utc::now(),
+                size: add.size,
+                e_tag: None,
+                version: None,
+            };
+            Ok((meta, add.partition_values))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // The dataset schema is the schema of the data files plus the partition columns.
+    let schema = {
+        let (first, _) = &files[0];
+        let reader = parquet::arrow::async_reader::ParquetObjectReader::new(
+            source_store.inner.clone(),
+            first.location.clone(),
+        )
+        .with_file_size(first.size);
+        let builder = ParquetRecordBatchStreamBuilder::new(reader)
+            .await
+            .map_err(|e| parquet_error(first, e))?;
+        with_partition_fields(builder.schema(), &partition_fields)
+    };
+    let dataset = Dataset::write(
+        RecordBatchIterator::new(vec![], schema.clone()),
+        dest_uri,
+        Some(WriteParams {
+            mode: WriteMode::Create,
+            ..write_params.clone()
+        }),
+    )
+    .await?;
+    let dataset = Arc::new(dataset);
+
+    let append_params = WriteParams {
+        mode: WriteMode::Append,
+        ..write_params.clone()
+    };
+    let fragments = futures::stream::iter(files)
+        .map(|(meta, partition_values)| {
+            let source_store = source_store.clone();
+            let dataset = dataset.clone();
+            let append_params = &append_params;
+            let partition_fields = &partition_fields;
+            let schema = schema.clone();
+            async move {
+                let stream = open_parquet(&source_store, &meta, params.batch_size).await?;
+                let stream =
+                    add_partition_columns(stream, schema, partition_fields, &partition_values)?;
+                let transaction = InsertBuilder::new(dataset)
+                    .with_params(append_params)
+                    .execute_uncommitted_stream(stream)
+                    .await?;
+                match transaction.operation {
+                    Operation::Append { fragments } => Ok(fragments),
+                    _ => Err(Error::Internal {
+                        message: "Expected an append transaction".into(),
+                        location: location!(),
+                    }),
+                }
+            }
+        })
+        .buffered(params.parallelism.max(1))
+        .try_concat()
+        .await?;
+
+    // Commit all files at once so the dataset is never left half imported.
+    let transaction = Transaction::new(
+        dataset.manifest.version,
+        Operation::Append { fragments },
+        None,
+        None,
+    );
+    let mut commit_builder = CommitBuilder::new(dataset).with_transaction_properties(
+        HashMap::from([(DELTA_VERSION_KEY.to_string(), snapshot.version.to_string())]),
+    );
+    if let Some(store_params) = append_params.store_params.as_ref() {
+        commit_builder = commit_builder.with_store_params(store_params.clone());
+    }
+    commit_builder.execute(transaction).await
+}
+
+/// Replay the Delta log up to `version`, or the latest version if not given.
+async fn load_snapshot(
+    store: &ObjectStore,
+    table_path: &Path,
+    version: Option<u64>,
+) -> Result<Snapshot> {
+    let log_path = table_path.child(DELTA_LOG_DIR);
+    let mut commits = store
+        .read_dir_all(&log_path, None)
+        .try_filter_map(|meta| async move {
+            let version = meta
+                .location
+                .filename()
+                .and_then(|name| name.strip_suffix(".json"))
+                .filter(|stem| stem.len() == 20)
+                .and_then(|stem| stem.parse::<u64>().ok());
+            Ok(version.map(|version| (version, meta.location)))
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+    commits.sort_by_key(|(version, _)| *version);
+    let latest = commits.last().map(|(version, _)| *version);
+    let target = match (version, latest) {
+        (Some(version), _) => version,
+        (None, Some(latest)) => latest,
+        (None, None) => {
+            return Err(Error::DatasetNotFound {
+                path: table_path.to_string(),
+                source: "no Delta log found".into(),
+                location: location!(),
+            });
+        }
+    };
+
+    let mut snapshot = Snapshot {
+        version: target,
+        ..Default::default()
+    };
+    let mut next_version = 0;
+    let last_checkpoint_path = log_path.child("_last_checkpoint");
+    if store.exists(&last_checkpoint_path).await? {
+        let data = store.read_one_all(&last_checkpoint_path).await?;
+        let checkpoint: LastCheckpoint = serde_json::from_slice(&data)?;
+        if checkpoint.version <= target {
+            if checkpoint.parts.is_some() {
+                return Err(Error::NotSupported {
+                    source: "Multi-part Delta checkpoints are not supported".into(),
+                    location: location!(),
+                });
+            }
+            let path = log_path.child(format!("{:020}.checkpoint.parquet", checkpoint.version));
+            snapshot.apply_json(&read_checkpoint(store, &path).await?)?;
+            next_version = checkpoint.version + 1;
+        }
+    }
+
+    let commits = commits
+        .into_iter()
+        .filter(|(version, _)| *version >= next_version && *version <= target)
+        .collect::<Vec<_>>();
+    // Every version after the checkpoint must still be in the log.
+    let expected = (next_version..=target).collect::<Vec<_>>();
+    if commits.iter().map(|(v, _)| *v).collect::<Vec<_>>() != expected {
+        return Err(Error::invalid_input(
+            format!(
+                "Delta log for {} is incomplete, cannot reconstruct version {}",
+                table_path, target
+            ),
+            location!(),
+        ));
+    }
+    for (_, path) in commits {
+        snapshot.apply_json(&store.read_one_all(&path).await?)?;
+    }
+    Ok(snapshot)
+}
+
+/// Read a checkpoint and return its actions as newline delimited JSON, the
+/// same form as a commit file.
+async fn read_checkpoint(store: &ObjectStore, path: &Path) -> Result<Vec<u8>> {
+    let meta = store.inner.head(path).await?;
+    let batches = open_parquet(store, &meta, 8192)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    let mut writer = arrow::json::LineDelimitedWriter::new(Vec::new());
+    for batch in &batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    Ok(writer.into_inner())
+}
+
+fn partition_fields(metadata: &MetadataAction) -> Result<Vec<ArrowField>> {
+    let schema: DeltaSchema = serde_json::from_str(&metadata.schema_string)?;
+    metadata
+        .partition_columns
+        .iter()
+        .map(|name| {
+            let field = schema
+                .fields
+                .iter()
+                .find(|f| &f.name == name)
+                .ok_or_else(|| {
+                    Error::invalid_input(
+                        format!("Partition column {} is not in the Delta schema", name),
+                        location!(),
+                    )
+                })?;
+            let data_type = field
+                .data_type
+                .as_str()
+                .and_then(primitive_type)
+                .ok_or_else(|| Error::NotSupported {
+                    source: format!(
+                        "Partition column {} has unsupported type {}",
+                        name, field.data_type
+                    )
+                    .into(),
+                    location: location!(),
+                })?;
+            Ok(ArrowField::new(name, data_type, field.nullable))
+        })
+        .collect()
+}
+
+/// Map a primitive Delta type name to an Arrow type.
+fn primitive_type(name: &str) -> Option<DataType> {
+    let data_type = match name {
+        "string" => DataType::Utf8,
+        "long" => DataType::Int64,
+        "integer" => DataType::Int32,
+        "short" => DataType::Int16,
+        "byte" => DataType::Int8,
+        "float" => DataType::Float32,
+        "double" => DataType::Float64,
+        "boolean" => DataType::Boolean,
+        "binary" => DataType::Binary,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        "timestamp_ntz" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        _ => {
+            let (precision, scale) = name
+                .strip_prefix("decimal(")?
+                .strip_suffix(')')?
+                .split_once(',')?;
+            DataType::Decimal128(precision.trim().parse().ok()?, scale.trim().parse().ok()?)
+        }
+    };
+    Some(data_type)
+}
+
+fn with_partition_fields(schema: &SchemaRef, partition_fields: &[ArrowField]) -> SchemaRef {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| f.as_ref().clone())
+        .chain(partition_fields.iter().cloned())
+        .collect::<Vec<_>>();
+    Arc::new(ArrowSchema::new(fields))
+}
+
+/// Append constant partition columns to every batch of a data file.
+fn add_partition_columns(
+    stream: SendableRecordBatchStream,
+    schema: SchemaRef,
+    partition_fields: &[ArrowField],
+    partition_values: &HashMap<String, Option<String>>,
+) -> Result<SendableRecordBatchStream> {
+    if partition_fields.is_empty() {
+        return Ok(stream);
+    }
+    let values = partition_fields
+        .iter()
+        .map(|field| {
+            let value = partition_values.get(field.name()).cloned().flatten();
+            (field.data_type().clone(), value)
+        })
+        .collect::<Vec<_>>();
+    let output_schema = schema.clone();
+    let stream = stream.map(move |batch| {
+        let batch = batch?;
+        let mut columns = batch.columns().to_vec();
+        for (data_type, value) in &values {
+            let column: ArrayRef = match value {
+                Some(value) => arrow::compute::cast(
+                    &StringArray::from(vec![value.as_str(); batch.num_rows()]),
+                    data_type,
+                )?,
+                None => new_null_array(data_type, batch.num_rows()),
+            };
+            columns.push(column);
+        }
+        RecordBatch::try_new(output_schema.clone(), columns)
+            .map_err(|e| DataFusionError::ArrowError(e, None))
+    });
+    Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{cast::AsArray, types::Int64Type, Int64Array};
+    use parquet::arrow::ArrowWriter;
+
+    fn write_parquet(path: &std::path::Path, values: std::ops::Range<i64>) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(values))],
+        )
+        .unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    fn add(path: &str, dir: &std::path::Path, part: &str) -> String {
+        let size = std::fs::metadata(dir.join(path)).unwrap().len();
+        format!(
+            r#"{{"add":{{"path":"{}","partitionValues":{{"part":"{}"}},"size":{},"modificationTime":0,"dataChange":true}}}}"#,
+            path, part, size
+        )
+    }
+
+    #[tokio::test]
+    async fn test_import_delta() {
+        let table_dir = tempfile::tempdir().unwrap();
+        let table = table_dir.path();
+        write_parquet(&table.join("part=1/a.parquet"), 0..10);
+        write_parquet(&table.join("part=2/b.parquet"), 10..15);
+        write_parquet(&table.join("part=2/c.parquet"), 15..20);
+
+        let log_dir = table.join(DELTA_LOG_DIR);
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let schema_string = r#"{"type":"struct","fields":[{"name":"id","type":"long","nullable":false,"metadata":{}},{"name":"part","type":"long","nullable":true,"metadata":{}}]}"#;
+        let commit_0 = [
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#.to_string(),
+            serde_json::json!({"metaData": {
+                "id": "test",
+                "format": {"provider": "parquet", "options": {}},
+                "schemaString": schema_string,
+                "partitionColumns": ["part"],
+                "configuration": {},
+            }})
+            .to_string(),
+            add("part=1/a.parquet", table, "1"),
+            add("part=2/c.parquet", table, "2"),
+        ]
+        .join("\n");
+        std::fs::write(log_dir.join(format!("{:020}.json", 0)), commit_0).unwrap();
+        let commit_1 = [
+            add("part=2/b.parquet", table, "2"),
+            r#"{"remove":{"path":"part=2/c.parquet","dataChange":true}}"#.to_string(),
+        ]
+        .join("\n");
+        std::fs::write(log_dir.join(format!("{:020}.json", 1)), commit_1).unwrap();
+
+        let dest_dir = tempfile::tempdir().unwrap();
+        let dataset = import_delta(
+            table.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 15);
+        assert_eq!(
+            dataset.schema().field("part").unwrap().data_type(),
+            DataType::Int64
+        );
+        let mut scan = dataset.scan();
+        scan.filter("part = 2").unwrap();
+        let batch = scan.try_into_batch().await.unwrap();
+        let ids = batch["id"].as_primitive::<Int64Type>();
+        assert_eq!(ids.values().to_vec(), (10..15).collect::<Vec<_>>());
+
+        // Importing an older version picks the files live at that version.
+        let dest_dir = tempfile::tempdir().unwrap();
+        let params = DeltaImportParams {
+            version: Some(0),
+            preserve_partition_columns: false,
+            ..Default::default()
+        };
+        let dataset = import_delta(
+            table.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &params,
+        )
+        .await
+        .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 15);
+        assert!(dataset.schema().field("part").is_none());
+    }
+
+    /// Write the actions of a snapshot as a Delta checkpoint.
+    fn write_checkpoint(path: &std::path::Path, actions: &[String]) {
+        let data = actions.join("\n");
+        let (schema, _) = arrow::json::reader::infer_json_schema_from_seekable(
+            std::io::Cursor::new(data.as_bytes()),
+            None,
+        )
+        .unwrap();
+        let schema = Arc::new(schema);
+        let reader = arrow::json::ReaderBuilder::new(schema.clone())
+            .build(std::io::Cursor::new(data.as_bytes()))
+            .unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+        for batch in reader {
+            writer.write(&batch.unwrap()).unwrap();
+        }
+        writer.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_delta_checkpoint() {
+        // Delta writes a checkpoint every `delta.checkpointInterval` commits, 10 by default
+        const CHECKPOINT_INTERVAL: u64 = 10;
+
+        let table_dir = tempfile::tempdir().unwrap();
+        let table = table_dir.path();
+        let log_dir = table.join(DELTA_LOG_DIR);
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let schema_string = r#"{"type":"struct","fields":[{"name":"id","type":"long","nullable":false,"metadata":{}},{"name":"part","type":"long","nullable":true,"metadata":{}}]}"#;
+        let mut checkpoint = vec![
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#.to_string(),
+            serde_json::json!({"metaData": {
+                "id": "test",
+                "format": {"provider": "parquet"},
+                "schemaString": schema_string,
+                "partitionColumns": ["part"],
+            }})
+            .to_string(),
+        ];
+        // One data file per commit, with 5 rows each
+        for version in 0..=CHECKPOINT_INTERVAL + 1 {
+            let part = (version % 2).to_string();
+            let path = format!("part={}/{}.parquet", part, version);
+            let start = version as i64 * 5;
+            write_parquet(&table.join(&path), start..start + 5);
+            let mut actions = vec![add(&path, table, &part)];
+            if version == CHECKPOINT_INTERVAL + 1 {
+                actions.push(r#"{"remove":{"path":"part=1/1.parquet","dataChange":true}}"#.into());
+            }
+            if version <= CHECKPOINT_INTERVAL {
+                checkpoint.extend(actions.iter().cloned());
+            }
+            if version == 0 {
+                actions.splice(0..0, checkpoint[..2].iter().cloned());
+            }
+            std::fs::write(
+                log_dir.join(format!("{:020}.json", version)),
+                actions.join("\n"),
+            )
+            .unwrap();
+        }
+        write_checkpoint(
+            &log_dir.join(format!("{:020}.checkpoint.parquet", CHECKPOINT_INTERVAL)),
+            &checkpoint,
+        );
+        std::fs::write(
+            log_dir.join("_last_checkpoint"),
+            format!(
+                r#"{{"version":{},"size":{}}}"#,
+                CHECKPOINT_INTERVAL,
+                checkpoint.len()
+            ),
+        )
+        .unwrap();
+        // The commits before the checkpoint have been cleaned up, so the
+        // snapshot can only be rebuilt from the checkpoint.
+        for version in 0..CHECKPOINT_INTERVAL {
+            std::fs::remove_file(log_dir.join(format!("{:020}.json", version))).unwrap();
+        }
+
+        let dest_dir = tempfile::tempdir().unwrap();
+        let dataset = import_delta(
+            table.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 55);
+        assert_eq!(
+            dataset.manifest.transaction_properties[DELTA_VERSION_KEY],
+            (CHECKPOINT_INTERVAL + 1).to_string()
+        );
+        let mut scan = dataset.scan();
+        scan.filter("part = 1").unwrap();
+        let batch = scan.try_into_batch().await.unwrap();
+        let mut ids = batch["id"].as_primitive::<Int64Type>().values().to_vec();
+        ids.sort();
+        let expected = [3, 5, 7, 9, 11]
+            .into_iter()
+            .flat_map(|version| version * 5..version * 5 + 5)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+
+        // The checkpoint version itself needs no commit file
+        let dest_dir = tempfile::tempdir().unwrap();
+        let params = DeltaImportParams {
+            version: Some(CHECKPOINT_INTERVAL),
+            ..Default::default()
+        };
+        let dataset = import_delta(
+            table.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &params,
+        )
+        .await
+        .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 55);
+
+        // Versions before the checkpoint are gone
+        let dest_dir = tempfile::tempdir().unwrap();
+        let params = DeltaImportParams {
+            version: Some(CHECKPOINT_INTERVAL - 1),
+            ..Default::default()
+        };
+        assert!(import_delta(
+            table.to_str().unwrap(),
+            dest_dir.path().to_str().unwrap(),
+            &params,
+        )
+        .await
+        .is_err());
+    }
+}
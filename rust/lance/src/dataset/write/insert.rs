@@ -11,6 +11,7 @@ use humantime::format_duration;
 use lance_core::datatypes::NullabilityComparison;
 use lance_core::datatypes::Schema;
 use lance_core::datatypes::SchemaCompareOptions;
+use lance_core::datatypes::StorageClass;
 use lance_datafusion::utils::StreamingWriteSource;
 use lance_file::version::LanceFileVersion;
 use lance_io::object_store::ObjectStore;
@@ -22,11 +23,12 @@ use snafu::location;
 use crate::dataset::builder::DatasetBuilder;
 use crate::dataset::transaction::Operation;
 use crate::dataset::transaction::Transaction;
-use crate::dataset::write::write_fragments_internal;
+use crate::dataset::write::{apply_column_settings, write_fragments_internal};
 use crate::dataset::ReadParams;
 use crate::Dataset;
 use crate::{Error, Result};
 
+use super::checkpoint::{skip_rows, CheckpointProgress, WriteCheckpoint};
 use super::commit::CommitBuilder;
 use super::resolve_commit_handler;
 use super::WriteDestination;
@@ -49,6 +51,7 @@ pub struct InsertBuilder<'a> {
     dest: WriteDestination<'a>,
     // TODO: make these parameters a part of the builder, and add specific methods.
     params: Option<&'a WriteParams>,
+    checkpoint: Option<String>,
 }
 
 impl<'a> InsertBuilder<'a> {
@@ -56,6 +59,7 @@ impl<'a> InsertBuilder<'a> {
         Self {
             dest: dest.into(),
             params: None,
+            checkpoint: None,
         }
     }
 
@@ -64,12 +68,31 @@ impl<'a> InsertBuilder<'a> {
         self
     }
 
+    /// Make the write resumable, by recording each completed fragment in the
+    /// checkpoint `name`.
+    ///
+    /// If the write fails, running it again with the same checkpoint skips the
+    /// rows of the recorded fragments and commits them with the rest of the data,
+    /// instead of writing everything again. The source must then produce the same
+    /// rows in the same order. The checkpoint is removed once the write is
+    /// committed.
+    ///
+    /// The fragments of the checkpoint are not referenced by the dataset until the
+    /// commit, so they must not be cleaned up in the meantime. Checkpoints are not
+    /// supported by the `_uncommitted` methods, nor for blob columns.
+    pub fn with_checkpoint(mut self, name: impl Into<String>) -> Self {
+        self.checkpoint = Some(name.into());
+        self
+    }
+
     /// Execute the insert operation with the given data.
     ///
     /// This writes the data fragments and commits them into the dataset.
     pub async fn execute(&self, data: Vec<RecordBatch>) -> Result<Dataset> {
         let (transaction, context) = self.write_uncommitted_impl(data).await?;
-        Self::do_commit(&context, transaction).await
+        let dataset = Self::do_commit(&context, transaction).await?;
+        self.remove_checkpoint(&context).await?;
+        Ok(dataset)
     }
 
     /// Execute the insert operation with the given stream.
@@ -86,7 +109,9 @@ impl<'a> InsertBuilder<'a> {
         schema: Schema,
     ) -> Result<Dataset> {
         let (transaction, context) = self.write_uncommitted_stream_impl(stream, schema).await?;
-        Self::do_commit(&context, transaction).await
+        let dataset = Self::do_commit(&context, transaction).await?;
+        self.remove_checkpoint(&context).await?;
+        Ok(dataset)
     }
 
     /// Write data files, but don't commit the transaction yet.
@@ -114,9 +139,32 @@ impl<'a> InsertBuilder<'a> {
     /// # }
     /// ```
     pub async fn execute_uncommitted(&self, data: Vec<RecordBatch>) -> Result<Transaction> {
+        self.check_uncommitted()?;
         self.write_uncommitted_impl(data).await.map(|(t, _)| t)
     }
 
+    fn check_uncommitted(&self) -> Result<()> {
+        if self.checkpoint.is_some() {
+            return Err(Error::invalid_input(
+                "Checkpoints are only supported by writes that are committed",
+                location!(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn remove_checkpoint(&self, context: &WriteContext<'_>) -> Result<()> {
+        if let Some(name) = &self.checkpoint {
+            let path = WriteCheckpoint::path(&context.base_path, name);
+            // Nothing is checkpointed until the first fragment is complete
+            match context.object_store.inner.delete(&path).await {
+                Ok(_) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
     async fn do_commit(context: &WriteContext<'_>, transaction: Transaction) -> Result<Dataset> {
         let mut commit_builder = CommitBuilder::new(context.dest.clone())
             .use_move_stable_row_ids(context.params.enable_move_stable_row_ids)
@@ -169,6 +217,7 @@ impl<'a> InsertBuilder<'a> {
         &self,
         source: impl StreamingWriteSource,
     ) -> Result<Transaction> {
+        self.check_uncommitted()?;
         let (stream, schema) = source.into_stream_and_schema().await?;
         let (transaction, _) = self.write_uncommitted_stream_impl(stream, schema).await?;
         Ok(transaction)
@@ -183,7 +232,34 @@ impl<'a> InsertBuilder<'a> {
 
         self.validate_write(&mut context, &schema)?;
 
-        let written_frags = write_fragments_internal(
+        let (stream, checkpointed) = match &self.checkpoint {
+            Some(name) => {
+                if schema
+                    .fields
+                    .iter()
+                    .any(|field| field.storage_class() == StorageClass::Blob)
+                {
+                    return Err(Error::invalid_input(
+                        "Checkpoints are not supported for blob columns",
+                        location!(),
+                    ));
+                }
+                let path = WriteCheckpoint::path(&context.base_path, name);
+                let checkpoint = WriteCheckpoint::load(&context.object_store, &path).await?;
+                let stream = skip_rows(stream, checkpoint.num_rows);
+                let fragments = checkpoint.fragments.clone();
+                context.params.progress = Arc::new(CheckpointProgress::new(
+                    context.params.progress.clone(),
+                    context.object_store.clone(),
+                    path,
+                    checkpoint,
+                ));
+                (stream, fragments)
+            }
+            None => (stream, Vec::new()),
+        };
+
+        let mut written_frags = write_fragments_internal(
             context.dest.dataset(),
             context.object_store.clone(),
             &context.base_path,
@@ -192,6 +268,10 @@ impl<'a> InsertBuilder<'a> {
             context.params.clone(),
         )
         .await?;
+        if !checkpointed.is_empty() {
+            let fragments = std::mem::take(&mut written_frags.default.0);
+            written_frags.default.0 = checkpointed.into_iter().chain(fragments).collect();
+        }
 
         let transaction = Self::build_transaction(schema, written_frags, &context)?;
 
@@ -203,6 +283,8 @@ impl<'a> InsertBuilder<'a> {
         written_frags: WrittenFragments,
         context: &WriteContext<'_>,
     ) -> Result<Transaction> {
+        // The column options are kept in the schema of a new or overwritten dataset
+        let schema = apply_column_settings(schema, &context.params)?;
         let operation = match context.params.mode {
             WriteMode::Create => {
                 // Fetch auto_cleanup params from context
@@ -261,7 +343,7 @@ impl<'a> InsertBuilder<'a> {
             WriteMode::Append => Operation::Append { fragments: blob.0 },
         });
 
-        Ok(Transaction::new(
+        let transaction = Transaction::new(
             context
                 .dest
                 .dataset()
@@ -270,7 +352,12 @@ impl<'a> InsertBuilder<'a> {
             operation,
             blobs_op,
             None,
-        ))
+        )
+        .with_transaction_properties(context.params.transaction_properties.clone());
+        Ok(match &context.params.transaction_id {
+            Some(transaction_id) => transaction.with_transaction_id(transaction_id),
+            None => transaction,
+        })
     }
 
     fn validate_write(&self, context: &mut WriteContext, data_schema: &Schema) -> Result<()> {
@@ -356,6 +443,9 @@ impl<'a> InsertBuilder<'a> {
     }
 
     async fn resolve_context(&self) -> Result<WriteContext<'a>> {
+        if let WriteDestination::Dataset(dataset) = &self.dest {
+            dataset.ensure_writable("write to")?;
+        }
         let params = self.params.cloned().unwrap_or_default();
         let (object_store, base_path, commit_handler) = match &self.dest {
             WriteDestination::Dataset(dataset) => (
@@ -369,12 +459,13 @@ impl<'a> InsertBuilder<'a> {
                     .as_ref()
                     .map(|s| s.store_registry())
                     .unwrap_or_else(|| Arc::new(Default::default()));
-                let (object_store, base_path) = ObjectStore::from_uri_and_params(
-                    registry,
-                    uri,
-                    &params.store_params.clone().unwrap_or_default(),
-                )
-                .await?;
+                let store_params = params.store_params.clone().unwrap_or_default();
+                let store_params = match params.session.as_ref() {
+                    Some(session) => session.config().store_params(&store_params),
+                    None => store_params,
+                };
+                let (object_store, base_path) =
+                    ObjectStore::from_uri_and_params(registry, uri, &store_params).await?;
                 let commit_handler = resolve_commit_handler(
                     uri,
                     params.commit_handler.clone(),
@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Streaming readers for CSV and newline-delimited JSON files.
+//!
+//! The returned streams can be passed straight to
+//! [`super::InsertBuilder::execute_stream`]. Files are downloaded and decoded
+//! incrementally, so memory use is bounded by the batch size rather than the
+//! file size.
+//!
+//! Unless a schema is given, it is inferred from the first records of the file
+//! and individual column types can then be overridden.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::csv::reader::Format;
+use arrow_array::RecordBatch;
+use arrow_schema::{ArrowError, DataType, Field, Schema as ArrowSchema, SchemaRef};
+use bytes::{Buf, Bytes};
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{StreamExt, TryStreamExt};
+use lance_io::object_store::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use object_store::path::Path;
+use snafu::location;
+
+use lance_core::{Error, Result};
+
+/// Number of bytes read from the start of a file to infer its schema.
+const INFER_SCHEMA_BYTES: usize = 4 * 1024 * 1024;
+
+/// Options for [`read_csv`].
+#[derive(Debug, Clone)]
+pub struct CsvReadOptions {
+    /// The schema of the file. If not set, it is inferred.
+    pub schema: Option<SchemaRef>,
+    /// Types replacing the inferred type of the named columns.
+    pub column_types: HashMap<String, DataType>,
+    /// Whether the first line holds the column names.
+    pub has_header: bool,
+    pub delimiter: u8,
+    /// Maximum number of records used to infer the schema.
+    pub infer_max_records: usize,
+    /// Number of rows in each batch.
+    pub batch_size: usize,
+    pub store_params: Option<ObjectStoreParams>,
+}
+
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self {
+            schema: None,
+            column_types: HashMap::new(),
+            has_header: true,
+            delimiter: b',',
+            infer_max_records: 1000,
+            batch_size: 8192,
+            store_params: None,
+        }
+    }
+}
+
+/// Options for [`read_json`].
+#[derive(Debug, Clone)]
+pub struct JsonReadOptions {
+    /// The schema of the file. If not set, it is inferred.
+    pub schema: Option<SchemaRef>,
+    /// Types replacing the inferred type of the named columns.
+    pub column_types: HashMap<String, DataType>,
+    /// Maximum number of records used to infer the schema.
+    pub infer_max_records: usize,
+    /// Number of rows in each batch.
+    pub batch_size: usize,
+    pub store_params: Option<ObjectStoreParams>,
+}
+
+impl Default for JsonReadOptions {
+    fn default() -> Self {
+        Self {
+            schema: None,
+            column_types: HashMap::new(),
+            infer_max_records: 1000,
+            batch_size: 8192,
+            store_params: None,
+        }
+    }
+}
+
+/// Read a CSV file as a stream of record batches.
+pub async fn read_csv(uri: &str, options: &CsvReadOptions) -> Result<SendableRecordBatchStream> {
+    let (store, path) = open(uri, options.store_params.as_ref()).await?;
+    let schema = match options.schema.clone() {
+        Some(schema) => schema,
+        None => {
+            let prefix = read_prefix(&store, &path).await?;
+            let (schema, _) = Format::default()
+                .with_header(options.has_header)
+                .with_delimiter(options.delimiter)
+                .infer_schema(Cursor::new(prefix), Some(options.infer_max_records))?;
+            override_types(schema, &options.column_types)?
+        }
+    };
+    let decoder = arrow::csv::ReaderBuilder::new(schema.clone())
+        .with_header(options.has_header)
+        .with_delimiter(options.delimiter)
+        .with_batch_size(options.batch_size)
+        .build_decoder();
+    decode(&store, &path, schema, decoder).await
+}
+
+/// Read a newline-delimited JSON file as a stream of record batches.
+pub async fn read_json(uri: &str, options: &JsonReadOptions) -> Result<SendableRecordBatchStream> {
+    let (store, path) = open(uri, options.store_params.as_ref()).await?;
+    let schema = match options.schema.clone() {
+        Some(schema) => schema,
+        None => {
+            let prefix = read_prefix(&store, &path).await?;
+            let (schema, _) = arrow::json::reader::infer_json_schema(
+                Cursor::new(prefix),
+                Some(options.infer_max_records),
+            )?;
+            override_types(schema, &options.column_types)?
+        }
+    };
+    let decoder = arrow::json::ReaderBuilder::new(schema.clone())
+        .with_batch_size(options.batch_size)
+        .build_decoder()?;
+    decode(&store, &path, schema, decoder).await
+}
+
+async fn open(uri: &str, params: Option<&ObjectStoreParams>) -> Result<(Arc<ObjectStore>, Path)> {
+    ObjectStore::from_uri_and_params(
+        Arc::new(ObjectStoreRegistry::default()),
+        uri,
+        &params.cloned().unwrap_or_default(),
+    )
+    .await
+}
+
+/// Read the start of a file, cut at the last complete line.
+async fn read_prefix(store: &ObjectStore, path: &Path) -> Result<Bytes> {
+    let reader = store.open(path).await?;
+    let size = reader.size().await?;
+    let prefix = reader.get_range(0..size.min(INFER_SCHEMA_BYTES)).await?;
+    if prefix.len() == size {
+        return Ok(prefix);
+    }
+    let end = prefix
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map(|pos| pos + 1)
+        .unwrap_or(prefix.len());
+    Ok(prefix.slice(..end))
+}
+
+fn override_types(
+    schema: ArrowSchema,
+    column_types: &HashMap<String, DataType>,
+) -> Result<SchemaRef> {
+    for name in column_types.keys() {
+        if schema.field_with_name(name).is_err() {
+            return Err(Error::invalid_input(
+                format!("Column {} is not in the file", name),
+                location!(),
+            ));
+        }
+    }
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match column_types.get(field.name()) {
+            Some(data_type) => Arc::new(Field::new(
+                field.name(),
+                data_type.clone(),
+                field.is_nullable(),
+            )),
+            None => field.clone(),
+        })
+        .collect::<Vec<_>>();
+    Ok(Arc::new(ArrowSchema::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    )))
+}
+
+/// The push-based decoders of the CSV and JSON readers.
+trait BatchDecoder: Send + 'static {
+    /// Decode rows from `buf`, returning the number of bytes consumed. Stops
+    /// early once a full batch is buffered.
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError>;
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError>;
+}
+
+impl BatchDecoder for arrow::csv::reader::Decoder {
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError> {
+        Self::decode(self, buf)
+    }
+
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError> {
+        Self::flush(self)
+    }
+}
+
+impl BatchDecoder for arrow::json::reader::Decoder {
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError> {
+        Self::decode(self, buf)
+    }
+
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError> {
+        Self::flush(self)
+    }
+}
+
+async fn decode(
+    store: &ObjectStore,
+    path: &Path,
+    schema: SchemaRef,
+    decoder: impl BatchDecoder,
+) -> Result<SendableRecordBatchStream> {
+    let input = store
+        .inner
+        .get(path)
+        .await?
+        .into_stream()
+        .map_err(Error::from)
+        .fuse();
+    let batches = futures::stream::try_unfold(
+        (decoder, input, Bytes::new()),
+        |(mut decoder, mut input, mut buffered)| async move {
+            loop {
+                if buffered.is_empty() {
+                    match input.try_next().await? {
+                        Some(bytes) => buffered = bytes,
+                        None => break,
+                    }
+                }
+                let consumed = decoder.decode(&buffered)?;
+                if consumed == 0 {
+                    // A full batch is buffered.
+                    break;
+                }
+                buffered.advance(consumed);
+            }
+            Ok::<_, Error>(
+                decoder
+                    .flush()?
+                    .map(|batch| (batch, (decoder, input, buffered))),
+            )
+        },
+    );
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        schema,
+        batches.map_err(DataFusionError::from),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Array, Float64Array, Int64Array, StringArray};
+
+    use crate::dataset::InsertBuilder;
+
+    #[tokio::test]
+    async fn test_read_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let mut content = "id,name,score\n".to_string();
+        for i in 0..100 {
+            content.push_str(&format!("{},name-{},{}.5\n", i, i, i));
+        }
+        std::fs::write(&path, content).unwrap();
+
+        let options = CsvReadOptions {
+            batch_size: 16,
+            column_types: HashMap::from([("id".to_string(), DataType::Int32)]),
+            ..Default::default()
+        };
+        let stream = read_csv(path.to_str().unwrap(), &options).await.unwrap();
+        let schema = stream.schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(schema.field(2).data_type(), &DataType::Float64);
+
+        let uri = dir.path().join("dataset").to_str().unwrap().to_string();
+        let dataset = InsertBuilder::new(uri.as_str())
+            .execute_stream(stream)
+            .await
+            .unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 100);
+        let mut scan = dataset.scan();
+        scan.filter("id = 42").unwrap();
+        let batch = scan.try_into_batch().await.unwrap();
+        let score = batch["score"]
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(score.value(0), 42.5);
+    }
+
+    #[tokio::test]
+    async fn test_read_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.jsonl");
+        let content = (0..50)
+            .map(|i| format!("{{\"id\": {}, \"tag\": \"t{}\"}}\n", i, i % 3))
+            .collect::<String>();
+        std::fs::write(&path, content).unwrap();
+
+        let options = JsonReadOptions {
+            batch_size: 20,
+            ..Default::default()
+        };
+        let batches = read_json(path.to_str().unwrap(), &options)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![20, 20, 10]
+        );
+        let ids = batches[2]["id"]
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.value(9), 49);
+        let tags = batches[0]["tag"]
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(tags.value(4), "t1");
+        assert_eq!(tags.null_count(), 0);
+
+        let options = JsonReadOptions {
+            column_types: HashMap::from([("missing".to_string(), DataType::Int32)]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            read_json(path.to_str().unwrap(), &options).await,
+            Err(Error::InvalidInput { .. })
+        ));
+    }
+}
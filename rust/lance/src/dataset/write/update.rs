@@ -172,6 +172,7 @@ impl UpdateBuilder {
     // pub fn with_write_params(mut self, params: WriteParams) -> Self { ... }
 
     pub fn build(self) -> Result<UpdateJob> {
+        self.dataset.ensure_writable("update")?;
         if self
             .dataset
             .schema()
@@ -0,0 +1,646 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! C entry points for a DuckDB table function.
+//!
+//! The lifecycle mirrors DuckDB's table function callbacks:
+//!
+//! * bind: [`lance_duckdb_open`], [`lance_duckdb_schema`] and
+//!   [`lance_duckdb_cardinality`].
+//! * global init: [`lance_duckdb_scan_init`] with the projected column ids and
+//!   the table filters, translated by the extension into
+//!   [`LanceDuckDbFilter`] trees. [`lance_duckdb_scan_max_threads`] gives the
+//!   useful degree of parallelism.
+//! * local init: [`lance_duckdb_local_init`] for each DuckDB thread.
+//! * function: [`lance_duckdb_scan_next`], which fills one Arrow struct array
+//!   of at most [`LANCE_DUCKDB_VECTOR_SIZE`] rows.
+//!
+//! Threads claim whole fragments from a shared counter, so each fragment is
+//! read by exactly one thread. Errors are reported through
+//! [`crate::ffi::lance_last_error`].
+
+use std::ffi::{c_char, c_int};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::{Array, RecordBatch, StructArray};
+use arrow_schema::SchemaRef;
+use chrono::DateTime;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance_io::ffi::{c_str, guard, RT};
+use lance_table::format::Fragment;
+use snafu::location;
+
+use crate::dataset::builder::DatasetBuilder;
+use crate::dataset::scanner::Scanner;
+use crate::utils::filter::{quote_identifier, quote_string};
+use crate::{Dataset, Error, Result};
+
+/// DuckDB's `STANDARD_VECTOR_SIZE`; no chunk returned is larger.
+pub const LANCE_DUCKDB_VECTOR_SIZE: usize = 2048;
+
+/// Compare `column` with `value` using `comparison`.
+pub const LANCE_DUCKDB_FILTER_CONSTANT: c_int = 0;
+pub const LANCE_DUCKDB_FILTER_IS_NULL: c_int = 1;
+pub const LANCE_DUCKDB_FILTER_IS_NOT_NULL: c_int = 2;
+/// All `children` must hold.
+pub const LANCE_DUCKDB_FILTER_AND: c_int = 3;
+/// Any of `children` must hold.
+pub const LANCE_DUCKDB_FILTER_OR: c_int = 4;
+
+pub const LANCE_DUCKDB_COMPARE_EQ: c_int = 0;
+pub const LANCE_DUCKDB_COMPARE_NE: c_int = 1;
+pub const LANCE_DUCKDB_COMPARE_LT: c_int = 2;
+pub const LANCE_DUCKDB_COMPARE_LE: c_int = 3;
+pub const LANCE_DUCKDB_COMPARE_GT: c_int = 4;
+pub const LANCE_DUCKDB_COMPARE_GE: c_int = 5;
+
+pub const LANCE_DUCKDB_VALUE_NULL: c_int = 0;
+pub const LANCE_DUCKDB_VALUE_BOOLEAN: c_int = 1;
+/// `int_value` holds any integer type up to 64 bits.
+pub const LANCE_DUCKDB_VALUE_INTEGER: c_int = 2;
+pub const LANCE_DUCKDB_VALUE_DOUBLE: c_int = 3;
+pub const LANCE_DUCKDB_VALUE_VARCHAR: c_int = 4;
+/// `int_value` holds days since the epoch.
+pub const LANCE_DUCKDB_VALUE_DATE: c_int = 5;
+/// `int_value` holds microseconds since the epoch.
+pub const LANCE_DUCKDB_VALUE_TIMESTAMP: c_int = 6;
+
+/// A constant of a comparison filter.
+#[repr(C)]
+pub struct LanceDuckDbValue {
+    pub kind: c_int,
+    pub int_value: i64,
+    pub double_value: f64,
+    /// NUL-terminated UTF-8 string for `LANCE_DUCKDB_VALUE_VARCHAR`.
+    pub str_value: *const c_char,
+}
+
+/// A node of a DuckDB table filter.
+#[repr(C)]
+pub struct LanceDuckDbFilter {
+    pub kind: c_int,
+    /// Index of the column among the top-level fields of the dataset.
+    pub column: c_int,
+    pub comparison: c_int,
+    pub value: LanceDuckDbValue,
+    pub children: *const LanceDuckDbFilter,
+    pub num_children: usize,
+}
+
+/// A dataset opened by the bind callback.
+pub struct LanceDuckDbTable {
+    dataset: Arc<Dataset>,
+}
+
+/// The global state of a scan, shared by all threads.
+pub struct LanceDuckDbScan {
+    dataset: Arc<Dataset>,
+    columns: Vec<String>,
+    filter: Option<String>,
+    schema: SchemaRef,
+    fragments: Vec<Fragment>,
+    next_fragment: AtomicUsize,
+}
+
+/// The state of a scan on one thread.
+#[derive(Default)]
+pub struct LanceDuckDbLocalScan {
+    stream: Option<SendableRecordBatchStream>,
+    batch: Option<RecordBatch>,
+    offset: usize,
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::invalid_input(message, location!())
+}
+
+unsafe fn non_null<'a, T>(ptr: *const T, what: &str) -> Result<&'a T> {
+    ptr.as_ref()
+        .ok_or_else(|| invalid(format!("The {} must not be null", what)))
+}
+
+unsafe fn as_slice<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// Translate a filter into a Lance SQL predicate.
+///
+/// # Safety
+///
+/// All pointers in the tree must be valid.
+unsafe fn filter_to_sql(filter: &LanceDuckDbFilter, columns: &[String]) -> Result<String> {
+    let column = || {
+        usize::try_from(filter.column)
+            .ok()
+            .and_then(|idx| columns.get(idx))
+            .map(|name| quote_identifier(name))
+            .ok_or_else(|| invalid(format!("Invalid filter column {}", filter.column)))
+    };
+    let children = || {
+        as_slice(filter.children, filter.num_children)
+            .iter()
+            .map(|child| filter_to_sql(child, columns))
+            .collect::<Result<Vec<_>>>()
+    };
+    Ok(match filter.kind {
+        LANCE_DUCKDB_FILTER_CONSTANT => {
+            let op = match filter.comparison {
+                LANCE_DUCKDB_COMPARE_EQ => "=",
+                LANCE_DUCKDB_COMPARE_NE => "!=",
+                LANCE_DUCKDB_COMPARE_LT => "<",
+                LANCE_DUCKDB_COMPARE_LE => "<=",
+                LANCE_DUCKDB_COMPARE_GT => ">",
+                LANCE_DUCKDB_COMPARE_GE => ">=",
+                other => return Err(invalid(format!("Invalid comparison {}", other))),
+            };
+            format!("({} {} {})", column()?, op, value_to_sql(&filter.value)?)
+        }
+        LANCE_DUCKDB_FILTER_IS_NULL => format!("({} IS NULL)", column()?),
+        LANCE_DUCKDB_FILTER_IS_NOT_NULL => format!("({} IS NOT NULL)", column()?),
+        LANCE_DUCKDB_FILTER_AND | LANCE_DUCKDB_FILTER_OR => {
+            let children = children()?;
+            if children.is_empty() {
+                return Err(invalid("Conjunctions must have children"));
+            }
+            let op = if filter.kind == LANCE_DUCKDB_FILTER_AND {
+                " AND "
+            } else {
+                " OR "
+            };
+            format!("({})", children.join(op))
+        }
+        other => return Err(invalid(format!("Invalid filter kind {}", other))),
+    })
+}
+
+unsafe fn value_to_sql(value: &LanceDuckDbValue) -> Result<String> {
+    Ok(match value.kind {
+        LANCE_DUCKDB_VALUE_NULL => "NULL".to_string(),
+        LANCE_DUCKDB_VALUE_BOOLEAN => (value.int_value != 0).to_string(),
+        LANCE_DUCKDB_VALUE_INTEGER => value.int_value.to_string(),
+        LANCE_DUCKDB_VALUE_DOUBLE if value.double_value.is_finite() => {
+            format!("{:?}", value.double_value)
+        }
+        LANCE_DUCKDB_VALUE_VARCHAR => quote_string(
+            c_str(value.str_value)?.ok_or_else(|| invalid("String values must not be null"))?,
+        ),
+        LANCE_DUCKDB_VALUE_DATE => {
+            let date = DateTime::from_timestamp(value.int_value * 86_400, 0)
+                .ok_or_else(|| invalid("Date out of range"))?;
+            format!("date '{}'", date.format("%Y-%m-%d"))
+        }
+        LANCE_DUCKDB_VALUE_TIMESTAMP => {
+            let ts = DateTime::from_timestamp_micros(value.int_value)
+                .ok_or_else(|| invalid("Timestamp out of range"))?;
+            format!(
+                "timestamp '{}'",
+                ts.naive_utc().format("%Y-%m-%d %H:%M:%S%.6f")
+            )
+        }
+        other => return Err(invalid(format!("Unsupported value kind {}", other))),
+    })
+}
+
+/// Open a dataset. Returns null on failure.
+///
+/// # Safety
+///
+/// `uri` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_open(uri: *const c_char) -> *mut LanceDuckDbTable {
+    guard(std::ptr::null_mut(), || {
+        let uri = c_str(uri)?.ok_or_else(|| invalid("The dataset uri must not be null"))?;
+        let dataset = RT.block_on(DatasetBuilder::from_uri(uri).load())?;
+        Ok(Box::into_raw(Box::new(LanceDuckDbTable {
+            dataset: Arc::new(dataset),
+        })))
+    })
+}
+
+/// Close a dataset opened with [`lance_duckdb_open`].
+///
+/// # Safety
+///
+/// `table` must be null or returned by [`lance_duckdb_open`], and not used
+/// afterwards. Scans of the table remain valid.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_close(table: *mut LanceDuckDbTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+/// Export the schema of the dataset. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `table` must be valid and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_schema(
+    table: *const LanceDuckDbTable,
+    out: *mut FFI_ArrowSchema,
+) -> c_int {
+    guard(-1, || {
+        let table = non_null(table, "table")?;
+        let schema = arrow_schema::Schema::from(table.dataset.schema());
+        let schema = FFI_ArrowSchema::try_from(&schema)?;
+        std::ptr::write_unaligned(out, schema);
+        Ok(0)
+    })
+}
+
+/// The number of rows in the dataset, or -1 on failure.
+///
+/// # Safety
+///
+/// `table` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_cardinality(table: *const LanceDuckDbTable) -> i64 {
+    guard(-1, || {
+        let table = non_null(table, "table")?;
+        Ok(RT.block_on(table.dataset.count_rows(None))? as i64)
+    })
+}
+
+/// Start a scan of `num_columns` columns, given as indices of top-level
+/// fields, keeping rows that match all of the `num_filters` filters. Returns
+/// null on failure.
+///
+/// # Safety
+///
+/// `table` must be valid, `column_ids` must point to `num_columns` integers and
+/// `filters` to `num_filters` valid filter trees.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_scan_init(
+    table: *const LanceDuckDbTable,
+    column_ids: *const c_int,
+    num_columns: usize,
+    filters: *const LanceDuckDbFilter,
+    num_filters: usize,
+) -> *mut LanceDuckDbScan {
+    guard(std::ptr::null_mut(), || {
+        let dataset = non_null(table, "table")?.dataset.clone();
+        let fields = dataset
+            .schema()
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect::<Vec<_>>();
+        let columns = as_slice(column_ids, num_columns)
+            .iter()
+            .map(|id| {
+                usize::try_from(*id)
+                    .ok()
+                    .and_then(|idx| fields.get(idx))
+                    .cloned()
+                    .ok_or_else(|| invalid(format!("Invalid column id {}", id)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let filters = as_slice(filters, num_filters)
+            .iter()
+            .map(|filter| filter_to_sql(filter, &fields))
+            .collect::<Result<Vec<_>>>()?;
+        let filter = (!filters.is_empty()).then(|| filters.join(" AND "));
+
+        // Building the scanner up front validates the projection and filter
+        // before any thread starts.
+        let schema = RT.block_on(scanner(&dataset, &columns, filter.as_deref())?.schema())?;
+        Ok(Box::into_raw(Box::new(LanceDuckDbScan {
+            fragments: dataset
+                .get_fragments()
+                .iter()
+                .map(|fragment| fragment.metadata().clone())
+                .collect(),
+            dataset,
+            columns,
+            filter,
+            schema,
+            next_fragment: AtomicUsize::new(0),
+        })))
+    })
+}
+
+fn scanner(dataset: &Arc<Dataset>, columns: &[String], filter: Option<&str>) -> Result<Scanner> {
+    let mut scanner = dataset.scan();
+    scanner
+        .batch_size(LANCE_DUCKDB_VECTOR_SIZE)
+        .scan_in_order(false);
+    scanner.project(columns)?;
+    if let Some(filter) = filter {
+        scanner.filter(filter)?;
+    }
+    Ok(scanner)
+}
+
+impl LanceDuckDbScan {
+    /// Claim the next fragment and open a stream over it.
+    fn next_stream(&self) -> Result<Option<SendableRecordBatchStream>> {
+        let idx = self.next_fragment.fetch_add(1, Ordering::Relaxed);
+        let Some(fragment) = self.fragments.get(idx) else {
+            return Ok(None);
+        };
+        let mut scanner = scanner(&self.dataset, &self.columns, self.filter.as_deref())?;
+        scanner.with_fragments(vec![fragment.clone()]);
+        Ok(Some(RT.block_on(scanner.try_into_stream())?))
+    }
+}
+
+/// The maximum number of threads that can usefully scan in parallel.
+///
+/// # Safety
+///
+/// `scan` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_scan_max_threads(scan: *const LanceDuckDbScan) -> u64 {
+    scan.as_ref()
+        .map_or(1, |scan| scan.fragments.len().max(1) as u64)
+}
+
+/// Export the schema of the chunks produced by a scan. Returns 0 on success
+/// and -1 on failure.
+///
+/// # Safety
+///
+/// `scan` must be valid and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_scan_schema(
+    scan: *const LanceDuckDbScan,
+    out: *mut FFI_ArrowSchema,
+) -> c_int {
+    guard(-1, || {
+        let scan = non_null(scan, "scan")?;
+        let schema = FFI_ArrowSchema::try_from(scan.schema.as_ref())?;
+        std::ptr::write_unaligned(out, schema);
+        Ok(0)
+    })
+}
+
+/// Create the state of one scanning thread.
+#[no_mangle]
+pub extern "C" fn lance_duckdb_local_init() -> *mut LanceDuckDbLocalScan {
+    Box::into_raw(Box::default())
+}
+
+/// Produce the next chunk of a scan as an Arrow struct array.
+///
+/// Returns 1 when `out` was filled, 0 when the scan is exhausted and -1 on
+/// failure.
+///
+/// # Safety
+///
+/// `scan` must be valid, `local` must be valid and not used concurrently, and
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_scan_next(
+    scan: *const LanceDuckDbScan,
+    local: *mut LanceDuckDbLocalScan,
+    out: *mut FFI_ArrowArray,
+) -> c_int {
+    guard(-1, || {
+        let scan = non_null(scan, "scan")?;
+        let local = local
+            .as_mut()
+            .ok_or_else(|| invalid("The local state must not be null"))?;
+        let Some(chunk) = local.next_chunk(scan)? else {
+            return Ok(0);
+        };
+        let (array, _) = to_ffi(&StructArray::from(chunk).into_data())?;
+        std::ptr::write_unaligned(out, array);
+        Ok(1)
+    })
+}
+
+impl LanceDuckDbLocalScan {
+    fn next_chunk(&mut self, scan: &LanceDuckDbScan) -> Result<Option<RecordBatch>> {
+        loop {
+            if let Some(batch) = &self.batch {
+                if self.offset < batch.num_rows() {
+                    let len = (batch.num_rows() - self.offset).min(LANCE_DUCKDB_VECTOR_SIZE);
+                    let chunk = batch.slice(self.offset, len);
+                    self.offset += len;
+                    return Ok(Some(chunk));
+                }
+                self.batch = None;
+            }
+            if let Some(stream) = &mut self.stream {
+                match RT.block_on(stream.try_next())? {
+                    Some(batch) => {
+                        self.batch = Some(batch);
+                        self.offset = 0;
+                    }
+                    None => self.stream = None,
+                }
+                continue;
+            }
+            match scan.next_stream()? {
+                Some(stream) => self.stream = Some(stream),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Release the state of a scanning thread.
+///
+/// # Safety
+///
+/// `local` must be null or returned by [`lance_duckdb_local_init`].
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_local_close(local: *mut LanceDuckDbLocalScan) {
+    if !local.is_null() {
+        drop(Box::from_raw(local));
+    }
+}
+
+/// Release a scan once all threads are done with it.
+///
+/// # Safety
+///
+/// `scan` must be null or returned by [`lance_duckdb_scan_init`].
+#[no_mangle]
+pub unsafe extern "C" fn lance_duckdb_scan_close(scan: *mut LanceDuckDbScan) {
+    if !scan.is_null() {
+        drop(Box::from_raw(scan));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ffi::CString;
+
+    use arrow::ffi::from_ffi;
+    use arrow_array::{Int32Array, RecordBatchIterator, StringArray};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+
+    use crate::dataset::WriteParams;
+
+    fn value(kind: c_int, int_value: i64) -> LanceDuckDbValue {
+        LanceDuckDbValue {
+            kind,
+            int_value,
+            double_value: 0.0,
+            str_value: std::ptr::null(),
+        }
+    }
+
+    fn filter(
+        kind: c_int,
+        column: c_int,
+        comparison: c_int,
+        value: LanceDuckDbValue,
+    ) -> LanceDuckDbFilter {
+        LanceDuckDbFilter {
+            kind,
+            column,
+            comparison,
+            value,
+            children: std::ptr::null(),
+            num_children: 0,
+        }
+    }
+
+    #[test]
+    fn test_filter_to_sql() {
+        let columns = vec!["id".to_string(), "day".to_string(), "a b".to_string()];
+        let text = CString::new("it's").unwrap();
+        let children = [
+            filter(
+                LANCE_DUCKDB_FILTER_CONSTANT,
+                1,
+                LANCE_DUCKDB_COMPARE_GE,
+                value(LANCE_DUCKDB_VALUE_DATE, 19_000),
+            ),
+            filter(
+                LANCE_DUCKDB_FILTER_CONSTANT,
+                2,
+                LANCE_DUCKDB_COMPARE_EQ,
+                LanceDuckDbValue {
+                    str_value: text.as_ptr(),
+                    ..value(LANCE_DUCKDB_VALUE_VARCHAR, 0)
+                },
+            ),
+            filter(LANCE_DUCKDB_FILTER_IS_NULL, 0, 0, value(0, 0)),
+        ];
+        let or = LanceDuckDbFilter {
+            children: children.as_ptr(),
+            num_children: children.len(),
+            ..filter(LANCE_DUCKDB_FILTER_OR, 0, 0, value(0, 0))
+        };
+        assert_eq!(
+            unsafe { filter_to_sql(&or, &columns) }.unwrap(),
+            "((day >= date '2022-01-08') OR (`a b` = 'it''s') OR (id IS NULL))"
+        );
+
+        let bad = filter(
+            LANCE_DUCKDB_FILTER_CONSTANT,
+            7,
+            LANCE_DUCKDB_COMPARE_EQ,
+            value(LANCE_DUCKDB_VALUE_INTEGER, 1),
+        );
+        assert!(unsafe { filter_to_sql(&bad, &columns) }.is_err());
+    }
+
+    #[test]
+    fn test_parallel_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = dir.path().to_str().unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10_000)),
+                Arc::new(StringArray::from_iter(
+                    (0..10_000).map(|i| (i % 2 == 0).then(|| i.to_string())),
+                )),
+            ],
+        )
+        .unwrap();
+        let params = WriteParams {
+            max_rows_per_file: 3_000,
+            max_rows_per_group: 3_000,
+            ..Default::default()
+        };
+        RT.block_on(Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            uri,
+            Some(params),
+        ))
+        .unwrap();
+
+        let c_uri = CString::new(uri).unwrap();
+        let table = unsafe { lance_duckdb_open(c_uri.as_ptr()) };
+        assert!(!table.is_null());
+        assert_eq!(unsafe { lance_duckdb_cardinality(table) }, 10_000);
+
+        let columns = [0];
+        let filters = [
+            filter(
+                LANCE_DUCKDB_FILTER_CONSTANT,
+                0,
+                LANCE_DUCKDB_COMPARE_GE,
+                value(LANCE_DUCKDB_VALUE_INTEGER, 1_000),
+            ),
+            filter(LANCE_DUCKDB_FILTER_IS_NOT_NULL, 1, 0, value(0, 0)),
+        ];
+        let scan =
+            unsafe { lance_duckdb_scan_init(table, columns.as_ptr(), 1, filters.as_ptr(), 2) };
+        assert!(!scan.is_null());
+        unsafe { lance_duckdb_close(table) };
+        assert_eq!(unsafe { lance_duckdb_scan_max_threads(scan) }, 4);
+
+        let mut schema = FFI_ArrowSchema::empty();
+        assert_eq!(unsafe { lance_duckdb_scan_schema(scan, &mut schema) }, 0);
+
+        let scan_addr = scan as usize;
+        let threads = (0..3)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let scan = scan_addr as *const LanceDuckDbScan;
+                    let local = lance_duckdb_local_init();
+                    let mut ids = Vec::new();
+                    loop {
+                        let mut array = FFI_ArrowArray::empty();
+                        match unsafe { lance_duckdb_scan_next(scan, local, &mut array) } {
+                            0 => break,
+                            1 => {}
+                            _ => panic!("scan failed"),
+                        }
+                        let mut schema = FFI_ArrowSchema::empty();
+                        unsafe { lance_duckdb_scan_schema(scan, &mut schema) };
+                        let data = unsafe { from_ffi(array, &schema) }.unwrap();
+                        let chunk = StructArray::from(data);
+                        assert!(chunk.len() <= LANCE_DUCKDB_VECTOR_SIZE);
+                        let column = chunk
+                            .column(0)
+                            .as_any()
+                            .downcast_ref::<Int32Array>()
+                            .unwrap();
+                        ids.extend(column.values().iter().copied());
+                    }
+                    unsafe { lance_duckdb_local_close(local) };
+                    ids
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut ids = threads
+            .into_iter()
+            .flat_map(|t| t.join().unwrap())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, (1_000..10_000).step_by(2).collect::<Vec<_>>());
+        unsafe { lance_duckdb_scan_close(scan) };
+    }
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! C entry points exporting scans as Arrow C streams.
+//!
+//! Engines that speak the [Arrow C stream interface] can read a dataset
+//! without going through another language binding:
+//!
+//! ```c
+//! struct ArrowArrayStream stream;
+//! const char* columns[] = {"id", "vector"};
+//! if (lance_scan_to_arrow_stream("s3://bucket/items.lance", -1, columns, 2,
+//!                                "id > 10", &stream) != 0) {
+//!   fprintf(stderr, "%s\n", lance_last_error());
+//! }
+//! ```
+//!
+//! Batches are produced lazily as the consumer calls `get_next`. The scan runs
+//! on a runtime owned by this module, so the stream can be consumed from any
+//! thread, but not from within a Tokio runtime.
+//!
+//! [Arrow C stream interface]: https://arrow.apache.org/docs/format/CStreamInterface.html
+
+use std::ffi::{c_char, c_int};
+
+use arrow::ffi_stream::FFI_ArrowArrayStream;
+use lance_io::ffi::{c_str, guard, last_error, to_ffi_arrow_array_stream, RT};
+use snafu::location;
+
+use crate::dataset::builder::DatasetBuilder;
+use crate::{Error, Result};
+
+/// The message of the last error raised on the calling thread, or null.
+///
+/// The pointer stays valid until the next error is raised on the same thread,
+/// including errors of the Lance file API.
+#[no_mangle]
+pub extern "C" fn lance_last_error() -> *const c_char {
+    last_error()
+}
+
+/// Scan a dataset into an Arrow C stream.
+///
+/// * `version` selects the version to read; a negative value reads the latest.
+/// * `columns` is an array of `num_columns` column names, or null to read
+///   all columns.
+/// * `filter` is an optional SQL predicate.
+///
+/// On success `out` is initialized and 0 is returned. The caller owns the
+/// stream and must call its `release` callback. On failure -1 is returned and
+/// the error is available from [`lance_last_error`].
+///
+/// # Safety
+///
+/// `uri` and `filter` must be null or valid NUL-terminated strings, `columns`
+/// must point to `num_columns` such strings, and `out` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn lance_scan_to_arrow_stream(
+    uri: *const c_char,
+    version: i64,
+    columns: *const *const c_char,
+    num_columns: usize,
+    filter: *const c_char,
+    out: *mut FFI_ArrowArrayStream,
+) -> c_int {
+    guard(-1, || {
+        let uri = c_str(uri)?
+            .ok_or_else(|| Error::invalid_input("The dataset uri must not be null", location!()))?;
+        let columns = if columns.is_null() {
+            None
+        } else {
+            Some(
+                (0..num_columns)
+                    .map(|i| {
+                        c_str(*columns.add(i))?.ok_or_else(|| {
+                            Error::invalid_input("Column names must not be null", location!())
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        };
+        let filter = c_str(filter)?;
+        if out.is_null() {
+            return Err(Error::invalid_input(
+                "The output stream must not be null",
+                location!(),
+            ));
+        }
+        let version = u64::try_from(version).ok();
+        let stream = scan_to_arrow_stream(uri, version, columns, filter)?;
+        std::ptr::write_unaligned(out, stream);
+        Ok(0)
+    })
+}
+
+fn scan_to_arrow_stream(
+    uri: &str,
+    version: Option<u64>,
+    columns: Option<Vec<&str>>,
+    filter: Option<&str>,
+) -> Result<FFI_ArrowArrayStream> {
+    let stream = RT.block_on(async {
+        let mut builder = DatasetBuilder::from_uri(uri);
+        if let Some(version) = version {
+            builder = builder.with_version(version);
+        }
+        let dataset = builder.load().await?;
+        let mut scanner = dataset.scan();
+        if let Some(columns) = columns {
+            scanner.project(&columns)?;
+        }
+        if let Some(filter) = filter {
+            scanner.filter(filter)?;
+        }
+        scanner.try_into_stream().await
+    })?;
+    to_ffi_arrow_array_stream(stream, RT.handle().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ffi::{CStr, CString};
+    use std::sync::Arc;
+
+    use arrow::ffi_stream::ArrowArrayStreamReader;
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator, RecordBatchReader};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+
+    use crate::Dataset;
+
+    #[test]
+    fn test_scan_to_arrow_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = dir.path().to_str().unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("value", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..100)),
+                Arc::new(Int32Array::from_iter_values(100..200)),
+            ],
+        )
+        .unwrap();
+        RT.block_on(Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            uri,
+            None,
+        ))
+        .unwrap();
+
+        let c_uri = CString::new(uri).unwrap();
+        let column = CString::new("value").unwrap();
+        let columns = [column.as_ptr()];
+        let filter = CString::new("id >= 90").unwrap();
+        let mut stream = FFI_ArrowArrayStream::empty();
+        let rc = unsafe {
+            lance_scan_to_arrow_stream(
+                c_uri.as_ptr(),
+                -1,
+                columns.as_ptr(),
+                1,
+                filter.as_ptr(),
+                &mut stream,
+            )
+        };
+        assert_eq!(rc, 0);
+        let reader = ArrowArrayStreamReader::try_new(stream).unwrap();
+        assert_eq!(reader.schema().fields().len(), 1);
+        let num_rows = reader.map(|b| b.unwrap().num_rows()).sum::<usize>();
+        assert_eq!(num_rows, 10);
+
+        let missing = CString::new(format!("{}/missing", uri)).unwrap();
+        let mut stream = FFI_ArrowArrayStream::empty();
+        let rc = unsafe {
+            lance_scan_to_arrow_stream(
+                missing.as_ptr(),
+                -1,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+                &mut stream,
+            )
+        };
+        assert_eq!(rc, -1);
+        let message = unsafe { CStr::from_ptr(lance_last_error()) };
+        assert!(message.to_str().unwrap().contains("not found"));
+    }
+}
@@ -0,0 +1,476 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Serve datasets over [Arrow Flight](https://arrow.apache.org/docs/format/Flight.html).
+//!
+//! [`LanceFlightService`] exposes the datasets found under a root URI. A
+//! dataset is addressed by its path relative to the root, either as a `PATH`
+//! flight descriptor or through the `dataset` field of a [`ScanTicket`].
+//!
+//! * `GetFlightInfo` / `GetSchema` accept a `PATH` descriptor or a `CMD`
+//!   descriptor holding an encoded [`ScanTicket`]. The returned endpoint ticket
+//!   pins the version that was resolved, so the scan is consistent.
+//! * `DoGet` takes an encoded [`ScanTicket`] and streams the matching rows.
+//! * `DoPut` appends the uploaded batches to the dataset named by the `PATH`
+//!   descriptor of the first message, creating it if needed. The result
+//!   metadata is the new version number as a decimal string.
+//!
+//! Tickets are protobuf messages so they can be built by clients in any
+//! language; see [`ScanTicket`] for the field numbers.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use arrow_ipc::writer::IpcWriteOptions;
+use arrow_schema::Schema as ArrowSchema;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt, TryStreamExt};
+use lance_io::object_store::ObjectStoreParams;
+use prost::Message;
+use snafu::location;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::dataset::builder::DatasetBuilder;
+use crate::dataset::{InsertBuilder, WriteMode, WriteParams};
+use crate::session::Session;
+use crate::{Dataset, Error, Result};
+
+#[cfg(feature = "flight-sql")]
+pub mod sql;
+
+/// A scan request, used as the Flight ticket of `DoGet`.
+#[derive(Clone, PartialEq, Message)]
+pub struct ScanTicket {
+    /// Path of the dataset, relative to the root of the service.
+    #[prost(string, tag = "1")]
+    pub dataset: String,
+    /// Version to read. The latest version is read if not set.
+    #[prost(uint64, optional, tag = "2")]
+    pub version: Option<u64>,
+    /// Columns to return. All columns are returned if empty.
+    #[prost(string, repeated, tag = "3")]
+    pub columns: Vec<String>,
+    /// Filter, as a serialized Substrait `ExtendedExpression`.
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub substrait_filter: Option<Vec<u8>>,
+    /// Maximum number of rows to return.
+    #[prost(int64, optional, tag = "5")]
+    pub limit: Option<i64>,
+}
+
+/// An Arrow Flight service reading and appending to the datasets under a root URI.
+#[derive(Debug, Clone)]
+pub struct LanceFlightService {
+    root: String,
+    session: Arc<Session>,
+    storage_options: HashMap<String, String>,
+}
+
+impl LanceFlightService {
+    pub fn new(root: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            session: Arc::new(Session::default()),
+            storage_options: HashMap::new(),
+        }
+    }
+
+    /// Share caches and the object store registry with other datasets.
+    pub fn with_session(mut self, session: Arc<Session>) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn with_storage_options(mut self, storage_options: HashMap<String, String>) -> Self {
+        self.storage_options = storage_options;
+        self
+    }
+
+    /// Wrap the service in a tonic server that can be added to a router.
+    pub fn into_server(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    fn dataset_uri(&self, path: &str) -> Result<String> {
+        let path = path.trim_matches('/');
+        if path.is_empty()
+            || path
+                .split('/')
+                .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            return Err(Error::invalid_input(
+                format!("Invalid dataset path: {}", path),
+                location!(),
+            ));
+        }
+        Ok(format!("{}/{}", self.root.trim_end_matches('/'), path))
+    }
+
+    async fn load(&self, path: &str, version: Option<u64>) -> Result<Dataset> {
+        let mut builder = DatasetBuilder::from_uri(self.dataset_uri(path)?)
+            .with_session(self.session.clone())
+            .with_storage_options(self.storage_options.clone());
+        if let Some(version) = version {
+            builder = builder.with_version(version);
+        }
+        builder.load().await
+    }
+
+    /// Resolve a descriptor to a scan of a specific version.
+    async fn resolve(&self, descriptor: &FlightDescriptor) -> Result<(ScanTicket, Dataset)> {
+        let mut ticket = match descriptor.r#type() {
+            DescriptorType::Path => ScanTicket {
+                dataset: descriptor.path.join("/"),
+                ..Default::default()
+            },
+            DescriptorType::Cmd => ScanTicket::decode(descriptor.cmd.clone())
+                .map_err(|e| Error::invalid_input(e.to_string(), location!()))?,
+            DescriptorType::Unknown => {
+                return Err(Error::invalid_input(
+                    "Flight descriptor type must be PATH or CMD",
+                    location!(),
+                ))
+            }
+        };
+        let dataset = self.load(&ticket.dataset, ticket.version).await?;
+        ticket.version = Some(dataset.version().version);
+        Ok((ticket, dataset))
+    }
+
+    async fn scan(&self, ticket: &ScanTicket) -> Result<SendableRecordBatchStream> {
+        let dataset = self.load(&ticket.dataset, ticket.version).await?;
+        let mut scanner = dataset.scan();
+        if !ticket.columns.is_empty() {
+            scanner.project(&ticket.columns)?;
+        }
+        if let Some(filter) = ticket.substrait_filter.as_ref() {
+            scanner.filter_substrait(filter)?;
+        }
+        if ticket.limit.is_some() {
+            scanner.limit(ticket.limit, None)?;
+        }
+        scanner
+            .try_into_stream()
+            .await
+            .map(SendableRecordBatchStream::from)
+    }
+
+    /// Append a stream of batches to the dataset at `path`, creating it if needed.
+    async fn append(&self, path: &str, stream: SendableRecordBatchStream) -> Result<Dataset> {
+        let params = WriteParams {
+            mode: WriteMode::Append,
+            session: Some(self.session.clone()),
+            store_params: Some(ObjectStoreParams {
+                storage_options: Some(self.storage_options.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        match self.load(path, None).await {
+            Ok(dataset) => {
+                InsertBuilder::new(Arc::new(dataset))
+                    .with_params(&params)
+                    .execute_stream(stream)
+                    .await
+            }
+            Err(Error::DatasetNotFound { .. }) => {
+                let uri = self.dataset_uri(path)?;
+                InsertBuilder::new(&uri)
+                    .with_params(&params)
+                    .execute_stream(stream)
+                    .await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn schema_result(schema: &ArrowSchema) -> std::result::Result<SchemaResult, Status> {
+        SchemaAsIpc::new(schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(|e: arrow_schema::ArrowError| Status::internal(e.to_string()))
+    }
+}
+
+fn to_status(err: Error) -> Status {
+    match err {
+        Error::DatasetNotFound { .. } => Status::not_found(err.to_string()),
+        Error::InvalidInput { .. } | Error::SchemaMismatch { .. } => {
+            Status::invalid_argument(err.to_string())
+        }
+        Error::NotSupported { .. } => Status::unimplemented(err.to_string()),
+        Error::CommitConflict { .. } | Error::RetryableCommitConflict { .. } => {
+            Status::aborted(err.to_string())
+        }
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+type FlightStream<T> = Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl FlightService for LanceFlightService {
+    type HandshakeStream = FlightStream<HandshakeResponse>;
+    type ListFlightsStream = FlightStream<FlightInfo>;
+    type DoGetStream = FlightStream<FlightData>;
+    type DoPutStream = FlightStream<PutResult>;
+    type DoActionStream = FlightStream<arrow_flight::Result>;
+    type ListActionsStream = FlightStream<ActionType>;
+    type DoExchangeStream = FlightStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> std::result::Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("Handshake is not supported"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> std::result::Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("ListFlights is not supported"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        let (ticket, dataset) = self.resolve(&descriptor).await.map_err(to_status)?;
+        let mut schema = ArrowSchema::from(dataset.schema());
+        if !ticket.columns.is_empty() {
+            schema = schema
+                .project(
+                    &ticket
+                        .columns
+                        .iter()
+                        .map(|c| schema.index_of(c))
+                        .collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(|e| Status::invalid_argument(e.to_string()))?,
+                )
+                .map_err(|e| Status::internal(e.to_string()))?;
+        }
+        let mut info = FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(ticket.encode_to_vec())))
+            .with_descriptor(descriptor);
+        if ticket.substrait_filter.is_none() && ticket.limit.is_none() {
+            let num_rows = dataset.count_rows(None).await.map_err(to_status)?;
+            info = info.with_total_records(num_rows as i64);
+        }
+        Ok(Response::new(info))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("PollFlightInfo is not supported"))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<SchemaResult>, Status> {
+        let (_, dataset) = self
+            .resolve(&request.into_inner())
+            .await
+            .map_err(to_status)?;
+        let schema = ArrowSchema::from(dataset.schema());
+        Ok(Response::new(Self::schema_result(&schema)?))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<Self::DoGetStream>, Status> {
+        let ticket = ScanTicket::decode(request.into_inner().ticket)
+            .map_err(|e| Status::invalid_argument(format!("Invalid scan ticket: {}", e)))?;
+        let stream = self.scan(&ticket).await.map_err(to_status)?;
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(stream.schema())
+            .build(stream.map_err(|e| FlightError::ExternalError(Box::new(e))))
+            .map_err(Status::from);
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn do_put(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoPutStream>, Status> {
+        let mut input = request.into_inner();
+        let first = input
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("DoPut stream is empty"))?;
+        let path = match first.flight_descriptor.as_ref() {
+            Some(descriptor) if descriptor.r#type() == DescriptorType::Path => {
+                descriptor.path.join("/")
+            }
+            _ => {
+                return Err(Status::invalid_argument(
+                    "The first DoPut message must carry a PATH descriptor",
+                ))
+            }
+        };
+        let schema = Arc::new(
+            ArrowSchema::try_from(&first)
+                .map_err(|e| Status::invalid_argument(format!("Invalid schema: {}", e)))?,
+        );
+        let data = futures::stream::once(futures::future::ready(Ok(first)))
+            .chain(input.map_err(FlightError::from));
+        let batches = arrow_flight::decode::FlightRecordBatchStream::new_from_flight_data(data)
+            .map_err(|e| DataFusionError::External(Box::new(e)));
+        let stream: SendableRecordBatchStream =
+            Box::pin(RecordBatchStreamAdapter::new(schema, batches));
+
+        let dataset = self.append(&path, stream).await.map_err(to_status)?;
+        let result = PutResult {
+            app_metadata: dataset.version().version.to_string().into(),
+        };
+        let stream: BoxStream<'static, _> = futures::stream::once(async { Ok(result) }).boxed();
+        Ok(Response::new(stream))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> std::result::Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("DoAction is not supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> std::result::Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(futures::stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("DoExchange is not supported"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_flight::decode::FlightRecordBatchStream;
+    use arrow_schema::{DataType, Field};
+
+    fn batch(values: std::ops::Range<i32>) -> RecordBatch {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("value", DataType::Int32, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from_iter_values(values.clone())),
+                Arc::new(Int32Array::from_iter_values(values.map(|v| v * 10))),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flight_scan_and_append() {
+        let root = tempfile::tempdir().unwrap();
+        let root_uri = root.path().to_str().unwrap();
+        let data = batch(0..10);
+        let schema = data.schema();
+        Dataset::write(
+            RecordBatchIterator::new(vec![Ok(data)], schema.clone()),
+            &format!("{}/db/items", root_uri),
+            None,
+        )
+        .await
+        .unwrap();
+        let service = LanceFlightService::new(root_uri);
+
+        let info = service
+            .get_flight_info(Request::new(FlightDescriptor::new_path(vec![
+                "db".to_string(),
+                "items".to_string(),
+            ])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(info.total_records, 10);
+        let ticket = info.endpoint[0].ticket.clone().unwrap();
+        assert_eq!(
+            ScanTicket::decode(ticket.ticket.clone()).unwrap().version,
+            Some(1)
+        );
+
+        // Appends are not visible to a ticket pinned to an older version.
+        let stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
+            schema,
+            futures::stream::iter(vec![Ok(batch(10..15))]),
+        ));
+        let dataset = service.append("db/items", stream).await.unwrap();
+        assert_eq!(dataset.version().version, 2);
+
+        let read = |ticket: Ticket| {
+            let service = service.clone();
+            async move {
+                let stream = service
+                    .do_get(Request::new(ticket))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .map_err(FlightError::from);
+                FlightRecordBatchStream::new_from_flight_data(stream)
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+            }
+        };
+        let batches = read(ticket).await;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        let ticket = ScanTicket {
+            dataset: "db/items".to_string(),
+            columns: vec!["value".to_string()],
+            limit: Some(3),
+            ..Default::default()
+        };
+        let batches = read(Ticket::new(ticket.encode_to_vec())).await;
+        let batch = arrow_select::concat::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(batch.num_columns(), 1);
+        assert_eq!(batch.num_rows(), 3);
+
+        let err = service
+            .get_schema(Request::new(FlightDescriptor::new_path(vec![
+                "..".to_string(),
+                "items".to_string(),
+            ])))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let err = service
+            .get_schema(Request::new(FlightDescriptor::new_path(vec![
+                "missing".to_string()
+            ])))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+}
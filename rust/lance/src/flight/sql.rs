@@ -0,0 +1,583 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! [Arrow Flight SQL](https://arrow.apache.org/docs/format/FlightSql.html)
+//! on top of [`LanceFlightService`].
+//!
+//! Each dataset directly under the root is a table named after its directory,
+//! without the `.lance` extension. Queries are planned and run by DataFusion,
+//! so JDBC and ODBC drivers speaking Flight SQL can query datasets.
+//!
+//! Supported commands are `GetSqlInfo`, `GetTables`, ad-hoc statements and
+//! prepared statements. Prepared statements take positional `$1`, `$2`, ...
+//! parameters; the values bound by `DoPut` are the first row of the uploaded
+//! batch and stay bound until the statement is closed or bound again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use arrow_array::RecordBatch;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::sql::metadata::{SqlInfoData, SqlInfoDataBuilder};
+use arrow_flight::sql::server::{FlightSqlService, PeekableFlightDataStream};
+use arrow_flight::sql::{
+    ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+    ActionCreatePreparedStatementResult, CommandGetSqlInfo, CommandGetTables,
+    CommandPreparedStatementQuery, CommandStatementQuery, DoPutPreparedStatementResult,
+    ProstMessageExt, SqlInfo, TicketStatementQuery,
+};
+use arrow_flight::{
+    Action, FlightDescriptor, FlightEndpoint, FlightInfo, IpcMessage, SchemaAsIpc, Ticket,
+};
+use arrow_ipc::writer::IpcWriteOptions;
+use arrow_schema::{Field, Schema as ArrowSchema};
+use bytes::Bytes;
+use datafusion::common::{ParamValues, ScalarValue};
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::SessionContext;
+use datafusion::logical_expr::LogicalPlan;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance_io::object_store::{ObjectStore, ObjectStoreParams};
+use prost::Message;
+use snafu::location;
+use tonic::{Request, Response, Status};
+
+use super::{to_status, LanceFlightService};
+use crate::datafusion::LanceTableProvider;
+use crate::{Error, Result};
+
+static SQL_INFO: LazyLock<SqlInfoData> = LazyLock::new(|| {
+    let mut builder = SqlInfoDataBuilder::new();
+    builder.append(SqlInfo::FlightSqlServerName, "Lance");
+    builder.append(SqlInfo::FlightSqlServerVersion, env!("CARGO_PKG_VERSION"));
+    builder.append(SqlInfo::FlightSqlServerArrowVersion, "1.3");
+    builder.append(SqlInfo::FlightSqlServerReadOnly, true);
+    builder.build().expect("SQL info is valid")
+});
+
+struct PreparedStatement {
+    ctx: SessionContext,
+    plan: LogicalPlan,
+    params: Option<ParamValues>,
+}
+
+/// A Flight SQL service querying the datasets under a root URI.
+#[derive(Clone)]
+pub struct LanceFlightSqlService {
+    flight: LanceFlightService,
+    statements: Arc<Mutex<HashMap<Bytes, PreparedStatement>>>,
+}
+
+impl LanceFlightSqlService {
+    pub fn new(flight: LanceFlightService) -> Self {
+        Self {
+            flight,
+            statements: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Wrap the service in a tonic server that can be added to a router.
+    pub fn into_server(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    /// List the tables under the root as `(table name, dataset path)` pairs.
+    async fn tables(&self) -> Result<Vec<(String, String)>> {
+        let params = ObjectStoreParams {
+            storage_options: Some(self.flight.storage_options.clone()),
+            ..Default::default()
+        };
+        let (store, root) = ObjectStore::from_uri_and_params(
+            self.flight.session.store_registry(),
+            &self.flight.root,
+            &params,
+        )
+        .await?;
+        let mut tables = Vec::new();
+        for name in store.read_dir(root.clone()).await? {
+            if store
+                .exists(&root.child(name.as_str()).child("_versions"))
+                .await?
+            {
+                let table = name.strip_suffix(".lance").unwrap_or(&name).to_string();
+                tables.push((table, name));
+            }
+        }
+        tables.sort();
+        Ok(tables)
+    }
+
+    /// A DataFusion context with every table registered.
+    async fn context(&self) -> Result<SessionContext> {
+        let ctx = SessionContext::new();
+        for (table, path) in self.tables().await? {
+            let dataset = self.flight.load(&path, None).await?;
+            ctx.register_table(
+                table.as_str(),
+                Arc::new(LanceTableProvider::new(Arc::new(dataset), false, false)),
+            )?;
+        }
+        Ok(ctx)
+    }
+
+    async fn plan(&self, sql: &str) -> Result<(SessionContext, LogicalPlan)> {
+        let ctx = self.context().await?;
+        let plan = ctx.state().create_logical_plan(sql).await?;
+        Ok((ctx, plan))
+    }
+
+    fn flight_info(
+        schema: &ArrowSchema,
+        ticket: impl ProstMessageExt,
+        descriptor: FlightDescriptor,
+    ) -> std::result::Result<FlightInfo, Status> {
+        Ok(FlightInfo::new()
+            .try_with_schema(schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(
+                FlightEndpoint::new().with_ticket(Ticket::new(ticket.as_any().encode_to_vec())),
+            )
+            .with_descriptor(descriptor))
+    }
+
+    /// Bind the first row of `batches` to the parameters of a prepared statement.
+    fn bind(&self, handle: &Bytes, batches: &[RecordBatch]) -> Result<()> {
+        let Some(batch) = batches.iter().find(|batch| batch.num_rows() > 0) else {
+            return Err(Error::invalid_input(
+                "No parameter values were given",
+                location!(),
+            ));
+        };
+        let values = batch
+            .columns()
+            .iter()
+            .map(|column| ScalarValue::try_from_array(column, 0))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut statements = self.statements.lock().unwrap();
+        let statement = statements
+            .get_mut(handle)
+            .ok_or_else(|| unknown_statement(handle))?;
+        statement.params = Some(ParamValues::List(values));
+        Ok(())
+    }
+
+    async fn execute_prepared(&self, handle: &Bytes) -> Result<SendableRecordBatchStream> {
+        let (ctx, plan) = {
+            let statements = self.statements.lock().unwrap();
+            let statement = statements
+                .get(handle)
+                .ok_or_else(|| unknown_statement(handle))?;
+            let plan = match &statement.params {
+                Some(params) => statement.plan.clone().with_param_values(params.clone())?,
+                None => statement.plan.clone(),
+            };
+            (statement.ctx.clone(), plan)
+        };
+        Ok(ctx
+            .execute_logical_plan(plan)
+            .await?
+            .execute_stream()
+            .await?)
+    }
+}
+
+fn unknown_statement(handle: &Bytes) -> Error {
+    Error::invalid_input(
+        format!(
+            "Unknown prepared statement {}",
+            String::from_utf8_lossy(handle)
+        ),
+        location!(),
+    )
+}
+
+fn df_status(err: DataFusionError) -> Status {
+    match err {
+        DataFusionError::SQL(..) | DataFusionError::Plan(_) | DataFusionError::SchemaError(..) => {
+            Status::invalid_argument(err.to_string())
+        }
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+fn schema_bytes(schema: &ArrowSchema) -> std::result::Result<Bytes, Status> {
+    let IpcMessage(bytes) = SchemaAsIpc::new(schema, &IpcWriteOptions::default())
+        .try_into()
+        .map_err(|e: arrow_schema::ArrowError| Status::internal(e.to_string()))?;
+    Ok(bytes)
+}
+
+type DoGetStream = <LanceFlightSqlService as FlightService>::DoGetStream;
+
+fn encode_batch(batch: RecordBatch) -> DoGetStream {
+    let stream = FlightDataEncoderBuilder::new()
+        .with_schema(batch.schema())
+        .build(futures::stream::once(async { Ok(batch) }))
+        .map_err(Status::from);
+    Box::pin(stream)
+}
+
+fn encode(stream: SendableRecordBatchStream) -> DoGetStream {
+    let stream = FlightDataEncoderBuilder::new()
+        .with_schema(stream.schema())
+        .build(stream.map_err(|e| FlightError::ExternalError(Box::new(e))))
+        .map_err(Status::from);
+    Box::pin(stream)
+}
+
+#[tonic::async_trait]
+impl FlightSqlService for LanceFlightSqlService {
+    type FlightService = Self;
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder(&SQL_INFO).schema();
+        Ok(Response::new(Self::flight_info(
+            &schema,
+            query,
+            request.into_inner(),
+        )?))
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        _request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let batch = query
+            .into_builder(&SQL_INFO)
+            .build()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(encode_batch(batch)))
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        Ok(Response::new(Self::flight_info(
+            &schema,
+            query,
+            request.into_inner(),
+        )?))
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let include_schema = query.include_schema;
+        let mut builder = query.into_builder();
+        for (table, path) in self.tables().await.map_err(to_status)? {
+            let schema = if include_schema {
+                let dataset = self.flight.load(&path, None).await.map_err(to_status)?;
+                ArrowSchema::from(dataset.schema())
+            } else {
+                ArrowSchema::empty()
+            };
+            builder
+                .append("", "", &table, "TABLE", &schema)
+                .map_err(|e| Status::internal(e.to_string()))?;
+        }
+        let batch = builder
+            .build()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(encode_batch(batch)))
+    }
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let (_, plan) = self.plan(&query.query).await.map_err(to_status)?;
+        let ticket = TicketStatementQuery {
+            statement_handle: query.query.into(),
+        };
+        Ok(Response::new(Self::flight_info(
+            plan.schema().as_arrow(),
+            ticket,
+            request.into_inner(),
+        )?))
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        _request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let sql = String::from_utf8(ticket.statement_handle.to_vec())
+            .map_err(|_| Status::invalid_argument("Statement handle is not valid UTF-8"))?;
+        let (ctx, plan) = self.plan(&sql).await.map_err(to_status)?;
+        let stream = ctx
+            .execute_logical_plan(plan)
+            .await
+            .map_err(df_status)?
+            .execute_stream()
+            .await
+            .map_err(df_status)?;
+        Ok(Response::new(encode(stream)))
+    }
+
+    async fn do_action_create_prepared_statement(
+        &self,
+        query: ActionCreatePreparedStatementRequest,
+        _request: Request<Action>,
+    ) -> std::result::Result<ActionCreatePreparedStatementResult, Status> {
+        let (ctx, plan) = self.plan(&query.query).await.map_err(to_status)?;
+        let mut params = plan
+            .get_parameter_types()
+            .map_err(df_status)?
+            .into_iter()
+            .map(|(name, data_type)| {
+                let position = name
+                    .strip_prefix('$')
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .ok_or_else(|| {
+                        Status::invalid_argument(format!(
+                            "Only positional parameters are supported, got {}",
+                            name
+                        ))
+                    })?;
+                let data_type = data_type.unwrap_or(arrow_schema::DataType::Null);
+                Ok((position, Field::new(name, data_type, true)))
+            })
+            .collect::<std::result::Result<Vec<_>, Status>>()?;
+        params.sort_by_key(|(position, _)| *position);
+        let parameter_schema = ArrowSchema::new(
+            params
+                .into_iter()
+                .map(|(_, field)| field)
+                .collect::<Vec<_>>(),
+        );
+        let dataset_schema = plan.schema().as_arrow().clone();
+
+        let handle = Bytes::from(uuid::Uuid::new_v4().to_string());
+        self.statements.lock().unwrap().insert(
+            handle.clone(),
+            PreparedStatement {
+                ctx,
+                plan,
+                params: None,
+            },
+        );
+        Ok(ActionCreatePreparedStatementResult {
+            prepared_statement_handle: handle,
+            dataset_schema: schema_bytes(&dataset_schema)?,
+            parameter_schema: schema_bytes(&parameter_schema)?,
+        })
+    }
+
+    async fn do_put_prepared_statement_query(
+        &self,
+        query: CommandPreparedStatementQuery,
+        request: Request<PeekableFlightDataStream>,
+    ) -> std::result::Result<DoPutPreparedStatementResult, Status> {
+        let batches = FlightRecordBatchStream::new_from_flight_data(
+            request.into_inner().map_err(FlightError::from),
+        )
+        .try_collect::<Vec<_>>()
+        .await?;
+        self.bind(&query.prepared_statement_handle, &batches)
+            .map_err(to_status)?;
+        Ok(DoPutPreparedStatementResult {
+            prepared_statement_handle: Some(query.prepared_statement_handle),
+        })
+    }
+
+    async fn get_flight_info_prepared_statement(
+        &self,
+        query: CommandPreparedStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let schema = {
+            let statements = self.statements.lock().unwrap();
+            let statement = statements
+                .get(&query.prepared_statement_handle)
+                .ok_or_else(|| to_status(unknown_statement(&query.prepared_statement_handle)))?;
+            statement.plan.schema().as_arrow().clone()
+        };
+        Ok(Response::new(Self::flight_info(
+            &schema,
+            query,
+            request.into_inner(),
+        )?))
+    }
+
+    async fn do_get_prepared_statement(
+        &self,
+        query: CommandPreparedStatementQuery,
+        _request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let stream = self
+            .execute_prepared(&query.prepared_statement_handle)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(encode(stream)))
+    }
+
+    async fn do_action_close_prepared_statement(
+        &self,
+        query: ActionClosePreparedStatementRequest,
+        _request: Request<Action>,
+    ) -> std::result::Result<(), Status> {
+        self.statements
+            .lock()
+            .unwrap()
+            .remove(&query.prepared_statement_handle);
+        Ok(())
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{Int32Array, Int64Array, RecordBatchIterator, StringArray};
+    use arrow_schema::DataType;
+
+    use crate::Dataset;
+
+    async fn collect(response: Response<DoGetStream>) -> Vec<RecordBatch> {
+        FlightRecordBatchStream::new_from_flight_data(
+            response.into_inner().map_err(FlightError::from),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flight_sql() {
+        let root = tempfile::tempdir().unwrap();
+        let root_uri = root.path().to_str().unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("value", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..100)),
+                Arc::new(Int32Array::from_iter_values((0..100).map(|v| v * 2))),
+            ],
+        )
+        .unwrap();
+        Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            &format!("{}/items.lance", root_uri),
+            None,
+        )
+        .await
+        .unwrap();
+        std::fs::create_dir(root.path().join("not_a_dataset")).unwrap();
+        let service = LanceFlightSqlService::new(LanceFlightService::new(root_uri));
+
+        let tables = collect(
+            service
+                .do_get_tables(
+                    CommandGetTables {
+                        include_schema: true,
+                        ..Default::default()
+                    },
+                    Request::new(Ticket::new(Bytes::new())),
+                )
+                .await
+                .unwrap(),
+        )
+        .await;
+        let names = tables[0]
+            .column_by_name("table_name")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(names.len(), 1);
+        assert_eq!(names.value(0), "items");
+
+        let batches = collect(
+            service
+                .do_get_statement(
+                    TicketStatementQuery {
+                        statement_handle: "SELECT count(*) AS n FROM items".into(),
+                    },
+                    Request::new(Ticket::new(Bytes::new())),
+                )
+                .await
+                .unwrap(),
+        )
+        .await;
+        let count = batches[0]["n"]
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(count.value(0), 100);
+
+        let prepared = service
+            .do_action_create_prepared_statement(
+                ActionCreatePreparedStatementRequest {
+                    query: "SELECT value FROM items WHERE id >= $1 ORDER BY id".to_string(),
+                    transaction_id: None,
+                },
+                Request::new(Action::new("CreatePreparedStatement", "")),
+            )
+            .await
+            .unwrap();
+        let handle = prepared.prepared_statement_handle;
+        let param_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "$1",
+            DataType::Int32,
+            true,
+        )]));
+        let params =
+            RecordBatch::try_new(param_schema, vec![Arc::new(Int32Array::from(vec![95]))]).unwrap();
+        service.bind(&handle, &[params]).unwrap();
+        let query = CommandPreparedStatementQuery {
+            prepared_statement_handle: handle.clone(),
+        };
+        let batches = collect(
+            service
+                .do_get_prepared_statement(query.clone(), Request::new(Ticket::new(Bytes::new())))
+                .await
+                .unwrap(),
+        )
+        .await;
+        let values = arrow_select::concat::concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(values.num_rows(), 5);
+        assert_eq!(
+            values["value"]
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .value(0),
+            190
+        );
+
+        service
+            .do_action_close_prepared_statement(
+                ActionClosePreparedStatementRequest {
+                    prepared_statement_handle: handle,
+                },
+                Request::new(Action::new("ClosePreparedStatement", "")),
+            )
+            .await
+            .unwrap();
+        let err = service
+            .do_get_prepared_statement(query, Request::new(Ticket::new(Bytes::new())))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -56,6 +56,7 @@ use lance_io::utils::{
 use lance_table::format::Index as IndexMetadata;
 use lance_table::format::{Fragment, SelfDescribingFileReader};
 use lance_table::io::manifest::read_manifest_indexes;
+use prost::Message;
 use roaring::RoaringBitmap;
 use scalar::{
     build_inverted_index, detect_scalar_index_type, index_matches_criteria, infer_index_type,
@@ -261,6 +262,7 @@ impl DatasetIndexExt for Dataset {
         params: &dyn IndexParams,
         replace: bool,
     ) -> Result<()> {
+        self.ensure_writable("create an index on")?;
         if columns.len() != 1 {
             return Err(Error::Index {
                 message: "Only support building index on 1 column at the moment".to_string(),
@@ -301,6 +303,9 @@ impl DatasetIndexExt for Dataset {
         }
 
         let index_id = Uuid::new_v4();
+        // Building the index reads the whole column, keep it within the
+        // background bandwidth limits
+        let background = self.for_background();
         let index_details = match (index_type, params.index_name()) {
             (
                 IndexType::Bitmap
@@ -311,7 +316,7 @@ impl DatasetIndexExt for Dataset {
                 LANCE_SCALAR_INDEX,
             ) => {
                 let params = ScalarIndexParams::new(index_type.try_into()?);
-                build_scalar_index(self, column, &index_id.to_string(), &params).await?
+                build_scalar_index(&background, column, &index_id.to_string(), &params).await?
             }
             (IndexType::Scalar, LANCE_SCALAR_INDEX) => {
                 // Guess the index type
@@ -322,7 +327,7 @@ impl DatasetIndexExt for Dataset {
                         message: "Scalar index type must take a ScalarIndexParams".to_string(),
                         location: location!(),
                     })?;
-                build_scalar_index(self, column, &index_id.to_string(), params).await?
+                build_scalar_index(&background, column, &index_id.to_string(), params).await?
             }
             (IndexType::Inverted, _) => {
                 // Inverted index params.
@@ -334,7 +339,8 @@ impl DatasetIndexExt for Dataset {
                         location: location!(),
                     })?;
 
-                build_inverted_index(self, column, &index_id.to_string(), inverted_params).await?;
+                build_inverted_index(&background, column, &index_id.to_string(), inverted_params)
+                    .await?;
                 inverted_index_details()
             }
             (IndexType::Vector, LANCE_VECTOR_INDEX) => {
@@ -349,7 +355,7 @@ impl DatasetIndexExt for Dataset {
 
                 // this is a large future so move it to heap
                 Box::pin(build_vector_index(
-                    self,
+                    &background,
                     column,
                     &index_name,
                     &index_id.to_string(),
@@ -381,7 +387,7 @@ impl DatasetIndexExt for Dataset {
                         location: location!(),
                     })?;
 
-                ext.create_index(self, column, &index_id.to_string(), params)
+                ext.create_index(&background, column, &index_id.to_string(), params)
                     .await?;
                 vector_index_details()
             }
@@ -478,12 +484,25 @@ impl DatasetIndexExt for Dataset {
         {
             Some(indices) => indices,
             None => {
-                let loaded_indices = read_manifest_indexes(
-                    &self.object_store,
-                    &self.manifest_location,
-                    &self.manifest,
-                )
-                .await?;
+                // The encoded index section may have been kept when the manifest was read.
+                let loaded_indices = if let Some(section) = self
+                    .session
+                    .index_cache
+                    .get_encoded_metadata(self.base.as_ref(), self.version().version)
+                {
+                    lance_table::format::pb::IndexSection::decode(section.0.as_slice())?
+                        .indices
+                        .into_iter()
+                        .map(IndexMetadata::try_from)
+                        .collect::<Result<Vec<_>>>()?
+                } else {
+                    read_manifest_indexes(
+                        &self.object_store,
+                        &self.manifest_location,
+                        &self.manifest,
+                    )
+                    .await?
+                };
                 let loaded_indices = Arc::new(loaded_indices);
                 self.session.index_cache.insert_metadata(
                     self.base.as_ref(),
@@ -2146,6 +2165,9 @@ mod tests {
 
         session.index_cache.clear(); // Clear the cache
 
+        // Also drop the cached manifest, so that opening the dataset reads it again
+        session.metadata_cache.invalidate_prefix("");
+
         let dataset2 = DatasetBuilder::from_uri(test_uri)
             .with_session(session.clone())
             .with_read_params(ReadParams {
@@ -2162,6 +2184,13 @@ mod tests {
         let stats = io_stats.incremental_stats(); // Reset
         assert!(stats.read_bytes < 64 * 1024);
 
+        // The index metadata is only decoded on first use.
+        let version = dataset2.version().version;
+        assert!(session
+            .index_cache
+            .get_metadata(dataset2.base.as_ref(), version)
+            .is_none());
+
         // Because the manifest is so small, we should have opportunistically
         // cached the indices in memory already.
         let indices2 = dataset2.load_indices().await.unwrap();
@@ -2169,6 +2198,19 @@ mod tests {
         assert_eq!(stats.read_iops, 0);
         assert_eq!(stats.read_bytes, 0);
         assert_eq!(indices2.len(), 1);
+
+        session.index_cache.clear();
+        let dataset3 = DatasetBuilder::from_uri(test_uri)
+            .with_session(session.clone())
+            .with_index_metadata_prefetch(true)
+            .load()
+            .await
+            .unwrap();
+        let indices3 = session
+            .index_cache
+            .get_metadata(dataset3.base.as_ref(), version)
+            .unwrap();
+        assert_eq!(indices3.len(), 1);
     }
 
     #[tokio::test]
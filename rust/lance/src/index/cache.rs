@@ -4,214 +4,181 @@
 use std::sync::Arc;
 
 use deepsize::DeepSizeOf;
+use lance_core::cache::{CacheStats, LanceCache};
 use lance_index::vector::VectorIndexCacheEntry;
 use lance_index::{
     scalar::{ScalarIndex, ScalarIndexType},
     vector::VectorIndex,
 };
 use lance_table::format::Index;
-use moka::sync::Cache;
 
 use lance_index::frag_reuse::FragReuseIndex;
-use std::sync::atomic::{AtomicU64, Ordering};
 
-#[derive(Debug, Default, DeepSizeOf)]
-struct CacheStats {
-    hits: AtomicU64,
-    misses: AtomicU64,
-}
-
-impl CacheStats {
-    fn record_hit(&self) {
-        self.hits.fetch_add(1, Ordering::Relaxed);
-    }
+/// The encoded index section of a manifest, kept so the index metadata can be
+/// decoded on first use without reading the manifest again.
+#[derive(Debug, DeepSizeOf)]
+pub(crate) struct EncodedIndexSection(pub Vec<u8>);
 
-    fn record_miss(&self) {
-        self.misses.fetch_add(1, Ordering::Relaxed);
-    }
+/// Name of the cache category for a kind of index entry.
+fn category(kind: &str) -> String {
+    format!("index/{}", kind)
 }
 
+/// Cache for opened indices and their metadata.
+///
+/// Each kind of entry is accounted to its own category of the underlying
+/// [`LanceCache`], e.g. `index/vector`.
 #[derive(Clone)]
 pub struct IndexCache {
-    // TODO: Can we merge these two caches into one for uniform memory management?
-    scalar_cache: Arc<Cache<String, Arc<dyn ScalarIndex>>>,
-    vector_cache: Arc<Cache<String, Arc<dyn VectorIndex>>>,
-    frag_reuse_cache: Arc<Cache<String, Arc<FragReuseIndex>>>,
-    // this is for v3 index, sadly we can't use the same cache as the vector index for now
-    vector_partition_cache: Arc<Cache<String, Arc<dyn VectorIndexCacheEntry>>>,
+    scalar_cache: LanceCache,
+    vector_cache: LanceCache,
+    frag_reuse_cache: LanceCache,
+    vector_partition_cache: LanceCache,
 
     /// Index metadata cache.
     ///
     /// The key is "{dataset_base_path}:{version}".
-    /// Value is all the indies of a particular version of the dataset.
-    metadata_cache: Arc<Cache<String, Arc<Vec<Index>>>>,
+    /// Value is all the indies of a particular version of the dataset, or their
+    /// [`EncodedIndexSection`] until they are first used.
+    metadata_cache: LanceCache,
 
     /// Caches the ScalarIndexType for each index (it can be expensive to determine this
     /// in older indices that do not store index_details)
-    type_cache: Arc<Cache<String, ScalarIndexType>>,
-
-    cache_stats: Arc<CacheStats>,
+    type_cache: LanceCache,
 }
 
 impl DeepSizeOf for IndexCache {
-    fn deep_size_of_children(&self, context: &mut deepsize::Context) -> usize {
-        self.scalar_cache
-            .iter()
-            .map(|(_, v)| v.deep_size_of_children(context))
-            .sum::<usize>()
-            + self
-                .vector_cache
-                .iter()
-                .map(|(_, v)| v.deep_size_of_children(context))
-                .sum::<usize>()
-            + self
-                .vector_partition_cache
-                .iter()
-                .map(|(_, v)| v.deep_size_of_children(context))
-                .sum::<usize>()
-            + self
-                .frag_reuse_cache
-                .iter()
-                .map(|(_, v)| v.deep_size_of_children(context))
-                .sum::<usize>()
-            + self
-                .metadata_cache
-                .iter()
-                .map(|(_, v)| v.deep_size_of_children(context))
-                .sum::<usize>()
-            + self.cache_stats.deep_size_of_children(context)
+    fn deep_size_of_children(&self, _: &mut deepsize::Context) -> usize {
+        self.stats().size_bytes as usize
     }
 }
 
 impl IndexCache {
+    /// Create a cache holding up to `capacity` entries of each kind.
     pub(crate) fn new(capacity: usize) -> Self {
+        let cache = |name: &str, capacity: usize| {
+            LanceCache::with_entry_capacity(capacity).with_category(&category(name))
+        };
         Self {
-            scalar_cache: Arc::new(Cache::new(capacity as u64)),
-            vector_cache: Arc::new(Cache::new(capacity as u64)),
-            vector_partition_cache: Arc::new(Cache::new(capacity as u64)),
+            scalar_cache: cache("scalar", capacity),
+            vector_cache: cache("vector", capacity),
+            vector_partition_cache: cache("vector_partition", capacity),
             // there is always 1 fragment reuse index that should be used
-            frag_reuse_cache: Arc::new(Cache::new(1)),
-            metadata_cache: Arc::new(Cache::new(capacity as u64)),
-            type_cache: Arc::new(Cache::new(capacity as u64)),
-            cache_stats: Arc::new(CacheStats::default()),
+            frag_reuse_cache: cache("frag_reuse", 1),
+            metadata_cache: cache("metadata", capacity),
+            type_cache: cache("type", capacity),
+        }
+    }
+
+    /// Create an index cache that shares entries and its byte budget with
+    /// `cache`.
+    pub(crate) fn with_cache(cache: &LanceCache) -> Self {
+        let cache = |name: &str| cache.with_category(&category(name));
+        Self {
+            scalar_cache: cache("scalar"),
+            vector_cache: cache("vector"),
+            vector_partition_cache: cache("vector_partition"),
+            frag_reuse_cache: cache("frag_reuse"),
+            metadata_cache: cache("metadata"),
+            type_cache: cache("type"),
+        }
+    }
+
+    fn caches(&self) -> [&LanceCache; 6] {
+        [
+            &self.scalar_cache,
+            &self.vector_cache,
+            &self.vector_partition_cache,
+            &self.frag_reuse_cache,
+            &self.metadata_cache,
+            &self.type_cache,
+        ]
+    }
+
+    /// Statistics for the index entries only, broken down by kind.
+    pub(crate) fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+        for cache in self.caches() {
+            let category = cache
+                .stats()
+                .categories
+                .remove(cache.category())
+                .unwrap_or_default();
+            stats.hits += category.hits;
+            stats.misses += category.misses;
+            stats.num_entries += category.num_entries;
+            stats.size_bytes += category.size_bytes;
+            stats
+                .categories
+                .insert(cache.category().to_string(), category);
         }
+        stats
     }
 
     /// Clear the cache
     #[cfg(test)]
     pub fn clear(&self) {
-        self.scalar_cache.invalidate_all();
-        self.scalar_cache.run_pending_tasks();
-
-        self.vector_cache.invalidate_all();
-        self.vector_cache.run_pending_tasks();
-
-        self.vector_partition_cache.invalidate_all();
-        self.vector_partition_cache.run_pending_tasks();
-
-        self.frag_reuse_cache.invalidate_all();
-        self.frag_reuse_cache.run_pending_tasks();
-
-        self.metadata_cache.invalidate_all();
-        self.metadata_cache.run_pending_tasks();
-
-        self.type_cache.invalidate_all();
-        self.type_cache.run_pending_tasks();
+        for cache in self.caches() {
+            cache.invalidate_prefix("");
+        }
     }
 
     #[allow(dead_code)]
     pub(crate) fn len_vector(&self) -> usize {
-        self.vector_cache.run_pending_tasks();
-        self.vector_cache.entry_count() as usize
+        self.stats()
+            .categories
+            .get(&category("vector"))
+            .map(|c| c.num_entries as usize)
+            .unwrap_or_default()
     }
 
     pub(crate) fn get_size(&self) -> usize {
-        self.scalar_cache.run_pending_tasks();
-        self.vector_cache.run_pending_tasks();
-        self.vector_partition_cache.run_pending_tasks();
-        self.frag_reuse_cache.run_pending_tasks();
-        self.metadata_cache.run_pending_tasks();
-        (self.scalar_cache.entry_count()
-            + self.vector_cache.entry_count()
-            + self.vector_partition_cache.entry_count()
-            + self.frag_reuse_cache.entry_count()
-            + self.metadata_cache.entry_count()) as usize
+        self.stats().num_entries as usize
     }
 
     pub(crate) fn approx_size(&self) -> usize {
-        (self.scalar_cache.entry_count()
-            + self.vector_cache.entry_count()
-            + self.vector_partition_cache.entry_count()
-            + self.frag_reuse_cache.entry_count()
-            + self.metadata_cache.entry_count()) as usize
+        self.get_size()
     }
 
     pub(crate) fn get_type(&self, key: &str) -> Option<ScalarIndexType> {
-        if let Some(index) = self.type_cache.get(key) {
-            self.cache_stats.record_hit();
-            Some(index)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.type_cache
+            .get::<ScalarIndexType>(key)
+            .map(|index_type| *index_type)
     }
 
     /// Get an Index if present. Otherwise returns [None].
     pub(crate) fn get_scalar(&self, key: &str) -> Option<Arc<dyn ScalarIndex>> {
-        if let Some(index) = self.scalar_cache.get(key) {
-            self.cache_stats.record_hit();
-            Some(index)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.scalar_cache.get_unsized::<dyn ScalarIndex>(key)
     }
 
     pub(crate) fn get_vector(&self, key: &str) -> Option<Arc<dyn VectorIndex>> {
-        if let Some(index) = self.vector_cache.get(key) {
-            self.cache_stats.record_hit();
-            Some(index)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.vector_cache.get_unsized::<dyn VectorIndex>(key)
     }
 
     pub(crate) fn get_vector_partition(&self, key: &str) -> Option<Arc<dyn VectorIndexCacheEntry>> {
-        if let Some(index) = self.vector_partition_cache.get(key) {
-            self.cache_stats.record_hit();
-            Some(index)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.vector_partition_cache
+            .get_unsized::<dyn VectorIndexCacheEntry>(key)
     }
 
     pub(crate) fn get_frag_reuse(&self, key: &str) -> Option<Arc<FragReuseIndex>> {
-        if let Some(index) = self.frag_reuse_cache.get(key) {
-            self.cache_stats.record_hit();
-            Some(index)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.frag_reuse_cache.get(key)
     }
 
     /// Insert a new entry into the cache.
     pub(crate) fn insert_scalar(&self, key: &str, index: Arc<dyn ScalarIndex>) {
-        self.scalar_cache.insert(key.to_string(), index);
+        self.scalar_cache.insert_unsized(key, index);
     }
 
     pub(crate) fn insert_vector(&self, key: &str, index: Arc<dyn VectorIndex>) {
-        self.vector_cache.insert(key.to_string(), index);
+        self.vector_cache.insert_unsized(key, index);
     }
 
     pub(crate) fn insert_frag_reuse(&self, key: &str, index: Arc<FragReuseIndex>) {
-        self.frag_reuse_cache.insert(key.to_string(), index);
+        self.frag_reuse_cache.insert(key, index);
     }
 
     pub(crate) fn insert_vector_partition(&self, key: &str, index: Arc<dyn VectorIndexCacheEntry>) {
-        self.vector_partition_cache.insert(key.to_string(), index);
+        self.vector_partition_cache.insert_unsized(key, index);
     }
 
     /// Construct a key for index metadata arrays.
@@ -222,35 +189,43 @@ impl IndexCache {
     /// Get all index metadata for a particular dataset version.
     pub(crate) fn get_metadata(&self, key: &str, version: u64) -> Option<Arc<Vec<Index>>> {
         let key = Self::metadata_key(key, version);
-        if let Some(indices) = self.metadata_cache.get(&key) {
-            self.cache_stats.record_hit();
-            Some(indices)
-        } else {
-            self.cache_stats.record_miss();
-            None
-        }
+        self.metadata_cache.get(&key)
     }
 
     pub(crate) fn insert_metadata(&self, key: &str, version: u64, indices: Arc<Vec<Index>>) {
         let key = Self::metadata_key(key, version);
 
-        self.metadata_cache.insert(key, indices);
+        self.metadata_cache.insert(&key, indices);
+    }
+
+    /// Get the encoded index section for a particular dataset version.
+    pub(crate) fn get_encoded_metadata(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Option<Arc<EncodedIndexSection>> {
+        let key = Self::metadata_key(key, version);
+        self.metadata_cache.get(&key)
+    }
+
+    pub(crate) fn insert_encoded_metadata(
+        &self,
+        key: &str,
+        version: u64,
+        section: Arc<EncodedIndexSection>,
+    ) {
+        let key = Self::metadata_key(key, version);
+
+        self.metadata_cache.insert(&key, section);
     }
 
     pub(crate) fn insert_type(&self, key: &str, index_type: ScalarIndexType) {
-        self.type_cache.insert(key.to_string(), index_type);
+        self.type_cache.insert(key, Arc::new(index_type));
     }
 
     /// Get cache hit ratio.
     #[allow(dead_code)]
     pub(crate) fn hit_rate(&self) -> f32 {
-        let hits = self.cache_stats.hits.load(Ordering::Relaxed) as f32;
-        let misses = self.cache_stats.misses.load(Ordering::Relaxed) as f32;
-        // Returns 1.0 if hits + misses == 0 and avoids division by zero.
-        if (hits + misses) == 0.0 {
-            1.0
-        } else {
-            hits / (hits + misses)
-        }
+        self.stats().hit_rate()
     }
 }
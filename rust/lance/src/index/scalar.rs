@@ -41,6 +41,7 @@ use lance_index::{
     },
     IndexType,
 };
+use lance_io::scheduler::IoPriorityClass;
 use lance_table::format::Index;
 use log::info;
 use snafu::location;
@@ -84,6 +85,7 @@ impl TrainingRequest {
         let num_rows = self.dataset.count_all_rows().await?;
 
         let mut scan = self.dataset.scan();
+        scan.io_priority(IoPriorityClass::Background);
 
         let column_field =
             self.dataset
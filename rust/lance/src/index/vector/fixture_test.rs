@@ -263,6 +263,7 @@ mod test {
                 refine_factor: None,
                 metric_type: metric,
                 use_index: true,
+                cancellation_token: None,
             };
             let idx = make_idx.clone()(expected_query_at_subindex, metric).await;
             let partition_ids = idx.find_partitions(&q).unwrap();
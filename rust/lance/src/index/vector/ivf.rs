@@ -81,7 +81,7 @@ use lance_io::{
     traits::{Reader, WriteExt, Writer},
 };
 use lance_linalg::distance::{DistanceType, Dot, MetricType, L2};
-use lance_linalg::{distance::Normalize, kernels::normalize_fsl};
+use lance_linalg::{distance::Normalize, kernels::normalize_fsl, kmeans::KMeansParams};
 use log::{info, warn};
 use object_store::path::Path;
 use roaring::RoaringBitmap;
@@ -1217,7 +1217,8 @@ pub async fn build_ivf_model(
         "Loading training data for IVF. Sample size: {}",
         sample_size_hint
     );
-    let training_data = maybe_sample_training_data(dataset, column, sample_size_hint).await?;
+    let training_data =
+        maybe_sample_training_data(dataset, column, sample_size_hint, params.kmeans_seed).await?;
     info!(
         "Finished loading training data in {:02} seconds",
         start.elapsed().as_secs_f32()
@@ -1747,15 +1748,19 @@ where
     PrimitiveArray<T>: From<Vec<T::Native>>,
 {
     const REDOS: usize = 1;
-    let kmeans = lance_index::vector::kmeans::train_kmeans::<T>(
-        centroids,
+    let mut kmeans_params =
+        KMeansParams::new(centroids, params.max_iters as u32, REDOS, metric_type);
+    kmeans_params.batch_size = params.kmeans_batch_size;
+    kmeans_params.seed = params.kmeans_seed;
+    if let Some(tolerance) = params.kmeans_tolerance {
+        kmeans_params.tolerance = tolerance;
+    }
+    let kmeans = lance_index::vector::kmeans::train_kmeans_with_params::<T>(
         data,
         dimension,
         params.num_partitions,
-        params.max_iters as u32,
-        REDOS,
-        metric_type,
         params.sample_rate,
+        &kmeans_params,
     )?;
     Ok(IvfModel::new(
         FixedSizeListArray::try_new_from_values(kmeans.centroids, dimension as i32)?,
@@ -2067,6 +2072,7 @@ mod tests {
                     refine_factor: None,
                     metric_type: MetricType::L2,
                     use_index: true,
+                    cancellation_token: None,
                 };
                 let partitions = index.find_partitions(&query).unwrap();
                 let nearest_partition_id = partitions.value(0) as usize;
@@ -2638,6 +2644,30 @@ mod tests {
             });
     }
 
+    #[tokio::test]
+    async fn test_build_ivf_model_seeded() {
+        let test_dir = tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let (dataset, _) = generate_test_dataset(test_uri, 1000.0..1100.0).await;
+
+        // The sample is smaller than the dataset, so the seed has to cover both
+        // the sampling and the training
+        let ivf_params = IvfBuildParams {
+            sample_rate: 64,
+            kmeans_seed: Some(42),
+            ..IvfBuildParams::new(4)
+        };
+        assert!(ivf_params.num_partitions * ivf_params.sample_rate < 1000);
+        let first = build_ivf_model(&dataset, "vector", DIM, MetricType::L2, &ivf_params)
+            .await
+            .unwrap();
+        let second = build_ivf_model(&dataset, "vector", DIM, MetricType::L2, &ivf_params)
+            .await
+            .unwrap();
+        assert_eq!(first.centroids, second.centroids);
+    }
+
     #[tokio::test]
     async fn test_build_ivf_model_cosine() {
         let test_dir = tempdir().unwrap();
@@ -630,16 +630,13 @@ mod tests {
     use itertools::Itertools;
     use lance_arrow::FixedSizeListArrayExt;
 
-    use crate::index::{vector::is_ivf_hnsw, DatasetIndexInternalExt};
+    use crate::dataset::{InsertBuilder, UpdateBuilder, WriteMode, WriteParams};
+    use crate::index::DatasetIndexInternalExt;
     use crate::utils::test::copy_test_data_to_tmp;
     use crate::{
         dataset::optimize::{compact_files, CompactionOptions},
         index::vector::{is_ivf_pq, IndexFileVersion},
     };
-    use crate::{
-        dataset::{InsertBuilder, UpdateBuilder, WriteMode, WriteParams},
-        index::vector::is_ivf_flat,
-    };
     use crate::{index::vector::VectorIndexParams, Dataset};
     use lance_core::cache::LanceCache;
     use lance_core::{Result, ROW_ID};
@@ -862,12 +859,8 @@ mod tests {
                 )
                 .await;
 
-                // *_FLAT doesn't support float16/float64
-                if !(is_ivf_flat(&params.stages) // IVF_FLAT
-                    || (is_ivf_hnsw(&params.stages) && params.stages.len() == 2)) // IVF_HNSW_FLAT
-                    && dataset.is_none()
                 // if dataset is provided, it has been created, so the data type is already determined, no need to test float64
-                {
+                if dataset.is_none() {
                     test_index_impl::<Float64Type>(
                         params,
                         nlist,
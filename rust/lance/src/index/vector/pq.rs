@@ -11,7 +11,6 @@ use arrow_array::{
     Array, FixedSizeListArray, RecordBatch, UInt64Array, UInt8Array,
 };
 use arrow_array::{ArrayRef, Float32Array, UInt32Array};
-use arrow_ord::sort::sort_to_indices;
 use arrow_schema::{DataType, Field, Schema};
 use arrow_select::take::take;
 use async_trait::async_trait;
@@ -32,7 +31,7 @@ use lance_index::{
     Index, IndexType,
 };
 use lance_io::{traits::Reader, utils::read_fixed_stride_array};
-use lance_linalg::distance::{DistanceType, MetricType};
+use lance_linalg::distance::{topk, DistanceType, MetricType};
 use log::{info, warn};
 use roaring::RoaringBitmap;
 use serde_json::json;
@@ -250,42 +249,28 @@ impl VectorIndex for PQIndex {
             debug_assert_eq!(distances.len(), row_ids.len());
 
             let limit = query.k * query.refine_factor.unwrap_or(1) as usize;
-            if query.lower_bound.is_none() && query.upper_bound.is_none() {
-                let indices = sort_to_indices(&distances, None, Some(limit))?;
-                let distances = take(&distances, &indices, None)?;
-                let row_ids = take(row_ids.as_ref(), &indices, None)?;
-                Ok(RecordBatch::try_new(
-                    KNN_INDEX_SCHEMA.clone(),
-                    vec![distances, row_ids],
-                )?)
-            } else {
-                let indices = sort_to_indices(&distances, None, None)?;
-                let mut dists = Vec::with_capacity(limit);
-                let mut ids = Vec::with_capacity(limit);
-                for idx in indices.values().iter() {
-                    let dist = distances.value(*idx as usize);
-                    let id = row_ids.value(*idx as usize);
-                    if query.lower_bound.is_some_and(|lb| dist < lb) {
-                        continue;
-                    }
-                    if query.upper_bound.is_some_and(|ub| dist >= ub) {
-                        break;
-                    }
-
-                    dists.push(dist);
-                    ids.push(id);
-
-                    if dists.len() >= limit {
-                        break;
-                    }
-                }
-                let dists = Arc::new(Float32Array::from(dists));
-                let ids = Arc::new(UInt64Array::from(ids));
-                Ok(RecordBatch::try_new(
-                    KNN_INDEX_SCHEMA.clone(),
-                    vec![dists, ids],
-                )?)
-            }
+            // Ties are broken by row id, so the results are deterministic
+            let (ids, dists): (Vec<_>, Vec<_>) = topk(
+                row_ids
+                    .values()
+                    .iter()
+                    .copied()
+                    .zip(distances.values().iter().copied())
+                    .filter(|(_, dist)| {
+                        query.lower_bound.is_none_or(|lb| lb <= *dist)
+                            && query.upper_bound.is_none_or(|ub| *dist < ub)
+                    }),
+                limit,
+            )
+            .into_iter()
+            .unzip();
+            Ok(RecordBatch::try_new(
+                KNN_INDEX_SCHEMA.clone(),
+                vec![
+                    Arc::new(Float32Array::from(dists)),
+                    Arc::new(UInt64Array::from(ids)),
+                ],
+            )?)
         })
         .await
     }
@@ -538,7 +523,7 @@ pub async fn build_pq_model(
     );
     let start = std::time::Instant::now();
     let mut training_data =
-        maybe_sample_training_data(dataset, column, expected_sample_size).await?;
+        maybe_sample_training_data(dataset, column, expected_sample_size, None).await?;
     info!(
         "Finished loading training data in {:02} seconds",
         start.elapsed().as_secs_f32()
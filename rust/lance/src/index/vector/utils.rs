@@ -107,10 +107,13 @@ fn infer_vector_element_type_impl(
 ///
 /// Returns a [FixedSizeListArray], containing the training dataset.
 ///
+/// With a `seed`, the same rows are sampled from the same version of the
+/// dataset. Otherwise the sample is seeded from entropy.
 pub async fn maybe_sample_training_data(
     dataset: &Dataset,
     column: &str,
     sample_size_hint: usize,
+    seed: Option<u64>,
 ) -> Result<FixedSizeListArray> {
     let num_rows = dataset.count_rows(None).await?;
 
@@ -138,7 +141,7 @@ pub async fn maybe_sample_training_data(
 
     let batch = if num_rows > sample_size_hint && !is_nullable {
         let projection = dataset.schema().project(&[column])?;
-        let batch = dataset.sample(sample_size_hint, &projection).await?;
+        let batch = dataset.sample(sample_size_hint, &projection, seed).await?;
         info!(
             "Sample training data: retrieved {} rows by sampling",
             batch.num_rows()
@@ -156,7 +159,7 @@ pub async fn maybe_sample_training_data(
             .byte_width_opt()
             .unwrap_or(4 * 1024);
 
-        let ranges = random_ranges(num_rows, sample_size_hint, block_size, byte_width);
+        let ranges = random_ranges(num_rows, sample_size_hint, block_size, byte_width, seed);
 
         let mut collected = Vec::with_capacity(ranges.size_hint().0);
         let mut indices = Vec::with_capacity(sample_size_hint);
@@ -294,9 +297,13 @@ fn random_ranges(
     sample_size_hint: usize,
     block_size: usize,
     byte_width: usize,
+    seed: Option<u64>,
 ) -> impl Iterator<Item = std::ops::Range<u64>> + Send {
     let rows_per_batch = 1.max(block_size / byte_width);
-    let mut rng = SmallRng::from_entropy();
+    let mut rng = match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_entropy(),
+    };
     let num_bins = num_rows.div_ceil(rows_per_batch);
 
     let bins_iter: Box<dyn Iterator<Item = usize> + Send> = if sample_size_hint * 5 >= num_rows {
@@ -360,7 +367,7 @@ mod tests {
         assert_eq!(bin_size, 10);
 
         let mut ranges =
-            random_ranges(num_rows, sample_size, block_size, byte_width).collect::<Vec<_>>();
+            random_ranges(num_rows, sample_size, block_size, byte_width, None).collect::<Vec<_>>();
         ranges.sort_by_key(|r| r.start);
         let expected = (0..num_rows as u64).step_by(bin_size).map(|start| {
             let end = std::cmp::min(start + bin_size as u64, num_rows as u64);
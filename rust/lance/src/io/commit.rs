@@ -28,6 +28,7 @@ use conflict_resolver::TransactionRebase;
 use lance_core::cache::LanceCache;
 use lance_core::utils::backoff::{Backoff, SlotBackoff};
 use lance_core::utils::mask::RowIdTreeMap;
+use lance_core::utils::metrics;
 use lance_file::version::LanceFileVersion;
 use lance_index::metrics::NoOpMetricsCollector;
 use lance_io::utils::CachedFileSize;
@@ -52,9 +53,11 @@ use prost::Message;
 use super::ObjectStore;
 use crate::dataset::cleanup::auto_cleanup_hook;
 use crate::dataset::fragment::FileFragment;
+use crate::dataset::history::COMMIT_DURATION_KEY;
 use crate::dataset::transaction::{Operation, Transaction};
 use crate::dataset::{
-    load_new_transactions, write_manifest_file, ManifestWriteConfig, NewTransactionResult, BLOB_DIR,
+    find_committed_transaction, load_new_transactions, write_manifest_file, ManifestWriteConfig,
+    NewTransactionResult, BLOB_DIR,
 };
 use crate::index::DatasetIndexInternalExt;
 use crate::io::deletion::read_dataset_deletion_file;
@@ -65,6 +68,7 @@ mod conflict_resolver;
 mod dynamodb;
 #[cfg(test)]
 mod external_manifest;
+pub mod quota;
 #[cfg(all(feature = "dynamodb_tests", test))]
 mod s3_test;
 
@@ -579,15 +583,16 @@ pub(crate) async fn do_commit_detached_transaction(
 
         let (mut manifest, mut indices) = match transaction.operation {
             Operation::Restore { version } => {
-                Transaction::restore_old_manifest(
-                    object_store,
-                    commit_handler,
-                    &dataset.base,
-                    version,
-                    write_config,
-                    &transaction_file,
-                )
-                .await?
+                transaction
+                    .restore_old_manifest(
+                        object_store,
+                        commit_handler,
+                        &dataset.base,
+                        version,
+                        write_config,
+                        &transaction_file,
+                    )
+                    .await?
             }
             _ => transaction.build_manifest(
                 Some(dataset.manifest.as_ref()),
@@ -661,6 +666,7 @@ pub(crate) async fn commit_detached_transaction(
     write_config: &ManifestWriteConfig,
     commit_config: &CommitConfig,
 ) -> Result<(Manifest, ManifestLocation)> {
+    dataset.ensure_writable("commit to")?;
     let new_blob_version = if let Some(blob_op) = transaction.blobs_op.as_ref() {
         let blobs_dataset = dataset.blobs_dataset().await?.unwrap();
         let blobs_tx =
@@ -704,6 +710,69 @@ pub(crate) async fn commit_transaction(
     manifest_naming_scheme: ManifestNamingScheme,
     affected_rows: Option<&RowIdTreeMap>,
 ) -> Result<(Manifest, ManifestLocation)> {
+    let start = Instant::now();
+    let result = do_commit_transaction(
+        dataset,
+        object_store,
+        commit_handler,
+        transaction,
+        write_config,
+        commit_config,
+        manifest_naming_scheme,
+        affected_rows,
+    )
+    .await;
+    let outcome = match &result {
+        Ok(_) => "success",
+        Err(Error::CommitConflict { .. } | Error::RetryableCommitConflict { .. }) => "conflict",
+        Err(_) => "error",
+    };
+    let operation = transaction.operation.name();
+    metrics::increment_counter(
+        metrics::COMMITS,
+        &[("operation", operation), ("outcome", outcome)],
+        1,
+    );
+    metrics::record_histogram(
+        metrics::COMMIT_DURATION_SECONDS,
+        &[("operation", operation)],
+        start.elapsed().as_secs_f64(),
+    );
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn do_commit_transaction(
+    dataset: &Dataset,
+    object_store: &ObjectStore,
+    commit_handler: &dyn CommitHandler,
+    transaction: &Transaction,
+    write_config: &ManifestWriteConfig,
+    commit_config: &CommitConfig,
+    manifest_naming_scheme: ManifestNamingScheme,
+    affected_rows: Option<&RowIdTreeMap>,
+) -> Result<(Manifest, ManifestLocation)> {
+    dataset.ensure_writable("commit to")?;
+    // If the handler queues writers, wait for our turn. The lease is held
+    // until we return, so at most one writer is resolving conflicts at a time.
+    let _queue_lease = if let Some(queue) = commit_handler.commit_queue() {
+        Some(queue.acquire().await?)
+    } else {
+        None
+    };
+
+    // The transaction may already have been committed by an earlier attempt of a
+    // retried job step, which may have read an older version than this one.
+    if let Some(transaction_id) = transaction.transaction_id() {
+        if let Some(version) = find_committed_transaction(dataset, transaction_id).await? {
+            let committed = dataset.checkout_version(version).await?;
+            return Ok((
+                committed.manifest.as_ref().clone(),
+                committed.manifest_location.clone(),
+            ));
+        }
+    }
+
     let new_blob_version = if let Some(blob_op) = transaction.blobs_op.as_ref() {
         let blobs_dataset = dataset.blobs_dataset().await?.unwrap();
         let blobs_tx =
@@ -775,6 +844,20 @@ pub(crate) async fn commit_transaction(
         // Use small amount of backoff to handle transactions that all
         // started at exact same time better.
 
+        // The transaction may have been committed concurrently since the check above
+        if let Some(transaction_id) = transaction.transaction_id() {
+            if let Some((version, _)) = other_transactions
+                .iter()
+                .find(|(_, other)| other.transaction_id() == Some(transaction_id))
+            {
+                let committed = dataset.checkout_version(*version).await?;
+                return Ok((
+                    committed.manifest.as_ref().clone(),
+                    committed.manifest_location.clone(),
+                ));
+            }
+        }
+
         let mut rebase =
             TransactionRebase::try_new(&original_dataset, transaction, affected_rows).await?;
 
@@ -795,15 +878,16 @@ pub(crate) async fn commit_transaction(
         // Build an up-to-date manifest from the transaction and current manifest
         let (mut manifest, mut indices) = match transaction.operation {
             Operation::Restore { version } => {
-                Transaction::restore_old_manifest(
-                    object_store,
-                    commit_handler,
-                    &dataset.base,
-                    version,
-                    write_config,
-                    &transaction_file,
-                )
-                .await?
+                transaction
+                    .restore_old_manifest(
+                        object_store,
+                        commit_handler,
+                        &dataset.base,
+                        version,
+                        write_config,
+                        &transaction_file,
+                    )
+                    .await?
             }
             _ => transaction.build_manifest(
                 Some(dataset.manifest.as_ref()),
@@ -815,6 +899,10 @@ pub(crate) async fn commit_transaction(
         };
 
         manifest.version = target_version;
+        manifest.transaction_properties.insert(
+            COMMIT_DURATION_KEY.to_string(),
+            start.elapsed().as_millis().to_string(),
+        );
 
         let previous_writer_version = &dataset.manifest.writer_version;
         // The versions of Lance prior to when we started writing the writer version
@@ -831,6 +919,15 @@ pub(crate) async fn commit_transaction(
 
         migrate_indices(&dataset, &mut indices).await?;
 
+        quota::check_quota(
+            &dataset,
+            object_store,
+            commit_handler,
+            &transaction,
+            &manifest,
+        )
+        .await?;
+
         // Try to commit the manifest
         let result = write_manifest_file(
             object_store,
@@ -874,6 +971,7 @@ pub(crate) async fn commit_transaction(
                 return Ok((manifest, manifest_location));
             }
             Err(CommitError::CommitConflict) => {
+                metrics::increment_counter(metrics::COMMIT_CONFLICTS, &[], 1);
                 let next_attempt_i = backoff.attempt() + 1;
 
                 if backoff.attempt() == 0 {
@@ -921,8 +1019,10 @@ mod tests {
     use lance_index::IndexType;
     use lance_linalg::distance::MetricType;
     use lance_table::format::{DataFile, DataStorageFormat};
+    use lance_table::io::commit::queue::{CommitQueue, QueuedCommitHandler};
     use lance_table::io::commit::{
-        CommitLease, CommitLock, RenameCommitHandler, UnsafeCommitHandler,
+        CommitLease, CommitLock, ConditionalPutCommitHandler, RenameCommitHandler,
+        UnsafeCommitHandler,
     };
     use lance_testing::datagen::generate_random_array;
 
@@ -1250,6 +1350,170 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_queued_concurrent_writes() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        Dataset::write(
+            RecordBatchIterator::new(vec![].into_iter().map(Ok), schema.clone()),
+            test_uri,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let handler = Arc::new(QueuedCommitHandler::new(
+            Arc::new(ConditionalPutCommitHandler),
+            CommitQueue::new(None),
+        ));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+
+        let futures: Vec<_> = (0..5)
+            .map(|_| {
+                let batch = batch.clone();
+                let schema = schema.clone();
+                let uri = test_uri.to_string();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+                    Dataset::write(
+                        reader,
+                        &uri,
+                        Some(WriteParams {
+                            mode: WriteMode::Append,
+                            commit_handler: Some(handler),
+                            ..Default::default()
+                        }),
+                    )
+                    .await
+                })
+            })
+            .collect();
+        for result in join_all(futures).await {
+            assert!(matches!(result, Ok(Ok(_))), "{:?}", result);
+        }
+
+        let metrics = handler.metrics();
+        assert_eq!(metrics.num_granted, 5);
+        assert_eq!(metrics.num_timed_out, 0);
+        assert_eq!(metrics.num_waiting, 0);
+
+        let dataset = Dataset::open(test_uri).await.unwrap();
+        assert_eq!(dataset.version().version, 6);
+        assert_eq!(dataset.get_fragments().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_quota() {
+        use super::quota::DatasetQuota;
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let data = |range: std::ops::Range<i32>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(range))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let assert_quota_exceeded = |result: Result<()>, what: &str| match result {
+            Err(Error::QuotaExceeded { message, .. }) => assert!(message.contains(what)),
+            other => panic!("Expected quota error, got {:?}", other),
+        };
+
+        let mut dataset = Dataset::write(data(0..100), test_uri, None).await.unwrap();
+        let mut quota = DatasetQuota {
+            max_rows: Some(150),
+            max_versions: Some(4),
+            ..Default::default()
+        };
+        dataset.update_config(quota.to_config()).await.unwrap();
+        assert_eq!(
+            DatasetQuota::from_config(&dataset.config().unwrap()).unwrap(),
+            quota
+        );
+
+        assert_quota_exceeded(dataset.append(data(100..200), None).await, "rows");
+        dataset.append(data(100..150), None).await.unwrap();
+        assert_eq!(dataset.version().version, 3);
+
+        // Lowering the quota below the current usage is allowed, and so is
+        // shrinking the dataset afterwards. But there is no room for a new version.
+        quota.max_rows = Some(100);
+        dataset.update_config(quota.to_config()).await.unwrap();
+        assert_quota_exceeded(dataset.delete("i < 10").await, "versions");
+
+        quota.max_versions = Some(10);
+        dataset.update_config(quota.to_config()).await.unwrap();
+        dataset.delete("i < 10").await.unwrap();
+        assert_eq!(dataset.count_rows(None).await.unwrap(), 140);
+        assert_quota_exceeded(dataset.append(data(200..201), None).await, "rows");
+    }
+
+    #[tokio::test]
+    async fn test_version_quota_after_cleanup() {
+        use super::quota::DatasetQuota;
+        use lance_core::utils::testing::MockClock;
+
+        // Versions are written at the mocked time, so it has to move on for cleanup
+        let clock = MockClock::new();
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let data = || {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(0..10))],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+
+        let mut dataset = Dataset::write(data(), test_uri, None).await.unwrap();
+        let quota = DatasetQuota {
+            max_versions: Some(3),
+            ..Default::default()
+        };
+        dataset.update_config(quota.to_config()).await.unwrap();
+        // Counts the versions, and caches the count for version 3
+        dataset.append(data(), None).await.unwrap();
+        assert!(matches!(
+            dataset.append(data(), None).await,
+            Err(Error::QuotaExceeded { .. })
+        ));
+
+        // The cached count is stale once old versions are removed
+        clock.set_system_time(chrono::Duration::seconds(1));
+        dataset
+            .cleanup_old_versions(chrono::Duration::zero(), Some(true), None)
+            .await
+            .unwrap();
+        dataset.append(data(), None).await.unwrap();
+        assert_eq!(dataset.version().version, 4);
+    }
+
     async fn get_empty_dataset() -> (tempfile::TempDir, Dataset) {
         let test_dir = tempfile::tempdir().unwrap();
         let test_uri = test_dir.path().to_str().unwrap();
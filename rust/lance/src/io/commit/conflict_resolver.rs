@@ -48,6 +48,7 @@ impl<'a> TransactionRebase<'a> {
             | Operation::ReserveFragments { .. }
             | Operation::Project { .. }
             | Operation::UpdateConfig { .. }
+            | Operation::EnableStableRowIds
             | Operation::Restore { .. } => Ok(Self {
                 transaction,
                 affected_rows,
@@ -206,6 +207,9 @@ impl<'a> TransactionRebase<'a> {
             Operation::UpdateConfig { .. } => {
                 self.check_update_config_txn(other_transaction, other_version)
             }
+            Operation::EnableStableRowIds => {
+                self.check_enable_stable_row_ids_txn(other_transaction, other_version)
+            }
         }
     }
 
@@ -300,7 +304,8 @@ impl<'a> TransactionRebase<'a> {
                 }
                 Ok(())
             }
-            Operation::Merge { .. } => {
+            // The updated fragments were read before row ids were assigned.
+            Operation::Merge { .. } | Operation::EnableStableRowIds => {
                 Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
             }
             Operation::Overwrite { .. } | Operation::Restore { .. } => {
@@ -419,6 +424,11 @@ impl<'a> TransactionRebase<'a> {
                     // TODO(rmeng): check that the new indices isn't on the column being replaced
                     Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
                 }
+                // The index was built against row addresses, but the dataset
+                // is now addressed by stable row ids.
+                Operation::EnableStableRowIds => {
+                    Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
+                }
                 Operation::Overwrite { .. } | Operation::Restore { .. } => Err(
                     self.incompatible_conflict_err(other_transaction, other_version, location!())
                 ),
@@ -504,6 +514,10 @@ impl<'a> TransactionRebase<'a> {
                     // TODO(rmeng): check that the fragments being replaced are not part of the groups
                     Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
                 }
+                // The rewritten fragments need to carry over the newly assigned row ids.
+                Operation::EnableStableRowIds => {
+                    Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
+                }
                 Operation::CreateIndex {
                     new_indices,
                     removed_indices,
@@ -621,7 +635,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::Restore { .. }
             | Operation::ReserveFragments { .. }
             | Operation::Update { .. }
-            | Operation::Project { .. } => Ok(()),
+            | Operation::Project { .. }
+            | Operation::EnableStableRowIds => Ok(()),
         }
     }
 
@@ -645,7 +660,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::Project { .. }
             | Operation::Merge { .. }
             | Operation::UpdateConfig { .. }
-            | Operation::DataReplacement { .. } => Ok(()),
+            | Operation::DataReplacement { .. }
+            | Operation::EnableStableRowIds => Ok(()),
         }
     }
 
@@ -661,7 +677,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::Merge { .. }
             | Operation::UpdateConfig { .. }
             | Operation::ReserveFragments { .. }
-            | Operation::Project { .. } => Ok(()),
+            | Operation::Project { .. }
+            | Operation::EnableStableRowIds => Ok(()),
             Operation::CreateIndex { .. } => {
                 // TODO(rmeng): check that the new indices isn't on the column being replaced
                 Err(self.incompatible_conflict_err(other_transaction, other_version, location!()))
@@ -695,7 +712,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::Delete { .. }
             | Operation::Rewrite { .. }
             | Operation::Merge { .. }
-            | Operation::DataReplacement { .. } => {
+            | Operation::DataReplacement { .. }
+            | Operation::EnableStableRowIds => {
                 Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
             }
             Operation::Overwrite { .. } | Operation::Restore { .. } | Operation::Project { .. } => {
@@ -717,7 +735,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::ReserveFragments { .. }
             | Operation::Update { .. }
             | Operation::Project { .. }
-            | Operation::UpdateConfig { .. } => Ok(()),
+            | Operation::UpdateConfig { .. }
+            | Operation::EnableStableRowIds => Ok(()),
         }
     }
 
@@ -739,7 +758,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::ReserveFragments { .. }
             | Operation::Update { .. }
             | Operation::Project { .. }
-            | Operation::UpdateConfig { .. } => Ok(()),
+            | Operation::UpdateConfig { .. }
+            | Operation::EnableStableRowIds => Ok(()),
         }
     }
 
@@ -757,7 +777,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::CreateIndex { .. }
             | Operation::DataReplacement { .. }
             | Operation::Rewrite { .. }
-            | Operation::ReserveFragments { .. } => Ok(()),
+            | Operation::ReserveFragments { .. }
+            | Operation::EnableStableRowIds => Ok(()),
             Operation::Merge { .. } | Operation::Project { .. } => {
                 // Need to recompute the schema
                 Err(self.retryable_conflict_err(other_transaction, other_version, location!()))
@@ -827,13 +848,41 @@ impl<'a> TransactionRebase<'a> {
                 | Operation::Restore { .. }
                 | Operation::ReserveFragments { .. }
                 | Operation::Update { .. }
-                | Operation::Project { .. } => Ok(()),
+                | Operation::Project { .. }
+                | Operation::EnableStableRowIds => Ok(()),
             }
         } else {
             Err(wrong_operation_err(&self.transaction.operation))
         }
     }
 
+    fn check_enable_stable_row_ids_txn(
+        &mut self,
+        other_transaction: &Transaction,
+        other_version: u64,
+    ) -> Result<()> {
+        match &other_transaction.operation {
+            // Row ids are assigned to whatever fragments exist at commit time,
+            // so changes to the fragment list are fine.
+            Operation::Append { .. }
+            | Operation::Delete { .. }
+            | Operation::Update { .. }
+            | Operation::Rewrite { .. }
+            | Operation::DataReplacement { .. }
+            | Operation::Merge { .. }
+            | Operation::ReserveFragments { .. }
+            | Operation::Project { .. }
+            | Operation::UpdateConfig { .. } => Ok(()),
+            // Indices hold row addresses and would be invalidated.
+            Operation::CreateIndex { .. }
+            | Operation::EnableStableRowIds
+            | Operation::Overwrite { .. }
+            | Operation::Restore { .. } => {
+                Err(self.incompatible_conflict_err(other_transaction, other_version, location!()))
+            }
+        }
+    }
+
     /// Writes
     pub async fn finish(self, dataset: &Dataset) -> Result<Transaction> {
         match &self.transaction.operation {
@@ -849,7 +898,8 @@ impl<'a> TransactionRebase<'a> {
             | Operation::Restore { .. }
             | Operation::ReserveFragments { .. }
             | Operation::Project { .. }
-            | Operation::UpdateConfig { .. } => Ok(self.transaction),
+            | Operation::UpdateConfig { .. }
+            | Operation::EnableStableRowIds => Ok(self.transaction),
         }
     }
 
@@ -2089,6 +2139,7 @@ mod tests {
             | Operation::ReserveFragments { .. }
             | Operation::Project { .. }
             | Operation::UpdateConfig { .. }
+            | Operation::EnableStableRowIds
             | Operation::Restore { .. } => Box::new(std::iter::empty()),
             Operation::Delete {
                 updated_fragments,
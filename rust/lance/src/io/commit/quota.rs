@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Dataset quotas
+//!
+//! Quotas limit how large a dataset can grow. They are stored in the dataset
+//! config (see [`Dataset::update_config`]) under the `lance.quota.*` keys, so
+//! every writer enforces them, and are checked against the new manifest before
+//! each commit. A commit that would exceed a quota fails with
+//! [`Error::QuotaExceeded`].
+//!
+//! Commits that only change the config are never rejected, so that quotas can
+//! always be raised or removed. Row and byte quotas only reject commits that
+//! grow the dataset, so a dataset that is already over quota (for example
+//! because the quota was lowered) can still be shrunk with deletes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use lance_core::{Error, Result};
+use lance_table::format::Manifest;
+use lance_table::io::commit::CommitHandler;
+use snafu::location;
+
+use crate::dataset::transaction::{Operation, Transaction};
+use crate::io::ObjectStore;
+use crate::Dataset;
+
+/// Config key for the maximum number of live (not deleted) rows.
+pub const MAX_ROWS_KEY: &str = "lance.quota.max_rows";
+/// Config key for the maximum total size of the data files, in bytes.
+pub const MAX_BYTES_KEY: &str = "lance.quota.max_bytes";
+/// Config key for the maximum number of versions that can exist at once.
+///
+/// Removing old versions with cleanup frees up room for new ones.
+pub const MAX_VERSIONS_KEY: &str = "lance.quota.max_versions";
+
+/// The quotas configured for a dataset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetQuota {
+    pub max_rows: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub max_versions: Option<u64>,
+}
+
+impl DatasetQuota {
+    /// Read the quotas from a dataset config.
+    pub fn from_config(config: &HashMap<String, String>) -> Result<Self> {
+        let parse = |key: &str| -> Result<Option<u64>> {
+            config
+                .get(key)
+                .map(|value| {
+                    value.parse::<u64>().map_err(|e| Error::InvalidInput {
+                        source: format!("Invalid value for {}: {}", key, e).into(),
+                        location: location!(),
+                    })
+                })
+                .transpose()
+        };
+        Ok(Self {
+            max_rows: parse(MAX_ROWS_KEY)?,
+            max_bytes: parse(MAX_BYTES_KEY)?,
+            max_versions: parse(MAX_VERSIONS_KEY)?,
+        })
+    }
+
+    /// The config entries for the quotas that are set, for use with
+    /// [`Dataset::update_config`].
+    pub fn to_config(&self) -> HashMap<String, String> {
+        [
+            (MAX_ROWS_KEY, self.max_rows),
+            (MAX_BYTES_KEY, self.max_bytes),
+            (MAX_VERSIONS_KEY, self.max_versions),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+        .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.max_rows.is_none() && self.max_bytes.is_none() && self.max_versions.is_none()
+    }
+}
+
+/// The resources used by a version of a dataset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Usage {
+    rows: u64,
+    bytes: u64,
+}
+
+impl Usage {
+    fn of(manifest: &Manifest) -> Self {
+        manifest
+            .fragments
+            .iter()
+            .fold(Self::default(), |mut usage, fragment| {
+                usage.rows += fragment.num_rows().unwrap_or_default() as u64;
+                usage.bytes += fragment
+                    .files
+                    .iter()
+                    .filter_map(|file| file.file_size_bytes.get())
+                    .map(|size| size.get())
+                    .sum::<u64>();
+                usage
+            })
+    }
+}
+
+fn quota_exceeded(what: &str, value: u64, limit: u64) -> Error {
+    Error::QuotaExceeded {
+        message: format!(
+            "the commit would bring the dataset to {} {}, which exceeds the quota of {}",
+            value, what, limit
+        ),
+        location: location!(),
+    }
+}
+
+/// Check that committing `manifest` keeps the dataset within its quotas.
+///
+/// `dataset` is the latest version, which `manifest` will replace.
+pub(super) async fn check_quota(
+    dataset: &Dataset,
+    object_store: &ObjectStore,
+    commit_handler: &dyn CommitHandler,
+    transaction: &Transaction,
+    manifest: &Manifest,
+) -> Result<()> {
+    if matches!(transaction.operation, Operation::UpdateConfig { .. }) {
+        return Ok(());
+    }
+    let quota = DatasetQuota::from_config(&manifest.config)?;
+    if quota.is_empty() {
+        return Ok(());
+    }
+
+    let previous = Usage::of(dataset.manifest.as_ref());
+    let next = Usage::of(manifest);
+    if let Some(max_rows) = quota.max_rows {
+        if next.rows > max_rows && next.rows > previous.rows {
+            return Err(quota_exceeded("rows", next.rows, max_rows));
+        }
+    }
+    if let Some(max_bytes) = quota.max_bytes {
+        if next.bytes > max_bytes && next.bytes > previous.bytes {
+            return Err(quota_exceeded("bytes", next.bytes, max_bytes));
+        }
+    }
+    if let Some(max_versions) = quota.max_versions {
+        // Cleanups only ever remove versions, so a cached count may be too high but never
+        // too low.  It is enough to accept the commit, but is recounted before rejecting it.
+        let cached = dataset
+            .metadata_cache
+            .get::<u64>(&version_count_cache_key(dataset.manifest.version));
+        let mut num_versions = match &cached {
+            Some(count) => **count + 1,
+            None => count_versions(dataset, object_store, commit_handler).await? + 1,
+        };
+        if num_versions > max_versions && cached.is_some() {
+            num_versions = count_versions(dataset, object_store, commit_handler).await? + 1;
+        }
+        if num_versions > max_versions {
+            return Err(quota_exceeded("versions", num_versions, max_versions));
+        }
+        dataset.metadata_cache.insert(
+            &version_count_cache_key(manifest.version),
+            Arc::new(num_versions),
+        );
+    }
+    Ok(())
+}
+
+/// Key of the number of versions that exist once `version` is the latest one.
+fn version_count_cache_key(version: u64) -> String {
+    format!("quota/versions/{version}")
+}
+
+async fn count_versions(
+    dataset: &Dataset,
+    object_store: &ObjectStore,
+    commit_handler: &dyn CommitHandler,
+) -> Result<u64> {
+    commit_handler
+        .list_manifest_locations(&dataset.base, object_store, false)
+        .try_fold(0u64, |count, _| async move { Ok(count + 1) })
+        .await
+}
@@ -5,6 +5,7 @@
 //!
 //! WARNING: Internal API with no stability guarantees.
 
+mod allow_list;
 mod filter;
 pub mod filtered_read;
 pub mod fts;
@@ -15,11 +16,13 @@ mod pushdown_scan;
 mod rowids;
 pub mod scalar_index;
 mod scan;
+mod sorted_projection;
 mod take;
 #[cfg(test)]
 pub mod testing;
 pub mod utils;
 
+pub use allow_list::{AllowListFilterExec, AllowListPrefilterExec};
 pub use filter::LanceFilterExec;
 pub use knn::{ANNIvfPartitionExec, ANNIvfSubIndexExec, KNNVectorDistanceExec};
 pub use lance_datafusion::planner::Planner;
@@ -29,6 +32,7 @@ pub use projection::project;
 pub use pushdown_scan::{LancePushdownScanExec, ScanConfig};
 pub use rowids::AddRowAddrExec;
 pub use scan::{LanceScanConfig, LanceScanExec};
+pub use sorted_projection::SortedProjectionScanExec;
 pub use take::TakeExec;
 pub use utils::PreFilterSource;
 pub(crate) use utils::{ShareableRecordBatchStream, ShareableRecordBatchStreamAdapter};
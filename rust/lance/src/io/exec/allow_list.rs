@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Execution nodes that restrict a search to a caller-supplied set of row ids
+
+use std::sync::Arc;
+
+use arrow_array::{cast::AsArray, types::UInt64Type, BooleanArray, RecordBatch};
+use arrow_schema::SchemaRef;
+use arrow_select::filter::filter_record_batch;
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::{SendableRecordBatchStream, TaskContext},
+    physical_plan::{
+        execution_plan::{Boundedness, EmissionType},
+        metrics::{ExecutionPlanMetricsSet, MetricsSet},
+        DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
+    },
+};
+use datafusion_physical_expr::EquivalenceProperties;
+use futures::{StreamExt, TryStreamExt};
+use lance_core::{
+    utils::mask::{RowIdMask, RowIdTreeMap},
+    ROW_ID,
+};
+use lance_index::prefilter::FilterLoader;
+
+use super::{
+    scalar_index::SCALAR_INDEX_SCHEMA,
+    utils::{prefilter_loader, InstrumentedRecordBatchStreamAdapter},
+    PreFilterSource,
+};
+
+/// A prefilter that only allows the row ids in an allow list
+///
+/// If there is another prefilter (e.g. from the query's filter) then only the row ids
+/// allowed by both are allowed.  Like [`super::scalar_index::ScalarIndexExec`], the
+/// output is a row id mask (serialized into a record batch) so it can be used as a
+/// [`PreFilterSource::ScalarIndexQuery`].
+#[derive(Debug)]
+pub struct AllowListPrefilterExec {
+    allowed: Arc<RowIdTreeMap>,
+    prefilter_source: PreFilterSource,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl AllowListPrefilterExec {
+    pub fn new(allowed: Arc<RowIdTreeMap>, prefilter_source: PreFilterSource) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(SCALAR_INDEX_SCHEMA.clone()),
+            Partitioning::RoundRobinBatch(1),
+            EmissionType::Final,
+            Boundedness::Bounded,
+        );
+        Self {
+            allowed,
+            prefilter_source,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    async fn load_mask(
+        allowed: Arc<RowIdTreeMap>,
+        loader: Option<Box<dyn FilterLoader>>,
+    ) -> lance_core::Result<RecordBatch> {
+        let allowed = RowIdMask::from_allowed(allowed.as_ref().clone());
+        let mask = match loader {
+            Some(loader) => loader.load().await? & allowed,
+            None => allowed,
+        };
+        Ok(RecordBatch::try_new(
+            SCALAR_INDEX_SCHEMA.clone(),
+            vec![Arc::new(mask.into_arrow()?)],
+        )?)
+    }
+}
+
+impl DisplayAs for AllowListPrefilterExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let num_allowed = self
+            .allowed
+            .len()
+            .map(|len| len.to_string())
+            .unwrap_or_else(|| "?".to_string());
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "AllowListPrefilter: num_allowed={}", num_allowed)
+            }
+            DisplayFormatType::TreeRender => {
+                write!(f, "AllowListPrefilter\nnum_allowed={}", num_allowed)
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for AllowListPrefilterExec {
+    fn name(&self) -> &str {
+        "AllowListPrefilterExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        SCALAR_INDEX_SCHEMA.clone()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        match &self.prefilter_source {
+            PreFilterSource::FilteredRowIds(src) | PreFilterSource::ScalarIndexQuery(src) => {
+                vec![src]
+            }
+            PreFilterSource::None => vec![],
+        }
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != self.children().len() {
+            return Err(DataFusionError::Internal(
+                "AllowListPrefilterExec: invalid number of children".into(),
+            ));
+        }
+        let prefilter_source = match &self.prefilter_source {
+            PreFilterSource::FilteredRowIds(_) => {
+                PreFilterSource::FilteredRowIds(children.pop().unwrap())
+            }
+            PreFilterSource::ScalarIndexQuery(_) => {
+                PreFilterSource::ScalarIndexQuery(children.pop().unwrap())
+            }
+            PreFilterSource::None => PreFilterSource::None,
+        };
+        Ok(Arc::new(Self::new(self.allowed.clone(), prefilter_source)))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let loader = prefilter_loader(context, partition, &self.prefilter_source)?;
+        let batch_fut = Self::load_mask(self.allowed.clone(), loader);
+        let stream = futures::stream::once(batch_fut)
+            .map_err(DataFusionError::from)
+            .boxed();
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            SCALAR_INDEX_SCHEMA.clone(),
+            stream,
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
+
+/// Drops the rows whose `_rowid` is not in an allow list
+///
+/// This applies the allow list to searches that don't go through an index, such as a
+/// flat vector search.
+#[derive(Debug)]
+pub struct AllowListFilterExec {
+    input: Arc<dyn ExecutionPlan>,
+    allowed: Arc<RowIdTreeMap>,
+    row_id_pos: usize,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl AllowListFilterExec {
+    /// Create a new node
+    ///
+    /// # Errors
+    ///
+    /// If the input doesn't have a `_rowid` column
+    pub fn try_new(input: Arc<dyn ExecutionPlan>, allowed: Arc<RowIdTreeMap>) -> Result<Self> {
+        let (row_id_pos, _) = input.schema().column_with_name(ROW_ID).ok_or_else(|| {
+            DataFusionError::Internal(
+                "AllowListFilterExec: input schema does not have a _rowid column".into(),
+            )
+        })?;
+        let properties = input.properties().clone();
+        Ok(Self {
+            input,
+            allowed,
+            row_id_pos,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl DisplayAs for AllowListFilterExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AllowListFilter")
+    }
+}
+
+impl ExecutionPlan for AllowListFilterExec {
+    fn name(&self) -> &str {
+        "AllowListFilterExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        // We aren't doing much work here, best to avoid the thread overhead
+        vec![false]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Internal(
+                "AllowListFilterExec: invalid number of children".into(),
+            ));
+        }
+        Ok(Arc::new(Self::try_new(
+            children.into_iter().next().unwrap(),
+            self.allowed.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let allowed = self.allowed.clone();
+        let row_id_pos = self.row_id_pos;
+        let stream = self
+            .input
+            .execute(partition, context)?
+            .map(move |batch| {
+                let batch = batch?;
+                let row_ids = batch.column(row_id_pos).as_primitive::<UInt64Type>();
+                let keep = row_ids
+                    .iter()
+                    .map(|row_id| Some(row_id.is_some_and(|row_id| allowed.contains(row_id))))
+                    .collect::<BooleanArray>();
+                Ok(filter_record_batch(&batch, &keep)?)
+            })
+            .boxed();
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            self.schema(),
+            stream,
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
@@ -5,28 +5,35 @@ use std::iter::Peekable;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{ops::Range, sync::Arc};
 
-use arrow_schema::SchemaRef;
+use arrow_schema::{Schema as ArrowSchema, SchemaRef};
 use async_recursion::async_recursion;
+use datafusion::common::DFSchema;
 use datafusion::error::{DataFusionError, Result as DataFusionResult};
 use datafusion::execution::{SendableRecordBatchStream, TaskContext};
-use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion::logical_expr::Expr;
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_expr::execution_props::ExecutionProps;
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     execution_plan::{Boundedness, EmissionType},
     DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties,
 };
+use datafusion::scalar::ScalarValue;
 use datafusion_physical_expr::{EquivalenceProperties, Partitioning, PhysicalExpr};
 use datafusion_physical_plan::metrics::{BaselineMetrics, Count, Time};
 use futures::stream::BoxStream;
 use futures::{FutureExt, Stream, StreamExt, TryStreamExt};
+use lance_core::datatypes::{Projection, Schema};
 use lance_core::utils::deletion::DeletionVector;
 use lance_core::utils::futures::FinallyStreamExt;
-use lance_core::{datatypes::Projection, Error, Result};
+use lance_core::{Error, Result};
 use lance_datafusion::planner::Planner;
 use lance_datafusion::utils::{
     ExecutionPlanMetricsSetExt, FRAGMENTS_SCANNED_METRIC, RANGES_SCANNED_METRIC,
     ROWS_SCANNED_METRIC, TASK_WAIT_TIME_METRIC,
 };
+use lance_file::v2::statistics::ChunkStatistics;
 use lance_index::scalar::expression::{FilterPlan, IndexExprResult, ScalarIndexExpr};
 use lance_index::{DatasetIndexExt, ScalarIndexCriteria};
 use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
@@ -44,6 +51,7 @@ use crate::dataset::scanner::{
 };
 use crate::Dataset;
 
+use super::pushdown_scan::extract_guarantees;
 use super::utils::{IndexMetrics, IoMetrics};
 
 struct EvaluatedIndex {
@@ -86,6 +94,9 @@ struct LoadedFragment {
     //
     // This count does not include deleted rows
     num_logical_rows: u64,
+    // The chunk statistics of the data files that have them, only loaded if
+    // there is a filter
+    chunk_statistics: Vec<ChunkStatistics>,
 }
 
 /// Given a sorted iterator of deleted row offsets, return a sorted iterator of valid row ranges
@@ -301,6 +312,8 @@ impl FilteredReadStream {
             .unwrap_or_else(|| dataset.fragments().clone());
         global_metrics.fragments_scanned.add(fragments.len());
 
+        let statistics_projection = Self::statistics_projection(dataset.as_ref(), &options);
+
         // Ideally we don't need to collect here but if we don't we get "implementation of FnOnce is
         // not general enough" false positives from rustc
         let frag_futs = fragments
@@ -310,6 +323,7 @@ impl FilteredReadStream {
                     dataset.clone(),
                     frag.clone(),
                     options.with_deleted_rows,
+                    statistics_projection.clone(),
                 ))
             })
             .collect::<Vec<_>>();
@@ -330,6 +344,7 @@ impl FilteredReadStream {
             output_schema.clone(),
             loaded_fragments,
             &evaluated_index,
+            statistics_projection,
             options,
             &global_metrics,
             scan_scheduler.clone(),
@@ -350,10 +365,30 @@ impl FilteredReadStream {
         })
     }
 
+    /// The fields used by the filter, if there is one
+    ///
+    /// These are the fields whose chunk statistics we load.  If the filter
+    /// refers to something other than fields of the dataset, e.g. the row id,
+    /// we don't use statistics.
+    fn statistics_projection(
+        dataset: &Dataset,
+        options: &FilteredReadOptions,
+    ) -> Option<Arc<Schema>> {
+        let filter_plan = &options.filter_plan;
+        // The full expression includes the refine expression
+        let expr = filter_plan
+            .full_expr
+            .as_ref()
+            .or(filter_plan.refine_expr.as_ref())?;
+        let columns = Planner::column_names_in_expr(expr);
+        dataset.schema().project(&columns).ok().map(Arc::new)
+    }
+
     async fn load_fragment(
         dataset: Arc<Dataset>,
         frag: Fragment,
         include_deleted_rows: bool,
+        statistics_projection: Option<Arc<Schema>>,
     ) -> Result<LoadedFragment> {
         let file_fragment = FileFragment::new(dataset.clone(), frag.clone());
         let deletion_vector = if include_deleted_rows {
@@ -374,12 +409,24 @@ impl FilteredReadStream {
             let addrs_as_ids = Arc::new(RowIdSequence::from(row_ids_start..row_ids_end));
             (addrs_as_ids, num_logical_rows)
         };
+        let chunk_statistics = match statistics_projection {
+            // Legacy files have page statistics instead, used by the pushdown scan
+            Some(projection) if !frag.has_legacy_files() => {
+                file_fragment
+                    .open(projection.as_ref(), FragReadConfig::default())
+                    .await?
+                    .read_chunk_statistics()
+                    .await?
+            }
+            _ => Vec::new(),
+        };
         Ok(LoadedFragment {
             row_id_sequence,
             fragment: file_fragment,
             num_physical_rows,
             num_logical_rows,
             deletion_vector,
+            chunk_statistics,
         })
     }
 
@@ -397,6 +444,7 @@ impl FilteredReadStream {
         output_schema: SchemaRef,
         fragments: Vec<LoadedFragment>,
         evaluated_index: &Option<Arc<EvaluatedIndex>>,
+        statistics_projection: Option<Arc<Schema>>,
         options: FilteredReadOptions,
         global_metrics: &FilteredReadGlobalMetrics,
         scan_scheduler: Arc<ScanScheduler>,
@@ -413,15 +461,15 @@ impl FilteredReadStream {
         let mut _filtered_range_offset = Some(0);
 
         let planner = Planner::new(output_schema);
-        let refine_filter = options
-            .filter_plan
-            .refine_expr
-            .map(|refine_expr| planner.create_physical_expr(&refine_expr))
+        let refine_expr = options.filter_plan.refine_expr;
+        let full_expr = options.filter_plan.full_expr;
+        let refine_filter = refine_expr
+            .as_ref()
+            .map(|refine_expr| planner.create_physical_expr(refine_expr))
             .transpose()?;
-        let full_filter = options
-            .filter_plan
-            .full_expr
-            .map(|full_expr| planner.create_physical_expr(&full_expr))
+        let full_filter = full_expr
+            .as_ref()
+            .map(|full_expr| planner.create_physical_expr(full_expr))
             .transpose()?;
 
         for (
@@ -432,6 +480,7 @@ impl FilteredReadStream {
                 num_logical_rows,
                 num_physical_rows,
                 deletion_vector,
+                chunk_statistics,
             },
         ) in fragments.into_iter().enumerate()
         {
@@ -447,6 +496,7 @@ impl FilteredReadStream {
             // By default we assume we will need to apply the full filter
             // This will get refined if we have an exact match
             let mut filter = &full_filter;
+            let mut filter_expr = &full_expr;
 
             let mut to_read: Vec<Range<u64>> = if let Some(evaluated_index) = evaluated_index {
                 if evaluated_index
@@ -463,6 +513,7 @@ impl FilteredReadStream {
 
                             // Also, with an exact match, we only need to apply the refine filter
                             filter = &refine_filter;
+                            filter_expr = &refine_expr;
 
                             let valid_ranges = row_id_sequence.mask_to_offset_ranges(row_id_mask);
                             if let Some(deletion_vector) = &deletion_vector {
@@ -542,6 +593,21 @@ impl FilteredReadStream {
                 to_read = Self::trim_ranges(to_read, range_start..range_end, range_before_filter);
             }
 
+            // Skip the chunks that cannot match the filter.  This happens after applying
+            // the scan range because the scan range counts all rows, matching or not.
+            if let (Some(filter_expr), Some(statistics_projection)) =
+                (filter_expr, &statistics_projection)
+            {
+                if !chunk_statistics.is_empty() && !to_read.is_empty() {
+                    to_read = Self::prune_ranges(
+                        to_read,
+                        filter_expr,
+                        statistics_projection,
+                        &chunk_statistics,
+                    )?;
+                }
+            }
+
             if !to_read.is_empty() {
                 global_metrics
                     .rows_scanned
@@ -582,6 +648,75 @@ impl FilteredReadStream {
         Ok(scoped_fragments)
     }
 
+    /// Removes the chunks that cannot match `filter`, according to the chunk statistics
+    /// of the data files, from `ranges`
+    fn prune_ranges(
+        ranges: Vec<Range<u64>>,
+        filter: &Expr,
+        statistics_projection: &Schema,
+        chunk_statistics: &[ChunkStatistics],
+    ) -> Result<Vec<Range<u64>>> {
+        let schema: DFSchema = ArrowSchema::from(statistics_projection).try_into()?;
+        let props = ExecutionProps::new();
+        let context = SimplifyContext::new(&props).with_schema(Arc::new(schema));
+        let mut simplifier = ExprSimplifier::new(context);
+
+        let mut ranges = ranges;
+        for statistics in chunk_statistics {
+            let chunk_sizes = statistics
+                .chunk_sizes()
+                .iter()
+                .map(|size| *size as usize)
+                .collect::<Vec<_>>();
+            let guarantees = extract_guarantees(
+                statistics_projection,
+                &chunk_sizes,
+                statistics.field_statistics(),
+            );
+            let mut skipped = Vec::new();
+            for (chunk, guarantees) in statistics.chunk_ranges().into_iter().zip(guarantees) {
+                simplifier = simplifier.with_guarantees(guarantees);
+                // A null filter result doesn't match either
+                if let Ok(Expr::Literal(ScalarValue::Boolean(Some(false) | None), _)) =
+                    simplifier.simplify(filter.clone())
+                {
+                    skipped.push(chunk);
+                }
+            }
+            ranges = Self::remove_ranges(ranges, &skipped);
+        }
+        Ok(ranges)
+    }
+
+    /// Removes `to_remove` from `ranges`, both must be sorted and non-overlapping
+    fn remove_ranges(ranges: Vec<Range<u64>>, to_remove: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut remaining = Vec::with_capacity(ranges.len());
+        let mut to_remove = to_remove.iter().peekable();
+        for mut range in ranges {
+            while range.start < range.end {
+                while to_remove
+                    .peek()
+                    .is_some_and(|removed| removed.end <= range.start)
+                {
+                    to_remove.next();
+                }
+                match to_remove.peek() {
+                    Some(removed) if removed.start < range.end => {
+                        if removed.start > range.start {
+                            remaining.push(range.start..removed.start);
+                        }
+                        range.start = removed.end.min(range.end);
+                    }
+                    _ => {
+                        remaining.push(range.clone());
+                        break;
+                    }
+                }
+            }
+        }
+        remaining
+    }
+
     fn filter_deleted_rows(
         ranges: Vec<Range<u64>>,
         deletion_vector: &Arc<DeletionVector>,
@@ -1161,6 +1296,10 @@ impl ExecutionPlan for FilteredReadExec {
     ) -> DataFusionResult<SendableRecordBatchStream> {
         Ok(self.obtain_stream(partition))
     }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
 }
 
 #[cfg(test)]
@@ -1169,7 +1308,7 @@ mod tests {
         compute::concat_batches,
         datatypes::{UInt16Type, UInt32Type, UInt64Type},
     };
-    use arrow_array::{Array, UInt32Array};
+    use arrow_array::{Array, RecordBatchIterator, UInt32Array};
     use itertools::Itertools;
     use lance_core::datatypes::OnMissing;
     use lance_datagen::{array, r#gen, BatchCount, RowCount};
@@ -1598,4 +1737,63 @@ mod tests {
             .unwrap();
         assert_eq!(num_rows, 300);
     }
+
+    #[test]
+    fn test_remove_ranges() {
+        let remove = FilteredReadStream::remove_ranges;
+        assert_eq!(remove(vec![0..100], &[]), vec![0..100]);
+        assert_eq!(
+            remove(vec![0..100], &[10..20, 50..100]),
+            vec![0..10, 20..50]
+        );
+        assert_eq!(
+            remove(vec![0..10, 15..30, 40..50], &[5..20, 25..45]),
+            vec![0..5, 20..25, 45..50]
+        );
+        assert_eq!(remove(vec![10..20], &[0..100]), Vec::<Range<u64>>::new());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chunk_statistics() {
+        let tmp_path = tempfile::tempdir().unwrap();
+        let batch = gen()
+            .col("x", array::step::<UInt32Type>())
+            .into_batch_rows(RowCount::from(1000))
+            .unwrap();
+        let data = RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema());
+        let write_params = WriteParams {
+            statistics_chunk_rows: Some(100),
+            ..Default::default()
+        };
+        let dataset = Dataset::write(data, tmp_path.path().to_str().unwrap(), Some(write_params))
+            .await
+            .unwrap();
+        let dataset = Arc::new(dataset);
+
+        let planner = Planner::new(Arc::new(arrow_schema::Schema::from(dataset.schema())));
+        let index_info = dataset.scalar_index_info().await.unwrap();
+        let filter_plan = planner
+            .create_filter_plan(
+                planner.parse_filter("x >= 850").unwrap(),
+                &index_info,
+                false,
+            )
+            .unwrap();
+        let options = FilteredReadOptions::basic_full_read(&dataset).with_filter_plan(filter_plan);
+        let plan = FilteredReadExec::try_new(dataset.clone(), options).unwrap();
+        let stream = plan.execute(0, Arc::new(TaskContext::default())).unwrap();
+        let schema = stream.schema();
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        let batch = concat_batches(&schema, &batches).unwrap();
+        assert_eq!(batch.column(0).as_ref(), u32s(vec![850..1000]).as_ref());
+
+        // Only the last two chunks can match
+        let rows_scanned = plan
+            .metrics()
+            .unwrap()
+            .sum_by_name(ROWS_SCANNED_METRIC)
+            .unwrap()
+            .as_usize();
+        assert_eq!(rows_scanned, 200);
+    }
 }
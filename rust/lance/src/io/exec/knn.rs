@@ -34,6 +34,7 @@ use datafusion_physical_plan::metrics::{BaselineMetrics, Count};
 use futures::{future, stream, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use itertools::Itertools;
 use lance_core::utils::futures::FinallyStreamExt;
+use lance_core::utils::tracing::StreamTracingExt;
 use lance_core::ROW_ID;
 use lance_core::{utils::tokio::get_num_compute_intensive_cpus, ROW_ID_FIELD};
 use lance_datafusion::utils::{
@@ -45,16 +46,18 @@ use lance_index::vector::VectorIndex;
 use lance_index::vector::{
     flat::compute_distance, Query, DIST_COL, INDEX_UUID_COLUMN, PART_ID_COLUMN,
 };
-use lance_linalg::distance::DistanceType;
+use lance_linalg::distance::{topk, DistanceType};
 use lance_linalg::kernels::normalize_arrow;
 use lance_table::format::Index;
 use snafu::location;
 use tokio::sync::Notify;
+use tracing::Instrument;
 
 use crate::dataset::Dataset;
 use crate::index::prefilter::{DatasetPreFilter, FilterLoader};
 use crate::index::vector::utils::get_vector_type;
 use crate::index::DatasetIndexInternalExt;
+use crate::utils::query_tracing::index_search_span;
 use crate::{Error, Result};
 use lance_arrow::*;
 
@@ -759,6 +762,9 @@ impl ANNIvfSubIndexExec {
                     let state = state.clone();
                     let index = index.clone();
                     async move {
+                        if let Some(token) = &query.cancellation_token {
+                            token.check()?;
+                        }
                         let _timer = metrics.baseline_metrics.elapsed_compute().timer();
                         let mut query = query.clone();
                         if index.metric_type() == DistanceType::Cosine {
@@ -818,6 +824,9 @@ impl ANNIvfSubIndexExec {
                 let pre_filter = prefilter.clone();
                 let state = state.clone();
                 async move {
+                    if let Some(token) = &query.cancellation_token {
+                        token.check()?;
+                    }
                     let _timer = metrics.baseline_metrics.elapsed_compute().timer();
                     let mut query = query.clone();
                     if index.metric_type() == DistanceType::Cosine {
@@ -981,10 +990,12 @@ impl ExecutionPlan for ANNIvfSubIndexExec {
                     let pre_filter = pre_filter.clone();
                     let state = state.clone();
                     let query = query.clone();
+                    let span = index_search_span(&ds, &column, &index_uuid);
 
                     async move {
                         let raw_index = ds
                             .open_vector_index(&column, &index_uuid, &metrics.index_metrics)
+                            .instrument(span.clone())
                             .await?;
 
                         let early_search = Self::initial_search(
@@ -1003,7 +1014,9 @@ impl ExecutionPlan for ANNIvfSubIndexExec {
                             metrics,
                             state,
                         );
-                        DataFusionResult::Ok(early_search.chain(late_search).boxed())
+                        DataFusionResult::Ok(
+                            early_search.chain(late_search).stream_in_span(span).boxed(),
+                        )
                     }
                 })
                 // Must use flatten_unordered to avoid deadlock.
@@ -1203,12 +1216,16 @@ impl ExecutionPlan for MultivectorScoringExec {
                 missed_sim_sum += min_sim;
             }
 
-            let (row_ids, sims): (Vec<_>, Vec<_>) = results.into_iter().unzip();
-            let dists = sims
-                .into_iter()
-                // it's similarity, so we need to convert it back to distance
-                .map(|sim| num_queries - sim)
-                .collect::<Vec<_>>();
+            // it's similarity, so we need to convert it back to distance,
+            // and keep only the nearest k * refine_factor rows
+            let (row_ids, dists): (Vec<_>, Vec<_>) = topk(
+                results
+                    .into_iter()
+                    .map(|(row_id, sim)| (row_id, num_queries - sim)),
+                k * refactor,
+            )
+            .into_iter()
+            .unzip();
             let row_ids = UInt64Array::from(row_ids);
             let dists = Float32Array::from(dists);
             let batch = RecordBatch::try_new(
@@ -1400,6 +1417,7 @@ mod tests {
             refine_factor: None,
             metric_type: DistanceType::Cosine,
             use_index: true,
+            cancellation_token: None,
         };
 
         async fn multivector_scoring(
@@ -556,107 +556,6 @@ impl FragmentScanner {
         Ok(batch)
     }
 
-    /// Parse the statistics into a set of guarantees for each batch.
-    fn extract_guarantees<'a>(
-        predicate_projection: &'a Schema,
-        batch_sizes: &'a [usize],
-        stats: &RecordBatch,
-    ) -> impl Iterator<Item = Vec<(Expr, NullableInterval)>> + 'a {
-        let mut null_counts: HashMap<i32, PrimitiveArray<Int64Type>> = HashMap::new();
-        let mut min_values: HashMap<i32, Arc<dyn Array>> = HashMap::new();
-        let mut max_values: HashMap<i32, Arc<dyn Array>> = HashMap::new();
-
-        for field_id in predicate_projection.field_ids() {
-            let field_stats = stats.column_by_name(&field_id.to_string());
-            if let Some(field_stats) = field_stats {
-                if !matches!(field_stats.data_type(), DataType::Struct(_)) {
-                    log::error!(
-                        "Invalid statistics: Field stats for field {} is not a struct, but a {}",
-                        field_id,
-                        field_stats.data_type()
-                    );
-                    continue;
-                }
-                let field_stats_ref = field_stats.as_struct();
-
-                if let Some(null_count_col) = field_stats_ref.column_by_name("null_count") {
-                    let null_count_col = null_count_col
-                        .as_any()
-                        .downcast_ref::<Int64Array>()
-                        .unwrap();
-                    null_counts.insert(field_id, null_count_col.clone());
-                }
-
-                if let Some(min_col) = field_stats_ref.column_by_name("min_value") {
-                    min_values.insert(field_id, min_col.clone());
-                }
-
-                if let Some(max_col) = field_stats_ref.column_by_name("max_value") {
-                    max_values.insert(field_id, max_col.clone());
-                }
-            }
-        }
-
-        (0..stats.num_rows())
-            .map(move |batch_id| {
-                let mut guarantees = Vec::new();
-                for field in predicate_projection.fields_pre_order() {
-                    let null_count = if field.nullable {
-                        let maybe_null_count = null_counts.get(&field.id).map(|arr| arr.value(batch_id));
-                        if let Some(null_count) = maybe_null_count {
-                            null_count
-                        } else {
-                            continue
-                        }
-                    } else {
-                        0
-                    };
-
-                    let min_value = {
-                        let maybe_min_value = min_values.get(&field.id)
-                            .map(|arr| ScalarValue::try_from_array(arr, batch_id));
-                        match maybe_min_value {
-                            Some(Ok(min_value)) => min_value,
-                            Some(Err(err)) => {
-                                log::error!("Invalid statistics: Failed to convert min_value for field {} to ScalarValue: {}", field.id, err);
-                                continue
-                            }
-                            None => continue
-                        }
-                    };
-
-                    let max_value = {
-                        let maybe_max_value = max_values.get(&field.id)
-                            .map(|arr| ScalarValue::try_from_array(arr, batch_id));
-                        match maybe_max_value {
-                            Some(Ok(max_value)) => max_value,
-                            Some(Err(err)) => {
-                                log::error!("Invalid statistics: Failed to convert max_value for field {} to ScalarValue: {}", field.id, err);
-                                continue
-                            }
-                            None => continue
-                        }
-                    };
-
-                    let values = Interval::try_new(min_value, max_value).unwrap();
-                    let batch_size = batch_sizes[batch_id];
-                    let interval = match (null_count, batch_size) {
-                        (0, _) => NullableInterval::NotNull { values },
-                        (null_count, batch_size) if null_count == batch_size as i64 => NullableInterval::Null { datatype: field.data_type() },
-                        _ => NullableInterval::MaybeNull { values }
-                    };
-                    let column_path = predicate_projection.field_ancestry_by_id(field.id).unwrap();
-                    let mut parts_iter = column_path.into_iter().map(|part| part.name.as_str());
-                    let mut expr = col(parts_iter.next().unwrap());
-                    for part in parts_iter {
-                        expr = expr.field(part);
-                    }
-                    guarantees.push((expr, interval));
-                }
-                guarantees
-            })
-    }
-
     fn simplified_predicates(&self) -> Result<Vec<Expr>> {
         let num_batches = self.reader.legacy_num_batches();
 
@@ -676,9 +575,7 @@ impl FragmentScanner {
             let mut simplifier = ExprSimplifier::new(context);
 
             let mut predicates = Vec::with_capacity(num_batches);
-            for guarantees in
-                Self::extract_guarantees(&self.predicate_projection, &batch_sizes, stats)
-            {
+            for guarantees in extract_guarantees(&self.predicate_projection, &batch_sizes, stats) {
                 simplifier = simplifier.with_guarantees(guarantees);
                 let simplified_expr = match simplifier.simplify(self.predicate.clone()) {
                     Ok(expr) => expr,
@@ -699,6 +596,118 @@ impl FragmentScanner {
     }
 }
 
+/// Parse the statistics into a set of guarantees for each batch.
+pub(crate) fn extract_guarantees<'a>(
+    predicate_projection: &'a Schema,
+    batch_sizes: &'a [usize],
+    stats: &RecordBatch,
+) -> impl Iterator<Item = Vec<(Expr, NullableInterval)>> + 'a {
+    let mut null_counts: HashMap<i32, PrimitiveArray<Int64Type>> = HashMap::new();
+    let mut min_values: HashMap<i32, Arc<dyn Array>> = HashMap::new();
+    let mut max_values: HashMap<i32, Arc<dyn Array>> = HashMap::new();
+
+    for field_id in predicate_projection.field_ids() {
+        let field_stats = stats.column_by_name(&field_id.to_string());
+        if let Some(field_stats) = field_stats {
+            if !matches!(field_stats.data_type(), DataType::Struct(_)) {
+                log::error!(
+                    "Invalid statistics: Field stats for field {} is not a struct, but a {}",
+                    field_id,
+                    field_stats.data_type()
+                );
+                continue;
+            }
+            let field_stats_ref = field_stats.as_struct();
+
+            if let Some(null_count_col) = field_stats_ref.column_by_name("null_count") {
+                let null_count_col = null_count_col
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                null_counts.insert(field_id, null_count_col.clone());
+            }
+
+            if let Some(min_col) = field_stats_ref.column_by_name("min_value") {
+                min_values.insert(field_id, min_col.clone());
+            }
+
+            if let Some(max_col) = field_stats_ref.column_by_name("max_value") {
+                max_values.insert(field_id, max_col.clone());
+            }
+        }
+    }
+
+    (0..stats.num_rows())
+        .map(move |batch_id| {
+            let mut guarantees = Vec::new();
+            for field in predicate_projection.fields_pre_order() {
+                let null_count = if field.nullable {
+                    let maybe_null_count = null_counts.get(&field.id).map(|arr| arr.value(batch_id));
+                    if let Some(null_count) = maybe_null_count {
+                        null_count
+                    } else {
+                        continue
+                    }
+                } else {
+                    0
+                };
+
+                let min_value = {
+                    let maybe_min_value = min_values.get(&field.id)
+                        .map(|arr| ScalarValue::try_from_array(arr, batch_id));
+                    match maybe_min_value {
+                        Some(Ok(min_value)) => min_value,
+                        Some(Err(err)) => {
+                            log::error!("Invalid statistics: Failed to convert min_value for field {} to ScalarValue: {}", field.id, err);
+                            continue
+                        }
+                        None => continue
+                    }
+                };
+
+                let max_value = {
+                    let maybe_max_value = max_values.get(&field.id)
+                        .map(|arr| ScalarValue::try_from_array(arr, batch_id));
+                    match maybe_max_value {
+                        Some(Ok(max_value)) => max_value,
+                        Some(Err(err)) => {
+                            log::error!("Invalid statistics: Failed to convert max_value for field {} to ScalarValue: {}", field.id, err);
+                            continue
+                        }
+                        None => continue
+                    }
+                };
+
+                let batch_size = batch_sizes[batch_id];
+                let interval = if null_count > 0 && null_count == batch_size as i64 {
+                    // The min and max of an all-null batch are placeholders
+                    NullableInterval::Null { datatype: field.data_type() }
+                } else {
+                    let values = match Interval::try_new(min_value, max_value) {
+                        Ok(values) => values,
+                        Err(err) => {
+                            log::error!("Invalid statistics: Failed to create an interval for field {}: {}", field.id, err);
+                            continue
+                        }
+                    };
+                    if null_count == 0 {
+                        NullableInterval::NotNull { values }
+                    } else {
+                        NullableInterval::MaybeNull { values }
+                    }
+                };
+                let column_path = predicate_projection.field_ancestry_by_id(field.id).unwrap();
+                let mut parts_iter = column_path.into_iter().map(|part| part.name.as_str());
+                let mut expr = col(parts_iter.next().unwrap());
+                for part in parts_iter {
+                    expr = expr.field(part);
+                }
+                guarantees.push((expr, interval));
+            }
+            guarantees
+        })
+}
+
 #[cfg(test)]
 mod test {
     use arrow_array::{
@@ -1401,6 +1410,15 @@ mod test {
                 predicate: col("timestamp")
                     .lt(lit(ScalarValue::TimestampMicrosecond(Some(3), None))),
             },
+            // Evaluated against the dictionary values
+            TestCase {
+                projection_indices: vec![0, 4],
+                predicate: col("str_dict").eq(lit("b")),
+            },
+            TestCase {
+                projection_indices: vec![0, 1, 4],
+                predicate: col("str_dict").in_list(vec![lit("a"), lit("c")], false),
+            },
             // TODO: I think there's something wrong with how we handle nulls with dictionaries.
             // TestCase {
             //     projection_indices: vec![0, 1, 4],
@@ -9,7 +9,7 @@ use crate::{
     index::{prefilter::DatasetPreFilter, DatasetIndexInternalExt},
     Dataset,
 };
-use arrow_array::{RecordBatch, UInt64Array};
+use arrow_array::{cast::AsArray, types::UInt64Type, Int64Array, RecordBatch, UInt64Array};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use async_trait::async_trait;
 use datafusion::{
@@ -35,12 +35,13 @@ use lance_datafusion::chunker::break_stream;
 use lance_index::{
     metrics::MetricsCollector,
     scalar::{
+        btree::BTreeIndex,
         expression::{IndexExprResult, ScalarIndexExpr, ScalarIndexLoader, ScalarIndexSearch},
         SargableQuery, ScalarIndex,
     },
     DatasetIndexExt, ScalarIndexCriteria,
 };
-use lance_table::format::Fragment;
+use lance_table::format::{Fragment, Index as IndexMetadata};
 use roaring::RoaringBitmap;
 use snafu::location;
 use tracing::{debug_span, instrument};
@@ -500,32 +501,7 @@ impl MaterializeIndexExec {
         fragments: Arc<Vec<Fragment>>,
         metrics: Arc<IndexMetrics>,
     ) -> Result<RecordBatch> {
-        let expr_result = expr.evaluate(dataset.as_ref(), metrics.as_ref());
-        let span = debug_span!("create_prefilter");
-        let prefilter = span.in_scope(|| {
-            let fragment_bitmap =
-                RoaringBitmap::from_iter(fragments.iter().map(|frag| frag.id as u32));
-            // The user-requested `fragments` is guaranteed to be stricter than the index's fragment
-            // bitmap.  This node only runs on indexed fragments and any fragments that were deleted
-            // when the index was trained will still be deleted when the index is queried.
-            DatasetPreFilter::create_deletion_mask(dataset.clone(), fragment_bitmap)
-        });
-        let mask = if let Some(prefilter) = prefilter {
-            let (expr_result, prefilter) = futures::try_join!(expr_result, prefilter)?;
-            let mask = match expr_result {
-                IndexExprResult::Exact(mask) => mask,
-                IndexExprResult::AtMost(mask) => mask,
-                IndexExprResult::AtLeast(_) => todo!("Support AtLeast in MaterializeIndexExec"),
-            };
-            mask & (*prefilter).clone()
-        } else {
-            let expr_result = expr_result.await?;
-            match expr_result {
-                IndexExprResult::Exact(mask) => mask,
-                IndexExprResult::AtMost(mask) => mask,
-                IndexExprResult::AtLeast(_) => todo!("Support AtLeast in MaterializeIndexExec"),
-            }
-        };
+        let mask = search_with_deletions(&expr, &dataset, &fragments, metrics.as_ref()).await?;
         let ids = row_ids_for_mask(mask, &dataset, &fragments).await?;
         let ids = UInt64Array::from(ids);
         Ok(RecordBatch::try_new(
@@ -535,6 +511,40 @@ impl MaterializeIndexExec {
     }
 }
 
+/// Search the index and remove the rows deleted from `fragments` from the result
+async fn search_with_deletions(
+    expr: &ScalarIndexExpr,
+    dataset: &Arc<Dataset>,
+    fragments: &[Fragment],
+    metrics: &IndexMetrics,
+) -> Result<RowIdMask> {
+    let expr_result = expr.evaluate(dataset.as_ref(), metrics);
+    let span = debug_span!("create_prefilter");
+    let prefilter = span.in_scope(|| {
+        let fragment_bitmap = RoaringBitmap::from_iter(fragments.iter().map(|frag| frag.id as u32));
+        // The user-requested `fragments` is guaranteed to be stricter than the index's fragment
+        // bitmap.  This node only runs on indexed fragments and any fragments that were deleted
+        // when the index was trained will still be deleted when the index is queried.
+        DatasetPreFilter::create_deletion_mask(dataset.clone(), fragment_bitmap)
+    });
+    if let Some(prefilter) = prefilter {
+        let (expr_result, prefilter) = futures::try_join!(expr_result, prefilter)?;
+        let mask = match expr_result {
+            IndexExprResult::Exact(mask) => mask,
+            IndexExprResult::AtMost(mask) => mask,
+            IndexExprResult::AtLeast(_) => todo!("Support AtLeast in MaterializeIndexExec"),
+        };
+        Ok(mask & (*prefilter).clone())
+    } else {
+        let expr_result = expr_result.await?;
+        Ok(match expr_result {
+            IndexExprResult::Exact(mask) => mask,
+            IndexExprResult::AtMost(mask) => mask,
+            IndexExprResult::AtLeast(_) => todo!("Support AtLeast in MaterializeIndexExec"),
+        })
+    }
+}
+
 #[instrument(name = "make_row_ids", skip(mask, dataset, fragments))]
 async fn row_ids_for_mask(
     mask: RowIdMask,
@@ -716,6 +726,567 @@ impl ExecutionPlan for MaterializeIndexExec {
     }
 }
 
+lazy_static::lazy_static! {
+    pub static ref SCALAR_INDEX_COUNT_SCHEMA: SchemaRef = Arc::new(Schema::new(vec![Field::new("count_rows", DataType::Int64, false)]));
+}
+
+/// An execution node that counts the rows matching a scalar index search without reading data
+///
+/// The index is searched and the deleted rows of the indexed fragments are removed from the
+/// result, as in [`MaterializeIndexExec`].  The matching rows are then counted straight from the
+/// mask, only enumerating row ids when the size of the mask is unknown.  This is only correct
+/// when the index query is exact (does not need a recheck).
+///
+/// Fragments that are not covered by the index are counted by the optional `residual` input,
+/// typically a filtered scan of those fragments.  The output is a single batch with a single
+/// `count_rows` value.
+#[derive(Debug)]
+pub struct ScalarIndexCountExec {
+    dataset: Arc<Dataset>,
+    expr: ScalarIndexExpr,
+    fragments: Arc<Vec<Fragment>>,
+    residual: Option<Arc<dyn ExecutionPlan>>,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl DisplayAs for ScalarIndexCountExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "ScalarIndexCount: query={}, indexed_fragments={}",
+                    self.expr,
+                    self.fragments.len()
+                )
+            }
+            DisplayFormatType::TreeRender => {
+                write!(
+                    f,
+                    "ScalarIndexCount\nquery={}\nindexed_fragments={}",
+                    self.expr,
+                    self.fragments.len()
+                )
+            }
+        }
+    }
+}
+
+impl ScalarIndexCountExec {
+    pub fn new(
+        dataset: Arc<Dataset>,
+        expr: ScalarIndexExpr,
+        fragments: Arc<Vec<Fragment>>,
+        residual: Option<Arc<dyn ExecutionPlan>>,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(SCALAR_INDEX_COUNT_SCHEMA.clone()),
+            Partitioning::RoundRobinBatch(1),
+            EmissionType::Final,
+            Boundedness::Bounded,
+        );
+        Self {
+            dataset,
+            expr,
+            fragments,
+            residual,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    #[instrument(name = "count_scalar_index", skip_all, level = "debug")]
+    async fn do_execute(
+        expr: ScalarIndexExpr,
+        dataset: Arc<Dataset>,
+        fragments: Arc<Vec<Fragment>>,
+        residual: Option<datafusion::physical_plan::SendableRecordBatchStream>,
+        metrics: Arc<IndexMetrics>,
+    ) -> Result<RecordBatch> {
+        let mut count = if fragments.is_empty() {
+            0
+        } else {
+            let mask = search_with_deletions(&expr, &dataset, &fragments, metrics.as_ref()).await?;
+            count_for_mask(mask, &dataset, &fragments).await?
+        };
+        if let Some(residual) = residual {
+            count += residual
+                .try_fold(
+                    0,
+                    |acc, batch| async move { Ok(acc + batch.num_rows() as u64) },
+                )
+                .await?;
+        }
+        Ok(RecordBatch::try_new(
+            SCALAR_INDEX_COUNT_SCHEMA.clone(),
+            vec![Arc::new(Int64Array::from(vec![count as i64]))],
+        )?)
+    }
+}
+
+#[instrument(name = "count_rows_in_mask", skip_all)]
+async fn count_for_mask(mask: RowIdMask, dataset: &Dataset, fragments: &[Fragment]) -> Result<u64> {
+    match (mask.allow_list, mask.block_list) {
+        (None, None) => Ok(fragments
+            .iter()
+            .map(|frag| frag.physical_rows.unwrap_or_default() as u64)
+            .sum()),
+        (Some(mut allow_list), block_list) => {
+            retain_fragments(&mut allow_list, fragments, dataset).await?;
+            if let Some(block_list) = &block_list {
+                allow_list -= block_list;
+            }
+            if let Some(len) = allow_list.len() {
+                return Ok(len);
+            }
+            // The allow list selects entire fragments, we have to enumerate the row ids
+            let mask = RowIdMask {
+                allow_list: Some(allow_list),
+                block_list: None,
+            };
+            Ok(row_ids_for_mask(mask, dataset, fragments).await?.len() as u64)
+        }
+        (None, block_list) => {
+            let mask = RowIdMask {
+                allow_list: None,
+                block_list,
+            };
+            Ok(row_ids_for_mask(mask, dataset, fragments).await?.len() as u64)
+        }
+    }
+}
+
+impl ExecutionPlan for ScalarIndexCountExec {
+    fn name(&self) -> &str {
+        "ScalarIndexCountExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        SCALAR_INDEX_COUNT_SCHEMA.clone()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        self.residual.iter().collect()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != self.residual.iter().count() {
+            return Err(datafusion::error::DataFusionError::Internal(
+                "ScalarIndexCountExec has at most one child, the residual scan".to_string(),
+            ));
+        }
+        Ok(Arc::new(Self::new(
+            self.dataset.clone(),
+            self.expr.clone(),
+            self.fragments.clone(),
+            children.pop(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<datafusion::execution::context::TaskContext>,
+    ) -> datafusion::error::Result<datafusion::physical_plan::SendableRecordBatchStream> {
+        let residual = self
+            .residual
+            .as_ref()
+            .map(|residual| residual.execute(partition, context))
+            .transpose()?;
+        let metrics = Arc::new(IndexMetrics::new(&self.metrics, partition));
+        let batch_fut = Self::do_execute(
+            self.expr.clone(),
+            self.dataset.clone(),
+            self.fragments.clone(),
+            residual,
+            metrics,
+        );
+        let stream = futures::stream::once(batch_fut.map_err(|err| err.into()));
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            SCALAR_INDEX_COUNT_SCHEMA.clone(),
+            stream,
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn statistics(&self) -> datafusion::error::Result<datafusion::physical_plan::Statistics> {
+        Ok(Statistics::new_unknown(&SCALAR_INDEX_COUNT_SCHEMA))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
+
+/// An execution node that reads row ids from a btree index in the order of the indexed column
+///
+/// The pages of a btree index are stored sorted by value, so reading them in order yields the
+/// row ids sorted by the indexed column.  Pages are read lazily, so an ordered scan with a limit
+/// only needs to load the first few pages instead of reading and sorting the entire column.
+/// Rows that were deleted since the index was trained are filtered out.
+///
+/// Ascending order places nulls first and descending order places nulls last.  The output of
+/// this node is a stream of row ids suitable for use in a take operation.
+#[derive(Debug)]
+pub struct OrderedIndexScanExec {
+    dataset: Arc<Dataset>,
+    column: String,
+    index: IndexMetadata,
+    descending: bool,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl DisplayAs for OrderedIndexScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let order = if self.descending { "desc" } else { "asc" };
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "OrderedIndexScan: index={}, column={}, order={}",
+                    self.index.name, self.column, order
+                )
+            }
+            DisplayFormatType::TreeRender => {
+                write!(
+                    f,
+                    "OrderedIndexScan\nindex={}\ncolumn={}\norder={}",
+                    self.index.name, self.column, order
+                )
+            }
+        }
+    }
+}
+
+impl OrderedIndexScanExec {
+    /// Create a new ordered scan of `index`, which must be a btree index on `column`
+    pub fn new(
+        dataset: Arc<Dataset>,
+        column: String,
+        index: IndexMetadata,
+        descending: bool,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(MATERIALIZE_INDEX_SCHEMA.clone()),
+            Partitioning::RoundRobinBatch(1),
+            EmissionType::Incremental,
+            Boundedness::Bounded,
+        );
+        Self {
+            dataset,
+            column,
+            index,
+            descending,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    #[instrument(name = "ordered_index_scan", skip_all, level = "debug")]
+    async fn open_stream(
+        dataset: Arc<Dataset>,
+        column: String,
+        index: IndexMetadata,
+        descending: bool,
+        metrics: Arc<IndexMetrics>,
+    ) -> Result<BoxStream<'static, Result<RecordBatch>>> {
+        let scalar_index = dataset
+            .open_scalar_index(&column, &index.uuid.to_string(), metrics.as_ref())
+            .await?;
+        let btree = scalar_index
+            .as_any()
+            .downcast_ref::<BTreeIndex>()
+            .ok_or_else(|| Error::Internal {
+                message: format!(
+                    "Scanner created an ordered index scan on index {} but it is not a btree index",
+                    index.name
+                ),
+                location: location!(),
+            })?;
+        let pages = btree.ordered_stream(descending).await?;
+        // The index may still reference rows that were deleted, or fragments that were
+        // removed, after it was trained
+        let fragment_bitmap = index.fragment_bitmap.clone().unwrap_or_default();
+        let deletion_mask = match DatasetPreFilter::create_deletion_mask(dataset, fragment_bitmap) {
+            Some(deletion_mask) => Some(deletion_mask.await?),
+            None => None,
+        };
+        Ok(pages
+            .map_err(Error::from)
+            .and_then(move |page| {
+                let deletion_mask = deletion_mask.clone();
+                async move {
+                    let row_ids = page.column(1).as_primitive::<UInt64Type>();
+                    let row_ids = match deletion_mask {
+                        Some(mask) => UInt64Array::from_iter_values(
+                            row_ids
+                                .values()
+                                .iter()
+                                .copied()
+                                .filter(|id| mask.selected(*id)),
+                        ),
+                        None => row_ids.clone(),
+                    };
+                    Ok(RecordBatch::try_new(
+                        MATERIALIZE_INDEX_SCHEMA.clone(),
+                        vec![Arc::new(row_ids)],
+                    )?)
+                }
+            })
+            .boxed())
+    }
+}
+
+impl ExecutionPlan for OrderedIndexScanExec {
+    fn name(&self) -> &str {
+        "OrderedIndexScanExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        MATERIALIZE_INDEX_SCHEMA.clone()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            Err(datafusion::error::DataFusionError::Internal(
+                "OrderedIndexScanExec does not have children".to_string(),
+            ))
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<datafusion::execution::context::TaskContext>,
+    ) -> datafusion::error::Result<datafusion::physical_plan::SendableRecordBatchStream> {
+        let metrics = Arc::new(IndexMetrics::new(&self.metrics, partition));
+        let stream_fut = Self::open_stream(
+            self.dataset.clone(),
+            self.column.clone(),
+            self.index.clone(),
+            self.descending,
+            metrics,
+        );
+        let stream = futures::stream::once(stream_fut)
+            .try_flatten()
+            .map_err(|err| err.into())
+            .boxed()
+            as BoxStream<'static, datafusion::common::Result<RecordBatch>>;
+        let stream = Box::pin(RecordBatchStreamAdapter::new(
+            MATERIALIZE_INDEX_SCHEMA.clone(),
+            stream,
+        ));
+        let stream = break_stream(stream, context.session_config().batch_size());
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            MATERIALIZE_INDEX_SCHEMA.clone(),
+            stream.map_err(|err| err.into()),
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn statistics(&self) -> datafusion::error::Result<datafusion::physical_plan::Statistics> {
+        Ok(Statistics::new_unknown(&MATERIALIZE_INDEX_SCHEMA))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
+
+/// An execution node that counts the rows for each value of a column using a scalar index
+///
+/// The counts come straight from the index (the bitmaps of a bitmap index or the pages of a
+/// btree index) and the deletion files, no data pages are read.  The index must cover every
+/// fragment of the dataset.  The output is a single batch with the distinct values of the
+/// column and a `count_rows` column.
+#[derive(Debug)]
+pub struct IndexValueCountExec {
+    dataset: Arc<Dataset>,
+    column: String,
+    index: IndexMetadata,
+    schema: SchemaRef,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl DisplayAs for IndexValueCountExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "IndexValueCount: index={}, column={}",
+                    self.index.name, self.column
+                )
+            }
+            DisplayFormatType::TreeRender => {
+                write!(
+                    f,
+                    "IndexValueCount\nindex={}\ncolumn={}",
+                    self.index.name, self.column
+                )
+            }
+        }
+    }
+}
+
+impl IndexValueCountExec {
+    /// Create a new node counting the values of `column` with `index`
+    ///
+    /// `schema` must have two fields, the column and an Int64 count.
+    pub fn new(
+        dataset: Arc<Dataset>,
+        column: String,
+        index: IndexMetadata,
+        schema: SchemaRef,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            Partitioning::RoundRobinBatch(1),
+            EmissionType::Final,
+            Boundedness::Bounded,
+        );
+        Self {
+            dataset,
+            column,
+            index,
+            schema,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    #[instrument(name = "index_value_count", skip_all, level = "debug")]
+    async fn do_execute(
+        dataset: Arc<Dataset>,
+        column: String,
+        index: IndexMetadata,
+        schema: SchemaRef,
+        metrics: Arc<IndexMetrics>,
+    ) -> Result<RecordBatch> {
+        let scalar_index = dataset
+            .open_scalar_index(&column, &index.uuid.to_string(), metrics.as_ref())
+            .await?;
+        let fragment_bitmap = index.fragment_bitmap.clone().unwrap_or_default();
+        let mask = match DatasetPreFilter::create_deletion_mask(dataset, fragment_bitmap) {
+            Some(mask) => (*mask.await?).clone(),
+            None => RowIdMask::all_rows(),
+        };
+        let (values, counts) = scalar_index
+            .value_counts(&mask, metrics.as_ref())
+            .await?
+            .ok_or_else(|| Error::Internal {
+                message: format!(
+                    "Scanner created an index value count on index {} but the index can't count its values",
+                    index.name
+                ),
+                location: location!(),
+            })?;
+        let counts = Int64Array::from_iter_values(counts.into_iter().map(|count| count as i64));
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![values, Arc::new(counts)],
+        )?)
+    }
+}
+
+impl ExecutionPlan for IndexValueCountExec {
+    fn name(&self) -> &str {
+        "IndexValueCountExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            Err(datafusion::error::DataFusionError::Internal(
+                "IndexValueCountExec does not have children".to_string(),
+            ))
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<datafusion::execution::context::TaskContext>,
+    ) -> datafusion::error::Result<datafusion::physical_plan::SendableRecordBatchStream> {
+        let metrics = Arc::new(IndexMetrics::new(&self.metrics, partition));
+        let batch_fut = Self::do_execute(
+            self.dataset.clone(),
+            self.column.clone(),
+            self.index.clone(),
+            self.schema.clone(),
+            metrics,
+        );
+        let stream = futures::stream::once(batch_fut.map_err(|err| err.into()));
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn statistics(&self) -> datafusion::error::Result<datafusion::physical_plan::Statistics> {
+        Ok(Statistics::new_unknown(&self.schema))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{ops::Bound, sync::Arc};
@@ -12,7 +12,9 @@ use arrow_schema::{Schema as ArrowSchema, SchemaRef};
 use datafusion::common::stats::Precision;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::physical_plan::execution_plan::{Boundedness, EmissionType};
-use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricsSet,
+};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties, RecordBatchStream,
@@ -24,10 +26,12 @@ use futures::stream::{BoxStream, Stream};
 use futures::{stream, FutureExt, TryFutureExt};
 use futures::{StreamExt, TryStreamExt};
 use lance_arrow::SchemaExt;
+use lance_core::utils::cancellation::CancellationToken;
 use lance_core::utils::tokio::get_num_compute_intensive_cpus;
 use lance_core::utils::tracing::StreamTracingExt;
 use lance_core::{Error, ROW_ADDR_FIELD, ROW_ID_FIELD};
-use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
+use lance_datafusion::utils::{ExecutionPlanMetricsSetExt, FRAGMENTS_SCANNED_METRIC};
+use lance_io::scheduler::{CoalescingConfig, IoPriorityClass, ScanScheduler, SchedulerConfig};
 use lance_table::format::Fragment;
 use log::debug;
 use snafu::location;
@@ -36,10 +40,11 @@ use tracing::Instrument;
 use crate::dataset::fragment::{FileFragment, FragReadConfig, FragmentReader};
 use crate::dataset::scanner::{
     BATCH_SIZE_FALLBACK, DEFAULT_FRAGMENT_READAHEAD, DEFAULT_IO_BUFFER_SIZE,
-    LEGACY_DEFAULT_FRAGMENT_READAHEAD,
+    DEFAULT_PREFETCH_DEPTH, LEGACY_DEFAULT_FRAGMENT_READAHEAD,
 };
 use crate::dataset::Dataset;
 use crate::datatypes::Schema;
+use crate::utils::query_tracing::fragment_span;
 
 use super::utils::IoMetrics;
 use futures::ready;
@@ -73,6 +78,7 @@ struct FragmentWithRange {
 struct ScanMetrics {
     baseline_metrics: BaselineMetrics,
     io_metrics: IoMetrics,
+    fragments_scanned: Count,
 }
 
 impl ScanMetrics {
@@ -80,10 +86,23 @@ impl ScanMetrics {
         Self {
             baseline_metrics: BaselineMetrics::new(metrics, partition),
             io_metrics: IoMetrics::new(metrics, partition),
+            fragments_scanned: metrics.new_count(FRAGMENTS_SCANNED_METRIC, partition),
         }
     }
 }
 
+/// Count a fragment as scanned once its stream of batch tasks is exhausted
+fn count_when_exhausted<S>(tasks: S, fragments_scanned: Count) -> impl Stream<Item = S::Item> + Send
+where
+    S: Stream + Send,
+    S::Item: Send,
+{
+    tasks.chain(
+        stream::once(async move { fragments_scanned.add(1) })
+            .filter_map(|_| std::future::ready(None)),
+    )
+}
+
 struct StrictBatchSizeStream<S> {
     inner: S,
     batch_size: usize,
@@ -185,9 +204,22 @@ impl Stream for LanceStream {
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
+        // Checked before every batch so an abandoned scan stops reading new pages
+        if let Some(token) = &this.config.cancellation_token {
+            if let Err(e) = token.check() {
+                return Poll::Ready(Some(Err(e.into())));
+            }
+        }
         let timer = this.scan_metrics.baseline_metrics.elapsed_compute().timer();
 
         let poll_result = match this.inner_stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(batch))) => {
+                // Keep the I/O counts current so the progress of the scan can be observed
+                if let Some(scheduler) = &this.scan_scheduler {
+                    this.scan_metrics.io_metrics.record_progress(scheduler);
+                }
+                Poll::Ready(Some(Ok(batch)))
+            }
             Poll::Ready(None) => {
                 if let Some(scheduler) = &this.scan_scheduler {
                     this.scan_metrics.io_metrics.record_final(scheduler);
@@ -354,15 +386,67 @@ impl LanceStream {
             dataset.object_store.clone(),
             SchedulerConfig {
                 io_buffer_size_bytes: config.io_buffer_size,
+                priority_class: config.io_priority,
+                uncached_reads: config.uncached_reads,
+                coalescing: CoalescingConfig::default(),
+                max_iops_per_file: config.fragment_io_parallelism.map(|n| n as u32),
             },
         );
 
         let scan_scheduler_clone = scan_scheduler.clone();
 
+        // Fragments that are not yet in the readahead window, to prefetch metadata for
+        let upcoming = file_fragments
+            .iter()
+            .map(|file_fragment| file_fragment.fragment.clone())
+            .collect::<Vec<_>>();
+        let mut prefetched_up_to = 0;
+        let fragments_scanned = scan_metrics.fragments_scanned.clone();
+
         let batches = stream::iter(file_fragments.into_iter().enumerate())
             .map(move |(priority, file_fragment)| {
                 let project_schema = project_schema.clone();
                 let scan_scheduler = scan_scheduler.clone();
+                let fragments_scanned = fragments_scanned.clone();
+                if config.prefetch_depth > 0 {
+                    // As fragment `priority` enters the window, make sure the fragments
+                    // up to `prefetch_depth` past the end of the window are being prefetched
+                    let start = prefetched_up_to.max(priority + frag_parallelism);
+                    let end =
+                        (priority + frag_parallelism + config.prefetch_depth).min(upcoming.len());
+                    if start < end {
+                        prefetched_up_to = end;
+                        let to_prefetch = upcoming[start..end].to_vec();
+                        let project_schema = project_schema.clone();
+                        let scan_scheduler = scan_scheduler.clone();
+                        tokio::spawn(
+                            async move {
+                                for (offset, fragment) in to_prefetch.into_iter().enumerate() {
+                                    if let Err(err) = fragment
+                                        .prefetch_metadata(
+                                            &project_schema,
+                                            &scan_scheduler,
+                                            (start + offset) as u32,
+                                        )
+                                        .await
+                                    {
+                                        debug!(
+                                            "Failed to prefetch metadata of fragment {}: {}",
+                                            fragment.id(),
+                                            err
+                                        );
+                                    }
+                                }
+                            }
+                            .in_current_span(),
+                        );
+                    }
+                }
+                // Opening the fragment schedules its reads, so they are traced as children
+                let span = fragment_span(
+                    file_fragment.fragment.dataset(),
+                    file_fragment.fragment.id() as u64,
+                );
                 #[allow(clippy::type_complexity)]
                 let frag_task: BoxFuture<
                     Result<BoxStream<Result<BoxFuture<Result<RecordBatch>>>>>,
@@ -384,17 +468,19 @@ impl LanceStream {
                             reader.read_all(config.batch_size as u32)?.boxed()
                         };
                         let batch_stream: BoxStream<Result<BoxFuture<Result<RecordBatch>>>> =
-                            batch_stream
-                                .map(|fut| {
+                            count_when_exhausted(
+                                batch_stream.map(|fut| {
                                     Result::Ok(
                                         fut.map_err(|e| DataFusionError::External(Box::new(e)))
                                             .boxed(),
                                     )
-                                })
-                                .boxed();
+                                }),
+                                fragments_scanned,
+                            )
+                            .boxed();
                         Result::Ok(batch_stream)
                     })
-                    .in_current_span(),
+                    .instrument(span),
                 )
                 .map(|res_res| res_res.unwrap())
                 .boxed();
@@ -467,6 +553,7 @@ impl LanceStream {
         let batches = if config.ordered_output {
             let readers = stream::iter(file_fragments)
                 .map(move |file_fragment| {
+                    let span = fragment_span(file_fragment.dataset(), file_fragment.id() as u64);
                     Ok(open_file(
                         file_fragment,
                         project_schema.clone(),
@@ -475,14 +562,19 @@ impl LanceStream {
                             .with_row_address(config.with_row_address),
                         config.with_make_deletions_null,
                         None,
-                    ))
+                    )
+                    .instrument(span))
                 })
                 .try_buffered(fragment_readahead);
+            let fragments_scanned = scan_metrics.fragments_scanned.clone();
             let tasks = readers.and_then(move |reader| {
                 std::future::ready(
                     reader
                         .read_all(config.batch_size as u32)
-                        .map(|task_stream| task_stream.map(Ok))
+                        .map(|task_stream| {
+                            count_when_exhausted(task_stream.map(Ok), fragments_scanned.clone())
+                                .boxed()
+                        })
                         .map_err(DataFusionError::from),
                 )
             });
@@ -496,6 +588,7 @@ impl LanceStream {
         } else {
             let readers = stream::iter(file_fragments)
                 .map(move |file_fragment| {
+                    let span = fragment_span(file_fragment.dataset(), file_fragment.id() as u64);
                     Ok(open_file(
                         file_fragment,
                         project_schema.clone(),
@@ -504,14 +597,19 @@ impl LanceStream {
                             .with_row_address(config.with_row_address),
                         config.with_make_deletions_null,
                         None,
-                    ))
+                    )
+                    .instrument(span))
                 })
                 .try_buffered(fragment_readahead);
+            let fragments_scanned = scan_metrics.fragments_scanned.clone();
             let tasks = readers.and_then(move |reader| {
                 std::future::ready(
                     reader
                         .read_all(config.batch_size as u32)
-                        .map(|task_stream| task_stream.map(Ok))
+                        .map(|task_stream| {
+                            count_when_exhausted(task_stream.map(Ok), fragments_scanned.clone())
+                                .boxed()
+                        })
                         .map_err(DataFusionError::from),
                 )
             });
@@ -576,12 +674,22 @@ pub struct LanceScanConfig {
     pub batch_size: usize,
     pub batch_readahead: usize,
     pub fragment_readahead: Option<usize>,
+    /// The most IOPS each data file may have in flight (v2 only, unlimited if None)
+    pub fragment_io_parallelism: Option<usize>,
     pub io_buffer_size: u64,
+    pub io_priority: IoPriorityClass,
+    /// Number of fragments past the readahead window whose metadata is
+    /// prefetched (v2 only)
+    pub prefetch_depth: usize,
+    /// Keep the data read from local files out of the OS page cache (v2 only)
+    pub uncached_reads: bool,
     pub with_row_id: bool,
     pub with_row_address: bool,
     pub with_make_deletions_null: bool,
     pub ordered_output: bool,
     pub strict_batch_size: bool,
+    /// If set, the scan fails once this token is cancelled or its deadline passes
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 // This is mostly for testing purposes, end users are unlikely to create this
@@ -592,12 +700,17 @@ impl Default for LanceScanConfig {
             batch_size: BATCH_SIZE_FALLBACK,
             batch_readahead: get_num_compute_intensive_cpus(),
             fragment_readahead: None,
+            fragment_io_parallelism: None,
             io_buffer_size: *DEFAULT_IO_BUFFER_SIZE,
+            io_priority: IoPriorityClass::default(),
+            prefetch_depth: *DEFAULT_PREFETCH_DEPTH,
+            uncached_reads: false,
             with_row_id: false,
             with_row_address: false,
             with_make_deletions_null: false,
             ordered_output: false,
             strict_batch_size: false,
+            cancellation_token: None,
         }
     }
 }
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::{RecordBatch, UInt32Array};
+use arrow_schema::{Schema as ArrowSchema, SchemaRef};
+use arrow_select::take::take_record_batch;
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::{SendableRecordBatchStream, TaskContext},
+    physical_plan::{
+        execution_plan::{Boundedness, EmissionType},
+        metrics::{ExecutionPlanMetricsSet, MetricsSet},
+        DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
+    },
+};
+use datafusion_physical_expr::EquivalenceProperties;
+use futures::{StreamExt, TryStreamExt};
+
+use super::utils::InstrumentedRecordBatchStreamAdapter;
+use crate::dataset::sorted_projection::SortedProjection;
+
+/// Reads a range of rows from a sorted projection
+///
+/// The rows are read in batches and, if `reverse` is set, from the end of the range backwards
+/// (reversing each batch) to produce the rows in the opposite of the projection's order.  A
+/// page of a paginated query only reads the rows on that page.
+#[derive(Debug)]
+pub struct SortedProjectionScanExec {
+    projection: Arc<SortedProjection>,
+    columns: Vec<String>,
+    range: Range<u64>,
+    reverse: bool,
+    batch_size: usize,
+    schema: SchemaRef,
+    properties: PlanProperties,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl SortedProjectionScanExec {
+    /// Create a new node that reads `columns` of the rows in `range`
+    pub fn try_new(
+        projection: Arc<SortedProjection>,
+        columns: Vec<String>,
+        range: Range<u64>,
+        reverse: bool,
+        batch_size: usize,
+    ) -> crate::Result<Self> {
+        let schema: ArrowSchema = (&projection.reader().schema().project(&columns)?).into();
+        let schema = Arc::new(schema);
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            Partitioning::RoundRobinBatch(1),
+            EmissionType::Incremental,
+            Boundedness::Bounded,
+        );
+        Ok(Self {
+            projection,
+            columns,
+            range,
+            reverse,
+            batch_size: batch_size.max(1),
+            schema,
+            properties,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    fn batch_ranges(&self) -> Vec<Range<usize>> {
+        let start = self.range.start as usize;
+        let end = self.range.end as usize;
+        if self.reverse {
+            // Read from the end so the first batch has the first rows of the output
+            (start..end)
+                .rev()
+                .step_by(self.batch_size)
+                .map(|last| last.saturating_sub(self.batch_size - 1).max(start)..last + 1)
+                .collect()
+        } else {
+            (start..end)
+                .step_by(self.batch_size)
+                .map(|start| start..(start + self.batch_size).min(end))
+                .collect()
+        }
+    }
+
+    fn reverse_batch(batch: RecordBatch) -> crate::Result<RecordBatch> {
+        let indices = UInt32Array::from_iter_values((0..batch.num_rows() as u32).rev());
+        Ok(take_record_batch(&batch, &indices)?)
+    }
+}
+
+impl DisplayAs for SortedProjectionScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let order = if self.reverse { "reverse" } else { "forward" };
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "SortedProjectionScan: name={}, range={:?}, order={}",
+                    self.projection.name(),
+                    self.range,
+                    order
+                )
+            }
+            DisplayFormatType::TreeRender => {
+                write!(
+                    f,
+                    "SortedProjectionScan\nname={}\nrange={:?}\norder={}",
+                    self.projection.name(),
+                    self.range,
+                    order
+                )
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for SortedProjectionScanExec {
+    fn name(&self) -> &str {
+        "SortedProjectionScanExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            Err(DataFusionError::Internal(
+                "SortedProjectionScanExec does not have children".to_string(),
+            ))
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let reader = self.projection.reader().clone();
+        let columns = Arc::new(self.columns.clone());
+        let reverse = self.reverse;
+        let stream = futures::stream::iter(self.batch_ranges())
+            .then(move |range| {
+                let reader = reader.clone();
+                let columns = columns.clone();
+                async move {
+                    let columns = columns.iter().map(String::as_str).collect::<Vec<_>>();
+                    let batch = reader.read_range(range, Some(&columns)).await?;
+                    if reverse {
+                        Self::reverse_batch(batch)
+                    } else {
+                        Ok(batch)
+                    }
+                }
+            })
+            .map_err(DataFusionError::from)
+            .boxed();
+        Ok(Box::pin(InstrumentedRecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+            partition,
+            &self.metrics,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+}
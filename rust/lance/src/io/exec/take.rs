@@ -30,6 +30,7 @@ use lance_core::utils::address::RowAddress;
 use lance_core::utils::futures::FinallyStreamExt;
 use lance_core::utils::tokio::get_num_compute_intensive_cpus;
 use lance_core::{ROW_ADDR, ROW_ID};
+use lance_datafusion::projection::ProjectionPlan;
 use lance_io::scheduler::{ScanScheduler, SchedulerConfig};
 
 use crate::dataset::fragment::{FragReadConfig, FragmentReader};
@@ -385,7 +386,7 @@ impl TakeExec {
         Ok(Some(Self {
             dataset,
             output_projection: original_projection,
-            schema_to_take: projection.into_schema_ref(),
+            schema_to_take: ProjectionPlan::unload_blobs(&projection.into_schema_ref()),
             input,
             output_schema: output_arrow,
             properties,
@@ -410,7 +411,8 @@ impl TakeExec {
         // TakeExec doesn't reorder top-level fields and so the first thing we need to do is determine the
         // top-level field order.
         let mut top_level_fields_added = HashSet::with_capacity(input_schema.fields.len());
-        let projected_schema = projection.to_schema();
+        // Like a scan, a take returns the descriptions of blobs instead of their bytes
+        let projected_schema = ProjectionPlan::unload_blobs(&Arc::new(projection.to_schema()));
 
         let mut output_fields =
             Vec::with_capacity(input_schema.fields.len() + projected_schema.fields.len());
@@ -437,8 +439,9 @@ impl TakeExec {
         output_fields.extend(
             projected_schema
                 .fields
-                .into_iter()
-                .filter(|f| !top_level_fields_added.contains(&f.id)),
+                .iter()
+                .filter(|f| !top_level_fields_added.contains(&f.id))
+                .cloned(),
         );
         Schema {
             fields: output_fields,
@@ -1,6 +1,6 @@
 use lance_datafusion::utils::{
     ExecutionPlanMetricsSetExt, BYTES_READ_METRIC, INDEX_COMPARISONS_METRIC, INDICES_LOADED_METRIC,
-    IOPS_METRIC, PARTS_LOADED_METRIC, REQUESTS_METRIC,
+    IOPS_METRIC, PAGES_DECODED_METRIC, PARTS_LOADED_METRIC, PREFETCH_HITS_METRIC, REQUESTS_METRIC,
 };
 use lance_index::metrics::MetricsCollector;
 use lance_io::scheduler::ScanScheduler;
@@ -51,6 +51,20 @@ pub(crate) fn build_prefilter(
     ds: Arc<Dataset>,
     index_meta: &[Index],
 ) -> Result<Arc<DatasetPreFilter>> {
+    let prefilter_loader = prefilter_loader(context, partition, prefilter_source)?;
+    Ok(Arc::new(DatasetPreFilter::new(
+        ds,
+        index_meta,
+        prefilter_loader,
+    )))
+}
+
+/// Start executing the prefilter source, if any, and return a loader for its row id mask
+pub(crate) fn prefilter_loader(
+    context: Arc<datafusion::execution::TaskContext>,
+    partition: usize,
+    prefilter_source: &PreFilterSource,
+) -> Result<Option<Box<dyn FilterLoader>>> {
     let prefilter_loader = match &prefilter_source {
         PreFilterSource::FilteredRowIds(src_node) => {
             let stream = src_node.execute(partition, context)?;
@@ -62,11 +76,7 @@ pub(crate) fn build_prefilter(
         }
         PreFilterSource::None => None,
     };
-    Ok(Arc::new(DatasetPreFilter::new(
-        ds,
-        index_meta,
-        prefilter_loader,
-    )))
+    Ok(prefilter_loader)
 }
 
 // Utility to convert an input (containing row ids) into a prefilter
@@ -373,6 +383,8 @@ pub struct IoMetrics {
     iops: Count,
     requests: Count,
     bytes_read: Count,
+    prefetch_hits: Count,
+    pages_decoded: Count,
 }
 
 impl IoMetrics {
@@ -380,18 +392,37 @@ impl IoMetrics {
         let iops = metrics.new_count(IOPS_METRIC, partition);
         let requests = metrics.new_count(REQUESTS_METRIC, partition);
         let bytes_read = metrics.new_count(BYTES_READ_METRIC, partition);
+        let prefetch_hits = metrics.new_count(PREFETCH_HITS_METRIC, partition);
+        let pages_decoded = metrics.new_count(PAGES_DECODED_METRIC, partition);
         Self {
             iops,
             requests,
             bytes_read,
+            prefetch_hits,
+            pages_decoded,
         }
     }
 
-    pub fn record_final(&self, scan_scheduler: &ScanScheduler) {
+    /// Bring the counts up to date with the I/O performed by the scheduler so far
+    ///
+    /// Only the I/O since the previous call is added, so this can be called
+    /// repeatedly while the stream is running.
+    pub fn record_progress(&self, scan_scheduler: &ScanScheduler) {
         let stats = scan_scheduler.stats();
-        self.iops.add(stats.iops as usize);
-        self.requests.add(stats.requests as usize);
-        self.bytes_read.add(stats.bytes_read as usize);
+        self.iops
+            .add((stats.iops as usize).saturating_sub(self.iops.value()));
+        self.requests
+            .add((stats.requests as usize).saturating_sub(self.requests.value()));
+        self.bytes_read
+            .add((stats.bytes_read as usize).saturating_sub(self.bytes_read.value()));
+        self.prefetch_hits
+            .add((stats.prefetch_hits as usize).saturating_sub(self.prefetch_hits.value()));
+        self.pages_decoded
+            .add((stats.pages_decoded as usize).saturating_sub(self.pages_decoded.value()));
+    }
+
+    pub fn record_final(&self, scan_scheduler: &ScanScheduler) {
+        self.record_progress(scan_scheduler);
     }
 }
 
@@ -74,11 +74,21 @@ use dataset::builder::DatasetBuilder;
 pub use lance_core::{datatypes, error};
 pub use lance_core::{Error, Result};
 
+#[cfg(feature = "adbc")]
+pub mod adbc;
 pub mod arrow;
 pub mod datafusion;
 pub mod dataset;
+#[cfg(feature = "duckdb")]
+pub mod duckdb;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "flight")]
+pub mod flight;
 pub mod index;
 pub mod io;
+#[cfg(feature = "polars")]
+pub mod polars;
 pub mod session;
 pub mod table;
 pub mod utils;
@@ -0,0 +1,298 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Interop with [Polars](https://pola.rs) data frames.
+//!
+//! Polars uses its own Arrow implementation, so columns are exchanged through
+//! the Arrow C data interface. Buffers are shared rather than copied, except
+//! where Polars needs to rechunk or convert a type (for example string views
+//! into large strings when converting back to Arrow).
+//!
+//! ```ignore
+//! let mut scan = dataset.scan();
+//! scan.filter(&lance::polars::expr_to_filter(&col("x").gt(lit(10)))?)?;
+//! let df = lance::polars::scan_to_df(&scan).await?;
+//! ```
+
+use std::sync::Arc;
+
+use ::polars::prelude::{
+    AnyValue, BooleanFunction, CompatLevel, DataFrame, Expr, FunctionExpr, Operator, PolarsError,
+    Series,
+};
+use arrow::ffi::{from_ffi, to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::{make_array, RecordBatch, RecordBatchIterator, RecordBatchReader};
+use arrow_schema::{Field, Schema as ArrowSchema};
+use futures::TryStreamExt;
+use polars_arrow::ffi::{ArrowArray as PolarsFfiArray, ArrowSchema as PolarsFfiSchema};
+use snafu::location;
+
+use crate::dataset::scanner::Scanner;
+use crate::utils::filter::{quote_identifier, quote_string};
+use crate::{Error, Result};
+
+fn polars_error(err: PolarsError) -> Error {
+    Error::Arrow {
+        message: format!("Polars error: {}", err),
+        location: location!(),
+    }
+}
+
+/// Convert a record batch into a data frame.
+pub fn record_batch_to_df(batch: &RecordBatch) -> Result<DataFrame> {
+    let columns = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| {
+            let (array, schema) = to_ffi(&array.to_data())?;
+            // SAFETY: both types are `#[repr(C)]` definitions of the same C
+            // structs, and ownership of the buffers moves to Polars.
+            let (array, schema) = unsafe {
+                (
+                    std::mem::transmute::<FFI_ArrowArray, PolarsFfiArray>(array),
+                    std::mem::transmute::<FFI_ArrowSchema, PolarsFfiSchema>(schema),
+                )
+            };
+            let imported = unsafe {
+                let field =
+                    polars_arrow::ffi::import_field_from_c(&schema).map_err(polars_error)?;
+                polars_arrow::ffi::import_array_from_c(array, field.dtype).map_err(polars_error)?
+            };
+            Ok(Series::from_arrow(field.name().into(), imported)
+                .map_err(polars_error)?
+                .into())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    DataFrame::new(columns).map_err(polars_error)
+}
+
+/// Convert a data frame into record batches, one per chunk.
+pub fn df_to_record_batches(df: &DataFrame) -> Result<Vec<RecordBatch>> {
+    let names = df.get_column_names();
+    df.iter_chunks(CompatLevel::oldest(), false)
+        .map(|chunk| {
+            let (fields, columns): (Vec<_>, Vec<_>) = names
+                .iter()
+                .zip(chunk.into_arrays())
+                .map(|(name, array)| {
+                    let field = polars_arrow::datatypes::Field::new(
+                        (*name).clone(),
+                        array.dtype().clone(),
+                        true,
+                    );
+                    let schema = polars_arrow::ffi::export_field_to_c(&field);
+                    let array = polars_arrow::ffi::export_array_to_c(array);
+                    // SAFETY: see `record_batch_to_df`.
+                    let data = unsafe {
+                        from_ffi(
+                            std::mem::transmute::<PolarsFfiArray, FFI_ArrowArray>(array),
+                            &std::mem::transmute::<PolarsFfiSchema, FFI_ArrowSchema>(schema),
+                        )?
+                    };
+                    let array = make_array(data);
+                    let field = Field::new(name.as_str(), array.data_type().clone(), true);
+                    Ok((field, array))
+                })
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .unzip();
+            Ok(RecordBatch::try_new(
+                Arc::new(ArrowSchema::new(fields)),
+                columns,
+            )?)
+        })
+        .collect()
+}
+
+/// Convert a data frame into a reader that can be passed to
+/// [`crate::Dataset::write`].
+pub fn df_to_reader(df: &DataFrame) -> Result<impl RecordBatchReader + Send + 'static> {
+    let batches = df_to_record_batches(df)?;
+    let schema = match batches.first() {
+        Some(batch) => batch.schema(),
+        None => Arc::new(ArrowSchema::empty()),
+    };
+    Ok(RecordBatchIterator::new(
+        batches.into_iter().map(Ok),
+        schema,
+    ))
+}
+
+/// Run a scan and collect the results into a single data frame.
+///
+/// Each batch becomes a chunk of the data frame; call
+/// [`DataFrame::rechunk_mut`] if contiguous columns are needed.
+pub async fn scan_to_df(scanner: &Scanner) -> Result<DataFrame> {
+    let schema = scanner.schema().await?;
+    let mut stream = scanner.try_into_stream().await?;
+    let mut df = record_batch_to_df(&RecordBatch::new_empty(schema))?;
+    while let Some(batch) = stream.try_next().await? {
+        df.vstack_mut(&record_batch_to_df(&batch)?)
+            .map_err(polars_error)?;
+    }
+    Ok(df)
+}
+
+/// Translate a Polars predicate into a Lance SQL filter.
+///
+/// Column references, literals, comparisons, arithmetic, boolean logic and
+/// null checks are supported. Other expressions return
+/// [`Error::NotSupported`]; such predicates should be applied in Polars after
+/// the scan instead.
+pub fn expr_to_filter(expr: &Expr) -> Result<String> {
+    let not_supported = || Error::NotSupported {
+        source: format!("Polars expression {:?} cannot be pushed down", expr).into(),
+        location: location!(),
+    };
+    Ok(match expr {
+        Expr::Alias(inner, _) => expr_to_filter(inner)?,
+        Expr::Column(name) => quote_identifier(name),
+        Expr::Literal(value) => {
+            literal(&value.to_any_value().ok_or_else(not_supported)?).ok_or_else(not_supported)?
+        }
+        // Polars always divides as floats, SQL divides integers as integers
+        Expr::BinaryExpr {
+            left,
+            op: Operator::TrueDivide,
+            right,
+        } => format!(
+            "(CAST({} AS DOUBLE) / {})",
+            expr_to_filter(left)?,
+            expr_to_filter(right)?
+        ),
+        Expr::BinaryExpr { left, op, right } => {
+            let op = match op {
+                Operator::Eq => "=",
+                Operator::NotEq => "!=",
+                Operator::Lt => "<",
+                Operator::LtEq => "<=",
+                Operator::Gt => ">",
+                Operator::GtEq => ">=",
+                Operator::Plus => "+",
+                Operator::Minus => "-",
+                Operator::Multiply => "*",
+                Operator::And | Operator::LogicalAnd => "AND",
+                Operator::Or | Operator::LogicalOr => "OR",
+                _ => return Err(not_supported()),
+            };
+            format!(
+                "({} {} {})",
+                expr_to_filter(left)?,
+                op,
+                expr_to_filter(right)?
+            )
+        }
+        Expr::Function {
+            input,
+            function: FunctionExpr::Boolean(function),
+            ..
+        } if input.len() == 1 => {
+            let input = expr_to_filter(&input[0])?;
+            match function {
+                BooleanFunction::IsNull => format!("({} IS NULL)", input),
+                BooleanFunction::IsNotNull => format!("({} IS NOT NULL)", input),
+                BooleanFunction::Not => format!("(NOT {})", input),
+                _ => return Err(not_supported()),
+            }
+        }
+        _ => return Err(not_supported()),
+    })
+}
+
+fn literal(value: &AnyValue) -> Option<String> {
+    Some(match value {
+        AnyValue::Null => "NULL".to_string(),
+        AnyValue::Boolean(v) => v.to_string(),
+        AnyValue::String(v) => quote_string(v),
+        AnyValue::StringOwned(v) => quote_string(v),
+        AnyValue::Int8(v) => v.to_string(),
+        AnyValue::Int16(v) => v.to_string(),
+        AnyValue::Int32(v) => v.to_string(),
+        AnyValue::Int64(v) => v.to_string(),
+        AnyValue::UInt8(v) => v.to_string(),
+        AnyValue::UInt16(v) => v.to_string(),
+        AnyValue::UInt32(v) => v.to_string(),
+        AnyValue::UInt64(v) => v.to_string(),
+        AnyValue::Float32(v) if v.is_finite() => v.to_string(),
+        AnyValue::Float64(v) if v.is_finite() => v.to_string(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ::polars::prelude::{binary_expr, col, lit};
+    use arrow_array::{Int32Array, StringArray};
+    use arrow_schema::DataType;
+
+    use crate::Dataset;
+
+    #[test]
+    fn test_expr_to_filter() {
+        let expr = col("x").gt(lit(10)).and(col("name").eq(lit("o'brien")));
+        assert_eq!(
+            expr_to_filter(&expr).unwrap(),
+            "((x > 10) AND (name = 'o''brien'))"
+        );
+        let expr = col("my col").is_null().or(col("y").not());
+        assert_eq!(
+            expr_to_filter(&expr).unwrap(),
+            "((`my col` IS NULL) OR (NOT y))"
+        );
+        let expr = binary_expr(col("x"), Operator::TrueDivide, lit(2));
+        assert_eq!(expr_to_filter(&expr).unwrap(), "(CAST(x AS DOUBLE) / 2)");
+        assert!(matches!(
+            expr_to_filter(&col("x").sum()),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_polars_round_trip() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..100)),
+                Arc::new(StringArray::from_iter(
+                    (0..100).map(|i| (i % 10 != 0).then(|| format!("n{}", i))),
+                )),
+            ],
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let uri = dir.path().to_str().unwrap();
+        let dataset = Dataset::write(RecordBatchIterator::new(vec![Ok(batch)], schema), uri, None)
+            .await
+            .unwrap();
+
+        // Polars keeps id = 89, which integer division would drop
+        let half = binary_expr(col("id"), Operator::TrueDivide, lit(2));
+        let mut scan = dataset.scan();
+        scan.filter(&expr_to_filter(&half.gt(lit(44))).unwrap())
+            .unwrap();
+        assert_eq!(scan.count_rows().await.unwrap(), 11);
+
+        let mut scan = dataset.scan();
+        scan.filter(&expr_to_filter(&col("id").gt_eq(lit(90))).unwrap())
+            .unwrap();
+        let df = scan_to_df(&scan).await.unwrap();
+        assert_eq!(df.shape(), (10, 2));
+        assert_eq!(df.column("name").unwrap().null_count(), 1);
+
+        let copy_uri = dir.path().join("copy").to_str().unwrap().to_string();
+        let copy = Dataset::write(df_to_reader(&df).unwrap(), &copy_uri, None)
+            .await
+            .unwrap();
+        assert_eq!(copy.count_rows(None).await.unwrap(), 10);
+        let batch = copy.scan().try_into_batch().await.unwrap();
+        assert_eq!(batch["id"].data_type(), &DataType::Int32);
+    }
+}
@@ -2,21 +2,27 @@
 // SPDX-FileCopyrightText: Copyright The Lance Authors
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use deepsize::DeepSizeOf;
-use lance_core::cache::LanceCache;
+use lance_core::cache::{CacheStats, LanceCache};
 use lance_core::{Error, Result};
 use lance_index::IndexType;
 use lance_io::object_store::ObjectStoreRegistry;
 use snafu::location;
 
-use crate::dataset::{DEFAULT_INDEX_CACHE_SIZE, DEFAULT_METADATA_CACHE_SIZE};
 use crate::index::cache::IndexCache;
 
+use self::config::SessionConfig;
 use self::index_extension::IndexExtension;
+use self::slow_query_log::SlowQueryLog;
 
+pub mod config;
 pub mod index_extension;
+pub mod slow_query_log;
+
+/// Cache category of file and manifest metadata.
+const METADATA_CATEGORY: &str = "metadata";
 
 /// A user session tracks the runtime state.
 #[derive(Clone)]
@@ -33,7 +39,7 @@ pub struct Session {
 
     pub(crate) index_extensions: HashMap<(IndexType, String), Arc<dyn IndexExtension>>,
 
-    store_registry: Arc<ObjectStoreRegistry>,
+    config: SessionConfig,
 }
 
 impl DeepSizeOf for Session {
@@ -67,6 +73,7 @@ impl std::fmt::Debug for Session {
                 "index_extensions",
                 &self.index_extensions.keys().collect::<Vec<_>>(),
             )
+            .field("config", &self.config)
             .finish()
     }
 }
@@ -86,14 +93,86 @@ impl Session {
         metadata_cache_size: usize,
         store_registry: Arc<ObjectStoreRegistry>,
     ) -> Self {
+        Self::from_config(SessionConfig {
+            index_cache_size,
+            metadata_cache_size_bytes: metadata_cache_size,
+            store_registry,
+            ..Default::default()
+        })
+    }
+
+    /// Create a session from its configuration.
+    ///
+    /// The datasets opened or written with the session share its caches, and the
+    /// object store settings of the configuration apply to them unless they are
+    /// given for the open or write itself.
+    pub fn from_config(config: SessionConfig) -> Self {
+        let (index_cache, metadata_cache) = match config.cache_budget_bytes {
+            Some(capacity_bytes) => {
+                let cache = LanceCache::with_capacity(capacity_bytes);
+                (
+                    IndexCache::with_cache(&cache),
+                    cache.with_category(METADATA_CATEGORY),
+                )
+            }
+            None => (
+                IndexCache::new(config.index_cache_size),
+                LanceCache::with_capacity(config.metadata_cache_size_bytes)
+                    .with_category(METADATA_CATEGORY),
+            ),
+        };
         Self {
-            index_cache: IndexCache::new(index_cache_size),
-            metadata_cache: LanceCache::with_capacity(metadata_cache_size),
+            index_cache,
+            metadata_cache,
             index_extensions: HashMap::new(),
-            store_registry,
+            config,
         }
     }
 
+    /// Create a session whose metadata and index caches share a single budget.
+    ///
+    /// Unlike [`Self::new`], where the index cache is bounded by a number of
+    /// entries, every entry here counts towards `capacity_bytes`. This makes
+    /// it possible to cap the memory a process spends on caching. The budget
+    /// can be changed later with [`Self::resize_cache`].
+    pub fn with_cache_budget(
+        capacity_bytes: usize,
+        store_registry: Arc<ObjectStoreRegistry>,
+    ) -> Self {
+        Self::from_config(SessionConfig {
+            cache_budget_bytes: Some(capacity_bytes),
+            store_registry,
+            ..Default::default()
+        })
+    }
+
+    /// A session shared by the whole process, with the default cache sizes.
+    ///
+    /// Opening a dataset with this session, e.g. once per request in a
+    /// service, reuses the manifests and index metadata read by earlier opens
+    /// of the same dataset instead of fetching them again. Only the check for
+    /// the latest version goes to storage. The session is also used by default
+    /// when the `LANCE_SHARED_SESSION` environment variable is set.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<Session>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Self::default())).clone()
+    }
+
+    /// Report the queries of this session that are slower than the log's threshold
+    pub fn with_slow_query_log(mut self, slow_query_log: SlowQueryLog) -> Self {
+        self.config.slow_query_log = Some(slow_query_log);
+        self
+    }
+
+    pub fn slow_query_log(&self) -> Option<&SlowQueryLog> {
+        self.config.slow_query_log.as_ref()
+    }
+
+    /// The configuration the session was created with.
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
     /// Register a new index extension.
     ///
     /// A name can only be registered once per type of index extension.
@@ -158,22 +237,54 @@ impl Session {
 
     /// Get the object store registry.
     pub fn store_registry(&self) -> Arc<ObjectStoreRegistry> {
-        self.store_registry.clone()
+        self.config.store_registry.clone()
     }
 
     pub fn metadata_cache_stats(&self) -> lance_core::cache::CacheStats {
         self.metadata_cache.stats()
     }
+
+    /// Statistics for the metadata and index caches, with the memory used,
+    /// hits and misses of each category of entries.
+    pub fn cache_stats(&self) -> CacheStats {
+        let mut stats = self.index_cache.stats();
+        let metadata = self
+            .metadata_cache
+            .stats()
+            .categories
+            .remove(METADATA_CATEGORY)
+            .unwrap_or_default();
+        stats.hits += metadata.hits;
+        stats.misses += metadata.misses;
+        stats.num_entries += metadata.num_entries;
+        stats.size_bytes += metadata.size_bytes;
+        stats
+            .categories
+            .insert(METADATA_CATEGORY.to_string(), metadata);
+        stats
+    }
+
+    /// Change the budget shared by the metadata and index caches, in bytes.
+    ///
+    /// Only sessions created with [`Self::with_cache_budget`] have a single
+    /// budget. Otherwise the index cache is bounded by a number of entries
+    /// rather than bytes, so this returns an error instead of resizing only
+    /// some of the caches.
+    pub fn resize_cache(&self, capacity_bytes: usize) -> Result<()> {
+        if self.config.cache_budget_bytes.is_none() {
+            return Err(Error::NotSupported {
+                source: "resize_cache requires a session created with a cache budget".into(),
+                location: location!(),
+            });
+        }
+        self.metadata_cache.resize(capacity_bytes);
+        Ok(())
+    }
 }
 
 impl Default for Session {
     fn default() -> Self {
-        Self {
-            index_cache: IndexCache::new(DEFAULT_INDEX_CACHE_SIZE),
-            metadata_cache: LanceCache::with_capacity(DEFAULT_METADATA_CACHE_SIZE),
-            index_extensions: HashMap::new(),
-            store_registry: Arc::new(ObjectStoreRegistry::default()),
-        }
+        Self::from_config(SessionConfig::default())
     }
 }
 
@@ -187,6 +298,7 @@ mod tests {
 
     use crate::index::vector::pq::PQIndex;
     use lance_index::vector::pq::ProductQuantizer;
+    use lance_io::object_store::{ObjectStore, ObjectStoreParams};
     use lance_linalg::distance::DistanceType;
 
     #[test]
@@ -257,4 +369,63 @@ mod tests {
         // Capacity is 10 so there should be at most 10 items
         assert_eq!(session.index_cache.len_vector(), 10);
     }
+
+    #[test]
+    fn test_cache_budget() {
+        let session = Session::with_cache_budget(1024 * 1024, Default::default());
+        let pq = ProductQuantizer::new(
+            1,
+            8,
+            1,
+            FixedSizeListArray::try_new_from_values(Float32Array::from(vec![0.0f32; 8]), 1)
+                .unwrap(),
+            DistanceType::L2,
+        );
+        let idx = Arc::new(PQIndex::new(pq, DistanceType::L2, None));
+        session.index_cache.insert_vector("abc", idx);
+        session
+            .metadata_cache
+            .insert("manifest", Arc::new(vec![0u8; 1000]));
+        assert!(session.index_cache.get_vector("abc").is_some());
+
+        let stats = session.cache_stats();
+        assert_eq!(stats.num_entries, 2);
+        assert_eq!(stats.hits, 1);
+        assert!(stats.categories["metadata"].size_bytes >= 1000);
+        assert_eq!(stats.categories["index/vector"].num_entries, 1);
+
+        // Both caches draw from the same budget.
+        session.resize_cache(0).unwrap();
+        assert_eq!(session.cache_stats().num_entries, 0);
+
+        // Without a budget the caches can't be resized together
+        let session = Session::default();
+        assert!(matches!(
+            session.resize_cache(1024),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_config() {
+        let session = Session::from_config(SessionConfig {
+            io_parallelism: Some(3),
+            background_io_parallelism: Some(1),
+            ..Default::default()
+        });
+
+        // The settings of the session apply unless given for the open itself
+        let params = session.config().store_params(&ObjectStoreParams {
+            background_io_parallelism: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(params.io_parallelism, Some(3));
+        assert_eq!(params.background_io_parallelism, Some(2));
+
+        let (store, _) =
+            ObjectStore::from_uri_and_params(session.store_registry(), "memory:///", &params)
+                .await
+                .unwrap();
+        assert_eq!(store.io_parallelism(), 3);
+    }
 }
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Configuration of a session
+//!
+//! A [`SessionConfig`] gathers the settings shared by all the datasets opened or written
+//! with a [`super::Session`]: the size of its caches, how concurrently and how
+//! persistently object stores are accessed, and the hooks that observe them.  Settings
+//! given to a single open or write, e.g. in its [`ObjectStoreParams`], take precedence.
+
+use std::sync::Arc;
+
+use lance_io::object_store::metrics::ObjectStoreMetrics;
+use lance_io::object_store::retry::RetryPolicy;
+use lance_io::object_store::{ObjectStoreParams, ObjectStoreRegistry};
+
+use super::slow_query_log::SlowQueryLog;
+use crate::dataset::{DEFAULT_INDEX_CACHE_SIZE, DEFAULT_METADATA_CACHE_SIZE};
+
+/// Configuration of a [`super::Session`], see [`super::Session::from_config`]
+#[derive(Clone)]
+pub struct SessionConfig {
+    /// Number of opened indices to cache. Zero disables the index cache.
+    pub index_cache_size: usize,
+    /// Size in bytes of the cache of file and manifest metadata. Zero disables the cache.
+    pub metadata_cache_size_bytes: usize,
+    /// A single budget in bytes for the metadata and index caches, in place of
+    /// `index_cache_size` and `metadata_cache_size_bytes`, see
+    /// [`super::Session::with_cache_budget`].
+    pub cache_budget_bytes: Option<usize>,
+    /// Maximum number of concurrent IOPS of a single scheduler, see
+    /// [`ObjectStoreParams::io_parallelism`].
+    pub io_parallelism: Option<usize>,
+    /// Maximum number of concurrent IOPS of a single background job, see
+    /// [`ObjectStoreParams::background_io_parallelism`].
+    pub background_io_parallelism: Option<usize>,
+    /// Retry policy of the object stores, see [`ObjectStoreParams::retry_policy`].
+    pub retry_policy: Option<Arc<dyn RetryPolicy>>,
+    /// Receives the metrics of the object stores, see [`ObjectStoreParams::metrics`].
+    pub metrics: Option<Arc<dyn ObjectStoreMetrics>>,
+    /// Reports the queries that are slower than its threshold.
+    pub slow_query_log: Option<SlowQueryLog>,
+    /// The object store registry used when opening datasets. This determines which
+    /// schemes are available, and also allows re-using object stores.
+    pub store_registry: Arc<ObjectStoreRegistry>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            index_cache_size: DEFAULT_INDEX_CACHE_SIZE,
+            metadata_cache_size_bytes: DEFAULT_METADATA_CACHE_SIZE,
+            cache_budget_bytes: None,
+            io_parallelism: None,
+            background_io_parallelism: None,
+            retry_policy: None,
+            metrics: None,
+            slow_query_log: None,
+            store_registry: Arc::new(ObjectStoreRegistry::default()),
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("index_cache_size", &self.index_cache_size)
+            .field("metadata_cache_size_bytes", &self.metadata_cache_size_bytes)
+            .field("cache_budget_bytes", &self.cache_budget_bytes)
+            .field("io_parallelism", &self.io_parallelism)
+            .field("background_io_parallelism", &self.background_io_parallelism)
+            .field("retry_policy", &self.retry_policy.is_some())
+            .field("metrics", &self.metrics.is_some())
+            .field("slow_query_log", &self.slow_query_log)
+            .finish()
+    }
+}
+
+impl SessionConfig {
+    /// The object store parameters of an open or a write, with the settings of the
+    /// session filled in where `params` leaves them unset.
+    pub(crate) fn store_params(&self, params: &ObjectStoreParams) -> ObjectStoreParams {
+        let mut params = params.clone();
+        params.io_parallelism = params.io_parallelism.or(self.io_parallelism);
+        params.background_io_parallelism = params
+            .background_io_parallelism
+            .or(self.background_io_parallelism);
+        // A retry policy may also be given through the storage options
+        if params.retry_policy().is_none() {
+            params.retry_policy = self.retry_policy.clone();
+        }
+        if params.metrics.is_none() {
+            params.metrics = self.metrics.clone();
+        }
+        params
+    }
+}
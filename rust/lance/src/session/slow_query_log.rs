@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Reporting of slow queries
+//!
+//! A [`SlowQueryLog`] installed on a [`super::Session`] is called with every query of the
+//! session that takes longer than its threshold.  The report has the plan of the query,
+//! annotated with the metrics of each node, so slow queries can be investigated without
+//! running them again under `analyze_plan`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use datafusion::physical_plan::display::DisplayableExecutionPlan;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
+use futures::TryStreamExt;
+use lance_core::utils::futures::FinallyStreamExt;
+use lance_datafusion::exec::{plan_summary_counts, ExecutionSummaryCounts};
+
+/// A query that took longer than the threshold of the [`SlowQueryLog`]
+#[derive(Debug, Clone)]
+pub struct SlowQuery {
+    /// The URI of the dataset that was queried
+    pub dataset_uri: String,
+    /// The version of the dataset that was queried
+    pub version: u64,
+    /// The id of the query, if one was set with [`crate::dataset::scanner::Scanner::query_id`]
+    pub query_id: Option<String>,
+    /// The filter of the query, as given to the scanner
+    pub filter: Option<String>,
+    /// The time from planning the query to its last batch
+    pub duration: Duration,
+    /// The number of rows returned
+    pub num_rows: usize,
+    /// The plan of the query, with the metrics of each node
+    ///
+    /// This shows how the filter was applied (pushed into the scan, answered by scalar
+    /// indices or applied after the scan) and which indices were searched.
+    pub plan: String,
+    /// The I/O and index statistics of the query
+    pub stats: ExecutionSummaryCounts,
+}
+
+/// Called with each query that is slower than the threshold of the [`SlowQueryLog`]
+pub type SlowQueryCallback = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// Reports the queries that take longer than a threshold
+#[derive(Clone)]
+pub struct SlowQueryLog {
+    threshold: Duration,
+    callback: SlowQueryCallback,
+}
+
+impl std::fmt::Debug for SlowQueryLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlowQueryLog")
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+/// What is known about a query before it runs
+pub(crate) struct QueryDescription {
+    pub dataset_uri: String,
+    pub version: u64,
+    pub query_id: Option<String>,
+    pub filter: Option<String>,
+}
+
+impl SlowQueryLog {
+    /// Call `callback` with each query that takes longer than `threshold`
+    ///
+    /// The callback is called when the query finishes, on the thread that reads the last
+    /// batch, so it should hand the report off rather than do slow work itself.
+    pub fn new(threshold: Duration, callback: SlowQueryCallback) -> Self {
+        Self {
+            threshold,
+            callback,
+        }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Report the query run by `stream` if it is slow
+    ///
+    /// `start` is when planning started.  Queries that are dropped before they finish are
+    /// not reported.
+    pub(crate) fn watch(
+        &self,
+        stream: SendableRecordBatchStream,
+        plan: Arc<dyn ExecutionPlan>,
+        start: Instant,
+        description: QueryDescription,
+    ) -> SendableRecordBatchStream {
+        let schema = stream.schema();
+        let log = self.clone();
+        let num_rows = Arc::new(AtomicUsize::new(0));
+        let stream = stream
+            .inspect_ok({
+                let num_rows = num_rows.clone();
+                move |batch| {
+                    num_rows.fetch_add(batch.num_rows(), Ordering::Relaxed);
+                }
+            })
+            .finally(move || {
+                let duration = start.elapsed();
+                if duration < log.threshold {
+                    return;
+                }
+                let plan_display = DisplayableExecutionPlan::with_metrics(plan.as_ref())
+                    .indent(true)
+                    .to_string();
+                (log.callback)(&SlowQuery {
+                    dataset_uri: description.dataset_uri,
+                    version: description.version,
+                    query_id: description.query_id,
+                    filter: description.filter,
+                    duration,
+                    num_rows: num_rows.load(Ordering::Relaxed),
+                    plan: plan_display,
+                    stats: plan_summary_counts(plan.as_ref()),
+                });
+            });
+        Box::pin(RecordBatchStreamAdapter::new(schema, stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+
+    use super::*;
+    use crate::dataset::builder::DatasetBuilder;
+    use crate::dataset::Dataset;
+    use crate::session::Session;
+
+    #[tokio::test]
+    async fn test_slow_query_log() {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "i",
+            DataType::Int32,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_uri = test_dir.path().to_str().unwrap();
+        Dataset::write(
+            RecordBatchIterator::new(vec![Ok(batch)], schema),
+            test_uri,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let open = |threshold: Duration| {
+            let reports = Arc::new(Mutex::new(Vec::<SlowQuery>::new()));
+            let callback = {
+                let reports = reports.clone();
+                Arc::new(move |query: &SlowQuery| reports.lock().unwrap().push(query.clone()))
+            };
+            let session =
+                Session::default().with_slow_query_log(SlowQueryLog::new(threshold, callback));
+            let dataset = DatasetBuilder::from_uri(test_uri)
+                .with_session(Arc::new(session))
+                .load();
+            (dataset, reports)
+        };
+
+        // Every query is slower than a threshold of zero
+        let (dataset, reports) = open(Duration::ZERO);
+        let dataset = dataset.await.unwrap();
+        let batch = dataset
+            .scan()
+            .filter("i >= 90")
+            .unwrap()
+            .query_id("q1")
+            .try_into_batch()
+            .await
+            .unwrap();
+        assert_eq!(batch.num_rows(), 10);
+        {
+            let reports = reports.lock().unwrap();
+            assert_eq!(reports.len(), 1);
+            let report = &reports[0];
+            assert_eq!(report.dataset_uri, dataset.uri());
+            assert_eq!(report.version, 1);
+            assert_eq!(report.query_id.as_deref(), Some("q1"));
+            assert_eq!(report.filter.as_deref(), Some("i >= 90"));
+            assert_eq!(report.num_rows, 10);
+            assert!(report.plan.contains("LanceScan"), "{}", report.plan);
+            assert!(report.plan.contains("metrics="), "{}", report.plan);
+            assert!(report.stats.bytes_read > 0);
+        }
+
+        let (dataset, reports) = open(Duration::from_secs(3600));
+        let dataset = dataset.await.unwrap();
+        dataset.scan().try_into_batch().await.unwrap();
+        assert!(reports.lock().unwrap().is_empty());
+    }
+}
@@ -3,7 +3,10 @@
 
 //! Various utilities
 
+#[cfg(any(feature = "polars", feature = "duckdb"))]
+pub(crate) mod filter;
 pub(crate) mod future;
+pub(crate) mod query_tracing;
 pub(crate) mod temporal;
 #[cfg(test)]
 pub(crate) mod test;
@@ -11,5 +14,6 @@ pub(crate) mod test;
 pub mod tfrecord;
 
 // Re-export
+pub use lance_core::utils::metrics;
 pub use lance_datafusion::sql;
 pub use lance_linalg::kmeans;
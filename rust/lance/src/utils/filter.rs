@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Helpers for building SQL filters from other engines' predicates.
+
+/// Quote a column name for use in a filter, if it needs quoting.
+pub(crate) fn quote_identifier(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+    {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Quote a string literal.
+pub(crate) fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote_identifier("x_1"), "x_1");
+        assert_eq!(quote_identifier("1x"), "`1x`");
+        assert_eq!(quote_identifier("a `b`"), "`a ``b```");
+        assert_eq!(quote_string("it's"), "'it''s'");
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The Lance Authors
+
+//! Spans that show where the time of a query goes
+//!
+//! With the `query-tracing` feature a query gets a span (target
+//! [`lance_core::utils::tracing::TRACE_QUERY`]) with the query id, dataset URI and version.
+//! The fragment scans and index searches of the query are children of that span and the
+//! I/O requests they make are children of theirs.  Without the feature these functions
+//! return disabled spans, which cost next to nothing to enter.
+
+use tracing::Span;
+
+use crate::dataset::Dataset;
+
+#[cfg(feature = "query-tracing")]
+pub(crate) fn query_span(dataset: &Dataset, query_id: Option<&str>) -> Span {
+    let query_id = query_id
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    tracing::info_span!(
+        target: lance_core::utils::tracing::TRACE_QUERY,
+        "query",
+        query_id = %query_id,
+        dataset_uri = %dataset.uri(),
+        version = dataset.manifest().version,
+    )
+}
+
+#[cfg(not(feature = "query-tracing"))]
+pub(crate) fn query_span(_dataset: &Dataset, _query_id: Option<&str>) -> Span {
+    Span::none()
+}
+
+#[cfg(feature = "query-tracing")]
+pub(crate) fn fragment_span(dataset: &Dataset, fragment_id: u64) -> Span {
+    tracing::info_span!(
+        target: lance_core::utils::tracing::TRACE_QUERY,
+        "scan_fragment",
+        dataset_uri = %dataset.uri(),
+        version = dataset.manifest().version,
+        fragment_id,
+    )
+}
+
+#[cfg(not(feature = "query-tracing"))]
+pub(crate) fn fragment_span(_dataset: &Dataset, _fragment_id: u64) -> Span {
+    Span::none()
+}
+
+#[cfg(feature = "query-tracing")]
+pub(crate) fn index_search_span(dataset: &Dataset, column: &str, index_uuid: &str) -> Span {
+    tracing::info_span!(
+        target: lance_core::utils::tracing::TRACE_QUERY,
+        "search_index",
+        dataset_uri = %dataset.uri(),
+        version = dataset.manifest().version,
+        column,
+        index_uuid,
+    )
+}
+
+#[cfg(not(feature = "query-tracing"))]
+pub(crate) fn index_search_span(_dataset: &Dataset, _column: &str, _index_uuid: &str) -> Span {
+    Span::none()
+}